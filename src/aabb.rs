@@ -2,7 +2,7 @@ use crate::primitives::vector::Vector3;
 use std::fmt::{Display, Error, Formatter};
 use crate::primitives::face::Plane3;
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct AABB {
     north: f32,
     south: f32,
@@ -46,6 +46,39 @@ impl AABB {
     pub fn east(&self) -> f32 { self.east }
     pub fn west(&self) -> f32 { self.west }
     
+    /// Returns the same box with all its coordinates multiplied by `factor`
+    pub fn scaled(&self, factor: f32) -> AABB {
+        AABB {
+            north: self.north * factor,
+            south: self.south * factor,
+            top: self.top * factor,
+            bottom: self.bottom * factor,
+            east: self.east * factor,
+            west: self.west * factor,
+        }
+    }
+
+    /// Returns the same box moved by `delta`
+    pub fn translated(&self, delta: &Vector3) -> AABB {
+        AABB {
+            north: self.north + delta.z(),
+            south: self.south + delta.z(),
+            top: self.top + delta.y(),
+            bottom: self.bottom + delta.y(),
+            east: self.east + delta.x(),
+            west: self.west + delta.x(),
+        }
+    }
+
+    /// Lowest and highest coordinates of the box along the axis (0 = x, 1 = y, 2 = z)
+    pub fn range(&self, axis: usize) -> (f32, f32) {
+        match axis {
+            0 => (self.west, self.east),
+            1 => (self.bottom, self.top),
+            _ => (self.south, self.north),
+        }
+    }
+
     pub fn collides(&self, aabb: &AABB) -> bool {
         self.west <= aabb.east &&
             self.east >= aabb.west &&
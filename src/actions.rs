@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use crate::block_entity::BlockEntity;
 use crate::block_kind::Block;
 use crate::cube::Cube;
 use crate::primitives::vector::Vector3;
@@ -14,8 +15,17 @@ pub enum Action {
     // Adds a cube
     Add {
         at: Vector3,
-        block: Block
-    }
+        block: Block,
+        /// Metadata of the new cell, how the block is turned (see `Cube::metadata`)
+        #[serde(default)]
+        metadata: u8,
+    },
+
+    /// Replaces the state of a cube (the items of a chest), see `BlockEntity`
+    SetBlockEntity {
+        at: Vector3,
+        entity: BlockEntity,
+    },
 }
 
 impl Action {
@@ -25,8 +35,9 @@ impl Action {
         as_json.into_bytes()
     }
 
-    pub fn from_str(text: &str) -> Self {
-        serde_json::from_str(text).unwrap()
+    /// The action written by `to_bytes`, None if the text is not one
+    pub fn from_str(text: &str) -> Option<Self> {
+        serde_json::from_str(text).ok()
     }
 }
 
@@ -1,5 +1,6 @@
 use std::env;
 use clap::{Parser, ValueEnum};
+use crate::persistence::SAVES_DIR;
 
 #[allow(dead_code)]
 #[derive(Debug, Clone, ValueEnum)]
@@ -7,20 +8,31 @@ pub enum WorldInitializer {
     RANDOM,
     FLAT,
     DISK,
+    TERRAIN,
+    AMPLIFIED,
 }
 
 impl WorldInitializer {
     pub fn from_args() -> Self {
+        Self::from_flags().unwrap_or(Self::RANDOM)
+    }
+
+    /// The initializer given on the command line, if any
+    pub fn from_flags() -> Option<Self> {
         let args: Vec<String> = env::args().collect();
         println!("args = {args:?}");
         if args.contains(&"--random".to_string()) {
-            Self::RANDOM
+            Some(Self::RANDOM)
         } else if args.contains(&"--flat".to_string()) {
-            Self::FLAT
+            Some(Self::FLAT)
         } else if args.contains(&"--disk".to_string()) {
-            Self::DISK
+            Some(Self::DISK)
+        } else if args.contains(&"--terrain".to_string()) {
+            Some(Self::TERRAIN)
+        } else if args.contains(&"--amplified".to_string()) {
+            Some(Self::AMPLIFIED)
         } else {
-            Self::RANDOM
+            None
         }
     }
 }
@@ -51,7 +63,19 @@ pub struct Args {
     pub name: String,
     
     #[arg(value_enum, short, long, help = "How to initialize the world", default_value = "random")]
-    pub init: WorldInitializer
+    pub init: WorldInitializer,
+
+    #[arg(long, help = "Seed of the generated world, the same seed always gives the same world")]
+    pub seed: Option<u64>,
+
+    #[arg(long, help = "Time [s] between two autosaves of the world by the server, no autosave if not given")]
+    pub autosave: Option<u64>,
+
+    #[arg(long, help = "Directory of the saves where the server loads (--init disk) and saves the world", default_value_t = String::from(SAVES_DIR))]
+    pub saves_dir: String,
+
+    #[arg(long, help = "Whether the server also saves the world when it is stopped with /stop")]
+    pub save_on_exit: bool,
 }
 
 impl Args {
@@ -18,11 +18,11 @@ impl EntityAttack {
         vec![self.attacked, self.strength]
     }
 
-    pub fn from_bytes(bytes_to_parse: &[u8]) -> Self {
-        Self {
-            attacked: bytes_to_parse[0],
-            strength: bytes_to_parse[1]
-        }
+    pub fn from_bytes(bytes_to_parse: &[u8]) -> Option<Self> {
+        Some(Self {
+            attacked: *bytes_to_parse.first()?,
+            strength: *bytes_to_parse.get(1)?
+        })
     }
 
     pub fn set_strength(&mut self, strength: u8) {
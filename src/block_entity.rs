@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::block_kind::Block;
+use crate::chunk::CubeIndex;
+use crate::player_items::{Inventory, ItemStack, STORAGE_SIZE};
+
+/// Number of slots of a chest, as many as the storage of a player
+pub const CHEST_SLOTS: usize = STORAGE_SIZE;
+
+/// State of a block that does not fit in the metadata of its cell, kept by its chunk (see `Chunk::block_entity`)
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum BlockEntity {
+    Chest(Container),
+}
+
+impl BlockEntity {
+    /// The state of a new block of this kind, None for the blocks without one
+    pub fn for_block(block: Block) -> Option<Self> {
+        match block {
+            Block::CHEST => Some(BlockEntity::Chest(Container::new())),
+            _ => None,
+        }
+    }
+
+    pub fn container(&self) -> Option<&Container> {
+        match self {
+            BlockEntity::Chest(container) => Some(container),
+        }
+    }
+
+    /// Advances the state by one tick of the world, returns true if it changed.
+    /// The chests only change when a player moves their items.
+    pub fn tick(&mut self) -> bool {
+        match self {
+            BlockEntity::Chest(_) => false,
+        }
+    }
+}
+
+/// The block entities of a chunk, by the index of their cell in the chunk.
+/// A cell has a block entity as long as its block has one (see `BlockEntity::for_block`): it is created with the
+/// block and dropped when the block is broken or replaced.
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct BlockEntities {
+    entities: HashMap<CubeIndex, BlockEntity>,
+}
+
+impl BlockEntities {
+    pub fn new() -> Self {
+        Self { entities: HashMap::new() }
+    }
+
+    pub fn get(&self, index: &CubeIndex) -> Option<&BlockEntity> {
+        self.entities.get(index)
+    }
+
+    /// Replaces the state of the cell, when it is loaded or changed by a player
+    pub fn set(&mut self, index: CubeIndex, entity: BlockEntity) {
+        self.entities.insert(index, entity);
+    }
+
+    /// Gives its new state to the block placed in the cell, the state of the previous block is dropped
+    pub fn on_block_placed(&mut self, index: CubeIndex, block: Block) {
+        match BlockEntity::for_block(block) {
+            Some(entity) => self.entities.insert(index, entity),
+            None => self.entities.remove(&index),
+        };
+    }
+
+    /// Drops the state of the cell, when its block is broken
+    pub fn on_block_removed(&mut self, index: &CubeIndex) -> Option<BlockEntity> {
+        self.entities.remove(index)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&CubeIndex, &BlockEntity)> {
+        self.entities.iter()
+    }
+
+    /// Ticks all the block entities, returns the cells whose state changed
+    pub fn tick(&mut self) -> Vec<CubeIndex> {
+        self.entities.iter_mut().filter_map(|(index, entity)| entity.tick().then_some(*index)).collect()
+    }
+}
+
+/// Slots of items kept by a block, filled and emptied one item at a time like the slots of the inventory
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Container {
+    slots: [Option<ItemStack>; CHEST_SLOTS],
+}
+
+impl Container {
+    pub fn new() -> Self {
+        Self { slots: [None; CHEST_SLOTS] }
+    }
+
+    pub fn get(&self, index: usize) -> Option<ItemStack> {
+        self.slots[index]
+    }
+
+    /// Sets the whole stack of a slot, when the container is loaded
+    pub fn set(&mut self, index: usize, stack: Option<ItemStack>) {
+        self.slots[index] = stack;
+    }
+
+    pub fn take(&mut self, index: usize) -> Option<ItemStack> {
+        Inventory::take_item(&mut self.slots[index])
+    }
+
+    pub fn put(&mut self, index: usize, item: ItemStack) -> bool {
+        Inventory::put_item(&mut self.slots[index], item)
+    }
+
+    /// The stacks of the slots that are not empty
+    pub fn stacks(&self) -> impl Iterator<Item = &ItemStack> {
+        self.slots.iter().flatten()
+    }
+}
+
+impl Default for Container {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::block_entity::{BlockEntities, BlockEntity, Container, CHEST_SLOTS};
+    use crate::block_kind::Block;
+    use crate::player_items::ItemStack;
+    use crate::tool::Tool;
+
+    #[test]
+    fn test_chests_saved_when_the_tools_were_blocks() {
+        let mut slots = vec![String::from("null"); CHEST_SLOTS];
+        slots[0] = String::from("[\"SWORD\",1]");
+        slots[1] = String::from("[\"DIRT\",12]");
+        let json = format!("{{\"Chest\":{{\"slots\":[{}]}}}}", slots.join(","));
+        let mut container = Container::new();
+        container.set(0, Some(ItemStack::new(Tool::Sword, 1)));
+        container.set(1, Some(ItemStack::new(Block::DIRT, 12)));
+        assert_eq!(serde_json::from_str::<BlockEntity>(&json).unwrap(), BlockEntity::Chest(container.clone()));
+
+        // The chests saved since then are read back
+        let json = serde_json::to_string(&BlockEntity::Chest(container.clone())).unwrap();
+        assert_eq!(serde_json::from_str::<BlockEntity>(&json).unwrap(), BlockEntity::Chest(container));
+    }
+
+    #[test]
+    fn test_container_slots() {
+        let mut container = Container::new();
+        let [dirt, sand] = [Block::DIRT, Block::SAND].map(|block| ItemStack::new(block, 1));
+        assert!(container.put(0, dirt));
+        assert!(container.put(0, dirt));
+        // A slot only holds one kind of item
+        assert!(!container.put(0, sand));
+        assert!(container.put(CHEST_SLOTS - 1, sand));
+        assert_eq!(container.get(0), Some(ItemStack::new(Block::DIRT, 2)));
+
+        assert_eq!(container.take(0), Some(dirt));
+        assert_eq!(container.stacks().count(), 2);
+        assert_eq!(container.take(5), None);
+
+        assert_eq!(BlockEntity::for_block(Block::CHEST), Some(BlockEntity::Chest(Container::new())));
+        assert_eq!(BlockEntity::for_block(Block::DIRT), None);
+    }
+
+    #[test]
+    fn test_block_entities_follow_their_block() {
+        let mut entities = BlockEntities::new();
+        entities.on_block_placed((1, 2, 3), Block::CHEST);
+        entities.on_block_placed((4, 0, 0), Block::DIRT);
+        assert_eq!(entities.iter().count(), 1);
+
+        let mut container = Container::new();
+        container.put(0, ItemStack::new(Block::SAND, 1));
+        entities.set((1, 2, 3), BlockEntity::Chest(container.clone()));
+        // The chests do not change by themselves
+        assert!(entities.tick().is_empty());
+        assert_eq!(entities.get(&(1, 2, 3)), Some(&BlockEntity::Chest(container.clone())));
+
+        // Replacing or breaking the block drops its state
+        entities.on_block_placed((1, 2, 3), Block::STONE);
+        assert!(entities.get(&(1, 2, 3)).is_none());
+        entities.on_block_placed((1, 2, 3), Block::CHEST);
+        assert_eq!(entities.on_block_removed(&(1, 2, 3)), Some(BlockEntity::Chest(Container::new())));
+        assert_eq!(entities.iter().count(), 0);
+    }
+}
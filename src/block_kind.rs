@@ -1,75 +1,365 @@
 use serde::{Deserialize, Serialize};
-use strum::EnumIter;
-use strum::IntoEnumIterator;
+use strum::{EnumIter, IntoEnumIterator};
+
+use crate::block_shape::{laid_axis, BlockShape, Facing};
+use crate::texture_atlas::BlockFace;
+use crate::tool::{Material, Tool};
 
 /// The kind of cube
-/// Each kind is associated with 3 textures: side, top & bottom.
+/// Each kind is associated with 3 textures: side, top & bottom (see `TextureAtlas`).
+/// How a block is turned is not part of its kind, it is in the metadata of its cell (see `Cube::metadata`).
+/// The slabs have one kind per half of the cell, the first one being the item (see `drops`).
 #[derive(Clone, Copy, EnumIter, PartialEq, Debug, Serialize, Deserialize, Hash, Eq)]
+/// The value of each kind is its id in the saves (see `Block::id`): it is never changed nor given again to another kind.
+/// The missing ids are the tools, the food and the arrows, which were blocks before (see `Item`).
 pub enum Block {
     GRASS = 0,
-    DIRT,
-    COBBELSTONE,
-    OAKLOG,
-    OAKLEAVES,
-    WATER,
-    STONE,
-    SAND,
-    SWORD,
+    DIRT = 1,
+    COBBELSTONE = 2,
+    OAKLOG = 3,
+    OAKLEAVES = 4,
+    WATER = 5,
+    STONE = 6,
+    SAND = 7,
+    TORCH = 9,
+    GRAVEL = 10,
+    LAVA = 11,
+    GLASS = 12,
+    SLAB = 13,
+    #[serde(alias = "SLAB_TOP")]
+    SLABTOP = 14,
+    STAIRS = 15,
+    CHEST = 16,
+    FARMLAND = 18,
+    SEEDS = 19,
+    WHEATCROP = 20,
+    WHEAT = 21,
+    #[serde(alias = "SNOWY_GRASS")]
+    SNOWYGRASS = 22,
+    COALORE = 23,
+    IRONORE = 24,
+    GOLDORE = 25,
+    DIAMONDORE = 26,
 }
 
+/// Last growth stage of the crops, kept in the metadata of their cell: they are planted at 0 and give wheat once at this stage
+pub const MAX_CROP_STAGE: u8 = 7;
+
 impl Block {
+    /// The id of the block in the saves, it does not depend on the order of the kinds
+    pub fn id(&self) -> u8 {
+        *self as u8
+    }
+
+    /// The block of this id, see `id`
+    pub fn from_id(id: u8) -> Option<Self> {
+        Block::iter().find(|block| block.id() == id)
+    }
+
     fn file_name(&self) -> String {
         match self {
             Block::GRASS => "grass".to_string(),
             Block::DIRT => "dirt".to_string(),
-            Block::COBBELSTONE => "cobblestone".to_string(),
+            Block::COBBELSTONE | Block::SLAB | Block::SLABTOP => "cobblestone".to_string(),
             Block::OAKLOG => "oak_log".to_string(),
             Block::OAKLEAVES => "oak_leaves".to_string(),
             Block::WATER => "water".to_string(),
             Block::STONE => "stone".to_string(),
             Block::SAND => "sand".to_string(),
-            Block::SWORD => "stone_sword".to_string(),
+            Block::TORCH => "torch".to_string(),
+            Block::GRAVEL => "gravel".to_string(),
+            Block::LAVA => "lava".to_string(),
+            Block::GLASS => "glass".to_string(),
+            Block::STAIRS => "cobblestone".to_string(),
+            Block::CHEST => "chest".to_string(),
+            Block::FARMLAND => "farmland".to_string(),
+            Block::SEEDS => "wheat_seeds".to_string(),
+            Block::WHEATCROP => "wheat_crop".to_string(),
+            Block::WHEAT => "wheat".to_string(),
+            Block::SNOWYGRASS => "snowy_grass".to_string(),
+            Block::COALORE => "coal_ore".to_string(),
+            Block::IRONORE => "iron_ore".to_string(),
+            Block::GOLDORE => "gold_ore".to_string(),
+            Block::DIAMONDORE => "diamond_ore".to_string(),
         }
     }
 
-    /// Returns a list of all the textures to be loaded, in the proper order.
-    pub fn get_texture_files() -> Vec<String> {
-        let mut names = Vec::new();
-        for block_kind in Block::iter() {
-            let name = block_kind.file_name();
-            names.push(name.clone() + "_side");
-            names.push(name.clone() + "_top");
-            names.push(name.clone() + "_bottom");
+    /// Name of the texture file of the face, without its extension
+    pub fn texture_file(&self, face: BlockFace) -> String {
+        match (self, face) {
+            // Only the top of the farmland is tilled
+            (Block::FARMLAND, BlockFace::Side | BlockFace::Bottom) => Block::DIRT.texture_file(face),
+            (Block::SNOWYGRASS, BlockFace::Bottom) => Block::DIRT.texture_file(face),
+            _ => self.file_name() + face.suffix(),
         }
-        names
     }
 
     pub fn can_be_placed(&self) -> bool {
         match self {
-            Block::SWORD => false,
+            Block::WHEAT => false,
             _ => true,
         }
     }
 
-    pub fn attack_strength(&self) -> u8 {
+    /// Time [s] needed to break the block with the bare hand, the tools made for its material are faster (see `Tool::speed`)
+    pub fn hardness(&self) -> f32 {
         match self {
-            Block::SWORD => 2,
-            _ => 1
+            Block::TORCH => 0.1,
+            Block::OAKLEAVES | Block::GLASS => 0.5,
+            Block::GRASS | Block::SNOWYGRASS | Block::DIRT | Block::SAND | Block::GRAVEL => 1.,
+            Block::OAKLOG => 1.5,
+            Block::COBBELSTONE | Block::STONE | Block::WATER | Block::LAVA => 2.,
+            Block::SLAB | Block::SLABTOP => 1.,
+            Block::STAIRS => 2.,
+            Block::CHEST => 2.5,
+            Block::FARMLAND => 1.,
+            Block::SEEDS | Block::WHEATCROP => 0.1,
+            Block::WHEAT => 2.,
+            Block::COALORE | Block::IRONORE | Block::GOLDORE => 3.,
+            Block::DIAMONDORE => 4.,
+        }
+    }
+
+    /// What the block is made of, which decides the tool breaking it faster
+    pub fn material(&self) -> Material {
+        match self {
+            Block::STONE | Block::COBBELSTONE | Block::SLAB | Block::SLABTOP | Block::STAIRS => Material::Rock,
+            Block::COALORE | Block::IRONORE | Block::GOLDORE | Block::DIAMONDORE => Material::Rock,
+            Block::GRASS | Block::SNOWYGRASS | Block::DIRT | Block::FARMLAND | Block::SAND | Block::GRAVEL => Material::Earth,
+            Block::OAKLOG | Block::CHEST => Material::Wood,
+            Block::OAKLEAVES | Block::SEEDS | Block::WHEATCROP => Material::Plant,
+            _ => Material::Other,
+        }
+    }
+
+    /// The tool without which the block leaves nothing when it is broken: the rocks need a pickaxe
+    pub fn required_tool(&self) -> Option<Tool> {
+        match self.material() {
+            Material::Rock => Some(Tool::Pickaxe),
+            _ => None,
+        }
+    }
+
+    /// Whether the block leaves its items when it is broken with this tool in hand, see `required_tool`
+    pub fn is_harvested_by(&self, tool: Option<Tool>) -> bool {
+        match self.required_tool() {
+            Some(required) => tool == Some(required),
+            None => true,
         }
     }
 
     pub fn is_transparent(&self) -> bool {
         match self {
-            Block::OAKLEAVES | Block::WATER => true,
+            Block::OAKLEAVES | Block::WATER | Block::TORCH | Block::GLASS | Block::WHEATCROP => true,
             _ => false
         }
     }
-        
-    pub fn block_dropped(&self) -> Self {
+
+    /// Boxes of the block turned by the `metadata` of its cell, to draw it and to collide with it
+    pub fn shape(&self, metadata: u8) -> BlockShape {
+        match self {
+            Block::SLAB => BlockShape::Slab { top: false },
+            Block::SLABTOP => BlockShape::Slab { top: true },
+            Block::STAIRS => BlockShape::Stairs(Facing::from_metadata(metadata)),
+            Block::TORCH => BlockShape::Torch,
+            Block::WHEATCROP => BlockShape::Crop(metadata.min(MAX_CROP_STAGE)),
+            _ => BlockShape::Cube,
+        }
+    }
+
+    /// Axis the block is laid along (0 for x, 1 for y, 2 for z), from the `metadata` of its cell.
+    /// Its top and bottom textures are on the faces along this axis: only the logs can lie on their side.
+    pub fn axis(&self, metadata: u8) -> usize {
         match self {
-            Block::GRASS => Block::DIRT,
-            Block::STONE => Block::COBBELSTONE,
+            Block::OAKLOG => laid_axis(metadata),
+            _ => 1,
+        }
+    }
+
+    /// Opaque blocks filling their whole cell: only them hide the faces of the cubes next to them and stop the light.
+    /// Turning a block does not change whether it fills its cell.
+    pub fn is_opaque_cube(&self) -> bool {
+        !self.is_transparent() && self.shape(0).is_full()
+    }
+
+    /// Transparent blocks whose textures are either opaque or fully transparent: the shaders discard
+    /// their transparent pixels (alpha testing) instead of blending them
+    pub fn is_cutout(&self) -> bool {
+        match self {
+            Block::OAKLEAVES | Block::TORCH | Block::GLASS | Block::WHEATCROP => true,
+            _ => false
+        }
+    }
+
+    /// Whether the faces of a transparent block touching the same block are hidden.
+    /// The panes of glass and the water merge into one body, while the leaves show the ones behind them.
+    pub fn culls_same_neighbor(&self) -> bool {
+        match self {
+            Block::WATER | Block::GLASS => true,
+            _ => false
+        }
+    }
+
+    /// Whether the entities collide with the block, they go through the liquids and the torches
+    pub fn is_solid(&self) -> bool {
+        match self {
+            Block::WATER | Block::LAVA | Block::TORCH | Block::WHEATCROP => false,
+            _ => true
+        }
+    }
+
+    /// Blocks that fall when there is nothing solid below them
+    pub fn falls(&self) -> bool {
+        match self {
+            Block::SAND | Block::GRAVEL => true,
+            _ => false
+        }
+    }
+
+    /// Level of the light given off by the block, from 0 to `lighting::MAX_LIGHT`
+    pub fn light_emission(&self) -> u8 {
+        match self {
+            Block::LAVA => 15,
+            Block::TORCH => 14,
+            _ => 0
+        }
+    }
+
+    /// Levels of light lost by going through the transparent block, on top of the one lost at each step.
+    /// The opaque blocks stop the light whatever this attenuation.
+    pub fn light_attenuation(&self) -> u8 {
+        match self {
+            Block::OAKLEAVES => 1,
+            _ => 0
+        }
+    }
+
+    /// Number of images of the animation of the block, 1 for the static blocks.
+    /// The textures of an animated block stack its images vertically, the first one on top.
+    pub fn animation_frames(&self) -> u8 {
+        match self {
+            Block::WATER | Block::LAVA => 4,
+            _ => 1
+        }
+    }
+
+    /// Faces drawn in the grass color of the biome of their column, see `BiomeConfig::grass_tint`
+    pub fn takes_grass_tint(&self, face: BlockFace) -> bool {
+        matches!((self, face), (Block::GRASS, BlockFace::Top))
+    }
+
+    /// Blocks drawn in the foliage color of the biome of their column, see `BiomeConfig::foliage_tint`
+    pub fn takes_foliage_tint(&self) -> bool {
+        matches!(self, Block::OAKLEAVES)
+    }
+
+    /// Blocks that change slowly when their cell is picked by a random tick, see `World::random_tick`
+    pub fn receives_random_ticks(&self) -> bool {
+        match self {
+            Block::GRASS | Block::WHEATCROP => true,
+            _ => false
+        }
+    }
+
+    /// Blocks that are replaced when another block is placed in their cell
+    pub fn is_replaceable(&self) -> bool {
+        match self {
+            Block::WATER | Block::LAVA => true,
+            _ => false
+        }
+    }
+
+    /// Item the block is placed from: both halves of the slabs come from the lower one, and the crops from the seeds
+    pub fn item(&self) -> Self {
+        match self {
+            Block::SLABTOP => Block::SLAB,
+            Block::WHEATCROP => Block::SEEDS,
             block => *block
         }
     }
+
+    /// Item left when the block is broken, usually the block itself
+    pub fn drops(&self) -> Option<Self> {
+        match self {
+            Block::GRASS | Block::SNOWYGRASS => Some(Block::DIRT),
+            Block::STONE => Some(Block::COBBELSTONE),
+            Block::FARMLAND => Some(Block::DIRT),
+            Block::WATER | Block::LAVA => None,
+            block => Some(block.item())
+        }
+    }
+
+    /// Items left when the block of a cell with this `metadata` is broken: its drop, and the harvest of the grown crops
+    pub fn harvest(&self, metadata: u8) -> Vec<Self> {
+        match self {
+            Block::WHEATCROP if metadata >= MAX_CROP_STAGE => vec![Block::WHEAT, Block::SEEDS, Block::SEEDS],
+            block => block.drops().into_iter().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::block_kind::{Block, MAX_CROP_STAGE};
+    use strum::IntoEnumIterator;
+    use crate::tool::{Material, Tool};
+
+    #[test]
+    fn test_ids_do_not_follow_the_order_of_the_kinds() {
+        // The ids of the saves written before the kinds were reordered
+        assert_eq!(Block::GRASS.id(), 0);
+        assert_eq!(Block::SAND.id(), 7);
+        assert_eq!(Block::TORCH.id(), 9);
+        assert_eq!(Block::DIAMONDORE.id(), 26);
+        assert_eq!(Block::from_id(8), None);
+        for block in Block::iter() {
+            assert_eq!(Block::from_id(block.id()), Some(block));
+        }
+    }
+
+    #[test]
+    fn test_drops() {
+        assert_eq!(Block::STONE.drops(), Some(Block::COBBELSTONE));
+        assert_eq!(Block::GRASS.drops(), Some(Block::DIRT));
+        assert_eq!(Block::OAKLOG.drops(), Some(Block::OAKLOG));
+        assert_eq!(Block::WATER.drops(), None);
+        assert_eq!(Block::LAVA.drops(), None);
+        // Both halves of the slabs give back the same item
+        assert_eq!(Block::SLABTOP.drops(), Some(Block::SLAB));
+        assert_eq!(Block::STAIRS.drops(), Some(Block::STAIRS));
+    }
+
+    #[test]
+    fn test_crops_harvest() {
+        // A young crop only gives its seeds back, a grown one gives the wheat too
+        assert_eq!(Block::WHEATCROP.harvest(3), vec![Block::SEEDS]);
+        assert_eq!(Block::WHEATCROP.harvest(MAX_CROP_STAGE), vec![Block::WHEAT, Block::SEEDS, Block::SEEDS]);
+        assert_eq!(Block::STONE.harvest(0), vec![Block::COBBELSTONE]);
+        assert!(Block::WATER.harvest(0).is_empty());
+        assert_eq!(Block::WHEATCROP.shape(MAX_CROP_STAGE), Block::WHEATCROP.shape(15));
+        assert!(!Block::WHEATCROP.is_solid() && Block::SEEDS.can_be_placed() && !Block::WHEAT.can_be_placed());
+    }
+
+    #[test]
+    fn test_rocks_need_a_pickaxe() {
+        for block in [Block::STONE, Block::COBBELSTONE, Block::IRONORE, Block::STAIRS] {
+            assert!(!block.is_harvested_by(None));
+            assert!(!block.is_harvested_by(Some(Tool::Shovel)));
+            assert!(block.is_harvested_by(Some(Tool::Pickaxe)));
+        }
+        assert!(Block::DIRT.is_harvested_by(None) && Block::OAKLOG.is_harvested_by(Some(Tool::Pickaxe)));
+        assert_eq!(Block::OAKLOG.material(), Material::Wood);
+    }
+
+    #[test]
+    fn test_cutout_blocks() {
+        for block in [Block::GLASS, Block::OAKLEAVES] {
+            assert!(block.is_transparent() && block.is_cutout() && block.is_solid());
+        }
+        assert!(!Block::WATER.is_cutout());
+        // The glass merges with the glass next to it, the leaves don't
+        assert!(Block::GLASS.culls_same_neighbor());
+        assert!(!Block::OAKLEAVES.culls_same_neighbor());
+        assert_eq!(Block::GLASS.light_attenuation(), 0);
+    }
 }
@@ -0,0 +1,230 @@
+use crate::aabb::AABB;
+use crate::block_kind::{Block, MAX_CROP_STAGE};
+use crate::primitives::vector::Vector3;
+
+/// Horizontal direction a block is turned to, the stairs climb toward it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Facing {
+    /// +z
+    North,
+    /// +x
+    East,
+    /// -z
+    South,
+    /// -x
+    West,
+}
+
+impl Facing {
+    /// The facing stored in the metadata of a cell, see `metadata`
+    pub fn from_metadata(metadata: u8) -> Self {
+        match metadata & 3 {
+            0 => Facing::North,
+            1 => Facing::East,
+            2 => Facing::South,
+            _ => Facing::West,
+        }
+    }
+
+    /// Metadata of a cell whose block is turned to this facing, North being 0 so that a block placed without it faces north
+    pub fn metadata(&self) -> u8 {
+        *self as u8
+    }
+
+    /// The facing closest to the horizontal part of the direction
+    pub fn from_direction(direction: &Vector3) -> Self {
+        if direction.x().abs() > direction.z().abs() {
+            if direction.x() > 0. { Facing::East } else { Facing::West }
+        } else if direction.z() > 0. {
+            Facing::North
+        } else {
+            Facing::South
+        }
+    }
+}
+
+/// Axis of a block laid along x, y or z (0, 1 or 2) from the metadata of its cell.
+/// The metadata 0 is the standing block, along y.
+pub fn laid_axis(metadata: u8) -> usize {
+    match metadata {
+        1 => 0,
+        2 => 2,
+        _ => 1,
+    }
+}
+
+/// Metadata of a cell whose block is laid along `axis`, see `laid_axis`
+fn axis_metadata(axis: usize) -> u8 {
+    match axis {
+        0 => 1,
+        2 => 2,
+        _ => 0,
+    }
+}
+
+/// A box of a block, in cube units from the corner of its cell (from 0 to 1 on each axis)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ShapeBox {
+    pub min: [f32; 3],
+    pub max: [f32; 3],
+}
+
+impl ShapeBox {
+    pub const fn new(min: [f32; 3], max: [f32; 3]) -> Self {
+        Self { min, max }
+    }
+
+    /// The box placed in the cell whose corner is `corner`
+    pub fn aabb(&self, corner: &Vector3) -> AABB {
+        AABB::new(
+            corner[2] + self.max[2],
+            corner[2] + self.min[2],
+            corner[1] + self.max[1],
+            corner[1] + self.min[1],
+            corner[0] + self.max[0],
+            corner[0] + self.min[0],
+        ).unwrap()
+    }
+}
+
+const FULL: ShapeBox = ShapeBox::new([0., 0., 0.], [1., 1., 1.]);
+const LOWER_HALF: ShapeBox = ShapeBox::new([0., 0., 0.], [1., 0.5, 1.]);
+const UPPER_HALF: ShapeBox = ShapeBox::new([0., 0.5, 0.], [1., 1., 1.]);
+/// A thin stick standing in the middle of the cell
+const TORCH_STICK: ShapeBox = ShapeBox::new([7. / 16., 0., 7. / 16.], [9. / 16., 10. / 16., 9. / 16.]);
+
+/// The shape of a block, made of boxes.
+/// The boxes are both what the entities collide with and what is drawn: each of their faces gets the part of
+/// the texture of the block that a full cube would show there.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlockShape {
+    Cube,
+    /// Half of a cube, in the upper or the lower half of the cell
+    Slab { top: bool },
+    /// A lower slab with a step on the half of the cell toward the facing
+    Stairs(Facing),
+    Torch,
+    /// A plant growing from the floor, higher at each growth stage (from 0 to `MAX_CROP_STAGE`)
+    Crop(u8),
+}
+
+impl BlockShape {
+    pub fn is_full(&self) -> bool {
+        *self == BlockShape::Cube
+    }
+
+    pub fn boxes(&self) -> Vec<ShapeBox> {
+        match self {
+            BlockShape::Cube => vec![FULL],
+            BlockShape::Slab { top: false } => vec![LOWER_HALF],
+            BlockShape::Slab { top: true } => vec![UPPER_HALF],
+            BlockShape::Stairs(facing) => {
+                let step = match facing {
+                    Facing::North => ShapeBox::new([0., 0.5, 0.5], [1., 1., 1.]),
+                    Facing::East => ShapeBox::new([0.5, 0.5, 0.], [1., 1., 1.]),
+                    Facing::South => ShapeBox::new([0., 0.5, 0.], [1., 1., 0.5]),
+                    Facing::West => ShapeBox::new([0., 0.5, 0.], [0.5, 1., 1.]),
+                };
+                vec![LOWER_HALF, step]
+            }
+            BlockShape::Torch => vec![TORCH_STICK],
+            BlockShape::Crop(stage) => {
+                let height = (*stage as f32 + 1.) / (MAX_CROP_STAGE as f32 + 1.);
+                vec![ShapeBox::new([0., 0., 0.], [1., height, 1.])]
+            }
+        }
+    }
+
+    /// The boxes of the shape in the cell whose corner is `corner`
+    pub fn aabbs(&self, corner: &Vector3) -> Vec<AABB> {
+        self.boxes().iter().map(|b| b.aabb(corner)).collect()
+    }
+}
+
+/// The block, and the metadata of its cell, put in a cell when the player places the item `block` against the face
+/// of normal `normal`. `height` is the height of the point where the face was hit, on the grid, and `direction`
+/// is where the player looks.
+/// * The slabs go in the half of the cell closest to where the face was hit
+/// * The stairs climb away from the player
+/// * The logs are laid along the normal of the face
+/// * The torches only stand on the top of the blocks, None is returned for the other faces
+/// * The seeds are planted as a crop, on the top of the blocks as well
+pub fn placed_block(block: Block, normal: &Vector3, height: f32, direction: &Vector3) -> Option<(Block, u8)> {
+    match block {
+        Block::SLAB | Block::SLABTOP => {
+            let top = if normal.y() != 0. { normal.y() < 0. } else { height.fract() >= 0.5 };
+            Some((if top { Block::SLABTOP } else { Block::SLAB }, 0))
+        }
+        Block::STAIRS => Some((block, Facing::from_direction(direction).metadata())),
+        Block::OAKLOG => {
+            let axis = (0..3).find(|&axis| normal[axis] != 0.).unwrap_or(1);
+            Some((block, axis_metadata(axis)))
+        }
+        Block::TORCH | Block::SEEDS if normal.y() <= 0. => None,
+        Block::SEEDS => Some((Block::WHEATCROP, 0)),
+        block => Some((block, 0)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::block_kind::{Block, MAX_CROP_STAGE};
+    use crate::block_shape::{laid_axis, placed_block, BlockShape, Facing};
+    use crate::primitives::vector::Vector3;
+
+    #[test]
+    fn test_boxes_of_the_shapes() {
+        let volume = |shape: BlockShape| -> f32 {
+            shape.boxes().iter().map(|b| (0..3).map(|i| b.max[i] - b.min[i]).product::<f32>()).sum()
+        };
+        assert_eq!(volume(BlockShape::Cube), 1.);
+        assert_eq!(volume(BlockShape::Slab { top: true }), 0.5);
+        assert_eq!(volume(BlockShape::Stairs(Facing::West)), 0.75);
+        assert!(volume(BlockShape::Torch) < 0.1);
+        assert_eq!(volume(BlockShape::Crop(3)), 0.5);
+        assert_eq!(volume(BlockShape::Crop(MAX_CROP_STAGE)), 1.);
+
+        // The step of the stairs is on the side of their facing
+        let step = BlockShape::Stairs(Facing::North).boxes()[1];
+        assert_eq!((step.min[2], step.max[2]), (0.5, 1.));
+        let aabb = BlockShape::Slab { top: true }.aabbs(&Vector3::new(2., 3., 4.))[0].clone();
+        assert_eq!((aabb.bottom(), aabb.top()), (3.5, 4.));
+    }
+
+    #[test]
+    fn test_placement_follows_the_face() {
+        let down = Vector3::new(0., -1., 0.);
+        let forward = Vector3::new(0.2, -0.5, -1.);
+        // A slab against the bottom of a block goes on the top of its cell, and on the side it follows the hit point
+        assert_eq!(placed_block(Block::SLAB, &Vector3::unit_y(), 1., &down), Some((Block::SLAB, 0)));
+        assert_eq!(placed_block(Block::SLAB, &Vector3::unit_y().opposite(), 4., &down), Some((Block::SLABTOP, 0)));
+        assert_eq!(placed_block(Block::SLAB, &Vector3::unit_x(), 2.7, &forward), Some((Block::SLABTOP, 0)));
+        assert_eq!(placed_block(Block::SLAB, &Vector3::unit_x(), 2.2, &forward), Some((Block::SLAB, 0)));
+
+        assert_eq!(placed_block(Block::STAIRS, &Vector3::unit_y(), 1., &forward), Some((Block::STAIRS, Facing::South.metadata())));
+        assert_eq!(Facing::from_direction(&Vector3::new(1., 0., 0.5)), Facing::East);
+
+        // The torches only stand on the floor
+        assert_eq!(placed_block(Block::TORCH, &Vector3::unit_y(), 1., &down), Some((Block::TORCH, 0)));
+        assert_eq!(placed_block(Block::TORCH, &Vector3::unit_x(), 1.5, &forward), None);
+        assert_eq!(placed_block(Block::DIRT, &Vector3::unit_x(), 1.5, &forward), Some((Block::DIRT, 0)));
+    }
+
+    #[test]
+    fn test_orientation_in_the_metadata() {
+        // The logs lie along the normal of the face they are placed against
+        for (normal, axis) in [(Vector3::unit_x().opposite(), 0), (Vector3::unit_y(), 1), (Vector3::unit_z(), 2)] {
+            let (block, metadata) = placed_block(Block::OAKLOG, &normal, 1.5, &normal).unwrap();
+            assert_eq!(block.axis(metadata), axis);
+        }
+        assert_eq!(laid_axis(0), 1);
+        assert_eq!(Block::DIRT.axis(2), 1);
+
+        // Every facing of the stairs goes through the metadata
+        for facing in [Facing::North, Facing::East, Facing::South, Facing::West] {
+            assert_eq!(Facing::from_metadata(facing.metadata()), facing);
+            assert_eq!(Block::STAIRS.shape(facing.metadata()), BlockShape::Stairs(facing));
+        }
+        assert_eq!(Block::STAIRS.shape(0), BlockShape::Stairs(Facing::North));
+    }
+}
@@ -0,0 +1,94 @@
+use std::collections::{HashMap, HashSet};
+use crate::cube::Cube;
+use crate::primitives::vector::Vector3;
+
+/// Number of cells the water flows away from its source, on a flat floor
+pub const DEFAULT_FLOW_DISTANCE: u8 = 7;
+
+type Cell = [i32; 3];
+
+fn to_cell(pos: &Vector3) -> Cell {
+    let pos = pos.to_cube_coordinates();
+    [pos.x() as i32, pos.y() as i32, pos.z() as i32]
+}
+
+/// The cells of the world waiting for an update at the next tick, and the level of the flowing water.
+///
+/// The cells are updated in the order in which they were scheduled, so that the same actions
+/// always end up with the same world.
+pub struct BlockUpdates {
+    /// Cells to update at the next tick, in the order they were scheduled
+    pending: Vec<Cell>,
+    scheduled: HashSet<Cell>,
+    /// Level of the water that flowed from a source, it decreases by one at each cell.
+    /// The water without a level is a source.
+    levels: HashMap<Cell, u8>,
+    flow_distance: u8,
+}
+
+impl BlockUpdates {
+    pub fn new() -> Self {
+        Self {
+            pending: Vec::new(),
+            scheduled: HashSet::new(),
+            levels: HashMap::new(),
+            flow_distance: DEFAULT_FLOW_DISTANCE,
+        }
+    }
+
+    pub fn flow_distance(&self) -> u8 {
+        self.flow_distance
+    }
+
+    pub fn set_flow_distance(&mut self, flow_distance: u8) {
+        self.flow_distance = flow_distance;
+    }
+
+    /// Updates this cell at the next tick, unless it is already scheduled
+    pub fn schedule(&mut self, pos: &Vector3) {
+        let cell = to_cell(pos);
+        if self.scheduled.insert(cell) {
+            self.pending.push(cell);
+        }
+    }
+
+    /// Updates this cell and its 6 neighbors at the next tick
+    pub fn schedule_around(&mut self, pos: &Vector3) {
+        self.schedule(pos);
+        for neighbor in Cube::neighbors_positions(pos.to_cube_coordinates()) {
+            self.schedule(&neighbor);
+        }
+    }
+
+    /// Returns the cells to update during this tick, the ones scheduled meanwhile wait for the next one
+    pub fn take_pending(&mut self) -> Vec<Vector3> {
+        self.scheduled.clear();
+        std::mem::take(&mut self.pending)
+            .into_iter()
+            .map(|[x, y, z]| Vector3::newi(x, y, z))
+            .collect()
+    }
+
+    pub fn has_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Level of the water in this cell, the sources are at the flow distance
+    pub fn level(&self, pos: &Vector3) -> u8 {
+        *self.levels.get(&to_cell(pos)).unwrap_or(&self.flow_distance)
+    }
+
+    /// Whether the water of this cell was placed there, and not flowed from elsewhere
+    pub fn is_source(&self, pos: &Vector3) -> bool {
+        !self.levels.contains_key(&to_cell(pos))
+    }
+
+    pub fn set_level(&mut self, pos: &Vector3, level: u8) {
+        self.levels.insert(to_cell(pos), level);
+    }
+
+    /// Forgets the level of a cell whose water was removed
+    pub fn remove_level(&mut self, pos: &Vector3) {
+        self.levels.remove(&to_cell(pos));
+    }
+}
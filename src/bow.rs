@@ -0,0 +1,68 @@
+/// Time [s] to draw the bow fully
+pub const FULL_CHARGE: f32 = 1.;
+/// Shortest draw [s] shooting an arrow, the quicker releases shoot nothing
+const MIN_CHARGE: f32 = 0.1;
+/// Speed [cube/s] of an arrow shot by a fully drawn bow
+pub const MAX_ARROW_SPEED: f32 = 30.;
+
+/// Speed [cube/s] of an arrow shot by a bow drawn for this time [s]
+pub fn launch_speed(drawn: f32) -> f32 {
+    MAX_ARROW_SPEED * (drawn / FULL_CHARGE).min(1.)
+}
+
+/// The drawing of a bow: the longer the button is held, the faster the arrow leaves when it is released
+#[derive(Clone, Debug, Default)]
+pub struct BowCharge {
+    /// Time [s] the bow has been drawn for, None while it is not drawn
+    drawn: Option<f32>,
+}
+
+impl BowCharge {
+    pub fn new() -> Self {
+        Self { drawn: None }
+    }
+
+    /// Draws the bow during `dt` seconds while `drawing` is true.
+    /// Returns the speed [cube/s] of the arrow to shoot once it is released.
+    pub fn step(&mut self, drawing: bool, dt: f32) -> Option<f32> {
+        if drawing {
+            self.drawn = Some(self.drawn.unwrap_or(0.) + dt);
+            return None;
+        }
+        let drawn = self.drawn.take()?;
+        (drawn >= MIN_CHARGE).then(|| launch_speed(drawn))
+    }
+
+    /// Part of the full draw reached, from 0 to 1
+    pub fn charge(&self) -> f32 {
+        self.drawn.map_or(0., |drawn| (drawn / FULL_CHARGE).min(1.))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bow::{BowCharge, FULL_CHARGE, MAX_ARROW_SPEED};
+
+    #[test]
+    fn test_bow_charge() {
+        let mut bow = BowCharge::new();
+        assert_eq!(bow.step(false, 0.1), None);
+
+        // Half drawn, the arrow leaves at half the speed
+        for _ in 0..5 {
+            assert_eq!(bow.step(true, FULL_CHARGE / 10.), None);
+        }
+        assert!((bow.charge() - 0.5).abs() < 1e-5);
+        let speed = bow.step(false, 0.1).unwrap();
+        assert!((speed - MAX_ARROW_SPEED / 2.).abs() < 1e-3);
+        assert_eq!(bow.charge(), 0.);
+
+        // Held longer than the full draw
+        bow.step(true, 3. * FULL_CHARGE);
+        assert_eq!(bow.step(false, 0.1), Some(MAX_ARROW_SPEED));
+
+        // Released right away
+        bow.step(true, 0.01);
+        assert_eq!(bow.step(false, 0.1), None);
+    }
+}
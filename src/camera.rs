@@ -1,10 +1,122 @@
+use std::time::Duration;
+use crate::primitives::position::Position;
+use crate::primitives::vector::Vector3;
+
+/// Vertical field of view [rad] of the perspective
+pub const FOV: f32 = std::f32::consts::PI / 3.0;
+
+/// How the world is projected on the screen
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProjectionMode {
+    /// The far objects look smaller, like with an eye
+    Perspective,
+    /// The objects keep their size whatever their distance, for isometric or debug views.
+    /// `scale` [cube] is half the height of the view.
+    Orthographic { scale: f32 },
+}
+
+impl Default for ProjectionMode {
+    fn default() -> Self {
+        ProjectionMode::Perspective
+    }
+}
+
+/// Everything the projection of the view depends on, built once and then updated with the window
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Projection {
+    mode: ProjectionMode,
+    /// Vertical field of view [rad] of the perspective
+    fov: f32,
+    /// Size of the window [px], for its aspect ratio
+    dimensions: (u32, u32),
+}
+
+impl Projection {
+    pub fn new(dimensions: (u32, u32)) -> Self {
+        Self { mode: ProjectionMode::default(), fov: FOV, dimensions }
+    }
+
+    pub fn with_fov(mut self, fov: f32) -> Self {
+        self.set_fov(fov);
+        self
+    }
+
+    pub fn with_mode(mut self, mode: ProjectionMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn fov(&self) -> f32 {
+        self.fov
+    }
+
+    /// Sets the vertical field of view [rad], kept strictly between 0 and a half turn
+    pub fn set_fov(&mut self, fov: f32) {
+        self.fov = fov.clamp(0.01, std::f32::consts::PI - 0.01);
+    }
+
+    pub fn mode(&self) -> ProjectionMode {
+        self.mode
+    }
+
+    pub fn set_mode(&mut self, mode: ProjectionMode) {
+        self.mode = mode;
+    }
+
+    /// To call when the window is resized, so that the view is not stretched
+    pub fn set_dimensions(&mut self, dimensions: (u32, u32)) {
+        self.dimensions = dimensions;
+    }
+
+    /// Returns the projection matrix, for cubes of side `scale`. The field of view is multiplied by `fov_factor`,
+    /// for the effects that widen or narrow it for a moment.
+    pub fn matrix(&self, scale: f32, fov_factor: f32) -> [[f32; 4]; 4] {
+        let fov = (self.fov * fov_factor).clamp(0.01, std::f32::consts::PI - 0.01);
+        projection_matrix(self.dimensions, scale, self.mode, fov)
+    }
+}
+
+/// Where the camera is, relative to the player
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum CameraMode {
+    /// At the eyes of the player
+    #[default]
+    FirstPerson,
+    /// Behind the player, along the opposite of the view, the player itself is drawn
+    ThirdPerson,
+}
+
+impl CameraMode {
+    /// The other mode, to switch between them with a single key
+    pub fn toggle(&self) -> Self {
+        match self {
+            CameraMode::FirstPerson => CameraMode::ThirdPerson,
+            CameraMode::ThirdPerson => CameraMode::FirstPerson,
+        }
+    }
+}
+
+/// Returns the projection matrix of the given mode, see `perspective_matrix` for the arguments.
+/// `fov` [rad] is only used by the perspective.
+pub fn projection_matrix(dim: (u32, u32), scale: f32, mode: ProjectionMode, fov: f32) -> [[f32; 4]; 4] {
+    match mode {
+        ProjectionMode::Perspective => perspective_matrix_with_fov(dim, scale, fov),
+        ProjectionMode::Orthographic { scale: ortho_scale } => orthographic_matrix(dim, scale, ortho_scale),
+    }
+}
+
 /// Returns the perspective matrix, representing the camera model.
-pub fn perspective_matrix(dim: (u32, u32)) -> [[f32; 4]; 4] {
+/// The clipping planes are given in cubes, and `scale` is the side of a cube in world units.
+pub fn perspective_matrix(dim: (u32, u32), scale: f32) -> [[f32; 4]; 4] {
+    perspective_matrix_with_fov(dim, scale, FOV)
+}
+
+/// Same as `perspective_matrix`, with a vertical field of view `fov` [rad]
+pub fn perspective_matrix_with_fov(dim: (u32, u32), scale: f32, fov: f32) -> [[f32; 4]; 4] {
     let (width, height) = dim;
     let aspect_ratio = height as f32 / width as f32;
-    let fov: f32 = std::f32::consts::PI / 3.0;
-    let zfar = 1024.0;
-    let znear = 0.1;
+    let zfar = 1024.0 * scale;
+    let znear = 0.1 * scale;
     let f = 1.0 / (fov / 2.0).tan();
     [
         [f * aspect_ratio, 0.0, 0.0, 0.0],
@@ -13,3 +125,233 @@ pub fn perspective_matrix(dim: (u32, u32)) -> [[f32; 4]; 4] {
         [0.0, 0.0, -(2.0 * zfar * znear) / (zfar - znear), 0.0],
     ]
 }
+
+/// Returns the orthographic matrix, with the same clipping planes as `perspective_matrix`.
+/// `ortho_scale` [cube] is half the height of the view.
+pub fn orthographic_matrix(dim: (u32, u32), scale: f32, ortho_scale: f32) -> [[f32; 4]; 4] {
+    let (width, height) = dim;
+    let aspect_ratio = height as f32 / width as f32;
+    let zfar = 1024.0 * scale;
+    let znear = 0.1 * scale;
+    let f = 1.0 / (ortho_scale * scale);
+    [
+        [f * aspect_ratio, 0.0, 0.0, 0.0],
+        [0.0, f, 0.0, 0.0],
+        [0.0, 0.0, 2.0 / (zfar - znear), 0.0],
+        [0.0, 0.0, -(zfar + znear) / (zfar - znear), 1.0],
+    ]
+}
+
+/// Projects a point of the world space on the screen, in normalized device coordinates.
+/// None if it is behind the camera, out of the screen or beyond the clipping planes.
+pub fn project_on_screen(view: &[[f32; 4]; 4], projection: &[[f32; 4]; 4], point: Vector3) -> Option<[f32; 2]> {
+    // The matrices are stored column by column
+    let transform = |m: &[[f32; 4]; 4], v: [f32; 4]| -> [f32; 4] {
+        std::array::from_fn(|i| (0..4).map(|j| m[j][i] * v[j]).sum())
+    };
+    let clip = transform(projection, transform(view, [point.x(), point.y(), point.z(), 1.]));
+    if clip[3] <= 0. {
+        return None;
+    }
+    let ndc = [clip[0] / clip[3], clip[1] / clip[3], clip[2] / clip[3]];
+    ndc.iter().all(|c| c.abs() <= 1.).then_some([ndc[0], ndc[1]])
+}
+
+/// Below this impact speed [cube/s], a landing does not move the camera
+const HARD_LANDING_SPEED: f32 = 8.;
+/// Depth [cube] of the dip for each cube/s of impact speed
+const DIP_PER_IMPACT_SPEED: f32 = 0.02;
+
+/// View-only animation of the camera when landing hard: the eyes quickly go down, then ease back
+/// to their normal height. It never changes the physical position of the player.
+pub struct LandingDip {
+    /// Time [s] to get back to the normal eye height
+    duration: f32,
+    /// Deepest possible dip [cube]
+    max_dip: f32,
+    /// Depth of the current dip, at its start
+    dip: f32,
+    /// Time since the landing [s]
+    elapsed: f32,
+}
+
+impl LandingDip {
+    pub fn new(duration: f32, max_dip: f32) -> Self {
+        Self { duration, max_dip, dip: 0., elapsed: 0. }
+    }
+
+    pub fn set_config(&mut self, duration: f32, max_dip: f32) {
+        self.duration = duration;
+        self.max_dip = max_dip;
+    }
+
+    /// Starts a new dip, deeper with a higher impact speed [cube/s]
+    pub fn land(&mut self, impact_speed: f32) {
+        if impact_speed < HARD_LANDING_SPEED {
+            return;
+        }
+        self.dip = (impact_speed * DIP_PER_IMPACT_SPEED).min(self.max_dip);
+        self.elapsed = 0.;
+    }
+
+    pub fn step(&mut self, dt: f32) {
+        self.elapsed += dt;
+    }
+
+    /// Vertical offset [cube] of the eyes, negative while dipping and 0 once recovered
+    pub fn offset(&self) -> f32 {
+        if self.duration <= 0. || self.elapsed >= self.duration {
+            return 0.;
+        }
+        // Ease out: most of the recovery happens right after the landing
+        let remaining = 1. - self.elapsed / self.duration;
+        -self.dip * remaining * remaining
+    }
+}
+
+/// Scripted move of the camera towards a pose, for the cutscenes.
+/// It eases in and out, and ends exactly on the destination once the duration is over.
+pub struct CameraGlide {
+    from: Position,
+    to: Position,
+    /// Duration of the whole move
+    duration: Duration,
+    /// Time since the start
+    elapsed: Duration,
+}
+
+impl CameraGlide {
+    pub fn new(from: Position, to: Position, duration: Duration) -> Self {
+        Self { from, to, duration, elapsed: Duration::ZERO }
+    }
+
+    pub fn step(&mut self, elapsed: Duration) {
+        self.elapsed += elapsed;
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+
+    /// Pose of the camera at this point of the move
+    pub fn position(&self) -> Position {
+        if self.is_over() {
+            return self.to.clone();
+        }
+        // Smoothstep: no jump in speed at the start and at the end
+        let t = self.elapsed.as_secs_f32() / self.duration.as_secs_f32();
+        let t = t * t * (3. - 2. * t);
+        let lerp = |a: f32, b: f32| a + (b - a) * t;
+        Position::new(
+            self.from.pos() + (self.to.pos() - self.from.pos()) * t,
+            lerp(self.from.yaw(), self.to.yaw()),
+            lerp(self.from.pitch(), self.to.pitch()),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::camera::{project_on_screen, projection_matrix, LandingDip, Projection, ProjectionMode, FOV};
+    use crate::primitives::vector::Vector3;
+
+    /// Projects a point of the view space on the screen, in normalized device coordinates
+    fn project(m: &[[f32; 4]; 4], p: [f32; 3]) -> [f32; 2] {
+        let v = [p[0], p[1], p[2], 1.];
+        let clip: Vec<f32> = (0..4).map(|i| (0..4).map(|j| m[j][i] * v[j]).sum()).collect();
+        [clip[0] / clip[3], clip[1] / clip[3]]
+    }
+
+    /// Width on the screen of a cube of side 1, at this distance from the camera
+    fn screen_width(mode: ProjectionMode, depth: f32) -> f32 {
+        let m = projection_matrix((800, 600), 1., mode, FOV);
+        project(&m, [0.5, 0., depth])[0] - project(&m, [-0.5, 0., depth])[0]
+    }
+
+    #[test]
+    fn test_orthographic_keeps_sizes() {
+        let ortho = ProjectionMode::Orthographic { scale: 10. };
+        let near = screen_width(ortho, 5.);
+        let far = screen_width(ortho, 50.);
+        assert!((near - far).abs() < 1e-5);
+        assert!((near - 0.075).abs() < 1e-5);
+
+        // While the perspective makes the far cube smaller
+        let near = screen_width(ProjectionMode::Perspective, 5.);
+        let far = screen_width(ProjectionMode::Perspective, 50.);
+        assert!(far < near / 5.);
+
+        // Parallel lines stay parallel: a line going away keeps its position on the screen
+        let m = projection_matrix((800, 600), 1., ortho, FOV);
+        assert!((project(&m, [2., 1., 5.])[0] - project(&m, [2., 1., 500.])[0]).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_projection_builder() {
+        let projection = Projection::new((800, 600));
+        assert_eq!(projection.matrix(1., 1.), projection_matrix((800, 600), 1., ProjectionMode::Perspective, FOV));
+
+        // A narrower field of view zooms in
+        let zoomed = projection.with_fov(FOV / 2.);
+        assert!(zoomed.matrix(1., 1.)[1][1] > projection.matrix(1., 1.)[1][1]);
+        assert_eq!(zoomed.matrix(1., 2.), projection.matrix(1., 1.));
+
+        // The aspect ratio follows the window
+        let mut wide = projection;
+        wide.set_dimensions((1600, 600));
+        assert!((wide.matrix(1., 1.)[0][0] * 2. - projection.matrix(1., 1.)[0][0]).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_harder_landing_dips_deeper() {
+        let mut soft = LandingDip::new(0.3, 0.5);
+        let mut hard = LandingDip::new(0.3, 0.5);
+        soft.land(10.);
+        hard.land(20.);
+        assert!(hard.offset() < soft.offset());
+        assert!(soft.offset() < 0.);
+
+        // Never deeper than the configured limit
+        let mut very_hard = LandingDip::new(0.3, 0.25);
+        very_hard.land(100.);
+        assert_eq!(very_hard.offset(), -0.25);
+
+        // A small hop does not move the camera
+        let mut hop = LandingDip::new(0.3, 0.5);
+        hop.land(3.);
+        assert_eq!(hop.offset(), 0.);
+    }
+
+    #[test]
+    fn test_project_on_screen() {
+        let identity = [[1., 0., 0., 0.], [0., 1., 0., 0.], [0., 0., 1., 0.], [0., 0., 0., 1.]];
+        let m = projection_matrix((800, 600), 1., ProjectionMode::Perspective, FOV);
+        assert_eq!(project_on_screen(&identity, &m, Vector3::new(0., 0., 5.)), Some([0., 0.]));
+        let [x, y] = project_on_screen(&identity, &m, Vector3::new(1., 1., 5.)).unwrap();
+        assert!(x > 0. && y > x);
+        // Behind the camera, or too far aside
+        assert_eq!(project_on_screen(&identity, &m, Vector3::new(0., 0., -5.)), None);
+        assert_eq!(project_on_screen(&identity, &m, Vector3::new(50., 0., 5.)), None);
+    }
+
+    #[test]
+    fn test_landing_dip_recovers_within_duration() {
+        let mut dip = LandingDip::new(0.3, 0.5);
+        dip.land(20.);
+
+        let mut previous = dip.offset();
+        for _ in 0..29 {
+            dip.step(0.01);
+            // Going back up, never overshooting the normal height
+            assert!(dip.offset() >= previous && dip.offset() <= 0.);
+            previous = dip.offset();
+        }
+        assert!(dip.offset() < 0.);
+
+        // Back to normal once the duration is over
+        let mut dip = LandingDip::new(0.3, 0.5);
+        dip.land(20.);
+        dip.step(0.3);
+        assert_eq!(dip.offset(), 0.);
+    }
+}
@@ -0,0 +1,150 @@
+/// Time [s] a received line stays on the screen while the chat is closed
+pub const LINE_LIFETIME: f32 = 10.;
+/// Most lines kept, the oldest ones are dropped
+pub const MAX_LINES: usize = 8;
+/// Longest line [char] the player can write, as relayed by the server
+pub const MAX_DRAFT_LENGTH: usize = 100;
+
+/// A line of the chat, as relayed by the server
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChatLine {
+    pub sender: String,
+    pub text: String,
+    /// Time [s] since the line was received
+    age: f32,
+}
+
+/// The lines exchanged between the players, and the one being written.
+/// The chat is opened to write a line, which is sent to the server when it is closed with enter.
+pub struct Chat {
+    /// The most recent line last
+    lines: Vec<ChatLine>,
+    /// The line being written, None while the chat is closed
+    draft: Option<String>,
+}
+
+impl Chat {
+    pub fn new() -> Self {
+        Self { lines: Vec::new(), draft: None }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.draft.is_some()
+    }
+
+    /// Starts writing a new line
+    pub fn open(&mut self) {
+        self.draft = Some(String::new());
+    }
+
+    /// Stops writing, the line is lost
+    pub fn close(&mut self) {
+        self.draft = None;
+    }
+
+    pub fn draft(&self) -> Option<&str> {
+        self.draft.as_deref()
+    }
+
+    /// Adds a character to the line being written, the control characters are ignored
+    pub fn type_char(&mut self, c: char) {
+        if let Some(draft) = self.draft.as_mut().filter(|draft| draft.chars().count() < MAX_DRAFT_LENGTH) {
+            if !c.is_control() {
+                draft.push(c);
+            }
+        }
+    }
+
+    /// Removes the last character of the line being written
+    pub fn erase(&mut self) {
+        if let Some(draft) = self.draft.as_mut() {
+            draft.pop();
+        }
+    }
+
+    /// Closes the chat. Returns the line written, to be sent to the server, unless it is blank.
+    pub fn send(&mut self) -> Option<String> {
+        self.draft.take().map(|draft| draft.trim().to_string()).filter(|text| !text.is_empty())
+    }
+
+    /// Adds a line relayed by the server
+    pub fn receive(&mut self, sender: String, text: String) {
+        self.lines.push(ChatLine { sender, text, age: 0. });
+        if self.lines.len() > MAX_LINES {
+            self.lines.remove(0);
+        }
+    }
+
+    /// Ages the lines by `dt` seconds. Returns true when a line faded out, and the chat must be drawn again.
+    pub fn step(&mut self, dt: f32) -> bool {
+        let mut faded = false;
+        for line in &mut self.lines {
+            faded |= line.age < LINE_LIFETIME && line.age + dt >= LINE_LIFETIME;
+            line.age += dt;
+        }
+        faded && !self.is_open()
+    }
+
+    /// The lines to show, the most recent one last: all of them while the chat is open, or else the recent ones
+    pub fn visible_lines(&self) -> Vec<&ChatLine> {
+        self.lines.iter().filter(|line| self.is_open() || line.age < LINE_LIFETIME).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::chat::{Chat, LINE_LIFETIME, MAX_DRAFT_LENGTH, MAX_LINES};
+
+    #[test]
+    fn test_write_a_line() {
+        let mut chat = Chat::new();
+        chat.type_char('a');
+        assert_eq!(chat.draft(), None);
+
+        chat.open();
+        "hello!".chars().for_each(|c| chat.type_char(c));
+        chat.type_char('\r');
+        chat.erase();
+        assert_eq!(chat.draft(), Some("hello"));
+        assert_eq!(chat.send(), Some(String::from("hello")));
+        assert!(!chat.is_open());
+
+        // Nothing to send
+        chat.open();
+        chat.type_char(' ');
+        assert_eq!(chat.send(), None);
+        chat.open();
+        chat.type_char('a');
+        chat.close();
+        assert_eq!(chat.send(), None);
+
+        chat.open();
+        (0..2 * MAX_DRAFT_LENGTH).for_each(|_| chat.type_char('a'));
+        assert_eq!(chat.draft().unwrap().len(), MAX_DRAFT_LENGTH);
+    }
+
+    #[test]
+    fn test_received_lines_fade_out() {
+        let mut chat = Chat::new();
+        chat.receive(String::from("arthur"), String::from("hi"));
+        assert!(!chat.step(LINE_LIFETIME / 2.));
+        chat.receive(String::from("johan"), String::from("hello"));
+        assert_eq!(chat.visible_lines().len(), 2);
+
+        // The first line fades out
+        assert!(chat.step(LINE_LIFETIME / 2.));
+        let visible = chat.visible_lines();
+        assert_eq!(visible.len(), 1);
+        assert_eq!((visible[0].sender.as_str(), visible[0].text.as_str()), ("johan", "hello"));
+
+        // The history comes back while writing
+        chat.open();
+        assert_eq!(chat.visible_lines().len(), 2);
+
+        for i in 0..2 * MAX_LINES {
+            chat.receive(String::from("arthur"), i.to_string());
+        }
+        assert_eq!(chat.visible_lines().len(), MAX_LINES);
+        assert_eq!(chat.visible_lines().last().unwrap().text, (2 * MAX_LINES - 1).to_string());
+    }
+}
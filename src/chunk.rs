@@ -1,19 +1,25 @@
+use crate::block_entity::{BlockEntities, BlockEntity};
 use crate::block_kind::Block;
 use crate::block_kind::Block::{DIRT, GRASS};
 use crate::collidable::{Collidable, CollisionData};
 use crate::cube::Cube;
 use crate::primitives::vector::Vector3;
-use crate::world_serializer::{get_serialize_container, serialize_one_chunk, SerializedWorld};
+use crate::world_serializer::{get_serialize_container, serialize_one_chunk, unpack_cube_state, SerializedWorld};
 use strum::IntoEnumIterator;
 use crate::aabb::AABB;
 use crate::primitives::position::Position;
+use std::time::{Duration, Instant};
 
 type ChunkData = [[[Option<Cube>; CHUNK_SIZE]; CHUNK_SIZE]; CHUNK_HEIGHT];
 pub type CubeIndex = (usize, usize, usize);
+/// Identifies a chunk by the (x, z) position of its corner
+pub type ChunkCoord = (i32, i32);
 
 pub const CHUNK_SIZE: usize = 8;
-const CHUNK_HEIGHT: usize = 64;
+pub const CHUNK_HEIGHT: usize = 64;
 pub const CHUNK_FLOOR: usize = 9;
+/// Side [cube] of the super-cubes that replace the cubes of the far chunks
+pub const LOD_CUBE_SIZE: usize = 2;
 
 /// A chunk is a (size * size * h) partition of the space that contains cubes
 ///
@@ -24,18 +30,42 @@ pub const CHUNK_FLOOR: usize = 9;
 ///
 #[derive(Debug, Clone, PartialEq)]
 pub struct Chunk {
-    cubes: ChunkData,
+    /// On the heap, so that moving chunks around (in the workers, the regions or the tests) does not fill the stack
+    cubes: Box<ChunkData>,
     corner: [f32; 2],
+    /// When the chunk was loaded into the world, as long as it is fading in
+    load_time: Option<Instant>,
+    /// State of the cubes that do not fit in their cell (the items of the chests), by the index of their cell
+    block_entities: BlockEntities,
+}
+
+/// Opacity of a chunk loaded at `load_time`: 0 when it is loaded, then growing linearly up to 1 after `duration`
+pub fn fade_alpha(now: Instant, load_time: Instant, duration: Duration) -> f32 {
+    if duration.is_zero() {
+        return 1.;
+    }
+    let elapsed = now.saturating_duration_since(load_time);
+    (elapsed.as_secs_f32() / duration.as_secs_f32()).clamp(0., 1.)
 }
 
 impl Chunk {
     pub fn new(corner: [f32; 2]) -> Self {
         Self {
-            cubes: [[[None; CHUNK_SIZE]; CHUNK_SIZE]; CHUNK_HEIGHT],
+            cubes: Box::new([[[None; CHUNK_SIZE]; CHUNK_SIZE]; CHUNK_HEIGHT]),
             corner,
+            load_time: None,
+            block_entities: BlockEntities::new(),
         }
     }
 
+    pub fn load_time(&self) -> Option<Instant> {
+        self.load_time
+    }
+
+    pub fn set_load_time(&mut self, load_time: Option<Instant>) {
+        self.load_time = load_time;
+    }
+
     pub fn cubes(&self) -> &ChunkData {
         &self.cubes
     }
@@ -44,6 +74,17 @@ impl Chunk {
         self.corner
     }
 
+    pub fn coord(&self) -> ChunkCoord {
+        (self.corner[0] as i32, self.corner[1] as i32)
+    }
+
+    /// Converts a position inside the chunk into the global position of the cube.
+    /// Note that the `ChunkData` is indexed as `[ly][lx][lz]`.
+    pub fn local_to_global(&self, lx: usize, ly: usize, lz: usize) -> [i32; 3] {
+        let (x0, z0) = self.coord();
+        [x0 + lx as i32, ly as i32, z0 + lz as i32]
+    }
+
     /// Returns an iterator over all the positions of the chunk
     pub fn cubes_iter(&self) -> impl Iterator<Item=&Option<Cube>> {
         self.cubes.iter()
@@ -67,7 +108,7 @@ impl Chunk {
                 cubes[(CHUNK_FLOOR as i32 + z_offset) as usize][i][j] = Some(Cube::new([corner[0] + i as f32, (CHUNK_FLOOR as i32 + z_offset) as f32, corner[1] + j as f32], GRASS, 0));
             }
         }
-        Self { cubes, corner }
+        Self { cubes: Box::new(cubes), corner, load_time: None, block_entities: BlockEntities::new() }
     }
 
     /// Fills a full layer of the chunk with one kind of block
@@ -84,7 +125,8 @@ impl Chunk {
         let (i_z, i_x, i_y) = self.get_indices(&at);
         let in_bound = i_z < CHUNK_HEIGHT && i_x < CHUNK_SIZE && i_y < CHUNK_SIZE;
         if in_bound {
-            self.cubes[i_z][i_x][i_y] = None
+            self.cubes[i_z][i_x][i_y] = None;
+            self.block_entities.on_block_removed(&(i_z, i_x, i_y));
         }
     }
 
@@ -93,12 +135,55 @@ impl Chunk {
         let in_bound = i_z < CHUNK_HEIGHT && i_x < CHUNK_SIZE && i_y < CHUNK_SIZE;
         if in_bound {
             self.cubes[i_z][i_x][i_y] = Some(Cube::new(at.as_array(), block, neighbors));
+            self.block_entities.on_block_placed((i_z, i_x, i_y), block);
             self.cubes[i_z][i_x][i_y].as_mut()
         } else {
             None
         }
     }
 
+    /// Metadata of the cell (see `Cube::metadata`), 0 when it is empty
+    pub fn metadata_at(&self, pos: &Vector3) -> u8 {
+        self.cube_at(pos).map_or(0, |cube| cube.metadata())
+    }
+
+    /// Sets the metadata of the cube at this position, nothing happens if the cell is empty
+    pub fn set_metadata(&mut self, at: &Vector3, metadata: u8) {
+        if let Some(cube) = self.cube_at_mut(at) {
+            cube.set_metadata(metadata);
+        }
+    }
+
+    /// State of the cube at this position that does not fit in its cell, see `BlockEntity`
+    pub fn block_entity(&self, pos: &Vector3) -> Option<&BlockEntity> {
+        self.block_entities.get(&self.get_indices(pos))
+    }
+
+    /// Replaces the state of the cube at this position, nothing happens if the cell is empty
+    pub fn set_block_entity(&mut self, at: &Vector3, entity: BlockEntity) {
+        if self.cube_at(at).is_some() {
+            self.block_entities.set(self.get_indices(at), entity);
+        }
+    }
+
+    /// The states of the cubes of the chunk, with their global position
+    pub fn block_entities(&self) -> impl Iterator<Item = ([i32; 3], &BlockEntity)> {
+        self.block_entities.iter().map(|(&(k, i, j), entity)| (self.local_to_global(i, k, j), entity))
+    }
+
+    /// Ticks the block entities of the chunk, returns the ones that changed with their position
+    pub fn tick_block_entities(&mut self) -> Vec<(Vector3, BlockEntity)> {
+        self.block_entities
+            .tick()
+            .into_iter()
+            .filter_map(|index| {
+                let [x, y, z] = self.local_to_global(index.1, index.0, index.2);
+                let entity = self.block_entities.get(&index)?.clone();
+                Some((Vector3::new(x as f32, y as f32, z as f32), entity))
+            })
+            .collect()
+    }
+
     /// Returns true if the position is in the chunk
     pub fn is_in(&self, pos: &Vector3) -> bool {
         // Note that in the received position, the 'y' (from the plane) position is actually the third value
@@ -107,8 +192,9 @@ impl Chunk {
             pos[2] >= self.corner[1] && pos[2] < (self.corner[1] + CHUNK_SIZE as f32)
     }
     
+    /// The cell lets the faces next to it be seen: it is empty, transparent or not a full cube
     fn empty_or_transparent(cube: Option<Cube>) -> bool {
-        cube.is_none() || cube.unwrap().is_transparent()
+        cube.is_none() || !cube.unwrap().block().is_opaque_cube()
     }
 
     /// Returns true if the position in the chunk is not part of a cube.
@@ -186,7 +272,8 @@ impl Chunk {
 
     fn get_indices(&self, pos: &Vector3) -> CubeIndex {
         let i_x = (pos[0] - self.corner[0]) as usize;
-        let i_z = pos[1] as usize;
+        // Below the chunk is out of its bounds like above it, instead of the bottom layer
+        let i_z = if pos[1] < 0. { CHUNK_HEIGHT } else { pos[1] as usize };
         let i_y = (pos[2] - self.corner[1]) as usize;
         (i_z, i_x, i_y)
     }
@@ -209,6 +296,60 @@ impl Chunk {
         self.cube_at_index_mut(self.get_indices(pos))
     }
 
+    /// Box around the whole chunk, up to its highest possible cube
+    pub fn aabb(&self) -> AABB {
+        let s = CHUNK_SIZE as f32;
+        AABB::new(
+            self.corner[1] + s, self.corner[1],
+            CHUNK_HEIGHT as f32, 0.,
+            self.corner[0] + s, self.corner[0],
+        ).unwrap()
+    }
+
+    /// Distance in the x-z plane between the center of the chunk and the point
+    pub fn horizontal_distance_to(&self, pos: &Vector3) -> f32 {
+        let half = CHUNK_SIZE as f32 / 2.;
+        let dx = self.corner[0] + half - pos[0];
+        let dz = self.corner[1] + half - pos[2];
+        (dx * dx + dz * dz).sqrt()
+    }
+
+    /// Coarse version of the chunk, made of super-cubes of side `LOD_CUBE_SIZE`.
+    /// There is one super-cube for each group of cubes with a visible one, of the most common block
+    /// of the group, given by its corner with the lowest coordinates.
+    pub fn coarse_cubes(&self) -> Vec<(Vector3, Block)> {
+        let n = LOD_CUBE_SIZE;
+        let mut coarse = Vec::new();
+        for y in (0..CHUNK_HEIGHT).step_by(n) {
+            for x in (0..CHUNK_SIZE).step_by(n) {
+                for z in (0..CHUNK_SIZE).step_by(n) {
+                    let mut counts: Vec<(Block, usize)> = Vec::new();
+                    let mut visible = false;
+                    for cube in (0..n * n * n).filter_map(|i| self.cubes[y + i / (n * n)][x + (i / n) % n][z + i % n].as_ref()) {
+                        visible |= cube.is_visible();
+                        match counts.iter_mut().find(|(block, _)| block == cube.block()) {
+                            Some((_, count)) => *count += 1,
+                            None => counts.push((*cube.block(), 1)),
+                        }
+                    }
+                    if !visible {
+                        continue;
+                    }
+                    // On a tie, the first block found wins
+                    let mut dominant = counts[0];
+                    for &(block, count) in &counts[1..] {
+                        if count > dominant.1 {
+                            dominant = (block, count);
+                        }
+                    }
+                    let [gx, gy, gz] = self.local_to_global(x, y, z);
+                    coarse.push((Vector3::new(gx as f32, gy as f32, gz as f32), dominant.0));
+                }
+            }
+        }
+        coarse
+    }
+
     pub fn visible_cube_count(&self) -> usize {
         let mut count = 0;
         for k in 0..CHUNK_HEIGHT {
@@ -241,8 +382,10 @@ impl Chunk {
         let mut all_cubes = get_serialize_container();
         serialize_one_chunk(&mut all_cubes, self);
         let world = SerializedWorld {
+            meta: None,
             chunk_corners: vec![self.corner],
             cubes_by_kind: all_cubes,
+            block_entities: self.block_entities().map(|(at, entity)| (at, entity.clone())).collect(),
         };
         serde_json::to_string(&world).unwrap()
     }
@@ -251,15 +394,20 @@ impl Chunk {
         let serialized_world: SerializedWorld = serde_json::from_str(data)?;
         let mut chunk = Chunk::new(serialized_world.chunk_corners[0]);
         for block_kind in Block::iter() {
-            let cubes = serialized_world.cubes_by_kind.get(&block_kind).unwrap();
+            let cubes = serialized_world.cubes_by_kind.get(&block_kind).into_iter().flatten();
             for cube_data in cubes {
                 let x = cube_data[0] as f32;
                 let y = cube_data[1] as f32;
                 let z = cube_data[2] as f32;
-                let neighbors = cube_data[3] as u8;
-                chunk.add_cube(Vector3::new(x, y, z), block_kind, neighbors);
+                let (neighbors, metadata) = unpack_cube_state(cube_data[3]);
+                let at = Vector3::new(x, y, z);
+                chunk.add_cube(at, block_kind, neighbors);
+                chunk.set_metadata(&at, metadata);
             }
         }
+        for (at, entity) in serialized_world.block_entities {
+            chunk.set_block_entity(&Vector3::new(at[0] as f32, at[1] as f32, at[2] as f32), entity);
+        }
         Ok(chunk)
     }
 }
@@ -321,10 +469,32 @@ impl Collidable for Chunk {
 
 #[cfg(test)]
 mod tests {
-    use crate::block_kind::Block::GRASS;
-    use crate::chunk::{Chunk, CHUNK_HEIGHT, CHUNK_SIZE};
+    use crate::block_entity::{BlockEntity, Container};
+    use crate::block_kind::Block;
+    use crate::block_kind::Block::{DIRT, GRASS};
+    use crate::chunk::{fade_alpha, Chunk, CHUNK_HEIGHT, CHUNK_SIZE, LOD_CUBE_SIZE};
+    use crate::player_items::ItemStack;
+    use std::time::{Duration, Instant};
     use crate::primitives::vector::Vector3;
 
+    #[test]
+    fn test_coarse_cubes() {
+        let mut chunk = Chunk::new([0., 0.]);
+        for y in 0..CHUNK_SIZE {
+            for x in 0..CHUNK_SIZE {
+                for z in 0..CHUNK_SIZE {
+                    let block = if (x, y, z) == (0, 0, 0) { DIRT } else { GRASS };
+                    chunk.add_cube(Vector3::new(x as f32, y as f32, z as f32), block, 0);
+                }
+            }
+        }
+        let coarse = chunk.coarse_cubes();
+        assert_eq!(coarse.len() * 8, chunk.visible_cube_count());
+        // The group with one dirt cube is made of its most common block
+        assert_eq!(coarse[0], (Vector3::new(0., 0., 0.), GRASS));
+        assert!(coarse.iter().all(|(corner, _)| corner[0] as usize % LOD_CUBE_SIZE == 0 && corner[1] as usize % LOD_CUBE_SIZE == 0));
+    }
+
     #[test]
     fn test_bounding_area() {
         let chunk = Chunk::new([0., 0.]);
@@ -413,10 +583,38 @@ mod tests {
 
     #[test]
     fn test_chunk_persistence() {
-        let chunk = Chunk::new_for_demo([3., 4.], 5);
+        let mut chunk = Chunk::new_for_demo([3., 4.], 5);
+        let log = Vector3::new(5., 20., 6.);
+        chunk.add_cube(log, Block::OAKLOG, 2);
+        chunk.set_metadata(&log, 2);
         let serialized = chunk.to_json();
         let reconstructed = Chunk::from_json(serialized.as_str()).unwrap();
         assert_eq!(chunk, reconstructed);
+        assert_eq!(reconstructed.metadata_at(&log), 2);
+        assert_eq!(reconstructed.cube_at(&log).unwrap().n_neighbors(), 2);
+    }
+
+    #[test]
+    fn test_chest_keeps_its_items() {
+        let mut chunk = Chunk::new([8., 0.]);
+        let at = Vector3::new(9., 3., 2.);
+        chunk.add_cube(at, Block::CHEST, 0);
+        let mut container = chunk.block_entity(&at).and_then(|entity| entity.container()).unwrap().clone();
+        container.put(4, ItemStack::new(Block::SAND, 1));
+        chunk.set_block_entity(&at, BlockEntity::Chest(container));
+
+        let reconstructed = Chunk::from_json(chunk.to_json().as_str()).unwrap();
+        assert_eq!(chunk, reconstructed);
+        assert_eq!(reconstructed.block_entities().next().unwrap().0, [9, 3, 2]);
+        assert_eq!(reconstructed.block_entity(&at).and_then(|entity| entity.container()).unwrap().get(4), Some(ItemStack::new(Block::SAND, 1)));
+
+        // The items go with the chest, and the other blocks have no state
+        chunk.add_cube(at, Block::STONE, 0);
+        assert_eq!(chunk.block_entities().count(), 0);
+        chunk.add_cube(at, Block::CHEST, 0);
+        assert_eq!(chunk.block_entity(&at), Some(&BlockEntity::Chest(Container::new())));
+        chunk.destroy_cube(at);
+        assert!(chunk.block_entity(&at).is_none());
     }
     
     #[test]
@@ -425,5 +623,33 @@ mod tests {
         let tmp = chunk.cube_at(&Vector3::new(3., 2. * CHUNK_HEIGHT as f32, 3.));
         assert!(tmp.is_none());
     }
-    
+
+    #[test]
+    fn test_local_to_global() {
+        let chunk = Chunk::new([0., 0.]);
+        assert_eq!(chunk.local_to_global(0, 0, 0), [0, 0, 0]);
+        assert_eq!(chunk.local_to_global(3, 10, 7), [3, 10, 7]);
+
+        let s = CHUNK_SIZE as i32;
+        let chunk = Chunk::new([-(CHUNK_SIZE as f32), 2. * CHUNK_SIZE as f32]);
+        assert_eq!(chunk.coord(), (-s, 2 * s));
+        assert_eq!(chunk.local_to_global(0, 1, 0), [-s, 1, 2 * s]);
+        assert_eq!(chunk.local_to_global(CHUNK_SIZE - 1, 1, CHUNK_SIZE - 1), [-1, 1, 3 * s - 1]);
+    }
+
+    #[test]
+    fn test_fade_alpha() {
+        let load_time = Instant::now();
+        let duration = Duration::from_millis(400);
+
+        assert_eq!(fade_alpha(load_time, load_time, duration), 0.);
+        assert!((fade_alpha(load_time + Duration::from_millis(100), load_time, duration) - 0.25).abs() < 1e-6);
+        assert!((fade_alpha(load_time + Duration::from_millis(200), load_time, duration) - 0.5).abs() < 1e-6);
+        assert_eq!(fade_alpha(load_time + duration, load_time, duration), 1.);
+        assert_eq!(fade_alpha(load_time + Duration::from_secs(10), load_time, duration), 1.);
+
+        // Clamped before the load, and no fading without a duration
+        assert_eq!(fade_alpha(load_time, load_time + Duration::from_secs(1), duration), 0.);
+        assert_eq!(fade_alpha(load_time, load_time, Duration::ZERO), 1.);
+    }
 }
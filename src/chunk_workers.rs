@@ -0,0 +1,227 @@
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+
+use crate::block_kind::Block;
+use crate::chunk::{Chunk, ChunkCoord, CHUNK_SIZE};
+use crate::graphics::chunk_mesh::{greedy_mesh_in, ChunkMesh};
+use crate::lighting::ChunkLight;
+use crate::primitives::vector::Vector3;
+use crate::world_meta::WorldMeta;
+use crate::world_serializer::unpack_cube_state;
+
+/// A slow task on a chunk, done away from the render loop
+pub enum ChunkJob {
+    /// Generates the terrain of the chunk at this position, as the generator of the world with these settings does
+    Generate { meta: WorldMeta, coord: ChunkCoord },
+    /// Builds the mesh of a chunk, the chunks next to it hide the faces of its border.
+    /// The light of the chunk and of its neighbors is baked into the faces.
+    /// The ticket is given back with the mesh, to recognize the outdated ones.
+    /// The grass takes the colors of the biomes of the world of this seed.
+    Mesh { chunk: Chunk, neighbors: Vec<Chunk>, lights: Vec<ChunkLight>, seed: u64, ticket: u64 },
+    /// Fills the chunk at this corner with the cubes read from a save: their position and their packed state
+    /// (see `pack_cube_state`)
+    Load { corner: [f32; 2], cubes: Vec<(Block, [i32; 4])> },
+}
+
+impl ChunkJob {
+    pub fn coord(&self) -> ChunkCoord {
+        match self {
+            ChunkJob::Generate { coord, .. } => *coord,
+            ChunkJob::Mesh { chunk, .. } => chunk.coord(),
+            ChunkJob::Load { corner, .. } => (corner[0] as i32, corner[1] as i32),
+        }
+    }
+
+    fn run(self) -> ChunkResult {
+        match self {
+            ChunkJob::Generate { meta, coord } => ChunkResult::Generated(meta.generate_chunk(coord)),
+            ChunkJob::Mesh { chunk, neighbors, lights, seed, ticket } => {
+                ChunkResult::Meshed(chunk.coord(), greedy_mesh_in(&chunk, &neighbors, &lights, seed), ticket)
+            }
+            ChunkJob::Load { corner, cubes } => {
+                let mut chunk = Chunk::new(corner);
+                for (block, cube_data) in cubes {
+                    let at = Vector3::new(cube_data[0] as f32, cube_data[1] as f32, cube_data[2] as f32);
+                    let (neighbors, metadata) = unpack_cube_state(cube_data[3]);
+                    chunk.add_cube(at, block, neighbors);
+                    chunk.set_metadata(&at, metadata);
+                }
+                ChunkResult::Loaded(chunk)
+            }
+        }
+    }
+}
+
+/// What the workers send back to the main thread
+pub enum ChunkResult {
+    Generated(Chunk),
+    Meshed(ChunkCoord, ChunkMesh, u64),
+    Loaded(Chunk),
+}
+
+/// The jobs waiting for a worker, shared by all of them
+struct Queue {
+    jobs: Vec<ChunkJob>,
+    /// The jobs of the chunks closest to this point (on the grid, in the xz plane) are done first
+    focus: [f32; 2],
+    stopped: bool,
+}
+
+/// Index of the job whose chunk is the closest to the focus
+fn nearest_job(jobs: &[ChunkJob], focus: [f32; 2]) -> Option<usize> {
+    let half = CHUNK_SIZE as f32 / 2.;
+    let distance = |job: &ChunkJob| {
+        let (x, z) = job.coord();
+        let dx = x as f32 + half - focus[0];
+        let dz = z as f32 + half - focus[1];
+        dx * dx + dz * dz
+    };
+    (0..jobs.len()).min_by(|&a, &b| distance(&jobs[a]).total_cmp(&distance(&jobs[b])))
+}
+
+/// A pool of threads generating, loading and meshing chunks.
+/// The results are collected by the main thread with `try_recv`, whenever it is ready for them.
+pub struct ChunkWorkers {
+    queue: Arc<(Mutex<Queue>, Condvar)>,
+    results: Receiver<ChunkResult>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl ChunkWorkers {
+    pub fn new(n_threads: usize) -> Self {
+        let queue = Arc::new((Mutex::new(Queue { jobs: Vec::new(), focus: [0., 0.], stopped: false }), Condvar::new()));
+        let (sender, results) = mpsc::channel();
+        let threads = (0..n_threads.max(1))
+            .map(|_| {
+                let queue = Arc::clone(&queue);
+                let sender = sender.clone();
+                std::thread::spawn(move || Self::work(queue, sender))
+            })
+            .collect();
+        Self { queue, results, threads }
+    }
+
+    /// One thread per core, but one left for the render loop
+    pub fn with_available_threads() -> Self {
+        let cores = std::thread::available_parallelism().map_or(2, |n| n.get());
+        Self::new(cores - 1)
+    }
+
+    fn work(queue: Arc<(Mutex<Queue>, Condvar)>, sender: Sender<ChunkResult>) {
+        let (lock, condvar) = &*queue;
+        loop {
+            let job = {
+                let mut queue = condvar
+                    .wait_while(lock.lock().unwrap(), |queue| queue.jobs.is_empty() && !queue.stopped)
+                    .unwrap();
+                if queue.stopped {
+                    return;
+                }
+                let index = nearest_job(&queue.jobs, queue.focus).unwrap();
+                queue.jobs.swap_remove(index)
+            };
+            if sender.send(job.run()).is_err() {
+                return;
+            }
+        }
+    }
+
+    pub fn submit(&self, job: ChunkJob) {
+        let (lock, condvar) = &*self.queue;
+        lock.lock().unwrap().jobs.push(job);
+        condvar.notify_one();
+    }
+
+    /// Sets the point (on the grid) around which the chunks are handled first
+    pub fn set_focus(&self, x: f32, z: f32) {
+        self.queue.0.lock().unwrap().focus = [x, z];
+    }
+
+    /// Number of jobs not started yet
+    pub fn pending_jobs(&self) -> usize {
+        self.queue.0.lock().unwrap().jobs.len()
+    }
+
+    /// Returns the results finished since the last call, without waiting for the others
+    pub fn try_recv(&self) -> Vec<ChunkResult> {
+        self.results.try_iter().collect()
+    }
+
+    /// Waits for the next finished result, `None` if the workers are gone
+    pub fn recv(&self) -> Option<ChunkResult> {
+        self.results.recv().ok()
+    }
+}
+
+impl Drop for ChunkWorkers {
+    fn drop(&mut self) {
+        let (lock, condvar) = &*self.queue;
+        lock.lock().unwrap().stopped = true;
+        condvar.notify_all();
+        for thread in self.threads.drain(..) {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crate::block_kind::Block;
+    use crate::chunk::{Chunk, CHUNK_SIZE};
+    use crate::chunk_workers::{nearest_job, ChunkJob, ChunkResult, ChunkWorkers};
+    use crate::primitives::vector::Vector3;
+    use crate::world_generation::world_generator::WorldGenerator;
+    use crate::world_meta::WorldMeta;
+
+    #[test]
+    fn test_nearest_job_first() {
+        let s = CHUNK_SIZE as i32;
+        let jobs: Vec<ChunkJob> = [(5 * s, 0), (s, -s), (-3 * s, 2 * s)]
+            .into_iter()
+            .map(|coord| ChunkJob::Generate { meta: WorldMeta::new(String::new(), 0), coord })
+            .collect();
+        assert_eq!(nearest_job(&jobs, [0., 0.]), Some(1));
+        assert_eq!(nearest_job(&jobs, [-40., 40.]), Some(2));
+        assert_eq!(nearest_job(&[], [0., 0.]), None);
+    }
+
+    #[test]
+    fn test_generate_and_mesh_in_background() {
+        let workers = ChunkWorkers::new(2);
+        workers.submit(ChunkJob::Generate { meta: WorldMeta::new(String::new(), 3), coord: (0, 0) });
+        let mut chunk = Chunk::new([0., 0.]);
+        chunk.fill_layer(0, Block::DIRT);
+        workers.submit(ChunkJob::Mesh { chunk, neighbors: Vec::new(), lights: Vec::new(), seed: 3, ticket: 7 });
+        // A torch standing on the west face, with no neighbor
+        workers.submit(ChunkJob::Load { corner: [16., -16.], cubes: vec![(Block::TORCH, [17, 3, -12, 0x30])] });
+
+        let mut results = Vec::new();
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while results.len() < 3 && Instant::now() < deadline {
+            results.extend(workers.try_recv());
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(results.len(), 3);
+        for result in results {
+            match result {
+                ChunkResult::Generated(chunk) => assert_eq!(chunk, WorldGenerator::generate_chunk(3, (0, 0))),
+                ChunkResult::Meshed(coord, mesh, ticket) => {
+                    assert_eq!(coord, (0, 0));
+                    assert_eq!(ticket, 7);
+                    // Nothing below the bottom layer
+                    assert_eq!(mesh.n_quads(), 5);
+                }
+                ChunkResult::Loaded(chunk) => {
+                    assert_eq!(chunk.coord(), (16, -16));
+                    let at = Vector3::new(17., 3., -12.);
+                    assert_eq!(chunk.cube_at(&at).map(|cube| *cube.block()), Some(Block::TORCH));
+                    assert_eq!(chunk.metadata_at(&at), 3);
+                }
+            }
+        }
+        assert_eq!(workers.pending_jobs(), 0);
+    }
+}
@@ -1,29 +1,280 @@
 use crate::block_kind::Block;
+use crate::food::Food;
+use crate::item::Item;
+use crate::player_items::{Inventory, ItemStack};
+use crate::tool::Tool;
 use std::collections::HashMap;
 
 /// A grid to encode the input of a crafting recipe
 pub type CraftingGrid = [[Option<Block>; 3]; 3];
 
-/// Structure responsible for the crafting recipes. For now, only its static
-/// methods will be used, but in the end it will load configuration from a file,
-/// so will be stateful
+/// Side of the grid of the inventory, and of the crafting table
+pub const SMALL_GRID: usize = 2;
+pub const LARGE_GRID: usize = 3;
+
+/// How the ingredients of a recipe must be laid on the grid
+#[derive(Clone, Debug, PartialEq)]
+pub enum RecipeShape {
+    /// The rows of the pattern, which can be anywhere on the grid (the empty rows and columns around it are ignored)
+    Shaped(Vec<Vec<Option<Block>>>),
+    /// The ingredients, in any place and any order
+    Shapeless(Vec<Block>),
+}
+
+/// A way to craft a stack of items
+#[derive(Clone, Debug, PartialEq)]
+pub struct Recipe {
+    shape: RecipeShape,
+    output: ItemStack,
+}
+
+/// The smallest part of the grid holding all of its blocks
+fn trim(grid: &CraftingGrid) -> Vec<Vec<Option<Block>>> {
+    let filled = |row: usize, col: usize| grid[row][col].is_some();
+    let rows: Vec<usize> = (0..3).filter(|&row| (0..3).any(|col| filled(row, col))).collect();
+    let cols: Vec<usize> = (0..3).filter(|&col| (0..3).any(|row| filled(row, col))).collect();
+    let (Some(&top), Some(&bottom), Some(&left), Some(&right)) = (rows.first(), rows.last(), cols.first(), cols.last()) else {
+        return Vec::new();
+    };
+    (top..=bottom).map(|row| grid[row][left..=right].to_vec()).collect()
+}
+
+impl Recipe {
+    pub fn shaped(pattern: &[&[Option<Block>]], output: impl Into<Item>, count: usize) -> Self {
+        Self {
+            shape: RecipeShape::Shaped(pattern.iter().map(|row| row.to_vec()).collect()),
+            output: ItemStack::new(output, count),
+        }
+    }
+
+    pub fn shapeless(ingredients: &[Block], output: impl Into<Item>, count: usize) -> Self {
+        Self {
+            shape: RecipeShape::Shapeless(ingredients.to_vec()),
+            output: ItemStack::new(output, count),
+        }
+    }
+
+    pub fn shape(&self) -> &RecipeShape {
+        &self.shape
+    }
+
+    pub fn output(&self) -> ItemStack {
+        self.output
+    }
+
+    /// Number of blocks of each kind consumed by the recipe
+    pub fn ingredients(&self) -> HashMap<Block, usize> {
+        let blocks: Vec<Block> = match &self.shape {
+            RecipeShape::Shaped(pattern) => pattern.iter().flatten().flatten().copied().collect(),
+            RecipeShape::Shapeless(ingredients) => ingredients.clone(),
+        };
+        let mut counts = HashMap::new();
+        for block in blocks {
+            *counts.entry(block).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Whether the recipe can be made on a grid of this side (see `SMALL_GRID` and `LARGE_GRID`)
+    pub fn fits_in(&self, grid_size: usize) -> bool {
+        match &self.shape {
+            RecipeShape::Shaped(pattern) => {
+                pattern.len() <= grid_size && pattern.iter().all(|row| row.len() <= grid_size)
+            }
+            RecipeShape::Shapeless(ingredients) => ingredients.len() <= grid_size * grid_size,
+        }
+    }
+
+    /// Whether the blocks of the grid are the ones of the recipe
+    pub fn matches(&self, grid: &CraftingGrid) -> bool {
+        match &self.shape {
+            RecipeShape::Shaped(pattern) => trim(grid) == *pattern,
+            RecipeShape::Shapeless(ingredients) => {
+                let mut on_grid: Vec<Block> = grid.iter().flatten().flatten().copied().collect();
+                let mut ingredients = ingredients.clone();
+                on_grid.sort_by_key(|block| *block as u8);
+                ingredients.sort_by_key(|block| *block as u8);
+                on_grid == ingredients
+            }
+        }
+    }
+}
+
+/// Structure responsible for the crafting recipes.
+/// The recipes are registered at the start, the UI then looks for the one laid on its grid.
 pub struct CraftingManager {
+    recipes: Vec<Recipe>,
 }
 
 impl CraftingManager {
-    pub fn recipe(grid: &CraftingGrid) -> Option<Block> {
-        let mut recipes: HashMap<CraftingGrid, Block> = HashMap::new();
-
-        // TODO the rows are in the wrong order, be careful
-        recipes.insert(
-            [
-                [None, Some(Block::OAKLOG), None],
-                [None, Some(Block::COBBELSTONE), None],
-                [None, Some(Block::COBBELSTONE), None],
+    pub fn new() -> Self {
+        Self { recipes: Vec::new() }
+    }
+
+    /// The recipes of the game
+    pub fn with_default_recipes() -> Self {
+        let mut manager = Self::new();
+        manager.register(Recipe::shaped(
+            &[&[Some(Block::OAKLOG)], &[Some(Block::COBBELSTONE)], &[Some(Block::COBBELSTONE)]],
+            Tool::Sword,
+            1,
+        ));
+        manager.register(Recipe::shaped(
+            &[
+                &[Some(Block::COBBELSTONE), Some(Block::COBBELSTONE)],
+                &[Some(Block::COBBELSTONE), Some(Block::COBBELSTONE)],
+            ],
+            Block::STONE,
+            4,
+        ));
+        manager.register(Recipe::shapeless(&[Block::DIRT, Block::OAKLEAVES], Block::GRASS, 1));
+        manager.register(Recipe::shaped(&[&[Some(Block::OAKLEAVES)], &[Some(Block::OAKLOG)]], Block::TORCH, 4));
+        manager.register(Recipe::shaped(&[&[Some(Block::SAND), Some(Block::SAND)], &[Some(Block::SAND), Some(Block::SAND)]], Block::GLASS, 4));
+        manager.register(Recipe::shaped(&[&[Some(Block::COBBELSTONE), Some(Block::COBBELSTONE), Some(Block::COBBELSTONE)]], Block::SLAB, 6));
+        manager.register(Recipe::shaped(
+            &[
+                &[Some(Block::COBBELSTONE), None, None],
+                &[Some(Block::COBBELSTONE), Some(Block::COBBELSTONE), None],
+                &[Some(Block::COBBELSTONE), Some(Block::COBBELSTONE), Some(Block::COBBELSTONE)],
             ],
-            Block::SWORD
-        );
-        
-        recipes.get(grid).copied()
+            Block::STAIRS,
+            4,
+        ));
+        manager.register(Recipe::shaped(
+            &[
+                &[Some(Block::OAKLOG), Some(Block::OAKLOG), Some(Block::OAKLOG)],
+                &[Some(Block::OAKLOG), None, Some(Block::OAKLOG)],
+                &[Some(Block::OAKLOG), Some(Block::OAKLOG), Some(Block::OAKLOG)],
+            ],
+            Block::CHEST,
+            1,
+        ));
+        manager.register(Recipe::shaped(
+            &[
+                &[Some(Block::COBBELSTONE), Some(Block::COBBELSTONE)],
+                &[None, Some(Block::OAKLOG)],
+                &[None, Some(Block::OAKLOG)],
+            ],
+            Tool::Hoe,
+            1,
+        ));
+        manager.register(Recipe::shaped(
+            &[
+                &[Some(Block::COBBELSTONE), Some(Block::COBBELSTONE), Some(Block::COBBELSTONE)],
+                &[None, Some(Block::OAKLOG), None],
+                &[None, Some(Block::OAKLOG), None],
+            ],
+            Tool::Pickaxe,
+            1,
+        ));
+        manager.register(Recipe::shaped(
+            &[&[Some(Block::COBBELSTONE)], &[Some(Block::OAKLOG)], &[Some(Block::OAKLOG)]],
+            Tool::Shovel,
+            1,
+        ));
+        manager.register(Recipe::shaped(
+            &[
+                &[Some(Block::COBBELSTONE), Some(Block::COBBELSTONE)],
+                &[Some(Block::COBBELSTONE), Some(Block::OAKLOG)],
+                &[None, Some(Block::OAKLOG)],
+            ],
+            Tool::Axe,
+            1,
+        ));
+        // The wheat is twisted into the string of the bow
+        manager.register(Recipe::shaped(
+            &[
+                &[None, Some(Block::OAKLOG), Some(Block::WHEAT)],
+                &[Some(Block::OAKLOG), None, Some(Block::WHEAT)],
+                &[None, Some(Block::OAKLOG), Some(Block::WHEAT)],
+            ],
+            Tool::Bow,
+            1,
+        ));
+        manager.register(Recipe::shaped(
+            &[&[Some(Block::COBBELSTONE)], &[Some(Block::OAKLOG)], &[Some(Block::OAKLEAVES)]],
+            Item::Arrow,
+            4,
+        ));
+        manager.register(Recipe::shaped(&[&[Some(Block::WHEAT), Some(Block::WHEAT), Some(Block::WHEAT)]], Food::Bread, 1));
+        manager
+    }
+
+    pub fn register(&mut self, recipe: Recipe) {
+        self.recipes.push(recipe);
+    }
+
+    pub fn recipes(&self) -> &[Recipe] {
+        &self.recipes
+    }
+
+    /// The recipe laid on the grid, if any
+    pub fn find(&self, grid: &CraftingGrid) -> Option<&Recipe> {
+        self.recipes.iter().find(|recipe| recipe.matches(grid))
+    }
+
+    /// The recipes that fit in a grid of this side and whose ingredients are in the inventory, for the UI to present them
+    pub fn craftable(&self, inventory: &Inventory, grid_size: usize) -> Vec<&Recipe> {
+        self.recipes
+            .iter()
+            .filter(|recipe| recipe.fits_in(grid_size) && inventory.can_craft(recipe))
+            .collect()
+    }
+
+    /// The output of the recipe laid on the grid, among the recipes of the game
+    pub fn recipe(grid: &CraftingGrid) -> Option<Item> {
+        Self::with_default_recipes().find(grid).map(|recipe| recipe.output().item)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::block_kind::Block::{COBBELSTONE, DIRT, GRASS, OAKLEAVES, OAKLOG, STONE};
+    use crate::crafting::{CraftingManager, Recipe, LARGE_GRID, SMALL_GRID};
+    use crate::item::Item;
+    use crate::player_items::ItemStack;
+    use crate::tool::Tool;
+
+    #[test]
+    fn test_shaped_recipe_anywhere_on_the_grid() {
+        let manager = CraftingManager::with_default_recipes();
+        let mut grid = [[None; 3]; 3];
+        grid[0][2] = Some(OAKLOG);
+        grid[1][2] = Some(COBBELSTONE);
+        grid[2][2] = Some(COBBELSTONE);
+        assert_eq!(CraftingManager::recipe(&grid), Some(Item::Tool(Tool::Sword)));
+
+        // Upside down, or with one more block, it is not a sword anymore
+        grid[0][2] = Some(COBBELSTONE);
+        grid[2][2] = Some(OAKLOG);
+        assert_eq!(manager.find(&grid), None);
+        grid[2][2] = Some(COBBELSTONE);
+        grid[0][2] = Some(OAKLOG);
+        grid[0][0] = Some(DIRT);
+        assert_eq!(manager.find(&grid), None);
+    }
+
+    #[test]
+    fn test_shapeless_recipe_in_any_order() {
+        let manager = CraftingManager::with_default_recipes();
+        let mut grid = [[None; 3]; 3];
+        grid[2][0] = Some(OAKLEAVES);
+        grid[0][1] = Some(DIRT);
+        assert_eq!(manager.find(&grid).map(|recipe| recipe.output()), Some(ItemStack::new(GRASS, 1)));
+        grid[1][1] = Some(DIRT);
+        assert_eq!(manager.find(&grid), None);
+    }
+
+    #[test]
+    fn test_recipe_sizes() {
+        let stone = Recipe::shaped(&[&[Some(COBBELSTONE), Some(COBBELSTONE)], &[Some(COBBELSTONE), Some(COBBELSTONE)]], STONE, 4);
+        assert!(stone.fits_in(SMALL_GRID));
+        assert_eq!(stone.ingredients()[&COBBELSTONE], 4);
+
+        let sword = Recipe::shaped(&[&[Some(OAKLOG)], &[Some(COBBELSTONE)], &[Some(COBBELSTONE)]], Tool::Sword, 1);
+        assert!(!sword.fits_in(SMALL_GRID));
+        assert!(sword.fits_in(LARGE_GRID));
+        assert!(Recipe::shapeless(&[DIRT; 5], GRASS, 1).fits_in(LARGE_GRID));
+        assert!(!Recipe::shapeless(&[DIRT; 5], GRASS, 1).fits_in(SMALL_GRID));
     }
 }
@@ -1,10 +1,14 @@
 use crate::aabb::AABB;
 use crate::block_kind::Block;
+use crate::block_shape::BlockShape;
 use crate::collidable::{Collidable, CollisionData};
 use crate::primitives::face::Plane3;
 use crate::primitives::position::Position;
 use crate::primitives::vector::Vector3;
 
+/// Bits of the metadata of a cell, a nibble
+pub const METADATA_MASK: u8 = 0x0F;
+
 /// Model of a cube in the 3D world.
 /// TODO why the fuck is there Copy here ?
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -12,6 +16,8 @@ pub struct Cube {
     position: Vector3,
     block: Block,
     n_neighbors: u8,
+    /// 4 bits of state of the cell, for now how its block is turned (see `Block::shape` and `Block::axis`)
+    metadata: u8,
 }
 
 impl Cube {
@@ -20,6 +26,7 @@ impl Cube {
             position: Vector3::newf(position),
             block,
             n_neighbors: neighbors,
+            metadata: 0,
         }
     }
 
@@ -74,6 +81,20 @@ impl Cube {
         self.n_neighbors
     }
 
+    pub fn metadata(&self) -> u8 {
+        self.metadata
+    }
+
+    /// Only the 4 lowest bits of the metadata are kept
+    pub fn set_metadata(&mut self, metadata: u8) {
+        self.metadata = metadata & METADATA_MASK;
+    }
+
+    /// Boxes of the block, turned by the metadata
+    pub fn shape(&self) -> BlockShape {
+        self.block.shape(self.metadata)
+    }
+
     pub fn to_cube_coordinates(&self) -> Vector3 {
         self.position.to_cube_coordinates()
     }
@@ -131,90 +152,104 @@ impl Cube {
         ).unwrap()
     }
 
-    fn aabb(&self) -> AABB {
-        Cube::cube_aabb(self.position)
+    /// Boxes of the cube that the entities collide with, see `BlockShape`
+    fn aabbs(&self) -> Vec<AABB> {
+        self.shape().aabbs(&self.position)
     }
 }
 
 impl Collidable for Cube {
     fn collides(&self, aabb: &AABB) -> bool {
-        self.aabb().collides(&aabb)
+        self.block.is_solid() && self.aabbs().iter().any(|cube_aabb| cube_aabb.collides(aabb))
     }
 
+    /// The earliest collision with the boxes of the cube
     fn collision_time(
         &self,
-        position: &Position,
+        _position: &Position,
         aabb: &AABB,
         target: &AABB,
         velocity: &Vector3,
     ) -> Option<CollisionData> {
-        let cube_aabb = self.aabb();
-
-        if aabb.collides(&cube_aabb) {
-            dbg!(&aabb);
-            dbg!(&cube_aabb);
-
-            panic!("should not collide before !");
-        }
-
-        // if no collision, no need to bother
-        if !target.collides(&cube_aabb) {
+        if !self.block.is_solid() {
             return None;
         }
-
-        // compute collision time in each direction
-        let mut tx = if velocity[0] > 0. {
-            (cube_aabb.west() - aabb.east()) / velocity[0]
-        } else {
-            (cube_aabb.east() - aabb.west()) / velocity[0]
-        };
-        let mut ty = if velocity[1] > 0. {
-            (cube_aabb.bottom() - aabb.top()) / velocity[1]
-        } else {
-            (cube_aabb.top() - aabb.bottom()) / velocity[1]
-        };
-
-        let mut tz = if velocity[2] > 0. {
-            (cube_aabb.south() - aabb.north()) / velocity[2]
-        } else {
-            (cube_aabb.north() - aabb.south()) / velocity[2]
-        };
-
-        // if negative, means the collision will not happen: put ∞
-        if tx <= 0. {
-            tx = f32::MAX
-        }
-        if ty <= 0. {
-            ty = f32::MAX
-        }
-        if tz <= 0. {
-            tz = f32::MAX
-        }
-
-        // collision time too big, we can discard it (for some reason, it can be
-        // super high, but not ∞)
-        if tx.min(ty.min(tz)) > 1e10 {
-            return None;
-        }
-
-        if tx < ty && tx < tz {
-            Some(CollisionData {
-                time: tx,
-                normal: Vector3::unit_x() * if velocity[0] > 0. { -1. } else { 1. },
-            })
-        } else if ty < tx && ty < tz {
-            Some(CollisionData {
-                time: ty,
-                normal: Vector3::unit_y() * if velocity[1] > 0. { -1. } else { 1. },
-            })
-        } else if tz < tx && tz < ty {
-            Some(CollisionData {
-                time: tz,
-                normal: Vector3::unit_z() * if velocity[2] > 0. { -1. } else { 1. },
-            })
-        } else {
-            dbg!(tx, ty, tz);
-            panic!("should not be here:");
-        }
+        self.aabbs()
+            .iter()
+            .filter_map(|cube_aabb| box_collision_time(cube_aabb, aabb, target, velocity))
+            .min_by(|a, b| a.time.total_cmp(&b.time))
     }
 }
+
+/// Time before `aabb`, moving at `velocity` toward `target`, hits the box `cube_aabb`
+fn box_collision_time(cube_aabb: &AABB, aabb: &AABB, target: &AABB, velocity: &Vector3) -> Option<CollisionData> {
+    if aabb.collides(cube_aabb) {
+        dbg!(&aabb);
+        dbg!(cube_aabb);
+
+        panic!("should not collide before !");
+    }
+
+    // if no collision, no need to bother
+    if !target.collides(cube_aabb) {
+        return None;
+    }
+
+    // compute collision time in each direction
+    let mut tx = if velocity[0] > 0. {
+        (cube_aabb.west() - aabb.east()) / velocity[0]
+    } else {
+        (cube_aabb.east() - aabb.west()) / velocity[0]
+    };
+    let mut ty = if velocity[1] > 0. {
+        (cube_aabb.bottom() - aabb.top()) / velocity[1]
+    } else {
+        (cube_aabb.top() - aabb.bottom()) / velocity[1]
+    };
+
+    let mut tz = if velocity[2] > 0. {
+        (cube_aabb.south() - aabb.north()) / velocity[2]
+    } else {
+        (cube_aabb.north() - aabb.south()) / velocity[2]
+    };
+
+    // if negative, means the collision will not happen: put ∞
+    if tx <= 0. {
+        tx = f32::MAX
+    }
+    if ty <= 0. {
+        ty = f32::MAX
+    }
+    if tz <= 0. {
+        tz = f32::MAX
+    }
+
+    // collision time too big, we can discard it (for some reason, it can be
+    // super high, but not ∞)
+    if tx.min(ty.min(tz)) > 1e10 {
+        return None;
+    }
+
+    // The axes are resolved independently: the collision happens on the axis that is reached first.
+    // When several of them are reached at the same time (an edge or a corner of the cube is hit),
+    // the one where the target penetrates the least is blocked, so that the entity can
+    // slide along the other ones instead of getting stuck.
+    let times = [tx, ty, tz];
+    let time = tx.min(ty.min(tz));
+    let penetrations = [
+        target.east().min(cube_aabb.east()) - target.west().max(cube_aabb.west()),
+        target.top().min(cube_aabb.top()) - target.bottom().max(cube_aabb.bottom()),
+        target.north().min(cube_aabb.north()) - target.south().max(cube_aabb.south()),
+    ];
+    let axis = (0..3)
+        .filter(|&i| times[i] == time)
+        .min_by(|&a, &b| penetrations[a].total_cmp(&penetrations[b]))
+        .unwrap();
+
+    let unit = [Vector3::unit_x(), Vector3::unit_y(), Vector3::unit_z()];
+    Some(CollisionData {
+        time,
+        normal: unit[axis] * if velocity[axis] > 0. { -1. } else { 1. },
+    })
+}
+
@@ -1,9 +1,27 @@
-use crate::chunk::Chunk;
+use crate::block_kind::Block;
+use crate::chunk::{Chunk, ChunkCoord, CHUNK_SIZE};
 use crate::cube::Cube;
 use crate::graphics::cube::CubeInstance;
 use crate::primitives::vector::Vector3;
+use crate::world_generation::biome::BiomeGenerator;
+use crate::world_generation::biomes_def::BIOMES;
 use glium::glutin::surface::WindowSurface;
 use glium::{Display, VertexBuffer};
+use std::collections::{HashMap, HashSet};
+use strum::IntoEnumIterator;
+
+/// Color of the leaves at this position, the one of its biome in the world of this seed
+fn foliage_tint(seed: u64, position: &Vector3) -> [f32; 3] {
+    let [x, _, z] = position.to_cell();
+    BIOMES[BiomeGenerator::biome_at(seed, x, z) as usize].foliage_tint
+}
+
+/// Chunk that contains this instance
+fn chunk_of(instance: &CubeInstance) -> ChunkCoord {
+    let s = CHUNK_SIZE as i32;
+    let [x, _, z] = instance.position();
+    ((x as i32).div_euclid(s) * s, (z as i32).div_euclid(s) * s)
+}
 
 /// Control the cubes to be drawn
 pub struct CubesToDraw {
@@ -13,75 +31,168 @@ pub struct CubesToDraw {
     // apparently glium can build its buffer vector from multiple references 
     // so no need to copy everythink into one vector but it has to be tested (I haven't tested it)
     cubes_to_draw: Vec<CubeInstance>,
-    selected_cube_index: Option<usize>,
+    /// Transparent cubes are drawn after the opaque ones, from back to front, so that they blend correctly
+    transparent_cubes: Vec<CubeInstance>,
+    /// Seed of the world, whose biomes give their color to the leaves
+    seed: u64,
 }
 
 impl CubesToDraw {
     pub fn new() -> Self {
+        Self::with_seed(0)
+    }
+
+    /// The cubes of the world of this seed
+    pub fn with_seed(seed: u64) -> Self {
         Self {
             cubes_to_draw: Vec::new(),
-            selected_cube_index: None
+            transparent_cubes: Vec::new(),
+            seed,
         }
     }
 
-    /// Set the vector of CubeAttr from parameter
-    pub fn set_cube_to_draw(&mut self, cubes_to_draw: Vec<CubeInstance>) {
+    /// Set the vectors of opaque and transparent CubeAttr from parameter
+    pub fn set_cube_to_draw(&mut self, cubes_to_draw: Vec<CubeInstance>, transparent_cubes: Vec<CubeInstance>) {
         self.cubes_to_draw = cubes_to_draw;
+        self.transparent_cubes = transparent_cubes;
     }
 
     /// Add a CubeAttr to the Vector from the parameter of a Cube
+    /// Transparent cubes go in their own set
     pub fn add_cube(&mut self, c: &Cube) {
-        self.cubes_to_draw.push(CubeInstance::new(c));
+        self.add_cube_with_hidden_faces(c, 0);
+    }
+
+    /// Same as `add_cube`, without drawing the faces of the bitmask.
+    /// A cube that is not a full cube has one instance per box of its shape.
+    pub fn add_cube_with_hidden_faces(&mut self, c: &Cube, hidden_faces: u8) {
+        for mut instance in CubeInstance::instances(c) {
+            instance.set_hidden_faces(hidden_faces);
+            if c.block().takes_foliage_tint() {
+                instance.set_tint(foliage_tint(self.seed, c.position()));
+            }
+            if c.is_transparent() {
+                self.transparent_cubes.push(instance);
+            } else {
+                self.cubes_to_draw.push(instance);
+            }
+        }
     }
 
-    /// Returns the OpenGL buffer with cubes to be drawn
-    /// If you want to have one cube drawn as 'selected', pass it in the argument `selected`
-    pub fn get_cubes_buffer(&mut self, display: &Display<WindowSurface>, selected_cube: Option<Cube>) -> VertexBuffer<CubeInstance>{
-        if let Some(selected) = selected_cube {
-            self.cubes_to_draw.push(CubeInstance::new_selected(&selected));
+    /// Updates the faces that are not drawn for the cube at this position
+    /// Does nothing if there is no such cube
+    pub fn set_hidden_faces(&mut self, position: &Vector3, hidden_faces: u8) {
+        let at = position.as_array();
+        for instance in self.cubes_to_draw.iter_mut().chain(self.transparent_cubes.iter_mut()).filter(|c| c.position() == at) {
+            instance.set_hidden_faces(hidden_faces);
         }
-        let buffer = VertexBuffer::immutable(display, &self.cubes_to_draw);
-        if selected_cube.is_some() {
-            self.cubes_to_draw.pop();
+    }
+
+    /// Same as `set_hidden_faces` for many cubes at once, given by their position
+    pub fn set_hidden_faces_of(&mut self, hidden_faces: &HashMap<[i32; 3], u8>) {
+        if hidden_faces.is_empty() {
+            return;
+        }
+        for instance in self.cubes_to_draw.iter_mut().chain(self.transparent_cubes.iter_mut()) {
+            if let Some(faces) = hidden_faces.get(&instance.position().map(|x| x as i32)) {
+                instance.set_hidden_faces(*faces);
+            }
         }
-        buffer.unwrap()
     }
-    
-    /// Try to remove a cube at at position, 
-    /// Will not panic if a cubeAttr is not present in the Vec
-    pub fn remove_cube(&mut self, position: &Vector3) {
-        for i in 0..self.cubes_to_draw.len() {
-            if self.cubes_to_draw[i].position() == position.as_array() {
-                // Potentially unselect the cube if it was
-                if self.selected_cube_index == Some(i) {
-                    self.selected_cube_index = None;
-                }
-                self.cubes_to_draw.swap_remove(i);
-                return;
+
+    /// Sets the opacity of all the cubes of this chunk
+    pub fn set_chunk_alpha(&mut self, coord: ChunkCoord, alpha: f32) {
+        let s = CHUNK_SIZE as f32;
+        let (x0, z0) = (coord.0 as f32, coord.1 as f32);
+        let in_chunk = |c: &CubeInstance| {
+            let [x, _, z] = c.position();
+            x >= x0 && x < x0 + s && z >= z0 && z < z0 + s
+        };
+        for instance in self.cubes_to_draw.iter_mut().chain(self.transparent_cubes.iter_mut()) {
+            if in_chunk(instance) {
+                instance.set_alpha(alpha);
             }
         }
     }
 
+    /// Shows the image of the animated textures for this time [s], the static cubes are left as they are
+    pub fn set_animation_time(&mut self, time: f32, fps: f32) {
+        if !Block::iter().any(|block| block.animation_frames() > 1) {
+            return;
+        }
+        for instance in self.cubes_to_draw.iter_mut().chain(self.transparent_cubes.iter_mut()) {
+            if instance.is_animated() {
+                instance.set_animation_time(time, fps);
+            }
+        }
+    }
+
+    /// Sorts the transparent cubes from the farthest to the closest of the camera
+    pub fn sort_transparent_cubes(&mut self, camera: &Vector3) {
+        self.transparent_cubes.sort_by(|a, b| b.distance_to(camera).total_cmp(&a.distance_to(camera)));
+    }
+
+    /// Returns the OpenGL buffer with cubes to be drawn.
+    /// The cubes of the `skipped` chunks are left out, they are drawn by other means (meshes).
+    pub fn get_cubes_buffer(&mut self, display: &Display<WindowSurface>, skipped: &HashSet<ChunkCoord>) -> VertexBuffer<CubeInstance>{
+        if !skipped.is_empty() {
+            let instances: Vec<CubeInstance> = self
+                .cubes_to_draw
+                .iter()
+                .filter(|instance| !skipped.contains(&chunk_of(instance)))
+                .copied()
+                .collect();
+            return VertexBuffer::immutable(display, &instances).unwrap();
+        }
+        VertexBuffer::immutable(display, &self.cubes_to_draw).unwrap()
+    }
+
+    /// Returns the OpenGL buffer with the transparent cubes, sorted back-to-front from the camera
+    pub fn get_transparent_cubes_buffer(&mut self, display: &Display<WindowSurface>, camera: &Vector3) -> VertexBuffer<CubeInstance> {
+        self.sort_transparent_cubes(camera);
+        VertexBuffer::immutable(display, &self.transparent_cubes).unwrap()
+    }
+
+    /// Try to remove a cube at at position, with all the instances of its shape
+    /// Will not panic if a cubeAttr is not present in the Vec
+    pub fn remove_cube(&mut self, position: &Vector3) {
+        let at = position.as_array();
+        self.cubes_to_draw.retain(|c| c.position() != at);
+        self.transparent_cubes.retain(|c| c.position() != at);
+    }
+
     pub fn cubes_to_draw(&self) -> &[CubeInstance] {
         &self.cubes_to_draw
     }
 
+    pub fn transparent_cubes_to_draw(&self) -> &[CubeInstance] {
+        &self.transparent_cubes
+    }
+
     pub fn number_cubes_rendered(&self) -> usize {
-        self.cubes_to_draw.len()
+        self.cubes_to_draw.len() + self.transparent_cubes.len()
     }
 
-    /// Add the corresponding CubeAttr from he cube in a chunk
+    /// Add the corresponding CubeAttr from the visible cubes in a chunk
     pub fn add_chunk(&mut self, chunk: &Chunk) {
         for layer in chunk.cubes() {
             for row in layer {
                 for cube in row {
                     if let Some(c) = cube {
-                        self.add_cube(c)
+                        if c.is_visible() {
+                            self.add_cube(c)
+                        }
                     }
                 }
             }
         }
     }
+
+    /// Removes all the cubes of this chunk, when it is unloaded
+    pub fn remove_chunk(&mut self, coord: ChunkCoord) {
+        self.cubes_to_draw.retain(|instance| chunk_of(instance) != coord);
+        self.transparent_cubes.retain(|instance| chunk_of(instance) != coord);
+    }
 }
 
 
@@ -90,7 +201,10 @@ mod tests {
     use crate::cube::Cube;
 
     use super::CubesToDraw;
-    use crate::block_kind::Block::DIRT;
+    use crate::block_kind::Block::{DIRT, OAKLEAVES, STAIRS, WATER};
+    use crate::world_generation::biome::BiomeGenerator;
+    use crate::world_generation::biomes_def::BIOMES;
+    use crate::chunk::CHUNK_SIZE;
     use crate::primitives::vector::Vector3;
 
     #[test]
@@ -109,4 +223,89 @@ mod tests {
         cube_to_draw.remove_cube(&Vector3::newf([0., 0., 0.]));
         assert!(cube_to_draw.cubes_to_draw().len() == 0);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_leaves_take_the_color_of_their_biome() {
+        let seed = 3;
+        let mut cubes_to_draw = CubesToDraw::with_seed(seed);
+        cubes_to_draw.add_cube(&Cube::new([2., 5., 7.], OAKLEAVES, 0));
+        cubes_to_draw.add_cube(&Cube::new([2., 4., 7.], DIRT, 0));
+
+        let biome = &BIOMES[BiomeGenerator::biome_at(seed, 2, 7) as usize];
+        assert_eq!(cubes_to_draw.transparent_cubes_to_draw()[0].tint(), biome.foliage_tint);
+        assert_eq!(cubes_to_draw.cubes_to_draw()[0].tint(), [1.; 3]);
+    }
+
+    #[test]
+    fn test_shaped_cube_is_removed_with_all_its_boxes() {
+        let mut cube_to_draw = CubesToDraw::new();
+        cube_to_draw.add_cube(&Cube::new([0., 0., 0.], STAIRS, 0));
+        cube_to_draw.add_cube(&Cube::new([1., 0., 0.], DIRT, 0));
+        assert_eq!(cube_to_draw.cubes_to_draw().len(), 3);
+
+        cube_to_draw.remove_cube(&Vector3::newf([0., 0., 0.]));
+        assert_eq!(cube_to_draw.cubes_to_draw().len(), 1);
+    }
+
+    #[test]
+    fn test_transparent_cube_goes_in_transparent_set() {
+        let mut cube_to_draw = CubesToDraw::new();
+        cube_to_draw.add_cube(&Cube::new([0., 0., 0.], WATER, 0));
+        cube_to_draw.add_cube(&Cube::new([1., 0., 0.], DIRT, 0));
+
+        assert_eq!(cube_to_draw.transparent_cubes_to_draw().len(), 1);
+        assert_eq!(cube_to_draw.cubes_to_draw().len(), 1);
+        assert_eq!(cube_to_draw.number_cubes_rendered(), 2);
+
+        cube_to_draw.remove_cube(&Vector3::newf([0., 0., 0.]));
+        assert_eq!(cube_to_draw.transparent_cubes_to_draw().len(), 0);
+    }
+
+    #[test]
+    fn test_transparent_cubes_sorted_back_to_front() {
+        let mut cube_to_draw = CubesToDraw::new();
+        cube_to_draw.add_cube(&Cube::new([1., 0., 0.], WATER, 0));
+        cube_to_draw.add_cube(&Cube::new([5., 0., 0.], WATER, 0));
+        cube_to_draw.add_cube(&Cube::new([3., 0., 0.], WATER, 0));
+        cube_to_draw.add_cube(&Cube::new([-10., 0., 0.], WATER, 0));
+
+        let camera = Vector3::new(0., 0., 0.);
+        cube_to_draw.sort_transparent_cubes(&camera);
+        let distances: Vec<f32> = cube_to_draw.transparent_cubes_to_draw().iter().map(|c| c.distance_to(&camera)).collect();
+        assert!(distances.windows(2).all(|w| w[0] >= w[1]));
+        assert_eq!(cube_to_draw.transparent_cubes_to_draw()[0].position(), [-10., 0., 0.]);
+
+        // Moving the camera changes the order
+        let camera = Vector3::new(-10., 0., 0.);
+        cube_to_draw.sort_transparent_cubes(&camera);
+        assert_eq!(cube_to_draw.transparent_cubes_to_draw()[0].position(), [5., 0., 0.]);
+    }
+
+    #[test]
+    fn test_set_chunk_alpha() {
+        let mut cube_to_draw = CubesToDraw::new();
+        let s = CHUNK_SIZE as f32;
+        cube_to_draw.add_cube(&Cube::new([1., 0., 1.], DIRT, 0));
+        cube_to_draw.add_cube(&Cube::new([2., 3., 4.], WATER, 0));
+        cube_to_draw.add_cube(&Cube::new([s, 0., 1.], DIRT, 0));
+
+        cube_to_draw.set_chunk_alpha((0, 0), 0.3);
+        assert_eq!(cube_to_draw.cubes_to_draw()[0].alpha(), 0.3);
+        assert_eq!(cube_to_draw.transparent_cubes_to_draw()[0].alpha(), 0.3);
+        // The other chunk is left as it was
+        assert_eq!(cube_to_draw.cubes_to_draw()[1].alpha(), 1.);
+    }
+
+    #[test]
+    fn test_remove_chunk() {
+        let mut cubes_to_draw = CubesToDraw::new();
+        let s = CHUNK_SIZE as f32;
+        cubes_to_draw.add_cube(&Cube::new([1., 0., 1.], DIRT, 0));
+        cubes_to_draw.add_cube(&Cube::new([2., 0., 1.], WATER, 0));
+        cubes_to_draw.add_cube(&Cube::new([s + 1., 0., 1.], DIRT, 0));
+
+        cubes_to_draw.remove_chunk((0, 0));
+        assert_eq!(cubes_to_draw.number_cubes_rendered(), 1);
+        assert_eq!(cubes_to_draw.cubes_to_draw()[0].position(), [s + 1., 0., 1.]);
+    }
+}
@@ -3,4 +3,12 @@ pub mod entity;
 pub mod entity_manager;
 pub mod monster;
 pub mod walker_in_circle;
-pub mod chaser;
\ No newline at end of file
+pub mod chaser;
+pub mod body;
+pub mod store;
+pub mod pig;
+pub mod pathfinding;
+pub mod zombie;
+pub mod arrow;
+pub mod interpolation;
+pub mod replica;
\ No newline at end of file
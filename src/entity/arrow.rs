@@ -0,0 +1,169 @@
+use std::any::Any;
+
+use crate::entity::body::Body;
+use crate::entity::store::{EntityBehavior, EntityId, EntityStore, EntityUpdate};
+use crate::graphics::cube::CubeInstance;
+use crate::item::Item;
+use crate::primitives::vector::Vector3;
+use crate::world::World;
+
+/// Side [cube] of the box of an arrow
+pub const ARROW_SIZE: f32 = 0.2;
+/// Side [cube] of the cube drawn for an arrow, larger than its box so that it looks stuck in the blocks
+const DRAWN_SIZE: f32 = 0.5;
+/// Damage dealt by an arrow for each cube/s of its speed
+const DAMAGE_PER_SPEED: f32 = 0.2;
+/// Time [s] after which an arrow stuck in a block disappears
+const STUCK_LIFETIME: f32 = 30.;
+
+/// A projectile shot by a bow. It flies along a ballistic curve until it hits an entity (see `strike`)
+/// or sticks into the first block in its way.
+pub struct Arrow {
+    /// Center of the arrow at the last call to `strike`, the entities between it and the arrow are hit
+    from: Vector3,
+    /// Speed [cube/s] of the arrow in flight, None once it is stuck in a block
+    flight: Option<Vector3>,
+    /// Angle [rad] of the arrow around the vertical axis, kept from its flight
+    yaw: f32,
+    /// Time [s] since the arrow got stuck
+    stuck_time: f32,
+}
+
+impl Arrow {
+    /// An arrow leaving `center` (in cube coordinates) at this speed [cube/s]
+    pub fn spawn(entities: &mut EntityStore, center: Vector3, velocity: Vector3) -> EntityId {
+        let bottom = center - Vector3::unit_y() * (ARROW_SIZE / 2.);
+        let arrow = Self { from: center, flight: Some(velocity), yaw: velocity.z().atan2(velocity.x()), stuck_time: 0. };
+        entities.spawn(Body::new(bottom, ARROW_SIZE, ARROW_SIZE).with_velocity(velocity), arrow)
+    }
+
+    pub fn is_stuck(&self) -> bool {
+        self.flight.is_none()
+    }
+}
+
+/// Health lost by an entity hit by an arrow at this speed [cube/s]
+pub fn arrow_damage(speed: f32) -> u8 {
+    (speed * DAMAGE_PER_SPEED).ceil().min(u8::MAX as f32) as u8
+}
+
+impl EntityBehavior for Arrow {
+    /// Sticks into the block met by the body, on any side
+    fn update(&mut self, body: &mut Body, dt: f32, world: &World) -> EntityUpdate {
+        if !world.is_loaded(&body.position()) {
+            return EntityUpdate::Remove;
+        }
+        let Some(flight) = self.flight else {
+            self.stuck_time += dt;
+            return if self.stuck_time < STUCK_LIFETIME { EntityUpdate::Keep } else { EntityUpdate::Remove };
+        };
+
+        let velocity = body.velocity();
+        let hits_ceiling = flight.y() > 0. && velocity.y() == 0.;
+        if body.blocked() || body.on_ground() || hits_ceiling {
+            self.flight = None;
+            body.set_velocity(Vector3::empty());
+            body.set_gravity(0.);
+        } else {
+            self.flight = Some(velocity);
+            self.yaw = velocity.z().atan2(velocity.x());
+        }
+        EntityUpdate::Keep
+    }
+
+    fn instances(&self, body: &Body) -> Vec<CubeInstance> {
+        vec![CubeInstance::new_item(Item::Arrow, &body.center(), DRAWN_SIZE, self.yaw)]
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// The arrows in flight hurt the first entity on their way since the last call, and disappear.
+/// The damage grows with the speed of the arrow, see `arrow_damage`.
+pub fn strike(entities: &mut EntityStore) {
+    let mut moves = Vec::new();
+    for (id, body, arrow) in entities.of_type_mut::<Arrow>() {
+        let from = arrow.from;
+        arrow.from = body.center();
+        if let Some(flight) = arrow.flight {
+            moves.push((id, from, body.center() - from, flight.norm()));
+        }
+    }
+
+    for (id, from, travel, speed) in moves {
+        let distance = travel.norm();
+        if distance < 1e-6 {
+            continue;
+        }
+        let direction = travel / distance;
+        if let Some((target, _)) = entities.raycast(&from, &direction, distance) {
+            entities.hit(target, arrow_damage(speed), &direction);
+            entities.remove(id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::block_kind::Block;
+    use crate::chunk::{Chunk, CHUNK_SIZE};
+    use crate::entity::arrow::{arrow_damage, Arrow, ARROW_SIZE};
+    use crate::entity::pig::Pig;
+    use crate::primitives::vector::Vector3;
+    use crate::world::World;
+
+    #[test]
+    fn test_arrows_fly_and_stick() {
+        // A wall at x = 6
+        let mut chunk = Chunk::new([0., 0.]);
+        chunk.fill_layer(0, Block::STONE);
+        for y in 1..8 {
+            for z in 0..CHUNK_SIZE as i32 {
+                chunk.add_cube(Vector3::newi(6, y, z), Block::STONE, 0);
+            }
+        }
+        let mut world = World::new(vec![chunk]);
+        let id = Arrow::spawn(world.entities_mut(), Vector3::new(1., 4., 2.5), Vector3::new(12.5, 2., 0.));
+
+        // It goes up, then falls on its way
+        let mut highest: f32 = 0.;
+        for _ in 0..30 {
+            world.step_entities(0.02);
+            highest = highest.max(world.entities().body(id).unwrap().center().y());
+        }
+        let center = world.entities().body(id).unwrap().center();
+        assert!(highest > 4. && center.y() < highest);
+
+        for _ in 0..20 {
+            world.step_entities(0.02);
+        }
+        assert!(world.entities().behavior::<Arrow>(id).unwrap().is_stuck());
+        let stuck = world.entities().body(id).unwrap().center();
+        assert!((stuck.x() - (6. - ARROW_SIZE / 2.)).abs() < 1e-3, "{stuck:?}");
+        world.step_entities(1.);
+        assert_eq!(world.entities().body(id).unwrap().center(), stuck);
+    }
+
+    #[test]
+    fn test_arrows_hit_entities() {
+        let mut chunk = Chunk::new([0., 0.]);
+        chunk.fill_layer(0, Block::GRASS);
+        let mut world = World::new(vec![chunk]);
+        let pig = Pig::spawn(world.entities_mut(), Vector3::new(5., 1., 2.), 3);
+        let health = world.entities().health(pig).unwrap();
+        let arrow = Arrow::spawn(world.entities_mut(), Vector3::new(1., 1.7, 2.5), Vector3::new(30., 0., 0.));
+
+        for _ in 0..20 {
+            world.step_entities(0.02);
+        }
+        assert!(world.entities().body(arrow).is_none());
+        assert!(world.entities().health(pig).unwrap() < health);
+        assert!(arrow_damage(30.) > arrow_damage(10.));
+    }
+}
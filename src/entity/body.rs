@@ -0,0 +1,183 @@
+use crate::aabb::AABB;
+use crate::player::{GRAVITY, TERMINAL_VELOCITY};
+use crate::primitives::vector::Vector3;
+use crate::world::World;
+
+/// Fraction of the speed of a knockback lost per second
+const KNOCKBACK_DRAG: f32 = 6.;
+
+/// The physical part of an entity: a box moving through the cubes of the world, pulled down by the gravity.
+/// All the entities (item drops, falling blocks, mobs, projectiles) move with it, see `EntityStore`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Body {
+    /// Center of the bottom of the box, in cube coordinates
+    position: Vector3,
+    /// Speed [cube/s]
+    velocity: Vector3,
+    /// Horizontal speed [cube/s] of the body thrown back by a hit, slowing down, on top of its own speed
+    knockback: Vector3,
+    /// Side [cube] of the box, horizontally
+    width: f32,
+    /// Height [cube] of the box
+    height: f32,
+    /// Factor of the gravity pulling the body, 0 for the ones floating in the air
+    gravity: f32,
+    /// The body rests on a cube since its last step
+    on_ground: bool,
+    /// A cube stopped the horizontal movement of the body during its last step
+    blocked: bool,
+}
+
+impl Body {
+    /// A body at rest, its bottom at `position`
+    pub fn new(position: Vector3, width: f32, height: f32) -> Self {
+        Self {
+            position,
+            velocity: Vector3::empty(),
+            knockback: Vector3::empty(),
+            width,
+            height,
+            gravity: 1.,
+            on_ground: false,
+            blocked: false,
+        }
+    }
+
+    pub fn with_velocity(mut self, velocity: Vector3) -> Self {
+        self.velocity = velocity;
+        self
+    }
+
+    pub fn with_gravity(mut self, gravity: f32) -> Self {
+        self.gravity = gravity;
+        self
+    }
+
+    pub fn position(&self) -> Vector3 {
+        self.position
+    }
+
+    pub fn set_position(&mut self, position: Vector3) {
+        self.position = position;
+    }
+
+    /// Center of the box
+    pub fn center(&self) -> Vector3 {
+        self.position + Vector3::unit_y() * (self.height / 2.)
+    }
+
+    pub fn velocity(&self) -> Vector3 {
+        self.velocity
+    }
+
+    pub fn set_velocity(&mut self, velocity: Vector3) {
+        self.velocity = velocity;
+    }
+
+    pub fn set_gravity(&mut self, gravity: f32) {
+        self.gravity = gravity;
+    }
+
+    /// Throws the body: the horizontal part of the speed [cube/s] is a knockback, see `knockback`, and the vertical
+    /// part lifts the body
+    pub fn knock(&mut self, speed: Vector3) {
+        self.knockback = Vector3::new(speed.x(), 0., speed.z());
+        self.velocity[1] = self.velocity.y().max(speed.y());
+    }
+
+    pub fn knockback(&self) -> Vector3 {
+        self.knockback
+    }
+
+    pub fn width(&self) -> f32 {
+        self.width
+    }
+
+    pub fn height(&self) -> f32 {
+        self.height
+    }
+
+    pub fn on_ground(&self) -> bool {
+        self.on_ground
+    }
+
+    pub fn blocked(&self) -> bool {
+        self.blocked
+    }
+
+    /// The box of the body, in cube coordinates
+    pub fn aabb(&self) -> AABB {
+        let r = self.width / 2.;
+        let p = self.position;
+        AABB::new(p.z() + r, p.z() - r, p.y() + self.height, p.y(), p.x() + r, p.x() - r).unwrap()
+    }
+
+    /// Accelerates the body with the gravity and moves it during `dt` seconds, until the cubes in its way.
+    /// The speed is lost on the axes where a cube stops the body. The knockback slows down.
+    /// The bodies in the chunks that are not loaded wait for them.
+    pub fn step(&mut self, dt: f32, world: &World) {
+        if !world.is_loaded(&self.position) {
+            return;
+        }
+        self.velocity[1] = (self.velocity[1] - GRAVITY * self.gravity * dt).max(-TERMINAL_VELOCITY);
+        let delta = (self.velocity + self.knockback) * dt;
+        let allowed = world.resolve_on_grid(&self.aabb(), &delta);
+        self.blocked = false;
+        for axis in 0..3 {
+            if (allowed[axis] - delta[axis]).abs() > 1e-6 {
+                self.velocity[axis] = 0.;
+                self.knockback[axis] = 0.;
+                self.blocked |= axis != 1;
+            }
+        }
+        self.knockback = self.knockback * (1. - KNOCKBACK_DRAG * dt).max(0.);
+        self.on_ground = delta.y() < 0. && allowed.y() > delta.y() + 1e-6;
+        self.position += allowed;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::block_kind::Block;
+    use crate::chunk::Chunk;
+    use crate::entity::body::Body;
+    use crate::primitives::vector::Vector3;
+    use crate::world::World;
+
+    #[test]
+    fn test_bodies_fall_and_slide() {
+        let mut chunk = Chunk::new([0., 0.]);
+        chunk.fill_layer(0, Block::STONE);
+        chunk.add_cube(Vector3::new(4., 1., 2.), Block::STONE, 0);
+        chunk.add_cube(Vector3::new(4., 2., 2.), Block::STONE, 0);
+        let world = World::new(vec![chunk]);
+
+        // Thrown toward the wall, the body stops against it and falls on the floor
+        let mut body = Body::new(Vector3::new(2.5, 3., 2.5), 0.5, 0.5).with_velocity(Vector3::new(5., 0., 0.));
+        for _ in 0..100 {
+            body.step(0.02, &world);
+        }
+        assert!(body.on_ground());
+        assert!((body.position().y() - 1.).abs() < 1e-4, "{:?}", body.position());
+        assert!((body.position().x() - 3.75).abs() < 1e-4, "{:?}", body.position());
+        assert_eq!(body.velocity().x(), 0.);
+        assert!(!body.blocked());
+
+        // Knocked back, it slides and stops
+        let mut body = Body::new(Vector3::new(1.5, 1., 2.5), 0.5, 0.5);
+        body.knock(Vector3::new(4., 3., 0.));
+        body.step(0.02, &world);
+        assert!(body.position().y() > 1. && body.position().x() > 1.5);
+        for _ in 0..100 {
+            body.step(0.02, &world);
+        }
+        assert!(body.knockback().norm() < 1e-3);
+        assert!(body.on_ground() && body.position().x() < 3.75);
+
+        // Without gravity, it floats
+        let mut body = Body::new(Vector3::new(2.5, 3., 2.5), 0.5, 0.5).with_gravity(0.);
+        body.step(1., &world);
+        assert_eq!(body.position(), Vector3::new(2.5, 3., 2.5));
+        assert!(!body.on_ground());
+    }
+}
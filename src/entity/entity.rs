@@ -2,7 +2,14 @@ use crate::aabb::AABB;
 use crate::graphics::entity::EntityCube;
 use crate::entity::humanoid;
 use crate::entity::humanoid::humanoid_aabb;
+use crate::entity::interpolation::Interpolation;
+use crate::player::FOREHEAD;
 use crate::primitives::position::Position;
+use crate::primitives::vector::Vector3;
+use crate::server::game_server::SNAPSHOT_PERIOD;
+
+/// Height [cube] of the name tags above the top of the head
+const NAME_TAG_HEIGHT: f32 = 0.5;
 
 #[derive(Debug, PartialEq, Clone)]
 /// Enum for the different types of entity
@@ -44,7 +51,10 @@ impl EntityKind {
 pub struct Entity {
     id: usize,
     entity_type: EntityKind,
-    position: Position,
+    /// The pose drawn, between the snapshots sent by the server
+    motion: Interpolation,
+    /// Shown above the head of the other players
+    name: Option<String>,
 }
 
 impl Entity {
@@ -52,16 +62,43 @@ impl Entity {
         Self { 
             id,
             entity_type,
-            position,
+            motion: Interpolation::new(position, SNAPSHOT_PERIOD.as_secs_f32()),
+            name: None,
         }
     }
+
+    pub fn with_name(mut self, name: String) -> Self {
+        self.name = Some(name);
+        self
+    }
     
+    /// The position of a new snapshot. The players, whose positions come at each snapshot, reach it smoothly,
+    /// the monsters are moved there at once.
     pub fn set_position(&mut self, position: Position) {
-        self.position = position;
+        if self.entity_type.is_player() {
+            self.motion.push(position);
+        } else {
+            self.motion = Interpolation::new(position, SNAPSHOT_PERIOD.as_secs_f32());
+        }
+    }
+
+    /// Moves towards the last snapshot
+    pub fn step(&mut self, dt: f32) {
+        self.motion.step(dt);
+    }
+
+    /// The position drawn, of the eyes
+    pub fn position(&self) -> Position {
+        self.motion.position()
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
     }
 
-    pub fn position(&self) -> &Position {
-        &self.position
+    /// Where the name tag is drawn, above the head, for a world where a cube is `scale` world units wide
+    pub fn name_tag_position(&self, scale: f32) -> Vector3 {
+        self.position().pos() + Vector3::unit_y() * ((FOREHEAD + NAME_TAG_HEIGHT) * scale)
     }
     
     pub fn id(&self) -> usize {
@@ -74,16 +111,17 @@ impl Entity {
 
     /// Draw the entity and return a Vec of EntityCube
     pub fn get_opengl_entities(&self) -> Vec<EntityCube> {
+        let position = self.position();
         match self.entity_type {
-            EntityKind::Player => humanoid::get_opengl_entities(self.position.clone(), 0),
-            EntityKind::Monster1 => humanoid::get_opengl_entities(self.position.clone(), 1),
-            EntityKind::Monster2 => humanoid::get_opengl_entities(self.position.clone(), 2),
+            EntityKind::Player => humanoid::get_opengl_entities(position, 0),
+            EntityKind::Monster1 => humanoid::get_opengl_entities(position, 1),
+            EntityKind::Monster2 => humanoid::get_opengl_entities(position, 2),
         }
     }
 
     pub fn aabb(&self) -> AABB {
         match self.entity_type {
-            EntityKind::Player | EntityKind::Monster1 | EntityKind::Monster2 => humanoid_aabb(&self.position)
+            EntityKind::Player | EntityKind::Monster1 | EntityKind::Monster2 => humanoid_aabb(&self.position())
         
         }
     }
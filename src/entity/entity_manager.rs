@@ -25,6 +25,13 @@ impl EntityManager {
         self.entities.insert(id, entity);
     }
 
+    /// Register another player, with the name shown above its head
+    pub fn register_player(&mut self, id: u8, name: String, pos: Position) {
+        println!("{name} has joined the game: {id}");
+        let entity = Entity::new(id as usize, EntityKind::Player, pos).with_name(name);
+        self.entities.insert(id, entity);
+    }
+
     /// Remove an entity from the Manager
     pub fn remove_entity(&mut self, id: u8) {
         if !self.entities.contains_key(&id) {
@@ -37,6 +44,21 @@ impl EntityManager {
         self.entities.get_mut(&id).map(|entity| entity.set_position(position));
     }
 
+    /// Moves the entities between the snapshots of the server
+    pub fn step(&mut self, dt: f32) {
+        self.entities.values_mut().for_each(|entity| entity.step(dt));
+    }
+
+    /// The names of the players, with the point above their head where they are shown.
+    /// Only the players closer than `range` (in world units) to `eye` are named.
+    pub fn name_tags(&self, eye: Vector3, range: f32, scale: f32) -> Vec<(String, Vector3)> {
+        self.entities
+            .values()
+            .filter_map(|entity| Some((entity.name()?.to_string(), entity.name_tag_position(scale))))
+            .filter(|(_, at)| (*at - eye).norm() <= range)
+            .collect()
+    }
+
     /// Returns the list of OpenGL attributes to be rendered
     pub fn get_opengl_entities(&self) -> Vec<EntityCube> {
         self.entities
@@ -66,6 +88,7 @@ mod tests {
     use crate::entity::entity_manager::EntityManager;
     use crate::primitives::position::Position;
     use crate::primitives::vector::Vector3;
+    use crate::server::game_server::SNAPSHOT_PERIOD;
 
     #[test]
     fn test_basic_functionality() {
@@ -92,6 +115,31 @@ mod tests {
         assert_eq!(None, mgr.attack(Vector3::unit_x(), Vector3::unit_z()));
     }
 
+    #[test]
+    fn test_named_players_move_smoothly() {
+        let mut mgr = EntityManager::new();
+        mgr.register_new_entity(1, EntityKind::Monster1, Position::from_pos(Vector3::empty()));
+        mgr.register_player(2, "arthur".to_string(), Position::from_pos(Vector3::empty()));
+
+        // Only the players have a name
+        let tags = mgr.name_tags(Vector3::empty(), 10., 1.);
+        assert_eq!(tags.len(), 1);
+        assert_eq!(tags[0].0, "arthur");
+        assert!(tags[0].1.y() > 0.);
+        assert!(mgr.name_tags(Vector3::new(20., 0., 0.), 10., 1.).is_empty());
+
+        // The monsters are moved at once
+        mgr.set_position(1, Position::from_pos(Vector3::new(4., 0., 0.)));
+        assert_eq!(mgr.entities[&1].position().pos(), Vector3::new(4., 0., 0.));
+
+        // Halfway to the next snapshot
+        mgr.set_position(2, Position::from_pos(Vector3::new(4., 0., 0.)));
+        mgr.step(SNAPSHOT_PERIOD.as_secs_f32() / 2.);
+        assert!((mgr.name_tags(Vector3::empty(), 10., 1.)[0].1.x() - 2.).abs() < 1e-4);
+        mgr.step(SNAPSHOT_PERIOD.as_secs_f32());
+        assert!((mgr.name_tags(Vector3::empty(), 10., 1.)[0].1.x() - 4.).abs() < 1e-4);
+    }
+
     #[test]
     #[should_panic]
     fn test_remove() {
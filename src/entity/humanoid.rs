@@ -6,6 +6,7 @@ use crate::player::{DIAMETER, FOREHEAD, PLAYER_HEIGHT};
 use crate::graphics::entity::EntityCube;
 use crate::primitives::position::Position;
 use crate::primitives::vector::Vector3;
+use crate::resource_pack::ResourcePack;
 use crate::texture;
 use crate::texture::ImageCut;
 
@@ -72,14 +73,15 @@ const PLAYER_CUT_TEMPLATE: [ImageCut; 24] = [
     [ 8. / 12., 1. / 4., 1. / 12., 1. / 8.],
 ];
 
-const HUMANOID_TEXTURES_PATH: [&str; 2] = [
+const HUMANOID_TEXTURES_PATH: [&str; 3] = [
     "player.png",
     "monster.png",
+    "pig.png",
 ]; 
 
 /// Load the texture for an humanoid entity
-pub fn load_humanoid_textures(root: &str, display: &Display<WindowSurface>) -> Texture2dArray {
-    texture::load_texture_cut(root, HUMANOID_TEXTURES_PATH.to_vec(), display, &PLAYER_CUT_TEMPLATE)
+pub fn load_humanoid_textures(pack: &ResourcePack, root: &str, display: &Display<WindowSurface>) -> Texture2dArray {
+    texture::load_texture_cut(pack, root, HUMANOID_TEXTURES_PATH.to_vec(), display, &PLAYER_CUT_TEMPLATE)
 }
 
 /// Return a vector of EntityCube forming a humanoid
@@ -115,12 +117,18 @@ pub fn get_opengl_entities(mut position: Position, monster_type: u8) -> Vec<Enti
 
 /// Returns the bounding box around the player
 pub fn humanoid_aabb(eye_position: &Position) -> AABB {
+    scaled_humanoid_aabb(eye_position, 1.)
+}
+
+/// Same as `humanoid_aabb`, for a world where a cube is `scale` world units wide
+pub fn scaled_humanoid_aabb(eye_position: &Position, scale: f32) -> AABB {
+    let radius = scale * DIAMETER / 2.;
     AABB::new(
-        eye_position.z() + DIAMETER / 2.,
-        eye_position.z() - DIAMETER / 2.,
-        eye_position.y() + FOREHEAD,
-        eye_position.y() - PLAYER_HEIGHT + FOREHEAD,
-        eye_position.x() + DIAMETER / 2.,
-        eye_position.x() - DIAMETER / 2.,
+        eye_position.z() + radius,
+        eye_position.z() - radius,
+        eye_position.y() + scale * FOREHEAD,
+        eye_position.y() - scale * (PLAYER_HEIGHT - FOREHEAD),
+        eye_position.x() + radius,
+        eye_position.x() - radius,
     ).unwrap()
 }
@@ -0,0 +1,84 @@
+use std::f32::consts::PI;
+
+use crate::primitives::position::Position;
+
+/// Pose of a remote entity between the snapshots sent by the server. Each snapshot is reached in one period
+/// of the snapshots, from where the entity was drawn when it was received, so that it moves smoothly
+/// instead of jumping at each of them.
+#[derive(Clone, Debug)]
+pub struct Interpolation {
+    from: Position,
+    to: Position,
+    /// Time [s] since the last snapshot
+    elapsed: f32,
+    /// Time [s] between two snapshots
+    period: f32,
+}
+
+/// Difference [rad] from the angle `a` to the angle `b`, the short way around
+fn angle_difference(a: f32, b: f32) -> f32 {
+    (b - a + PI).rem_euclid(2. * PI) - PI
+}
+
+impl Interpolation {
+    /// An entity standing at `position`, receiving a snapshot every `period` seconds
+    pub fn new(position: Position, period: f32) -> Self {
+        Self { from: position.clone(), to: position, elapsed: 0., period }
+    }
+
+    /// Heads to the pose of a new snapshot
+    pub fn push(&mut self, position: Position) {
+        self.from = self.position();
+        self.to = position;
+        self.elapsed = 0.;
+    }
+
+    pub fn step(&mut self, dt: f32) {
+        self.elapsed += dt;
+    }
+
+    /// The pose to draw, it stays on the last snapshot until the next one arrives
+    pub fn position(&self) -> Position {
+        let t = if self.period > 0. { (self.elapsed / self.period).min(1.) } else { 1. };
+        let lerp = |a: f32, b: f32| a + angle_difference(a, b) * t;
+        Position::new(
+            self.from.pos() + (self.to.pos() - self.from.pos()) * t,
+            lerp(self.from.yaw(), self.to.yaw()),
+            self.from.pitch() + (self.to.pitch() - self.from.pitch()) * t,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use crate::entity::interpolation::Interpolation;
+    use crate::primitives::position::Position;
+    use crate::primitives::vector::Vector3;
+
+    #[test]
+    fn test_moves_between_snapshots() {
+        let mut motion = Interpolation::new(Position::new(Vector3::empty(), 0., 0.), 0.05);
+        motion.push(Position::new(Vector3::new(2., 0., 0.), 0., 0.4));
+        assert_eq!(motion.position().pos(), Vector3::empty());
+
+        motion.step(0.025);
+        assert!((motion.position().x() - 1.).abs() < 1e-5);
+        assert!((motion.position().pitch() - 0.2).abs() < 1e-5);
+
+        // A new snapshot starts from where the entity is drawn
+        motion.push(Position::new(Vector3::new(3., 0., 0.), 0., 0.4));
+        assert!((motion.position().x() - 1.).abs() < 1e-5);
+        motion.step(0.2);
+        assert_eq!(motion.position().pos(), Vector3::new(3., 0., 0.));
+    }
+
+    #[test]
+    fn test_turns_the_short_way() {
+        let mut motion = Interpolation::new(Position::new(Vector3::empty(), PI - 0.1, 0.), 0.05);
+        motion.push(Position::new(Vector3::empty(), -PI + 0.1, 0.));
+        motion.step(0.025);
+        assert!((motion.position().yaw().rem_euclid(2. * PI) - PI).abs() < 1e-4);
+    }
+}
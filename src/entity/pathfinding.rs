@@ -0,0 +1,192 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use crate::primitives::vector::Vector3;
+use crate::world::World;
+
+/// A cell of the grid, holding the feet of a walking entity
+pub type Cell = [i32; 3];
+
+/// Cost of a step to the next cell
+const WALK_COST: u32 = 10;
+/// Cost added to a step for the jump onto a cube
+const JUMP_COST: u32 = 10;
+/// Cost added to a step for each cube of a fall
+const FALL_COST: u32 = 5;
+/// Highest fall [cube] taken by a path
+pub const MAX_FALL: i32 = 3;
+/// Number of cells looked at before the search gives up
+const MAX_VISITED: usize = 2000;
+
+/// The cell in which the bottom of a box at this position (in cube coordinates) stands
+pub fn cell_of(position: &Vector3) -> Cell {
+    [position.x().floor() as i32, (position.y() + 1e-2).floor() as i32, position.z().floor() as i32]
+}
+
+/// The position of the middle of the bottom of the cell
+pub fn cell_bottom(cell: &Cell) -> Vector3 {
+    Vector3::new(cell[0] as f32 + 0.5, cell[1] as f32, cell[2] as f32 + 0.5)
+}
+
+fn is_solid(world: &World, cell: Cell) -> bool {
+    world.block_at(&Vector3::newi(cell[0], cell[1], cell[2])).is_some_and(|block| block.is_solid())
+}
+
+/// Whether an entity `height` cubes high fits in the column of cells starting at `cell`
+fn fits(world: &World, cell: Cell, height: i32) -> bool {
+    (0..height).all(|dy| !is_solid(world, [cell[0], cell[1] + dy, cell[2]]))
+}
+
+/// Whether an entity `height` cubes high can stand with its feet in the cell: it fits and the cell below is solid
+pub fn can_stand(world: &World, cell: Cell, height: i32) -> bool {
+    is_solid(world, [cell[0], cell[1] - 1, cell[2]]) && fits(world, cell, height)
+}
+
+/// The cells reached from `cell` in one step, with their cost: the next cells on the same level, a cube higher
+/// with a jump, or up to `MAX_FALL` cubes lower
+fn neighbors(world: &World, cell: Cell, height: i32) -> Vec<(Cell, u32)> {
+    let [x, y, z] = cell;
+    let mut next = Vec::new();
+    for (dx, dz) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+        let side = [x + dx, y, z + dz];
+        if can_stand(world, side, height) {
+            next.push((side, WALK_COST));
+        } else if fits(world, side, height) {
+            // Over a hole, the entity falls to the first cube below
+            for drop in 1..=MAX_FALL {
+                let below = [x + dx, y - drop, z + dz];
+                if can_stand(world, below, height) {
+                    next.push((below, WALK_COST + FALL_COST * drop as u32));
+                    break;
+                }
+                if is_solid(world, below) {
+                    break;
+                }
+            }
+        } else {
+            // Against a cube, the entity jumps on it if there is room above it
+            let up = [x + dx, y + 1, z + dz];
+            if can_stand(world, up, height) && !is_solid(world, [x, y + height, z]) {
+                next.push((up, WALK_COST + JUMP_COST));
+            }
+        }
+    }
+    next
+}
+
+/// Horizontal distance between the cells, times the cost of a step: never more than the cost of the path
+fn heuristic(cell: &Cell, goal: &Cell) -> u32 {
+    WALK_COST * ((cell[0] - goal[0]).unsigned_abs() + (cell[2] - goal[2]).unsigned_abs())
+}
+
+/// The cells to walk through, with A*, for an entity `height` cubes high to go from the cell `start` to the column
+/// of the cell `goal` (one cube above or below it at most, for the players jumping).
+/// When the goal cannot be reached, the path leads to the cell found the closest to it. The start is not in the path.
+pub fn find_path(world: &World, start: Cell, goal: Cell, height: i32) -> Vec<Cell> {
+    let mut open = BinaryHeap::new();
+    let mut costs: HashMap<Cell, u32> = HashMap::new();
+    let mut came_from: HashMap<Cell, Cell> = HashMap::new();
+    open.push(Reverse((heuristic(&start, &goal), start)));
+    costs.insert(start, 0);
+
+    let mut closest = (heuristic(&start, &goal), start);
+    let mut visited = 0;
+    while let Some(Reverse((_, cell))) = open.pop() {
+        let reached = cell[0] == goal[0] && cell[2] == goal[2] && (cell[1] - goal[1]).abs() <= 1;
+        if reached {
+            closest = (0, cell);
+            break;
+        }
+        visited += 1;
+        if visited > MAX_VISITED {
+            break;
+        }
+        let cost = costs[&cell];
+        for (next, step) in neighbors(world, cell, height) {
+            let next_cost = cost + step;
+            if costs.get(&next).is_some_and(|&known| known <= next_cost) {
+                continue;
+            }
+            costs.insert(next, next_cost);
+            came_from.insert(next, cell);
+            let h = heuristic(&next, &goal);
+            closest = closest.min((h, next));
+            open.push(Reverse((next_cost + h, next)));
+        }
+    }
+
+    let mut path = vec![closest.1];
+    while let Some(previous) = came_from.get(path.last().unwrap()) {
+        path.push(*previous);
+    }
+    path.pop();
+    path.reverse();
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::block_kind::Block;
+    use crate::chunk::{Chunk, CHUNK_SIZE};
+    use crate::entity::pathfinding::{can_stand, find_path};
+    use crate::primitives::vector::Vector3;
+    use crate::world::World;
+
+    #[test]
+    fn test_path_around_a_wall() {
+        // A wall 3 cubes high across the chunk, but for a gap at z = 6
+        let mut chunk = Chunk::new([0., 0.]);
+        chunk.fill_layer(0, Block::STONE);
+        for z in 0..CHUNK_SIZE as i32 {
+            for y in 1..4 {
+                if z != 6 {
+                    chunk.add_cube(Vector3::newi(4, y, z), Block::STONE, 0);
+                }
+            }
+        }
+        let world = World::new(vec![chunk]);
+
+        let path = find_path(&world, [1, 1, 1], [6, 1, 1], 2);
+        assert_eq!(path.last(), Some(&[6, 1, 1]));
+        assert!(path.contains(&[4, 1, 6]));
+        assert!(path.iter().all(|cell| can_stand(&world, *cell, 2)));
+        // Each step goes to a cell next to the previous one
+        for pair in path.windows(2) {
+            assert_eq!((pair[0][0] - pair[1][0]).abs() + (pair[0][2] - pair[1][2]).abs(), 1);
+        }
+    }
+
+    #[test]
+    fn test_path_jumps_and_falls() {
+        // Stairs of three steps across the chunk, which end with a drop of 3 cubes
+        let mut chunk = Chunk::new([0., 0.]);
+        chunk.fill_layer(0, Block::STONE);
+        for x in 2..5 {
+            for y in 1..x {
+                for z in 0..CHUNK_SIZE as i32 {
+                    chunk.add_cube(Vector3::newi(x, y, z), Block::STONE, 0);
+                }
+            }
+        }
+        let world = World::new(vec![chunk]);
+
+        // The path climbs the steps one by one, and falls at their end
+        let path = find_path(&world, [0, 1, 2], [6, 1, 2], 2);
+        assert_eq!(path.last(), Some(&[6, 1, 2]));
+        assert!(path.contains(&[2, 2, 2]));
+        assert!(path.contains(&[3, 3, 2]));
+        assert!(path.contains(&[4, 4, 2]));
+        assert!(path.contains(&[5, 1, 2]));
+
+        // A goal out of reach gives the path to the closest cell
+        let mut walled = Chunk::new([0., 0.]);
+        walled.fill_layer(0, Block::STONE);
+        for z in 0..CHUNK_SIZE as i32 {
+            for y in 1..5 {
+                walled.add_cube(Vector3::newi(5, y, z), Block::STONE, 0);
+            }
+        }
+        let world = World::new(vec![walled]);
+        assert_eq!(find_path(&world, [2, 1, 2], [7, 1, 2], 2).last(), Some(&[4, 1, 2]));
+    }
+}
@@ -0,0 +1,296 @@
+use std::any::Any;
+use std::f32::consts::PI;
+
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+
+use crate::aabb::AABB;
+use crate::entity::body::Body;
+use crate::entity::replica::ReplicaKind;
+use crate::entity::store::{EntityBehavior, EntityId, EntityStore, EntityUpdate};
+use crate::food::Food;
+use crate::graphics::entity::EntityCube;
+use crate::item::Item;
+use crate::primitives::position::Position;
+use crate::primitives::vector::Vector3;
+use crate::world::World;
+
+/// Index of the texture of the pigs among the textures of the entities, see `humanoid::load_humanoid_textures`
+pub const PIG_TEXTURE: u8 = 2;
+/// Side [cube] of the box of a pig
+pub const PIG_SIZE: f32 = 0.9;
+/// Speed [cube/s] of a pig walking to its target
+const WALK_SPEED: f32 = 1.5;
+/// Largest distance [cube] to the next target of a pig
+const WANDER_RANGE: f32 = 6.;
+/// Time [s] a pig rests between two walks, at most
+const MAX_REST: f32 = 6.;
+/// Time [s] after which a pig gives up a target it cannot reach
+const MAX_WALK: f32 = 8.;
+/// Distance [cube] to its target under which a pig stops
+const ARRIVAL_DISTANCE: f32 = 0.3;
+/// Highest drop [cube] a pig walks down, it turns back before higher cliffs
+const MAX_DROP: i32 = 2;
+/// Speed [cube/s] at which a pig jumps on a cube in its way, enough for one cube
+const JUMP_SPEED: f32 = 7.;
+/// Speed [cube/s] of a pig pushed away by the player
+const PUSH_SPEED: f32 = 3.;
+/// Health of a pig when it spawns
+const PIG_HEALTH: u8 = 10;
+/// Number of porkchops left by a killed pig
+const PORKCHOP_DROPS: usize = 2;
+/// Most pigs in the loaded chunks
+pub const MAX_PIGS: usize = 12;
+/// Number of attempts per second to spawn a pig, see `World::spawn_animals`
+pub const PIG_SPAWN_RATE: f32 = 0.5;
+
+// The cubes of the model, in cube units: length along the facing, height, width
+const BODY_SCALE: [f32; 3] = [0.9, 0.45, 0.6];
+const HEAD_SCALE: [f32; 3] = [0.45; 3];
+const LEG_SCALE: [f32; 3] = [0.2, 0.35, 0.2];
+
+/// A passive animal wandering around: it rests for a while, then walks to a random target close to it,
+/// jumping on the cubes in its way and turning back before the cliffs.
+pub struct Pig {
+    rng: SmallRng,
+    /// Where the pig walks to, None while it rests
+    target: Option<Vector3>,
+    /// Time [s] left to rest, or spent walking to the target
+    timer: f32,
+    /// Angle [rad] of the facing of the pig around the vertical axis
+    yaw: f32,
+    /// Horizontal speed [cube/s] given by the player pushing the pig, until the next update
+    push: Vector3,
+}
+
+impl Pig {
+    /// A resting pig, its random choices made from `seed`
+    pub fn new(seed: u64) -> Self {
+        let mut rng = SmallRng::seed_from_u64(seed);
+        let yaw = rng.gen_range(0. ..2. * PI);
+        let timer = rng.gen_range(0. ..MAX_REST);
+        Self { rng, target: None, timer, yaw, push: Vector3::empty() }
+    }
+
+    /// A pig standing on the cube below the cell `at`, its random choices made from `seed`
+    pub fn spawn(entities: &mut EntityStore, at: Vector3, seed: u64) -> EntityId {
+        let bottom = at.to_cube_coordinates() + Vector3::new(0.5, 0., 0.5);
+        entities.spawn(Self::body(bottom), Self::new(seed))
+    }
+
+    /// The body of a pig, with its bottom at this position
+    pub fn body(bottom: Vector3) -> Body {
+        Body::new(bottom, PIG_SIZE, PIG_SIZE)
+    }
+
+    pub fn target(&self) -> Option<Vector3> {
+        self.target
+    }
+
+    fn rest(&mut self) {
+        self.target = None;
+        self.timer = self.rng.gen_range(0. ..MAX_REST);
+    }
+
+    fn pick_target(&mut self, from: &Vector3) {
+        let angle = self.rng.gen_range(0. ..2. * PI);
+        let distance = self.rng.gen_range(1. ..WANDER_RANGE);
+        self.target = Some(*from + Vector3::new(angle.cos(), 0., angle.sin()) * distance);
+        self.timer = 0.;
+    }
+
+    /// Whether the ground in front of the pig, going toward `direction`, is more than `MAX_DROP` cubes below it
+    fn is_cliff_ahead(body: &Body, direction: &Vector3, world: &World) -> bool {
+        let ahead = body.position() + *direction * (body.width() / 2. + 0.5);
+        let y = body.position().y().round();
+        (1..=MAX_DROP + 1).all(|depth| {
+            let cell = Vector3::new(ahead.x(), y - depth as f32, ahead.z());
+            !world.block_at(&cell).is_some_and(|block| block.is_solid())
+        })
+    }
+}
+
+impl EntityBehavior for Pig {
+    /// Rests or walks to the target, the pigs in the chunks that are not loaded anymore are removed
+    fn update(&mut self, body: &mut Body, dt: f32, world: &World) -> EntityUpdate {
+        if !world.is_loaded(&body.position()) {
+            return EntityUpdate::Remove;
+        }
+
+        let mut walk = Vector3::empty();
+        match self.target {
+            None => {
+                self.timer -= dt;
+                if self.timer <= 0. {
+                    self.pick_target(&body.position());
+                }
+            }
+            Some(target) => {
+                self.timer += dt;
+                let mut direction = target - body.position();
+                direction[1] = 0.;
+                if direction.norm() < ARRIVAL_DISTANCE || self.timer > MAX_WALK {
+                    self.rest();
+                } else {
+                    direction.normalize();
+                    if body.on_ground() && Self::is_cliff_ahead(body, &direction, world) {
+                        self.rest();
+                    } else {
+                        walk = direction * WALK_SPEED;
+                        self.yaw = direction.z().atan2(direction.x());
+                    }
+                }
+            }
+        }
+
+        let mut velocity = walk + self.push;
+        velocity[1] = body.velocity().y();
+        if walk.norm() > 0. && body.on_ground() && body.blocked() {
+            velocity[1] = JUMP_SPEED;
+        }
+        body.set_velocity(velocity);
+        self.push = Vector3::empty();
+        EntityUpdate::Keep
+    }
+
+    fn max_health(&self) -> Option<u8> {
+        Some(PIG_HEALTH)
+    }
+
+    fn loot(&self) -> Vec<Item> {
+        vec![Item::Food(Food::Porkchop); PORKCHOP_DROPS]
+    }
+
+    fn replica(&self) -> Option<ReplicaKind> {
+        Some(ReplicaKind::Pig)
+    }
+
+    fn yaw(&self) -> f32 {
+        self.yaw
+    }
+
+    fn set_yaw(&mut self, yaw: f32) {
+        self.yaw = yaw;
+    }
+
+    /// The body, the head in front of it and the four legs
+    fn parts(&self, body: &Body, scale: f32) -> Vec<EntityCube> {
+        let forward = Vector3::new(self.yaw.cos(), 0., self.yaw.sin());
+        let side = Vector3::new(-self.yaw.sin(), 0., self.yaw.cos());
+        let part = |offset: Vector3, body_part: u8, size: [f32; 3]| {
+            let position = Position::new((body.position() + offset) * scale, self.yaw, 0.);
+            EntityCube::new_only_yaw(&position, body_part, PIG_TEXTURE, size.map(|s| s * scale))
+        };
+
+        let leg_height = LEG_SCALE[1];
+        let mut parts = vec![
+            part(Vector3::unit_y() * (leg_height + BODY_SCALE[1] / 2.), 2, BODY_SCALE),
+            part(forward * (BODY_SCALE[0] / 2.) + Vector3::unit_y() * (leg_height + BODY_SCALE[1]), 0, HEAD_SCALE),
+        ];
+        for (f, s) in [(1., 1.), (1., -1.), (-1., 1.), (-1., -1.)] {
+            let offset = forward * (f * 0.3) + side * (s * 0.18) + Vector3::unit_y() * (leg_height / 2.);
+            parts.push(part(offset, 1, LEG_SCALE));
+        }
+        parts
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// Pushes the pigs touching the box of the player (in cube coordinates) away from it
+pub fn push_pigs(entities: &mut EntityStore, player: &AABB) {
+    let player_center = Vector3::new(
+        (player.west() + player.east()) / 2.,
+        0.,
+        (player.south() + player.north()) / 2.,
+    );
+    for (_, body, pig) in entities.of_type_mut::<Pig>() {
+        if !body.aabb().collides(player) {
+            continue;
+        }
+        let mut away = body.position() - player_center;
+        away[1] = 0.;
+        if away.norm() < 1e-3 {
+            away = Vector3::unit_x();
+        }
+        away.normalize();
+        pig.push = away * PUSH_SPEED;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aabb::AABB;
+    use crate::block_kind::Block;
+    use crate::chunk::Chunk;
+    use crate::entity::pig::{push_pigs, Pig, PIG_HEALTH, PORKCHOP_DROPS};
+    use crate::food::Food;
+    use crate::item_drop::ItemDrop;
+    use crate::player_items::ItemStack;
+    use crate::primitives::vector::Vector3;
+    use crate::world::World;
+
+    #[test]
+    fn test_pigs_do_not_walk_off_cliffs() {
+        // A platform of 5x5 cubes, 5 cubes above the floor
+        let mut chunk = Chunk::new([0., 0.]);
+        chunk.fill_layer(0, Block::STONE);
+        for x in 2..7 {
+            for z in 2..7 {
+                chunk.add_cube(Vector3::newi(x, 5, z), Block::GRASS, 0);
+            }
+        }
+        let mut world = World::new(vec![chunk]);
+        let id = Pig::spawn(world.entities_mut(), Vector3::new(4., 6., 4.), 3);
+
+        let mut targets = 0;
+        for _ in 0..2000 {
+            world.step_entities(0.05);
+            let pig = world.entities().behavior::<Pig>(id).unwrap();
+            targets += pig.target().is_some() as usize;
+            let position = world.entities().body(id).unwrap().position();
+            assert!((position.y() - 6.).abs() < 1e-3, "{position:?}");
+        }
+        // It did walk around
+        assert!(targets > 0);
+    }
+
+    #[test]
+    fn test_player_pushes_pigs() {
+        // The same pig, with and without the player standing against it on its +x side
+        let player = AABB::new(4.8, 4.2, 2.8, 1., 5.3, 4.7).unwrap();
+        let x_after = |pushed: bool| {
+            let mut chunk = Chunk::new([0., 0.]);
+            chunk.fill_layer(0, Block::GRASS);
+            let mut world = World::new(vec![chunk]);
+            let id = Pig::spawn(world.entities_mut(), Vector3::new(4., 1., 4.), 3);
+            world.step_entities(0.1);
+            if pushed {
+                push_pigs(world.entities_mut(), &player);
+            }
+            world.step_entities(0.1);
+            world.step_entities(0.1);
+            world.entities().body(id).unwrap().position().x()
+        };
+        assert!(x_after(true) < x_after(false) - 0.2);
+    }
+
+    #[test]
+    fn test_killed_pigs_leave_porkchops() {
+        let mut chunk = Chunk::new([0., 0.]);
+        chunk.fill_layer(0, Block::GRASS);
+        let mut world = World::new(vec![chunk]);
+        let id = Pig::spawn(world.entities_mut(), Vector3::new(4., 1., 4.), 3);
+        assert!(world.entities_mut().hit(id, PIG_HEALTH, &Vector3::unit_x()));
+        assert!(world.entities().body(id).is_none());
+        world.step_entities(0.01);
+        let drops: Vec<ItemStack> = world.entities().of_type::<ItemDrop>().map(|(_, _, drop)| drop.stack()).collect();
+        assert_eq!(drops, vec![ItemStack::new(Food::Porkchop, PORKCHOP_DROPS)]);
+    }
+}
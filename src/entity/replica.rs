@@ -0,0 +1,82 @@
+use serde::{Deserialize, Serialize};
+
+use crate::block_kind::Block;
+use crate::entity::pig::Pig;
+use crate::entity::store::{EntityId, EntityStore};
+use crate::entity::zombie::Zombie;
+use crate::falling_block::FallingBlock;
+use crate::primitives::vector::Vector3;
+
+/// The entities simulated by the server and drawn by the clients. The server sends where they are
+/// (see `ServerUpdate::WorldEntity`), and the clients only move them there, see `EntityStore::spawn_remote`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReplicaKind {
+    FallingBlock(Block),
+    Pig,
+    Zombie,
+}
+
+impl ReplicaKind {
+    /// Adds the entity drawn by a client, with the bottom of its body at `position` [cube]
+    pub fn spawn_remote(&self, entities: &mut EntityStore, position: Vector3) -> EntityId {
+        match self {
+            ReplicaKind::FallingBlock(block) => entities.spawn_remote(FallingBlock::body(position), FallingBlock::new(*block)),
+            ReplicaKind::Pig => entities.spawn_remote(Pig::body(position), Pig::new(0)),
+            ReplicaKind::Zombie => entities.spawn_remote(Zombie::body(position), Zombie::default()),
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        serde_json::to_string(self).unwrap().into_bytes()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        serde_json::from_slice(bytes).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::block_kind::Block;
+    use crate::entity::pig::Pig;
+    use crate::entity::replica::ReplicaKind;
+    use crate::entity::store::{EntityBehavior, EntityStore};
+    use crate::falling_block::FallingBlock;
+    use crate::primitives::vector::Vector3;
+    use crate::world::World;
+
+    #[test]
+    fn test_remote_entities_only_move_where_the_server_says() {
+        let mut server = EntityStore::new();
+        let id = FallingBlock::spawn(&mut server, Block::SAND, Vector3::new(2., 10., 3.));
+        let replicas: Vec<_> = server.replicas().collect();
+        assert_eq!(replicas, vec![(id, ReplicaKind::FallingBlock(Block::SAND), Vector3::new(2.5, 10., 3.5), 0.)]);
+
+        let kind = ReplicaKind::from_bytes(&replicas[0].1.to_bytes()).unwrap();
+        let mut client = EntityStore::new();
+        let local = kind.spawn_remote(&mut client, replicas[0].2);
+        assert_eq!(client.behavior::<FallingBlock>(local).unwrap().block(), Block::SAND);
+
+        // The client does not let it fall, and does not send it back
+        client.step(1., &World::empty());
+        assert_eq!(client.body(local).unwrap().position(), Vector3::new(2.5, 10., 3.5));
+        assert_eq!(client.replicas().count(), 0);
+        client.place(local, Vector3::new(2.5, 7., 3.5), 0.);
+        assert_eq!(client.body(local).unwrap().position(), Vector3::new(2.5, 7., 3.5));
+    }
+
+    #[test]
+    fn test_remote_pigs_face_where_the_server_says() {
+        let mut server = EntityStore::new();
+        let id = Pig::spawn(&mut server, Vector3::new(2., 1., 3.), 5);
+        let (_, kind, bottom, yaw) = server.replicas().next().unwrap();
+        assert_eq!(kind, ReplicaKind::Pig);
+        assert_eq!(yaw, server.behavior::<Pig>(id).unwrap().yaw());
+
+        let mut client = EntityStore::new();
+        let local = kind.spawn_remote(&mut client, bottom);
+        client.place(local, bottom, 1.5);
+        assert_eq!(client.behavior::<Pig>(local).unwrap().yaw(), 1.5);
+        assert!(client.behavior::<Pig>(local).unwrap().target().is_none());
+    }
+}
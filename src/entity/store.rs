@@ -0,0 +1,484 @@
+use std::any::Any;
+
+use crate::actions::Action;
+use crate::cube::Cube;
+use crate::entity::body::Body;
+use crate::entity::replica::ReplicaKind;
+use crate::graphics::cube::CubeInstance;
+use crate::graphics::entity::EntityCube;
+use crate::health::Health;
+use crate::item::Item;
+use crate::item_drop::ItemDrop;
+use crate::player_items::ItemStack;
+use crate::primitives::vector::Vector3;
+use crate::world::World;
+
+pub type EntityId = u32;
+
+/// Time [s] after a hit during which an entity cannot be hit again
+pub const HIT_COOLDOWN: f32 = 0.5;
+/// Time [s] during which an entity flashes red after a hit
+const FLASH_TIME: f32 = 0.3;
+/// Horizontal speed [cube/s] of an entity thrown back by a hit
+const KNOCKBACK_SPEED: f32 = 8.;
+/// Vertical speed [cube/s] of an entity thrown back by a hit
+const KNOCKBACK_LIFT: f32 = 4.;
+
+/// What becomes of an entity after its update
+#[derive(Clone, Debug, PartialEq)]
+pub enum EntityUpdate {
+    Keep,
+    Remove,
+    /// The entity is removed, and the action applied on the world (a falling block landing)
+    Apply(Action),
+}
+
+/// What becomes of an entity hit, see `EntityStore::hurt`
+#[derive(Clone, Debug, PartialEq)]
+pub enum Hit {
+    /// The entity cannot be hit, or it was hit a moment ago
+    Ignored,
+    Hurt,
+    /// The entity is removed, its loot is to be left where its center was [cube]
+    Killed(Vector3, Vec<Item>),
+}
+
+/// What an entity does on top of the movement of its body: the entities of the world differ by their behavior only,
+/// and they all share the physics of `Body`
+pub trait EntityBehavior: Any + Send {
+    /// Updates the entity once its body moved during `dt` seconds
+    fn update(&mut self, body: &mut Body, dt: f32, world: &World) -> EntityUpdate;
+
+    /// Health of the entity when it spawns, None for the entities that cannot be hit
+    fn max_health(&self) -> Option<u8> {
+        None
+    }
+
+    /// Items left on the ground when the entity is killed
+    fn loot(&self) -> Vec<Item> {
+        Vec::new()
+    }
+
+    /// The cubes drawn for the entity with the textures of the blocks
+    fn instances(&self, _body: &Body) -> Vec<CubeInstance> {
+        Vec::new()
+    }
+
+    /// The cubes drawn for the entity with the textures of the entities, in a world where a cube is `scale`
+    /// world units wide
+    fn parts(&self, _body: &Body, _scale: f32) -> Vec<EntityCube> {
+        Vec::new()
+    }
+
+    /// How the clients draw the entity, None for the entities the server does not send them
+    fn replica(&self) -> Option<ReplicaKind> {
+        None
+    }
+
+    /// Direction [rad] the entity faces, sent to the clients with its position
+    fn yaw(&self) -> f32 {
+        0.
+    }
+
+    /// Turns the entity drawn by a client to the direction sent by the server
+    fn set_yaw(&mut self, _yaw: f32) {}
+
+    fn as_any(&self) -> &dyn Any;
+
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+/// The entities of a world, stored as arrays of their parts (the bodies are next to each other for the physics).
+/// The same index gives the id, the body, the behavior and the health of an entity.
+#[derive(Default)]
+pub struct EntityStore {
+    next_id: EntityId,
+    ids: Vec<EntityId>,
+    bodies: Vec<Body>,
+    behaviors: Vec<Box<dyn EntityBehavior>>,
+    /// None for the entities that cannot be hit
+    healths: Vec<Option<Health>>,
+    /// Time [s] since the last hit of each entity
+    since_hit: Vec<f32>,
+    /// The entities simulated by the server, only moved by `place`
+    remote: Vec<bool>,
+    /// The hits on the entities simulated by the server, to be sent to it, see `take_remote_hits`
+    remote_hits: Vec<(EntityId, u8, Vector3)>,
+}
+
+impl EntityStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// Adds an entity, returns its id
+    pub fn spawn(&mut self, body: Body, behavior: impl EntityBehavior) -> EntityId {
+        self.push(body, behavior, false)
+    }
+
+    /// Adds an entity simulated by the server, which the client only draws where the server says, see `place`.
+    /// Returns its id in this store, which differs from its id on the server.
+    pub fn spawn_remote(&mut self, body: Body, behavior: impl EntityBehavior) -> EntityId {
+        self.push(body, behavior, true)
+    }
+
+    fn push(&mut self, body: Body, behavior: impl EntityBehavior, remote: bool) -> EntityId {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.ids.push(id);
+        self.bodies.push(body);
+        self.healths.push(behavior.max_health().map(Health::new));
+        self.since_hit.push(HIT_COOLDOWN);
+        self.remote.push(remote);
+        self.behaviors.push(Box::new(behavior));
+        id
+    }
+
+    /// Moves an entity where the server says it is, with the bottom of its body at `position` [cube]
+    pub fn place(&mut self, id: EntityId, position: Vector3, yaw: f32) {
+        if let Some(index) = self.index_of(id) {
+            self.bodies[index].set_position(position);
+            self.behaviors[index].set_yaw(yaw);
+        }
+    }
+
+    /// The entities simulated here that the clients draw, with how they draw them, the bottom of their body
+    /// and their direction
+    pub fn replicas(&self) -> impl Iterator<Item = (EntityId, ReplicaKind, Vector3, f32)> + '_ {
+        (0..self.len())
+            .filter(|&index| !self.remote[index])
+            .filter_map(|index| {
+                let behavior = &self.behaviors[index];
+                behavior.replica().map(|kind| (self.ids[index], kind, self.bodies[index].position(), behavior.yaw()))
+            })
+    }
+
+    fn index_of(&self, id: EntityId) -> Option<usize> {
+        self.ids.iter().position(|other| *other == id)
+    }
+
+    /// Removes the entity, returns false if there was none with this id
+    pub fn remove(&mut self, id: EntityId) -> bool {
+        match self.index_of(id) {
+            Some(index) => {
+                self.remove_at(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn remove_at(&mut self, index: usize) {
+        self.ids.swap_remove(index);
+        self.bodies.swap_remove(index);
+        self.behaviors.swap_remove(index);
+        self.healths.swap_remove(index);
+        self.since_hit.swap_remove(index);
+        self.remote.swap_remove(index);
+    }
+
+    pub fn body(&self, id: EntityId) -> Option<&Body> {
+        self.index_of(id).map(|index| &self.bodies[index])
+    }
+
+    pub fn body_mut(&mut self, id: EntityId) -> Option<&mut Body> {
+        self.index_of(id).map(|index| &mut self.bodies[index])
+    }
+
+    /// Health left to the entity, None if it cannot be hit
+    pub fn health(&self, id: EntityId) -> Option<u8> {
+        self.index_of(id).and_then(|index| self.healths[index].as_ref()).map(Health::health)
+    }
+
+    /// The closest entity that can be hit on the ray from `origin` along `direction` (normalized), within `reach`
+    /// (all in cube coordinates), with its distance
+    pub fn raycast(&self, origin: &Vector3, direction: &Vector3, reach: f32) -> Option<(EntityId, f32)> {
+        (0..self.len())
+            .filter(|&index| self.healths[index].is_some())
+            .filter_map(|index| {
+                let faces = self.bodies[index].aabb().faces();
+                Cube::intersection_with_faces(&faces, *origin, *direction).map(|(distance, _)| (self.ids[index], distance))
+            })
+            .filter(|(_, distance)| *distance <= reach)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+    }
+
+    /// Hits the entity like `hurt`, the loot of the entity killed is left on the ground. Returns true if it was hurt.
+    pub fn hit(&mut self, id: EntityId, damage: u8, direction: &Vector3) -> bool {
+        match self.hurt(id, damage, direction) {
+            Hit::Ignored => false,
+            Hit::Hurt => true,
+            Hit::Killed(at, loot) => {
+                for item in loot {
+                    ItemDrop::spawn(self, ItemStack::new(item, 1), at);
+                }
+                true
+            }
+        }
+    }
+
+    /// Hits the entity with this damage, and throws it back along the horizontal part of `direction`.
+    /// The entities hit a moment ago (see `HIT_COOLDOWN`) and the ones without health are not hurt.
+    /// The entity is removed once its health is down to 0. The entities simulated by the server only flash,
+    /// the hit is kept to be sent to it, see `take_remote_hits`.
+    pub fn hurt(&mut self, id: EntityId, damage: u8, direction: &Vector3) -> Hit {
+        let Some(index) = self.index_of(id) else {
+            return Hit::Ignored;
+        };
+        if self.since_hit[index] < HIT_COOLDOWN {
+            return Hit::Ignored;
+        }
+        let Some(health) = self.healths[index].as_mut() else {
+            return Hit::Ignored;
+        };
+        if self.remote[index] {
+            self.since_hit[index] = 0.;
+            self.remote_hits.push((id, damage, *direction));
+            return Hit::Hurt;
+        }
+        health.damage(damage);
+        if !health.alive() {
+            let at = self.bodies[index].center();
+            let loot = self.behaviors[index].loot();
+            self.remove_at(index);
+            return Hit::Killed(at, loot);
+        }
+        self.since_hit[index] = 0.;
+        let mut back = Vector3::new(direction.x(), 0., direction.z());
+        if back.norm() > 1e-3 {
+            back.normalize();
+        }
+        self.bodies[index].knock(back * KNOCKBACK_SPEED + Vector3::unit_y() * KNOCKBACK_LIFT);
+        Hit::Hurt
+    }
+
+    /// The hits on the entities simulated by the server since the last call, with their id in this store,
+    /// the damage and the direction of the hit
+    pub fn take_remote_hits(&mut self) -> Vec<(EntityId, u8, Vector3)> {
+        std::mem::take(&mut self.remote_hits)
+    }
+
+    /// The behavior of the entity, if it is a `T`
+    pub fn behavior<T: EntityBehavior>(&self, id: EntityId) -> Option<&T> {
+        self.index_of(id).and_then(|index| self.behaviors[index].as_any().downcast_ref::<T>())
+    }
+
+    pub fn behavior_mut<T: EntityBehavior>(&mut self, id: EntityId) -> Option<&mut T> {
+        self.index_of(id).and_then(|index| self.behaviors[index].as_any_mut().downcast_mut::<T>())
+    }
+
+    /// The entities whose behavior is a `T`
+    pub fn of_type<T: EntityBehavior>(&self) -> impl Iterator<Item = (EntityId, &Body, &T)> {
+        self.ids
+            .iter()
+            .zip(self.bodies.iter())
+            .zip(self.behaviors.iter())
+            .filter_map(|((id, body), behavior)| behavior.as_any().downcast_ref::<T>().map(|behavior| (*id, body, behavior)))
+    }
+
+    pub fn of_type_mut<T: EntityBehavior>(&mut self) -> impl Iterator<Item = (EntityId, &mut Body, &mut T)> {
+        self.ids
+            .iter()
+            .zip(self.bodies.iter_mut())
+            .zip(self.behaviors.iter_mut())
+            .filter_map(|((id, body), behavior)| behavior.as_any_mut().downcast_mut::<T>().map(|behavior| (*id, body, behavior)))
+    }
+
+    /// Keeps the entities of type `T` for which `keep` is true, the other types are all kept
+    pub fn retain_of_type<T: EntityBehavior>(&mut self, mut keep: impl FnMut(&Body, &mut T) -> bool) {
+        let mut index = 0;
+        while index < self.len() {
+            let kept = match self.behaviors[index].as_any_mut().downcast_mut::<T>() {
+                Some(behavior) => keep(&self.bodies[index], behavior),
+                None => true,
+            };
+            if kept {
+                index += 1;
+            } else {
+                self.remove_at(index);
+            }
+        }
+    }
+
+    /// Moves the bodies during `dt` seconds and updates the entities. The ones that fell under the world are removed.
+    /// Returns the actions of the entities to apply on the world.
+    pub fn step(&mut self, dt: f32, world: &World) -> Vec<Action> {
+        let mut actions = Vec::new();
+        let mut index = 0;
+        while index < self.len() {
+            self.since_hit[index] += dt;
+            if self.remote[index] {
+                index += 1;
+                continue;
+            }
+            let body = &mut self.bodies[index];
+            body.step(dt, world);
+            let update = if body.position().y() < 0. {
+                EntityUpdate::Remove
+            } else {
+                self.behaviors[index].update(body, dt, world)
+            };
+            match update {
+                EntityUpdate::Keep => index += 1,
+                EntityUpdate::Remove => self.remove_at(index),
+                EntityUpdate::Apply(action) => {
+                    actions.push(action);
+                    self.remove_at(index);
+                }
+            }
+        }
+        actions
+    }
+
+    /// The cubes drawn for all the entities, apart from the cubes of the chunks (see `CubesToDraw`)
+    pub fn instances(&self) -> Vec<CubeInstance> {
+        self.bodies
+            .iter()
+            .zip(self.behaviors.iter())
+            .flat_map(|(body, behavior)| behavior.instances(body))
+            .collect()
+    }
+
+    /// The cubes drawn for all the entities with the textures of the entities, see `EntityBehavior::parts`.
+    /// The entities just hit are drawn in red.
+    pub fn parts(&self, scale: f32) -> Vec<EntityCube> {
+        (0..self.len())
+            .flat_map(|index| {
+                let hurt = self.since_hit[index] < FLASH_TIME;
+                let parts = self.behaviors[index].parts(&self.bodies[index], scale);
+                parts.into_iter().map(move |part| if hurt { part.hurt() } else { part })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::any::Any;
+
+    use crate::block_kind::Block;
+    use crate::chunk::Chunk;
+    use crate::entity::body::Body;
+    use crate::entity::store::{EntityBehavior, EntityStore, EntityUpdate, Hit, HIT_COOLDOWN};
+    use crate::primitives::vector::Vector3;
+    use crate::world::World;
+
+    /// Disappears after a number of updates
+    struct Countdown(u32);
+
+    impl EntityBehavior for Countdown {
+        fn update(&mut self, _body: &mut Body, _dt: f32, _world: &World) -> EntityUpdate {
+            self.0 = self.0.saturating_sub(1);
+            if self.0 == 0 { EntityUpdate::Remove } else { EntityUpdate::Keep }
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+
+        fn as_any_mut(&mut self) -> &mut dyn Any {
+            self
+        }
+    }
+
+    /// Stands still until it is hit to death
+    struct Target;
+
+    impl EntityBehavior for Target {
+        fn update(&mut self, _body: &mut Body, _dt: f32, _world: &World) -> EntityUpdate {
+            EntityUpdate::Keep
+        }
+
+        fn max_health(&self) -> Option<u8> {
+            Some(5)
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+
+        fn as_any_mut(&mut self) -> &mut dyn Any {
+            self
+        }
+    }
+
+    #[test]
+    fn test_store_updates_and_removes() {
+        let world = World::new(vec![Chunk::new([0., 0.])]);
+        let mut store = EntityStore::new();
+        let floating = Body::new(Vector3::new(2., 5., 2.), 0.5, 0.5).with_gravity(0.);
+        let short = store.spawn(floating.clone(), Countdown(1));
+        let long = store.spawn(floating.clone(), Countdown(3));
+        // Nothing stops this one
+        store.spawn(Body::new(Vector3::new(4., 0.1, 4.), 0.5, 0.5), Countdown(100));
+        assert_eq!(store.len(), 3);
+
+        assert!(store.step(0.1, &world).is_empty());
+        assert!(store.body(short).is_none());
+        assert_eq!(store.body(long), Some(&floating));
+        assert_eq!(store.len(), 1);
+        assert_eq!(store.of_type::<Countdown>().map(|(_, _, countdown)| countdown.0).collect::<Vec<_>>(), vec![2]);
+
+        store.retain_of_type::<Countdown>(|_, countdown| countdown.0 > 2);
+        assert!(store.is_empty());
+        assert!(!store.remove(long));
+    }
+
+    #[test]
+    fn test_hit_entities() {
+        let mut chunk = Chunk::new([0., 0.]);
+        chunk.fill_layer(0, Block::STONE);
+        let world = World::new(vec![chunk]);
+        let mut store = EntityStore::new();
+        // In a row along x: only the targets can be hit
+        store.spawn(Body::new(Vector3::new(3., 1., 2.), 1., 1.), Countdown(100));
+        let near = store.spawn(Body::new(Vector3::new(5., 1., 2.), 1., 1.), Target);
+        let far = store.spawn(Body::new(Vector3::new(7., 1., 2.), 1., 1.), Target);
+        assert_eq!(store.health(near), Some(5));
+
+        let origin = Vector3::new(1., 1.5, 2.);
+        let direction = Vector3::unit_x();
+        let (id, distance) = store.raycast(&origin, &direction, 10.).unwrap();
+        assert_eq!(id, near);
+        assert!((distance - 3.5).abs() < 1e-4);
+        assert!(store.raycast(&origin, &direction, 3.).is_none());
+
+        // Thrown back along the look direction, and not hurt again right away
+        assert!(store.hit(near, 2, &direction));
+        assert_eq!(store.health(near), Some(3));
+        assert!(!store.hit(near, 2, &direction));
+        store.step(0.1, &world);
+        assert!(store.body(near).unwrap().position().x() > 5.);
+        assert!(store.body(near).unwrap().position().y() > 1.);
+
+        store.step(HIT_COOLDOWN, &world);
+        assert!(store.hit(near, 3, &direction));
+        assert!(store.body(near).is_none());
+        assert_eq!(store.raycast(&origin, &direction, 10.).map(|(id, _)| id), Some(far));
+    }
+
+    #[test]
+    fn test_hits_on_remote_entities_kept_for_the_server() {
+        let mut store = EntityStore::new();
+        let local = store.spawn(Body::new(Vector3::new(5., 1., 2.), 1., 1.), Target);
+        let remote = store.spawn_remote(Body::new(Vector3::new(7., 1., 2.), 1., 1.), Target);
+
+        assert_eq!(store.hurt(local, 5, &Vector3::unit_x()), Hit::Killed(Vector3::new(5., 1.5, 2.), Vec::new()));
+        assert!(store.take_remote_hits().is_empty());
+
+        // The server decides whether it dies
+        assert_eq!(store.hurt(remote, 5, &Vector3::unit_x()), Hit::Hurt);
+        assert_eq!(store.hurt(remote, 5, &Vector3::unit_x()), Hit::Ignored);
+        assert_eq!(store.health(remote), Some(5));
+        assert_eq!(store.take_remote_hits(), vec![(remote, 5, Vector3::unit_x())]);
+        assert!(store.take_remote_hits().is_empty());
+    }
+}
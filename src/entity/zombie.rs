@@ -0,0 +1,218 @@
+use std::any::Any;
+
+use crate::aabb::AABB;
+use crate::entity::body::Body;
+use crate::entity::humanoid;
+use crate::entity::pathfinding::{cell_bottom, cell_of, find_path, Cell};
+use crate::entity::replica::ReplicaKind;
+use crate::entity::store::{EntityBehavior, EntityId, EntityStore, EntityUpdate};
+use crate::graphics::entity::EntityCube;
+use crate::player::{DIAMETER, FOREHEAD, PLAYER_HEIGHT};
+use crate::primitives::position::Position;
+use crate::primitives::vector::Vector3;
+use crate::world::World;
+
+/// Index of the texture of the zombies among the textures of the entities, see `humanoid::load_humanoid_textures`
+const ZOMBIE_TEXTURE: u8 = 1;
+/// Number of cells taken by a zombie, vertically
+const ZOMBIE_CELLS: i32 = 2;
+/// Speed [cube/s] of a zombie walking to the player
+const WALK_SPEED: f32 = 2.5;
+/// Speed [cube/s] at which a zombie jumps on a cube in its way, enough for one cube
+const JUMP_SPEED: f32 = 7.;
+/// Distance [cube] under which a zombie chases the player
+const CHASE_RANGE: f32 = 24.;
+/// Time [s] between two searches of the path to the player
+const REPATH_PERIOD: f32 = 0.5;
+/// Damage dealt to the player touched by a zombie
+const ATTACK_DAMAGE: u8 = 3;
+/// Time [s] between two attacks of a zombie
+const ATTACK_COOLDOWN: f32 = 1.;
+/// Health of a zombie when it spawns
+const ZOMBIE_HEALTH: u8 = 20;
+/// Most zombies in the loaded chunks
+pub const MAX_ZOMBIES: usize = 8;
+/// Number of attempts per second to spawn a zombie at night, see `World::spawn_monsters`
+pub const ZOMBIE_SPAWN_RATE: f32 = 1.;
+/// The zombies spawn when the daylight is under this level, and are removed above it
+pub const NIGHT_DAYLIGHT: f32 = 0.3;
+/// The zombies only spawn on the cells whose light is at most this one
+pub const MAX_SPAWN_LIGHT: u8 = 7;
+/// The zombies spawn between these horizontal distances [cube] from the player
+pub const MIN_SPAWN_DISTANCE: i32 = 8;
+pub const MAX_SPAWN_DISTANCE: i32 = 24;
+
+/// A hostile humanoid coming out at night. It follows the path to the player found by `find_path`,
+/// and hurts the player when it touches it.
+#[derive(Default)]
+pub struct Zombie {
+    /// Bottom of the player being chased, in cube coordinates
+    target: Option<Vector3>,
+    /// The cells left to walk through to the target
+    path: Vec<Cell>,
+    /// Time [s] until the next search of the path
+    repath: f32,
+    /// Time [s] until the zombie can attack again
+    cooldown: f32,
+    /// Angle [rad] of the facing of the zombie around the vertical axis
+    yaw: f32,
+}
+
+impl Zombie {
+    /// A zombie with its feet in the cell `at`
+    pub fn spawn(entities: &mut EntityStore, at: Vector3) -> EntityId {
+        let bottom = at.to_cube_coordinates() + Vector3::new(0.5, 0., 0.5);
+        entities.spawn(Self::body(bottom), Self::default())
+    }
+
+    /// The body of a zombie, as big as a player, with its bottom at this position
+    pub fn body(bottom: Vector3) -> Body {
+        Body::new(bottom, DIAMETER, PLAYER_HEIGHT)
+    }
+
+    pub fn path(&self) -> &[Cell] {
+        &self.path
+    }
+}
+
+impl EntityBehavior for Zombie {
+    /// Follows the path to the target, searched again now and then since the player moves
+    fn update(&mut self, body: &mut Body, dt: f32, world: &World) -> EntityUpdate {
+        if !world.is_loaded(&body.position()) {
+            return EntityUpdate::Remove;
+        }
+        self.cooldown = (self.cooldown - dt).max(0.);
+        self.repath -= dt;
+        match self.target {
+            Some(target) if self.repath <= 0. => {
+                self.path = find_path(world, cell_of(&body.position()), cell_of(&target), ZOMBIE_CELLS);
+                self.repath = REPATH_PERIOD;
+            }
+            None => self.path.clear(),
+            _ => {}
+        }
+
+        // The cells reached are left behind
+        let position = body.position();
+        let reached = |cell: &Cell| {
+            let mut offset = cell_bottom(cell) - position;
+            offset[1] = 0.;
+            offset.norm() < 0.2 && (position.y() - cell[1] as f32).abs() < 0.5
+        };
+        while self.path.first().is_some_and(reached) {
+            self.path.remove(0);
+        }
+
+        let mut velocity = Vector3::new(0., body.velocity().y(), 0.);
+        if let Some(next) = self.path.first() {
+            let mut direction = cell_bottom(next) - position;
+            direction[1] = 0.;
+            if direction.norm() > 1e-3 {
+                direction.normalize();
+                self.yaw = direction.z().atan2(direction.x());
+                velocity += direction * WALK_SPEED;
+            }
+            let climbs = next[1] as f32 > position.y() + 0.5;
+            if body.on_ground() && (climbs || body.blocked()) {
+                velocity[1] = JUMP_SPEED;
+            }
+        }
+        body.set_velocity(velocity);
+        EntityUpdate::Keep
+    }
+
+    fn max_health(&self) -> Option<u8> {
+        Some(ZOMBIE_HEALTH)
+    }
+
+    fn replica(&self) -> Option<ReplicaKind> {
+        Some(ReplicaKind::Zombie)
+    }
+
+    fn yaw(&self) -> f32 {
+        self.yaw
+    }
+
+    fn set_yaw(&mut self, yaw: f32) {
+        self.yaw = yaw;
+    }
+
+    /// The same humanoid as the monsters, with the texture of the zombies
+    fn parts(&self, body: &Body, scale: f32) -> Vec<EntityCube> {
+        let eye = body.position() + Vector3::unit_y() * (PLAYER_HEIGHT - FOREHEAD);
+        humanoid::get_opengl_entities(Position::new(eye * scale, self.yaw, 0.), ZOMBIE_TEXTURE)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// Sets the closest of the players, whose bottoms are at `players` (in cube coordinates), as the target of the zombies
+/// close enough to it
+pub fn chase(entities: &mut EntityStore, players: &[Vector3]) {
+    for (_, body, zombie) in entities.of_type_mut::<Zombie>() {
+        let distance = |player: &&Vector3| (body.position() - **player).norm();
+        zombie.target = players
+            .iter()
+            .filter(|player| distance(player) <= CHASE_RANGE)
+            .min_by(|a, b| distance(a).total_cmp(&distance(b)))
+            .copied();
+    }
+}
+
+/// The zombies touching the box of the player (in cube coordinates) attack it, if they can.
+/// Returns the damage dealt to the player.
+pub fn attack(entities: &mut EntityStore, player: &AABB) -> u8 {
+    let mut damage = 0;
+    for (_, body, zombie) in entities.of_type_mut::<Zombie>() {
+        if zombie.cooldown <= 0. && body.aabb().collides(player) {
+            zombie.cooldown = ATTACK_COOLDOWN;
+            damage += ATTACK_DAMAGE;
+        }
+    }
+    damage
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aabb::AABB;
+    use crate::block_kind::Block;
+    use crate::chunk::{Chunk, CHUNK_SIZE};
+    use crate::entity::zombie::{attack, chase, Zombie, ATTACK_DAMAGE};
+    use crate::primitives::vector::Vector3;
+    use crate::world::World;
+
+    #[test]
+    fn test_zombies_reach_and_hurt_the_player() {
+        // The player stands behind a wall, with a gap at z = 6
+        let mut chunk = Chunk::new([0., 0.]);
+        chunk.fill_layer(0, Block::STONE);
+        for z in 0..CHUNK_SIZE as i32 {
+            for y in 1..4 {
+                if z != 6 {
+                    chunk.add_cube(Vector3::newi(4, y, z), Block::STONE, 0);
+                }
+            }
+        }
+        let mut world = World::new(vec![chunk]);
+        let id = Zombie::spawn(world.entities_mut(), Vector3::new(1., 1., 1.));
+        let player = Vector3::new(6.5, 1., 1.5);
+        let player_box = AABB::new(1.75, 1.25, 2.8, 1., 6.75, 6.25).unwrap();
+
+        let mut damage = 0;
+        for _ in 0..400 {
+            chase(world.entities_mut(), &[player]);
+            world.step_entities(0.05);
+            damage += attack(world.entities_mut(), &player_box);
+        }
+        let position = world.entities().body(id).unwrap().position();
+        assert!((position - player).norm() < 1., "{position:?}");
+        // Once per second at most
+        assert!(damage >= ATTACK_DAMAGE && damage <= 20 * ATTACK_DAMAGE, "{damage}");
+    }
+}
@@ -0,0 +1,66 @@
+use std::any::Any;
+
+use crate::actions::Action;
+use crate::block_kind::Block;
+use crate::entity::body::Body;
+use crate::entity::replica::ReplicaKind;
+use crate::entity::store::{EntityBehavior, EntityId, EntityStore, EntityUpdate};
+use crate::graphics::cube::CubeInstance;
+use crate::primitives::vector::Vector3;
+use crate::world::World;
+
+/// A block (sand, gravel) that lost its support, and falls until it lands on a solid block,
+/// where it becomes a cube again. See `World::step_entities`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FallingBlock {
+    block: Block,
+}
+
+impl FallingBlock {
+    pub fn new(block: Block) -> Self {
+        Self { block }
+    }
+
+    /// The block starts falling from the cell `at`, without any speed
+    pub fn spawn(entities: &mut EntityStore, block: Block, at: Vector3) -> EntityId {
+        let bottom = at.to_cube_coordinates() + Vector3::new(0.5, 0., 0.5);
+        entities.spawn(Self::body(bottom), Self::new(block))
+    }
+
+    /// The body of a falling block, a whole cube, with its bottom at this position
+    pub fn body(bottom: Vector3) -> Body {
+        Body::new(bottom, 1., 1.)
+    }
+
+    pub fn block(&self) -> Block {
+        self.block
+    }
+}
+
+impl EntityBehavior for FallingBlock {
+    /// Lands in the cell the body rests in
+    fn update(&mut self, body: &mut Body, _dt: f32, _world: &World) -> EntityUpdate {
+        if !body.on_ground() {
+            return EntityUpdate::Keep;
+        }
+        let p = body.position();
+        let at = Vector3::new(p.x(), (p.y() - 1e-3).ceil(), p.z()).to_cube_coordinates();
+        EntityUpdate::Apply(Action::Add { at, block: self.block, metadata: 0 })
+    }
+
+    fn instances(&self, body: &Body) -> Vec<CubeInstance> {
+        vec![CubeInstance::new_item(self.block.into(), &body.center(), 1., 0.)]
+    }
+
+    fn replica(&self) -> Option<ReplicaKind> {
+        Some(ReplicaKind::FallingBlock(self.block))
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
@@ -0,0 +1,41 @@
+use serde::{Deserialize, Serialize};
+use strum::EnumIter;
+
+use crate::texture_atlas::BlockFace;
+
+/// The items eaten to fill the food bar (see `Hunger`). They cannot be placed like the blocks.
+/// The food was blocks before: it keeps the id (see `Item::id`) and accepts the name it had then in the saves.
+#[derive(Clone, Copy, EnumIter, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Food {
+    #[serde(alias = "BREAD")]
+    Bread = 32,
+    #[serde(alias = "PORKCHOP")]
+    Porkchop = 33,
+}
+
+impl Food {
+    /// The id of the food in the saves, never given to a block (see `Block::id`)
+    pub fn id(&self) -> u8 {
+        *self as u8
+    }
+
+    fn file_name(&self) -> &'static str {
+        match self {
+            Food::Bread => "bread",
+            Food::Porkchop => "porkchop",
+        }
+    }
+
+    /// Name of the texture file of the face of the food drawn as a cube, without its extension
+    pub fn texture_file(&self, face: BlockFace) -> String {
+        self.file_name().to_string() + face.suffix()
+    }
+
+    /// Points of food given back by eating it, see `Hunger`
+    pub fn points(&self) -> u8 {
+        match self {
+            Food::Bread => 5,
+            Food::Porkchop => 4,
+        }
+    }
+}
@@ -8,7 +8,9 @@ const BUFFER_LEN: usize = 10;
 pub struct FpsManager {
     counter: f32,
     buffer: BoundedVecDeque<f32>,
-    fps: f32
+    fps: f32,
+    /// Duration of the last frame [s]
+    frame_time: f32,
 }
 
 impl FpsManager {
@@ -16,12 +18,14 @@ impl FpsManager {
         Self {
             counter: 0.,
             fps: 0.,
+            frame_time: 0.,
             buffer: BoundedVecDeque::new(BUFFER_LEN),
         }
     }
 
     pub fn step(&mut self, elapsed: Duration) {
         let dt = elapsed.as_secs_f32();
+        self.frame_time = dt;
         self.buffer.push_front(dt);
         self.counter += dt;
         if self.counter > TIME_TO_PRINT {
@@ -38,4 +42,9 @@ impl FpsManager {
     pub fn fps(&self) -> f32 {
         self.fps
     }
+
+    /// Duration of the last frame [s]
+    pub fn frame_time(&self) -> f32 {
+        self.frame_time
+    }
 }
\ No newline at end of file
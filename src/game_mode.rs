@@ -0,0 +1,73 @@
+use serde::{Deserialize, Serialize};
+use crate::block_kind::Block;
+use crate::tool::Tool;
+
+/// Maximal distance [cube] at which a survival player can select a cube
+const SURVIVAL_REACH: f32 = 6.;
+const CREATIVE_REACH: f32 = 10.;
+
+/// How the player interacts with the world.
+/// All the behaviors that depend on the mode are decided here, so that switching the mode is enough
+/// to change all of them at once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum GameMode {
+    /// Flying, instant breaking, no fall damage
+    Creative,
+    /// Gravity, blocks take time to break depending on their hardness
+    #[default]
+    Survival,
+}
+
+impl GameMode {
+    /// Returns the other mode
+    pub fn toggle(&self) -> Self {
+        match self {
+            GameMode::Creative => GameMode::Survival,
+            GameMode::Survival => GameMode::Creative,
+        }
+    }
+
+    pub fn can_fly(&self) -> bool {
+        *self == GameMode::Creative
+    }
+
+    pub fn takes_fall_damage(&self) -> bool {
+        *self == GameMode::Survival
+    }
+
+    /// Whether the hostile mobs hurt the player
+    pub fn is_attacked(&self) -> bool {
+        *self == GameMode::Survival
+    }
+
+    /// Whether the player gets hungry and has to eat, see `Hunger`
+    pub fn gets_hungry(&self) -> bool {
+        *self == GameMode::Survival
+    }
+
+    /// Whether the broken blocks leave an item
+    pub fn drops_items(&self) -> bool {
+        *self == GameMode::Survival
+    }
+
+    /// Whether the tools wear out as they are used, see `Tool::durability`
+    pub fn wears_tools(&self) -> bool {
+        *self == GameMode::Survival
+    }
+
+    /// Time [s] needed to break the block with this tool in hand
+    pub fn break_time(&self, block: &Block, tool: Option<Tool>) -> f32 {
+        match self {
+            GameMode::Creative => 0.,
+            GameMode::Survival => block.hardness() / tool.map_or(1., |tool| tool.speed(block)),
+        }
+    }
+
+    /// Maximal distance [cube] at which a cube can be selected
+    pub fn reach(&self) -> f32 {
+        match self {
+            GameMode::Creative => CREATIVE_REACH,
+            GameMode::Survival => SURVIVAL_REACH,
+        }
+    }
+}
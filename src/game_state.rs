@@ -0,0 +1,207 @@
+use crate::settings::Settings;
+
+/// Choices of field of view [degree] offered by the settings menu, in the order they are cycled
+const FOV_CHOICES: [u32; 6] = [50, 60, 70, 80, 90, 100];
+/// Choices of render distance [chunk] offered by the settings menu, in the order they are cycled
+const RENDER_DISTANCE_CHOICES: [i32; 5] = [2, 4, 6, 8, 12];
+
+/// Where the player is in the game, which decides what the inputs do and whether the world runs.
+/// The mouse is only grabbed, and the world only ticks, while playing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GameState {
+    /// Before the game starts
+    #[default]
+    MainMenu,
+    /// The saved worlds, to choose the one to play in
+    WorldList,
+    /// The name, the seed and the generator of a new world
+    CreateWorld,
+    Playing,
+    /// The world is frozen until the player resumes
+    Paused,
+    /// The settings menu, opened from the main menu or from the pause menu
+    Settings { from_pause: bool },
+}
+
+/// What can be chosen in the menus
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MenuEntry {
+    Play,
+    Resume,
+    Settings,
+    SaveAndQuit,
+    Quit,
+    Fullscreen,
+    Vsync,
+    FieldOfView,
+    RenderDistance,
+    Back,
+    /// The saved world at this index of the list
+    OpenWorld(usize),
+    NewWorld,
+    /// The fields of the new world, see `NewWorld`
+    WorldName,
+    Seed,
+    Generator,
+    /// The layers of a flat world, only offered for the flat generator
+    FlatLayers,
+    Create,
+}
+
+impl MenuEntry {
+    /// Text of the entry, without any value
+    pub fn name(&self) -> &'static str {
+        match self {
+            MenuEntry::Play => "play",
+            MenuEntry::Resume => "resume",
+            MenuEntry::Settings => "settings",
+            MenuEntry::SaveAndQuit => "save and quit",
+            MenuEntry::Quit => "quit",
+            MenuEntry::Fullscreen => "fullscreen",
+            MenuEntry::Vsync => "vsync",
+            MenuEntry::FieldOfView => "fov",
+            MenuEntry::RenderDistance => "render distance",
+            MenuEntry::Back => "back",
+            MenuEntry::OpenWorld(_) => "world",
+            MenuEntry::NewWorld => "new world",
+            MenuEntry::WorldName => "name",
+            MenuEntry::Seed => "seed",
+            MenuEntry::Generator => "generator",
+            MenuEntry::FlatLayers => "layers",
+            MenuEntry::Create => "create",
+        }
+    }
+
+    /// Text of the entry, with the current value of the settings it changes
+    pub fn label(&self, settings: &Settings) -> String {
+        let on_off = |value: bool| if value { "on" } else { "off" };
+        match self {
+            MenuEntry::Fullscreen => format!("{}: {}", self.name(), on_off(settings.fullscreen)),
+            MenuEntry::Vsync => format!("{}: {}", self.name(), on_off(settings.vsync)),
+            MenuEntry::FieldOfView => format!("{}: {}", self.name(), settings.fov.to_degrees().round()),
+            MenuEntry::RenderDistance => format!("{}: {}", self.name(), settings.render_distance),
+            _ => self.name().to_string(),
+        }
+    }
+
+    /// Whether the player types the value of the entry when it is selected
+    pub fn is_text_field(&self) -> bool {
+        matches!(self, MenuEntry::WorldName | MenuEntry::Seed)
+    }
+}
+
+/// The next field of view of `FOV_CHOICES` [rad], back to the first one after the last
+pub fn next_fov(fov: f32) -> f32 {
+    let degrees = fov.to_degrees().round() as u32;
+    let next = FOV_CHOICES.iter().find(|choice| **choice > degrees).unwrap_or(&FOV_CHOICES[0]);
+    (*next as f32).to_radians()
+}
+
+/// The next render distance of `RENDER_DISTANCE_CHOICES`, back to the first one after the last
+pub fn next_render_distance(render_distance: i32) -> i32 {
+    *RENDER_DISTANCE_CHOICES.iter().find(|choice| **choice > render_distance).unwrap_or(&RENDER_DISTANCE_CHOICES[0])
+}
+
+impl GameState {
+    pub fn is_playing(&self) -> bool {
+        *self == GameState::Playing
+    }
+
+    /// The state after pressing escape: it pauses the game, resumes it, or leaves the settings
+    pub fn escape(&self) -> Self {
+        match self {
+            GameState::MainMenu => GameState::MainMenu,
+            GameState::WorldList => GameState::MainMenu,
+            GameState::CreateWorld => GameState::WorldList,
+            GameState::Playing => GameState::Paused,
+            GameState::Paused => GameState::Playing,
+            GameState::Settings { from_pause: true } => GameState::Paused,
+            GameState::Settings { from_pause: false } => GameState::MainMenu,
+        }
+    }
+
+    /// Title and entries of the menu shown in this state, none while playing.
+    /// The menus listing the worlds depend on the saves, see `world_selection`.
+    pub fn menu(&self) -> Option<(&'static str, &'static [MenuEntry])> {
+        match self {
+            GameState::MainMenu => Some(("crafty", &[MenuEntry::Play, MenuEntry::Settings, MenuEntry::Quit])),
+            GameState::WorldList | GameState::CreateWorld | GameState::Playing => None,
+            GameState::Paused => Some(("paused", &[MenuEntry::Resume, MenuEntry::Settings, MenuEntry::SaveAndQuit])),
+            GameState::Settings { .. } => Some(("settings", &[MenuEntry::Fullscreen, MenuEntry::Vsync, MenuEntry::FieldOfView, MenuEntry::RenderDistance, MenuEntry::Back])),
+        }
+    }
+
+    /// The state after choosing an entry of the menu, none when the game must stop.
+    /// The entries changing a setting keep the same state.
+    pub fn choose(&self, entry: MenuEntry) -> Option<Self> {
+        match entry {
+            MenuEntry::Play => Some(GameState::WorldList),
+            MenuEntry::Resume | MenuEntry::OpenWorld(_) | MenuEntry::Create => Some(GameState::Playing),
+            MenuEntry::Settings => Some(GameState::Settings { from_pause: *self == GameState::Paused }),
+            MenuEntry::NewWorld => Some(GameState::CreateWorld),
+            MenuEntry::SaveAndQuit | MenuEntry::Quit => None,
+            MenuEntry::Back => Some(self.escape()),
+            MenuEntry::Fullscreen | MenuEntry::Vsync | MenuEntry::FieldOfView | MenuEntry::RenderDistance => Some(*self),
+            MenuEntry::WorldName | MenuEntry::Seed | MenuEntry::Generator | MenuEntry::FlatLayers => Some(*self),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game_state::{next_fov, next_render_distance, GameState, MenuEntry};
+    use crate::settings::Settings;
+
+    #[test]
+    fn test_state_transitions() {
+        let state = GameState::default();
+        assert_eq!(state, GameState::MainMenu);
+        assert_eq!(state.escape(), GameState::MainMenu);
+
+        // Through the list of the worlds
+        let list = state.choose(MenuEntry::Play).unwrap();
+        assert_eq!(list, GameState::WorldList);
+        let create = list.choose(MenuEntry::NewWorld).unwrap();
+        assert_eq!(create.choose(MenuEntry::Generator), Some(GameState::CreateWorld));
+        assert_eq!(create.escape(), GameState::WorldList);
+        assert_eq!(list.escape(), GameState::MainMenu);
+
+        let state = list.choose(MenuEntry::OpenWorld(0)).unwrap();
+        assert!(state.is_playing());
+        assert!(state.menu().is_none());
+
+        // Escape pauses and resumes
+        let state = state.escape();
+        assert_eq!(state, GameState::Paused);
+        assert_eq!(state.escape(), GameState::Playing);
+        assert_eq!(state.choose(MenuEntry::Resume), Some(GameState::Playing));
+
+        // The settings go back to the menu they were opened from
+        let settings = state.choose(MenuEntry::Settings).unwrap();
+        assert_eq!(settings.choose(MenuEntry::Vsync), Some(settings));
+        assert_eq!(settings.choose(MenuEntry::Back), Some(GameState::Paused));
+        let settings = GameState::MainMenu.choose(MenuEntry::Settings).unwrap();
+        assert_eq!(settings.escape(), GameState::MainMenu);
+
+        assert_eq!(state.choose(MenuEntry::SaveAndQuit), None);
+    }
+
+    #[test]
+    fn test_menu_labels() {
+        let mut settings = Settings::default();
+        settings.vsync = false;
+        settings.fov = 70_f32.to_radians();
+        assert_eq!(MenuEntry::Vsync.label(&settings), "vsync: off");
+        assert_eq!(MenuEntry::FieldOfView.label(&settings), "fov: 70");
+
+        // The field of view cycles through the choices
+        assert_eq!(next_fov(settings.fov).to_degrees().round(), 80.);
+        assert_eq!(next_fov(100_f32.to_radians()).to_degrees().round(), 50.);
+
+        settings.render_distance = 6;
+        assert_eq!(MenuEntry::RenderDistance.label(&settings), "render distance: 6");
+        assert_eq!(next_render_distance(6), 8);
+        assert_eq!(next_render_distance(12), 2);
+        assert_eq!(next_render_distance(5), 6);
+    }
+}
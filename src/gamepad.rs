@@ -0,0 +1,169 @@
+use crate::input_map::InputAction;
+use gilrs::{Axis, Button, EventType, Gilrs};
+use std::collections::HashMap;
+
+/// Part of the travel of the sticks that is ignored, as they never rest exactly at the center
+pub const DEFAULT_DEAD_ZONE: f32 = 0.15;
+/// Rotation speed [rad/s] of the view with the right stick fully pushed
+pub const DEFAULT_LOOK_SPEED: f32 = 3.;
+
+/// Removes the dead zone around the center of a stick. The remaining travel is stretched to cover
+/// the full range, so that the movement starts smoothly at the edge of the dead zone.
+pub fn apply_dead_zone(stick: [f32; 2], dead_zone: f32) -> [f32; 2] {
+    let magnitude = (stick[0] * stick[0] + stick[1] * stick[1]).sqrt();
+    if magnitude <= dead_zone || dead_zone >= 1. {
+        return [0., 0.];
+    }
+    let scaled = ((magnitude - dead_zone) / (1. - dead_zone)).min(1.);
+    [stick[0] / magnitude * scaled, stick[1] / magnitude * scaled]
+}
+
+/// How the controller drives the player
+#[derive(Clone, Debug)]
+pub struct GamepadConfig {
+    /// See `DEFAULT_DEAD_ZONE`, between 0 and 1
+    pub dead_zone: f32,
+    /// See `DEFAULT_LOOK_SPEED`
+    pub look_speed: f32,
+    /// The action triggered by each button, the same actions as the keyboard
+    pub buttons: HashMap<Button, InputAction>,
+}
+
+impl Default for GamepadConfig {
+    fn default() -> Self {
+        let buttons = [
+            (Button::South, InputAction::Jump),
+            (Button::RightTrigger2, InputAction::BreakBlock),
+            (Button::LeftTrigger2, InputAction::PlaceBlock),
+            (Button::LeftThumb, InputAction::Sprint),
+            (Button::East, InputAction::Sneak),
+            (Button::North, InputAction::ThirdPerson),
+            (Button::Select, InputAction::HelpMenu),
+        ];
+        Self {
+            dead_zone: DEFAULT_DEAD_ZONE,
+            look_speed: DEFAULT_LOOK_SPEED,
+            buttons: buttons.into_iter().collect(),
+        }
+    }
+}
+
+/// A controller, as an input next to the keyboard and the mouse.
+/// The left stick moves the player, the right one turns the view, and the buttons trigger the actions of the config.
+pub struct Gamepad {
+    /// None when the controllers are not available on this system
+    gilrs: Option<Gilrs>,
+    config: GamepadConfig,
+    /// Position of the sticks, from -1 to 1 along each axis (up and right are positive)
+    left_stick: [f32; 2],
+    right_stick: [f32; 2],
+}
+
+impl Gamepad {
+    pub fn new(config: GamepadConfig) -> Self {
+        let gilrs = Gilrs::new()
+            .map_err(|error| println!("No gamepad support: {error}"))
+            .ok();
+        Self { gilrs, config, left_stick: [0.; 2], right_stick: [0.; 2] }
+    }
+
+    pub fn config(&self) -> &GamepadConfig {
+        &self.config
+    }
+
+    pub fn set_config(&mut self, config: GamepadConfig) {
+        self.config = config;
+    }
+
+    /// Reads the events of the controllers since the last call, and returns the actions pressed (true) and released (false)
+    pub fn poll(&mut self) -> Vec<(InputAction, bool)> {
+        let mut events = Vec::new();
+        if let Some(gilrs) = self.gilrs.as_mut() {
+            while let Some(event) = gilrs.next_event() {
+                events.push(event.event);
+            }
+        }
+        events.into_iter().filter_map(|event| self.handle_event(event)).collect()
+    }
+
+    fn handle_event(&mut self, event: EventType) -> Option<(InputAction, bool)> {
+        match event {
+            EventType::ButtonPressed(button, _) => self.handle_button(button, true),
+            EventType::ButtonReleased(button, _) => self.handle_button(button, false),
+            EventType::AxisChanged(axis, value, _) => {
+                self.handle_axis(axis, value);
+                None
+            }
+            // A controller unplugged while its sticks are pushed would keep the player moving
+            EventType::Disconnected => {
+                self.left_stick = [0.; 2];
+                self.right_stick = [0.; 2];
+                None
+            }
+            _ => None,
+        }
+    }
+
+    fn handle_button(&self, button: Button, pressed: bool) -> Option<(InputAction, bool)> {
+        self.config.buttons.get(&button).map(|action| (*action, pressed))
+    }
+
+    fn handle_axis(&mut self, axis: Axis, value: f32) {
+        match axis {
+            Axis::LeftStickX => self.left_stick[0] = value,
+            Axis::LeftStickY => self.left_stick[1] = value,
+            Axis::RightStickX => self.right_stick[0] = value,
+            Axis::RightStickY => self.right_stick[1] = value,
+            _ => {}
+        }
+    }
+
+    /// How much the player is asked to go to the right and forward, from -1 to 1
+    pub fn movement(&self) -> [f32; 2] {
+        apply_dead_zone(self.left_stick, self.config.dead_zone)
+    }
+
+    /// Rotation [rad] of the view over `dt` seconds, to the right and up
+    pub fn look(&self, dt: f32) -> [f32; 2] {
+        let [right, up] = apply_dead_zone(self.right_stick, self.config.dead_zone);
+        [right * self.config.look_speed * dt, up * self.config.look_speed * dt]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::gamepad::{apply_dead_zone, Gamepad, GamepadConfig, DEFAULT_LOOK_SPEED};
+    use crate::input_map::InputAction;
+    use gilrs::{Axis, Button, EventType};
+
+    #[test]
+    fn test_dead_zone() {
+        assert_eq!(apply_dead_zone([0.1, -0.05], 0.15), [0., 0.]);
+        assert_eq!(apply_dead_zone([1., 0.], 0.15), [1., 0.]);
+
+        // Stretched beyond the dead zone, keeping the direction
+        let [x, y] = apply_dead_zone([0.3, 0.4], 0.2);
+        assert!((x.hypot(y) - 0.375).abs() < 1e-5);
+        assert!((y / x - 4. / 3.).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_buttons_and_sticks() {
+        let mut gamepad = Gamepad { gilrs: None, config: GamepadConfig::default(), left_stick: [0.; 2], right_stick: [0.; 2] };
+        assert!(gamepad.poll().is_empty());
+
+        assert_eq!(gamepad.handle_button(Button::South, true), Some((InputAction::Jump, true)));
+        assert_eq!(gamepad.handle_button(Button::South, false), Some((InputAction::Jump, false)));
+        assert_eq!(gamepad.handle_button(Button::Mode, true), None);
+
+        gamepad.handle_axis(Axis::LeftStickY, 1.);
+        gamepad.handle_axis(Axis::RightStickX, 0.05);
+        assert_eq!(gamepad.movement(), [0., 1.]);
+        assert_eq!(gamepad.look(0.1), [0., 0.]);
+        gamepad.handle_axis(Axis::RightStickX, -1.);
+        assert!((gamepad.look(0.1)[0] + DEFAULT_LOOK_SPEED * 0.1).abs() < 1e-5);
+
+        gamepad.handle_event(EventType::Disconnected);
+        assert_eq!(gamepad.movement(), [0., 0.]);
+    }
+}
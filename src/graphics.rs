@@ -2,6 +2,7 @@
 
 
 pub mod cube;
+pub mod chunk_mesh;
 
 pub mod rectangle;
 pub mod hud_renderer;
@@ -10,6 +11,7 @@ pub mod font;
 pub mod menu_help;
 pub mod menu_debug;
 pub mod string_rect;
+pub mod text_layout;
 pub mod entity;
 pub mod items_bar;
 pub mod health_bar;
@@ -18,3 +20,7 @@ pub mod inventory_space;
 pub mod inventory_event;
 pub mod inventory_slot;
 pub mod update_status;
+pub mod crosshair;
+pub mod menu_screen;
+pub mod text_renderer;
+pub mod block_outline;
@@ -0,0 +1,187 @@
+use glium::implement_vertex;
+
+use crate::cube::Cube;
+use crate::graphics::cube::{CubeInstance, SelectionMode};
+use crate::primitives::vector::Vector3;
+
+pub const OUTLINE_VERTEX_SHADER: &str = r#"
+        #version 150
+
+        in vec3 position;
+
+        uniform mat4 perspective;
+        uniform mat4 view;
+        // Side of a cube in world units
+        uniform float scale;
+
+        void main() {
+            gl_Position = perspective * view * vec4(scale * position, 1.0);
+        }
+    "#;
+
+pub const OUTLINE_FRAGMENT_SHADER: &str = r#"
+        #version 140
+
+        uniform vec4 outline_color;
+
+        out vec4 color;
+
+        void main() {
+            color = outline_color;
+        }
+    "#;
+
+/// Distance between the wireframe and the faces of the cube [cube side].
+/// It is further than the inflated cube of the face highlight, so that the edges are drawn over it.
+const OUTLINE_INFLATION: f32 = 0.01;
+
+/// Strength of the highlight mixed over the faces of the targeted cube, from 0 to 1
+const SELECTED_INTENSITY: f32 = 0.2;
+
+/// Number of images of the cracks of a cube being broken, stacked vertically in their texture from the first one
+pub const CRACK_STAGES: u32 = 10;
+
+/// The image of the cracks of a cube broken up to `progress` (from 0 to 1), none before its breaking starts
+pub fn crack_stage(progress: f32) -> Option<u32> {
+    (progress > 0.).then(|| ((progress * CRACK_STAGES as f32) as u32).min(CRACK_STAGES - 1))
+}
+
+/// An end of an edge of the wireframe, in cube units
+#[derive(Copy, Clone, Debug)]
+pub struct OutlineVertex {
+    position: [f32; 3],
+}
+
+implement_vertex!(OutlineVertex, position);
+
+/// The 12 edges of the cube whose corner with the lowest coordinates is `corner`, slightly inflated.
+/// They are drawn as a list of lines, two vertices per edge.
+pub fn outline_vertices(corner: &Vector3) -> [OutlineVertex; 24] {
+    let low = [corner[0] - OUTLINE_INFLATION, corner[1] - OUTLINE_INFLATION, corner[2] - OUTLINE_INFLATION];
+    let high = [corner[0] + 1. + OUTLINE_INFLATION, corner[1] + 1. + OUTLINE_INFLATION, corner[2] + 1. + OUTLINE_INFLATION];
+    // The corner of the cube from 3 bits, one per axis: 0 for the low side, 1 for the high side
+    let vertex = |bits: usize| OutlineVertex {
+        position: [0, 1, 2].map(|axis| if bits >> axis & 1 == 0 { low[axis] } else { high[axis] }),
+    };
+
+    let mut vertices = [vertex(0); 24];
+    let mut i = 0;
+    for axis in 0..3 {
+        // The 4 edges along an axis start from the corners on the low side of this axis
+        for start in (0..8).filter(|bits| bits >> axis & 1 == 0) {
+            vertices[i] = vertex(start);
+            vertices[i + 1] = vertex(start | 1 << axis);
+            i += 2;
+        }
+    }
+    vertices
+}
+
+/// What is drawn for the cube targeted by the player, on top of the other cubes
+pub struct BlockHighlight {
+    /// An inflated copy of the cube, whose faces show the highlight and the progress of the breaking
+    pub faces: Option<CubeInstance>,
+    /// The wireframe of the cube
+    pub edges: Option<[OutlineVertex; 24]>,
+    /// The image of the cracks drawn over the faces, see `crack_stage`
+    pub crack: Option<u32>,
+    /// Strength of the highlight on the faces, none when they only show the cracks
+    pub intensity: f32,
+    /// The cube targeted, highlighted or not
+    target: Option<Cube>,
+}
+
+impl BlockHighlight {
+    /// The highlight of the `selected` cube, nothing is shown when the highlight is disabled
+    pub fn new(target: Option<Cube>, enabled: bool, mode: SelectionMode) -> Self {
+        let selected = target.filter(|_| enabled && mode != SelectionMode::None);
+        Self {
+            faces: selected.as_ref().map(CubeInstance::new_selected),
+            edges: selected.filter(|_| mode == SelectionMode::Outline).map(|cube| outline_vertices(cube.position())),
+            crack: None,
+            intensity: SELECTED_INTENSITY,
+            target,
+        }
+    }
+
+    /// Shows the cracks of the breaking of the cube on its faces, even when the highlight is disabled
+    pub fn with_break_progress(mut self, progress: f32) -> Self {
+        self.crack = crack_stage(progress).filter(|_| self.target.is_some());
+        if self.crack.is_some() && self.faces.is_none() {
+            self.faces = self.target.as_ref().map(CubeInstance::new_selected);
+            self.intensity = 0.;
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::block_kind::Block::DIRT;
+    use crate::cube::Cube;
+    use crate::graphics::block_outline::{crack_stage, outline_vertices, BlockHighlight, CRACK_STAGES, OUTLINE_INFLATION};
+    use crate::graphics::cube::SelectionMode;
+    use crate::primitives::vector::Vector3;
+
+    #[test]
+    fn test_outline_edges() {
+        let vertices = outline_vertices(&Vector3::new(1., 2., 3.));
+        let side = 1. + 2. * OUTLINE_INFLATION;
+        let mut edges_per_axis = [0; 3];
+        for edge in vertices.chunks(2) {
+            let [a, b] = [edge[0].position, edge[1].position];
+            // Each edge goes along a single axis, over the whole inflated side
+            let moving: Vec<usize> = (0..3).filter(|axis| a[*axis] != b[*axis]).collect();
+            assert_eq!(moving.len(), 1);
+            assert!((b[moving[0]] - a[moving[0]] - side).abs() < 1e-5);
+            edges_per_axis[moving[0]] += 1;
+        }
+        assert_eq!(edges_per_axis, [4, 4, 4]);
+
+        // The wireframe surrounds the cube
+        let min = vertices.iter().map(|v| v.position[1]).fold(f32::MAX, f32::min);
+        let max = vertices.iter().map(|v| v.position[1]).fold(f32::MIN, f32::max);
+        assert!((min - (2. - OUTLINE_INFLATION)).abs() < 1e-5);
+        assert!((max - (3. + OUTLINE_INFLATION)).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_highlight_modes() {
+        let cube = Some(Cube::new([0., 0., 0.], DIRT, 0));
+        let outline = BlockHighlight::new(cube, true, SelectionMode::Outline);
+        assert!(outline.faces.unwrap().is_selected());
+        assert!(outline.edges.is_some());
+
+        let faces = BlockHighlight::new(cube, true, SelectionMode::FaceHighlight);
+        assert!(faces.faces.is_some() && faces.edges.is_none());
+
+        // Nothing is shown when disabled, or without a cube
+        for highlight in [
+            BlockHighlight::new(cube, true, SelectionMode::None),
+            BlockHighlight::new(cube, false, SelectionMode::Outline),
+            BlockHighlight::new(None, true, SelectionMode::Outline),
+        ] {
+            assert!(highlight.faces.is_none() && highlight.edges.is_none());
+        }
+    }
+
+    #[test]
+    fn test_cracks_follow_the_breaking() {
+        assert_eq!(crack_stage(0.), None);
+        assert_eq!(crack_stage(0.01), Some(0));
+        assert_eq!(crack_stage(0.55), Some(5));
+        assert_eq!(crack_stage(1.), Some(CRACK_STAGES - 1));
+
+        let cube = Some(Cube::new([0., 0., 0.], DIRT, 0));
+        let highlight = BlockHighlight::new(cube, true, SelectionMode::FaceHighlight).with_break_progress(0.3);
+        assert_eq!(highlight.crack, Some(3));
+        assert_eq!(BlockHighlight::new(cube, true, SelectionMode::Outline).with_break_progress(0.).crack, None);
+        // The cracks are drawn without the highlight when it is disabled
+        let disabled = BlockHighlight::new(cube, false, SelectionMode::Outline).with_break_progress(0.3);
+        assert_eq!(disabled.crack, Some(3));
+        assert!(disabled.faces.is_some() && disabled.edges.is_none());
+        assert_eq!(disabled.intensity, 0.);
+        assert!(BlockHighlight::new(cube, false, SelectionMode::Outline).with_break_progress(0.).faces.is_none());
+        assert_eq!(BlockHighlight::new(None, true, SelectionMode::Outline).with_break_progress(0.3).crack, None);
+    }
+}
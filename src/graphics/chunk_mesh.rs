@@ -0,0 +1,615 @@
+use glium::implement_vertex;
+
+use crate::block_kind::Block;
+use crate::chunk::{Chunk, CHUNK_HEIGHT, CHUNK_SIZE};
+use crate::cube::Cube;
+use crate::lighting::{brightness, ChunkLight, LightChannel, MAX_LIGHT};
+use crate::primitives::vector::Vector3;
+use crate::texture_atlas::{BlockFace, TextureAtlas};
+use crate::world_generation::biome::BiomeGenerator;
+use crate::world_generation::biomes_def::BIOMES;
+
+// Vertex shader of the meshed chunks
+// The vertices are already in the grid, there is no instance to place them
+pub const CHUNK_VERTEX_SHADER: &str = r#"
+        #version 150
+
+        in vec3 position;
+        in vec2 tex_coords;
+        in float layer;
+        in float frames;
+        in vec3 normal;
+        in vec2 light;
+        in float occlusion;
+        in vec3 tint;
+
+        out vec2 v_tex_coords;
+        flat out float layer_s;
+        flat out float frames_s;
+        flat out vec3 normal_s;
+        out vec2 light_s;
+        out float occlusion_s;
+        flat out vec3 tint_s;
+
+        uniform mat4 perspective;
+        uniform mat4 view;
+        // Side of a cube in world units
+        uniform float scale;
+
+        void main() {
+            gl_Position = perspective * view * vec4(scale * position, 1.0);
+            v_tex_coords = tex_coords;
+            layer_s = layer;
+            frames_s = frames;
+            normal_s = normal;
+            light_s = light;
+            occlusion_s = occlusion;
+            tint_s = tint;
+        }
+    "#;
+
+// Fragment shader of the meshed chunks
+pub const CHUNK_FRAGMENT_SHADER: &str = r#"
+        #version 140
+
+        in vec2 v_tex_coords;
+        flat in float layer_s;
+        // Number of images of the animated texture, they follow the layer of the first one
+        flat in float frames_s;
+        flat in vec3 normal_s;
+        // Brightness of the sky light and of the block light reaching the face, interpolated between its corners
+        in vec2 light_s;
+        // Darkening of the corners by the blocks around them
+        in float occlusion_s;
+        // Color of the grass of the biome, white for the other faces
+        flat in vec3 tint_s;
+
+        out vec4 color;
+
+        uniform sampler2DArray textures;
+        // Opacity of the chunk, while it fades in
+        uniform float alpha;
+        // Light of the sun, as for the cubes
+        uniform vec3 light_direction;
+        uniform float ambient;
+        uniform float sun_intensity;
+        // Time of the animation clock [s], and its speed [image/s] (see `current_frame`)
+        uniform float animation_time;
+        uniform float animation_fps;
+
+        void main() {
+            float frame = mod(floor(animation_time * animation_fps), frames_s);
+            // A merged face spans several cubes, the texture is repeated once per cube
+            color = texture(textures, vec3(fract(v_tex_coords), layer_s + frame));
+            color.rgb *= tint_s;
+            float sun = (ambient + sun_intensity * max(dot(normal_s, -light_direction), 0.0)) * light_s.x;
+            // The torches light the caves and the night, slightly dimmer than the sun
+            float light = max(sun, 0.9 * light_s.y);
+            color.rgb *= clamp(light, 0.03, 1.0) * occlusion_s;
+            color.a *= alpha;
+        }
+    "#;
+
+/// A vertex of the mesh of a chunk, in cube coordinates
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ChunkVertex {
+    position: [f32; 3],
+    /// Goes from 0 to the number of cubes covered by the face along each side
+    tex_coords: [f32; 2],
+    /// Index of the texture in the texture array
+    layer: f32,
+    /// Number of images of the animation of the texture, 1 if it is static
+    frames: f32,
+    /// Direction the face looks to, for its lighting
+    normal: [f32; 3],
+    /// Brightness of the sky light and of the block light around the corner, see `lighting::brightness`
+    light: [f32; 2],
+    /// Ambient occlusion of the corner, from 1 in the open down to `OCCLUSION[3]` in a nook
+    occlusion: f32,
+    /// Color multiplying the texture, see `Block::takes_grass_tint`
+    tint: [f32; 3],
+}
+
+implement_vertex!(ChunkVertex, position, tex_coords, layer, frames, normal, light, occlusion, tint);
+
+impl ChunkVertex {
+    pub fn position(&self) -> [f32; 3] {
+        self.position
+    }
+
+    pub fn light(&self) -> [f32; 2] {
+        self.light
+    }
+
+    pub fn occlusion(&self) -> f32 {
+        self.occlusion
+    }
+
+    pub fn tint(&self) -> [f32; 3] {
+        self.tint
+    }
+}
+
+/// Tint of the faces drawn with the colors of their texture
+pub const NO_TINT: [f32; 3] = [1.; 3];
+
+/// Darkening of a corner by the number of blocks around it (the two along the edges and the diagonal one)
+const OCCLUSION: [f32; 4] = [1., 0.8, 0.65, 0.5];
+
+/// Light, ambient occlusion and tint at one corner of a face
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Shade {
+    light: [f32; 2],
+    occlusion: f32,
+    tint: [f32; 3],
+}
+
+/// The faces of the opaque cubes of a chunk, where the coplanar faces of identical blocks are merged
+#[derive(Clone, Debug, Default)]
+pub struct ChunkMesh {
+    vertices: Vec<ChunkVertex>,
+    indices: Vec<u32>,
+}
+
+/// The six directions a face can look to, as an axis and a side along it
+const DIRECTIONS: [(usize, i32); 6] = [(0, 1), (0, -1), (1, 1), (1, -1), (2, 1), (2, -1)];
+
+impl ChunkMesh {
+    pub fn new() -> Self {
+        Self { vertices: Vec::new(), indices: Vec::new() }
+    }
+
+    pub fn vertices(&self) -> &[ChunkVertex] {
+        &self.vertices
+    }
+
+    pub fn indices(&self) -> &[u32] {
+        &self.indices
+    }
+
+    /// Number of quads of the mesh, each made of 4 vertices
+    pub fn n_quads(&self) -> usize {
+        self.vertices.len() / 4
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vertices.is_empty()
+    }
+
+    /// Adds a quad of the block turned by `metadata` from its 4 corners, given in order around it, with the shade
+    /// of each of them. `uv` holds the texture coordinates of the first and of the third corners.
+    fn add_quad(&mut self, corners: [[f32; 3]; 4], uv: [[f32; 2]; 2], (block, metadata): (Block, u8), (axis, side): (usize, i32), normal: [f32; 3], shades: [Shade; 4]) {
+        let first = self.vertices.len() as u32;
+        let [low, high] = uv;
+        let (layer, turned) = face_texture(block, metadata, axis, side);
+        let mut tex_coords = [low, [high[0], low[1]], high, [low[0], high[1]]];
+        if turned {
+            tex_coords = tex_coords.map(|[s, t]| [t, s]);
+        }
+        let frames = block.animation_frames() as f32;
+        for ((position, tex_coords), shade) in corners.into_iter().zip(tex_coords).zip(shades) {
+            self.vertices.push(ChunkVertex {
+                position,
+                tex_coords,
+                layer,
+                frames,
+                normal,
+                light: shade.light,
+                occlusion: shade.occlusion,
+                tint: shade.tint,
+            });
+        }
+        // The quad is split along its brightest diagonal, so that a dark corner does not bleed over the whole face
+        let weight = |i: usize| shades[i].occlusion * (shades[i].light[0] + shades[i].light[1]);
+        if weight(0) + weight(2) >= weight(1) + weight(3) {
+            self.indices.extend([first, first + 1, first + 2, first + 2, first + 3, first]);
+        } else {
+            self.indices.extend([first + 1, first + 2, first + 3, first + 3, first, first + 1]);
+        }
+    }
+
+    /// Adds every face of the boxes of a block that is not a full cube, see `BlockShape`.
+    /// The faces against the side of the cell are left out when the cell in front of them is not exposed,
+    /// and each face is lit by the cell it looks into, without ambient occlusion.
+    fn add_shape(&mut self, cube: &Cube, is_exposed: &impl Fn(&Vector3) -> bool, light_at: &impl Fn(&Vector3) -> (u8, u8)) {
+        let cell = *cube.position();
+        let block = (*cube.block(), cube.metadata());
+        for shape_box in cube.shape().boxes() {
+            for (axis, side) in DIRECTIONS {
+                let (u, v) = face_axes(axis);
+                let (plane, on_border) = if side > 0 {
+                    (shape_box.max[axis], shape_box.max[axis] == 1.)
+                } else {
+                    (shape_box.min[axis], shape_box.min[axis] == 0.)
+                };
+                let mut front = cell;
+                if on_border {
+                    front[axis] += side as f32;
+                    if !is_exposed(&front) {
+                        continue;
+                    }
+                }
+                let (sky, block_light) = light_at(&front);
+                let shade = Shade { light: [brightness(sky), brightness(block_light)], occlusion: 1., tint: NO_TINT };
+
+                let corner = |a: usize, b: usize| {
+                    let mut p = [0.; 3];
+                    p[axis] = cell[axis] + plane;
+                    p[u] = cell[u] + if a == 0 { shape_box.min[u] } else { shape_box.max[u] };
+                    p[v] = cell[v] + if b == 0 { shape_box.min[v] } else { shape_box.max[v] };
+                    p
+                };
+                let mut normal = [0.; 3];
+                normal[axis] = side as f32;
+                self.add_quad(
+                    [corner(0, 0), corner(1, 0), corner(1, 1), corner(0, 1)],
+                    [[shape_box.min[u], shape_box.min[v]], [shape_box.max[u], shape_box.max[v]]],
+                    block,
+                    (axis, side),
+                    normal,
+                    [shade; 4],
+                );
+            }
+        }
+    }
+}
+
+/// The two axes of the plane of the faces looking along `axis`, `v` goes upward for the side faces
+fn face_axes(axis: usize) -> (usize, usize) {
+    match axis {
+        0 => (2, 1),
+        1 => (0, 2),
+        _ => (0, 1),
+    }
+}
+
+/// Index in the texture array of the face looking to `axis` and `side` of this block turned by `metadata`,
+/// and whether the texture is turned a quarter. A block laid along another axis than y (see `Block::axis`)
+/// shows its top and bottom on the faces along this axis, and its sides are turned to follow it.
+fn face_texture(block: Block, metadata: u8, axis: usize, side: i32) -> (f32, bool) {
+    let block_axis = block.axis(metadata);
+    let face = if axis == block_axis { BlockFace::from_axis(1, side) } else { BlockFace::Side };
+    let turned = axis != block_axis && face_axes(axis).1 != block_axis;
+    (TextureAtlas::get().layer(block, face), turned)
+}
+
+/// Light and ambient occlusion of the corner of a face, whose cell in front is `front`.
+/// The corner is toward `toward` along the axes `u` and `v` of the face: the light is averaged over the
+/// open cells touching it, and each block touching it darkens it.
+fn corner_shade(
+    front: &Vector3,
+    (u, v): (usize, usize),
+    toward: [f32; 2],
+    is_exposed: &impl Fn(&Vector3) -> bool,
+    light_at: &impl Fn(&Vector3) -> (u8, u8),
+) -> Shade {
+    let mut side_u = *front;
+    side_u[u] += toward[0];
+    let mut side_v = *front;
+    side_v[v] += toward[1];
+    let mut diagonal = side_u;
+    diagonal[v] += toward[1];
+
+    let (open_u, open_v) = (is_exposed(&side_u), is_exposed(&side_v));
+    // Between two blocks, the diagonal cell cannot be seen from the corner
+    let open_diagonal = (open_u || open_v) && is_exposed(&diagonal);
+
+    let mut light = [0.; 2];
+    let mut n_open = 0;
+    for (cell, open) in [(front, true), (&side_u, open_u), (&side_v, open_v), (&diagonal, open_diagonal)] {
+        if open {
+            let (sky, block) = light_at(cell);
+            light[0] += brightness(sky);
+            light[1] += brightness(block);
+            n_open += 1;
+        }
+    }
+    Shade {
+        light: [light[0] / n_open as f32, light[1] / n_open as f32],
+        occlusion: OCCLUSION[4 - n_open],
+        tint: NO_TINT,
+    }
+}
+
+/// Builds the mesh of the opaque cubes of the chunk with greedy meshing, the slabs and the stairs are added box by box.
+/// A face is only emitted when the cell in front of it `is_exposed`, this is where the cells
+/// of the next chunks are checked. The transparent cubes are left out, they are drawn on their own.
+/// Each corner of a face is lit by the (sky, block) `light_at` the cells around it and darkened by the
+/// blocks touching it (ambient occlusion), and only the faces with the same corners are merged.
+/// The faces of the grass take the color of the biome `tint_at` their cell.
+pub fn greedy_mesh(
+    chunk: &Chunk,
+    is_exposed: impl Fn(&Vector3) -> bool,
+    light_at: impl Fn(&Vector3) -> (u8, u8),
+    tint_at: impl Fn(&Vector3) -> [f32; 3],
+) -> ChunkMesh {
+    let mut mesh = ChunkMesh::new();
+    let corner = chunk.corner();
+    let origin = [corner[0], 0., corner[1]];
+    let dims = [CHUNK_SIZE, CHUNK_HEIGHT, CHUNK_SIZE];
+
+    for (axis, side) in DIRECTIONS {
+        let (u, v) = face_axes(axis);
+        let (du, dv) = (dims[u], dims[v]);
+
+        for slice in 0..dims[axis] {
+            // Block, metadata and shade of the corners of the exposed face of each cell of the slice
+            let mut mask: Vec<Option<((Block, u8), [Shade; 4])>> = vec![None; du * dv];
+            for j in 0..dv {
+                for i in 0..du {
+                    let mut pos = Vector3::newf(origin);
+                    pos[axis] += slice as f32;
+                    pos[u] += i as f32;
+                    pos[v] += j as f32;
+                    let Some(cube) = chunk.cube_at(&pos) else {
+                        continue;
+                    };
+                    if !cube.block().is_opaque_cube() {
+                        continue;
+                    }
+                    let mut neighbor = pos;
+                    neighbor[axis] += side as f32;
+                    if is_exposed(&neighbor) {
+                        let tinted = cube.block().takes_grass_tint(BlockFace::from_axis(axis, side));
+                        let tint = if tinted { tint_at(&pos) } else { NO_TINT };
+                        let shades = [[-1., -1.], [1., -1.], [1., 1.], [-1., 1.]]
+                            .map(|toward| Shade { tint, ..corner_shade(&neighbor, (u, v), toward, &is_exposed, &light_at) });
+                        mask[i + j * du] = Some(((*cube.block(), cube.metadata()), shades));
+                    }
+                }
+            }
+
+            let mut normal = [0.; 3];
+            normal[axis] = side as f32;
+
+            // Grow each face as wide, then as high as possible, and clear what it covers
+            let plane = origin[axis] + slice as f32 + if side > 0 { 1. } else { 0. };
+            for j in 0..dv {
+                let mut i = 0;
+                while i < du {
+                    let Some(face) = mask[i + j * du] else {
+                        i += 1;
+                        continue;
+                    };
+                    let mut w = 1;
+                    while i + w < du && mask[i + w + j * du] == Some(face) {
+                        w += 1;
+                    }
+                    let mut h = 1;
+                    while j + h < dv && (i..i + w).all(|k| mask[k + (j + h) * du] == Some(face)) {
+                        h += 1;
+                    }
+                    for jj in j..j + h {
+                        for ii in i..i + w {
+                            mask[ii + jj * du] = None;
+                        }
+                    }
+
+                    let corner = |a: usize, b: usize| {
+                        let mut p = [0.; 3];
+                        p[axis] = plane;
+                        p[u] = origin[u] + (i + a) as f32;
+                        p[v] = origin[v] + (j + b) as f32;
+                        p
+                    };
+                    let (block, shades) = face;
+                    mesh.add_quad(
+                        [corner(0, 0), corner(w, 0), corner(w, h), corner(0, h)],
+                        [[0., 0.], [w as f32, h as f32]],
+                        block,
+                        (axis, side),
+                        normal,
+                        shades,
+                    );
+                    i += w;
+                }
+            }
+        }
+    }
+
+    // The blocks that are not full cubes are not merged, the faces of their boxes are added one by one
+    for cube in chunk.cubes_iter().flatten() {
+        if !cube.is_transparent() && !cube.shape().is_full() {
+            mesh.add_shape(cube, &is_exposed, &light_at);
+        }
+    }
+    mesh
+}
+
+/// Same as `greedy_mesh`, the cells outside of the chunk are looked up in the chunks next to it,
+/// and the light in the `lights` of these chunks. Without light, the faces are in full sunlight.
+/// The grass is tinted by the biomes of the world of this `seed`.
+/// This only needs copies of the chunks, so that the mesh can be built on another thread.
+pub fn greedy_mesh_in(chunk: &Chunk, neighbors: &[Chunk], lights: &[ChunkLight], seed: u64) -> ChunkMesh {
+    let is_exposed = |pos: &Vector3| {
+        pos.y() >= 0.
+            && std::iter::once(chunk)
+                .chain(neighbors)
+                .filter(|c| c.is_in(pos))
+                .all(|c| c.is_position_free_or_transparent(pos))
+    };
+    let light_at = |pos: &Vector3| {
+        let cell = pos.to_cell();
+        lights
+            .iter()
+            .find(|light| light.contains(cell))
+            .map_or((MAX_LIGHT, 0), |light| (light.get(cell, LightChannel::Sky), light.get(cell, LightChannel::Block)))
+    };
+    // The biome of each column is only looked for once
+    let (x0, z0) = chunk.coord();
+    let tints: Vec<[f32; 3]> = (0..CHUNK_SIZE * CHUNK_SIZE)
+        .map(|i| {
+            let (x, z) = (x0 + (i / CHUNK_SIZE) as i32, z0 + (i % CHUNK_SIZE) as i32);
+            BIOMES[BiomeGenerator::biome_at(seed, x, z) as usize].grass_tint
+        })
+        .collect();
+    let tint_at = |pos: &Vector3| {
+        let [x, _, z] = pos.to_cell();
+        tints[(x - x0) as usize * CHUNK_SIZE + (z - z0) as usize]
+    };
+    greedy_mesh(chunk, is_exposed, light_at, tint_at)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::block_kind::Block::{DIRT, GRASS, OAKLOG, SLAB, STAIRS, WATER};
+    use crate::chunk::{Chunk, CHUNK_SIZE};
+    use crate::graphics::chunk_mesh::{greedy_mesh, greedy_mesh_in, NO_TINT};
+    use crate::lighting::{brightness, MAX_LIGHT};
+    use crate::primitives::vector::Vector3;
+    use crate::texture_atlas::{BlockFace, TextureAtlas};
+
+    #[test]
+    fn test_flat_layer_is_six_quads() {
+        let mut chunk = Chunk::new([0., 0.]);
+        chunk.fill_layer(0, GRASS);
+
+        // Nothing around the chunk: the faces are exposed outside of it and on its free cells
+        let is_exposed = |pos: &Vector3| !chunk.is_in(pos) || chunk.is_position_free(pos);
+        let mesh = greedy_mesh(&chunk, is_exposed, |_| (MAX_LIGHT, 0), |_| NO_TINT);
+        assert_eq!(mesh.n_quads(), 6);
+        assert_eq!(mesh.indices().len(), 6 * 6);
+
+        // The top face covers the whole chunk
+        let s = CHUNK_SIZE as f32;
+        let top: Vec<[f32; 3]> = mesh.vertices().iter().map(|v| v.position()).filter(|p| p[1] == 1.).collect();
+        assert!(top.contains(&[0., 1., 0.]));
+        assert!(top.contains(&[s, 1., s]));
+    }
+
+    #[test]
+    fn test_slabs_and_stairs_are_added_box_by_box() {
+        let mut chunk = Chunk::new([0., 0.]);
+        chunk.add_cube(Vector3::new(0., 0., 0.), SLAB, 0);
+        chunk.add_cube(Vector3::new(4., 0., 0.), STAIRS, 0);
+        let is_exposed = |pos: &Vector3| pos.y() >= 0. && (!chunk.is_in(pos) || chunk.is_position_free_or_transparent(pos));
+        let mesh = greedy_mesh(&chunk, is_exposed, |_| (MAX_LIGHT, 0), |_| NO_TINT);
+
+        // Nothing against the bottom of the world, and every face of the step of the stairs
+        assert_eq!(mesh.n_quads(), 5 + 5 + 6);
+        // The top of the slab is halfway up, its sides show the lower half of the texture
+        let corner: Vec<[f32; 2]> = mesh.vertices().iter().filter(|v| v.position() == [1., 0.5, 1.]).map(|v| v.tex_coords).collect();
+        assert_eq!(corner.len(), 3);
+        assert!(corner.contains(&[1., 0.5]) && corner.contains(&[1., 1.]));
+    }
+
+    #[test]
+    fn test_laid_log_turns_its_textures() {
+        let mut chunk = Chunk::new([0., 0.]);
+        chunk.add_cube(Vector3::new(0., 0., 0.), OAKLOG, 0);
+        chunk.set_metadata(&Vector3::new(0., 0., 0.), 1);
+        chunk.add_cube(Vector3::new(1., 0., 0.), OAKLOG, 0);
+        let is_exposed = |pos: &Vector3| pos.y() >= 0. && (!chunk.is_in(pos) || chunk.is_position_free(pos));
+        let mesh = greedy_mesh(&chunk, is_exposed, |_| (MAX_LIGHT, 0), |_| NO_TINT);
+        // Nothing against the bottom of the world, and the laid log is not merged with the standing one
+        assert_eq!(mesh.n_quads(), 4 + 4);
+
+        let atlas = TextureAtlas::get();
+        let face = |position: [f32; 3], normal: [f32; 3]| {
+            *mesh.vertices().iter().find(|v| v.position() == position && v.normal == normal).unwrap()
+        };
+        // The bottom of the laid log is toward -x, its bark goes along x on its top
+        assert_eq!(face([0., 0., 0.], [-1., 0., 0.]).layer, atlas.layer(OAKLOG, BlockFace::Bottom));
+        let top = face([1., 1., 0.], [0., 1., 0.]);
+        assert_eq!(top.layer, atlas.layer(OAKLOG, BlockFace::Side));
+        assert_eq!(top.tex_coords, [0., 1.]);
+        assert_eq!(face([2., 1., 0.], [0., 1., 0.]).layer, atlas.layer(OAKLOG, BlockFace::Top));
+    }
+
+    #[test]
+    fn test_different_blocks_are_not_merged() {
+        let mut chunk = Chunk::new([0., 0.]);
+        chunk.add_cube(Vector3::new(0., 0., 0.), GRASS, 0);
+        chunk.add_cube(Vector3::new(1., 0., 0.), DIRT, 0);
+        chunk.add_cube(Vector3::new(2., 0., 0.), DIRT, 0);
+        // Water is not meshed, and the faces next to it are still drawn
+        chunk.add_cube(Vector3::new(3., 0., 0.), WATER, 0);
+
+        let is_exposed = |pos: &Vector3| !chunk.is_in(pos) || pos.y() < 0. || chunk.is_position_free_or_transparent(pos);
+        let mesh = greedy_mesh(&chunk, is_exposed, |_| (MAX_LIGHT, 0), |_| NO_TINT);
+        // GRASS: 5 faces (not toward the dirt), DIRT: the two cubes share 4 faces, plus the one toward the water
+        assert_eq!(mesh.n_quads(), 5 + 4 + 1);
+    }
+
+    #[test]
+    fn test_grass_takes_the_tint_of_its_biome() {
+        let mut chunk = Chunk::new([0., 0.]);
+        chunk.fill_layer(0, GRASS);
+        chunk.add_cube(Vector3::new(2., 1., 2.), DIRT, 0);
+
+        // Two biomes of different colors, split at x = 4
+        let yellow = [1., 0.9, 0.5];
+        let tint_at = |pos: &Vector3| if pos.x() < 4. { yellow } else { NO_TINT };
+        let is_exposed = |pos: &Vector3| pos.y() >= 0. && (!chunk.is_in(pos) || chunk.is_position_free_or_transparent(pos));
+        let mesh = greedy_mesh(&chunk, is_exposed, |_| (MAX_LIGHT, 0), tint_at);
+
+        // Only the top of the grass is tinted, and the faces of different colors are not merged
+        let tints = |normal: [f32; 3]| -> Vec<[f32; 3]> {
+            mesh.vertices().iter().filter(|v| v.normal == normal && v.position()[1] == 1.).map(|v| v.tint()).collect()
+        };
+        let top = tints([0., 1., 0.]);
+        assert!(top.contains(&yellow) && top.contains(&NO_TINT));
+        assert!(mesh.vertices().iter().filter(|v| v.tint() == yellow).all(|v| v.position()[0] <= 4.));
+        assert!(tints([1., 0., 0.]).iter().all(|tint| *tint == NO_TINT));
+        assert!(mesh.vertices().iter().filter(|v| v.position()[1] == 2.).all(|v| v.tint() == NO_TINT));
+    }
+
+    #[test]
+    fn test_hidden_faces_are_skipped() {
+        let mut chunk = Chunk::new([0., 0.]);
+        for h in 0..3 {
+            chunk.fill_layer(h, GRASS);
+        }
+        // Only the top is exposed, as if the chunk was surrounded by others
+        let mesh = greedy_mesh(&chunk, |pos| pos.y() >= 3., |_| (MAX_LIGHT, 0), |_| NO_TINT);
+        assert_eq!(mesh.n_quads(), 1);
+    }
+
+    #[test]
+    fn test_neighbors_hide_the_border() {
+        let s = CHUNK_SIZE as f32;
+        let mut chunk = Chunk::new([0., 0.]);
+        chunk.fill_layer(0, DIRT);
+        let mut next = Chunk::new([s, 0.]);
+        next.fill_layer(0, DIRT);
+
+        assert_eq!(greedy_mesh_in(&chunk, &[], &[], 0).n_quads(), 5);
+        // The face toward the next chunk is gone
+        assert_eq!(greedy_mesh_in(&chunk, &[next], &[], 0).n_quads(), 4);
+    }
+
+    #[test]
+    fn test_light_is_baked_into_the_faces() {
+        let mut chunk = Chunk::new([0., 0.]);
+        chunk.fill_layer(0, GRASS);
+
+        // A torch lights the cells above x = 0, the light is smoothed over the corners around them
+        let light_at = |pos: &Vector3| if pos.x() == 0. { (MAX_LIGHT, 14) } else { (MAX_LIGHT, 0) };
+        let mesh = greedy_mesh(&chunk, |pos| pos.y() >= 1., light_at, |_| NO_TINT);
+        // The top face is split at x = 0 and x = 1, whose corners are lit differently
+        assert_eq!(mesh.n_quads(), 3);
+        let block_light = |x: f32| {
+            let vertex = mesh.vertices().iter().find(|v| v.position()[0] == x).unwrap();
+            vertex.light()[1]
+        };
+        let half_lit = (brightness(14) + brightness(0)) / 2.;
+        assert!((block_light(0.) - half_lit).abs() < 1e-6);
+        assert!((block_light(1.) - half_lit).abs() < 1e-6);
+        assert!((block_light(CHUNK_SIZE as f32) - brightness(0)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_ambient_occlusion_around_a_block() {
+        let mut chunk = Chunk::new([0., 0.]);
+        chunk.fill_layer(0, GRASS);
+        chunk.add_cube(Vector3::new(3., 1., 3.), DIRT, 0);
+
+        let is_exposed = |pos: &Vector3| pos.y() >= 0. && (!chunk.is_in(pos) || chunk.is_position_free_or_transparent(pos));
+        let mesh = greedy_mesh(&chunk, is_exposed, |_| (MAX_LIGHT, 0), |_| NO_TINT);
+        // The corners at the foot of the block are darkened, the ones in the open are not
+        let at_foot = mesh.vertices().iter().filter(|v| v.position() == [3., 1., 3.]).collect::<Vec<_>>();
+        assert!(!at_foot.is_empty());
+        assert!(at_foot.iter().all(|v| v.occlusion() < 1.));
+        assert!(mesh.vertices().iter().filter(|v| v.position() == [6., 1., 6.]).all(|v| v.occlusion() == 1.));
+    }
+}
@@ -1,5 +1,6 @@
 #[derive(Copy, Clone)]
 pub enum Color {
+    White,
     Red,
     LightCoral,
     LightGray,
@@ -8,11 +9,14 @@ pub enum Color {
     LightYellow,
     Sky1,
     Sky2,
+    /// Any RGBa color
+    Custom([f32; 4]),
 }
 
 impl Color {
     pub fn rgba(&self) -> [f32; 4] {
         match self {
+            Color::White => [1., 1., 1., 1.],
             Color::Red => [1., 0., 0., 1.],
             Color::LightCoral => [240./255., 128./255., 128./255., 0.6],
             Color::LightGray => [192./255., 192./255., 192./255., 0.3],
@@ -20,12 +24,13 @@ impl Color {
             Color::EvenLighterGray => [240./255., 240./255., 240./255., 0.6],
             Color::LightYellow => [252./255., 253./255., 181./255., 0.8],
             Color::Sky1 => [146./255., 210./255., 249./255., 1.],
-            Color::Sky2 => [205./255., 226./255., 238./255., 1.]
+            Color::Sky2 => [205./255., 226./255., 238./255., 1.],
+            Color::Custom(rgba) => *rgba,
         }
     }
     
     pub fn to_tuple(&self) -> (f32, f32, f32, f32) {
         let rgba = self.rgba();
-        (rgba[0], rgba[1], rgba[2], rgba[2])
+        (rgba[0], rgba[1], rgba[2], rgba[3])
     }
 }
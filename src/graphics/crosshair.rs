@@ -0,0 +1,73 @@
+use crate::graphics::color::Color;
+use crate::graphics::rectangle::HudQuad;
+
+/// Accessibility settings for the crosshair and for the outline of the selected cube
+#[derive(Clone, Copy)]
+pub struct CrosshairConfig {
+    pub crosshair_color: Color,
+    /// Length [pixel] of each branch of the cross
+    pub crosshair_size: f32,
+    /// Thickness [pixel] of each branch of the cross
+    pub crosshair_thickness: f32,
+    pub outline_color: Color,
+    /// Width [pixel] of the lines of the wireframe around the selected cube.
+    /// A thickness of 0 disables the wireframe.
+    pub outline_thickness: f32,
+}
+
+impl CrosshairConfig {
+    pub fn new() -> Self {
+        Self {
+            crosshair_color: Color::Red,
+            crosshair_size: 48.,
+            crosshair_thickness: 2.5,
+            outline_color: Color::Custom([0.1, 0.1, 0.1, 1.]),
+            outline_thickness: 2.,
+        }
+    }
+}
+
+/// Computes the horizontal and the vertical quads of the crosshair.
+/// `screen` is the size of the window and `size`, `thickness` are given in pixels, so that
+/// the cross stays square whatever the aspect ratio of the window.
+pub fn crosshair_quads(screen: (u32, u32), size: f32, thickness: f32) -> [HudQuad; 2] {
+    // One pixel in NDC, which spans 2 units over the whole window
+    let px = 2. / screen.0 as f32;
+    let py = 2. / screen.1 as f32;
+    [
+        HudQuad { center: [0., 0.], half_extents: [size / 2. * px, thickness / 2. * py] },
+        HudQuad { center: [0., 0.], half_extents: [thickness / 2. * px, size / 2. * py] },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graphics::crosshair::crosshair_quads;
+
+    #[test]
+    fn test_crosshair_quads_for_wide_window() {
+        let screen = (1600, 900);
+        let [horizontal, vertical] = crosshair_quads(screen, 40., 4.);
+
+        // Centered
+        for quad in [&horizontal, &vertical] {
+            assert_eq!(quad.center, [0., 0.]);
+            let corners = quad.corners();
+            let sum_u: f32 = corners.iter().map(|c| c[0]).sum();
+            let sum_v: f32 = corners.iter().map(|c| c[1]).sum();
+            assert!(sum_u.abs() < 1e-6 && sum_v.abs() < 1e-6);
+        }
+
+        // Back to pixels, both branches have the same length and thickness
+        let to_pixels = |extent: [f32; 2]| [extent[0] * screen.0 as f32, extent[1] * screen.1 as f32];
+        let h = to_pixels(horizontal.half_extents);
+        let v = to_pixels(vertical.half_extents);
+        assert!((h[0] - 40.).abs() < 1e-3 && (h[1] - 4.).abs() < 1e-3);
+        assert!((v[0] - 4.).abs() < 1e-3 && (v[1] - 40.).abs() < 1e-3);
+
+        // Which means different extents in NDC, since the window is not square
+        assert!(horizontal.half_extents[0] < vertical.half_extents[1]);
+        let corner = horizontal.corners()[0];
+        assert!((corner[0] + 0.025).abs() < 1e-6 && (corner[1] + 4. / 900.).abs() < 1e-6);
+    }
+}
@@ -1,7 +1,11 @@
 use glium::implement_vertex;
 
+use crate::block_kind::Block;
+use crate::block_shape::ShapeBox;
 use crate::cube::Cube;
+use crate::item::Item;
 use crate::primitives::vector::Vector3;
+use crate::texture_atlas::TextureAtlas;
 
 // Vertex shader
 // Most basic example with a camera
@@ -17,27 +21,97 @@ pub const CUBE_VERTEX_SHADER: &str = r#"
         in int face;
         flat out int face_s;
 
-        // Index of the block to be used
-        in int block_id;
-        flat out int block_id_s;
+        // Layers of the side, top and bottom textures of the block in the texture array
+        in vec3 layers;
+        flat out vec3 layers_s;
 
-        // Is the cube currently selected
+        // Is the cube currently selected, its faces are then highlighted
         in int is_selected;
         flat out int is_selected_s;
 
+        // Bitmask of the faces that must not be drawn (one bit per face)
+        in int hidden_faces;
+
+        // Are the transparent pixels of the texture discarded (alpha testing) ?
+        in int is_cutout;
+        flat out int is_cutout_s;
+
+        // Opacity of the chunk of the cube, while it fades in
+        in float alpha;
+        out float alpha_s;
+
+        // Color multiplying the texture (the biome of the leaves)
+        in vec3 tint;
+        flat out vec3 tint_s;
+
+        // Box of the shape of the block drawn by the instance, in its cell (see `BlockShape`)
+        in vec3 shape_min;
+        in vec3 shape_max;
+
+        // Axis the block is laid along (0 for x, 1 for y, 2 for z, see `Block::axis`)
+        in int axis;
+        // Face of the standing block whose texture is drawn on the face
+        flat out int texture_face_s;
+
+        // Current image of the animated texture, 0 for a static one
+        in int animation_frame;
+        flat out int animation_frame_s;
+
         // Where is the vertex located on the face ?
-        in vec2 tex_coords;
         out vec2 v_tex_coords;
 
         uniform mat4 perspective;
         uniform mat4 view;
+        // Side of a cube in world units
+        uniform float scale;
+
+        // The faces swapped when the x or the z axis is swapped with the y axis
+        const int SWAP_X[6] = int[6](0, 4, 2, 5, 1, 3);
+        const int SWAP_Z[6] = int[6](5, 1, 4, 3, 2, 0);
+
+        // Texture coordinates of a point of the cell on a face, the same as the ones of `VERTICES` for a full cube
+        vec2 face_tex_coords(int face, vec3 p) {
+            if (face == 0) {
+                return p.xy;
+            } else if (face == 1) {
+                return p.zy;
+            } else if (face == 2) {
+                return vec2(1.0 - p.x, p.y);
+            } else if (face == 3) {
+                return vec2(1.0 - p.z, p.y);
+            }
+            return vec2(p.z, 1.0 - p.x);
+        }
 
         void main() {
-            gl_Position = perspective * view * world_matrix * vec4(position, 1.0);
-            v_tex_coords = tex_coords;
+            if (((hidden_faces >> face) & 1) != 0) {
+                // Every vertex of the face ends up on the same point outside of the screen,
+                // so the face is never rasterized
+                gl_Position = vec4(2.0, 2.0, 2.0, 1.0);
+            } else {
+                vec4 world_position = world_matrix * vec4(position, 1.0);
+                gl_Position = perspective * view * vec4(scale * world_position.xyz, 1.0);
+            }
+            // A box smaller than the cell shows the part of the texture that a full cube would show there
+            vec3 p = mix(shape_min, shape_max, position + 0.5);
+            // A laid block shows the texture of the standing block, with its axis swapped with the y axis
+            int texture_face = face;
+            if (axis == 0) {
+                p = p.yxz;
+                texture_face = SWAP_X[face];
+            } else if (axis == 2) {
+                p = p.xzy;
+                texture_face = SWAP_Z[face];
+            }
+            v_tex_coords = face_tex_coords(texture_face, p);
+            texture_face_s = texture_face;
+            animation_frame_s = animation_frame;
             face_s = face;
-            block_id_s = block_id;
+            layers_s = layers;
             is_selected_s = is_selected;
+            is_cutout_s = is_cutout;
+            alpha_s = alpha;
+            tint_s = tint;
         }
     "#;
 
@@ -47,8 +121,13 @@ pub const CUBE_FRAGMENT_SHADER: &str = r#"
 
         // passed-through the vertex shader
         flat in int face_s;
-        flat in int block_id_s;
+        flat in int texture_face_s;
+        flat in vec3 layers_s;
+        flat in int animation_frame_s;
         flat in int is_selected_s;
+        flat in int is_cutout_s;
+        in float alpha_s;
+        flat in vec3 tint_s;
         in vec2 v_tex_coords;
 
         out vec4 color ;
@@ -58,28 +137,70 @@ pub const CUBE_FRAGMENT_SHADER: &str = r#"
         // uniforms for the selected block
         uniform sampler2D selected_texture;
         uniform float selected_intensity;
+        // Images of the cracks of the block being broken, the one drawn or -1 for none, out of `crack_stages`
+        uniform sampler2D crack_texture;
+        uniform int crack_stage;
+        uniform int crack_stages;
+
+        // Light of the sun (see `Sky`): the direction it travels, the light received by every face
+        // and the one added to the faces turned toward the sun
+        uniform vec3 light_direction;
+        uniform float ambient;
+        uniform float sun_intensity;
+
+        // Normal of each face, in the order of `VERTICES`
+        const vec3 NORMALS[6] = vec3[6](
+            vec3(0.0, 0.0, -1.0), vec3(1.0, 0.0, 0.0), vec3(0.0, 0.0, 1.0),
+            vec3(-1.0, 0.0, 0.0), vec3(0.0, 1.0, 0.0), vec3(0.0, -1.0, 0.0)
+        );
 
         void main() {
-            // Each block has 3 types of faces
-            int idx = block_id_s * 3;
-
-            if (face_s == 5) {
+            // Each block has 3 types of faces, the images of an animated face follow its first one
+            vec3 layers = layers_s + float(animation_frame_s);
+            if (texture_face_s == 5) {
                 // bottom
-                color = texture(textures, vec3(v_tex_coords, idx + 2));
-            } else if (face_s == 4) {
+                color = texture(textures, vec3(v_tex_coords, layers.z));
+            } else if (texture_face_s == 4) {
                 // top
-                color = texture(textures, vec3(v_tex_coords, idx + 1));
+                color = texture(textures, vec3(v_tex_coords, layers.y));
             } else {
                 // sides
-                color = texture(textures, vec3(v_tex_coords, float(idx)));
+                color = texture(textures, vec3(v_tex_coords, layers.x));
             }
+            // The holes of the glass and of the leaves
+            if (is_cutout_s != 0 && color.a < 0.5) {
+                discard;
+            }
+            color.rgb *= tint_s;
 
             if (is_selected_s != 0) {
                 color = mix(color, texture(selected_texture, v_tex_coords), selected_intensity);
+                if (crack_stage >= 0) {
+                    // The texture is loaded upside down, its first image is at the top
+                    float row = float(crack_stages - 1 - crack_stage);
+                    vec4 crack = texture(crack_texture, vec2(v_tex_coords.x, (clamp(v_tex_coords.y, 0.0, 1.0) + row) / float(crack_stages)));
+                    color.rgb = mix(color.rgb, crack.rgb, crack.a);
+                }
             }
+
+            float light = ambient + sun_intensity * max(dot(NORMALS[face_s], -light_direction), 0.0);
+            color.rgb *= min(light, 1.0);
+            color.a *= alpha_s;
         }
     "#;
 
+/// How the cube targeted by the player is shown, see `BlockHighlight`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SelectionMode {
+    /// The faces are highlighted and the edges are drawn as a wireframe
+    #[default]
+    Outline,
+    /// Only the faces are highlighted
+    FaceHighlight,
+    /// The cube is drawn as the others
+    None,
+}
+
 /// A vertex of a cube
 /// The position is expressed into the OpenGL reference frame
 #[derive(Copy, Clone)]
@@ -137,29 +258,92 @@ pub const VERTICES: [CubeVertex; 36] = [
     CubeVertex { position: [-0.5, -0.5, -0.5], tex_coords: [0.0, 1.0], face: 5 },
 ];
 
+/// Index of the face touching each of the neighbors returned by `Cube::neighbors_positions`
+/// (+x, -x, +y, -y, +z, -z)
+pub const NEIGHBOR_FACES: [u8; 6] = [1, 3, 4, 5, 2, 0];
+
+/// Image of an animation of `frames` images at `fps` images per second, shown at `time` [s].
+/// The animation loops forever.
+pub fn current_frame(time: f32, frames: u8, fps: f32) -> u8 {
+    if frames <= 1 || time < 0. {
+        return 0;
+    }
+    ((time * fps).floor() as u64 % frames as u64) as u8
+}
 
 /// An OpenGL type that contains the information for OpenGL's instancing
 #[derive(Copy, Clone)]
 pub struct CubeInstance {
     world_matrix: [[f32; 4]; 4],
-    block_id: u8,
+    /// Layers of the side, top and bottom textures of the block, see `TextureAtlas`
+    layers: [f32; 3],
     /// We use an integer, since booleans are not supported
     is_selected: u8,
+    /// Whether the transparent pixels of the texture are discarded, see `Block::is_cutout`
+    is_cutout: u8,
+    /// One bit per face, a face whose bit is set is not drawn
+    hidden_faces: u8,
+    /// Multiplies the opacity of the cube, used to fade the new chunks in
+    alpha: f32,
+    /// Color multiplying the texture, the one of the biome for the leaves (see `Block::takes_foliage_tint`)
+    tint: [f32; 3],
+    /// Image of the animation currently shown
+    animation_frame: u8,
+    /// Box of the shape of the block drawn, from 0 to 1 in the cell, see `instances`
+    shape_min: [f32; 3],
+    shape_max: [f32; 3],
+    /// Axis the block is laid along, see `Block::axis`
+    axis: u8,
     position: Vector3,
 }
 
-implement_vertex!(CubeInstance, world_matrix, block_id, is_selected);
+implement_vertex!(CubeInstance, world_matrix, layers, is_selected, is_cutout, hidden_faces, alpha, tint, animation_frame, shape_min, shape_max, axis);
 
 impl CubeInstance {
     pub fn new(cube: &Cube) -> Self {
         Self {
             world_matrix: Self::model_matrix(cube.position()),
-            block_id: cube.block_id(),
+            layers: TextureAtlas::get().layers(*cube.block()),
             is_selected: false as u8,
+            is_cutout: cube.block().is_cutout() as u8,
+            hidden_faces: 0,
+            alpha: 1.,
+            tint: [1.; 3],
+            animation_frame: 0,
+            shape_min: [0.; 3],
+            shape_max: [1.; 3],
+            axis: cube.block().axis(cube.metadata()) as u8,
             position: cube.position().clone(),
         }
     }
     
+    /// The instances drawing the cube, one per box of the shape of its block (see `BlockShape`)
+    pub fn instances(cube: &Cube) -> Vec<Self> {
+        cube.shape()
+            .boxes()
+            .iter()
+            .map(|shape_box| {
+                let mut instance = Self::new(cube);
+                instance.set_shape_box(shape_box);
+                instance
+            })
+            .collect()
+    }
+
+    /// Draws only this box of the cell
+    fn set_shape_box(&mut self, shape_box: &ShapeBox) {
+        let size = [0, 1, 2].map(|i| shape_box.max[i] - shape_box.min[i]);
+        let center = [0, 1, 2].map(|i| self.position[i] + (shape_box.min[i] + shape_box.max[i]) / 2.);
+        self.world_matrix = [
+            [size[0], 0.0, 0.0, 0.0],
+            [0.0, size[1], 0.0, 0.0],
+            [0.0, 0.0, size[2], 0.0],
+            [center[0], center[1], center[2], 1.0],
+        ];
+        self.shape_min = shape_box.min;
+        self.shape_max = shape_box.max;
+    }
+
     /// Creates a new selected cube
     /// This cube will be slightly inflated, which is a hack to greatly optimize performances
     /// Using this trick allows us to not have to update the existing `CubeInstance` selection property, 
@@ -167,17 +351,84 @@ impl CubeInstance {
     pub fn new_selected(cube: &Cube) -> Self {
         Self {
             world_matrix: Self::model_matrix_inflated(&(cube.position())),
-            block_id: cube.block_id(),
+            layers: TextureAtlas::get().layers(*cube.block()),
             is_selected: true as u8,
+            is_cutout: cube.block().is_cutout() as u8,
+            hidden_faces: 0,
+            alpha: 1.,
+            tint: [1.; 3],
+            animation_frame: 0,
+            shape_min: [0.; 3],
+            shape_max: [1.; 3],
+            axis: cube.block().axis(cube.metadata()) as u8,
             position: cube.position().clone(),
         }
     }
 
+    /// Creates a super-cube of this side, drawn instead of the cubes of a far chunk.
+    /// As for a cube, its position is the corner with the lowest coordinates.
+    pub fn new_coarse(corner: &Vector3, size: f32, block: Block) -> Self {
+        let half = size / 2.;
+        Self {
+            world_matrix: [
+                [size, 0.0, 0.0, 0.0],
+                [0.0, size, 0.0, 0.0],
+                [0.0, 0.0, size, 0.0],
+                [corner[0] + half, corner[1] + half, corner[2] + half, 1.0f32]
+            ],
+            layers: TextureAtlas::get().layers(block),
+            is_selected: false as u8,
+            is_cutout: block.is_cutout() as u8,
+            hidden_faces: 0,
+            alpha: 1.,
+            tint: [1.; 3],
+            animation_frame: 0,
+            shape_min: [0.; 3],
+            shape_max: [1.; 3],
+            axis: 1,
+            position: *corner,
+        }
+    }
+
+    /// A small cube of this side standing for an item, centered on `center` and turned by `angle` [rad] around the vertical
+    pub fn new_item(item: Item, center: &Vector3, size: f32, angle: f32) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        // The pictures of the other items are cut out of their background
+        let is_cutout = item.block().map_or(true, |block| block.is_cutout());
+        Self {
+            world_matrix: [
+                [size * cos, 0.0, -size * sin, 0.0],
+                [0.0, size, 0.0, 0.0],
+                [size * sin, 0.0, size * cos, 0.0],
+                [center[0], center[1], center[2], 1.0f32]
+            ],
+            layers: TextureAtlas::get().item_layers(item),
+            is_selected: false as u8,
+            is_cutout: is_cutout as u8,
+            hidden_faces: 0,
+            alpha: 1.,
+            tint: [1.; 3],
+            animation_frame: 0,
+            shape_min: [0.; 3],
+            shape_max: [1.; 3],
+            axis: 1,
+            position: *center,
+        }
+    }
+
     pub fn empty() -> Self {
         Self {
             world_matrix: [[0.; 4]; 4],
-            block_id: 0,
+            layers: [0.; 3],
             is_selected: 0,
+            is_cutout: 0,
+            hidden_faces: 0,
+            alpha: 1.,
+            tint: [1.; 3],
+            animation_frame: 0,
+            shape_min: [0.; 3],
+            shape_max: [1.; 3],
+            axis: 1,
             position: Vector3::empty(),
         }
     }
@@ -190,6 +441,57 @@ impl CubeInstance {
         self.is_selected = is_selected as u8;
     }
 
+    pub fn is_selected(&self) -> bool {
+        self.is_selected != 0
+    }
+
+    pub fn set_hidden_faces(&mut self, hidden_faces: u8) {
+        self.hidden_faces = hidden_faces;
+    }
+
+    pub fn set_alpha(&mut self, alpha: f32) {
+        self.alpha = alpha;
+    }
+
+    pub fn alpha(&self) -> f32 {
+        self.alpha
+    }
+
+    pub fn set_tint(&mut self, tint: [f32; 3]) {
+        self.tint = tint;
+    }
+
+    pub fn tint(&self) -> [f32; 3] {
+        self.tint
+    }
+
+    /// Number of images of the animated texture of the block, 1 if it is not animated
+    fn animation_frames(&self) -> u8 {
+        TextureAtlas::get().frames(self.layers[0])
+    }
+
+    pub fn is_animated(&self) -> bool {
+        self.animation_frames() > 1
+    }
+
+    /// Shows the image of the animation for this time [s]
+    pub fn set_animation_time(&mut self, time: f32, fps: f32) {
+        self.animation_frame = current_frame(time, self.animation_frames(), fps);
+    }
+
+    pub fn animation_frame(&self) -> u8 {
+        self.animation_frame
+    }
+
+    pub fn is_face_hidden(&self, face: u8) -> bool {
+        (self.hidden_faces >> face) & 1 != 0
+    }
+
+    /// Distance between the center of the cube and the given point
+    pub fn distance_to(&self, point: &Vector3) -> f32 {
+        (self.position + Vector3::new(0.5, 0.5, 0.5)).distance_to(point)
+    }
+
     pub fn model_matrix(position: &Vector3) -> [[f32; 4]; 4] {
         // TODO As you can see, I added 0.5 at each cube model
         //      It's because I was lazy to edit all the values in `VERTICES` of +0.5, but
@@ -246,7 +548,10 @@ impl CubeContainer {
 
 #[cfg(test)]
 mod tests {
-    use crate::graphics::cube::{CubeInstance, CubeContainer};
+    use crate::block_kind::Block;
+    use crate::block_shape::Facing;
+    use crate::cube::Cube;
+    use crate::graphics::cube::{current_frame, CubeInstance, CubeContainer};
 
     #[test]
     fn test_cube_container() {
@@ -260,4 +565,46 @@ mod tests {
         container.reset();
         assert_eq!(container.as_slice().len(), 0);
     }
+
+    #[test]
+    fn test_current_frame() {
+        // 4 images at 2 images per second
+        assert_eq!(current_frame(0., 4, 2.), 0);
+        assert_eq!(current_frame(0.49, 4, 2.), 0);
+        assert_eq!(current_frame(0.5, 4, 2.), 1);
+        assert_eq!(current_frame(1.75, 4, 2.), 3);
+        // Loops back to the first image
+        assert_eq!(current_frame(2., 4, 2.), 0);
+        assert_eq!(current_frame(2.6, 4, 2.), 1);
+
+        for time in [0., 0.3, 7.1, 123.4, 1e6] {
+            assert!(current_frame(time, 5, 8.) < 5);
+            // A static block only has one image
+            assert_eq!(current_frame(time, 1, 8.), 0);
+        }
+    }
+
+    #[test]
+    fn test_one_instance_per_box() {
+        let stairs = CubeInstance::instances(&Cube::new([2., 0., 0.], Block::STAIRS, 0));
+        assert_eq!(stairs.len(), 2);
+        // The lower half, in the middle of the lower half of its cell
+        assert_eq!(stairs[0].world_matrix[1][1], 0.5);
+        assert_eq!(stairs[0].world_matrix[3], [2.5, 0.25, 0.5, 1.]);
+        assert_eq!(stairs[1].shape_min, [0., 0.5, 0.5]);
+        assert!(stairs.iter().all(|instance| instance.position() == [2., 0., 0.]));
+
+        let dirt = CubeInstance::instances(&Cube::new([2., 0., 0.], Block::DIRT, 0));
+        assert_eq!(dirt.len(), 1);
+        assert_eq!(dirt[0].world_matrix, CubeInstance::model_matrix(&dirt[0].position));
+        assert_eq!(dirt[0].axis, 1);
+
+        // The stairs turned to the west have their step on the low x side, and a log laid along x tells the shaders
+        let mut turned = Cube::new([2., 0., 0.], Block::STAIRS, 0);
+        turned.set_metadata(Facing::West.metadata());
+        assert_eq!(CubeInstance::instances(&turned)[1].shape_max, [0.5, 1., 1.]);
+        let mut log = Cube::new([2., 0., 0.], Block::OAKLOG, 0);
+        log.set_metadata(1);
+        assert_eq!(CubeInstance::new(&log).axis, 0);
+    }
 }
@@ -25,6 +25,9 @@ pub const ENTITY_VERTEX_SHADER: &str = r#"
         in int monster_type;
         flat out int monster_type_s;
 
+        in int hurt;
+        flat out int hurt_s;
+
         uniform mat4 perspective;
         uniform mat4 view;
 
@@ -34,6 +37,7 @@ pub const ENTITY_VERTEX_SHADER: &str = r#"
             face_s = face;
             body_part_id_s = body_part_id;
             monster_type_s = monster_type;
+            hurt_s = hurt;
         }
     "#;
 
@@ -45,6 +49,7 @@ pub const ENTITY_FRAGMENT_SHADER: &str = r#"
         flat in int face_s;
         flat in int body_part_id_s;
         flat in int monster_type_s;
+        flat in int hurt_s;
         in vec2 v_tex_coords;
 
         out vec4 color ;
@@ -56,6 +61,10 @@ pub const ENTITY_FRAGMENT_SHADER: &str = r#"
             // There is 4 different block building a monster
             int idx = face_s + body_part_id_s * 6 + monster_type_s * 4 * 6;
             color = texture(entity_textures, vec3(v_tex_coords, idx));
+            // The entities just hit flash red
+            if (hurt_s != 0) {
+                color.rgb = mix(color.rgb, vec3(1.0, 0.0, 0.0), 0.5);
+            }
          }
     "#;
 
@@ -65,9 +74,11 @@ pub struct EntityCube {
     world_matrix: [[f32; 4]; 4],
     body_part_id: u8,
     monster_type: u8,
+    /// 1 while the entity flashes after being hit
+    hurt: u8,
 }
 
-implement_vertex!(EntityCube, world_matrix, body_part_id, monster_type);
+implement_vertex!(EntityCube, world_matrix, body_part_id, monster_type, hurt);
 
 impl EntityCube {
     /// Build a rendered cube center around position (and not around position + (0.5,0.5,0.5) as for CubeAttr !!!!)
@@ -80,7 +91,8 @@ impl EntityCube {
             world_matrix: Self::model_matrix_rot_yx(position, scale),
             // body part_id correspond to the [6*body_part_id,6*body_part_id+5] texture loaded
             body_part_id,
-            monster_type
+            monster_type,
+            hurt: 0,
         }
     }
 
@@ -90,10 +102,21 @@ impl EntityCube {
             world_matrix: Self::model_matrix_rot_y(position, scale),
             // body part_id correspond to the [6*body_part_id,6*body_part_id+5] texture loaded
             body_part_id,
-            monster_type
+            monster_type,
+            hurt: 0,
         }
     }
 
+    /// The same cube, drawn in red for an entity just hit
+    pub fn hurt(mut self) -> Self {
+        self.hurt = 1;
+        self
+    }
+
+    pub fn is_hurt(&self) -> bool {
+        self.hurt != 0
+    }
+
     /// Generate a world matrix with a scaing over each direction
     /// a rotation around y then
     /// a rotation around local x then
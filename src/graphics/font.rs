@@ -1,109 +1,64 @@
 const N_ROWS: f32 = 8.;
 const N_COLS: f32 = 16.;
 
-/// A character that can be rendered with our OpenGL pipeline
-#[derive(Clone,Copy)]
-pub enum GLChar {
-    A,B,C,D,E,F,G,H,I,J,K,L,M,N,O,P,Q,R,S,T,U,V,W,X,Y,Z,
-    DOT,DOUBLEPOINT,COMMA,MINUS,
-    ZERO,ONE,TWO,THREE,FOUR,FIVE,SIX,SEVEN,EIGHT,NINE
-}
+/// First and last characters drawn from the font atlas, the others are drawn with `GLChar::FALLBACK`
+const FIRST_PRINTABLE: u8 = b' ';
+const LAST_PRINTABLE: u8 = b'~';
+
+/// A character that can be rendered with our OpenGL pipeline.
+/// The font atlas holds the 128 ASCII characters, in 8 rows of 16 from its top left corner.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GLChar(u8);
 
 impl GLChar {
+    /// Drawn for the characters missing from the atlas: the empty box of its last cell
+    pub const FALLBACK: GLChar = GLChar(0x7F);
+
+    /// The glyph of the character, `FALLBACK` for the characters that are not printable ASCII
     pub fn from_char(c: char) -> Self {
-        match c {
-            'a' => GLChar::A,
-            'b' => GLChar::B,
-            'c' => GLChar::C,
-            'd' => GLChar::D,
-            'e' => GLChar::E,
-            'f' => GLChar::F,
-            'g' => GLChar::G,
-            'h' => GLChar::H,
-            'i' => GLChar::I,
-            'j' => GLChar::J,
-            'k' => GLChar::K,
-            'l' => GLChar::L,
-            'm' => GLChar::M,
-            'n' => GLChar::N,
-            'o' => GLChar::O,
-            'p' => GLChar::P,
-            'q' => GLChar::Q,
-            'r' => GLChar::R,
-            's' => GLChar::S,
-            't' => GLChar::T,
-            'u' => GLChar::U,
-            'v' => GLChar::V,
-            'w' => GLChar::W,
-            'x' => GLChar::X,
-            'y' => GLChar::Y,
-            'z' => GLChar::Z,
-            '.' => GLChar::DOT,
-            ':' => GLChar::DOUBLEPOINT,
-            ',' => GLChar::COMMA,
-            '-' => GLChar::MINUS,
-            '0' => GLChar::ZERO,
-            '1' => GLChar::ONE,
-            '2' => GLChar::TWO,
-            '3' => GLChar::THREE,
-            '4' => GLChar::FOUR,
-            '5' => GLChar::FIVE,
-            '6' => GLChar::SIX,
-            '7' => GLChar::SEVEN,
-            '8' => GLChar::EIGHT,
-            '9' => GLChar::NINE,
-            _ => panic!("Character is not supported: {c}")
+        if Self::is_supported(c) {
+            GLChar(c as u8)
+        } else {
+            Self::FALLBACK
         }
     }
 
+    /// Whether the atlas has a glyph for this character
+    pub fn is_supported(c: char) -> bool {
+        c.is_ascii() && (FIRST_PRINTABLE..=LAST_PRINTABLE).contains(&(c as u8))
+    }
+
     /// Returns the index of the bottom-left corner in the font atlas
     pub fn get_index(&self) -> [f32;2] {
-        match self {
-            GLChar::A => [1. / N_COLS, 3. / N_ROWS],
-            GLChar::B => [2. / N_COLS, 3. / N_ROWS],
-            GLChar::C => [3. / N_COLS, 3. / N_ROWS],
-            GLChar::D => [4. / N_COLS, 3. / N_ROWS],
-            GLChar::E => [5. / N_COLS, 3. / N_ROWS],
-            GLChar::F => [6. / N_COLS, 3. / N_ROWS],
-            GLChar::G => [7. / N_COLS, 3. / N_ROWS],
-            GLChar::H => [8. / N_COLS, 3. / N_ROWS],
-            GLChar::I => [9. / N_COLS, 3. / N_ROWS],
-            GLChar::J => [10. / N_COLS, 3. / N_ROWS],
-            GLChar::K => [11. / N_COLS, 3. / N_ROWS],
-            GLChar::L => [12. / N_COLS, 3. / N_ROWS],
-            GLChar::M => [13. / N_COLS, 3. / N_ROWS],
-            GLChar::N => [14. / N_COLS, 3. / N_ROWS],
-            GLChar::O => [15. / N_COLS, 3. / N_ROWS],
-            GLChar::P => [0. / N_COLS, 2. / N_ROWS],
-            GLChar::Q => [1. / N_COLS, 2. / N_ROWS],
-            GLChar::R => [2. / N_COLS, 2. / N_ROWS],
-            GLChar::S => [3. / N_COLS, 2. / N_ROWS],
-            GLChar::T => [4. / N_COLS, 2. / N_ROWS],
-            GLChar::U => [5. / N_COLS, 2. / N_ROWS],
-            GLChar::V => [6. / N_COLS, 2. / N_ROWS],
-            GLChar::W => [7. / N_COLS, 2. / N_ROWS],
-            GLChar::X => [8. / N_COLS, 2. / N_ROWS],
-            GLChar::Y => [9. / N_COLS, 2. / N_ROWS],
-            GLChar::Z => [10. / N_COLS, 2. / N_ROWS],
-            GLChar::COMMA => [12. / N_COLS, 5. / N_ROWS],
-            GLChar::DOT => [14. / N_COLS, 5. / N_ROWS],
-            GLChar::DOUBLEPOINT => [10. / N_COLS, 4. / N_ROWS],
-            GLChar::MINUS => [13. / N_COLS, 5. / N_ROWS],
-            GLChar::ZERO => [0. / N_COLS, 4. / N_ROWS],
-            GLChar::ONE => [1. / N_COLS, 4. / N_ROWS],
-            GLChar::TWO => [2. / N_COLS, 4. / N_ROWS],
-            GLChar::THREE => [3. / N_COLS, 4. / N_ROWS],
-            GLChar::FOUR => [4. / N_COLS, 4. / N_ROWS],
-            GLChar::FIVE => [5. / N_COLS, 4. / N_ROWS],
-            GLChar::SIX => [6. / N_COLS, 4. / N_ROWS],
-            GLChar::SEVEN => [7. / N_COLS, 4. / N_ROWS],
-            GLChar::EIGHT => [8. / N_COLS, 4. / N_ROWS],
-            GLChar::NINE => [9. / N_COLS, 4. / N_ROWS],
-        }
+        let col = self.0 % N_COLS as u8;
+        // The rows of the atlas are counted from its bottom
+        let row = N_ROWS as u8 - 1 - self.0 / N_COLS as u8;
+        [col as f32 / N_COLS, row as f32 / N_ROWS]
     }
 
     /// Returns the dimensions of each font in the font atlas
     pub fn get_offset() -> [f32;2] {
         [1. / N_COLS * 0.7, 1. / N_ROWS]
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graphics::font::{GLChar, N_COLS, N_ROWS};
+
+    #[test]
+    fn test_glyphs_in_the_atlas() {
+        assert_eq!(GLChar::from_char('A').get_index(), [1. / N_COLS, 3. / N_ROWS]);
+        assert_eq!(GLChar::from_char('a').get_index(), [1. / N_COLS, 1. / N_ROWS]);
+        assert_eq!(GLChar::from_char('0').get_index(), [0. / N_COLS, 4. / N_ROWS]);
+        assert_eq!(GLChar::from_char('/').get_index(), [15. / N_COLS, 5. / N_ROWS]);
+        assert_eq!(GLChar::from_char('~').get_index(), [14. / N_COLS, 0. / N_ROWS]);
+
+        // The other characters do not panic
+        for c in ['é', '\n', '\u{7F}', '日'] {
+            assert!(!GLChar::is_supported(c));
+            assert_eq!(GLChar::from_char(c), GLChar::FALLBACK);
+        }
+        assert_eq!(GLChar::FALLBACK.get_index(), [15. / N_COLS, 0. / N_ROWS]);
+    }
+}
@@ -1,20 +1,40 @@
+use crate::graphics::color::Color;
 use crate::graphics::color::Color::{LightGray, LightYellow, Red};
 use crate::graphics::rectangle::RectInstance;
 use crate::graphics::string_rect::StringRect;
 use crate::player_items::ItemStack;
 
+/// Left side of the health bar, and of the food bar on its right
+const HEALTH_X0: f32 = 0.12 - 1.;
+const FOOD_X0: f32 = 0.82 - 1.;
+const FOOD_COLOR: Color = Color::Custom([0.6, 0.4, 0.2, 1.]);
+
+/// A row of squares, one per point: the health of the player, or its food (see `Hunger`)
 pub struct HealthBar {
     health: u8,
     rects: Vec<RectInstance>,
     aspect_ratio: f32,
+    x0: f32,
+    color: Color,
 }
 
 impl HealthBar {
     pub fn new(health: u8, aspect_ratio: f32) -> Self {
+        Self::with_style(health, aspect_ratio, HEALTH_X0, Red)
+    }
+
+    /// The bar of the food points, next to the health
+    pub fn food(food: u8, aspect_ratio: f32) -> Self {
+        Self::with_style(food, aspect_ratio, FOOD_X0, FOOD_COLOR)
+    }
+
+    fn with_style(health: u8, aspect_ratio: f32, x0: f32, color: Color) -> Self {
         let mut menu = Self {
             health,
             rects: Vec::new(),
-            aspect_ratio
+            aspect_ratio,
+            x0,
+            color,
         };
 
         menu.update();
@@ -40,7 +60,6 @@ impl HealthBar {
         println!("there are {}", self.health);
         self.rects = Vec::new();
 
-        const X0: f32 = 0.12 - 1.;
         const Y0: f32 = 0.46 - 1.;
         const INNER_MARGIN: f32 = 0.01;
         const HP_SIDE: f32 = 0.05;
@@ -48,9 +67,9 @@ impl HealthBar {
         // Add the health points
         for n in 0..self.health {
             let mut hp = RectInstance::square_from_corner(
-                X0 + n as f32 * (HP_SIDE + INNER_MARGIN),
+                self.x0 + n as f32 * (HP_SIDE + INNER_MARGIN),
                 Y0,
-                HP_SIDE, self.aspect_ratio, Red);
+                HP_SIDE, self.aspect_ratio, self.color);
             self.rects.push(hp);
         }
     }
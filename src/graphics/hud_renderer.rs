@@ -1,13 +1,18 @@
-use crate::graphics::color::Color::{LightGray, Red};
+use crate::graphics::color::Color;
+use crate::graphics::color::Color::LightGray;
 
-use crate::graphics::font::GLChar;
+use crate::graphics::crosshair::{crosshair_quads, CrosshairConfig};
 use crate::graphics::rectangle::RectInstance;
+use crate::graphics::string_rect::StringRect;
+use crate::graphics::text_renderer::{Alignment, TextRenderer, TextSpan};
 
 use super::menu_help;
 use super::menu_help::HelpMenu;
 use super::menu_help::HelpMenuData;
 
-use crate::health::Health;
+use crate::health::{Health, MAX_HEALTH};
+use crate::hunger::{Hunger, MAX_FOOD};
+use crate::chat::Chat;
 
 use super::menu_debug;
 use super::menu_debug::DebugData;
@@ -15,12 +20,16 @@ use super::menu_debug::DebugMenu;
 use super::menu_debug::DebugMenuData;
 
 use super::inventory_menu::InventoryMenu;
+use super::menu_screen::MenuScreen;
+use crate::game_state::MenuEntry;
 use crate::graphics::update_status::UpdateStatus;
 
 use crate::graphics::inventory_event::InventoryEvent;
 use crate::graphics::items_bar::ItemBar;
 use crate::graphics::health_bar::HealthBar;
-use crate::player_items::{ItemStack, PlayerItems};
+use crate::player_items::{ItemStack, Inventory};
+use crate::block_entity::Container;
+use crate::primitives::vector::Vector3;
 
 /// Has the responsability to provide all the HUD to be drawn by OpenGL.
 pub struct HUDRenderer {
@@ -32,6 +41,11 @@ pub struct HUDRenderer {
     /// The rects that are always present on the screen
     base: Vec<RectInstance>,
 
+    /// Size of the window [pixel]
+    dimension: (u32, u32),
+    crosshair_config: CrosshairConfig,
+    crosshair: Vec<RectInstance>,
+
     help_menu: HelpMenu,
     show_help: bool,
 
@@ -40,10 +54,39 @@ pub struct HUDRenderer {
 
     items_bar: ItemBar,
     health_bar: HealthBar,
+    food_bar: HealthBar,
+
+    /// The lines of the chat shown, with their sender, and the line being written
+    chat_lines: Vec<(String, String)>,
+    chat_draft: Option<String>,
+    chat: Vec<RectInstance>,
+
+    /// The names of the other players, with the points of the screen they are drawn above, see `set_name_tags`
+    name_tag_points: Vec<(String, [f32; 2])>,
+    name_tags: Vec<RectInstance>,
 
     inventory_menu: Option<InventoryMenu>,
+
+    /// The main menu, the pause menu or the settings, drawn over everything else
+    menu: Option<MenuScreen>,
+
+    /// The camera is in the water, the whole view is tinted
+    underwater: bool,
 }
 
+/// Color laid over the view when the camera is in the water
+const UNDERWATER_TINT: [f32; 4] = [0.1, 0.3, 0.7, 0.4];
+/// Height of the glyphs of the names above the other players [pixel]
+const NAME_TAG_PIXEL_SIZE: f32 = 14.;
+/// Height of the glyphs of the chat [pixel]
+const CHAT_PIXEL_SIZE: f32 = 16.;
+/// Distance between the bottoms of two lines of the chat [pixel]
+const CHAT_LINE_HEIGHT: f32 = 22.;
+/// Bottom of the chat, above the items bar (in NDC)
+const CHAT_BOTTOM: f32 = -0.6;
+/// Left side of the chat (in NDC)
+const CHAT_LEFT: f32 = -0.97;
+
 impl HUDRenderer {
     pub fn new() -> Self {
 
@@ -55,13 +98,24 @@ impl HUDRenderer {
             aspect_ratio: 1.0,
             rects: Vec::new(),
             base: Vec::new(),
+            dimension: (1, 1),
+            crosshair_config: CrosshairConfig::new(),
+            crosshair: Vec::new(),
             help_menu: HelpMenu::new(&help_menu_data),
             debug_menu: DebugMenu::new(&debug_menu_data),
             show_help: false,
             show_debug: false,
             items_bar: ItemBar::new(),
-            health_bar: HealthBar::new(10, 1.),
+            health_bar: HealthBar::new(MAX_HEALTH, 1.),
+            food_bar: HealthBar::food(MAX_FOOD, 1.),
+            chat_lines: Vec::new(),
+            chat_draft: None,
+            chat: Vec::new(),
+            name_tag_points: Vec::new(),
+            name_tags: Vec::new(),
             inventory_menu: None,
+            menu: None,
+            underwater: false,
         };
 
         hud.add_cross();
@@ -72,22 +126,24 @@ impl HUDRenderer {
 
     /// Adds a cross in the center of the screen
     pub fn add_cross(&mut self) {
-        let w = 0.05;
-        let s = 0.003;
-        self.base.push(RectInstance::new(0., 0., w / 1.5, s, Red));
-        self.base.push(RectInstance::new(0., 0., s / 2.5, w, Red));
+        let config = &self.crosshair_config;
+        self.crosshair = crosshair_quads(self.dimension, config.crosshair_size, config.crosshair_thickness)
+            .iter()
+            .map(|quad| RectInstance::new(quad.center[0], quad.center[1], quad.half_extents[0], quad.half_extents[1], config.crosshair_color))
+            .collect();
+    }
+
+    pub fn set_crosshair_config(&mut self, config: CrosshairConfig) {
+        self.crosshair_config = config;
+        self.add_cross();
+        self.update();
     }
     
     pub fn add_crafty_label(&mut self) {
         let h = 0.4;
         let s = 0.05;
         let x0 = -0.3;
-        self.base.push(RectInstance::new_with_char(x0, h, s, GLChar::C));
-        self.base.push(RectInstance::new_with_char(x0 + 1. * s * 3., h, s, GLChar::R));
-        self.base.push(RectInstance::new_with_char(x0 + 2. * s * 3., h, s, GLChar::A));
-        self.base.push(RectInstance::new_with_char(x0 + 3. * s * 3., h, s, GLChar::F));
-        self.base.push(RectInstance::new_with_char(x0 + 4. * s * 3., h, s, GLChar::T));
-        self.base.push(RectInstance::new_with_char(x0 + 5. * s * 3., h, s, GLChar::Y));
+        self.base.extend(StringRect::instances("CRAFTY", x0, h, s));
     }
 
     /// Add/Remove the help menu
@@ -107,11 +163,22 @@ impl HUDRenderer {
         // We first clone and append the Vec in each menu
         // and then do it again here, maybe we can only do it here ?
         // rects() would return a Vec of ref to append
-        self.rects=self.base.clone();
+        self.rects = Vec::new();
+        // Under the rest of the HUD
+        if self.underwater {
+            self.rects.push(RectInstance::new(0., 0., 1., 1., Color::Custom(UNDERWATER_TINT)));
+        }
+        self.rects.extend(self.base.iter());
+        if self.menu.is_none() {
+            self.rects.extend(self.name_tags.iter());
+            self.rects.extend(self.crosshair.iter());
+        }
 
         if !self.is_inventory_open() {
             self.rects.append(&mut self.items_bar.rects());
             self.rects.append(&mut self.health_bar.rects());
+            self.rects.append(&mut self.food_bar.rects());
+            self.rects.extend(self.chat.iter());
         }
         
         if self.show_help {
@@ -123,6 +190,9 @@ impl HUDRenderer {
         if self.is_inventory_open() {
             self.rects.append(&mut self.inventory_menu.as_mut().unwrap().rects().clone());
         }
+        if let Some(menu) = &self.menu {
+            self.rects.extend(menu.rects().iter());
+        }
     }
 
     pub fn set_debug(&mut self, debug_data: DebugData) {
@@ -134,6 +204,11 @@ impl HUDRenderer {
         &self.rects
     }
     
+    /// Lays out texts of `pixel_size` pixels high for the current window
+    pub fn text_renderer(&self, pixel_size: f32) -> TextRenderer {
+        TextRenderer::new(pixel_size, self.dimension)
+    }
+
     pub fn show_debug(&self) -> bool {
         self.show_debug
     }
@@ -141,38 +216,112 @@ impl HUDRenderer {
     pub fn set_dimension(&mut self, dim: (u32, u32)) {
         println!("dimension={dim:?}");
         self.aspect_ratio = dim.0 as f32 / dim.1 as f32;
+        self.dimension = dim;
+        // The crosshair, the chat and the name tags are sized in pixels
+        self.add_cross();
+        self.layout_chat();
+        self.layout_name_tags();
 
         // Cascade down the aspect ratio to the HUD parts that require it
         self.items_bar.set_aspect_ratio(self.aspect_ratio);
         self.health_bar.set_aspect_ratio(self.aspect_ratio);
+        self.food_bar.set_aspect_ratio(self.aspect_ratio);
         self.inventory_menu.as_mut().map(|mut inv| { inv.set_aspect_ratio(self.aspect_ratio); });
         
         // Update the collection of rectangles
         self.update();
     }
 
-    pub fn set_player_items(&mut self, items: Vec<ItemStack>, selected: usize) {
+    pub fn set_player_items(&mut self, items: Vec<Option<ItemStack>>, selected: usize) {
         self.items_bar.set_items(items, selected);
         self.update();
     }
 
+    pub fn set_underwater(&mut self, underwater: bool) {
+        if self.underwater != underwater {
+            self.underwater = underwater;
+            self.update();
+        }
+    }
+
     pub fn set_health(&mut self, health: &Health) {
         self.health_bar.set_health(health.health());
         self.update();
     }
 
+    pub fn set_food(&mut self, hunger: &Hunger) {
+        self.food_bar.set_health(hunger.food());
+        self.update();
+    }
+
+    /// Shows the names of the other players, centered above these points of the screen (in NDC).
+    /// The HUD is only built again when they changed.
+    pub fn set_name_tags(&mut self, tags: &[(String, [f32; 2])]) {
+        if self.name_tag_points == tags {
+            return;
+        }
+        self.name_tag_points = tags.to_vec();
+        self.layout_name_tags();
+        self.update();
+    }
+
+    fn layout_name_tags(&mut self) {
+        let renderer = self.text_renderer(NAME_TAG_PIXEL_SIZE).with_alignment(Alignment::Center);
+        // The text stands on the point
+        let height = NAME_TAG_PIXEL_SIZE * 2. / self.dimension.1 as f32;
+        self.name_tags = self.name_tag_points
+            .iter()
+            .flat_map(|(name, at)| renderer.layout(&[TextSpan::new(name)], [at[0], at[1] + height]))
+            .collect();
+    }
+
+    /// Shows the recent lines of the chat, and the line being written under them
+    pub fn set_chat(&mut self, chat: &Chat) {
+        self.chat_lines = chat.visible_lines().iter().map(|line| (line.sender.clone(), line.text.clone())).collect();
+        self.chat_draft = chat.draft().map(String::from);
+        self.layout_chat();
+        self.update();
+    }
+
+    /// The rectangles of the chat, from its last line up
+    fn layout_chat(&mut self) {
+        let renderer = self.text_renderer(CHAT_PIXEL_SIZE);
+        let line_height = CHAT_LINE_HEIGHT * 2. / self.dimension.1 as f32;
+        let draft = self.chat_draft.as_ref().map(|draft| format!("> {draft}_"));
+        let lines = self.chat_lines.iter().map(|(sender, text)| (Some(format!("<{sender}> ")), text)).chain(draft.iter().map(|draft| (None, draft)));
+
+        self.chat = Vec::new();
+        let count = self.chat_lines.len() + draft.is_some() as usize;
+        for (i, (sender, text)) in lines.enumerate() {
+            let top = CHAT_BOTTOM + (count - i) as f32 * line_height;
+            let mut spans = Vec::new();
+            if let Some(sender) = &sender {
+                spans.push(TextSpan::new(sender).with_color(Color::LightYellow));
+            }
+            spans.push(TextSpan::new(text));
+            self.chat.extend(renderer.layout(&spans, [CHAT_LEFT, top]));
+        }
+    }
+
     pub fn is_inventory_open(&self) -> bool {
         self.inventory_menu.is_some()
     }
 
-    pub fn open_inventory(&mut self, items: PlayerItems) {
+    pub fn open_inventory(&mut self, items: Inventory) {
         self.inventory_menu = Some(InventoryMenu::new(self.aspect_ratio, items));
         self.update();
     }
 
+    /// Opens the inventory next to the slots of the chest at `at`
+    pub fn open_container(&mut self, items: Inventory, at: Vector3, container: Container) {
+        self.inventory_menu = Some(InventoryMenu::with_container(self.aspect_ratio, items, at, container));
+        self.update();
+    }
+
     /// Close inventory. This function may fail, because there are still items
-    /// in the crafting grid, and we do not want to loose them
-    pub fn close_inventory(&mut self) -> Option<PlayerItems> {
+    /// in the crafting grid, and we do not want to loose them.
+    /// The opened chest, if any, is given back with its position.
+    pub fn close_inventory(&mut self) -> Option<(Inventory, Option<(Vector3, Container)>)> {
         if self.inventory_menu.as_ref().unwrap().can_be_closed_safely() {
             let items = self.inventory_menu.take().unwrap().take_items();
             self.update();
@@ -183,6 +332,32 @@ impl HUDRenderer {
         }        
     }
 
+    /// Shows a menu over the game, or hides it with None
+    pub fn set_menu(&mut self, menu: Option<MenuScreen>) {
+        self.menu = menu;
+        self.update();
+    }
+
+    /// The entry of the menu selected, none without a menu
+    pub fn selected_menu_entry(&self) -> Option<MenuEntry> {
+        self.menu.as_ref().and_then(|menu| menu.selected())
+    }
+
+    /// Selects the entry of the menu under the cursor. Returns true if there is one.
+    pub fn hover_menu(&mut self, x: f32, y: f32) -> bool {
+        let hovered = self.menu.as_mut().is_some_and(|menu| menu.hover(x, y));
+        self.update();
+        hovered
+    }
+
+    /// Moves the selection of the menu down (positive) or up (negative)
+    pub fn move_menu_selection(&mut self, delta: i32) {
+        if let Some(menu) = self.menu.as_mut() {
+            menu.move_selection(delta);
+            self.update();
+        }
+    }
+
     /// If the inventory is open, forward it the event
     pub fn maybe_forward_inventory_event(&mut self, event: InventoryEvent) {
         let status = self.inventory_menu.as_mut().map(|inv| {
@@ -3,21 +3,24 @@ use crate::graphics::inventory_event::InventoryEvent;
 use crate::graphics::inventory_space;
 use crate::graphics::inventory_space::{InventoryPosition, InventoryRect};
 use crate::graphics::rectangle::RectInstance;
-use crate::player_items::PlayerItems;
+use crate::player_items::{Inventory, ItemStack, HOTBAR_SIZE};
 use crate::graphics::update_status::UpdateStatus;
 use crate::graphics::inventory_slot::InventorySlot;
 use winit::event::ElementState;
-use crate::block_kind::Block;
+use crate::block_entity::{Container, CHEST_SLOTS};
 use crate::crafting::{CraftingGrid, CraftingManager};
+use crate::item::Item;
+use crate::primitives::vector::Vector3;
 
 const INVENTORY_NROWS: usize = 4; // the 0th is the item bar
-const INVENTORY_NCOLS: usize = 8;
+const INVENTORY_NCOLS: usize = HOTBAR_SIZE;
+const CONTAINER_NROWS: usize = CHEST_SLOTS / INVENTORY_NCOLS;
 
 pub struct InventoryMenu {
     rects: Vec<RectInstance>,
     aspect_ratio: f32,
     
-    items: PlayerItems,
+    items: Inventory,
     cursor_pos: InventoryPosition,
     /// u, v, w, h of the UI, in NDC coordinates
     ui_rect: (f32, f32, f32, f32),
@@ -25,14 +28,18 @@ pub struct InventoryMenu {
     inventory_slots: [[InventorySlot; INVENTORY_NCOLS]; INVENTORY_NROWS],
     crafting_slots: [[InventorySlot; 3]; 3],
     crafting_output_slot: InventorySlot,
+    container_slots: [[InventorySlot; INVENTORY_NCOLS]; CONTAINER_NROWS],
 
-    carried_item: Option<Block>,
+    /// The item taken from a slot, a tool keeping its wear
+    carried_item: Option<ItemStack>,
     crafting_items: CraftingGrid,
-    crafting_output_items: Option<Block>,
+    crafting_output_items: Option<Item>,
+    /// The opened chest and the position of its cube, shown instead of the crafting grid
+    container: Option<(Vector3, Container)>,
 }
 
 impl InventoryMenu {
-    pub fn new(aspect_ratio: f32, items: PlayerItems) -> Self {
+    pub fn new(aspect_ratio: f32, items: Inventory) -> Self {
         let slot = InventorySlot::new(InventoryPosition::zero(), 1.);
         
         let mut inventory = Self {
@@ -44,16 +51,26 @@ impl InventoryMenu {
             inventory_slots: [[slot; INVENTORY_NCOLS]; INVENTORY_NROWS],
             crafting_slots: [[slot; 3]; 3],
             crafting_output_slot: slot,
+            container_slots: [[slot; INVENTORY_NCOLS]; CONTAINER_NROWS],
 
             carried_item: None,
             crafting_items: [[None; 3]; 3],
             crafting_output_items: None,
+            container: None,
         };
         inventory.update();
 
         inventory
     }
 
+    /// The inventory next to the slots of the chest at `at`
+    pub fn with_container(aspect_ratio: f32, items: Inventory, at: Vector3, container: Container) -> Self {
+        let mut inventory = Self::new(aspect_ratio, items);
+        inventory.container = Some((at, container));
+        inventory.update();
+        inventory
+    }
+
     pub fn set_aspect_ratio(&mut self, aspect_ratio: f32) {
         self.aspect_ratio = aspect_ratio;
         self.update()
@@ -63,8 +80,9 @@ impl InventoryMenu {
         &self.rects
     }
 
-    pub fn take_items(self) -> PlayerItems {
-        self.items
+    /// The items of the player, and the opened chest with its position
+    pub fn take_items(self) -> (Inventory, Option<(Vector3, Container)>) {
+        (self.items, self.container)
     }
 
     /// Inventory can be closed safely if there is no item in the crafting grid
@@ -129,14 +147,26 @@ impl InventoryMenu {
                 }
             }
 
-            // also check for crafting slots
-            for row in 0..3 {
-                for col in 0..3 {
-                    if self.crafting_slots[row][col].is_in(&self.cursor_pos) {
-                        if let None = self.crafting_items[row][col] {
-                            std::mem::swap(&mut self.crafting_items[row][col],
-                                           &mut self.carried_item);
-                            update_craft = true;
+            // or in the chest, whose slots are where the crafting grid is
+            if let Some((_, container)) = &mut self.container {
+                for row in 0..CONTAINER_NROWS {
+                    for col in 0..INVENTORY_NCOLS {
+                        if self.container_slots[row][col].is_in(&self.cursor_pos)
+                            && container.put(row * INVENTORY_NCOLS + col, carried_item) {
+                            self.carried_item = None;
+                        }
+                    }
+                }
+            } else {
+                // also check for crafting slots, which only take blocks
+                for row in 0..3 {
+                    for col in 0..3 {
+                        if self.crafting_slots[row][col].is_in(&self.cursor_pos) {
+                            if let (None, Some(block)) = (self.crafting_items[row][col], carried_item.item.block()) {
+                                self.crafting_items[row][col] = Some(block);
+                                self.carried_item = None;
+                                update_craft = true;
+                            }
                         }
                     }
                 }
@@ -150,27 +180,40 @@ impl InventoryMenu {
                         // grab it
                         if row == 0 {
                             // for item bar
-                            if let Some(block) = self.items.take_bar_item(col) {
-                                self.carried_item = Some(block);
+                            if let Some(item) = self.items.take_bar_item(col) {
+                                self.carried_item = Some(item);
                             }
                         }
                         else {
                             // for inventory
-                            if let Some(block) = self.items.take_inventory_item((row - 1) * INVENTORY_NCOLS + col) {
-                                self.carried_item = Some(block);
+                            if let Some(item) = self.items.take_inventory_item((row - 1) * INVENTORY_NCOLS + col) {
+                                self.carried_item = Some(item);
                             }
                         }
                     }
                 }
             }
 
+            if let Some((_, container)) = &mut self.container {
+                for row in 0..CONTAINER_NROWS {
+                    for col in 0..INVENTORY_NCOLS {
+                        if self.container_slots[row][col].is_in(&self.cursor_pos) {
+                            if let Some(item) = container.take(row * INVENTORY_NCOLS + col) {
+                                self.carried_item = Some(item);
+                            }
+                        }
+                    }
+                }
+                self.update();
+                return UpdateStatus::Update;
+            }
+
             // also check for crafting slots
             for row in 0..3 {
                 for col in 0..3 {
                     if self.crafting_slots[row][col].is_in(&self.cursor_pos) {
-                        if let Some(_) = self.crafting_items[row][col] {
-                            std::mem::swap(&mut self.crafting_items[row][col],
-                                           &mut self.carried_item);
+                        if let Some(block) = self.crafting_items[row][col].take() {
+                            self.carried_item = Some(ItemStack::new(block, 1));
                             update_craft = true;
                         }
                     }
@@ -179,9 +222,8 @@ impl InventoryMenu {
 
             // and for the crafting output
             if self.crafting_output_slot.is_in(&self.cursor_pos) {
-                if let Some(_) = self.crafting_output_items {
-                    std::mem::swap(&mut self.crafting_output_items,
-                                   &mut self.carried_item);
+                if let Some(item) = self.crafting_output_items.take() {
+                    self.carried_item = Some(ItemStack::new(item, 1));
                     // also remove the crafting components !
                     for row in 0..3 {
                         for col in 0..3 {
@@ -223,7 +265,7 @@ impl InventoryMenu {
                         
                     } else {
                         // inventory itself
-                        self.items.get_inventory_item((row - 1) * 8 + col)
+                        self.items.get_inventory_item((row - 1) * INVENTORY_NCOLS + col)
                     };
                     
                     let slot = InventorySlot::new(InventoryPosition::new(margin + col as f32 * (item_size + margin),
@@ -235,6 +277,24 @@ impl InventoryMenu {
                 }
             }
 
+            // draw the slots of the chest instead of the crafting grid
+            if let Some((_, container)) = &self.container {
+                let chesty = 0.55;
+                for row in 0..CONTAINER_NROWS {
+                    for col in 0..INVENTORY_NCOLS {
+                        let slot = InventorySlot::new(InventoryPosition::new(margin + col as f32 * (item_size + margin),
+                                                                             chesty + row as f32 * (item_size + margin)),
+                                                      item_size);
+                        self.container_slots[row][col] = slot;
+
+                        let item = container.get(row * INVENTORY_NCOLS + col);
+                        self.rects.append(&mut slot.rects(&self.ui_rect, item, slot.is_in(&self.cursor_pos)));
+                    }
+                }
+                self.push_carried_item(item_size);
+                return;
+            }
+
             // draw crafting grid
             let craftx = 0.4;
             let crafty = 0.6;
@@ -246,7 +306,7 @@ impl InventoryMenu {
                                                   item_size);
                     self.crafting_slots[row][col] = slot;
 
-                    let item = self.crafting_items[row][col].map(|block| { (block, 1) });
+                    let item = self.crafting_items[row][col].map(|block| ItemStack::new(block, 1));
                     self.rects.append(&mut slot.rects(&self.ui_rect, item, slot.is_in(&self.cursor_pos)));
                 }
             }
@@ -258,22 +318,25 @@ impl InventoryMenu {
                                               item_size);
                 self.crafting_output_slot = slot;
 
-                let item = self.crafting_output_items.map(|block| { (block, 1) });
+                let item = self.crafting_output_items.map(|item| ItemStack::new(item, 1));
                 self.rects.append(&mut slot.rects(&self.ui_rect, item, slot.is_in(&self.cursor_pos)));
             }
 
-            // carried item
-            if let Some(block) = self.carried_item {
-                let (x, y, w, h) = inventory_space::from_ui_to_ndc_rect(&self.ui_rect,
-                                                                        &InventoryRect::new(self.cursor_pos.x,
-                                                                                           self.cursor_pos.y,
-                                                                                           item_size,
-                                                                                           item_size));
-                let mut rect = RectInstance::new_from_corner(x, y, w, h, Red);
-                rect.set_block_id(block as u8 as i8);
-                self.rects.push(rect);
-            }
-            
+            self.push_carried_item(item_size);
+        }
+    }
+
+    /// Draws the carried item under the cursor
+    fn push_carried_item(&mut self, item_size: f32) {
+        if let Some(carried) = self.carried_item {
+            let (x, y, w, h) = inventory_space::from_ui_to_ndc_rect(&self.ui_rect,
+                                                                    &InventoryRect::new(self.cursor_pos.x,
+                                                                                       self.cursor_pos.y,
+                                                                                       item_size,
+                                                                                       item_size));
+            let mut rect = RectInstance::new_from_corner(x, y, w, h, Red);
+            rect.set_item(carried.item);
+            self.rects.push(rect);
         }
     }
 
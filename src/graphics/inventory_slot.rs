@@ -35,9 +35,9 @@ impl InventorySlot {
         rects.push(RectInstance::new_from_corner(x, y, w, h, if hover { EvenLighterGray } else { LighterGray }));
 
         // draw the item as well
-        if let Some((block, count)) = item {
+        if let Some(ItemStack { item, count, .. }) = item {
             let mut rect = RectInstance::new_from_corner(x, y, w, h, LighterGray);
-            rect.set_block_id(block as u8 as i8);
+            rect.set_item(item);
             rects.push(rect);
 
             // and the count
@@ -4,7 +4,8 @@ use crate::graphics::string_rect::StringRect;
 use crate::player_items::ItemStack;
 
 pub struct ItemBar {
-    items: Vec<ItemStack>,
+    /// The slots of the hotbar, some of them empty
+    items: Vec<Option<ItemStack>>,
     selected_item: usize,
     rects: Vec<RectInstance>,
     aspect_ratio: f32,
@@ -22,7 +23,7 @@ impl ItemBar {
     }
 
     /// Sets the items currently showed on the bar
-    pub fn set_items(&mut self, items: Vec<ItemStack>, selected: usize) {
+    pub fn set_items(&mut self, items: Vec<Option<ItemStack>>, selected: usize) {
         self.items = items;
         self.selected_item = selected;
 
@@ -40,7 +41,7 @@ impl ItemBar {
         let mut x0 = -W / 2. + PADDING;
 
         const ITEM_SIDE: f32 = H * 0.9;
-        for (i, &(kind, quantity)) in self.items.iter().enumerate() {
+        for (i, &item) in self.items.iter().enumerate() {
             if i == self.selected_item {
                 // Add an indication that this is the selected item
                 const DX: f32 = 0.015;
@@ -51,11 +52,15 @@ impl ItemBar {
                 rects.push(cube);
             }
 
+            let Some(ItemStack { item: kind, count: quantity, .. }) = item else {
+                x0 += ITEM_SIDE;
+                continue;
+            };
             let mut cube = RectInstance::square_from_corner(
                 x0,
                 BOTTOM - 1. + 2. * PADDING,
                 ITEM_SIDE, self.aspect_ratio, Red);
-            cube.set_block_id(kind as u8 as i8);
+            cube.set_item(kind);
             rects.push(cube);
 
             // And we want to print the number of remaining items
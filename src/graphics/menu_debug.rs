@@ -1,27 +1,54 @@
 use crate::graphics::rectangle::RectInstance;
 use crate::graphics::string_rect::StringRect;
 
+use crate::chunk::ChunkCoord;
 use crate::primitives::position::Position;
 use std::f32::consts::PI;
 
-/// Data in the debug menu
-pub const DEBUG_MENU_DATA: [DebugItem; 4] = [
+/// Data in the debug menu, in the order of the values of `DebugData::values`
+pub const DEBUG_MENU_DATA: [DebugItem; 8] = [
     DebugItem::new("fps:"),
+    DebugItem::new("frame time:"),
     DebugItem::new("coord:"),
     DebugItem::new("rot:"),
+    DebugItem::new("chunk:"),
+    DebugItem::new("loaded chunks:"),
     DebugItem::new("cube rendered:"),
+    DebugItem::new("chunk memory:"),
 ];
 
+/// Offset of the values from their name
+const VALUE_OFFSET: f32 = 0.7;
 
 pub struct DebugData {
     fps: f32,
     pos: Position,
     cube_rendered: usize,
+    /// Duration of the last frame [s]
+    frame_time: f32,
+    /// Chunk of the player
+    chunk: ChunkCoord,
+    loaded_chunks: usize,
+    /// Size of the cubes of the loaded chunks [byte]
+    chunk_memory: usize,
 }
 
 impl DebugData {
     pub fn new(fps: f32, pos: Position, cube_rendered: usize) -> Self {
-        Self { fps, pos: pos.clone(), cube_rendered }
+        Self { fps, pos: pos.clone(), cube_rendered, frame_time: 0., chunk: (0, 0), loaded_chunks: 0, chunk_memory: 0 }
+    }
+
+    pub fn with_frame_time(mut self, frame_time: f32) -> Self {
+        self.frame_time = frame_time;
+        self
+    }
+
+    /// The chunk of the player, and how many chunks are loaded with how much memory
+    pub fn with_chunks(mut self, chunk: ChunkCoord, loaded_chunks: usize, chunk_memory: usize) -> Self {
+        self.chunk = chunk;
+        self.loaded_chunks = loaded_chunks;
+        self.chunk_memory = chunk_memory;
+        self
     }
 
     pub fn fps(&self) -> f32 {
@@ -35,6 +62,20 @@ impl DebugData {
     pub fn cube_rendered(&self) -> usize {
         self.cube_rendered
     }
+
+    /// The text of each line of the menu
+    pub fn values(&self) -> Vec<String> {
+        vec![
+            format!("{:5.1}", self.fps),
+            format!("{:5.1} ms", self.frame_time * 1000.),
+            format!("{:7.3}:{:7.3}:{:7.3}", self.pos.x(), self.pos.y(), self.pos.z()),
+            format!("{:7.3}:{:7.3}", self.pos.yaw() % (2. * PI), self.pos.pitch()),
+            format!("{}:{}", self.chunk.0, self.chunk.1),
+            format!("{}", self.loaded_chunks),
+            format!("{}", self.cube_rendered),
+            format!("{:.1} mb", self.chunk_memory as f32 / (1024. * 1024.)),
+        ]
+    }
 }
 
 /// Item for Debug menu: contain a string to be displayed
@@ -89,7 +130,7 @@ impl DebugMenu {
 
         for item in debug_menu_data.items() {
             StringRect::write_string(u, y, size, &item.element().to_string(), &mut static_part);
-            coord_to_update.push([u + 0.4, y]);
+            coord_to_update.push([u + VALUE_OFFSET, y]);
             y -= 4. * size;
         }
         Self {
@@ -108,17 +149,31 @@ impl DebugMenu {
     {
         self.rects = self.static_part.clone();
 
-        let fps_string = &format!("{:5.1}", debug_data.fps());
-        StringRect::write_string(self.coord_to_update[0][0], self.coord_to_update[0][1], 0.015, fps_string, &mut self.rects);
-
-        let pos_string = &format!("{:7.3}:{:7.3}:{:7.3}", debug_data.pos().x(), debug_data.pos().y(), debug_data.pos().z());
-        StringRect::write_string(self.coord_to_update[1][0], self.coord_to_update[1][1], 0.015, pos_string, &mut self.rects);
-
-        let rot_string = &format!("{:7.3}:{:7.3}", debug_data.pos().yaw() % (2. * PI), debug_data.pos.pitch());
-        StringRect::write_string(self.coord_to_update[2][0], self.coord_to_update[2][1], 0.015, rot_string, &mut self.rects);
+        for (value, [u, v]) in debug_data.values().iter().zip(&self.coord_to_update) {
+            StringRect::write_string(*u, *v, 0.015, value, &mut self.rects);
+        }
+    }
+}
 
-        let cube_string = &format!("{:7}", debug_data.cube_rendered());
-        StringRect::write_string(self.coord_to_update[3][0] + 0.3, self.coord_to_update[3][1], 0.015, cube_string, &mut self.rects);
+#[cfg(test)]
+mod tests {
+    use crate::graphics::menu_debug::{DebugData, DEBUG_MENU_DATA};
+    use crate::primitives::position::Position;
+    use crate::primitives::vector::Vector3;
+
+    #[test]
+    fn test_debug_values() {
+        let data = DebugData::new(59.94, Position::new(Vector3::new(1., -2.5, 3.), 0.5, 0.), 1200)
+            .with_frame_time(0.0167)
+            .with_chunks((-8, 16), 25, 3 * 1024 * 1024);
+        let values = data.values();
+        assert_eq!(values.len(), DEBUG_MENU_DATA.len());
+        assert_eq!(values[0], " 59.9");
+        assert_eq!(values[1], " 16.7 ms");
+        assert_eq!(values[4], "-8:16");
+        assert_eq!(values[5], "25");
+        assert_eq!(values[6], "1200");
+        assert_eq!(values[7], "3.0 mb");
     }
 }
 
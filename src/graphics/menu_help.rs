@@ -5,17 +5,25 @@ use crate::graphics::rectangle::RectInstance;
 use crate::graphics::string_rect::StringRect;
 
 /// Data in the help menu
-pub const HELP_MENU_DATA: [HelpMenuItem;9] = [
+pub const HELP_MENU_DATA: [HelpMenuItem;17] = [
     //HelpMenuItem{command: &str "move forward",key: String::from("z")},
     HelpMenuItem::new("move forward","z"),
     HelpMenuItem::new("move backward","s"),
     HelpMenuItem::new("move left","q"),
     HelpMenuItem::new("move right","d"),
     HelpMenuItem::new("jump","space"),
+    HelpMenuItem::new("sprint","ctrl"),
+    HelpMenuItem::new("sneak","shift"),
+    HelpMenuItem::new("fly","space x2"),
+    HelpMenuItem::new("zoom","c"),
     HelpMenuItem::new("debug menu","f3"),
     HelpMenuItem::new("help menu","f12"),
     HelpMenuItem::new("save map","f10"),
     HelpMenuItem::new("fullscreen","f11"),
+    HelpMenuItem::new("game mode","f4"),
+    HelpMenuItem::new("third person","f5"),
+    HelpMenuItem::new("pause","escape"),
+    HelpMenuItem::new("chat","t"),
 ];
 
 
@@ -0,0 +1,155 @@
+use crate::game_state::MenuEntry;
+use crate::graphics::color::Color;
+use crate::graphics::rectangle::RectInstance;
+use crate::graphics::string_rect::StringRect;
+
+/// Size of the letters of the title and of the entries
+const TITLE_SIZE: f32 = 0.04;
+const ENTRY_SIZE: f32 = 0.02;
+/// Size of the letters of the lines below the first one in an entry
+const DETAIL_SIZE: f32 = 0.015;
+/// Height of the title, and of the first entry
+const TITLE_V: f32 = 0.6;
+const FIRST_ENTRY_V: f32 = 0.3;
+/// Distance between the centers of two entries
+const ENTRY_SPACING: f32 = 0.16;
+/// Distance between the lines of an entry
+const LINE_SPACING: f32 = 0.05;
+/// Half size of the button behind each entry
+const BUTTON_HALF_WIDTH: f32 = 0.8;
+const BUTTON_HALF_HEIGHT: f32 = 0.065;
+
+/// Darkens the world behind the menu
+const BACKGROUND: [f32; 4] = [0., 0., 0., 0.5];
+
+/// A full screen menu: a title above a column of buttons, one of them selected.
+/// The entries are chosen with the mouse, or with the arrows and enter.
+/// The label of an entry can have a second line (after a `\n`), written smaller.
+pub struct MenuScreen {
+    title: String,
+    entries: Vec<(MenuEntry, String)>,
+    selected: usize,
+    rects: Vec<RectInstance>,
+}
+
+impl MenuScreen {
+    /// A menu with the entries and their labels, the first one selected
+    pub fn new(title: &str, entries: Vec<(MenuEntry, String)>) -> Self {
+        let mut menu = Self { title: title.to_string(), entries, selected: 0, rects: Vec::new() };
+        menu.build();
+        menu
+    }
+
+    /// Height of the center of the entry
+    fn entry_v(index: usize) -> f32 {
+        FIRST_ENTRY_V - index as f32 * ENTRY_SPACING
+    }
+
+    fn build(&mut self) {
+        self.rects = vec![RectInstance::new(0., 0., 1., 1., Color::Custom(BACKGROUND))];
+        StringRect::write_string_centered(TITLE_V, TITLE_SIZE, &self.title, &mut self.rects);
+        for (index, (_, label)) in self.entries.iter().enumerate() {
+            let v = Self::entry_v(index);
+            let color = if index == self.selected { Color::LightYellow } else { Color::LightGray };
+            self.rects.push(RectInstance::new(0., v, BUTTON_HALF_WIDTH, BUTTON_HALF_HEIGHT, color));
+            let n_lines = label.lines().count();
+            for (i, line) in label.lines().enumerate() {
+                let line_v = v + ((n_lines - 1) as f32 / 2. - i as f32) * LINE_SPACING;
+                let size = if i == 0 { ENTRY_SIZE } else { DETAIL_SIZE };
+                StringRect::write_string_centered(line_v, size, &line.to_string(), &mut self.rects);
+            }
+        }
+    }
+
+    pub fn rects(&self) -> &Vec<RectInstance> {
+        &self.rects
+    }
+
+    pub fn selected(&self) -> Option<MenuEntry> {
+        self.entries.get(self.selected).map(|(entry, _)| *entry)
+    }
+
+    /// Selects the entry, if it is in the menu
+    pub fn select(&mut self, entry: MenuEntry) {
+        if let Some(index) = self.entries.iter().position(|(e, _)| *e == entry) {
+            self.selected = index;
+            self.build();
+        }
+    }
+
+    /// Index of the button under the point of the screen (from -1 to 1 on both axes)
+    fn entry_at(&self, x: f32, y: f32) -> Option<usize> {
+        if x.abs() > BUTTON_HALF_WIDTH {
+            return None;
+        }
+        (0..self.entries.len()).find(|index| (y - Self::entry_v(*index)).abs() <= BUTTON_HALF_HEIGHT)
+    }
+
+    /// Selects the button under the cursor. Returns true if there is one.
+    pub fn hover(&mut self, x: f32, y: f32) -> bool {
+        match self.entry_at(x, y) {
+            Some(index) => {
+                if index != self.selected {
+                    self.selected = index;
+                    self.build();
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Selects the next entry (positive) or a previous one (negative), going around at the ends
+    pub fn move_selection(&mut self, delta: i32) {
+        if self.entries.is_empty() {
+            return;
+        }
+        let n = self.entries.len() as i32;
+        self.selected = (self.selected as i32 + delta).rem_euclid(n) as usize;
+        self.build();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game_state::MenuEntry;
+    use crate::graphics::menu_screen::MenuScreen;
+
+    fn pause_menu() -> MenuScreen {
+        MenuScreen::new("paused", vec![
+            (MenuEntry::Resume, String::from("resume")),
+            (MenuEntry::Settings, String::from("settings")),
+            (MenuEntry::SaveAndQuit, String::from("save and quit")),
+        ])
+    }
+
+    #[test]
+    fn test_keyboard_selection() {
+        let mut menu = pause_menu();
+        assert_eq!(menu.selected(), Some(MenuEntry::Resume));
+        menu.move_selection(1);
+        assert_eq!(menu.selected(), Some(MenuEntry::Settings));
+        // Going around
+        menu.move_selection(-2);
+        assert_eq!(menu.selected(), Some(MenuEntry::SaveAndQuit));
+        menu.move_selection(1);
+        assert_eq!(menu.selected(), Some(MenuEntry::Resume));
+
+        menu.select(MenuEntry::SaveAndQuit);
+        assert_eq!(menu.selected(), Some(MenuEntry::SaveAndQuit));
+        menu.select(MenuEntry::Play);
+        assert_eq!(menu.selected(), Some(MenuEntry::SaveAndQuit));
+    }
+
+    #[test]
+    fn test_mouse_selection() {
+        let mut menu = pause_menu();
+        assert!(menu.hover(0.1, MenuScreen::entry_v(2)));
+        assert_eq!(menu.selected(), Some(MenuEntry::SaveAndQuit));
+
+        // Besides the buttons, the selection stays
+        assert!(!menu.hover(0.9, MenuScreen::entry_v(1)));
+        assert!(!menu.hover(0., 0.9));
+        assert_eq!(menu.selected(), Some(MenuEntry::SaveAndQuit));
+    }
+}
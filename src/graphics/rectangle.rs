@@ -1,13 +1,15 @@
 use glium::implement_vertex;
 use crate::graphics::color::Color;
 use crate::graphics::font::GLChar;
+use crate::item::Item;
+use crate::texture_atlas::{BlockFace, TextureAtlas};
 
 pub const RECT_VERTEX_SHADER: &str = r"
     #version 330 core
     // Attributes of each vertex
     in vec3 position;
     in vec2 tex_coords;
-    in int block_id;
+    in float block_layer;
 
     // Atributes of each tile
     in mat4 transformation;
@@ -20,7 +22,7 @@ pub const RECT_VERTEX_SHADER: &str = r"
     out vec2 tex_coords_s;
     out vec2 font_coords_s;
     flat out int is_font_s;
-    flat out int block_id_s;
+    flat out float block_layer_s;
 
     void main()
     {
@@ -29,7 +31,7 @@ pub const RECT_VERTEX_SHADER: &str = r"
        tex_coords_s = tex_coords;
        font_coords_s = font_coords;
        is_font_s = is_font;
-       block_id_s = block_id;
+       block_layer_s = block_layer;
     }
     ";
 
@@ -41,7 +43,7 @@ pub const RECT_FRAGMENT_SHADER: &str = r"
     in vec2 tex_coords_s;
     in vec2 font_coords_s;
     flat in int is_font_s;
-    flat in int block_id_s;
+    flat in float block_layer_s;
 
     // Contains all the unicode characters
     uniform sampler2D font_atlas;
@@ -61,10 +63,10 @@ pub const RECT_FRAGMENT_SHADER: &str = r"
             // * `font_coords_s`: coordinates of the bottom-left corner of the character
             // * `tex_coords_s` : coordinates within the char rect
             // * `font_offsets` : dimensions of each character 
-            FragColor = texture(font_atlas, vec2(font_coords_s[0] + font_offsets[0] * tex_coords_s[0], font_coords_s[1] + font_offsets[1] * tex_coords_s[1]));
-        } else if (block_id_s >= 0) {
-            int idx = block_id_s * 3;
-            FragColor = texture(textures, vec3(tex_coords_s, float(idx)));
+            // The glyphs are white in the atlas, so that they take the color of the tile
+            FragColor = color_s * texture(font_atlas, vec2(font_coords_s[0] + font_offsets[0] * tex_coords_s[0], font_coords_s[1] + font_offsets[1] * tex_coords_s[1]));
+        } else if (block_layer_s >= 0.0) {
+            FragColor = texture(textures, vec3(tex_coords_s, block_layer_s));
         } else {
             // If the tile is not a font, then we just use the background color.
             FragColor = color_s;
@@ -90,6 +92,22 @@ pub const RECT_VERTICES: [RectVertex; 6] = [
     RectVertex { position: [-1.0, -1.0, 0.], tex_coords: [0., 0.] },
 ];
 
+/// A quad of the HUD, given by its center and its half extents in NDC
+#[derive(Debug, PartialEq)]
+pub struct HudQuad {
+    pub center: [f32; 2],
+    pub half_extents: [f32; 2],
+}
+
+impl HudQuad {
+    /// Corners of the quad, counter-clockwise from the bottom left one
+    pub fn corners(&self) -> [[f32; 2]; 4] {
+        let [u, v] = self.center;
+        let [w, h] = self.half_extents;
+        [[u - w, v - h], [u + w, v - h], [u + w, v + h], [u - w, v + h]]
+    }
+}
+
 /// Holds the model of 1 tile
 #[derive(Copy, Clone)]
 pub struct RectInstance {
@@ -100,12 +118,12 @@ pub struct RectInstance {
     is_font: u8,
     /// Coordinates of the font in the texture atlas
     font_coords: [f32; 2],
-    /// The id of the block whose' side texture will be drawn
+    /// Layer of the block texture drawn on the tile (see `TextureAtlas`)
     /// If the tile should not have a cube texture, then just put -1
-    block_id: i8
+    block_layer: f32
 }
 
-implement_vertex!(RectInstance, transformation, color, is_font, font_coords, block_id);
+implement_vertex!(RectInstance, transformation, color, is_font, font_coords, block_layer);
 
 impl RectInstance {
     /// Create a new rectangle
@@ -132,7 +150,7 @@ impl RectInstance {
             color: c.rgba(),
             is_font: false as u8,
             font_coords: [0., 0.],
-            block_id: -1
+            block_layer: -1.
         }
     }
     
@@ -156,15 +174,39 @@ impl RectInstance {
                 [0.0, 0.0, 1.0, 0.0],
                 [  u,   v, 0.0, 1.0]
             ],
-            color: [0.,0.,0.,0.],
+            color: Color::White.rgba(),
             is_font: true as u8,
             font_coords: c.get_index(),
-            block_id: -1
+            block_layer: -1.
         }
     }
 
-    pub fn set_block_id(&mut self, block_id: i8) {
-        self.block_id = block_id;
+    /// Creates a new rectangle that draws a given character, stretched over the quad
+    pub fn new_glyph(quad: &HudQuad, c: GLChar) -> Self {
+        let [u, v] = quad.center;
+        let [w, h] = quad.half_extents;
+        Self {
+            transformation: [
+                [  w, 0.0, 0.0, 0.0],
+                [0.0,   h, 0.0, 0.0],
+                [0.0, 0.0, 1.0, 0.0],
+                [  u,   v, 0.0, 1.0]
+            ],
+            color: Color::White.rgba(),
+            is_font: true as u8,
+            font_coords: c.get_index(),
+            block_layer: -1.
+        }
+    }
+
+    /// Draws the side texture of the item on the rectangle
+    pub fn set_item(&mut self, item: Item) {
+        self.block_layer = TextureAtlas::get().item_layer(item, BlockFace::Side);
+    }
+
+    /// Color of the rectangle, or of the character it draws
+    pub fn set_color(&mut self, c: Color) {
+        self.color = c.rgba();
     }
     
 }
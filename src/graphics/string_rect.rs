@@ -1,5 +1,6 @@
 use crate::graphics::font::GLChar;
 use crate::graphics::rectangle::RectInstance;
+use crate::graphics::text_layout::{layout_text, Anchor};
 
 
 
@@ -9,9 +10,26 @@ pub struct StringRect {
 }
 
 impl StringRect {
-    pub fn new(string: &String, u:f32, v:f32, size: f32) -> Self {
-        let mut rects=Vec::new();
-        StringRect::write_string(u, v, size, string, &mut rects);
+    pub fn new(string: &str, u:f32, v:f32, size: f32) -> Self {
+        Self {
+            rects: StringRect::instances(string, u, v, size)
+        }
+    }
+
+    /// The instances of the characters of the text, the first one centered on (u, v), each one `size` high
+    pub fn instances(text: &str, u: f32, v: f32, size: f32) -> Vec<RectInstance> {
+        let mut rects = Vec::new();
+        StringRect::write_string(u, v, size, text, &mut rects);
+        rects
+    }
+
+    /// Creates a string of `pixel_size` pixels high, attached to a point of the screen.
+    /// It must be created again when the size of the window changes.
+    pub fn new_anchored(string: &str, pixel_size: f32, anchor: Anchor, margin: f32, screen: (u32, u32)) -> Self {
+        let rects = layout_text(string, pixel_size, anchor, margin, screen)
+            .iter()
+            .map(|(c, quad)| RectInstance::new_glyph(quad, GLChar::from_char(*c)))
+            .collect();
         Self {
             rects
         }
@@ -23,17 +41,17 @@ impl StringRect {
 
     /// Transform each character of a String into a RectVertexAttr and add them to a Vec
     /// Return the u position of the last character 
-    pub fn write_string(u: f32, v: f32, w: f32, st: &String, rects: &mut Vec<RectInstance>) -> f32 {
+    pub fn write_string(u: f32, v: f32, w: f32, st: &str, rects: &mut Vec<RectInstance>) -> f32 {
         // This function could probably be moved somewhere else
         for (i,c) in st.chars().enumerate() {
             if c== ' ' {continue}
             rects.push(RectInstance::new_with_char(u + i as f32 * w * 3., v, w, GLChar::from_char(c)));
         }
-        u + st.len() as f32 * w * 3.
+        u + st.chars().count() as f32 * w * 3.
     }
 
-    pub fn write_string_centered(v: f32, w: f32, st: &String, rects: &mut Vec<RectInstance>) -> f32 {
-        StringRect::write_string(-3. * w*(st.len() as f32 -1.)/2., v, w, st, rects)
+    pub fn write_string_centered(v: f32, w: f32, st: &str, rects: &mut Vec<RectInstance>) -> f32 {
+        StringRect::write_string(-3. * w*(st.chars().count() as f32 -1.)/2., v, w, st, rects)
     }
 
 }
\ No newline at end of file
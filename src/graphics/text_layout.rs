@@ -0,0 +1,118 @@
+use crate::graphics::rectangle::HudQuad;
+
+/// Width of a glyph, relative to its height (same ratio as the characters in the font atlas)
+pub const GLYPH_ASPECT: f32 = 0.7;
+
+/// Point of the screen a text is attached to
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    /// Position of the anchor in NDC
+    fn position(&self) -> [f32; 2] {
+        match self {
+            Anchor::TopLeft => [-1., 1.],
+            Anchor::Top => [0., 1.],
+            Anchor::TopRight => [1., 1.],
+            Anchor::Left => [-1., 0.],
+            Anchor::Center => [0., 0.],
+            Anchor::Right => [1., 0.],
+            Anchor::BottomLeft => [-1., -1.],
+            Anchor::Bottom => [0., -1.],
+            Anchor::BottomRight => [1., -1.],
+        }
+    }
+
+    /// Which part of the text is on the anchor: 0 for its left (or bottom) side, 1 for its right (or top) side
+    fn alignment(&self) -> [f32; 2] {
+        let [u, v] = self.position();
+        [(u + 1.) / 2., (v + 1.) / 2.]
+    }
+}
+
+/// Computes the quads of the glyphs of a line of text, for a window of `screen` pixels.
+/// The glyphs are `pixel_size` pixels high whatever the resolution, and the text is placed so that
+/// the matching corner (or side) of the text touches the `anchor`, `margin` pixels away from the edges.
+/// Spaces advance the text, but have no quad.
+pub fn layout_text(text: &str, pixel_size: f32, anchor: Anchor, margin: f32, screen: (u32, u32)) -> Vec<(char, HudQuad)> {
+    // One pixel in NDC, which spans 2 units over the whole window
+    let px = 2. / screen.0 as f32;
+    let py = 2. / screen.1 as f32;
+
+    let glyph_w = pixel_size * GLYPH_ASPECT * px;
+    let glyph_h = pixel_size * py;
+    let text_w = glyph_w * text.chars().count() as f32;
+
+    // Bottom left corner of the text
+    let [au, av] = anchor.position();
+    let [align_u, align_v] = anchor.alignment();
+    let u0 = au - align_u * text_w + (1. - 2. * align_u) * margin * px;
+    let v0 = av - align_v * glyph_h + (1. - 2. * align_v) * margin * py;
+
+    text.chars()
+        .enumerate()
+        .filter(|(_, c)| *c != ' ')
+        .map(|(i, c)| {
+            let quad = HudQuad {
+                center: [u0 + (i as f32 + 0.5) * glyph_w, v0 + glyph_h / 2.],
+                half_extents: [glyph_w / 2., glyph_h / 2.],
+            };
+            (c, quad)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graphics::text_layout::{layout_text, Anchor};
+
+    #[test]
+    fn test_top_right_text_ends_on_the_right_edge() {
+        let screen = (1280, 720);
+        let quads = layout_text("fps:60", 20., Anchor::TopRight, 0., screen);
+        assert_eq!(quads.len(), 6);
+
+        let (c, last) = quads.last().unwrap();
+        assert_eq!(*c, '0');
+        let right = last.center[0] + last.half_extents[0];
+        let top = last.center[1] + last.half_extents[1];
+        assert!((right - 1.).abs() < 1e-5);
+        assert!((top - 1.).abs() < 1e-5);
+
+        // 20 pixels high, whatever the window
+        assert!((last.half_extents[1] * 2. * screen.1 as f32 / 2. - 20.).abs() < 1e-3);
+
+        // With a margin, the text moves inside the window by this many pixels
+        let quads = layout_text("fps:60", 20., Anchor::TopRight, 10., screen);
+        let (_, last) = quads.last().unwrap();
+        let right_px = (1. - (last.center[0] + last.half_extents[0])) * screen.0 as f32 / 2.;
+        assert!((right_px - 10.).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_doubling_pixel_size_doubles_glyphs() {
+        let screen = (800, 600);
+        let small = layout_text("ab c", 16., Anchor::BottomLeft, 0., screen);
+        let large = layout_text("ab c", 32., Anchor::BottomLeft, 0., screen);
+        // The space has no quad
+        assert_eq!(small.len(), 3);
+
+        for ((_, s), (_, l)) in small.iter().zip(large.iter()) {
+            assert!((l.half_extents[0] - 2. * s.half_extents[0]).abs() < 1e-6);
+            assert!((l.half_extents[1] - 2. * s.half_extents[1]).abs() < 1e-6);
+        }
+        // Anchored on the bottom left corner
+        let first = small[0].1.corners()[0];
+        assert!((first[0] + 1.).abs() < 1e-6 && (first[1] + 1.).abs() < 1e-6);
+    }
+}
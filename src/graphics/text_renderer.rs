@@ -0,0 +1,246 @@
+use crate::graphics::color::Color;
+use crate::graphics::font::GLChar;
+use crate::graphics::rectangle::{HudQuad, RectInstance};
+use crate::graphics::text_layout::GLYPH_ASPECT;
+
+/// Distance between the tops of two lines, relative to the height of the highest glyph of the first one
+const LINE_SPACING: f32 = 1.3;
+
+/// A part of a text written with the same color and size
+#[derive(Clone, Copy)]
+pub struct TextSpan<'a> {
+    pub text: &'a str,
+    pub color: Color,
+    /// Size of the glyphs, relative to the pixel size of the renderer
+    pub scale: f32,
+}
+
+impl<'a> TextSpan<'a> {
+    /// White text, at the size of the renderer
+    pub fn new(text: &'a str) -> Self {
+        Self { text, color: Color::White, scale: 1. }
+    }
+
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+}
+
+/// Side of the text block the lines are aligned on
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Alignment {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+/// A character of a span, with its style
+#[derive(Clone, Copy)]
+struct StyledChar {
+    c: char,
+    color: Color,
+    /// Height of the glyph [pixel]
+    height: f32,
+}
+
+impl StyledChar {
+    /// Advance of the glyph [pixel]
+    fn width(&self) -> f32 {
+        self.height * GLYPH_ASPECT
+    }
+}
+
+fn width(chars: &[StyledChar]) -> f32 {
+    chars.iter().map(StyledChar::width).sum()
+}
+
+/// Lays out texts made of several spans into glyphs for the HUD and the menus.
+/// The glyphs of a text are instances of the rectangles of the HUD, so that they are drawn
+/// with all the other rectangles in a single draw call.
+pub struct TextRenderer {
+    /// Height of the glyphs of scale 1 [pixel]
+    pixel_size: f32,
+    alignment: Alignment,
+    /// The lines are wrapped between two words so that they are not wider [pixel]
+    max_width: Option<f32>,
+    /// Size of the window [pixel]
+    screen: (u32, u32),
+}
+
+impl TextRenderer {
+    /// Left aligned text without wrapping, for a window of `screen` pixels
+    pub fn new(pixel_size: f32, screen: (u32, u32)) -> Self {
+        Self { pixel_size, alignment: Alignment::default(), max_width: None, screen }
+    }
+
+    pub fn with_alignment(mut self, alignment: Alignment) -> Self {
+        self.alignment = alignment;
+        self
+    }
+
+    /// Wraps the lines wider than `max_width` [pixel]. A word wider than this stays alone on its line.
+    pub fn with_max_width(mut self, max_width: f32) -> Self {
+        self.max_width = Some(max_width);
+        self
+    }
+
+    /// Splits the spans into lines, at each `\n` and wherever a line would be too wide.
+    /// The spaces at the end of a wrapped line are dropped.
+    fn lines(&self, spans: &[TextSpan]) -> Vec<Vec<StyledChar>> {
+        let mut lines = Vec::new();
+        let mut line: Vec<StyledChar> = Vec::new();
+        let mut word: Vec<StyledChar> = Vec::new();
+        // The spaces written after the last word of the line
+        let mut spaces: Vec<StyledChar> = Vec::new();
+
+        let end_word = |line: &mut Vec<StyledChar>, word: &mut Vec<StyledChar>, spaces: &mut Vec<StyledChar>, lines: &mut Vec<Vec<StyledChar>>| {
+            if word.is_empty() {
+                return;
+            }
+            let too_wide = self.max_width.is_some_and(|max| width(line) + width(spaces) + width(word) > max);
+            if too_wide && !line.is_empty() {
+                lines.push(std::mem::take(line));
+                spaces.clear();
+            }
+            line.append(spaces);
+            line.append(word);
+        };
+
+        for span in spans {
+            for c in span.text.chars() {
+                let styled = StyledChar { c, color: span.color, height: self.pixel_size * span.scale };
+                match c {
+                    '\n' => {
+                        end_word(&mut line, &mut word, &mut spaces, &mut lines);
+                        line.append(&mut spaces);
+                        lines.push(std::mem::take(&mut line));
+                    }
+                    ' ' => {
+                        end_word(&mut line, &mut word, &mut spaces, &mut lines);
+                        spaces.push(styled);
+                    }
+                    _ => word.push(styled),
+                }
+            }
+        }
+        end_word(&mut line, &mut word, &mut spaces, &mut lines);
+        line.append(&mut spaces);
+        lines.push(line);
+        lines
+    }
+
+    /// The glyphs of the text with their quad and color, see `layout`
+    fn glyphs(&self, spans: &[TextSpan], origin: [f32; 2]) -> Vec<(char, HudQuad, Color)> {
+        // One pixel in NDC, which spans 2 units over the whole window
+        let px = 2. / self.screen.0 as f32;
+        let py = 2. / self.screen.1 as f32;
+
+        let mut glyphs = Vec::new();
+        // Top of the line [pixel below the origin]
+        let mut top = 0.;
+        for line in self.lines(spans) {
+            let height = line.iter().map(|c| c.height).fold(self.pixel_size, f32::max);
+            let line_width = width(&line);
+            let mut x = match self.alignment {
+                Alignment::Left => 0.,
+                Alignment::Center => -line_width / 2.,
+                Alignment::Right => -line_width,
+            };
+            for styled in line {
+                let w = styled.width();
+                if styled.c != ' ' {
+                    // The glyphs of different sizes share the bottom of the line
+                    let bottom = top + height;
+                    let quad = HudQuad {
+                        center: [origin[0] + (x + w / 2.) * px, origin[1] - (bottom - styled.height / 2.) * py],
+                        half_extents: [w / 2. * px, styled.height / 2. * py],
+                    };
+                    glyphs.push((styled.c, quad, styled.color));
+                }
+                x += w;
+            }
+            top += height * LINE_SPACING;
+        }
+        glyphs
+    }
+
+    /// The rectangles drawing the spans, one after the other.
+    /// The `origin` (in NDC) is on the top of the first line: on its left side, its middle or its right side
+    /// depending on the alignment. The rectangles are added to the ones of the HUD.
+    pub fn layout(&self, spans: &[TextSpan], origin: [f32; 2]) -> Vec<RectInstance> {
+        self.glyphs(spans, origin)
+            .iter()
+            .map(|(c, quad, color)| {
+                let mut rect = RectInstance::new_glyph(quad, GLChar::from_char(*c));
+                rect.set_color(*color);
+                rect
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graphics::color::Color;
+    use crate::graphics::text_layout::GLYPH_ASPECT;
+    use crate::graphics::text_renderer::{Alignment, TextRenderer, TextSpan};
+
+    /// A window where a pixel is 0.01 in NDC
+    const SCREEN: (u32, u32) = (200, 200);
+
+    #[test]
+    fn test_wrapping_between_words() {
+        // Each glyph is 7 pixels wide
+        let renderer = TextRenderer::new(10., SCREEN).with_max_width(7. * 9.);
+        let lines = renderer.lines(&[TextSpan::new("one two three\nfour")]);
+        let text: Vec<String> = lines.iter().map(|line| line.iter().map(|c| c.c).collect()).collect();
+        assert_eq!(text, vec!["one two", "three", "four"]);
+
+        // Without a maximum width, only the new lines split the text
+        let lines = TextRenderer::new(10., SCREEN).lines(&[TextSpan::new("one two three")]);
+        assert_eq!(lines.len(), 1);
+    }
+
+    #[test]
+    fn test_spans_and_alignment() {
+        let spans = [TextSpan::new("hp "), TextSpan::new("20").with_color(Color::Red).with_scale(2.)];
+        let renderer = TextRenderer::new(10., SCREEN).with_alignment(Alignment::Right);
+        let glyphs = renderer.glyphs(&spans, [0.5, 0.5]);
+
+        // The space has no glyph
+        assert_eq!(glyphs.len(), 4);
+        let (c, quad, color) = &glyphs[3];
+        assert_eq!(*c, '0');
+        assert_eq!(color.rgba(), Color::Red.rgba());
+        assert!((quad.half_extents[1] - 0.1).abs() < 1e-5);
+        assert!((quad.half_extents[0] - 0.1 * GLYPH_ASPECT).abs() < 1e-5);
+
+        // Right aligned on the origin, and the glyphs share the bottom of the line
+        let right = quad.center[0] + quad.half_extents[0];
+        assert!((right - 0.5).abs() < 1e-5);
+        let small = glyphs[0].1.corners()[0];
+        let large = quad.corners()[0];
+        assert!((small[1] - large[1]).abs() < 1e-5);
+        assert!((quad.center[1] + quad.half_extents[1] - 0.5).abs() < 1e-5);
+        assert_eq!(glyphs[0].2.rgba(), Color::White.rgba());
+    }
+
+    #[test]
+    fn test_centered_lines() {
+        let renderer = TextRenderer::new(10., SCREEN).with_alignment(Alignment::Center);
+        let glyphs = renderer.glyphs(&[TextSpan::new("ab\nabcd")], [0., 0.]);
+        let left = |i: usize| glyphs[i].1.corners()[0][0];
+        let right = |i: usize| glyphs[i].1.corners()[1][0];
+        assert!((left(0) + right(1)).abs() < 1e-5);
+        assert!((left(2) + right(5)).abs() < 1e-5);
+        // The second line is below the first one
+        assert!(glyphs[2].1.center[1] < glyphs[0].1.center[1]);
+    }
+}
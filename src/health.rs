@@ -1,3 +1,21 @@
+/// Health of a player when it spawns
+pub const MAX_HEALTH: u8 = 10;
+
+/// Fastest landing [cube/s] that does not hurt, reached after a fall of about 3 cubes
+pub const FALL_DAMAGE_THRESHOLD: f32 = 11.;
+/// Health lost for each cube/s of landing speed above the threshold
+pub const FALL_DAMAGE_PER_SPEED: f32 = 0.5;
+
+/// Health lost when landing at this speed [cube/s]
+pub fn fall_damage(landing_speed: f32) -> u8 {
+    let excess = landing_speed - FALL_DAMAGE_THRESHOLD;
+    if excess <= 0. {
+        0
+    } else {
+        (excess * FALL_DAMAGE_PER_SPEED).ceil().min(u8::MAX as f32) as u8
+    }
+}
+
 pub struct Health {
     health: u8
 }
@@ -20,4 +38,39 @@ impl Health {
         { self.health - strength }
         else { 0 };
     }
+
+    /// Gives back health points, up to `MAX_HEALTH`
+    pub fn heal(&mut self, amount: u8) {
+        self.health = self.health.saturating_add(amount).min(MAX_HEALTH);
+    }
+
+    /// Back to full health, after a respawn
+    pub fn reset(&mut self) {
+        self.health = MAX_HEALTH;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::health::{fall_damage, Health, FALL_DAMAGE_THRESHOLD, MAX_HEALTH};
+
+    #[test]
+    fn test_fall_damage() {
+        assert_eq!(fall_damage(5.), 0);
+        assert_eq!(fall_damage(FALL_DAMAGE_THRESHOLD), 0);
+        assert_eq!(fall_damage(FALL_DAMAGE_THRESHOLD + 0.5), 1);
+        assert!(fall_damage(20.) < fall_damage(30.));
+
+        // A fall from the top of the world is deadly
+        let mut health = Health::new(MAX_HEALTH);
+        health.damage(fall_damage(40.));
+        assert!(!health.alive());
+        health.reset();
+        assert_eq!(health.health(), MAX_HEALTH);
+        health.damage(3);
+        health.heal(1);
+        assert_eq!(health.health(), MAX_HEALTH - 2);
+        health.heal(5);
+        assert_eq!(health.health(), MAX_HEALTH);
+    }
 }
@@ -0,0 +1,138 @@
+use crate::health::MAX_HEALTH;
+
+/// Food level of a player when it spawns
+pub const MAX_FOOD: u8 = 10;
+/// Exhaustion costing one point of food
+const EXHAUSTION_PER_FOOD: f32 = 4.;
+/// Exhaustion for each cube run while sprinting
+pub const SPRINT_EXHAUSTION: f32 = 0.1;
+/// Exhaustion of a jump
+pub const JUMP_EXHAUSTION: f32 = 0.2;
+/// Exhaustion of each health point regained
+const REGENERATION_EXHAUSTION: f32 = 3.;
+/// Time [s] between two health points regained with a full food bar, or lost with an empty one
+pub const HEALTH_PERIOD: f32 = 4.;
+/// Health lost by a starving player at each period
+pub const STARVATION_DAMAGE: u8 = 1;
+
+/// What the food level does to the health, see `Hunger::step`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HungerEffect {
+    None,
+    /// The player gets a health point back
+    Regenerate,
+    /// The player loses `STARVATION_DAMAGE`
+    Starve,
+}
+
+/// Food level of a player. Running and jumping exhaust the player, which empties the food bar;
+/// eating fills it. A full bar heals the player, an empty one hurts it.
+pub struct Hunger {
+    food: u8,
+    /// Exhaustion gathered since the last point of food lost
+    exhaustion: f32,
+    /// Time [s] since the last health point regained or lost
+    timer: f32,
+}
+
+impl Hunger {
+    pub fn new() -> Self {
+        Self { food: MAX_FOOD, exhaustion: 0., timer: 0. }
+    }
+
+    pub fn food(&self) -> u8 {
+        self.food
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.food == MAX_FOOD
+    }
+
+    /// Adds to the exhaustion, every `EXHAUSTION_PER_FOOD` costs a point of food
+    pub fn exhaust(&mut self, exhaustion: f32) {
+        self.exhaustion += exhaustion;
+        while self.exhaustion >= EXHAUSTION_PER_FOOD {
+            self.exhaustion -= EXHAUSTION_PER_FOOD;
+            self.food = self.food.saturating_sub(1);
+        }
+    }
+
+    /// Fills the food bar with these points of food, see `Block::food`
+    pub fn eat(&mut self, food: u8) {
+        self.food = self.food.saturating_add(food).min(MAX_FOOD);
+    }
+
+    /// Back to a full bar, after a respawn
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Waits `dt` seconds with this health: every `HEALTH_PERIOD`, a full food bar heals a hurt player
+    /// and an empty one hurts it
+    pub fn step(&mut self, dt: f32, health: u8) -> HungerEffect {
+        let effect = if self.food == MAX_FOOD && health < MAX_HEALTH {
+            HungerEffect::Regenerate
+        } else if self.food == 0 {
+            HungerEffect::Starve
+        } else {
+            HungerEffect::None
+        };
+        if effect == HungerEffect::None {
+            self.timer = 0.;
+            return effect;
+        }
+        self.timer += dt;
+        if self.timer < HEALTH_PERIOD {
+            return HungerEffect::None;
+        }
+        self.timer -= HEALTH_PERIOD;
+        if effect == HungerEffect::Regenerate {
+            self.exhaust(REGENERATION_EXHAUSTION);
+        }
+        effect
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::health::MAX_HEALTH;
+    use crate::hunger::{Hunger, HungerEffect, HEALTH_PERIOD, JUMP_EXHAUSTION, MAX_FOOD, SPRINT_EXHAUSTION};
+
+    #[test]
+    fn test_hunger_empties_and_fills() {
+        let mut hunger = Hunger::new();
+        assert!(hunger.is_full());
+        // A long run empties the bar
+        hunger.exhaust(1000. * SPRINT_EXHAUSTION);
+        assert!(hunger.food() < MAX_FOOD);
+        for _ in 0..1000 {
+            hunger.exhaust(JUMP_EXHAUSTION);
+        }
+        assert_eq!(hunger.food(), 0);
+
+        hunger.eat(4);
+        assert_eq!(hunger.food(), 4);
+        hunger.eat(MAX_FOOD);
+        assert!(hunger.is_full());
+    }
+
+    #[test]
+    fn test_regeneration_and_starvation() {
+        let mut hunger = Hunger::new();
+        // Nothing to heal
+        assert_eq!(hunger.step(2. * HEALTH_PERIOD, MAX_HEALTH), HungerEffect::None);
+
+        // A hurt player heals once per period, which uses the food
+        assert_eq!(hunger.step(HEALTH_PERIOD / 2., MAX_HEALTH - 2), HungerEffect::None);
+        assert_eq!(hunger.step(HEALTH_PERIOD / 2., MAX_HEALTH - 2), HungerEffect::Regenerate);
+        assert_eq!(hunger.step(HEALTH_PERIOD, MAX_HEALTH - 1), HungerEffect::Regenerate);
+        assert!(!hunger.is_full());
+        assert_eq!(hunger.step(HEALTH_PERIOD, MAX_HEALTH - 1), HungerEffect::None);
+
+        // An empty bar hurts
+        hunger.exhaust(1000.);
+        assert_eq!(hunger.step(HEALTH_PERIOD, MAX_HEALTH), HungerEffect::Starve);
+        hunger.reset();
+        assert!(hunger.is_full());
+    }
+}
@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 /// Reprensent a logical input to player's action
 pub enum MotionState {
     Up,
@@ -5,19 +7,27 @@ pub enum MotionState {
     Left,
     Right,
     Jump,
+    Sprint,
+    Sneak,
+    Zoom,
     LeftClick,
+    RightClick,
 }
 
 /// Collect the current player's action status
 pub struct PlayerInputStatus {
     left_click: bool,
     click_time: f32,
+    right_click: bool,
 
     forward: bool,
     backward: bool,
     left: bool,
     right: bool,
     jump: bool,
+    sprint: bool,
+    sneak: bool,
+    zoom: bool,
 }
 
 impl PlayerInputStatus {
@@ -25,11 +35,15 @@ impl PlayerInputStatus {
         Self {
             left_click: false,
             click_time: 0.,
+            right_click: false,
             forward: false,
             backward: false,
             left: false,
             right: false,
             jump: false,
+            sprint: false,
+            sneak: false,
+            zoom: false,
         }
     }
 
@@ -41,10 +55,14 @@ impl PlayerInputStatus {
             MotionState::Right => self.right = pressed,
             MotionState::Left => self.left = pressed,
             MotionState::Jump => self.jump = pressed,
+            MotionState::Sprint => self.sprint = pressed,
+            MotionState::Sneak => self.sneak = pressed,
+            MotionState::Zoom => self.zoom = pressed,
             MotionState::LeftClick => {
                 self.left_click = pressed;
                 self.click_time = 0.;
             }
+            MotionState::RightClick => self.right_click = pressed,
             _ => ()
         }
     }
@@ -89,11 +107,73 @@ impl PlayerInputStatus {
     pub fn jump(&self) -> bool {
         self.jump
     }
+
+    pub fn sprint(&self) -> bool {
+        self.sprint
+    }
+
+    pub fn sneak(&self) -> bool {
+        self.sneak
+    }
+
+    pub fn zoom(&self) -> bool {
+        self.zoom
+    }
+
+    pub fn right_click(&self) -> bool {
+        self.right_click
+    }
+}
+
+/// Minimum time between two repetitions of an action while its button is held
+pub struct ActionCooldown {
+    cooldown: Duration,
+    /// Time since the last action, None if the next one can happen right away
+    since_last: Option<Duration>,
+}
+
+impl ActionCooldown {
+    pub fn new(cooldown: Duration) -> Self {
+        Self { cooldown, since_last: None }
+    }
+
+    pub fn set_cooldown(&mut self, cooldown: Duration) {
+        self.cooldown = cooldown;
+    }
+
+    pub fn step(&mut self, elapsed: Duration) {
+        if let Some(since_last) = self.since_last.as_mut() {
+            *since_last += elapsed;
+        }
+    }
+
+    /// Returns true if the action can happen now, and starts the cooldown if so
+    pub fn try_act(&mut self) -> bool {
+        match self.since_last {
+            Some(since_last) if since_last < self.cooldown => false,
+            // The leftover time is kept, so that the actions happen at a steady rate whatever the frame rate
+            Some(since_last) => {
+                self.since_last = Some(since_last - self.cooldown);
+                true
+            }
+            None => {
+                self.since_last = Some(Duration::ZERO);
+                true
+            }
+        }
+    }
+
+    /// The next action can happen right away, for instance when the button is pressed again
+    pub fn reset(&mut self) {
+        self.since_last = None;
+    }
 }
 
 
 #[cfg(test)]
 mod tests {
+    use std::time::Duration;
+    use crate::input::ActionCooldown;
     use crate::input::PlayerInputStatus;
     use crate::input::MotionState;
 
@@ -154,4 +234,20 @@ mod tests {
         input.set_input(MotionState::LeftClick, false);
         assert_eq!(input.click_time(), 0.);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_action_cooldown() {
+        let mut cooldown = ActionCooldown::new(Duration::from_millis(100));
+        assert!(cooldown.try_act());
+        assert!(!cooldown.try_act());
+
+        cooldown.step(Duration::from_millis(60));
+        assert!(!cooldown.try_act());
+        cooldown.step(Duration::from_millis(60));
+        assert!(cooldown.try_act());
+
+        // Releasing the button allows to act right away
+        cooldown.reset();
+        assert!(cooldown.try_act());
+    }
+}
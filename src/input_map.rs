@@ -0,0 +1,259 @@
+use crate::input::MotionState;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::{BTreeMap, HashMap};
+use strum::EnumIter;
+use winit::event::MouseButton;
+use winit::keyboard::KeyCode;
+
+/// What the player can do with a key or a mouse button.
+/// The names in the config file are the snake case ones (`move_forward`, `break_block`, ...).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, EnumIter)]
+#[serde(rename_all = "snake_case")]
+pub enum InputAction {
+    MoveForward,
+    MoveBackward,
+    MoveLeft,
+    MoveRight,
+    Jump,
+    Sprint,
+    Sneak,
+    Zoom,
+    BreakBlock,
+    PlaceBlock,
+    OpenInventory,
+    ThirdPerson,
+    ToggleGameMode,
+    DebugMenu,
+    HelpMenu,
+    SaveMap,
+    SpawnMonster,
+    DebugInfo,
+    Chat,
+}
+
+impl InputAction {
+    /// The state of the player changed while the input is held, the other actions happen once when it is pressed
+    pub fn motion_state(&self) -> Option<MotionState> {
+        match self {
+            InputAction::MoveForward => Some(MotionState::Up),
+            InputAction::MoveBackward => Some(MotionState::Down),
+            InputAction::MoveLeft => Some(MotionState::Left),
+            InputAction::MoveRight => Some(MotionState::Right),
+            InputAction::Jump => Some(MotionState::Jump),
+            InputAction::Sprint => Some(MotionState::Sprint),
+            InputAction::Sneak => Some(MotionState::Sneak),
+            InputAction::Zoom => Some(MotionState::Zoom),
+            InputAction::BreakBlock => Some(MotionState::LeftClick),
+            InputAction::PlaceBlock => Some(MotionState::RightClick),
+            _ => None,
+        }
+    }
+
+    /// Name of the action in the config file
+    pub fn name(&self) -> String {
+        serde_json::to_value(self).ok().and_then(|value| value.as_str().map(String::from)).unwrap_or_default()
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        serde_json::from_value(serde_json::Value::String(name.to_string())).ok()
+    }
+}
+
+/// A physical key of the keyboard, or a button of the mouse
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+}
+
+/// The keys that can be bound, they are named in the config file as in `KeyCode` (`KeyW`, `Space`, `ShiftLeft`, ...)
+const BINDABLE_KEYS: [KeyCode; 60] = [
+    KeyCode::KeyA, KeyCode::KeyB, KeyCode::KeyC, KeyCode::KeyD, KeyCode::KeyE, KeyCode::KeyF, KeyCode::KeyG,
+    KeyCode::KeyH, KeyCode::KeyI, KeyCode::KeyJ, KeyCode::KeyK, KeyCode::KeyL, KeyCode::KeyM, KeyCode::KeyN,
+    KeyCode::KeyO, KeyCode::KeyP, KeyCode::KeyQ, KeyCode::KeyR, KeyCode::KeyS, KeyCode::KeyT, KeyCode::KeyU,
+    KeyCode::KeyV, KeyCode::KeyW, KeyCode::KeyX, KeyCode::KeyY, KeyCode::KeyZ,
+    KeyCode::F1, KeyCode::F2, KeyCode::F3, KeyCode::F4, KeyCode::F5, KeyCode::F6,
+    KeyCode::F7, KeyCode::F8, KeyCode::F9, KeyCode::F10, KeyCode::F11, KeyCode::F12,
+    KeyCode::Space, KeyCode::Tab, KeyCode::Enter, KeyCode::Backspace, KeyCode::CapsLock,
+    KeyCode::ShiftLeft, KeyCode::ShiftRight, KeyCode::ControlLeft, KeyCode::ControlRight,
+    KeyCode::AltLeft, KeyCode::AltRight,
+    KeyCode::ArrowUp, KeyCode::ArrowDown, KeyCode::ArrowLeft, KeyCode::ArrowRight,
+    KeyCode::Insert, KeyCode::Delete, KeyCode::Home, KeyCode::End, KeyCode::PageUp, KeyCode::PageDown,
+    KeyCode::Backquote,
+];
+
+impl Binding {
+    /// Name of the binding in the config file
+    pub fn name(&self) -> String {
+        match self {
+            Binding::Key(key) => format!("{key:?}"),
+            Binding::Mouse(MouseButton::Left) => String::from("MouseLeft"),
+            Binding::Mouse(MouseButton::Right) => String::from("MouseRight"),
+            Binding::Mouse(MouseButton::Middle) => String::from("MouseMiddle"),
+            Binding::Mouse(MouseButton::Back) => String::from("MouseBack"),
+            Binding::Mouse(MouseButton::Forward) => String::from("MouseForward"),
+            Binding::Mouse(MouseButton::Other(id)) => format!("Mouse{id}"),
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "MouseLeft" => Some(Binding::Mouse(MouseButton::Left)),
+            "MouseRight" => Some(Binding::Mouse(MouseButton::Right)),
+            "MouseMiddle" => Some(Binding::Mouse(MouseButton::Middle)),
+            "MouseBack" => Some(Binding::Mouse(MouseButton::Back)),
+            "MouseForward" => Some(Binding::Mouse(MouseButton::Forward)),
+            _ if name.starts_with("Mouse") => name["Mouse".len()..].parse().ok().map(|id| Binding::Mouse(MouseButton::Other(id))),
+            _ => BINDABLE_KEYS.iter().find(|key| format!("{key:?}") == name).map(|key| Binding::Key(*key)),
+        }
+    }
+}
+
+/// Which action each key and mouse button triggers.
+/// The config file maps the names of the actions to the names of their bindings, for instance
+/// `{"move_forward": "KeyZ", "jump": "MouseRight"}`. The actions it does not mention keep their default binding.
+/// The bindings are saved with the other settings, see `Settings`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InputMap {
+    bindings: HashMap<InputAction, Binding>,
+}
+
+impl InputMap {
+    /// Reads the bindings of a config file, on top of the default ones
+    pub fn from_json(data: &str) -> Result<Self, String> {
+        serde_json::from_str(data).map_err(|error| error.to_string())
+    }
+
+    /// Writes all the bindings, to be edited by the player
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    /// The default bindings, replaced by the ones given by their names
+    fn from_names(names: HashMap<String, String>) -> Result<Self, String> {
+        let mut map = Self::default();
+        for (action, binding) in names {
+            let action = InputAction::from_name(&action).ok_or(format!("unknown action '{action}'"))?;
+            let binding = Binding::from_name(&binding).ok_or(format!("unknown key '{binding}'"))?;
+            map.bind(action, binding);
+        }
+        Ok(map)
+    }
+
+    /// The names of the actions and of their bindings, sorted to keep the file stable
+    fn names(&self) -> BTreeMap<String, String> {
+        self.bindings
+            .iter()
+            .map(|(action, binding)| (action.name(), binding.name()))
+            .collect()
+    }
+
+    /// Binds the action to a key or a button. The action loses its previous binding,
+    /// and the binding is taken from the action that had it.
+    pub fn bind(&mut self, action: InputAction, binding: Binding) {
+        self.bindings.retain(|_, bound| *bound != binding);
+        self.bindings.insert(action, binding);
+    }
+
+    pub fn binding(&self, action: InputAction) -> Option<Binding> {
+        self.bindings.get(&action).copied()
+    }
+
+    /// The action triggered by the input, if any
+    pub fn action(&self, binding: Binding) -> Option<InputAction> {
+        self.bindings.iter().find(|(_, bound)| **bound == binding).map(|(action, _)| *action)
+    }
+
+    pub fn action_for_key(&self, key: KeyCode) -> Option<InputAction> {
+        self.action(Binding::Key(key))
+    }
+
+    pub fn action_for_button(&self, button: MouseButton) -> Option<InputAction> {
+        self.action(Binding::Mouse(button))
+    }
+}
+
+impl Serialize for InputMap {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.names().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for InputMap {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let names = HashMap::<String, String>::deserialize(deserializer)?;
+        Self::from_names(names).map_err(serde::de::Error::custom)
+    }
+}
+
+impl Default for InputMap {
+    fn default() -> Self {
+        let bindings = [
+            (InputAction::MoveForward, Binding::Key(KeyCode::KeyW)),
+            (InputAction::MoveBackward, Binding::Key(KeyCode::KeyS)),
+            (InputAction::MoveLeft, Binding::Key(KeyCode::KeyA)),
+            (InputAction::MoveRight, Binding::Key(KeyCode::KeyD)),
+            (InputAction::Jump, Binding::Key(KeyCode::Space)),
+            (InputAction::Sprint, Binding::Key(KeyCode::ControlLeft)),
+            (InputAction::Sneak, Binding::Key(KeyCode::ShiftLeft)),
+            (InputAction::Zoom, Binding::Key(KeyCode::KeyC)),
+            (InputAction::BreakBlock, Binding::Mouse(MouseButton::Left)),
+            (InputAction::PlaceBlock, Binding::Mouse(MouseButton::Right)),
+            (InputAction::OpenInventory, Binding::Key(KeyCode::KeyE)),
+            (InputAction::ThirdPerson, Binding::Key(KeyCode::F5)),
+            (InputAction::ToggleGameMode, Binding::Key(KeyCode::F4)),
+            (InputAction::DebugMenu, Binding::Key(KeyCode::F3)),
+            (InputAction::HelpMenu, Binding::Key(KeyCode::F12)),
+            (InputAction::SaveMap, Binding::Key(KeyCode::F10)),
+            (InputAction::SpawnMonster, Binding::Key(KeyCode::KeyX)),
+            (InputAction::DebugInfo, Binding::Key(KeyCode::KeyP)),
+            (InputAction::Chat, Binding::Key(KeyCode::KeyT)),
+        ];
+        Self { bindings: bindings.into_iter().collect() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::input_map::{Binding, InputAction, InputMap};
+    use strum::IntoEnumIterator;
+    use winit::event::MouseButton;
+    use winit::keyboard::KeyCode;
+
+    #[test]
+    fn test_every_action_has_a_default_binding() {
+        let map = InputMap::default();
+        for action in InputAction::iter() {
+            let binding = map.binding(action).unwrap();
+            assert_eq!(map.action(binding), Some(action));
+            assert_eq!(InputAction::from_name(&action.name()), Some(action));
+            assert_eq!(Binding::from_name(&binding.name()), Some(binding));
+        }
+        assert_eq!(InputAction::MoveForward.name(), "move_forward");
+    }
+
+    #[test]
+    fn test_remap_from_json() {
+        let map = InputMap::from_json(r#"{"move_forward": "KeyZ", "move_left": "KeyQ", "jump": "MouseMiddle"}"#).unwrap();
+        assert_eq!(map.action_for_key(KeyCode::KeyZ), Some(InputAction::MoveForward));
+        assert_eq!(map.action_for_key(KeyCode::KeyW), None);
+        assert_eq!(map.action_for_button(MouseButton::Middle), Some(InputAction::Jump));
+        assert_eq!(map.action_for_key(KeyCode::Space), None);
+        // The others keep their default binding
+        assert_eq!(map.action_for_key(KeyCode::KeyS), Some(InputAction::MoveBackward));
+
+        // Round trip through the file
+        assert_eq!(InputMap::from_json(&map.to_json()).unwrap(), map);
+
+        assert!(InputMap::from_json(r#"{"fly_away": "KeyZ"}"#).is_err());
+        assert!(InputMap::from_json(r#"{"jump": "NotAKey"}"#).is_err());
+    }
+
+    #[test]
+    fn test_binding_a_key_twice_moves_it() {
+        let mut map = InputMap::default();
+        map.bind(InputAction::Jump, Binding::Key(KeyCode::KeyW));
+        assert_eq!(map.action_for_key(KeyCode::KeyW), Some(InputAction::Jump));
+        assert_eq!(map.binding(InputAction::MoveForward), None);
+    }
+}
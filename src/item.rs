@@ -0,0 +1,227 @@
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+
+use crate::block_kind::Block;
+use crate::food::Food;
+use crate::texture_atlas::BlockFace;
+use crate::tool::Tool;
+
+/// Number of items of a kind a slot can hold, see `Item::max_in_slot`
+const MAX_ITEMS_IN_SLOT: usize = 64;
+/// The id of the arrows in the saves, which they had as a block (see `Item::id`)
+const ARROW_ID: u8 = 31;
+
+/// What the slots of the inventory and the chests hold: the blocks, most of which can be placed in the world,
+/// and the tools, the food and the arrows, which cannot
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize, Hash, Eq)]
+#[serde(from = "SavedItem")]
+pub enum Item {
+    Block(Block),
+    Tool(Tool),
+    Food(Food),
+    /// Shot by the bow, see `Arrow`
+    Arrow,
+}
+
+impl Item {
+    /// All the items, the blocks first, then the tools, the food and the arrows
+    pub fn iter() -> impl Iterator<Item = Item> {
+        Block::iter().map(Item::Block)
+            .chain(Tool::iter().map(Item::Tool))
+            .chain(Food::iter().map(Item::Food))
+            .chain([Item::Arrow])
+    }
+
+    /// The block of the item, if it is one
+    pub fn block(&self) -> Option<Block> {
+        match self {
+            Item::Block(block) => Some(*block),
+            _ => None,
+        }
+    }
+
+    /// The tool of the item, if it is one
+    pub fn tool(&self) -> Option<Tool> {
+        match self {
+            Item::Tool(tool) => Some(*tool),
+            _ => None,
+        }
+    }
+
+    /// The food of the item, if it is some
+    pub fn food(&self) -> Option<Food> {
+        match self {
+            Item::Food(food) => Some(*food),
+            _ => None,
+        }
+    }
+
+    /// Number of items of this kind a slot can hold, the tools are alone in their slot
+    pub fn max_in_slot(&self) -> usize {
+        match self {
+            Item::Tool(_) => 1,
+            _ => MAX_ITEMS_IN_SLOT,
+        }
+    }
+
+    /// Name of the texture file of the face of the item drawn as a cube, without its extension
+    pub fn texture_file(&self, face: BlockFace) -> String {
+        match self {
+            Item::Block(block) => block.texture_file(face),
+            Item::Tool(tool) => tool.texture_file(face),
+            Item::Food(food) => food.texture_file(face),
+            Item::Arrow => "arrow".to_string() + face.suffix(),
+        }
+    }
+
+    /// Damage dealt by a hit with this item in hand, see `Tool::damage`
+    pub fn attack_strength(&self) -> u8 {
+        self.tool().map_or(1, |tool| tool.damage())
+    }
+
+    /// The id of the item in the saves: all the items share the same ids (see `Block::id`, `Tool::id` and `Food::id`)
+    pub fn id(&self) -> u8 {
+        match self {
+            Item::Block(block) => block.id(),
+            Item::Tool(tool) => tool.id(),
+            Item::Food(food) => food.id(),
+            Item::Arrow => ARROW_ID,
+        }
+    }
+
+    /// The item of this id, see `id`
+    pub fn from_id(id: u8) -> Option<Self> {
+        Item::iter().find(|item| item.id() == id)
+    }
+}
+
+/// An item as it was named in the saves written when all the items were blocks: the name of its block, of its tool,
+/// of its food or of the arrows
+#[derive(Clone, Copy, PartialEq, Debug, Deserialize, Hash, Eq)]
+#[serde(untagged)]
+pub enum LegacyItem {
+    Block(Block),
+    Tool(Tool),
+    Food(Food),
+    Arrow(LegacyArrow),
+}
+
+/// The name of the arrows when they were a block
+#[derive(Clone, Copy, PartialEq, Debug, Deserialize, Hash, Eq)]
+pub enum LegacyArrow {
+    #[serde(rename = "ARROW")]
+    Arrow,
+}
+
+impl From<LegacyItem> for Item {
+    fn from(item: LegacyItem) -> Self {
+        match item {
+            LegacyItem::Block(block) => Item::Block(block),
+            LegacyItem::Tool(tool) => Item::Tool(tool),
+            LegacyItem::Food(food) => Item::Food(food),
+            LegacyItem::Arrow(_) => Item::Arrow,
+        }
+    }
+}
+
+/// An item as it is read from the saves: the items saved while the food and the arrows were blocks are written
+/// as blocks, by the name of their block
+#[derive(Deserialize)]
+enum SavedItem {
+    Block(LegacyItem),
+    Tool(Tool),
+    Food(Food),
+    Arrow,
+}
+
+impl From<SavedItem> for Item {
+    fn from(item: SavedItem) -> Self {
+        match item {
+            SavedItem::Block(item) => Item::from(item),
+            SavedItem::Tool(tool) => Item::Tool(tool),
+            SavedItem::Food(food) => Item::Food(food),
+            SavedItem::Arrow => Item::Arrow,
+        }
+    }
+}
+
+impl From<Block> for Item {
+    fn from(block: Block) -> Self {
+        Item::Block(block)
+    }
+}
+
+impl From<Tool> for Item {
+    fn from(tool: Tool) -> Self {
+        Item::Tool(tool)
+    }
+}
+
+impl From<Food> for Item {
+    fn from(food: Food) -> Self {
+        Item::Food(food)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::block_kind::Block;
+    use crate::food::Food;
+    use crate::item::{Item, LegacyItem};
+    use crate::tool::Tool;
+
+    #[test]
+    fn test_blocks_and_tools() {
+        assert_eq!(Item::from(Block::DIRT).block(), Some(Block::DIRT));
+        assert_eq!(Item::from(Block::DIRT).tool(), None);
+        assert_eq!(Item::from(Tool::Axe).tool(), Some(Tool::Axe));
+        assert_eq!(Item::from(Tool::Axe).block(), None);
+        // The wheat is eaten once baked
+        assert_eq!(Item::from(Food::Bread).food(), Some(Food::Bread));
+        assert_eq!(Item::from(Block::WHEAT).food(), None);
+
+        // Only the blocks stack
+        assert!(Item::from(Block::DIRT).max_in_slot() > 1);
+        assert_eq!(Item::from(Tool::Pickaxe).max_in_slot(), 1);
+
+        // The sword hits the hardest
+        assert_eq!(Item::from(Tool::Sword).attack_strength(), Tool::Sword.damage());
+        assert_eq!(Item::from(Block::DIRT).attack_strength(), 1);
+    }
+
+    #[test]
+    fn test_item_ids() {
+        for item in Item::iter() {
+            assert_eq!(Item::from_id(item.id()), Some(item));
+        }
+        // The tools kept the ids they had as blocks
+        assert_eq!(Item::from(Tool::Sword).id(), 8);
+        assert_eq!(Item::from(Tool::Bow).id(), 30);
+        assert_eq!(Item::from(Block::TORCH).id(), 9);
+        // And so did the food and the arrows
+        assert_eq!(Item::Arrow.id(), 31);
+        assert_eq!(Item::from(Food::Porkchop).id(), 33);
+        assert_eq!(Item::from_id(200), None);
+    }
+
+    #[test]
+    fn test_legacy_names() {
+        let item: LegacyItem = serde_json::from_str("\"SWORD\"").unwrap();
+        assert_eq!(Item::from(item), Item::Tool(Tool::Sword));
+        let item: LegacyItem = serde_json::from_str("\"DIRT\"").unwrap();
+        assert_eq!(Item::from(item), Item::Block(Block::DIRT));
+        let tool: Tool = serde_json::from_str("\"Pickaxe\"").unwrap();
+        assert_eq!(tool, Tool::Pickaxe);
+        let item: LegacyItem = serde_json::from_str("\"ARROW\"").unwrap();
+        assert_eq!(Item::from(item), Item::Arrow);
+
+        // The food and the arrows saved as blocks
+        let item: Item = serde_json::from_str("{\"Block\":\"BREAD\"}").unwrap();
+        assert_eq!(item, Item::Food(Food::Bread));
+        let item: Item = serde_json::from_str("{\"Block\":\"ARROW\"}").unwrap();
+        assert_eq!(item, Item::Arrow);
+        for item in Item::iter() {
+            assert_eq!(serde_json::from_str::<Item>(&serde_json::to_string(&item).unwrap()).unwrap(), item);
+        }
+    }
+}
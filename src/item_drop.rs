@@ -0,0 +1,217 @@
+use std::any::Any;
+
+use crate::entity::body::Body;
+use crate::entity::store::{EntityBehavior, EntityId, EntityStore, EntityUpdate};
+use crate::graphics::cube::CubeInstance;
+use crate::item::Item;
+use crate::player_items::{Inventory, ItemStack};
+use crate::primitives::vector::Vector3;
+use crate::world::World;
+
+/// Side [cube] of the cube drawn for a dropped item
+pub const DROP_SIZE: f32 = 0.25;
+/// Distance [cube] from the player under which the drops are picked up
+pub const PICKUP_RANGE: f32 = 1.5;
+/// Distance [cube] under which two drops of the same item merge into one, unless they are tools
+const MERGE_RANGE: f32 = 0.5;
+/// Time [s] before a new drop can be picked up, so that it is seen popping out of its block
+const PICKUP_DELAY: f32 = 0.5;
+/// Time [s] after which a drop nobody picked up disappears
+const LIFETIME: f32 = 300.;
+/// Speed [rad/s] of the drops spinning on themselves
+const SPIN_SPEED: f32 = 1.5;
+
+/// Items left on the ground by a broken block. They fall until they rest on a solid block,
+/// where they wait for a player to come close enough to pick them up.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ItemDrop {
+    /// The items, a tool keeping its wear
+    stack: ItemStack,
+    /// Time [s] since the drop appeared
+    age: f32,
+}
+
+impl ItemDrop {
+    /// Drops the stack in the middle of the cell `at`
+    pub fn spawn(entities: &mut EntityStore, stack: ItemStack, at: Vector3) -> EntityId {
+        let bottom = at.to_cube_coordinates() + Vector3::new(0.5, 0.5 - DROP_SIZE / 2., 0.5);
+        entities.spawn(Body::new(bottom, DROP_SIZE, DROP_SIZE), Self { stack, age: 0. })
+    }
+
+    pub fn stack(&self) -> ItemStack {
+        self.stack
+    }
+
+    /// Angle [rad] of the drop around the vertical axis
+    pub fn angle(&self) -> f32 {
+        self.age * SPIN_SPEED
+    }
+
+    fn can_be_picked_up(&self) -> bool {
+        self.age >= PICKUP_DELAY
+    }
+}
+
+impl EntityBehavior for ItemDrop {
+    /// Removes the drops nobody picked up for too long
+    fn update(&mut self, _body: &mut Body, dt: f32, _world: &World) -> EntityUpdate {
+        self.age += dt;
+        if self.age < LIFETIME { EntityUpdate::Keep } else { EntityUpdate::Remove }
+    }
+
+    /// The cube drawn for the drop, small and spinning
+    fn instances(&self, body: &Body) -> Vec<CubeInstance> {
+        vec![CubeInstance::new_item(self.stack.item, &body.center(), DROP_SIZE, self.angle())]
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// Merges the close drops of the same item into one
+pub fn merge_drops(entities: &mut EntityStore) {
+    let mut kept: Vec<(EntityId, Vector3, Item)> = Vec::new();
+    let mut merges = Vec::new();
+    for (id, body, drop) in entities.of_type::<ItemDrop>() {
+        let item = drop.stack.item;
+        let close = kept.iter().find(|(_, center, kind)| *kind == item && (*center - body.center()).norm() < MERGE_RANGE);
+        match close {
+            Some((into, _, _)) if item.max_in_slot() > 1 => merges.push((id, *into)),
+            _ => kept.push((id, body.center(), item)),
+        }
+    }
+
+    for (id, into) in merges {
+        let Some(&ItemDrop { stack, age }) = entities.behavior::<ItemDrop>(id) else {
+            continue;
+        };
+        entities.remove(id);
+        if let Some(other) = entities.behavior_mut::<ItemDrop>(into) {
+            other.stack.count += stack.count;
+            other.age = other.age.min(age);
+        }
+    }
+}
+
+/// Moves the items of the drops within the pickup range of the player, whose position is given in cube coordinates,
+/// to its inventory. The items that do not fit stay on the ground. Returns true if some items were picked up.
+pub fn pick_up(entities: &mut EntityStore, player: &Vector3, items: &mut Inventory) -> bool {
+    let mut picked = false;
+    entities.retain_of_type::<ItemDrop>(|body, drop| {
+        if !drop.can_be_picked_up() || (body.center() - *player).norm() > PICKUP_RANGE {
+            return true;
+        }
+        let left = items.collect_stack(drop.stack);
+        picked |= left != Some(drop.stack);
+        match left {
+            Some(left) => {
+                drop.stack = left;
+                true
+            }
+            None => false,
+        }
+    });
+    picked
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::block_kind::Block;
+    use crate::chunk::Chunk;
+    use crate::item::Item;
+    use crate::item_drop::{pick_up, ItemDrop, DROP_SIZE, PICKUP_RANGE};
+    use crate::player_items::{Inventory, ItemStack, HOTBAR_SIZE, STORAGE_SIZE};
+    use crate::primitives::vector::Vector3;
+    use crate::tool::Tool;
+    use crate::world::World;
+
+    /// A floor of stone, its top at y = 3
+    fn floor() -> World {
+        let mut chunk = Chunk::new([0., 0.]);
+        for y in 0..3 {
+            chunk.fill_layer(y, Block::STONE);
+        }
+        World::new(vec![chunk])
+    }
+
+    #[test]
+    fn test_drops_fall_on_the_floor() {
+        let mut world = floor();
+        let id = ItemDrop::spawn(world.entities_mut(), ItemStack::new(Block::DIRT, 1), Vector3::new(2., 8., 2.));
+        assert_eq!(world.entities().body(id).unwrap().center(), Vector3::new(2.5, 8.5, 2.5));
+        for _ in 0..200 {
+            world.step_entities(0.01);
+        }
+        let center = world.entities().body(id).unwrap().center();
+        assert!((center.y() - (3. + DROP_SIZE / 2.)).abs() < 1e-5);
+        assert!(world.entities().behavior::<ItemDrop>(id).unwrap().angle() > 0.);
+
+        // It stays on the floor
+        world.step_entities(0.5);
+        let center = world.entities().body(id).unwrap().center();
+        assert!((center.y() - (3. + DROP_SIZE / 2.)).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_close_drops_merge() {
+        let mut world = floor();
+        for (block, x) in [(Block::DIRT, 2.), (Block::DIRT, 2.), (Block::SAND, 2.), (Block::DIRT, 6.)] {
+            ItemDrop::spawn(world.entities_mut(), ItemStack::new(block, 1), Vector3::new(x, 3., 2.));
+        }
+        // The tools stay alone
+        for _ in 0..2 {
+            ItemDrop::spawn(world.entities_mut(), ItemStack::new(Tool::Shovel, 1), Vector3::new(2., 3., 2.));
+        }
+        world.step_entities(0.01);
+        let mut counts: Vec<(u8, usize)> = world.entities().of_type::<ItemDrop>().map(|(_, _, drop)| (drop.stack().item.id(), drop.stack().count)).collect();
+        counts.sort();
+        let [dirt, sand, shovel] = [Item::Block(Block::DIRT), Item::Block(Block::SAND), Item::Tool(Tool::Shovel)].map(|item| item.id());
+        assert_eq!(counts, vec![(dirt, 1), (dirt, 2), (sand, 1), (shovel, 1), (shovel, 1)]);
+    }
+
+    #[test]
+    fn test_pick_up_in_range() {
+        let mut world = floor();
+        for (block, x) in [(Block::DIRT, 2.), (Block::DIRT, 2.), (Block::SAND, 7.)] {
+            ItemDrop::spawn(world.entities_mut(), ItemStack::new(block, 1), Vector3::new(x, 3., x));
+        }
+        let player = Vector3::new(2.5, 4., 2.5 + PICKUP_RANGE / 2.);
+
+        let mut items = Inventory::empty();
+
+        // The new drops cannot be picked up right away
+        assert!(!pick_up(world.entities_mut(), &player, &mut items));
+        world.step_entities(1.);
+        assert!(pick_up(world.entities_mut(), &player, &mut items));
+        assert_eq!(items.count(Block::DIRT), 2);
+        assert_eq!(world.entities().len(), 1);
+        assert!(!pick_up(world.entities_mut(), &player, &mut items));
+    }
+
+    #[test]
+    fn test_items_stay_on_the_ground_when_the_inventory_is_full() {
+        let mut world = floor();
+        let id = ItemDrop::spawn(world.entities_mut(), ItemStack::new(Block::DIRT, 3), Vector3::new(2., 3., 2.));
+        world.step_entities(1.);
+        let player = Vector3::new(2.5, 4., 2.5);
+
+        // Room for a single dirt
+        let mut items = Inventory::empty();
+        items.collect_stack(ItemStack::new(Block::DIRT, 63));
+        for _ in 1..HOTBAR_SIZE + STORAGE_SIZE {
+            items.collect(Tool::Sword);
+        }
+        assert!(pick_up(world.entities_mut(), &player, &mut items));
+        assert_eq!(items.count(Block::DIRT), 64);
+        assert_eq!(world.entities().behavior::<ItemDrop>(id).unwrap().stack(), ItemStack::new(Block::DIRT, 2));
+
+        // Nothing fits anymore
+        assert!(!pick_up(world.entities_mut(), &player, &mut items));
+        assert_eq!(world.entities().len(), 1);
+    }
+}
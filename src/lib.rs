@@ -13,7 +13,7 @@ pub mod cubes_to_draw;
 pub mod world_serializer;
 pub mod network;
 pub mod entity;
-mod primitives;
+pub mod primitives;
 pub mod aabb;
 pub mod texture;
 pub mod collidable;
@@ -24,3 +24,30 @@ pub mod server;
 pub mod crafting;
 pub mod attack;
 pub mod health;
+pub mod game_mode;
+pub mod world_meta;
+pub mod block_updates;
+pub mod profile;
+pub mod structure;
+pub mod chunk_workers;
+pub mod persistence;
+pub mod sky;
+pub mod lighting;
+pub mod falling_block;
+pub mod input_map;
+pub mod gamepad;
+pub mod settings;
+pub mod game_state;
+pub mod world_selection;
+pub mod texture_atlas;
+pub mod resource_pack;
+pub mod block_shape;
+pub mod block_entity;
+pub mod random_ticks;
+pub mod tool;
+pub mod food;
+pub mod item;
+pub mod item_drop;
+pub mod bow;
+pub mod hunger;
+pub mod chat;
\ No newline at end of file
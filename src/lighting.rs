@@ -0,0 +1,423 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::block_kind::Block;
+use crate::chunk::{Chunk, ChunkCoord, CHUNK_HEIGHT, CHUNK_SIZE};
+
+/// Light of a cell in full sunlight, each level is stored on 4 bits
+pub const MAX_LIGHT: u8 = 15;
+
+/// Global position of a cell, in cube coordinates
+type Cell = [i32; 3];
+
+/// The six cells touching a cell, the one below is the fourth
+const DIRECTIONS: [Cell; 6] = [[1, 0, 0], [-1, 0, 0], [0, 1, 0], [0, -1, 0], [0, 0, 1], [0, 0, -1]];
+const DOWN: Cell = [0, -1, 0];
+
+/// The two kinds of light, propagated independently
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LightChannel {
+    /// Comes from the top of the world, and goes down the open columns without fading
+    Sky,
+    /// Emitted by the blocks like the torches
+    Block,
+}
+
+/// Brightness of a face lit with this level, as given to the shaders: each level is 20% darker than the one above
+pub fn brightness(level: u8) -> f32 {
+    0.8_f32.powi((MAX_LIGHT - level.min(MAX_LIGHT)) as i32)
+}
+
+/// Corner of the chunk holding this cell
+fn chunk_of(cell: Cell) -> ChunkCoord {
+    let s = CHUNK_SIZE as i32;
+    (cell[0].div_euclid(s) * s, cell[2].div_euclid(s) * s)
+}
+
+fn neighbor(cell: Cell, direction: Cell) -> Cell {
+    [cell[0] + direction[0], cell[1] + direction[1], cell[2] + direction[2]]
+}
+
+/// Block in this cell, None for air or outside of the loaded chunks
+fn block_in(chunks: &HashMap<ChunkCoord, Chunk>, cell: Cell) -> Option<Block> {
+    if cell[1] < 0 || cell[1] >= CHUNK_HEIGHT as i32 {
+        return None;
+    }
+    let coord = chunk_of(cell);
+    let chunk = chunks.get(&coord)?;
+    let cube = chunk.cubes()[cell[1] as usize][(cell[0] - coord.0) as usize][(cell[2] - coord.1) as usize];
+    cube.map(|cube| *cube.block())
+}
+
+/// The light does not go through the opaque blocks, it goes around the slabs and the stairs
+fn is_opaque(chunks: &HashMap<ChunkCoord, Chunk>, cell: Cell) -> bool {
+    block_in(chunks, cell).is_some_and(|block| block.is_opaque_cube())
+}
+
+/// Levels lost by the light going into this cell on top of the usual one, see `Block::light_attenuation`
+fn attenuation(chunks: &HashMap<ChunkCoord, Chunk>, cell: Cell) -> u8 {
+    block_in(chunks, cell).map_or(0, |block| block.light_attenuation())
+}
+
+/// Light levels of the cells of one chunk, indexed as its cubes (`[y][x][z]`).
+/// Each cell holds the sky light in its high 4 bits and the block light in its low 4 bits.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChunkLight {
+    coord: ChunkCoord,
+    cells: Vec<u8>,
+}
+
+impl ChunkLight {
+    pub fn new(coord: ChunkCoord) -> Self {
+        Self { coord, cells: vec![0; CHUNK_HEIGHT * CHUNK_SIZE * CHUNK_SIZE] }
+    }
+
+    pub fn coord(&self) -> ChunkCoord {
+        self.coord
+    }
+
+    /// Whether this global cell is inside the chunk
+    pub fn contains(&self, cell: Cell) -> bool {
+        chunk_of(cell) == self.coord && cell[1] >= 0 && cell[1] < CHUNK_HEIGHT as i32
+    }
+
+    fn index(&self, cell: Cell) -> usize {
+        let x = (cell[0] - self.coord.0) as usize;
+        let z = (cell[2] - self.coord.1) as usize;
+        (cell[1] as usize * CHUNK_SIZE + x) * CHUNK_SIZE + z
+    }
+
+    /// Light of this global cell, which must be in the chunk
+    pub fn get(&self, cell: Cell, channel: LightChannel) -> u8 {
+        let packed = self.cells[self.index(cell)];
+        match channel {
+            LightChannel::Sky => packed >> 4,
+            LightChannel::Block => packed & 0x0F,
+        }
+    }
+
+    fn set(&mut self, cell: Cell, channel: LightChannel, level: u8) {
+        let index = self.index(cell);
+        let packed = self.cells[index];
+        self.cells[index] = match channel {
+            LightChannel::Sky => (packed & 0x0F) | (level << 4),
+            LightChannel::Block => (packed & 0xF0) | level,
+        };
+    }
+}
+
+/// The light of the loaded chunks.
+/// The light is flood filled from its sources (the sky and the emitting blocks), losing one level
+/// at each cell, and the parts touched by a changed block are propagated again.
+/// When a chunk is unloaded, the light it brought to the chunks next to it is kept until they are lit again.
+#[derive(Clone, Debug, Default)]
+pub struct Lighting {
+    chunks: HashMap<ChunkCoord, ChunkLight>,
+}
+
+impl Lighting {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn chunk(&self, coord: ChunkCoord) -> Option<&ChunkLight> {
+        self.chunks.get(&coord)
+    }
+
+    /// Sky and block light of this cell.
+    /// Above the world and in the chunks that are not lit yet, this is the open sky, so that the border
+    /// of the loaded world is not drawn in the dark.
+    pub fn light_at(&self, cell: Cell) -> (u8, u8) {
+        match (self.get(cell, LightChannel::Sky), self.get(cell, LightChannel::Block)) {
+            (Some(sky), Some(block)) => (sky, block),
+            _ => (MAX_LIGHT, 0),
+        }
+    }
+
+    /// Light of the cell, None outside of the lit chunks
+    fn get(&self, cell: Cell, channel: LightChannel) -> Option<u8> {
+        if cell[1] < 0 || cell[1] >= CHUNK_HEIGHT as i32 {
+            return None;
+        }
+        self.chunks.get(&chunk_of(cell)).map(|light| light.get(cell, channel))
+    }
+
+    fn set(&mut self, cell: Cell, channel: LightChannel, level: u8, changed: &mut HashSet<ChunkCoord>) {
+        let coord = chunk_of(cell);
+        if let Some(light) = self.chunks.get_mut(&coord) {
+            light.set(cell, channel, level);
+            changed.insert(coord);
+        }
+    }
+
+    /// Lights the chunk at `coord` from the sky, from its emitting blocks and from the light of the chunks next to it,
+    /// which can be lit back in turn.
+    /// Returns the chunks whose light changed.
+    pub fn add_chunk(&mut self, coord: ChunkCoord, chunks: &HashMap<ChunkCoord, Chunk>) -> HashSet<ChunkCoord> {
+        let mut changed = HashSet::new();
+        self.chunks.insert(coord, ChunkLight::new(coord));
+        let mut sky = VecDeque::new();
+        let mut block = VecDeque::new();
+
+        let s = CHUNK_SIZE as i32;
+        for x in coord.0..coord.0 + s {
+            for z in coord.1..coord.1 + s {
+                // The open part of each column gets the full sky light, dimmed by the blocks like the leaves
+                let mut level = MAX_LIGHT;
+                for y in (0..CHUNK_HEIGHT as i32).rev() {
+                    if is_opaque(chunks, [x, y, z]) {
+                        break;
+                    }
+                    level = level.saturating_sub(attenuation(chunks, [x, y, z]));
+                    if level == 0 {
+                        break;
+                    }
+                    self.set([x, y, z], LightChannel::Sky, level, &mut changed);
+                    sky.push_back([x, y, z]);
+                }
+                for y in 0..CHUNK_HEIGHT as i32 {
+                    let emission = block_in(chunks, [x, y, z]).map_or(0, |b| b.light_emission());
+                    if emission > 0 {
+                        self.set([x, y, z], LightChannel::Block, emission, &mut changed);
+                        block.push_back([x, y, z]);
+                    }
+                }
+            }
+        }
+
+        // The border of the lit chunks next to this one
+        for (dx, dz, border_x, border_z) in [(1, 0, Some(0), None), (-1, 0, Some(s - 1), None), (0, 1, None, Some(0)), (0, -1, None, Some(s - 1))] {
+            let next = (coord.0 + dx * s, coord.1 + dz * s);
+            if !self.chunks.contains_key(&next) {
+                continue;
+            }
+            for i in 0..s {
+                for y in 0..CHUNK_HEIGHT as i32 {
+                    let x = next.0 + border_x.unwrap_or(i);
+                    let z = next.1 + border_z.unwrap_or(i);
+                    sky.push_back([x, y, z]);
+                    block.push_back([x, y, z]);
+                }
+            }
+        }
+
+        self.propagate(LightChannel::Sky, sky, chunks, &mut changed);
+        self.propagate(LightChannel::Block, block, chunks, &mut changed);
+        changed
+    }
+
+    pub fn remove_chunk(&mut self, coord: ChunkCoord) {
+        self.chunks.remove(&coord);
+    }
+
+    /// Lights again around this cell, after its block was added or removed.
+    /// Returns the chunks whose light changed.
+    pub fn update_cell(&mut self, cell: Cell, chunks: &HashMap<ChunkCoord, Chunk>) -> HashSet<ChunkCoord> {
+        let mut changed = HashSet::new();
+        if self.get(cell, LightChannel::Sky).is_none() {
+            return changed;
+        }
+        let opaque = is_opaque(chunks, cell);
+
+        for channel in [LightChannel::Sky, LightChannel::Block] {
+            let mut queue = self.unlight(cell, channel, chunks, &mut changed);
+            let source = match channel {
+                LightChannel::Sky if !opaque && cell[1] == CHUNK_HEIGHT as i32 - 1 => MAX_LIGHT.saturating_sub(attenuation(chunks, cell)),
+                LightChannel::Sky => 0,
+                LightChannel::Block => block_in(chunks, cell).map_or(0, |b| b.light_emission()),
+            };
+            if source > 0 {
+                self.set(cell, channel, source, &mut changed);
+                queue.push_back(cell);
+            }
+            if !opaque {
+                queue.extend(DIRECTIONS.iter().map(|direction| neighbor(cell, *direction)));
+            }
+            self.propagate(channel, queue, chunks, &mut changed);
+        }
+        changed
+    }
+
+    /// Darkens the cell and every cell that got its light through it.
+    /// Returns the lit cells at the edge of the darkened part, from which the light must be propagated again.
+    fn unlight(
+        &mut self,
+        cell: Cell,
+        channel: LightChannel,
+        chunks: &HashMap<ChunkCoord, Chunk>,
+        changed: &mut HashSet<ChunkCoord>,
+    ) -> VecDeque<Cell> {
+        let mut relight = VecDeque::new();
+        let level = self.get(cell, channel).unwrap_or(0);
+        self.set(cell, channel, 0, changed);
+        let mut queue = VecDeque::from([(cell, level)]);
+
+        while let Some((cell, level)) = queue.pop_front() {
+            for direction in DIRECTIONS {
+                let next = neighbor(cell, direction);
+                let Some(next_level) = self.get(next, channel) else {
+                    continue;
+                };
+                if next_level == 0 {
+                    continue;
+                }
+                let sky_column = channel == LightChannel::Sky && direction == DOWN && level == MAX_LIGHT;
+                let emitter = channel == LightChannel::Block && block_in(chunks, next).map_or(0, |b| b.light_emission()) > 0;
+                if (next_level < level || sky_column) && !emitter {
+                    self.set(next, channel, 0, changed);
+                    queue.push_back((next, next_level));
+                } else {
+                    relight.push_back(next);
+                }
+            }
+        }
+        relight
+    }
+
+    /// Flood fills the light from the cells of the queue, one level less at each step,
+    /// and the attenuation of the block the light goes into.
+    /// The full sky light goes straight down without fading through the air.
+    fn propagate(
+        &mut self,
+        channel: LightChannel,
+        mut queue: VecDeque<Cell>,
+        chunks: &HashMap<ChunkCoord, Chunk>,
+        changed: &mut HashSet<ChunkCoord>,
+    ) {
+        while let Some(cell) = queue.pop_front() {
+            let Some(level) = self.get(cell, channel) else {
+                continue;
+            };
+            if level == 0 {
+                continue;
+            }
+            for direction in DIRECTIONS {
+                let next = neighbor(cell, direction);
+                let Some(next_level) = self.get(next, channel) else {
+                    continue;
+                };
+                if is_opaque(chunks, next) {
+                    continue;
+                }
+                let new_level = if channel == LightChannel::Sky && direction == DOWN && level == MAX_LIGHT {
+                    MAX_LIGHT
+                } else {
+                    level - 1
+                }
+                .saturating_sub(attenuation(chunks, next));
+                if new_level > next_level {
+                    self.set(next, channel, new_level, changed);
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::block_kind::Block::{GLASS, GRASS, OAKLEAVES, TORCH};
+    use crate::chunk::{Chunk, ChunkCoord, CHUNK_HEIGHT, CHUNK_SIZE};
+    use crate::lighting::{brightness, Lighting, MAX_LIGHT};
+    use crate::primitives::vector::Vector3;
+
+    fn lit(chunks: &HashMap<ChunkCoord, Chunk>) -> Lighting {
+        let mut lighting = Lighting::new();
+        for coord in chunks.keys() {
+            lighting.add_chunk(*coord, chunks);
+        }
+        lighting
+    }
+
+    #[test]
+    fn test_torch_light_fades_with_distance() {
+        let mut chunk = Chunk::new([0., 0.]);
+        chunk.fill_layer(0, GRASS);
+        chunk.add_cube(Vector3::new(2., 1., 2.), TORCH, 0);
+        let mut chunks = HashMap::from([(chunk.coord(), chunk)]);
+        let s = CHUNK_SIZE as f32;
+        chunks.insert((CHUNK_SIZE as i32, 0), Chunk::new([s, 0.]));
+        let lighting = lit(&chunks);
+
+        assert_eq!(lighting.light_at([2, 1, 2]).1, 14);
+        assert_eq!(lighting.light_at([3, 1, 2]).1, 13);
+        assert_eq!(lighting.light_at([2, 3, 4]).1, 10);
+        // Into the next chunk
+        assert_eq!(lighting.light_at([CHUNK_SIZE as i32, 1, 2]).1, 14 - (CHUNK_SIZE as u8 - 2));
+        // Not through the ground
+        assert_eq!(lighting.light_at([2, 0, 2]).1, 0);
+        assert!(brightness(MAX_LIGHT) > brightness(14));
+    }
+
+    #[test]
+    fn test_roof_blocks_the_sky() {
+        let mut chunk = Chunk::new([0., 0.]);
+        chunk.fill_layer(0, GRASS);
+        let top = CHUNK_HEIGHT as f32 - 2.;
+        for x in 0..3 {
+            for z in 0..3 {
+                chunk.add_cube(Vector3::new(x as f32, top, z as f32), GRASS, 0);
+            }
+        }
+        let chunks = HashMap::from([(chunk.coord(), chunk)]);
+        let lighting = lit(&chunks);
+
+        assert_eq!(lighting.light_at([5, 1, 5]).0, MAX_LIGHT);
+        // Under the roof, the light comes from the side
+        assert_eq!(lighting.light_at([1, 1, 1]).0, MAX_LIGHT - 2);
+        // Nothing comes from the unloaded chunks
+        assert_eq!(lighting.light_at([0, 1, 0]).0, MAX_LIGHT - 3);
+    }
+
+    #[test]
+    fn test_changed_blocks_are_lit_again() {
+        let mut chunk = Chunk::new([0., 0.]);
+        chunk.fill_layer(0, GRASS);
+        let mut chunks = HashMap::from([(chunk.coord(), chunk)]);
+        let mut lighting = lit(&chunks);
+        assert_eq!(lighting.light_at([4, 1, 4]), (MAX_LIGHT, 0));
+
+        // A torch
+        let chunk = chunks.get_mut(&(0, 0)).unwrap();
+        chunk.add_cube(Vector3::new(4., 1., 4.), TORCH, 0);
+        let changed = lighting.update_cell([4, 1, 4], &chunks);
+        assert!(changed.contains(&(0, 0)));
+        assert_eq!(lighting.light_at([4, 1, 5]).1, 13);
+
+        // A block above the cell darkens the column below it
+        let chunk = chunks.get_mut(&(0, 0)).unwrap();
+        chunk.add_cube(Vector3::new(4., 5., 4.), GRASS, 0);
+        lighting.update_cell([4, 5, 4], &chunks);
+        assert_eq!(lighting.light_at([4, 4, 4]).0, MAX_LIGHT - 1);
+        assert_eq!(lighting.light_at([4, 6, 4]).0, MAX_LIGHT);
+
+        // Then everything is back once both are removed
+        let chunk = chunks.get_mut(&(0, 0)).unwrap();
+        chunk.destroy_cube(Vector3::new(4., 5., 4.));
+        chunk.destroy_cube(Vector3::new(4., 1., 4.));
+        lighting.update_cell([4, 5, 4], &chunks);
+        lighting.update_cell([4, 1, 4], &chunks);
+        assert_eq!(lighting.light_at([4, 4, 4]).0, MAX_LIGHT);
+        assert_eq!(lighting.light_at([4, 1, 5]).1, 0);
+    }
+
+    #[test]
+    fn test_light_through_glass_and_leaves() {
+        let mut chunk = Chunk::new([0., 0.]);
+        chunk.fill_layer(0, GRASS);
+        let top = CHUNK_HEIGHT as f32 - 2.;
+        chunk.add_cube(Vector3::new(2., top, 2.), GLASS, 0);
+        chunk.add_cube(Vector3::new(6., top, 6.), OAKLEAVES, 0);
+        let chunks = HashMap::from([(chunk.coord(), chunk)]);
+        let lighting = lit(&chunks);
+
+        // The glass lets all the sky light through
+        assert_eq!(lighting.light_at([2, 1, 2]).0, MAX_LIGHT);
+        // The leaves dim it, and the light from the open columns around takes over
+        let top = top as i32;
+        assert_eq!(lighting.light_at([6, top, 6]).0, MAX_LIGHT - 1);
+        assert_eq!(lighting.light_at([6, top - 1, 6]).0, MAX_LIGHT - 1);
+        assert_eq!(lighting.light_at([6, 1, 6]).0, MAX_LIGHT - 1);
+    }
+}
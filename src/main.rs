@@ -2,39 +2,40 @@ use crafty::args::WorldInitializer;
 use crafty::network::proxy::Proxy;
 use crafty::network::single_player_proxy::SinglePlayerProxy;
 use crafty::server::game_server::{handle_entity_thread, GameServer};
-use crafty::world::World;
+use crafty::persistence::SaveDir;
 use crafty::world_renderer::WorldRenderer;
-use crafty::{player::Player, world_generation::world_generator::WorldGenerator};
+use crafty::world_selection::{open_world, WorldChoice, WorldLauncher};
 use std::sync::{Arc, Mutex};
 
 fn main() {
-    // Create the initial world
-    let init = WorldInitializer::from_args();
-    
-    println!("Loading world using : {:?}", init);
-    println!("[Server] Creating a world ...");
-    let world = match init {
-        WorldInitializer::RANDOM => WorldGenerator::create_new_random_world(5),
-        WorldInitializer::FLAT => WorldGenerator::create_new_flat_world(10),
-        WorldInitializer::DISK => World::from_file("map.json").unwrap_or(WorldGenerator::create_new_random_world(10)),
-    };
-    println!("                          ... Finished !");
+    // The world is chosen in the menus, its server is started then
+    let launcher: WorldLauncher = Box::new(|choice| {
+        println!("[Server] Opening the world {choice:?} ...");
+        let (world, saved_players, regions) = open_world(&choice, &SaveDir::default())?;
+        println!("                          ... Finished !");
 
-    // The server holds the 'full' world
-    let server = Arc::new(Mutex::new(GameServer::new(world)));
+        // The server holds the 'full' world
+        let mut server = GameServer::new(world);
+        server.restore_players(saved_players);
+        server.set_regions(regions);
+        let server = Arc::new(Mutex::new(server));
 
-    // Spawn the entity thead
-    let ref1 = server.clone();
-    std::thread::spawn(move || handle_entity_thread(ref1));
+        // Spawn the entity thead
+        let ref1 = server.clone();
+        std::thread::spawn(move || handle_entity_thread(ref1));
 
-    // The proxy currently holds the server,
-    let mut proxy = SinglePlayerProxy::new(server);
-    proxy.login("local_client".to_string());
+        // The proxy currently holds the server,
+        let mut proxy = SinglePlayerProxy::new(server);
+        proxy.login("local_client".to_string());
+        Ok(Arc::new(Mutex::new(proxy)) as Arc<Mutex<dyn Proxy>>)
+    });
 
-    // The client is initialized with an empty world, as it will be the responsibility of the server
+    // The client starts without any world, as it will be the responsibility of the server
     // to provide it with the chunks.
-    // Currently, the client 'owns' the proxy, this is really the part that sucks for now.
-    let mut renderer =
-        WorldRenderer::new(Arc::new(Mutex::new(proxy)), World::empty(), Player::new());
+    let mut renderer = WorldRenderer::with_world_launcher(launcher);
+    // A world given on the command line skips the selection screen
+    if let Some(init) = WorldInitializer::from_flags() {
+        renderer.set_initial_world(WorldChoice::from_initializer(&init, &SaveDir::default()));
+    }
     renderer.run();
 }
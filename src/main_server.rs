@@ -1,9 +1,16 @@
 use crafty::args::{Args, WorldInitializer};
 use crafty::network::tcp_server::TcpServer;
 use crafty::server::game_server::{handle_entity_thread, GameServer};
+use crafty::persistence::SaveDir;
 use crafty::world::World;
-use crafty::world_generation::world_generator::WorldGenerator;
+use crafty::world_meta::WorldMeta;
+use crafty::world_generation::preset::GeneratorPreset;
+use crafty::world_generation::world_generator::DEFAULT_SEED;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Line to write in the console of the server to stop it cleanly
+const STOP_COMMAND: &str = "/stop";
 
 
 fn main() {
@@ -11,22 +18,56 @@ fn main() {
 
     // Create the initial world
     println!("[Server] Creating a world ...");
+    let mut saved_players = Vec::new();
+    let mut regions = None;
+    let seed = args.seed.unwrap_or(DEFAULT_SEED);
     let world = match args.init {
-        WorldInitializer::RANDOM => WorldGenerator::create_new_random_world(10),
-        WorldInitializer::FLAT => WorldGenerator::create_new_flat_world(10),
-        WorldInitializer::DISK => World::from_file("map.json").unwrap(),
+        WorldInitializer::RANDOM => GeneratorPreset::Random.generate_around(seed, 10),
+        WorldInitializer::FLAT => GeneratorPreset::Flat.generate_around(seed, 10),
+        WorldInitializer::TERRAIN => GeneratorPreset::Terrain.generate_around(seed, 10),
+        WorldInitializer::AMPLIFIED => GeneratorPreset::Amplified.generate_around(seed, 10),
+        WorldInitializer::DISK => match SaveDir::new(&args.saves_dir).load(&WorldMeta::default().name) {
+            Ok((world, players)) => {
+                saved_players = players;
+                regions = Some(SaveDir::new(&args.saves_dir).regions(&world.meta().name));
+                world
+            }
+            // Worlds saved before the save directories
+            Err(_) => World::from_file("map.json").unwrap(),
+        },
     };
     println!("                          ... Finished !");
 
     // Create the game model of the server.
     // It holds the 'full' world
     // It is put inside an ARC to be shared across each thread, and inside a Mute to have interior mutability.
-    let game = Arc::new(Mutex::new(GameServer::new(world)));
+    let mut game = GameServer::new(world);
+    game.restore_players(saved_players);
+    game.set_autosave_interval(args.autosave.map(Duration::from_secs));
+    game.set_autosave_dir(SaveDir::new(&args.saves_dir));
+    game.set_save_on_exit(args.save_on_exit);
+    if let Some(regions) = regions {
+        game.set_regions(regions);
+    }
+    let game = Arc::new(Mutex::new(game));
     
     // Spawn the entity thead
     let ref1 = game.clone();
     std::thread::spawn(move || handle_entity_thread(ref1));
     
+    // The lines written in the console are sent to the players, except the one stopping the server
+    let ref2 = game.clone();
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lines().map_while(Result::ok) {
+            if line.trim() == STOP_COMMAND {
+                println!("[Server] Stopping ...");
+                ref2.lock().unwrap().on_exit();
+                std::process::exit(0);
+            }
+            ref2.lock().unwrap().announce(line);
+        }
+    });
+
     // Starts the TCP server
     TcpServer::start(&args.url(), game)
 }
@@ -4,4 +4,5 @@ pub mod tcp_proxy;
 pub mod tcp_server;
 pub mod proxy;
 pub mod single_player_proxy;
-mod tcp_message_encoding;
\ No newline at end of file
+mod tcp_message_encoding;
+pub mod prediction;
\ No newline at end of file
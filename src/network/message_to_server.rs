@@ -1,7 +1,8 @@
 use crate::actions::Action;
-use crate::network::message_to_server::MessageToServer::{Attack, Login, OnNewAction, OnNewPosition, SpawnRequest};
-use crate::network::tcp_message_encoding::{TcpDeserialize, TcpSerialize};
+use crate::network::message_to_server::MessageToServer::{Attack, Chat, HitEntity, Login, OnNewAction, OnNewPosition, Respawn, SpawnRequest};
+use crate::network::tcp_message_encoding::{read_u32, TcpDeserialize, TcpSerialize};
 use crate::primitives::position::Position;
+use crate::primitives::vector::Vector3;
 use std::str::from_utf8;
 use crate::attack::EntityAttack;
 
@@ -10,51 +11,83 @@ use crate::attack::EntityAttack;
 pub enum MessageToServer {
     /// Ask the server to log in a new player with a given identifer
     Login(String),
-    OnNewPosition(Position),
+    /// The position of the player predicted by the client, with its sequence number (see `MovePrediction`)
+    OnNewPosition(u32, Position),
     OnNewAction(Action),
     Attack(EntityAttack),
     SpawnRequest(Position),
+    /// A line written in the chat, to be relayed to every player
+    Chat(String),
+    /// A hit on an entity of the world of the server (see `ServerUpdate::WorldEntity`), with its damage and its direction
+    HitEntity(u32, u8, Vector3),
+    /// Asks the server to put the dead player back at the spawn point, see `ServerUpdate::Respawn`
+    Respawn,
 }
 
 impl TcpSerialize for MessageToServer {
     fn to_u8(&self) -> u8 {
         match self {
             Login(_) => 0,
-            OnNewPosition(_) => 1,
+            OnNewPosition(_, _) => 1,
             OnNewAction(_) => 2,
             Attack(_) => 3,
             SpawnRequest(_) => 4,
+            Chat(_) => 5,
+            HitEntity(_, _, _) => 6,
+            Respawn => 7,
         }
     }
 
     fn to_bytes_representation(&self) -> Vec<u8> {
         match self {
-            Login(name) => name.clone().into_bytes(),
-            OnNewPosition(pos) | SpawnRequest(pos) => pos.to_bytes(),
+            Login(name) | Chat(name) => name.clone().into_bytes(),
+            OnNewPosition(sequence, pos) => {
+                let mut bytes = sequence.to_be_bytes().to_vec();
+                bytes.extend_from_slice(&pos.to_bytes());
+                bytes
+            }
+            SpawnRequest(pos) => pos.to_bytes(),
             OnNewAction(action) => action.to_bytes(),
-            Attack(attack) => attack.to_bytes()
+            Attack(attack) => attack.to_bytes(),
+            HitEntity(id, damage, direction) => {
+                let mut bytes = id.to_be_bytes().to_vec();
+                bytes.push(*damage);
+                bytes.extend_from_slice(&direction.to_bytes());
+                bytes
+            }
+            Respawn => Vec::new(),
         }
     }
 }
 
 impl TcpDeserialize for MessageToServer {
-    fn parse_bytes_representation(code: u8, bytes_to_parse: &[u8]) -> Self {
-        match code {
-            0 => Login(from_utf8(bytes_to_parse).unwrap().to_string()),
-            1 => OnNewPosition(Position::from_bytes(bytes_to_parse)),
-            2 => OnNewAction(Action::from_str(from_utf8(bytes_to_parse).unwrap())),
-            3 => Attack(EntityAttack::from_bytes(bytes_to_parse)),
-            4 => SpawnRequest(Position::from_bytes(bytes_to_parse)),
-            _ => panic!("Cannot build message to server from code {code}")
-        }
+    fn parse_bytes_representation(code: u8, bytes_to_parse: &[u8]) -> Option<Self> {
+        let message = match code {
+            0 => Login(from_utf8(bytes_to_parse).ok()?.to_string()),
+            1 => {
+                let sequence = read_u32(bytes_to_parse, 0)?;
+                OnNewPosition(sequence, Position::from_bytes(&bytes_to_parse[4..])?)
+            }
+            2 => OnNewAction(Action::from_str(from_utf8(bytes_to_parse).ok()?)?),
+            3 => Attack(EntityAttack::from_bytes(bytes_to_parse)?),
+            4 => SpawnRequest(Position::from_bytes(bytes_to_parse)?),
+            5 => Chat(String::from_utf8_lossy(bytes_to_parse).to_string()),
+            6 => {
+                let id = read_u32(bytes_to_parse, 0)?;
+                HitEntity(id, *bytes_to_parse.get(4)?, Vector3::from_bytes(&bytes_to_parse[5..])?)
+            }
+            7 => Respawn,
+            _ => return None,
+        };
+        Some(message)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::network::message_to_server::MessageToServer;
-    use crate::network::message_to_server::MessageToServer::{Login, OnNewPosition};
-    use crate::network::tcp_message_encoding::{from_tcp_repr, to_tcp_repr, ParseContext};
+    use crate::network::message_to_server::MessageToServer::{Chat, HitEntity, Login, OnNewPosition, Respawn};
+    use crate::network::tcp_message_encoding::{from_tcp_repr, to_tcp_repr, ParseContext, TcpDeserialize};
     use crate::primitives::position::Position;
     use crate::primitives::vector::Vector3;
 
@@ -68,8 +101,24 @@ mod tests {
     #[test]
     fn test_message_integrity() {
         test_integrity(Login("arthur".to_string()));
-        test_integrity(OnNewPosition(Position::new_vec(1.0, 1.0, 1.0)));
-        test_integrity(OnNewPosition(Position::new_vec(-1.0, 2.0, 100.012)));
+        test_integrity(OnNewPosition(0, Position::new_vec(1.0, 1.0, 1.0)));
+        test_integrity(OnNewPosition(u32::MAX, Position::new_vec(-1.0, 2.0, 100.012)));
+        test_integrity(Chat("hello, world!".to_string()));
+        test_integrity(HitEntity(70000, 7, Vector3::new(0.6, -0.2, -0.8)));
+        test_integrity(Respawn);
+    }
+
+    #[test]
+    fn test_malformed_messages() {
+        // The text of the chat is kept, whatever its encoding
+        let mut context = ParseContext::new();
+        let parsed = from_tcp_repr::<MessageToServer>(&[5, 2, 0, 0, 0, 0xff, b'a'], &mut context).unwrap();
+        assert_eq!(parsed[0], Chat("\u{FFFD}a".to_string()));
+
+        assert!(MessageToServer::parse_bytes_representation(1, &[0, 0]).is_none());
+        assert!(MessageToServer::parse_bytes_representation(6, &[0, 0, 0, 1]).is_none());
+        assert!(MessageToServer::parse_bytes_representation(3, &[1]).is_none());
+        assert!(from_tcp_repr::<MessageToServer>(&[1, 2, 0, 0, 0, 0, 0], &mut context).is_err());
     }
 
     fn test_multiple_messages(messages: &[MessageToServer]) {
@@ -90,8 +139,9 @@ mod tests {
     fn test_multiple_message_integrity() {
         let p1 = Vector3::new(1., 2., 3.);
         let p2 = Vector3::new(5., 6., 7.);
-        test_multiple_messages(&[OnNewPosition(Position::from_pos(p1.clone())), OnNewPosition(Position::from_pos(p2))]);
-        test_multiple_messages(&[Login("hey".to_string()), OnNewPosition(Position::from_pos(p1))]);
+        test_multiple_messages(&[OnNewPosition(1, Position::from_pos(p1.clone())), OnNewPosition(2, Position::from_pos(p2))]);
+        test_multiple_messages(&[Login("hey".to_string()), OnNewPosition(3, Position::from_pos(p1))]);
+        test_multiple_messages(&[Chat("hey".to_string()), Chat(String::new()), Login("arthur".to_string())]);
     }
 }
 
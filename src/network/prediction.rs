@@ -0,0 +1,122 @@
+use std::collections::VecDeque;
+
+use crate::primitives::vector::Vector3;
+
+/// Distance [cube] between the position predicted by the client and the one kept by the server
+/// above which the client is corrected
+pub const RECONCILE_TOLERANCE: f32 = 0.25;
+/// Most moves waiting for their acknowledgment, the oldest ones are forgotten
+const MAX_PENDING: usize = 256;
+
+/// The moves of the player, simulated by the client without waiting for the server.
+/// Each position sent to the server is tagged with a sequence number, and the server answers with the position
+/// it kept for it (see `ServerUpdate::PositionAck`). When they differ, for instance because of a block the client
+/// did not know about yet, the client is moved by the difference, and so are the moves sent since.
+pub struct MovePrediction {
+    next_sequence: u32,
+    /// The positions sent and not acknowledged yet, with their sequence number, the oldest first
+    pending: VecDeque<(u32, Vector3)>,
+}
+
+impl MovePrediction {
+    pub fn new() -> Self {
+        Self { next_sequence: 0, pending: VecDeque::new() }
+    }
+
+    /// Records the position of the player predicted by the client, returns the sequence number to send it with
+    pub fn predict(&mut self, position: Vector3) -> u32 {
+        let sequence = self.next_sequence;
+        self.next_sequence = self.next_sequence.wrapping_add(1);
+        self.pending.push_back((sequence, position));
+        if self.pending.len() > MAX_PENDING {
+            self.pending.pop_front();
+        }
+        sequence
+    }
+
+    /// Compares the position predicted for this move with the one kept by the server, further than `tolerance`
+    /// (in the same units). Returns the correction to add to the position of the player, if any.
+    /// The moves up to this one are acknowledged.
+    pub fn reconcile(&mut self, sequence: u32, authoritative: Vector3, tolerance: f32) -> Option<Vector3> {
+        // Already acknowledged, or forgotten
+        if !self.pending.iter().any(|(pending, _)| *pending == sequence) {
+            return None;
+        }
+        while let Some((pending, predicted)) = self.pending.pop_front() {
+            if pending != sequence {
+                continue;
+            }
+            let error = authoritative - predicted;
+            if error.norm() <= tolerance {
+                return None;
+            }
+            // The moves sent since started from the wrong position
+            for (_, later) in self.pending.iter_mut() {
+                *later += error;
+            }
+            return Some(error);
+        }
+        None
+    }
+
+    /// Forgets the moves waiting for their acknowledgment, when the server moved the player (a respawn).
+    /// The sequence numbers go on, so that the acknowledgments of these moves are ignored.
+    pub fn clear(&mut self) {
+        self.pending.clear();
+    }
+
+    /// Number of moves waiting for their acknowledgment
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::network::prediction::MovePrediction;
+    use crate::primitives::vector::Vector3;
+
+    #[test]
+    fn test_acknowledged_moves() {
+        let mut prediction = MovePrediction::new();
+        let first = prediction.predict(Vector3::new(0., 0., 0.));
+        let second = prediction.predict(Vector3::new(1., 0., 0.));
+        prediction.predict(Vector3::new(2., 0., 0.));
+        assert_eq!(prediction.pending(), 3);
+
+        // The server agrees, within the tolerance
+        assert_eq!(prediction.reconcile(second, Vector3::new(1.1, 0., 0.), 0.25), None);
+        assert_eq!(prediction.pending(), 1);
+        // Too late
+        assert_eq!(prediction.reconcile(first, Vector3::new(5., 0., 0.), 0.25), None);
+        assert_eq!(prediction.pending(), 1);
+    }
+
+    #[test]
+    fn test_blocked_move_is_corrected() {
+        let mut prediction = MovePrediction::new();
+        let blocked = prediction.predict(Vector3::new(3., 0., 0.));
+        let later = prediction.predict(Vector3::new(4., 0., 0.));
+
+        // A wall stopped the player at x = 2
+        let correction = prediction.reconcile(blocked, Vector3::new(2., 0., 0.), 0.25).unwrap();
+        assert_eq!(correction, Vector3::new(-1., 0., 0.));
+
+        // The next move was corrected with it, the server stops it at the same wall
+        assert_eq!(prediction.reconcile(later, Vector3::new(3., 0., 0.), 0.25), None);
+        assert_eq!(prediction.pending(), 0);
+    }
+
+    #[test]
+    fn test_moves_forgotten_at_respawn() {
+        let mut prediction = MovePrediction::new();
+        let before = prediction.predict(Vector3::new(50., 0., 0.));
+        prediction.clear();
+        let after = prediction.predict(Vector3::new(0., 0., 0.));
+        assert_ne!(before, after);
+
+        // The server rejected the move far from the spawn point, the player is not moved back
+        assert_eq!(prediction.reconcile(before, Vector3::new(0., 0., 0.), 0.25), None);
+        assert_eq!(prediction.pending(), 1);
+    }
+}
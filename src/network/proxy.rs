@@ -2,15 +2,29 @@ use crate::actions::Action;
 use crate::attack::EntityAttack;
 use crate::network::server_update::ServerUpdate;
 use crate::primitives::position::Position;
+use crate::primitives::vector::Vector3;
 
 /// Defines the interface that a client use to communicate with the game server
 pub trait Proxy {
     fn login(&mut self, name: String);
-    fn send_position_update(&mut self, position: Position);
+    /// Sends the position predicted by the client, the server acknowledges it with its sequence number
+    fn send_position_update(&mut self, sequence: u32, position: Position);
     fn on_new_action(&mut self, action: Action);
     fn on_new_attack(&mut self, attack: EntityAttack);
     fn request_to_spawn(&mut self, position: Position);
+    /// Sends a line written in the chat, the server relays it to every player
+    fn send_chat(&mut self, text: String);
+    /// Sends a hit on an entity of the world of the server, with its id on the server, see `EntityStore::take_remote_hits`
+    fn hit_entity(&mut self, id: u32, damage: u8, direction: Vector3);
+    /// Asks the server to put the dead player back at the spawn point, it answers with `ServerUpdate::Respawn`
+    fn request_respawn(&mut self);
     fn consume_server_updates(&mut self) -> Vec<ServerUpdate>;
     /// Returns the delay to wait for at startup
     fn loading_delay(&self) -> u64;
+    /// Freezes the world while the game is paused, when the server only runs for this client
+    fn set_paused(&mut self, paused: bool);
+    /// Saves the world, when the server only runs for this client
+    fn save_world(&mut self);
+    /// Sets the number of chunks sent in each direction around the player, when the server only runs for this client
+    fn set_render_distance(&mut self, render_distance: i32);
 }
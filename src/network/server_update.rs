@@ -1,12 +1,15 @@
 use crate::actions::Action;
-use crate::chunk::Chunk;
+use crate::chunk::{Chunk, ChunkCoord};
 use crate::entity::entity::EntityKind;
-use crate::network::server_update::ServerUpdate::{Attack, LoadChunk, LoggedIn, RegisterEntity, SendAction, UpdatePosition, RemoveEntity};
-use crate::network::tcp_message_encoding::{TcpDeserialize, TcpSerialize};
+use crate::entity::replica::ReplicaKind;
+use crate::network::server_update::ServerUpdate::{Attack, Chat, DropLoot, LoadChunk, LoggedIn, PositionAck, RegisterEntity, RegisterPlayer, RemoveWorldEntity, Respawn, SendAction, UpdatePosition, RemoveEntity, UnloadChunk, WorldEntity};
+use crate::network::tcp_message_encoding::{read_u32, TcpDeserialize, TcpSerialize};
 
 use std::str::from_utf8;
 use crate::attack::EntityAttack;
+use crate::item::Item;
 use crate::primitives::position::Position;
+use crate::primitives::vector::Vector3;
 
 pub const RESPONSE_OK: u8 = 100;
 pub const RESPONSE_ERROR: u8 = 101;
@@ -27,7 +30,25 @@ pub enum ServerUpdate {
     /// Attack to suffer... :(
     Attack(EntityAttack),
     /// Remove an entity
-    RemoveEntity(u32)
+    RemoveEntity(u32),
+    /// Ask the client to unload the chunk at this position, it is too far from the player
+    UnloadChunk(ChunkCoord),
+    /// A line of the chat, with the name of its sender
+    Chat(String, String),
+    /// The position the server kept for the move of the player with this sequence number, see `MovePrediction`
+    PositionAck(u32, Position),
+    /// Tell the client that another player, with this name, is part of the game
+    RegisterPlayer(u8, String, Position),
+    /// Where an entity of the world of the server is (a falling block...), with the bottom of its body in cube
+    /// coordinates. The client adds it the first time, see `EntityStore::spawn_remote`.
+    WorldEntity(u32, ReplicaKind, Position),
+    /// Remove an entity of the world of the server
+    RemoveWorldEntity(u32),
+    /// The items left where an entity of the world of the server was killed by the player [cube],
+    /// see `MessageToServer::HitEntity`
+    DropLoot(Vector3, Vec<Item>),
+    /// Where the server put the player after its death, the client moves it there, see `MessageToServer::Respawn`
+    Respawn(Position),
 }
 
 impl ServerUpdate {
@@ -48,6 +69,14 @@ impl TcpSerialize for ServerUpdate {
             UpdatePosition(_, _) => 4,
             Attack(_) => 5,
             RemoveEntity(_) => 6,
+            UnloadChunk(_) => 7,
+            Chat(_, _) => 8,
+            PositionAck(_, _) => 9,
+            RegisterPlayer(_, _, _) => 10,
+            WorldEntity(_, _, _) => 11,
+            RemoveWorldEntity(_) => 12,
+            DropLoot(_, _) => 13,
+            Respawn(_) => 14,
         }
     }
 
@@ -68,51 +97,126 @@ impl TcpSerialize for ServerUpdate {
                 bytes
             }
             Attack(attack) => attack.to_bytes(),
-            RemoveEntity(id) => id.to_be_bytes().to_vec(),
+            RemoveEntity(id) | RemoveWorldEntity(id) => id.to_be_bytes().to_vec(),
+            UnloadChunk((x, z)) => {
+                let mut bytes = x.to_be_bytes().to_vec();
+                bytes.extend_from_slice(&z.to_be_bytes());
+                bytes
+            }
+            Chat(sender, text) => {
+                // The length of the name comes first, to split it from the text
+                let name = &sender.as_bytes()[..sender.len().min(u8::MAX as usize)];
+                let mut bytes = vec![name.len() as u8];
+                bytes.extend_from_slice(name);
+                bytes.extend_from_slice(text.as_bytes());
+                bytes
+            }
+            PositionAck(sequence, pos) => {
+                let mut bytes = sequence.to_be_bytes().to_vec();
+                bytes.extend_from_slice(&pos.to_bytes());
+                bytes
+            }
+            RegisterPlayer(id, name, pos) => {
+                // The length of the name comes after the id, to split it from the position
+                let name = &name.as_bytes()[..name.len().min(u8::MAX as usize)];
+                let mut bytes = vec![*id, name.len() as u8];
+                bytes.extend_from_slice(name);
+                bytes.extend_from_slice(&pos.to_bytes());
+                bytes
+            }
+            WorldEntity(id, kind, pos) => {
+                // The length of the kind comes after the id, to split it from the position
+                let kind = kind.to_bytes();
+                let mut bytes = id.to_be_bytes().to_vec();
+                bytes.push(kind.len() as u8);
+                bytes.extend_from_slice(&kind);
+                bytes.extend_from_slice(&pos.to_bytes());
+                bytes
+            }
+            DropLoot(at, loot) => serde_json::to_vec(&(at, loot)).unwrap(),
+            Respawn(pos) => pos.to_bytes(),
         }
     }
 }
 
 impl TcpDeserialize for ServerUpdate {
-    fn parse_bytes_representation(code: u8, bytes_to_parse: &[u8]) -> ServerUpdate {
-        match code {
+    fn parse_bytes_representation(code: u8, bytes_to_parse: &[u8]) -> Option<ServerUpdate> {
+        let update = match code {
             0 => {
-                let as_json = from_utf8(bytes_to_parse).unwrap();
-                let chunk = Chunk::from_json(as_json);
-                match chunk {
+                let as_json = from_utf8(bytes_to_parse).ok()?;
+                match Chunk::from_json(as_json) {
                     Ok(chunk) => LoadChunk(chunk),
-                    Err(err) => panic!("Error while parsing a chunk: {err}")
+                    Err(err) => {
+                        println!("Error while parsing a chunk: {err}");
+                        return None;
+                    }
                 }
             }
             1 => {
-                LoggedIn(bytes_to_parse[0], Position::from_bytes(&bytes_to_parse[1..]))
+                LoggedIn(*bytes_to_parse.first()?, Position::from_bytes(&bytes_to_parse[1..])?)
             }
             2 => {
-                let as_json = from_utf8(bytes_to_parse).unwrap();
-                let action = Action::from_str(as_json);
-                SendAction(action)
+                let as_json = from_utf8(bytes_to_parse).ok()?;
+                SendAction(Action::from_str(as_json)?)
             }
             3 => {
-                RegisterEntity(bytes_to_parse[0], EntityKind::from_u8(bytes_to_parse[1]), Position::from_bytes(&bytes_to_parse[2..]))
+                RegisterEntity(*bytes_to_parse.first()?, EntityKind::from_u8(*bytes_to_parse.get(1)?), Position::from_bytes(&bytes_to_parse[2..])?)
             }
             4 => {
-                UpdatePosition(bytes_to_parse[0], Position::from_bytes(&bytes_to_parse[1..]))
+                UpdatePosition(*bytes_to_parse.first()?, Position::from_bytes(&bytes_to_parse[1..])?)
             }
             5 => {
-                Attack(EntityAttack::from_bytes(bytes_to_parse))
+                Attack(EntityAttack::from_bytes(bytes_to_parse)?)
             }
-            6 => RemoveEntity(u32::from_be_bytes([bytes_to_parse[0], bytes_to_parse[1], bytes_to_parse[2], bytes_to_parse[3]])),
-            _ => panic!("Cannot build server update from code {code}")
-        }
+            6 => RemoveEntity(read_u32(bytes_to_parse, 0)?),
+            7 => {
+                let x = read_u32(bytes_to_parse, 0)? as i32;
+                let z = read_u32(bytes_to_parse, 4)? as i32;
+                UnloadChunk((x, z))
+            }
+            8 => {
+                let name_end = 1 + *bytes_to_parse.first()? as usize;
+                let sender = String::from_utf8_lossy(bytes_to_parse.get(1..name_end)?).to_string();
+                let text = String::from_utf8_lossy(&bytes_to_parse[name_end..]).to_string();
+                Chat(sender, text)
+            }
+            9 => {
+                let sequence = read_u32(bytes_to_parse, 0)?;
+                PositionAck(sequence, Position::from_bytes(&bytes_to_parse[4..])?)
+            }
+            10 => {
+                let name_end = 2 + *bytes_to_parse.get(1)? as usize;
+                let name = String::from_utf8_lossy(bytes_to_parse.get(2..name_end)?).to_string();
+                RegisterPlayer(bytes_to_parse[0], name, Position::from_bytes(&bytes_to_parse[name_end..])?)
+            }
+            11 => {
+                let id = read_u32(bytes_to_parse, 0)?;
+                let kind_end = 5 + *bytes_to_parse.get(4)? as usize;
+                let kind = ReplicaKind::from_bytes(bytes_to_parse.get(5..kind_end)?)?;
+                WorldEntity(id, kind, Position::from_bytes(&bytes_to_parse[kind_end..])?)
+            }
+            12 => RemoveWorldEntity(read_u32(bytes_to_parse, 0)?),
+            13 => {
+                let (at, loot) = serde_json::from_slice(bytes_to_parse).ok()?;
+                DropLoot(at, loot)
+            }
+            14 => Respawn(Position::from_bytes(bytes_to_parse)?),
+            _ => return None,
+        };
+        Some(update)
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::block_kind::Block;
     use crate::chunk::Chunk;
+    use crate::entity::replica::ReplicaKind;
+    use crate::food::Food;
+    use crate::item::Item;
     use crate::network::server_update::ServerUpdate;
-    use crate::network::server_update::ServerUpdate::{LoadChunk, LoggedIn, RegisterEntity, RemoveEntity};
-    use crate::network::tcp_message_encoding::{from_tcp_repr, to_tcp_repr, ParseContext};
+    use crate::network::server_update::ServerUpdate::{Chat, DropLoot, LoadChunk, LoggedIn, PositionAck, RegisterEntity, RegisterPlayer, RemoveEntity, RemoveWorldEntity, Respawn, UnloadChunk, WorldEntity};
+    use crate::network::tcp_message_encoding::{from_tcp_repr, to_tcp_repr, ParseContext, TcpDeserialize};
     use crate::primitives::position::Position;
     use crate::primitives::vector::Vector3;
 
@@ -221,4 +325,119 @@ mod tests {
         let parsed: Vec<ServerUpdate> = from_tcp_repr(packet3, &mut context).unwrap();
         assert_eq!(1, parsed.len())
     }
+
+    #[test]
+    fn test_unload_chunk_encoding_decoding() {
+        let update = UnloadChunk((-32, 48));
+        let bytes = to_tcp_repr(&update);
+        let mut context = ParseContext::new();
+        let parsed = from_tcp_repr::<ServerUpdate>(bytes.as_slice(), &mut context).unwrap();
+
+        match &parsed[0] {
+            UnloadChunk(coord) => assert_eq!(*coord, (-32, 48)),
+            _ => assert!(false)
+        }
+    }
+
+    #[test]
+    fn test_chat_encoding_decoding() {
+        let mut bytes = to_tcp_repr(&Chat("arthur".to_string(), "hello, johan".to_string()));
+        bytes.extend(to_tcp_repr(&Chat("johan".to_string(), String::new())));
+        let mut context = ParseContext::new();
+        let parsed = from_tcp_repr::<ServerUpdate>(bytes.as_slice(), &mut context).unwrap();
+
+        match (&parsed[0], &parsed[1]) {
+            (Chat(name1, text1), Chat(name2, text2)) => {
+                assert_eq!((name1.as_str(), text1.as_str()), ("arthur", "hello, johan"));
+                assert_eq!((name2.as_str(), text2.as_str()), ("johan", ""));
+            }
+            _ => assert!(false)
+        }
+    }
+
+    #[test]
+    fn test_register_player_encoding_decoding() {
+        let position = Position::new(Vector3::new(1., -2., 3.5), 0.5, 0.);
+        let bytes = to_tcp_repr(&RegisterPlayer(4, "arnaud".to_string(), position.clone()));
+        let mut context = ParseContext::new();
+        let parsed = from_tcp_repr::<ServerUpdate>(bytes.as_slice(), &mut context).unwrap();
+
+        match &parsed[0] {
+            RegisterPlayer(id, name, parsed_position) => {
+                assert_eq!((*id, name.as_str()), (4, "arnaud"));
+                assert_eq!(*parsed_position, position);
+            }
+            _ => assert!(false)
+        }
+    }
+
+    #[test]
+    fn test_position_ack_encoding_decoding() {
+        let position = Position::new(Vector3::new(-3., 2., 34.532), 0.5, -0.25);
+        let mut bytes = to_tcp_repr(&PositionAck(70000, position.clone()));
+        bytes.extend(to_tcp_repr(&Respawn(position.clone())));
+        let mut context = ParseContext::new();
+        let parsed = from_tcp_repr::<ServerUpdate>(bytes.as_slice(), &mut context).unwrap();
+
+        match (&parsed[0], &parsed[1]) {
+            (PositionAck(sequence, parsed_position), Respawn(spawn)) => {
+                assert_eq!(*sequence, 70000);
+                assert_eq!(*parsed_position, position);
+                assert_eq!(*spawn, position);
+            }
+            _ => assert!(false)
+        }
+    }
+
+    #[test]
+    fn test_world_entity_encoding_decoding() {
+        let position = Position::new(Vector3::new(2.5, 17.25, -3.5), 0., 0.);
+        let mut bytes = to_tcp_repr(&WorldEntity(70000, ReplicaKind::FallingBlock(Block::GRAVEL), position.clone()));
+        bytes.extend(to_tcp_repr(&RemoveWorldEntity(70000)));
+        let mut context = ParseContext::new();
+        let parsed = from_tcp_repr::<ServerUpdate>(bytes.as_slice(), &mut context).unwrap();
+
+        match (&parsed[0], &parsed[1]) {
+            (WorldEntity(id, kind, parsed_position), RemoveWorldEntity(removed)) => {
+                assert_eq!((*id, *kind), (70000, ReplicaKind::FallingBlock(Block::GRAVEL)));
+                assert_eq!(*parsed_position, position);
+                assert_eq!(*removed, 70000);
+            }
+            _ => assert!(false)
+        }
+    }
+
+    #[test]
+    fn test_malformed_updates_are_rejected() {
+        // A name longer than the message, an id cut short, a kind longer than the message
+        assert!(ServerUpdate::parse_bytes_representation(8, &[10, b'a']).is_none());
+        assert!(ServerUpdate::parse_bytes_representation(8, &[]).is_none());
+        assert!(ServerUpdate::parse_bytes_representation(11, &[0, 0, 1, 0, 50, b'{']).is_none());
+        assert!(ServerUpdate::parse_bytes_representation(11, &[0, 0]).is_none());
+        assert!(ServerUpdate::parse_bytes_representation(12, &[0, 1]).is_none());
+        assert!(ServerUpdate::parse_bytes_representation(10, &[4]).is_none());
+        assert!(ServerUpdate::parse_bytes_representation(10, &[4, 20, b'a', b'r']).is_none());
+        assert!(ServerUpdate::parse_bytes_representation(200, &[]).is_none());
+
+        // The stream reports them instead of panicking
+        let mut context = ParseContext::new();
+        assert!(from_tcp_repr::<ServerUpdate>(&[12, 2, 0, 0, 0, 0, 1], &mut context).is_err());
+        let parsed = from_tcp_repr::<ServerUpdate>(to_tcp_repr(&RemoveWorldEntity(3)).as_slice(), &mut context).unwrap();
+        assert!(matches!(parsed[0], RemoveWorldEntity(3)));
+    }
+
+    #[test]
+    fn test_drop_loot_encoding_decoding() {
+        let bytes = to_tcp_repr(&DropLoot(Vector3::new(2.5, 1.45, -3.5), vec![Item::Food(Food::Porkchop); 2]));
+        let mut context = ParseContext::new();
+        let parsed = from_tcp_repr::<ServerUpdate>(bytes.as_slice(), &mut context).unwrap();
+
+        match &parsed[0] {
+            DropLoot(at, loot) => {
+                assert_eq!(*at, Vector3::new(2.5, 1.45, -3.5));
+                assert_eq!(*loot, vec![Item::Food(Food::Porkchop); 2]);
+            }
+            _ => assert!(false)
+        }
+    }
 }
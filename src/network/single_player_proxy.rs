@@ -4,7 +4,9 @@ use crate::attack::EntityAttack;
 use crate::server::game_server::GameServer;
 use crate::network::proxy::Proxy;
 use crate::network::server_update::ServerUpdate;
+use crate::persistence::SaveDir;
 use crate::primitives::position::Position;
+use crate::primitives::vector::Vector3;
 
 
 pub struct SinglePlayerProxy {
@@ -27,8 +29,8 @@ impl Proxy for SinglePlayerProxy {
         self.client_id = self.server.lock().unwrap().login(name);
     }
 
-    fn send_position_update(&mut self, position: Position) {
-        self.server.lock().unwrap().on_new_position_update(self.client_id, position);
+    fn send_position_update(&mut self, sequence: u32, position: Position) {
+        self.server.lock().unwrap().on_new_position_update(self.client_id, sequence, position);
     }
 
     fn on_new_action(&mut self, action: Action) {
@@ -43,6 +45,18 @@ impl Proxy for SinglePlayerProxy {
         self.server.lock().unwrap().spawn_monster(position);
     }
 
+    fn send_chat(&mut self, text: String) {
+        self.server.lock().unwrap().on_chat(self.client_id, text);
+    }
+
+    fn hit_entity(&mut self, id: u32, damage: u8, direction: Vector3) {
+        self.server.lock().unwrap().on_hit_entity(self.client_id, id, damage, direction);
+    }
+
+    fn request_respawn(&mut self) {
+        self.server.lock().unwrap().on_respawn(self.client_id);
+    }
+
     fn consume_server_updates(&mut self) -> Vec<ServerUpdate> {
         self.server.lock().unwrap().consume_updates(self.client_id)
     }
@@ -50,4 +64,18 @@ impl Proxy for SinglePlayerProxy {
     fn loading_delay(&self) -> u64 {
         0
     }
+
+    fn set_paused(&mut self, paused: bool) {
+        self.server.lock().unwrap().set_paused(paused);
+    }
+
+    fn save_world(&mut self) {
+        if let Err(err) = self.server.lock().unwrap().save_to(&SaveDir::default()) {
+            println!("Could not save the world: {err}");
+        }
+    }
+
+    fn set_render_distance(&mut self, render_distance: i32) {
+        self.server.lock().unwrap().set_render_distance(render_distance);
+    }
 }
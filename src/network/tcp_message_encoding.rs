@@ -2,7 +2,9 @@ use std::fmt::{write, Display, Formatter};
 
 #[derive(Debug)]
 enum TcpError {
-    LengthError
+    LengthError,
+    /// The content of a message with this code could not be read
+    InvalidMessage(u8),
 }
 
 impl Display for TcpError {
@@ -24,9 +26,16 @@ pub trait TcpSerialize {
 }
 
 /// A trait that an enum or a struct implement to be shared over the network.
-/// This trait can be used by `to_tcp_repr` to decode a message on our custom protocol.
-pub trait TcpDeserialize {
-    fn parse_bytes_representation(code: u8, bytes_to_parse: &[u8]) -> Self;
+/// This trait can be used by `from_tcp_repr` to decode a message on our custom protocol.
+pub trait TcpDeserialize: Sized {
+    /// Returns the object encoded in the bytes, None if they are not the representation of one with this code
+    fn parse_bytes_representation(code: u8, bytes_to_parse: &[u8]) -> Option<Self>;
+}
+
+/// The big-endian u32 starting at this index of the bytes, None if there are not enough of them
+pub fn read_u32(bytes: &[u8], start: usize) -> Option<u32> {
+    let bytes: [u8; 4] = bytes.get(start..start + 4)?.try_into().ok()?;
+    Some(u32::from_be_bytes(bytes))
 }
 
 /// Given an object that can be serialized to our TCP protocol,
@@ -133,8 +142,9 @@ pub fn from_tcp_repr<T: TcpDeserialize>(bytes: &[u8], context: &mut ParseContext
 
         // Depending on the type of the enum, parse correctly the content
         let parsed = T::parse_bytes_representation(context.code, &context.bytes);
-        to_return.push(parsed);
+        let code = context.code;
         context.flush();
+        to_return.push(parsed.ok_or(TcpError::InvalidMessage(code))?);
 
         // Increase the counter, in the case that there are several messaages to be parsed
         // in the current packet.
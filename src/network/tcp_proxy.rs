@@ -4,6 +4,7 @@ use crate::network::proxy::Proxy;
 use crate::network::server_update::ServerUpdate;
 use crate::network::tcp_message_encoding::{from_tcp_repr, to_tcp_repr, ParseContext};
 use crate::primitives::position::Position;
+use crate::primitives::vector::Vector3;
 use std::collections::VecDeque;
 use std::io::{Read, Write};
 use std::net::TcpStream;
@@ -26,8 +27,13 @@ fn handle_stream_with_server(mut stream: TcpStream, proxy: Arc<Mutex<TcpProxy>>,
         // Continuously read the bytes received by the server
         match stream.read(&mut data) {
             Ok(size) => {
-                for update in from_tcp_repr(&data[0..size], &mut context).unwrap() {
-                    proxy.lock().unwrap().push_server_update(update);
+                match from_tcp_repr(&data[0..size], &mut context) {
+                    Ok(updates) => {
+                        for update in updates {
+                            proxy.lock().unwrap().push_server_update(update);
+                        }
+                    }
+                    Err(err) => println!("Failed to read the updates of the server: {err}")
                 }
             }
             Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {}
@@ -94,8 +100,8 @@ impl Proxy for TcpProxy {
         }
     }
 
-    fn send_position_update(&mut self, position: Position) {
-        match self.updates_transmitter.send(MessageToServer::OnNewPosition(position)) {
+    fn send_position_update(&mut self, sequence: u32, position: Position) {
+        match self.updates_transmitter.send(MessageToServer::OnNewPosition(sequence, position)) {
             Ok(_) => {}
             Err(err) => println!("Error while sending: {err}")
         }
@@ -122,6 +128,27 @@ impl Proxy for TcpProxy {
         }
     }
 
+    fn send_chat(&mut self, text: String) {
+        match self.updates_transmitter.send(MessageToServer::Chat(text)) {
+            Ok(_) => {}
+            Err(err) => println!("Error while sending: {err}")
+        }
+    }
+
+    fn hit_entity(&mut self, id: u32, damage: u8, direction: Vector3) {
+        match self.updates_transmitter.send(MessageToServer::HitEntity(id, damage, direction)) {
+            Ok(_) => {}
+            Err(err) => println!("Error while sending: {err}")
+        }
+    }
+
+    fn request_respawn(&mut self) {
+        match self.updates_transmitter.send(MessageToServer::Respawn) {
+            Ok(_) => {}
+            Err(err) => println!("Error while sending: {err}")
+        }
+    }
+
     fn consume_server_updates(&mut self) -> Vec<ServerUpdate> {
         // TODO change the API to get something that complies more with the circular buffer
         //      for instance returning an iterator that consumes the front of the queue ?
@@ -138,4 +165,13 @@ impl Proxy for TcpProxy {
     fn loading_delay(&self) -> u64 {
         3000
     }
+
+    /// The other players keep playing
+    fn set_paused(&mut self, _paused: bool) {}
+
+    /// The world belongs to the server, which saves it itself
+    fn save_world(&mut self) {}
+
+    /// The server sends the same chunks to all its players
+    fn set_render_distance(&mut self, _render_distance: i32) {}
 }
\ No newline at end of file
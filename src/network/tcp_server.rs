@@ -71,8 +71,8 @@ fn handle_client(mut stream: TcpStream, game: Arc<Mutex<GameServer>>) {
                                     // The thread memorizes
                                     client_id = Some(id as usize);
                                 }
-                                MessageToServer::OnNewPosition(new_pos) => {
-                                    game.lock().unwrap().on_new_position_update(client_id.unwrap(), new_pos);
+                                MessageToServer::OnNewPosition(sequence, new_pos) => {
+                                    game.lock().unwrap().on_new_position_update(client_id.unwrap(), sequence, new_pos);
                                 }
                                 MessageToServer::OnNewAction(action) => {
                                     println!("Client {client_id:?} has submitted an action: {action:?}");
@@ -84,6 +84,21 @@ fn handle_client(mut stream: TcpStream, game: Arc<Mutex<GameServer>>) {
                                 MessageToServer::SpawnRequest(position) => {
                                     game.lock().unwrap().spawn_monster(position);
                                 }
+                                MessageToServer::Chat(text) => {
+                                    if let Some(id) = client_id {
+                                        game.lock().unwrap().on_chat(id, text);
+                                    }
+                                }
+                                MessageToServer::HitEntity(id, damage, direction) => {
+                                    if let Some(player_id) = client_id {
+                                        game.lock().unwrap().on_hit_entity(player_id, id, damage, direction);
+                                    }
+                                }
+                                MessageToServer::Respawn => {
+                                    if let Some(player_id) = client_id {
+                                        game.lock().unwrap().on_respawn(player_id);
+                                    }
+                                }
                             };
                         }
                     }
@@ -0,0 +1,440 @@
+pub mod region;
+
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+
+use crate::block_entity::{BlockEntity, Container, CHEST_SLOTS};
+use crate::block_kind::Block;
+use crate::chunk::{Chunk, CHUNK_HEIGHT, CHUNK_SIZE};
+use crate::item::Item;
+use crate::persistence::region::RegionStore;
+use crate::player_items::ItemStack;
+use crate::primitives::position::Position;
+use crate::primitives::vector::Vector3;
+use crate::world::World;
+use crate::world_meta::{unix_time, WorldMeta};
+
+/// Directory holding all the saves, one sub-directory per world
+pub const SAVES_DIR: &str = "saves";
+
+/// Settings of the world, kept readable
+const META_FILE: &str = "level.json";
+/// Cubes of all the chunks, in binary, as they were saved before the regions
+const CHUNKS_FILE: &str = "chunks.bin";
+/// Directory of the region files, see `RegionStore`
+const REGIONS_DIR: &str = "region";
+/// Position and orientation of each player, in binary
+const PLAYERS_FILE: &str = "players.bin";
+
+/// First bytes of the binary files, followed by the version of the format
+const MAGIC: &[u8; 4] = b"CRFT";
+/// The version 1 was written when the tools were blocks: the slots of the chests have no wear
+/// (see `decode_block_entities`). The files of this version are still read.
+pub const VERSION: u8 = 2;
+
+/// Number of cells stored for each chunk, one byte each
+const CELLS_PER_CHUNK: usize = CHUNK_HEIGHT * CHUNK_SIZE * CHUNK_SIZE;
+/// Number of bytes of a chunk: its cells followed by their metadata, two cells per byte
+const CHUNK_BYTES: usize = CELLS_PER_CHUNK + CELLS_PER_CHUNK / 2;
+
+/// A player of a saved game, found again by name when logging in
+pub type SavedPlayer = (String, Position);
+
+fn invalid(message: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, message.to_string())
+}
+
+/// Reads the bytes of a file, one field after the other
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8]> {
+        if self.bytes.len() < n {
+            return Err(invalid("unexpected end of file"));
+        }
+        let (taken, rest) = self.bytes.split_at(n);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn i32(&mut self) -> Result<i32> {
+        Ok(i32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn f32(&mut self) -> Result<f32> {
+        Ok(f32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    /// Checks the magic of the file, returns the version of its format
+    fn header(&mut self) -> Result<u8> {
+        if self.take(4)? != MAGIC {
+            return Err(invalid("not a crafty save"));
+        }
+        self.version()
+    }
+
+    /// The version of the format, the versions after the one of this build cannot be read
+    fn version(&mut self) -> Result<u8> {
+        match self.take(1)?[0] {
+            version @ 1..=VERSION => Ok(version),
+            version => Err(invalid(&format!("unsupported save version {version}"))),
+        }
+    }
+}
+
+fn header() -> Vec<u8> {
+    let mut bytes = MAGIC.to_vec();
+    bytes.push(VERSION);
+    bytes
+}
+
+/// The cells of the chunk in the order of its data (`[y][x][z]`): 0 for air, the block id + 1 otherwise (see `Block::id`).
+/// They are followed by the metadata of the cells in the same order, the first cell of each pair in the low bits.
+pub fn encode_chunk_cells(chunk: &Chunk) -> Vec<u8> {
+    let mut bytes: Vec<u8> = chunk
+        .cubes_iter()
+        .map(|cube| cube.map_or(0, |cube| cube.block().id() + 1))
+        .collect();
+    let metadata: Vec<u8> = chunk.cubes_iter().map(|cube| cube.map_or(0, |cube| cube.metadata())).collect();
+    bytes.extend(metadata.chunks(2).map(|pair| pair[0] | pair[1] << 4));
+    bytes
+}
+
+/// Builds back the chunk of this corner from its cells, see `encode_chunk_cells`.
+/// The chunks saved before the metadata only have their cells, all their metadata is 0.
+/// The neighbors of the cubes are not stored, they are counted again once the chunk is in a world.
+pub fn decode_chunk_cells(corner: [f32; 2], bytes: &[u8]) -> Result<Chunk> {
+    if bytes.len() != CELLS_PER_CHUNK && bytes.len() != CHUNK_BYTES {
+        return Err(invalid("wrong number of cells in a chunk"));
+    }
+    let (cells, metadata) = bytes.split_at(CELLS_PER_CHUNK);
+    let mut chunk = Chunk::new(corner);
+    for (i, &cell) in cells.iter().enumerate() {
+        if cell == 0 {
+            continue;
+        }
+        let block = Block::from_id(cell - 1).ok_or_else(|| invalid("unknown block"))?;
+        let y = i / (CHUNK_SIZE * CHUNK_SIZE);
+        let x = i / CHUNK_SIZE % CHUNK_SIZE;
+        let z = i % CHUNK_SIZE;
+        let at = Vector3::new(corner[0] + x as f32, y as f32, corner[1] + z as f32);
+        chunk.add_cube(at, block, 0);
+        if let Some(pair) = metadata.get(i / 2) {
+            chunk.set_metadata(&at, pair >> (4 * (i % 2)));
+        }
+    }
+    Ok(chunk)
+}
+
+/// The block entities of the chunk: their number (u16), then for each one the index of its cell in the order
+/// of `encode_chunk_cells` (u16), its kind (u8, 0 for a chest) and its slots, each the item id + 1 (u8, 0 when empty,
+/// see `Item::id`), the number of items (u8) and the wear of the tool (u16).
+pub fn encode_block_entities(chunk: &Chunk) -> Vec<u8> {
+    let entities: Vec<([i32; 3], &BlockEntity)> = chunk.block_entities().collect();
+    let (x0, z0) = chunk.coord();
+    let mut bytes = (entities.len() as u16).to_le_bytes().to_vec();
+    for ([x, y, z], entity) in entities {
+        let cell = y as usize * CHUNK_SIZE * CHUNK_SIZE + (x - x0) as usize * CHUNK_SIZE + (z - z0) as usize;
+        bytes.extend_from_slice(&(cell as u16).to_le_bytes());
+        match entity {
+            BlockEntity::Chest(container) => {
+                bytes.push(0);
+                for i in 0..CHEST_SLOTS {
+                    let (item, count, wear) = container.get(i).map_or((0, 0, 0), |stack| {
+                        (stack.item.id() + 1, stack.count as u8, stack.wear)
+                    });
+                    bytes.extend_from_slice(&[item, count]);
+                    bytes.extend_from_slice(&wear.to_le_bytes());
+                }
+            }
+        }
+    }
+    bytes
+}
+
+/// Gives back to the cubes of the chunk their block entities, see `encode_block_entities`.
+/// The chunks saved before the block entities have no bytes for them.
+/// The slots of the version 1 have no wear: the tools were blocks, whose ids they kept (see `Item::id`).
+pub fn decode_block_entities(chunk: &mut Chunk, bytes: &[u8], version: u8) -> Result<()> {
+    if bytes.is_empty() {
+        return Ok(());
+    }
+    let mut reader = Reader { bytes };
+    let corner = chunk.corner();
+    for _ in 0..reader.u16()? {
+        let cell = reader.u16()? as usize;
+        let y = cell / (CHUNK_SIZE * CHUNK_SIZE);
+        let x = cell / CHUNK_SIZE % CHUNK_SIZE;
+        let z = cell % CHUNK_SIZE;
+        let at = Vector3::new(corner[0] + x as f32, y as f32, corner[1] + z as f32);
+        let entity = match reader.take(1)?[0] {
+            0 => {
+                let mut container = Container::new();
+                for i in 0..CHEST_SLOTS {
+                    let slot = reader.take(2)?;
+                    let wear = if version == 1 { 0 } else { reader.u16()? };
+                    if slot[0] != 0 {
+                        let item = Item::from_id(slot[0] - 1).ok_or_else(|| invalid("unknown item"))?;
+                        container.set(i, Some(ItemStack { item, count: slot[1] as usize, wear }));
+                    }
+                }
+                BlockEntity::Chest(container)
+            }
+            kind => return Err(invalid(&format!("unknown block entity {kind}"))),
+        };
+        chunk.set_block_entity(&at, entity);
+    }
+    Ok(())
+}
+
+pub fn encode_chunks<'a>(chunks: impl Iterator<Item = &'a Chunk>) -> Vec<u8> {
+    let chunks: Vec<&Chunk> = chunks.collect();
+    let mut bytes = header();
+    bytes.extend_from_slice(&(chunks.len() as u32).to_le_bytes());
+    for chunk in chunks {
+        let (x, z) = chunk.coord();
+        bytes.extend_from_slice(&x.to_le_bytes());
+        bytes.extend_from_slice(&z.to_le_bytes());
+        // The chunks of this file never had metadata
+        bytes.extend(&encode_chunk_cells(chunk)[..CELLS_PER_CHUNK]);
+    }
+    bytes
+}
+
+pub fn decode_chunks(bytes: &[u8]) -> Result<Vec<Chunk>> {
+    let mut reader = Reader { bytes };
+    reader.header()?;
+    let n_chunks = reader.u32()?;
+    let mut chunks = Vec::new();
+    for _ in 0..n_chunks {
+        let corner = [reader.i32()? as f32, reader.i32()? as f32];
+        chunks.push(decode_chunk_cells(corner, reader.take(CELLS_PER_CHUNK)?)?);
+    }
+    Ok(chunks)
+}
+
+pub fn encode_players(players: &[SavedPlayer]) -> Vec<u8> {
+    let mut bytes = header();
+    bytes.extend_from_slice(&(players.len() as u32).to_le_bytes());
+    for (name, position) in players {
+        bytes.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(name.as_bytes());
+        let pos = position.pos();
+        for value in [pos.x(), pos.y(), pos.z(), position.yaw(), position.pitch()] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+    bytes
+}
+
+pub fn decode_players(bytes: &[u8]) -> Result<Vec<SavedPlayer>> {
+    let mut reader = Reader { bytes };
+    reader.header()?;
+    let n_players = reader.u32()?;
+    let mut players = Vec::new();
+    for _ in 0..n_players {
+        let length = reader.u16()? as usize;
+        let name = String::from_utf8(reader.take(length)?.to_vec()).map_err(|_| invalid("player name is not utf-8"))?;
+        let pos = Vector3::new(reader.f32()?, reader.f32()?, reader.f32()?);
+        let (yaw, pitch) = (reader.f32()?, reader.f32()?);
+        players.push((name, Position::new(pos, yaw, pitch)));
+    }
+    Ok(players)
+}
+
+/// Writes the file next to its final path first, so that a crash while saving leaves the previous save untouched
+fn write_atomically(path: &Path, bytes: &[u8]) -> Result<()> {
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, bytes)?;
+    std::fs::rename(&tmp, path)
+}
+
+/// The directory of the saves, each world is saved in its own sub-directory named after it:
+/// ```text
+/// saves/
+///   <world name>/
+///     level.json
+///     players.bin
+///     region/
+///       r.<x>.<z>.bin
+/// ```
+pub struct SaveDir {
+    root: PathBuf,
+}
+
+impl Default for SaveDir {
+    fn default() -> Self {
+        Self::new(SAVES_DIR)
+    }
+}
+
+impl SaveDir {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Directory of the world with this name
+    pub fn world_dir(&self, name: &str) -> PathBuf {
+        self.root.join(name)
+    }
+
+    pub fn exists(&self, name: &str) -> bool {
+        self.world_dir(name).join(META_FILE).is_file()
+    }
+
+    /// The chunks of the world with this name, to be loaded as the players come near them
+    pub fn regions(&self, name: &str) -> RegionStore {
+        RegionStore::new(self.world_dir(name).join(REGIONS_DIR))
+    }
+
+    /// Names of the saved worlds, sorted
+    pub fn list(&self) -> Vec<String> {
+        let Ok(entries) = std::fs::read_dir(&self.root) else {
+            return Vec::new();
+        };
+        let mut names: Vec<String> = entries
+            .flatten()
+            .filter(|entry| entry.path().join(META_FILE).is_file())
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// Settings of the saved worlds, the most recently played first.
+    /// The worlds whose settings cannot be read are left out.
+    pub fn summaries(&self) -> Vec<WorldMeta> {
+        let mut summaries: Vec<WorldMeta> = self
+            .list()
+            .into_iter()
+            .filter_map(|name| {
+                let meta = std::fs::read_to_string(self.world_dir(&name).join(META_FILE)).ok()?;
+                let meta: WorldMeta = serde_json::from_str(&meta).ok()?;
+                // The world is loaded by the name of its directory
+                Some(WorldMeta { name, ..meta })
+            })
+            .collect();
+        summaries.sort_by(|a, b| b.last_played.cmp(&a.last_played));
+        summaries
+    }
+
+    /// Saves the world and the players in the directory named after the world (see `WorldMeta::name`).
+    /// The chunks go to their regions, the chunks saved before and not loaded now are kept.
+    pub fn save(&self, world: &World, players: &[SavedPlayer]) -> Result<()> {
+        let dir = self.world_dir(&world.meta().name);
+        std::fs::create_dir_all(&dir)?;
+        let meta = WorldMeta { last_played: unix_time(), ..world.meta().clone() };
+        let meta = serde_json::to_string_pretty(&meta).map_err(Error::other)?;
+        write_atomically(&dir.join(META_FILE), meta.as_bytes())?;
+        self.regions(&world.meta().name).save_chunks(world.chunks())?;
+        write_atomically(&dir.join(PLAYERS_FILE), &encode_players(players))
+    }
+
+    /// Loads the settings of the world with this name and its players.
+    /// The chunks stay in the regions, they are loaded when needed (see `regions`),
+    /// except for the saves made before the regions which get all their chunks at once.
+    /// A save without settings or players gets the default ones.
+    pub fn load(&self, name: &str) -> Result<(World, Vec<SavedPlayer>)> {
+        let dir = self.world_dir(name);
+        if !dir.is_dir() {
+            return Err(Error::new(ErrorKind::NotFound, format!("no saved world named {name}")));
+        }
+        let chunks = match std::fs::read(dir.join(CHUNKS_FILE)) {
+            Ok(bytes) => decode_chunks(&bytes)?,
+            Err(_) => Vec::new(),
+        };
+        let mut world = World::new(chunks);
+        *world.meta_mut() = match std::fs::read_to_string(dir.join(META_FILE)) {
+            Ok(meta) => serde_json::from_str(&meta).map_err(Error::other)?,
+            Err(_) => WorldMeta { name: name.to_string(), ..Default::default() },
+        };
+        let players = match std::fs::read(dir.join(PLAYERS_FILE)) {
+            Ok(bytes) => decode_players(&bytes)?,
+            Err(_) => Vec::new(),
+        };
+        Ok((world, players))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::block_kind::Block;
+    use crate::chunk::{Chunk, CHUNK_SIZE};
+    use crate::persistence::{decode_chunks, decode_players, encode_chunk_cells, encode_chunks, encode_players, SaveDir};
+    use crate::primitives::position::Position;
+    use crate::primitives::vector::Vector3;
+    use crate::world::World;
+    use crate::world_meta::WorldMeta;
+
+    #[test]
+    fn test_chunks_round_trip() {
+        let mut chunk = Chunk::new([-16., 32.]);
+        chunk.fill_layer(0, Block::STONE);
+        chunk.add_cube(Vector3::new(-10., 5., 40.), Block::WATER, 0);
+        chunk.add_cube(Vector3::new(-1., 63., 47.), Block::OAKLEAVES, 0);
+        chunk.add_cube(Vector3::new(-16., 1., 32.), Block::TORCH, 0);
+        // The cells hold the ids of the blocks, not their place among the kinds
+        assert_eq!(encode_chunk_cells(&chunk)[CHUNK_SIZE * CHUNK_SIZE], Block::TORCH.id() + 1);
+
+        let decoded = decode_chunks(&encode_chunks([&chunk].into_iter())).unwrap();
+        assert_eq!(decoded, vec![chunk]);
+
+        // Not a save, or cut in the middle
+        assert!(decode_chunks(b"hello").is_err());
+        let bytes = encode_chunks([&Chunk::new([0., 0.])].into_iter());
+        assert!(decode_chunks(&bytes[..bytes.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_players_round_trip() {
+        let players = vec![
+            (String::from("arthur"), Position::new(Vector3::new(1., 20.5, -3.), 0.5, -0.2)),
+            (String::from("johan"), Position::new(Vector3::new(-7., 11., 2.25), 3., 0.)),
+        ];
+        let decoded = decode_players(&encode_players(&players)).unwrap();
+        assert_eq!(decoded, players);
+    }
+
+    #[test]
+    fn test_several_saves_coexist() {
+        let saves = SaveDir::new(std::env::temp_dir().join("crafty_test_saves"));
+        let _ = std::fs::remove_dir_all(std::env::temp_dir().join("crafty_test_saves"));
+
+        for (name, block) in [("first", Block::DIRT), ("second", Block::SAND)] {
+            let mut chunk = Chunk::new([0., 0.]);
+            chunk.fill_layer(0, block);
+            let mut world = World::new(vec![chunk]);
+            *world.meta_mut() = WorldMeta::new(name.to_string(), 7);
+            let player = (String::from("arthur"), Position::new(Vector3::new(2., 3., 4.), 1., 0.));
+            saves.save(&world, &[player]).unwrap();
+        }
+        assert_eq!(saves.list(), vec!["first", "second"]);
+        let summaries = saves.summaries();
+        assert_eq!(summaries.len(), 2);
+        assert!(summaries.iter().all(|meta| meta.seed == 7 && meta.last_played > 0));
+
+        let (world, players) = saves.load("second").unwrap();
+        assert_eq!(world.meta().name, "second");
+        assert_eq!(world.meta().seed, 7);
+        assert_eq!(players[0].1.pos(), Vector3::new(2., 3., 4.));
+
+        // The chunks are loaded from their region when needed
+        let chunk = saves.regions("second").load_chunk((0, 0)).unwrap().unwrap();
+        let world = World::new(vec![chunk]);
+        assert_eq!(world.block_at(&Vector3::new(3., 0., 3.)), Some(Block::SAND));
+        // The neighbors are counted again: the floor is only seen from above
+        assert!(world.cube_at(Vector3::new(3., 0., 3.)).unwrap().is_visible());
+
+        assert!(saves.load("third").is_err());
+    }
+}
@@ -0,0 +1,406 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{ErrorKind, Read, Result, Seek, SeekFrom};
+use std::path::PathBuf;
+
+use crate::chunk::{Chunk, ChunkCoord, CHUNK_SIZE};
+use crate::persistence::{
+    decode_block_entities, decode_chunk_cells, encode_block_entities, encode_chunk_cells, invalid, write_atomically, Reader,
+    CHUNK_BYTES,
+};
+
+/// Number of chunks in each direction of a region
+pub const REGION_SIZE: i32 = 32;
+const SLOTS: usize = (REGION_SIZE * REGION_SIZE) as usize;
+
+const MAGIC: &[u8; 4] = b"CRFR";
+/// The regions of the version 1 are read and written again in this version when their chunks are saved,
+/// see `decode_block_entities`
+const VERSION: u8 = 2;
+/// The magic, the version and then the offset and the length (u32) of the data of each chunk, 0 for a missing chunk
+const HEADER_SIZE: usize = 5 + 8 * SLOTS;
+
+/// Position of a region, counted in regions: the region (1, 0) starts at the 32nd chunk along x
+pub type RegionCoord = (i32, i32);
+
+/// The region containing this chunk, and the slot of the chunk in it
+pub fn region_of(coord: ChunkCoord) -> (RegionCoord, usize) {
+    let i = coord.0.div_euclid(CHUNK_SIZE as i32);
+    let k = coord.1.div_euclid(CHUNK_SIZE as i32);
+    let region = (i.div_euclid(REGION_SIZE), k.div_euclid(REGION_SIZE));
+    let slot = i.rem_euclid(REGION_SIZE) * REGION_SIZE + k.rem_euclid(REGION_SIZE);
+    (region, slot as usize)
+}
+
+/// The chunk at this slot of the region, the opposite of `region_of`
+fn chunk_at(region: RegionCoord, slot: usize) -> ChunkCoord {
+    let s = CHUNK_SIZE as i32;
+    let slot = slot as i32;
+    (
+        (region.0 * REGION_SIZE + slot / REGION_SIZE) * s,
+        (region.1 * REGION_SIZE + slot % REGION_SIZE) * s,
+    )
+}
+
+/// Run-length encoding of the cells of a chunk: a u16 count followed by the repeated byte.
+/// The chunks are mostly made of long runs of air and of the same ground, which shrinks them a lot.
+pub fn rle_encode(cells: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let mut i = 0;
+    while i < cells.len() {
+        let value = cells[i];
+        let run = cells[i..].iter().take(u16::MAX as usize).take_while(|&&c| c == value).count();
+        bytes.extend_from_slice(&(run as u16).to_le_bytes());
+        bytes.push(value);
+        i += run;
+    }
+    bytes
+}
+
+/// Decodes the output of `rle_encode`, until `max_len` cells or the end of the bytes.
+/// Returns the cells and the bytes after them.
+/// The chunks saved before the metadata are shorter, `decode_chunk_cells` checks the length.
+pub fn rle_decode(bytes: &[u8], max_len: usize) -> Result<(Vec<u8>, &[u8])> {
+    let mut reader = Reader { bytes };
+    let mut cells = Vec::with_capacity(max_len);
+    while !reader.bytes.is_empty() && cells.len() < max_len {
+        let run = reader.u16()? as usize;
+        let value = reader.take(1)?[0];
+        if cells.len() + run > max_len {
+            return Err(invalid("too many cells in a compressed chunk"));
+        }
+        cells.resize(cells.len() + run, value);
+    }
+    Ok((cells, reader.bytes))
+}
+
+/// The data of a chunk in its region: its compressed cells, followed by its block entities
+fn encode_slot(chunk: &Chunk) -> Vec<u8> {
+    let mut data = rle_encode(&encode_chunk_cells(chunk));
+    data.extend(encode_block_entities(chunk));
+    data
+}
+
+/// Builds back the chunk of this position from its data in a region of this version, see `encode_slot`
+fn decode_slot(coord: ChunkCoord, data: &[u8], version: u8) -> Result<Chunk> {
+    let (cells, rest) = rle_decode(data, CHUNK_BYTES)?;
+    let (x, z) = coord;
+    let mut chunk = decode_chunk_cells([x as f32, z as f32], &cells)?;
+    decode_block_entities(&mut chunk, rest, version)?;
+    Ok(chunk)
+}
+
+/// The version of the region file, and where the compressed data of each chunk is in it:
+/// its offset and its length, 0 for a missing chunk
+fn parse_table(header: &[u8]) -> Result<(u8, Vec<(usize, usize)>)> {
+    let mut reader = Reader { bytes: header };
+    if reader.take(4)? != MAGIC {
+        return Err(invalid("not a region file"));
+    }
+    let version = match reader.take(1)?[0] {
+        version @ 1..=VERSION => version,
+        version => return Err(invalid(&format!("unsupported region version {version}"))),
+    };
+    let table = (0..SLOTS).map(|_| Ok((reader.u32()? as usize, reader.u32()? as usize))).collect::<Result<_>>()?;
+    Ok((version, table))
+}
+
+/// The chunks of a world saved by regions of 32x32 chunks, one file per region:
+/// the table of the chunks (see `parse_table`) followed by their data (see `encode_slot`).
+/// A chunk is read alone when it is needed, and saving a chunk only writes its region again.
+pub struct RegionStore {
+    dir: PathBuf,
+}
+
+impl RegionStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn path(&self, region: RegionCoord) -> PathBuf {
+        self.dir.join(format!("r.{}.{}.bin", region.0, region.1))
+    }
+
+    /// Loads the chunk at this position, without reading the rest of its region.
+    /// Returns None if the chunk was never saved.
+    pub fn load_chunk(&self, coord: ChunkCoord) -> Result<Option<Chunk>> {
+        let (region, slot) = region_of(coord);
+        let mut file = match File::open(self.path(region)) {
+            Ok(file) => file,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        let mut header = vec![0; HEADER_SIZE];
+        file.read_exact(&mut header)?;
+        let (version, table) = parse_table(&header)?;
+        let (offset, length) = table[slot];
+        if length == 0 {
+            return Ok(None);
+        }
+        let mut data = vec![0; length];
+        file.seek(SeekFrom::Start(offset as u64))?;
+        file.read_exact(&mut data)?;
+        decode_slot(coord, &data, version).map(Some)
+    }
+
+    /// Reads the compressed chunks of the region, all missing if the region was never saved.
+    /// The chunks of an older version are encoded again in the current one.
+    fn read_region(&self, region: RegionCoord) -> Result<Vec<Option<Vec<u8>>>> {
+        let bytes = match std::fs::read(self.path(region)) {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(vec![None; SLOTS]),
+            Err(err) => return Err(err),
+        };
+        let (version, table) = parse_table(&bytes)?;
+        table
+            .into_iter()
+            .enumerate()
+            .map(|(slot, (offset, length))| {
+                if length == 0 {
+                    return Ok(None);
+                }
+                let data = bytes.get(offset..offset + length).ok_or_else(|| invalid("chunk outside of its region file"))?;
+                match version {
+                    VERSION => Ok(Some(data.to_vec())),
+                    _ => Ok(Some(encode_slot(&decode_slot(chunk_at(region, slot), data, version)?))),
+                }
+            })
+            .collect()
+    }
+
+    fn write_region(&self, region: RegionCoord, slots: &[Option<Vec<u8>>]) -> Result<()> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(VERSION);
+        let mut offset = HEADER_SIZE;
+        for slot in slots {
+            let length = slot.as_ref().map_or(0, |data| data.len());
+            bytes.extend_from_slice(&(offset as u32).to_le_bytes());
+            bytes.extend_from_slice(&(length as u32).to_le_bytes());
+            offset += length;
+        }
+        for data in slots.iter().flatten() {
+            bytes.extend_from_slice(data);
+        }
+        write_atomically(&self.path(region), &bytes)
+    }
+
+    /// Saves these chunks in their regions, the other chunks of the regions are kept.
+    /// The regions whose chunks did not change are not written again.
+    pub fn save_chunks<'a>(&self, chunks: impl Iterator<Item = &'a Chunk>) -> Result<()> {
+        let mut by_region: HashMap<RegionCoord, Vec<&Chunk>> = HashMap::new();
+        for chunk in chunks {
+            by_region.entry(region_of(chunk.coord()).0).or_default().push(chunk);
+        }
+        if by_region.is_empty() {
+            return Ok(());
+        }
+        std::fs::create_dir_all(&self.dir)?;
+
+        for (region, chunks) in by_region {
+            let mut slots = self.read_region(region)?;
+            let mut changed = false;
+            for chunk in chunks {
+                let data = Some(encode_slot(chunk));
+                let slot = &mut slots[region_of(chunk.coord()).1];
+                if *slot != data {
+                    *slot = data;
+                    changed = true;
+                }
+            }
+            if changed {
+                self.write_region(region, &slots)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Loads all the chunks saved in the store
+    pub fn load_all(&self) -> Result<Vec<Chunk>> {
+        let Ok(entries) = std::fs::read_dir(&self.dir) else {
+            return Ok(Vec::new());
+        };
+        let mut chunks = Vec::new();
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let Some(region) = parse_region_name(&name) else {
+                continue;
+            };
+            for (slot, data) in self.read_region(region)?.into_iter().enumerate() {
+                if let Some(data) = data {
+                    chunks.push(decode_slot(chunk_at(region, slot), &data, VERSION)?);
+                }
+            }
+        }
+        Ok(chunks)
+    }
+}
+
+/// The region of a file named like `r.<x>.<z>.bin`
+fn parse_region_name(name: &str) -> Option<RegionCoord> {
+    let mut parts = name.strip_prefix("r.")?.strip_suffix(".bin")?.split('.');
+    let x = parts.next()?.parse().ok()?;
+    let z = parts.next()?.parse().ok()?;
+    parts.next().is_none().then_some((x, z))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::block_entity::{BlockEntity, Container, CHEST_SLOTS};
+    use crate::block_kind::Block;
+    use crate::chunk::{Chunk, CHUNK_SIZE};
+    use crate::persistence::region::{
+        chunk_at, parse_region_name, region_of, rle_decode, rle_encode, RegionStore, HEADER_SIZE, MAGIC, REGION_SIZE, SLOTS,
+    };
+    use crate::persistence::{decode_chunk_cells, encode_chunk_cells, CELLS_PER_CHUNK, CHUNK_BYTES};
+    use crate::player_items::ItemStack;
+    use crate::primitives::vector::Vector3;
+    use crate::tool::Tool;
+
+    #[test]
+    fn test_region_of_chunk() {
+        let s = CHUNK_SIZE as i32;
+        assert_eq!(region_of((0, 0)), ((0, 0), 0));
+        assert_eq!(region_of((s, 2 * s)), ((0, 0), REGION_SIZE as usize + 2));
+        assert_eq!(region_of((-s, 0)), ((-1, 0), ((REGION_SIZE - 1) * REGION_SIZE) as usize));
+        assert_eq!(region_of((REGION_SIZE * s, -REGION_SIZE * s - s)).0, (1, -2));
+        for coord in [(0, 0), (-s, 5 * s), (40 * s, -33 * s)] {
+            let (region, slot) = region_of(coord);
+            assert_eq!(chunk_at(region, slot), coord);
+        }
+        assert_eq!(parse_region_name("r.-1.3.bin"), Some((-1, 3)));
+        assert_eq!(parse_region_name("r.1.bin"), None);
+    }
+
+    #[test]
+    fn test_rle_round_trip() {
+        let mut chunk = Chunk::new([0., 0.]);
+        chunk.fill_layer(0, Block::STONE);
+        chunk.fill_layer(1, Block::DIRT);
+        let log = Vector3::new(3., 2., 7.);
+        chunk.add_cube(log, Block::OAKLOG, 0);
+        chunk.set_metadata(&log, 2);
+        let cells = encode_chunk_cells(&chunk);
+        let compressed = rle_encode(&cells);
+        assert!(compressed.len() < 100);
+        assert_eq!(rle_decode(&compressed, CHUNK_BYTES).unwrap(), (cells.clone(), &[][..]));
+        assert_eq!(decode_chunk_cells([0., 0.], &cells).unwrap(), chunk);
+
+        // The chunks saved before the metadata are still read, without it
+        let old = decode_chunk_cells([0., 0.], &cells[..CELLS_PER_CHUNK]).unwrap();
+        assert_eq!(old.cube_at(&log).map(|cube| *cube.block()), Some(Block::OAKLOG));
+        assert_eq!(old.metadata_at(&log), 0);
+        assert!(decode_chunk_cells([0., 0.], &cells[..100]).is_err());
+
+        // Runs longer than a u16 are split
+        let long = vec![4; 100_000];
+        assert_eq!(rle_decode(&rle_encode(&long), long.len()).unwrap().0, long);
+        assert!(rle_decode(&rle_encode(&long), 10).is_err());
+
+        // The decoding stops after the last cell, the rest is for the block entities
+        let mut followed = rle_encode(&long);
+        followed.extend([1, 2, 3]);
+        assert_eq!(rle_decode(&followed, long.len()).unwrap().1, &[1, 2, 3]);
+    }
+
+    #[test]
+    fn test_chest_in_its_region() {
+        let dir = std::env::temp_dir().join("crafty_test_region_chests");
+        let _ = std::fs::remove_dir_all(&dir);
+        let store = RegionStore::new(&dir);
+
+        let mut chunk = Chunk::new([0., CHUNK_SIZE as f32]);
+        let at = Vector3::new(2., 5., CHUNK_SIZE as f32 + 3.);
+        chunk.add_cube(at, Block::CHEST, 0);
+        let mut container = Container::new();
+        container.put(CHEST_SLOTS - 1, ItemStack::new(Block::GLASS, 1));
+        container.put(3, ItemStack::new(Block::DIRT, 1));
+        container.put(3, ItemStack::new(Block::DIRT, 1));
+        // The tools keep their wear
+        container.put(5, ItemStack { wear: 12, ..ItemStack::new(Tool::Pickaxe, 1) });
+        chunk.set_block_entity(&at, BlockEntity::Chest(container.clone()));
+        store.save_chunks([&chunk].into_iter()).unwrap();
+
+        let loaded = store.load_chunk(chunk.coord()).unwrap().unwrap();
+        assert_eq!(loaded.block_entity(&at), Some(&BlockEntity::Chest(container)));
+        assert_eq!(loaded, chunk);
+    }
+
+    #[test]
+    fn test_regions_of_the_first_version_are_migrated() {
+        let dir = std::env::temp_dir().join("crafty_test_region_migration");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let store = RegionStore::new(&dir);
+
+        // A chest written when the tools were blocks: a sword (id 8) and torches, without wear
+        let mut chunk = Chunk::new([0., 0.]);
+        chunk.fill_layer(0, Block::STONE);
+        let at = Vector3::new(0., 1., 2.);
+        chunk.add_cube(at, Block::CHEST, 0);
+        let mut data = rle_encode(&encode_chunk_cells(&chunk));
+        data.extend_from_slice(&1u16.to_le_bytes());
+        data.extend_from_slice(&((CHUNK_SIZE * CHUNK_SIZE + 2) as u16).to_le_bytes());
+        data.push(0);
+        for slot in 0..CHEST_SLOTS {
+            data.extend_from_slice(match slot {
+                0 => &[9, 1],
+                2 => &[10, 5],
+                _ => &[0, 0],
+            });
+        }
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(1);
+        for slot in 0..SLOTS {
+            let (offset, length) = if slot == region_of((0, 0)).1 { (HEADER_SIZE, data.len()) } else { (0, 0) };
+            bytes.extend_from_slice(&(offset as u32).to_le_bytes());
+            bytes.extend_from_slice(&(length as u32).to_le_bytes());
+        }
+        bytes.extend(data);
+        std::fs::write(dir.join("r.0.0.bin"), bytes).unwrap();
+
+        let mut container = Container::new();
+        container.set(0, Some(ItemStack::new(Tool::Sword, 1)));
+        container.set(2, Some(ItemStack::new(Block::TORCH, 5)));
+        let loaded = store.load_chunk((0, 0)).unwrap().unwrap();
+        assert_eq!(loaded.block_entity(&at), Some(&BlockEntity::Chest(container.clone())));
+
+        // Saving another chunk of the region writes the old chunks again in the current version
+        let mut next = Chunk::new([CHUNK_SIZE as f32, 0.]);
+        next.fill_layer(0, Block::DIRT);
+        store.save_chunks([&next].into_iter()).unwrap();
+        assert_eq!(std::fs::read(dir.join("r.0.0.bin")).unwrap()[4], 2);
+        let loaded = store.load_chunk((0, 0)).unwrap().unwrap();
+        assert_eq!(loaded.block_entity(&at), Some(&BlockEntity::Chest(container)));
+    }
+
+    #[test]
+    fn test_save_and_load_chunks_by_region() {
+        let dir = std::env::temp_dir().join("crafty_test_regions");
+        let _ = std::fs::remove_dir_all(&dir);
+        let store = RegionStore::new(&dir);
+        let s = CHUNK_SIZE as f32;
+
+        let mut first = Chunk::new([0., 0.]);
+        first.fill_layer(0, Block::GRASS);
+        let mut other_region = Chunk::new([-s, 3. * s]);
+        other_region.fill_layer(2, Block::SAND);
+        store.save_chunks([&first, &other_region].into_iter()).unwrap();
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 2);
+
+        // Saving a chunk keeps the others of its region
+        let mut second = Chunk::new([s, 0.]);
+        second.fill_layer(1, Block::DIRT);
+        store.save_chunks([&second].into_iter()).unwrap();
+        assert_eq!(store.load_chunk((0, 0)).unwrap(), Some(first.clone()));
+        assert_eq!(store.load_chunk((s as i32, 0)).unwrap(), Some(second));
+        assert_eq!(store.load_chunk((-s as i32, 3 * s as i32)).unwrap(), Some(other_region));
+        assert_eq!(store.load_chunk((2 * s as i32, 0)).unwrap(), None);
+        assert_eq!(store.load_chunk((100 * s as i32, 0)).unwrap(), None);
+
+        // A chunk saved again replaces the previous one
+        first.fill_layer(3, Block::STONE);
+        store.save_chunks([&first].into_iter()).unwrap();
+        assert_eq!(store.load_chunk((0, 0)).unwrap(), Some(first));
+        assert_eq!(store.load_all().unwrap().len(), 3);
+    }
+}
@@ -1,29 +1,91 @@
-use crate::collidable::{Collidable, CollisionData};
+use crate::aabb::AABB;
+use crate::collidable::Collidable;
 use crate::cube::Cube;
-use crate::entity::humanoid::humanoid_aabb;
+use crate::block_kind::Block;
+use crate::camera::{CameraGlide, CameraMode, LandingDip};
+use crate::entity::humanoid::scaled_humanoid_aabb;
+use crate::game_mode::GameMode;
 use crate::input::MotionState;
-use crate::input::PlayerInputStatus;
+use crate::input::{ActionCooldown, PlayerInputStatus};
+use crate::player_items::Inventory;
+use crate::primitives::frustum::Frustum;
 use crate::primitives::position::Position;
+use crate::primitives::ray::Ray;
+use crate::profile::{FrameProfile, Phase};
 use crate::primitives::vector::Vector3;
-use crate::world::World;
+use crate::world::{RayHit, World};
+use crate::tool::Tool;
 use std::f32::consts::PI;
 use std::time::Duration;
 
-pub const CLICK_TIME_TO_BREAK: f32 = 2.0;
+// The speeds and the gravity below are expressed in cubes, they are multiplied by the scale of the world
 
 /// Travel speed [m/s] or [cube/s]
 const SPEED: f32 = 4.0;
 // TODO for some obscure reason, actual speed is lower than that. Perhaps the dt
 // is wrong, or yet again the collision ?
 
+/// Travel speed [cube/s] while flying, before the multiplier of the mouse wheel
+pub const FLY_SPEED: f32 = 10.;
+
+/// Multipliers of the travel speed while sprinting, and while sneaking
+pub const SPRINT_FACTOR: f32 = 1.3;
+pub const SNEAK_FACTOR: f32 = 0.3;
+/// Longest time [s] between two presses of the forward key to start sprinting, or of the jump key to toggle flying
+pub const DOUBLE_TAP_DELAY: f32 = 0.3;
+/// Widening of the field of view while sprinting, as a fraction of it
+pub const SPRINT_FOV_KICK: f32 = 0.15;
+/// Narrowing of the field of view while the zoom key is held
+pub const ZOOM_FACTOR: f32 = 0.3;
+/// Rate [1/s] at which the field of view catches up with its target
+const FOV_EASING: f32 = 10.;
+
 /// Velocity [cube/s] added when jumping
 pub const JUMP_VELOCITY: f32 = 7.;
 
 // TODO same problem
-pub const GRAVITY_ACCELERATION_VECTOR: Vector3 = Vector3::new(0., -2. * 9.81, 0.);
+pub const GRAVITY: f32 = 2. * 9.81;
+pub const GRAVITY_ACCELERATION_VECTOR: Vector3 = Vector3::new(0., -GRAVITY, 0.);
+
+/// Fastest falling speed [cube/s], where the drag of the air balances the gravity
+pub const TERMINAL_VELOCITY: f32 = 40.;
+
+/// Fraction of the falling speed given back upward on landing, none by default
+pub const RESTITUTION: f32 = 0.;
+/// Slowest bounce [cube/s], below it the player stays on the ground
+const MIN_BOUNCE_VELOCITY: f32 = 1.;
+
+/// Height [cube] of the shortest hop, when the jump key is released right away
+pub const MIN_JUMP_HEIGHT: f32 = 0.5;
+/// Height [cube] of a jump when the jump key is held, the same as a jump at `JUMP_VELOCITY`
+pub const MAX_JUMP_HEIGHT: f32 = JUMP_VELOCITY * JUMP_VELOCITY / (2. * GRAVITY);
 
 pub const PLAYER_MARGIN: f32 = 1e-5;
 
+/// Highest obstacle [cube] that a walking player climbs without jumping
+pub const STEP_HEIGHT: u32 = 1;
+
+/// Minimum time between two blocks placed while holding the button
+pub const PLACE_COOLDOWN: Duration = Duration::from_millis(250);
+/// Minimum time between two blocks broken while holding the button
+pub const BREAK_COOLDOWN: Duration = Duration::from_millis(250);
+
+/// Time [s] for the camera to recover from a hard landing
+pub const LANDING_DIP_DURATION: f32 = 0.25;
+/// Deepest dip [cube] of the camera on a hard landing
+pub const LANDING_MAX_DIP: f32 = 0.3;
+
+/// Distance [cube] between the eyes of the player and the camera in the third person view
+pub const THIRD_PERSON_DISTANCE: f32 = 4.;
+/// Gap [cube] kept between the camera and the cube it is pulled in by
+const CAMERA_WALL_MARGIN: f32 = 0.2;
+
+/// Bounds of the multiplier of the speed while flying
+pub const MIN_FLY_SPEED: f32 = 0.25;
+pub const MAX_FLY_SPEED: f32 = 4.;
+/// Factor applied to the fly speed by one notch of the mouse wheel
+pub const FLY_SPEED_STEP: f32 = 1.25;
+
 
 pub const PLAYER_HEIGHT: f32 = 1.8;
 pub const DIAMETER: f32 = 0.5;
@@ -49,12 +111,83 @@ pub struct Player {
     /// Currently logical inputs pressed
     input_status: PlayerInputStatus,
 
-    /// Tuple with (Cube, touched_position)
-    /// Position that the camera is currently pointing to
+    /// The cube that the camera is currently pointing to, with the face hit by the ray
     /// If there is no cube, it is set to none
-    touched_cube: Option<Cube>,
+    touched: Option<RayHit>,
+
+    /// Position of the cube being broken: the breaking starts over when another cube is targeted
+    breaking: Option<Vector3>,
+
+    /// The tool in hand, see `set_tool`
+    tool: Option<Tool>,
 
     in_air: bool,
+
+    /// Side of a cube in world units, taken from the world at each step
+    scale: f32,
+
+    /// Direction of the gravity, taken from the world at each step
+    down: Vector3,
+
+    /// Exponent applied to the magnitude of the mouse motion, 1.0 means no acceleration
+    mouse_acceleration: f32,
+
+    /// If true, the transparent cubes (water, leaves) cannot be selected, the player reaches through them
+    reach_through_transparent: bool,
+
+    game_mode: GameMode,
+
+    /// A flying player is not subject to the gravity
+    flying: bool,
+
+    /// Set by the sprint key or a double tap on forward, until the player stops going forward or sneaks
+    sprinting: bool,
+    /// Time [s] since the last press of the forward key, to detect a double tap
+    since_forward_tap: Option<f32>,
+    /// Time [s] since the last press of the jump key, a double tap toggles flying
+    since_jump_tap: Option<f32>,
+    /// Movement asked by an analog stick, to the right and forward, from -1 to 1 on each axis
+    analog_movement: [f32; 2],
+
+    /// Current multiplier of the field of view, eased toward the one of the sprint and the zoom
+    fov_factor: f32,
+
+    /// Multiplier of the speed while flying, changed with the mouse wheel between its bounds
+    fly_speed: f32,
+    min_fly_speed: f32,
+    max_fly_speed: f32,
+
+    /// Bounds [cube] of the height of a jump, depending on how long the jump key is held
+    min_jump_height: f32,
+    max_jump_height: f32,
+
+    /// Gap [cube] kept between the player and the cubes it collides with
+    collision_margin: f32,
+
+    /// Camera animation after a hard landing
+    landing_dip: LandingDip,
+
+    /// Fraction of the falling speed kept upward when landing, see `RESTITUTION`
+    restitution: f32,
+    /// Falling speed [cube/s] when the player landed during the last step, 0 otherwise
+    landing_speed: f32,
+    /// Whether the player left the ground with a jump during the last step
+    jumped: bool,
+
+    /// Rotation [rad] of the camera around the direction it looks at, only applied to the view
+    roll: f32,
+
+    camera_mode: CameraMode,
+    /// Distance [cube] of the camera behind the eyes in the third person view, when nothing is in the way
+    third_person_distance: f32,
+    /// Current distance [cube] of the camera behind the eyes, shortened when a cube is in the way
+    camera_distance: f32,
+
+    /// Scripted move of the camera, the inputs are ignored until it is over
+    glide: Option<CameraGlide>,
+
+    place_cooldown: ActionCooldown,
+    break_cooldown: ActionCooldown,
 }
 
 impl Player {
@@ -64,53 +197,312 @@ impl Player {
             position: Position::empty(),
             velocity: Vector3::new(0., 0., 0.),
             input_status: PlayerInputStatus::new(),
-            touched_cube: None,
+            touched: None,
+            breaking: None,
+            tool: None,
             in_air: true, // will be updated every frame anyway
+            scale: 1.,
+            down: Vector3::new(0., -1., 0.),
+            mouse_acceleration: 1.,
+            reach_through_transparent: false,
+            game_mode: GameMode::default(),
+            flying: false,
+            sprinting: false,
+            since_forward_tap: None,
+            since_jump_tap: None,
+            analog_movement: [0.; 2],
+            fov_factor: 1.,
+            fly_speed: 1.,
+            min_fly_speed: MIN_FLY_SPEED,
+            max_fly_speed: MAX_FLY_SPEED,
+            min_jump_height: MIN_JUMP_HEIGHT,
+            max_jump_height: MAX_JUMP_HEIGHT,
+            collision_margin: PLAYER_MARGIN,
+            landing_dip: LandingDip::new(LANDING_DIP_DURATION, LANDING_MAX_DIP),
+            restitution: RESTITUTION,
+            landing_speed: 0.,
+            jumped: false,
+            roll: 0.,
+            camera_mode: CameraMode::default(),
+            third_person_distance: THIRD_PERSON_DISTANCE,
+            camera_distance: 0.,
+            glide: None,
+            place_cooldown: ActionCooldown::new(PLACE_COOLDOWN),
+            break_cooldown: ActionCooldown::new(BREAK_COOLDOWN),
         }
     }
 
     pub fn step(&mut self, elapsed: Duration, world: &World) {
+        self.step_with_profile(elapsed, world, &mut FrameProfile::disabled());
+    }
+
+    /// Same as `step`, timing the physics and the rest of the step into the profile
+    pub fn step_with_profile(&mut self, elapsed: Duration, world: &World, profile: &mut FrameProfile) {
+        // A scripted move replaces the physics and the controls
+        if let Some(glide) = self.glide.as_mut() {
+            glide.step(elapsed);
+            self.position = glide.position();
+            if glide.is_over() {
+                self.glide = None;
+            }
+            return;
+        }
+
         // Compute the next position
+        let start = profile.start();
         let dt = elapsed.as_secs_f32();
+        self.scale = world.scale();
+        self.down = world.gravity_direction();
+        self.place_cooldown.step(elapsed);
+        self.break_cooldown.step(elapsed);
+        self.step_sprint(dt);
+        profile.stop(Phase::CameraStep, start);
 
         // add gravity
-        if self.in_air {
-            self.velocity += GRAVITY_ACCELERATION_VECTOR * dt;
+        let start = profile.start();
+        if self.in_air && !self.flying {
+            self.velocity += self.down * GRAVITY * self.scale * dt;
+            // The drag of the air caps the falling speed
+            let terminal = TERMINAL_VELOCITY * self.scale;
+            if self.vertical_velocity() < -terminal {
+                self.set_vertical_velocity(-terminal);
+            }
         }
 
         {
+            // The controls set the velocity across the gravity, the velocity along it is kept
             let controls_vel = self.controls_velocity();
-            self.velocity[0] = controls_vel[0];
-            self.velocity[2] = controls_vel[2];
+            let controls_vel = controls_vel - self.down * self.down.dot(&controls_vel);
+            self.velocity = self.down * self.down.dot(&self.velocity) + controls_vel;
         }
 
-        if self.input_status.jump() {
+        self.jumped = false;
+        if self.flying {
+            // When flying, jumping means going up and sneaking going down
+            let up = self.input_status.jump() as i32 - self.input_status.sneak() as i32;
+            self.set_vertical_velocity(up as f32 * FLY_SPEED * self.fly_speed * self.scale);
+        } else if self.input_status.jump() {
             self.jump();
         }
 
-        let mut dt = elapsed.as_secs_f32();
-        loop {
-            dt = dt - self.move_with_collision(dt, world);
-            if dt <= 0. {
-                break;
-            }
+        if self.is_sneaking() && !self.in_air && !self.flying {
+            self.guard_edges(dt, world);
         }
 
+        // The vertical velocity is lost when hitting the ground
+        let falling_speed = -self.vertical_velocity();
+
+        self.move_with_collision(elapsed.as_secs_f32(), world);
+
         // update in_air
-        let displacement = Vector3::new(0., -2.0 * PLAYER_MARGIN, 0.);
-        self.in_air = !world.collides(&humanoid_aabb(&(&self.position + displacement)));
+        let was_in_air = self.in_air;
+        let displacement = self.down * 2.0 * self.collision_margin * self.scale;
+        self.in_air = !world.collides(&scaled_humanoid_aabb(&(&self.position + displacement), self.scale));
+        profile.stop(Phase::Gravity, start);
+
+        let start = profile.start();
+        self.landing_dip.step(elapsed.as_secs_f32());
+        self.landing_speed = 0.;
+        if was_in_air && !self.in_air && !self.flying {
+            self.landing_speed = falling_speed / self.scale;
+            self.landing_dip.land(self.landing_speed);
+            self.bounce(falling_speed);
+        }
         self.compute_selected_cube(world);
+        self.update_camera_distance(world);
+        profile.stop(Phase::CameraStep, start);
+    }
+
+    pub fn camera_mode(&self) -> CameraMode {
+        self.camera_mode
+    }
+
+    pub fn set_camera_mode(&mut self, camera_mode: CameraMode) {
+        self.camera_mode = camera_mode;
+    }
+
+    /// Sets the distance [cube] of the camera behind the player in the third person view
+    pub fn set_third_person_distance(&mut self, distance: f32) {
+        self.third_person_distance = distance.max(0.);
+    }
+
+    /// Moves the camera behind the player in the third person view, closer if a cube would hide the player
+    fn update_camera_distance(&mut self, world: &World) {
+        self.camera_distance = match self.camera_mode {
+            CameraMode::FirstPerson => 0.,
+            CameraMode::ThirdPerson => {
+                let ray = Ray::new(self.eye_position(), self.direction().opposite());
+                match world.raycast(&ray, self.third_person_distance * self.scale, true) {
+                    Some(hit) => (hit.distance / self.scale - CAMERA_WALL_MARGIN).max(0.),
+                    None => self.third_person_distance,
+                }
+            }
+        };
+    }
+
+    /// Switches the game mode, and all the behaviors that come with it
+    pub fn set_game_mode(&mut self, game_mode: GameMode) {
+        self.game_mode = game_mode;
+        self.flying = game_mode.can_fly();
+        self.velocity[1] = 0.;
+    }
+
+    pub fn game_mode(&self) -> GameMode {
+        self.game_mode
+    }
+
+    pub fn is_flying(&self) -> bool {
+        self.flying
+    }
+
+    pub fn fly_speed(&self) -> f32 {
+        self.fly_speed
+    }
+
+    /// Sets the bounds of the fly speed multiplier, the current one is clamped into them
+    pub fn set_fly_speed_bounds(&mut self, min: f32, max: f32) {
+        self.min_fly_speed = min;
+        self.max_fly_speed = max;
+        self.fly_speed = self.fly_speed.clamp(min, max);
+    }
+
+    /// Handles a notch of the mouse wheel, `lines` being positive when scrolling up.
+    /// It changes the fly speed while flying, otherwise it cycles through the item bar.
+    /// Only the direction counts: a fast scroll is still a single step.
+    pub fn scroll(&mut self, lines: f32, items: &mut Inventory) {
+        if lines == 0. {
+            return;
+        }
+        if self.flying {
+            let factor = if lines > 0. { FLY_SPEED_STEP } else { 1. / FLY_SPEED_STEP };
+            self.fly_speed = (self.fly_speed * factor).clamp(self.min_fly_speed, self.max_fly_speed);
+        } else {
+            items.cycle_current_item(if lines > 0. { 1 } else { -1 });
+        }
+    }
+
+    /// Time [s] needed by the player to break this block, with the tool in hand
+    pub fn break_time(&self, block: &Block) -> f32 {
+        self.game_mode.break_time(block, self.tool)
+    }
+
+    /// Sets the tool in the hand of the player, which changes the time to break the blocks
+    pub fn set_tool(&mut self, tool: Option<Tool>) {
+        self.tool = tool;
+    }
+
+    /// How much of the selected cube is broken, from 0 to 1
+    pub fn break_progress(&self) -> f32 {
+        match self.selected_cube().filter(|cube| self.breaking == Some(*cube.position())) {
+            Some(cube) => {
+                let break_time = self.break_time(cube.block());
+                if break_time > 0. { (self.left_click_time() / break_time).min(1.) } else { 1. }
+            }
+            None => 0.,
+        }
     }
 
     pub fn toggle_state(&mut self, element: MotionState, pressed: bool ) {
+        if matches!(element, MotionState::Jump) && !pressed && self.input_status.jump() {
+            self.release_jump();
+        }
+        if matches!(element, MotionState::Jump) && pressed {
+            self.tap_jump();
+        }
+        if matches!(element, MotionState::Up) && pressed {
+            // A second press right after the first one starts sprinting
+            if self.since_forward_tap.is_some_and(|since| since <= DOUBLE_TAP_DELAY) {
+                self.sprinting = true;
+            }
+            self.since_forward_tap = Some(0.);
+        }
+        // A new click acts right away, the cooldowns only apply when the button is held
+        match element {
+            MotionState::LeftClick if !pressed => self.break_cooldown.reset(),
+            MotionState::RightClick if !pressed => self.place_cooldown.reset(),
+            _ => (),
+        }
         self.input_status.set_input(element, pressed);
     }
+
+    /// Sets the gap [cube] kept between the player and the cubes. A larger margin avoids touching the
+    /// walls exactly, at the cost of not fitting in gaps that are just wide enough.
+    pub fn set_collision_margin(&mut self, margin: f32) {
+        self.collision_margin = margin;
+    }
+
+    /// Sets the heights [cube] of the shortest hop and of the full jump
+    pub fn set_jump_heights(&mut self, min_height: f32, max_height: f32) {
+        self.min_jump_height = min_height.min(max_height);
+        self.max_jump_height = max_height;
+    }
+
+    /// Vertical velocity needed to go up to the given height [cube]
+    fn velocity_for_height(&self, height: f32) -> f32 {
+        (2. * GRAVITY * height).sqrt() * self.scale
+    }
+
+    /// Called when the jump key is released: a player still going up fast is slowed down to the
+    /// velocity of the shortest hop, which makes for a lower apex.
+    /// As the remaining velocity can only be cut, the apex stays between the min and max heights.
+    pub fn release_jump(&mut self) {
+        if self.flying {
+            return;
+        }
+        let min_velocity = self.velocity_for_height(self.min_jump_height);
+        if self.vertical_velocity() > min_velocity {
+            self.set_vertical_velocity(min_velocity);
+        }
+    }
+
+    /// Sets the fraction of the falling speed given back upward when landing, 0 for no bounce
+    pub fn set_restitution(&mut self, restitution: f32) {
+        self.restitution = restitution.clamp(0., 1.);
+    }
+
+    /// Throws the player back up after landing at `falling_speed`, if it is fast enough to bounce
+    fn bounce(&mut self, falling_speed: f32) {
+        let rebound = falling_speed * self.restitution;
+        if rebound > MIN_BOUNCE_VELOCITY * self.scale {
+            self.set_vertical_velocity(rebound);
+            self.in_air = true;
+        }
+    }
+
+    /// Current velocity [world unit/s]
+    pub fn velocity(&self) -> Vector3 {
+        self.velocity
+    }
+
+    /// Speed [cube/s] at which the player hit the ground during the last step, 0 if it did not land.
+    /// This is what hurts the player on a long fall.
+    pub fn landing_speed(&self) -> f32 {
+        self.landing_speed
+    }
+
+    /// Velocity going up, against the gravity
+    fn vertical_velocity(&self) -> f32 {
+        -self.down.dot(&self.velocity)
+    }
+
+    fn set_vertical_velocity(&mut self, velocity: f32) {
+        self.velocity = self.velocity - self.down * self.down.dot(&self.velocity) - self.down * velocity;
+    }
     
     /// Sets the position of the player to the given one, without collision checks
     pub fn set_position(&mut self, position: Position) {
         self.position = position
     }
 
+    /// Puts the player back at the spawn point after its death, without any speed left
+    pub fn respawn(&mut self, spawn: Position) {
+        self.position = spawn;
+        self.velocity = Vector3::empty();
+        self.in_air = true;
+        self.landing_speed = 0.;
+        self.glide = None;
+    }
+
     pub fn left_click(&self) -> bool {
         self.input_status.left_click()
     }
@@ -129,27 +521,92 @@ impl Player {
 
     pub fn jump(&mut self) {
         if !self.in_air {
-            self.velocity[1] = JUMP_VELOCITY;
+            self.set_vertical_velocity(self.velocity_for_height(self.max_jump_height));
+            self.jumped = true;
         }
     }
 
-    pub fn up(&mut self) {
-        self.position.translate_y(5.);
+    /// Whether the player jumped during the last step, which makes it hungry (see `Hunger`)
+    pub fn jumped(&self) -> bool {
+        self.jumped
     }
 
-    pub fn down(&mut self) {
-        self.position.translate_y(-1.);
+    /// Where the camera is: the position of the player, moved by the view-only animations
+    pub fn eye_position(&self) -> Vector3 {
+        self.position.pos() + Vector3::new(0., self.landing_dip.offset() * self.scale, 0.)
     }
 
-    /// Returns the view matrix, from the given camera parameters
-    pub fn view_matrix(&self) -> [[f32; 4]; 4] {
+    /// Where the view is from: the eyes, or behind them in the third person view
+    pub fn camera_position(&self) -> Vector3 {
+        self.eye_position() - self.direction() * (self.camera_distance * self.scale)
+    }
+
+    /// Sets how long [s] the camera takes to recover from a hard landing, and how deep [cube] it can dip
+    pub fn set_landing_dip(&mut self, duration: f32, max_dip: f32) {
+        self.landing_dip.set_config(duration, max_dip);
+    }
+
+    pub fn roll(&self) -> f32 {
+        self.roll
+    }
+
+    /// Tilts the camera by this angle [rad] around the direction it looks at.
+    /// It only changes the view, not where the player moves.
+    pub fn set_roll(&mut self, roll: f32) {
+        self.roll = roll;
+    }
+
+    pub fn add_roll(&mut self, delta: f32) {
+        self.roll += delta;
+    }
+
+    /// Orients the camera towards this point of the world
+    pub fn set_look_at(&mut self, target: Vector3) {
+        let to_target = self.position.pos().line_to(&target);
+        let norm = to_target.norm();
+        if norm == 0. {
+            return;
+        }
+        let yaw = to_target.z().atan2(to_target.x());
+        let pitch = (to_target.y() / norm).asin();
+        self.position = Position::new(self.position.pos(), yaw, pitch);
+    }
+
+    /// Moves the camera to `position`, with the `(yaw, pitch)` rotation, over `duration`.
+    /// The move is eased, and the inputs of the player are ignored until it is over.
+    pub fn glide_to(&mut self, position: Vector3, rotation: (f32, f32), duration: Duration) {
+        let (yaw, pitch) = rotation;
+        let to = Position::new(position, yaw, pitch);
+        self.glide = Some(CameraGlide::new(self.position.clone(), to, duration));
+        self.velocity = Vector3::empty();
+    }
+
+    pub fn is_gliding(&self) -> bool {
+        self.glide.is_some()
+    }
+
+    /// Returns the normalized frame of the camera `(right, up, forward)`.
+    /// It is orthonormal, with `right x up = forward`, and `right` is the direction of `ground_direction_right`
+    /// when the camera looks horizontally.
+    pub fn basis(&self) -> (Vector3, Vector3, Vector3) {
         // Compute the normalised direction vector
         let forward = self.direction();
         let camera_up = Vector3::new(0., 1., 0.);
         let mut s = camera_up.cross(&forward);
         s.normalize();
-        let u = forward.cross(&s);
-        let position = self.position.pos();
+        let mut u = forward.cross(&s);
+        if self.roll != 0. {
+            // Rotate the right and up vectors around the forward one
+            let (sin, cos) = self.roll.sin_cos();
+            (s, u) = (s * cos + u * sin, u * cos - s * sin);
+        }
+        (s, u, forward)
+    }
+
+    /// Returns the view matrix, from the given camera parameters
+    pub fn view_matrix(&self) -> [[f32; 4]; 4] {
+        let (s, u, forward) = self.basis();
+        let position = self.camera_position();
         let p = [
             -position[0] * s[0] - position[1] * s[1] - position[2] * s[2],
             -position[0] * u[0] - position[1] * u[1] - position[2] * u[2],
@@ -163,7 +620,27 @@ impl Player {
         ]
     }
 
+    /// Volume seen by the camera with this projection, in the world space
+    pub fn frustum(&self, projection: &[[f32; 4]; 4]) -> Frustum {
+        Frustum::from_matrices(&self.view_matrix(), projection)
+    }
+
+    /// Sets the exponent of the mouse acceleration curve.
+    /// With a value above 1.0, fast motions turn more than proportionally to slow ones.
+    pub fn set_mouse_acceleration(&mut self, gain: f32) {
+        self.mouse_acceleration = gain;
+    }
+
+    /// Whether the player selects the cubes behind the transparent ones
+    pub fn set_reach_through_transparent(&mut self, reach_through: bool) {
+        self.reach_through_transparent = reach_through;
+    }
+
     pub fn mousemove(&mut self, horizontal: f32, vertical: f32, sensitivity: f32) {
+        if self.glide.is_some() {
+            return;
+        }
+        let (horizontal, vertical) = accelerated_delta(horizontal, vertical, self.mouse_acceleration);
         self.position.rotate_yaw(-horizontal * sensitivity);
         if vertical > 0.0 && self.position.pitch() < PI * 0.5 - 0.05 {
             self.position.rotate_pitch(vertical * sensitivity);
@@ -174,21 +651,31 @@ impl Player {
 
     /// Returns the optional position of the cube that the player is looking at.
     pub fn selected_cube(&self) -> Option<Cube> {
-        self.touched_cube
+        self.touched.map(|hit| hit.cube)
+    }
+
+    /// The selected cube, with the face looked at and its distance to the eyes
+    pub fn selected_hit(&self) -> Option<RayHit> {
+        self.touched
     }
 
     pub fn is_selecting_cube(&self) -> bool {
-        self.touched_cube.is_some()
+        self.touched.is_some()
     }
 
     pub fn position(&self) -> &Position {
         &self.position
     }
 
+    /// Box around the body of the player, it grows with the cubes of the world
+    pub fn aabb(&self) -> AABB {
+        scaled_humanoid_aabb(&self.position, self.scale)
+    }
+
     /// Check if tha player is colliding with a block position
     pub fn is_in(&self, cube_pos: Vector3) -> bool {
-        let cube_aabb = Cube::cube_aabb(cube_pos);
-        let player_aabb = &humanoid_aabb(&self.position);
+        let cube_aabb = Cube::cube_aabb(cube_pos).scaled(self.scale);
+        let player_aabb = &self.aabb();
 
         return cube_aabb.collides(player_aabb);
     }
@@ -204,88 +691,140 @@ impl Player {
         let f = self.position.ground_direction_forward();
         let l = self.position.ground_direction_right();
 
+        let speed = if self.flying { FLY_SPEED * self.fly_speed } else { SPEED };
         let mut displacement = Vector3::empty();
         if self.input_status.forward() {
-            displacement += f * SPEED;
+            displacement += f * speed;
         }
         if self.input_status.backward() {
-            displacement -= f * SPEED;
+            displacement -= f * speed;
         }
         if self.input_status.right() {
-            displacement += l * SPEED;
+            displacement += l * speed;
         }
         if self.input_status.left() {
-            displacement -= l * SPEED;
+            displacement -= l * speed;
         }
+        let [right, forward] = self.analog_movement;
+        displacement += (l * right + f * forward) * speed;
 
-        displacement
+        displacement * self.speed_factor() * self.scale
     }
 
-    /// Set the attribute `selected` to the cube currently being selected
-    fn compute_selected_cube(&mut self, world: &World) {
-        let position = self.position.pos();
-        let direction = self.direction();
-
-        // How to find which cube is selected by the camera ?
-        // 1. loop through all the visible cubes near the player
-        // 2. compute the intersection between the player's ray and the cube
-        // 3. keep the intersection with the shortest distance
-            
-        let mut current_best: Option<(f32, Cube)> = None;
-        for cube in world.cubes_near_player(position)
-            .filter_map(|c| *c)
-            .filter(|c| c.is_visible())
-            .filter(|c| c.position().distance_to(&position) < 6.) {
-            if let Some(result) = cube.intersection_with(position, direction) {
-                if current_best.is_none() || result < current_best.unwrap().0 {
-                    current_best = Some((result, cube.clone()));
-                }
-            }
+    /// Multiplier of the travel speed for the current movement mode. Sneaking does not slow down a flying player.
+    fn speed_factor(&self) -> f32 {
+        if self.sprinting {
+            SPRINT_FACTOR
+        } else if self.is_sneaking() && !self.flying {
+            SNEAK_FACTOR
+        } else {
+            1.
         }
-        
-        self.touched_cube = current_best.map(|(_, cube)| cube)
     }
-    
-    /// Integrate the velocity to move the camera, with collision. Returns the
-    /// dt (in seconds), which can be smaller than `dt` if there is a collision.
-    fn move_with_collision(&mut self, dt: f32, world: &World) -> f32 {
-        let target = humanoid_aabb(&(&self.position + self.velocity * dt));
-
-        let collision = world
-            .collision_time(
-                &self.position,
-                &humanoid_aabb(&self.position),
-                &target,
-                &self.velocity
-            )
-            .unwrap_or(CollisionData {
-                time: f32::MAX,
-                normal: Vector3::empty(),
-            });
-
-        if collision.time >= dt {
-            // TO DO : do we also want a margin here ???????????
-
-            // can move straight away
-            self.position += self.velocity * dt;
-
-            dt
+
+    /// A second press of the jump key right after the first one toggles flying, when the game mode allows it
+    fn tap_jump(&mut self) {
+        if self.since_jump_tap.is_some_and(|since| since <= DOUBLE_TAP_DELAY) && self.game_mode.can_fly() {
+            self.flying = !self.flying;
+            self.set_vertical_velocity(0.);
+            // A third press starts a new double tap
+            self.since_jump_tap = None;
         } else {
-            // The margin is between the player and the block we colide with
-            // need the projection of velocity onto the normal
-            let mut dtmargin: f32 = 0.0;
-            if (self.velocity.norm() >= 1e-10) {
-                dtmargin = PLAYER_MARGIN / collision.normal.dot(&self.velocity).abs();
+            self.since_jump_tap = Some(0.);
+        }
+    }
+
+    /// Starts and stops sprinting from the inputs, and eases the field of view toward the one of the sprint and the zoom
+    fn step_sprint(&mut self, dt: f32) {
+        for since in [&mut self.since_forward_tap, &mut self.since_jump_tap].into_iter().flatten() {
+            *since += dt;
+        }
+        if self.input_status.sprint() && self.is_moving_forward() {
+            self.sprinting = true;
+        }
+        if !self.is_moving_forward() || self.input_status.sneak() {
+            self.sprinting = false;
+        }
+        let kick = if self.sprinting { 1. + SPRINT_FOV_KICK } else { 1. };
+        let zoom = if self.input_status.zoom() { ZOOM_FACTOR } else { 1. };
+        self.fov_factor += (kick * zoom - self.fov_factor) * (FOV_EASING * dt).min(1.);
+    }
+
+    /// Cancels the horizontal velocity that would walk the player off the edge of the cubes it stands on.
+    /// Each axis is checked on its own, so that the player can still slide along the edge.
+    fn guard_edges(&mut self, dt: f32, world: &World) {
+        let below = self.down * 2.0 * self.collision_margin * self.scale;
+        let mut offset = Vector3::empty();
+        for axis in [0, 2] {
+            let mut step = Vector3::empty();
+            step[axis] = self.velocity[axis] * dt;
+            let supported = world.collides(&scaled_humanoid_aabb(&(&self.position + (offset + step + below)), self.scale));
+            if supported {
+                offset += step;
+            } else {
+                self.velocity[axis] = 0.;
             }
-            // we want to put a margin, to avoid collision even with floats rounding
-            self.position += self.velocity * (collision.time - dtmargin);
-            
-            // remove component of velocity along the normal
-            let vnormal = collision.normal * collision.normal.dot(&self.velocity);
-            self.velocity = self.velocity - vnormal;
+        }
+    }
+
+    /// Sets the movement asked by an analog stick, to the right and forward, each between -1 and 1
+    pub fn set_analog_movement(&mut self, right: f32, forward: f32) {
+        self.analog_movement = [right.clamp(-1., 1.), forward.clamp(-1., 1.)];
+    }
+
+    /// Forgets the keys and the sticks held, for when the game stops listening to them (in the menus).
+    /// Otherwise a key released in a menu would stay held when playing again.
+    pub fn release_inputs(&mut self) {
+        self.input_status = PlayerInputStatus::new();
+        self.analog_movement = [0.; 2];
+    }
+
+    /// Going forward with the key, or with a stick pushed at least halfway
+    fn is_moving_forward(&self) -> bool {
+        self.input_status.forward() || self.analog_movement[1] > 0.5
+    }
+
+    pub fn is_sprinting(&self) -> bool {
+        self.sprinting
+    }
+
+    pub fn is_sneaking(&self) -> bool {
+        self.input_status.sneak()
+    }
+
+    /// Multiplier of the field of view of the camera: wider while sprinting, narrower while zooming
+    pub fn fov_factor(&self) -> f32 {
+        self.fov_factor
+    }
 
-            collision.time
+    /// Set the attribute `selected` to the cube currently being selected
+    fn compute_selected_cube(&mut self, world: &World) {
+        // The first cube along the view, the reach is given in cubes
+        let ray = Ray::new(self.position.pos(), self.direction());
+        let reach = self.game_mode.reach() * world.scale();
+        self.touched = world.raycast(&ray, reach, self.reach_through_transparent);
+    }
+    
+    /// Integrate the velocity to move the camera, with collision.
+    /// The velocity is lost along the axes where the player is blocked by a cube.
+    /// A player walking into an obstacle of at most `STEP_HEIGHT` cubes is raised onto it first
+    /// (only with the gravity pointing down, the climb is along +y).
+    fn move_with_collision(&mut self, dt: f32, world: &World) {
+        let delta = self.velocity * dt;
+        if !self.in_air && !self.flying && self.down.y() < 0. {
+            if let Some(height) = world.obstruction_height(&self.aabb(), &delta, STEP_HEIGHT) {
+                self.position += Vector3::new(0., height + self.collision_margin * self.scale, 0.);
+            }
         }
+        let mut movement = world.resolve_aabb_movement(&self.aabb(), &delta);
+        for axis in 0..3 {
+            if movement[axis] != delta[axis] {
+                // we want to put a margin, to avoid collision even with floats rounding
+                movement[axis] -= self.collision_margin * self.scale * delta[axis].signum();
+                self.velocity[axis] = 0.;
+            }
+        }
+        self.position += movement;
     }
 
     /// Returns the normalized direction vector
@@ -297,12 +836,32 @@ impl Player {
         )
     }
 
+    /// Sets the minimum time between two blocks placed, and between two blocks broken, while holding the buttons
+    pub fn set_action_cooldowns(&mut self, place: Duration, break_: Duration) {
+        self.place_cooldown.set_cooldown(place);
+        self.break_cooldown.set_cooldown(break_);
+    }
+
+    pub fn right_click(&self) -> bool {
+        self.input_status.right_click()
+    }
+
+    /// Returns true if a block should be placed now: the right button is held and the cooldown is over
+    pub fn is_time_to_place(&mut self) -> bool {
+        self.input_status.right_click() && self.place_cooldown.try_act()
+    }
 
-    /// Returns true if the player is asking to break a cube
+    /// Accumulates the time the left button is held on the selected cube, returns true once the cube is broken
     pub fn is_time_to_break_over(&mut self, dt: f32) -> bool {
-        if self.is_selecting_cube() && self.left_click() {
+        if let (Some(cube), true) = (self.selected_cube(), self.left_click()) {
+            if self.breaking != Some(*cube.position()) {
+                self.breaking = Some(*cube.position());
+                self.reset_click_time();
+            }
             self.add_click_time(dt);
-            if self.left_click_time() >= CLICK_TIME_TO_BREAK {
+            let break_time = self.break_time(cube.block());
+            // Instant breaking would break a cube per frame while the button is held, hence the cooldown
+            if self.left_click_time() >= break_time && self.break_cooldown.try_act() {
                 self.reset_click_time();
                 return true;
             }
@@ -310,3 +869,739 @@ impl Player {
         false
     }
 }
+
+/// Applies the acceleration curve to a mouse motion: its magnitude `m` becomes `m^gain`,
+/// and its direction is kept.
+fn accelerated_delta(horizontal: f32, vertical: f32, gain: f32) -> (f32, f32) {
+    if gain == 1. {
+        return (horizontal, vertical);
+    }
+    let magnitude = (horizontal * horizontal + vertical * vertical).sqrt();
+    if magnitude == 0. {
+        return (0., 0.);
+    }
+    let factor = magnitude.powf(gain - 1.);
+    (horizontal * factor, vertical * factor)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::{FRAC_PI_2, FRAC_PI_4};
+    use crate::block_kind::Block;
+    use std::time::Duration;
+    use crate::chunk::Chunk;
+    use crate::camera::CameraMode;
+    use crate::game_mode::GameMode;
+    use crate::input::MotionState;
+    use crate::player::{Player, THIRD_PERSON_DISTANCE, DIAMETER, FOREHEAD, FLY_SPEED, FLY_SPEED_STEP, SNEAK_FACTOR, SPRINT_FACTOR, TERMINAL_VELOCITY, ZOOM_FACTOR};
+    use crate::player_items::{Inventory, HOTBAR_SIZE};
+    use crate::primitives::position::Position;
+    use crate::primitives::vector::Vector3;
+    use crate::world::World;
+    use crate::tool::Tool;
+
+    const SENSITIVITY: f32 = 0.005;
+
+    #[test]
+    fn test_no_roll_keeps_view_matrix() {
+        let mut player = Player::new();
+        player.mousemove(40., 25., SENSITIVITY);
+
+        // The matrix without any roll
+        let forward = player.direction();
+        let mut s = Vector3::new(0., 1., 0.).cross(&forward);
+        s.normalize();
+        let u = forward.cross(&s);
+        let m = player.view_matrix();
+        for i in 0..3 {
+            assert_eq!([m[i][0], m[i][1], m[i][2]], [s[i], u[i], forward[i]]);
+        }
+
+        player.set_roll(0.4);
+        player.add_roll(-0.4);
+        assert_eq!(player.roll(), 0.);
+        assert_eq!(player.view_matrix(), m);
+    }
+
+    #[test]
+    fn test_roll_keeps_basis_orthonormal() {
+        let mut player = Player::new();
+        player.mousemove(40., 25., SENSITIVITY);
+        let direction = player.direction();
+        player.set_roll(0.7);
+        let m = player.view_matrix();
+
+        let axes: Vec<Vector3> = (0..3).map(|j| Vector3::new(m[0][j], m[1][j], m[2][j])).collect();
+        for a in 0..3 {
+            assert!((axes[a].norm() - 1.).abs() < 1e-5);
+            for b in a + 1..3 {
+                assert!(axes[a].dot(&axes[b]).abs() < 1e-5);
+            }
+        }
+        // The camera still looks in the same direction
+        assert_eq!(player.direction(), direction);
+        assert!((axes[2] - direction).norm() < 1e-6);
+    }
+
+    #[test]
+    fn test_basis_is_right_handed_orthonormal() {
+        for yaw in [0., 0.7, 2., -2.5, 4.] {
+            for pitch in [0., 0.4, -1.2, 1.5] {
+                let mut player = Player::new();
+                player.set_position(Position::new(Vector3::empty(), yaw, pitch));
+                let (right, up, forward) = player.basis();
+
+                for axis in [right, up, forward] {
+                    assert!((axis.norm() - 1.).abs() < 1e-5);
+                }
+                assert!(right.dot(&up).abs() < 1e-5);
+                assert!(right.dot(&forward).abs() < 1e-5);
+                assert!(up.dot(&forward).abs() < 1e-5);
+                assert!((right.cross(&up) - forward).norm() < 1e-5, "yaw {yaw}, pitch {pitch}");
+                assert!(up.y() > 0., "the camera is upside down at yaw {yaw}, pitch {pitch}");
+
+                // The right of the camera is the right used to walk
+                assert!((right - player.position().ground_direction_right()).norm() < 1e-5);
+                assert!(forward.equals(&player.direction()));
+            }
+        }
+    }
+
+    /// Returns the yaw rotation applied by a horizontal mouse motion
+    fn yaw_rotation(delta: f32, gain: f32) -> f32 {
+        let mut player = Player::new();
+        player.set_mouse_acceleration(gain);
+        let before = player.position().yaw();
+        player.mousemove(delta, 0., SENSITIVITY);
+        (player.position().yaw() - before).abs()
+    }
+
+    #[test]
+    fn test_mouse_acceleration_amplifies_large_motions() {
+        let small = yaw_rotation(2., 1.5);
+        let large = yaw_rotation(20., 1.5);
+        assert!(large > 10. * small);
+    }
+
+    #[test]
+    fn test_mouse_acceleration_disabled_is_linear() {
+        for delta in [0.5, 2., 20., 150.] {
+            let mut player = Player::new();
+            player.set_mouse_acceleration(1.);
+            player.mousemove(delta, -delta, SENSITIVITY);
+
+            let mut linear = Player::new();
+            linear.position.rotate_yaw(-delta * SENSITIVITY);
+            linear.position.rotate_pitch(-delta * SENSITIVITY);
+            assert_eq!(player.position().yaw(), linear.position().yaw());
+            assert_eq!(player.position().pitch(), linear.position().pitch());
+        }
+    }
+
+    #[test]
+    fn test_reach_through_transparent() {
+        let mut chunk = Chunk::new([0., 0.]);
+        // The bottommost layer is never visible
+        chunk.fill_layer(0, Block::GRASS);
+        chunk.fill_layer(1, Block::GRASS);
+        chunk.add_cube(Vector3::new(4., 2., 4.), Block::WATER, 0);
+        let world = World::new(vec![chunk]);
+
+        let mut player = Player::new();
+        player.set_position(Position::new(Vector3::new(4.5, 4., 4.5), 0., -FRAC_PI_2));
+        player.compute_selected_cube(&world);
+        assert_eq!(*player.selected_cube().unwrap().block(), Block::WATER);
+
+        player.set_reach_through_transparent(true);
+        player.compute_selected_cube(&world);
+        assert_eq!(*player.selected_cube().unwrap().block(), Block::GRASS);
+    }
+
+    #[test]
+    fn test_switching_game_mode() {
+        let world = World::empty();
+        let mut player = Player::new();
+        player.set_position(Position::new(Vector3::new(0., 30., 0.), 0., 0.));
+
+        // Creative: flying, so no gravity, and instant breaking
+        player.set_game_mode(GameMode::Creative);
+        assert!(player.is_flying());
+        player.step(Duration::from_millis(500), &world);
+        assert_eq!(player.position().pos().y(), 30.);
+        assert_eq!(player.break_time(&Block::STONE), 0.);
+        assert_eq!(player.break_time(&Block::DIRT), 0.);
+
+        // Survival: the player falls, and the blocks have their hardness back
+        player.set_game_mode(GameMode::Survival);
+        assert!(!player.is_flying());
+        player.step(Duration::from_millis(500), &world);
+        assert!(player.position().pos().y() < 30.);
+        assert_eq!(player.break_time(&Block::STONE), Block::STONE.hardness());
+        assert_eq!(player.break_time(&Block::DIRT), Block::DIRT.hardness());
+        assert!(player.break_time(&Block::DIRT) < player.break_time(&Block::STONE));
+
+        // The tool made for the block breaks it faster
+        player.set_tool(Some(Tool::Pickaxe));
+        assert!(player.break_time(&Block::STONE) < Block::STONE.hardness());
+        assert_eq!(player.break_time(&Block::DIRT), Block::DIRT.hardness());
+    }
+
+    #[test]
+    fn test_double_tap_jump_toggles_flying() {
+        let world = World::empty();
+        let frame = Duration::from_millis(100);
+        let double_tap = |player: &mut Player| {
+            player.toggle_state(MotionState::Jump, true);
+            player.toggle_state(MotionState::Jump, false);
+            player.step(frame, &world);
+            player.toggle_state(MotionState::Jump, true);
+            player.toggle_state(MotionState::Jump, false);
+        };
+
+        let mut player = Player::new();
+        player.set_position(Position::new(Vector3::new(0., 30., 0.), 0., 0.));
+        player.set_game_mode(GameMode::Creative);
+        double_tap(&mut player);
+        assert!(!player.is_flying());
+        double_tap(&mut player);
+        assert!(player.is_flying());
+
+        // Shift goes down, space goes up
+        let y = player.position().pos().y();
+        player.toggle_state(MotionState::Sneak, true);
+        player.step(frame, &world);
+        assert!((player.position().pos().y() - (y - FLY_SPEED * 0.1)).abs() < 1e-3);
+        player.toggle_state(MotionState::Sneak, false);
+        player.toggle_state(MotionState::Jump, true);
+        player.step(frame, &world);
+        assert!((player.position().pos().y() - y).abs() < 1e-3);
+
+        // A survival player cannot fly
+        let mut player = Player::new();
+        double_tap(&mut player);
+        assert!(!player.is_flying());
+    }
+
+    #[test]
+    fn test_wheel_cycles_item_bar() {
+        let mut player = Player::new();
+        let mut items = Inventory::empty();
+        items.collect(Block::COBBELSTONE);
+        items.collect(Block::DIRT);
+        items.collect(Block::OAKLOG);
+
+        player.scroll(1., &mut items);
+        assert_eq!(items.current_item(), 1);
+        assert_eq!(player.fly_speed(), 1.);
+
+        // Around the end of the bar, in both directions, the empty slots included
+        player.scroll(-1., &mut items);
+        player.scroll(-1., &mut items);
+        assert_eq!(items.current_item(), HOTBAR_SIZE - 1);
+        assert_eq!(items.get_current_block(), None);
+        player.scroll(3., &mut items);
+        assert_eq!(items.current_item(), 0);
+    }
+
+    #[test]
+    fn test_wheel_changes_fly_speed() {
+        let mut player = Player::new();
+        let mut items = Inventory::empty();
+        items.collect(Block::COBBELSTONE);
+        items.collect(Block::DIRT);
+        player.set_game_mode(GameMode::Creative);
+        player.set_fly_speed_bounds(0.5, 2.);
+
+        player.scroll(1., &mut items);
+        assert_eq!(player.fly_speed(), FLY_SPEED_STEP);
+        assert_eq!(items.current_item(), 0);
+
+        // Clamped to the maximum
+        for _ in 0..10 {
+            player.scroll(1., &mut items);
+        }
+        assert_eq!(player.fly_speed(), 2.);
+
+        // And to the minimum
+        for _ in 0..10 {
+            player.scroll(-1., &mut items);
+        }
+        assert_eq!(player.fly_speed(), 0.5);
+    }
+
+    #[test]
+    fn test_look_at() {
+        let mut player = Player::new();
+        player.set_position(Position::new(Vector3::new(1., 2., 3.), 0.3, 0.2));
+        for target in [Vector3::new(10., 2., 3.), Vector3::new(-4., 7., -2.), Vector3::new(1., -5., 3.5)] {
+            player.set_look_at(target);
+            let mut expected = player.position().pos().line_to(&target);
+            expected.normalize();
+            assert!((player.direction() - expected).norm() < 1e-5, "looking at {target:?}");
+        }
+    }
+
+    #[test]
+    fn test_glide_to() {
+        let world = World::empty();
+        let mut player = Player::new();
+        player.set_position(Position::new(Vector3::new(0., 50., 0.), 0., 0.));
+        let destination = Vector3::new(10., 60., -5.);
+        player.glide_to(destination, (1.2, -0.4), Duration::from_millis(1000));
+
+        // The inputs are ignored during the move
+        player.toggle_state(MotionState::Up, true);
+        player.mousemove(30., 10., SENSITIVITY);
+        for _ in 0..9 {
+            player.step(Duration::from_millis(100), &world);
+            assert!(player.is_gliding());
+        }
+        assert!(!player.position().pos().equals(&destination));
+
+        // And the destination is reached at the end of the duration
+        player.step(Duration::from_millis(100), &world);
+        assert!(!player.is_gliding());
+        assert!(player.position().pos().equals(&destination));
+        assert_eq!(player.position().yaw(), 1.2);
+        assert_eq!(player.position().pitch(), -0.4);
+    }
+
+    /// Jumps from a flat floor, releases the jump key after `hold` and returns the height of the apex
+    fn jump_apex(hold: Duration) -> f32 {
+        let mut chunk = Chunk::new([0., 0.]);
+        chunk.fill_layer(0, Block::GRASS);
+        let world = World::new(vec![chunk]);
+
+        let mut player = Player::new();
+        player.set_jump_heights(0.5, 1.2);
+        player.set_position(Position::new(Vector3::new(4.5, 3., 4.5), 0., 0.));
+        let frame = Duration::from_millis(5);
+        for _ in 0..200 {
+            player.step(frame, &world);
+        }
+        let ground = player.position().pos().y();
+
+        player.toggle_state(MotionState::Jump, true);
+        let mut held = Duration::ZERO;
+        let mut apex = ground;
+        for _ in 0..200 {
+            if held >= hold && player.input_status.jump() {
+                player.toggle_state(MotionState::Jump, false);
+            }
+            player.step(frame, &world);
+            held += frame;
+            apex = apex.max(player.position().pos().y());
+        }
+        apex - ground
+    }
+
+    #[test]
+    fn test_variable_jump_height() {
+        let hop = jump_apex(Duration::from_millis(10));
+        let full = jump_apex(Duration::from_secs(2));
+
+        assert!(hop < full);
+        // Some margin for the integration with a finite time step
+        assert!(hop >= 0.5 - 0.05 && hop < 0.8, "hop of {hop}");
+        assert!(full <= 1.2 + 0.05 && full > 1.1, "full jump of {full}");
+    }
+
+    #[test]
+    fn test_fall_at_terminal_velocity() {
+        let mut chunk = Chunk::new([0., 0.]);
+        chunk.fill_layer(0, Block::GRASS);
+        let world = World::new(vec![chunk]);
+
+        let mut player = Player::new();
+        player.set_position(Position::new(Vector3::new(4.5, 62., 4.5), 0., 0.));
+        let frame = Duration::from_millis(10);
+        let mut fastest = 0_f32;
+        let mut landing = 0.;
+        for _ in 0..500 {
+            player.step(frame, &world);
+            fastest = fastest.max(-player.velocity().y());
+            landing = player.landing_speed().max(landing);
+        }
+        assert!(fastest <= TERMINAL_VELOCITY + 1e-3 && fastest > TERMINAL_VELOCITY - 1., "fastest fall at {fastest}");
+        // Landed after a long fall, and stays on the ground
+        assert!(landing > 20., "landing at {landing}");
+        assert_eq!(player.landing_speed(), 0.);
+        assert_eq!(player.velocity().y(), 0.);
+    }
+
+    #[test]
+    fn test_restitution_bounces_back() {
+        let mut chunk = Chunk::new([0., 0.]);
+        chunk.fill_layer(0, Block::GRASS);
+        let world = World::new(vec![chunk]);
+
+        let mut player = Player::new();
+        player.set_restitution(0.5);
+        player.set_position(Position::new(Vector3::new(4.5, 8., 4.5), 0., 0.));
+        let frame = Duration::from_millis(5);
+        let mut bounced = false;
+        for _ in 0..400 {
+            player.step(frame, &world);
+            if player.landing_speed() > 0. {
+                bounced = player.velocity().y() > 0.;
+                break;
+            }
+        }
+        assert!(bounced);
+    }
+
+    /// Distance walked forward on a flat floor in one second, with the given extra input held
+    fn walked_distance(mode: Option<MotionState>) -> f32 {
+        let mut chunk = Chunk::new([0., 0.]);
+        chunk.fill_layer(0, Block::GRASS);
+        let world = World::new(vec![chunk]);
+
+        let mut player = Player::new();
+        player.set_position(Position::new(Vector3::new(0.5, 2.8, 4.5), 0., 0.));
+        let frame = Duration::from_millis(10);
+        for _ in 0..20 {
+            player.step(frame, &world);
+        }
+        if let Some(mode) = mode {
+            player.toggle_state(mode, true);
+        }
+        player.toggle_state(MotionState::Up, true);
+        let start = player.position().pos().x();
+        for _ in 0..100 {
+            player.step(frame, &world);
+        }
+        player.position().pos().x() - start
+    }
+
+    #[test]
+    fn test_sprint_and_sneak_speeds() {
+        let walk = walked_distance(None);
+        let sprint = walked_distance(Some(MotionState::Sprint));
+        let sneak = walked_distance(Some(MotionState::Sneak));
+        assert!((sprint / walk - SPRINT_FACTOR).abs() < 0.05, "walk {walk}, sprint {sprint}");
+        assert!((sneak / walk - SNEAK_FACTOR).abs() < 0.05, "walk {walk}, sneak {sneak}");
+    }
+
+    #[test]
+    fn test_analog_movement() {
+        let mut chunk = Chunk::new([0., 0.]);
+        chunk.fill_layer(0, Block::GRASS);
+        let world = World::new(vec![chunk]);
+
+        let mut player = Player::new();
+        player.set_position(Position::new(Vector3::new(0.5, 2.8, 4.5), 0., 0.));
+        let frame = Duration::from_millis(10);
+        for _ in 0..20 {
+            player.step(frame, &world);
+        }
+        // The stick halfway forward goes at half the speed of the key
+        player.set_analog_movement(0., 0.5);
+        let start = player.position().pos().x();
+        for _ in 0..100 {
+            player.step(frame, &world);
+        }
+        let half = player.position().pos().x() - start;
+        assert!((half * 2. - walked_distance(None)).abs() < 0.05, "walked {half}");
+        assert!(!player.is_sprinting());
+    }
+
+    #[test]
+    fn test_double_tap_to_sprint() {
+        let mut player = Player::new();
+        let frame = Duration::from_millis(100);
+        let world = World::new(vec![Chunk::new([0., 0.])]);
+        let fov = player.fov_factor();
+
+        player.toggle_state(MotionState::Up, true);
+        player.toggle_state(MotionState::Up, false);
+        player.step(frame, &world);
+        player.toggle_state(MotionState::Up, true);
+        assert!(player.is_sprinting());
+        for _ in 0..5 {
+            player.step(frame, &world);
+        }
+        assert!(player.fov_factor() > fov);
+
+        // Stops when the player stops going forward, and the taps too far apart do not start it
+        player.toggle_state(MotionState::Up, false);
+        player.step(frame, &world);
+        assert!(!player.is_sprinting());
+        for _ in 0..5 {
+            player.step(frame, &world);
+        }
+        player.toggle_state(MotionState::Up, true);
+        assert!(!player.is_sprinting());
+    }
+
+    #[test]
+    fn test_hold_to_zoom() {
+        let mut player = Player::new();
+        let world = World::new(vec![Chunk::new([0., 0.])]);
+        let frame = Duration::from_millis(50);
+        player.toggle_state(MotionState::Zoom, true);
+        for _ in 0..20 {
+            player.step(frame, &world);
+        }
+        assert!((player.fov_factor() - ZOOM_FACTOR).abs() < 1e-3);
+
+        // Back to the normal view once released
+        player.toggle_state(MotionState::Zoom, false);
+        for _ in 0..20 {
+            player.step(frame, &world);
+        }
+        assert!((player.fov_factor() - 1.).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_sneak_stops_at_the_edge() {
+        // A floor on the first half of the chunk only
+        let mut chunk = Chunk::new([0., 0.]);
+        for x in 0..4 {
+            for z in 0..8 {
+                chunk.add_cube(Vector3::new(x as f32, 0., z as f32), Block::STONE, 0);
+            }
+        }
+        let world = World::new(vec![chunk]);
+
+        for sneak in [true, false] {
+            let mut player = Player::new();
+            player.set_position(Position::new(Vector3::new(2., 2.8, 4.5), 0., 0.));
+            let frame = Duration::from_millis(10);
+            for _ in 0..20 {
+                player.step(frame, &world);
+            }
+            player.toggle_state(MotionState::Sneak, sneak);
+            player.toggle_state(MotionState::Up, true);
+            for _ in 0..300 {
+                player.step(frame, &world);
+            }
+            let end = player.position().pos();
+            if sneak {
+                // Still standing on the last cube, hanging over the void
+                assert!(end.y() > 2.7 && end.x() > 4. && end.x() < 4. + DIAMETER / 2., "sneaked to {:?}", end);
+            } else {
+                assert!(end.y() < 2., "walked to {:?}", end);
+            }
+        }
+    }
+
+    #[test]
+    fn test_slide_out_of_inside_corner() {
+        // A floor, with a wall along z at x = 5 and another one along x at z = 5
+        let mut chunk = Chunk::new([0., 0.]);
+        chunk.fill_layer(0, Block::GRASS);
+        for i in 0..6 {
+            for y in 1..4 {
+                chunk.add_cube(Vector3::new(5., y as f32, i as f32), Block::STONE, 0);
+                chunk.add_cube(Vector3::new(i as f32, y as f32, 5.), Block::STONE, 0);
+            }
+        }
+        let world = World::new(vec![chunk]);
+
+        // The player is against the first wall, and walks diagonally into the corner
+        let mut player = Player::new();
+        player.set_position(Position::new(Vector3::new(4.74, 2.8, 2.), FRAC_PI_4, 0.));
+        let frame = Duration::from_millis(10);
+        for _ in 0..20 {
+            player.step(frame, &world);
+        }
+        let start = player.position().pos();
+
+        player.toggle_state(MotionState::Up, true);
+        for _ in 0..30 {
+            player.step(frame, &world);
+        }
+        let end = player.position().pos();
+
+        // Blocked by the wall along x, but sliding along it
+        assert!(end.x() < 4.75 && (end.x() - start.x()).abs() < 0.01, "from {:?} to {:?}", start, end);
+        assert!(end.z() > start.z() + 0.5, "from {:?} to {:?}", start, end);
+
+        // Until the corner, where the player stops without going through any wall
+        for _ in 0..200 {
+            player.step(frame, &world);
+        }
+        let end = player.position().pos();
+        assert!(end.x() < 4.75 && end.z() < 4.75, "stuck at {:?}", end);
+        assert!(end.x() > 4.7 && end.z() > 4.7, "stuck at {:?}", end);
+    }
+
+    #[test]
+    fn test_step_up_onto_single_blocks() {
+        // A floor, with a step of one cube at x = 3 and a wall of two cubes at x = 6
+        let mut chunk = Chunk::new([0., 0.]);
+        chunk.fill_layer(0, Block::GRASS);
+        for z in 0..8 {
+            for x in 3..6 {
+                chunk.add_cube(Vector3::new(x as f32, 1., z as f32), Block::STONE, 0);
+            }
+            for y in 2..4 {
+                chunk.add_cube(Vector3::new(6., y as f32, z as f32), Block::STONE, 0);
+            }
+        }
+        let world = World::new(vec![chunk]);
+
+        let mut player = Player::new();
+        player.set_position(Position::new(Vector3::new(1., 2.8, 4.5), 0., 0.));
+        let frame = Duration::from_millis(10);
+        for _ in 0..20 {
+            player.step(frame, &world);
+        }
+        player.toggle_state(MotionState::Up, true);
+        for _ in 0..200 {
+            player.step(frame, &world);
+        }
+
+        // Up on the step, and stopped by the wall
+        let aabb = player.aabb();
+        assert!((aabb.bottom() - 2.).abs() < 0.01, "standing at {:?}", player.position().pos());
+        assert!(aabb.east() < 6. && aabb.east() > 6. - 0.01, "stopped at {:?}", player.position().pos());
+    }
+
+    #[test]
+    fn test_third_person_camera_pulled_in_by_walls() {
+        // A floor, with a wall at x = 1
+        let mut chunk = Chunk::new([0., 0.]);
+        chunk.fill_layer(0, Block::GRASS);
+        for z in 0..8 {
+            for y in 1..5 {
+                chunk.add_cube(Vector3::new(1., y as f32, z as f32), Block::STONE, 0);
+            }
+        }
+        let world = World::new(vec![chunk]);
+
+        // Looking along +x, away from the wall
+        let mut player = Player::new();
+        player.set_position(Position::new(Vector3::new(7.5, 2.8, 4.5), 0., 0.));
+        player.set_camera_mode(CameraMode::ThirdPerson);
+        player.step(Duration::from_millis(10), &world);
+        let eye = player.eye_position();
+        assert!(((eye - player.camera_position()).norm() - THIRD_PERSON_DISTANCE).abs() < 1e-4);
+        assert!(player.camera_position().x() < eye.x());
+
+        // Closer to the wall, the camera stays in front of it
+        player.set_position(Position::new(Vector3::new(4.5, 2.8, 4.5), 0., 0.));
+        player.step(Duration::from_millis(10), &world);
+        let camera = player.camera_position();
+        assert!(camera.x() > 2. && camera.x() < 2.5, "camera at {:?}", camera);
+
+        player.set_camera_mode(CameraMode::FirstPerson);
+        player.step(Duration::from_millis(10), &world);
+        assert_eq!(player.camera_position(), player.eye_position());
+    }
+
+    #[test]
+    fn test_collision_box_grows_with_the_cubes() {
+        // A floor, with a wall along z at x = 5
+        let mut chunk = Chunk::new([0., 0.]);
+        chunk.fill_layer(0, Block::GRASS);
+        for z in 0..10 {
+            for y in 1..4 {
+                chunk.add_cube(Vector3::new(5., y as f32, z as f32), Block::STONE, 0);
+            }
+        }
+        let mut world = World::new(vec![chunk]);
+        world.set_scale(2.);
+
+        let mut player = Player::new();
+        player.set_position(Position::new(Vector3::new(6., 6., 8.), 0., 0.));
+        let frame = Duration::from_millis(10);
+        player.toggle_state(MotionState::Up, true);
+        for _ in 0..200 {
+            player.step(frame, &world);
+        }
+
+        // The wall is at x = 10 in the world space, the player is twice as wide as on a unit grid
+        let aabb = player.aabb();
+        assert!((aabb.east() - aabb.west() - 2. * DIAMETER).abs() < 1e-4);
+        let end = player.position().pos();
+        assert!(end.x() < 10. - DIAMETER && end.x() > 10. - DIAMETER - 0.05, "stopped at {:?}", end);
+        // Standing on the floor, whose top is at y = 2
+        assert!((aabb.bottom() - 2.).abs() < 0.05, "standing at {:?}", end);
+    }
+
+    #[test]
+    fn test_breaking_starts_over_on_another_cube() {
+        let mut chunk = Chunk::new([0., 0.]);
+        chunk.fill_layer(0, Block::STONE);
+        chunk.fill_layer(1, Block::STONE);
+        let world = World::new(vec![chunk]);
+        let mut player = Player::new();
+        player.set_game_mode(GameMode::Survival);
+        player.set_position(Position::new(Vector3::new(2.5, 4., 2.5), 0., -FRAC_PI_2));
+        player.compute_selected_cube(&world);
+        let break_time = player.break_time(&Block::STONE);
+
+        // Holding the button on a cube breaks it little by little
+        player.toggle_state(MotionState::LeftClick, true);
+        assert!(!player.is_time_to_break_over(0.6 * break_time));
+        assert!((player.break_progress() - 0.6).abs() < 1e-4);
+
+        // Looking at the next cube starts over
+        player.set_position(Position::new(Vector3::new(3.5, 4., 2.5), 0., -FRAC_PI_2));
+        player.compute_selected_cube(&world);
+        assert_eq!(player.break_progress(), 0.);
+        assert!(!player.is_time_to_break_over(0.6 * break_time));
+        assert!((player.break_progress() - 0.6).abs() < 1e-4);
+        assert!(player.is_time_to_break_over(0.5 * break_time));
+    }
+
+    #[test]
+    fn test_place_cooldown() {
+        let world = World::empty();
+        let mut player = Player::new();
+        player.set_action_cooldowns(Duration::from_millis(150), Duration::from_millis(150));
+
+        // The click itself places a block right away
+        player.toggle_state(MotionState::RightClick, true);
+        assert!(player.is_time_to_place());
+
+        // Then holding the button for 1s, and trying to place at every frame
+        let frame = Duration::from_millis(5);
+        let mut placed = 0;
+        for _ in 0..200 {
+            player.step(frame, &world);
+            if player.is_time_to_place() {
+                placed += 1;
+            }
+        }
+        assert_eq!(placed, 1000 / 150);
+
+        // Releasing the button stops the placement, and clicking again places right away
+        player.toggle_state(MotionState::RightClick, false);
+        assert!(!player.is_time_to_place());
+        player.toggle_state(MotionState::RightClick, true);
+        assert!(player.is_time_to_place());
+    }
+
+    #[test]
+    fn test_flipped_gravity() {
+        // Only a ceiling, at y = 10
+        let mut chunk = Chunk::new([0., 0.]);
+        chunk.fill_layer(10, Block::STONE);
+        let mut world = World::new(vec![chunk]);
+        world.set_gravity_direction(Vector3::unit_y());
+
+        let mut player = Player::new();
+        player.set_position(Position::new(Vector3::new(4.5, 5., 4.5), 0., 0.));
+        let frame = Duration::from_millis(10);
+        let mut previous = player.position().pos().y();
+        for i in 0..100 {
+            player.step(frame, &world);
+            let y = player.position().pos().y();
+            // Falling up until the ceiling is reached
+            assert!(y >= previous, "going down at frame {i}");
+            previous = y;
+        }
+
+        // Landed against the bottom of the ceiling: the top of the player touches it
+        assert!(!player.in_air);
+        let top = player.position().pos().y() + FOREHEAD;
+        assert!(top < 10. && top > 10. - 1e-3, "top of the player at {top}");
+
+        // Jumping goes back down
+        player.toggle_state(MotionState::Jump, true);
+        player.step(frame, &world);
+        assert!(player.position().pos().y() < previous);
+    }
+}
@@ -1,43 +1,87 @@
+use serde::{Deserialize, Serialize};
+
 use crate::block_kind::Block;
+use crate::crafting::Recipe;
+use crate::food::Food;
+use crate::item::{Item, LegacyItem};
+use crate::tool::Tool;
+
+/// Number of slots of the hotbar, selected with the keys 1 to 9
+pub const HOTBAR_SIZE: usize = 9;
+/// Number of slots of the storage, only visible when the inventory is open
+pub const STORAGE_SIZE: usize = HOTBAR_SIZE * 3;
+
+/// Items of the same kind in a slot
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(from = "SavedStack")]
+pub struct ItemStack {
+    pub item: Item,
+    pub count: usize,
+    /// Number of times the tool of the stack was used, see `Tool::durability`, 0 for the other items.
+    /// The tool keeps it when it is moved to another slot, a chest or the ground.
+    pub wear: u16,
+}
 
-pub type ItemStack = (Block, usize);
+/// A stack as it is read from the saves, the chests saved before the tools were items hold `[block, count]` pairs
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SavedStack {
+    Stack { item: Item, count: usize, wear: u16 },
+    Legacy(LegacyItem, usize),
+}
+
+impl From<SavedStack> for ItemStack {
+    fn from(stack: SavedStack) -> Self {
+        match stack {
+            SavedStack::Stack { item, count, wear } => Self { item, count, wear },
+            SavedStack::Legacy(item, count) => Self::new(Item::from(item), count),
+        }
+    }
+}
 
-const CURRENT_ITEMS_SIZE: usize = 8;
-const MAX_ITEMS_IN_SLOT: usize = 64;
+impl ItemStack {
+    /// A stack of new items
+    pub fn new(item: impl Into<Item>, count: usize) -> Self {
+        Self { item: item.into(), count, wear: 0 }
+    }
 
+    /// Number of uses left to the tool of the stack
+    pub fn durability(&self) -> Option<u16> {
+        Some(self.item.tool()?.durability().saturating_sub(self.wear))
+    }
+}
 
-/// Holds the items of a player.
+/// Holds the items of a player: the hotbar and the storage
 #[derive(Clone)]
-pub struct PlayerItems {
+pub struct Inventory {
     /// The items always displayed on the item bars
-    bar_items: [Option<ItemStack>; CURRENT_ITEMS_SIZE],
+    bar_items: [Option<ItemStack>; HOTBAR_SIZE],
     /// The items only visible when crafting
-    inventory_items: [Option<ItemStack>; CURRENT_ITEMS_SIZE * 3],
-    current_item: usize
+    inventory_items: [Option<ItemStack>; STORAGE_SIZE],
+    /// The active slot of the hotbar, whose block is placed
+    current_item: usize,
 }
 
-impl PlayerItems {
+impl Inventory {
     pub fn empty() -> Self {
         Self {
-            bar_items: [None; CURRENT_ITEMS_SIZE],
-            inventory_items: [None; CURRENT_ITEMS_SIZE * 3],
+            bar_items: [None; HOTBAR_SIZE],
+            inventory_items: [None; STORAGE_SIZE],
             current_item: 0,
         }
     }
 
-    pub fn new(inventory_items: [Option<(Block, usize)>; CURRENT_ITEMS_SIZE * 3], current_items: [Option<(Block, usize)>; CURRENT_ITEMS_SIZE]) -> Self {
+    pub fn new(inventory_items: [Option<ItemStack>; STORAGE_SIZE], current_items: [Option<ItemStack>; HOTBAR_SIZE]) -> Self {
         Self {
             bar_items: current_items,
             inventory_items,
-            current_item: 0
+            current_item: 0,
         }
     }
 
-    pub fn get_bar_items(&self) -> Vec<ItemStack> {
-        self.bar_items.iter()
-            .filter(|item| item.is_some())
-            .map(|item| item.unwrap())
-            .collect()
+    /// The slots of the hotbar, including the empty ones
+    pub fn get_bar_items(&self) -> Vec<Option<ItemStack>> {
+        self.bar_items.to_vec()
     }
 
     pub fn get_bar_item(&self, index: usize) -> Option<ItemStack> {
@@ -48,59 +92,84 @@ impl PlayerItems {
         self.inventory_items[index]
     }
 
-    pub fn take_bar_item(&mut self, index: usize) -> Option<Block> {
+    /// Takes one item of this slot of the hotbar, a tool keeps its wear
+    pub fn take_bar_item(&mut self, index: usize) -> Option<ItemStack> {
         Self::take_item(&mut self.bar_items[index])
     }
 
-    pub fn take_inventory_item(&mut self, index: usize) -> Option<Block> {
+    pub fn take_inventory_item(&mut self, index: usize) -> Option<ItemStack> {
         Self::take_item(&mut self.inventory_items[index])
     }
 
-    pub fn put_bar_item(&mut self, index: usize, block: Block) -> bool {
-        Self::put_item(&mut self.bar_items[index], block)
+    pub fn put_bar_item(&mut self, index: usize, item: ItemStack) -> bool {
+        Self::put_item(&mut self.bar_items[index], item)
     }
     
-    pub fn put_inventory_item(&mut self, index: usize, block: Block) -> bool {
-        Self::put_item(&mut self.inventory_items[index], block)
+    pub fn put_inventory_item(&mut self, index: usize, item: ItemStack) -> bool {
+        Self::put_item(&mut self.inventory_items[index], item)
     }
     
+    /// The block in the active slot of the hotbar, if it holds one
     pub fn get_current_block(&self) -> Option<Block> {
-        if let Some((_, Some((block, _)))) = self.bar_items.iter()
-            .filter(|item| item.is_some())
-            .enumerate()
-            .find(|(i, _)| *i == self.current_item) {
-            Some(block.clone())
-        } else {
-            None
-        }
+        self.bar_items[self.current_item].and_then(|stack| stack.item.block())
     }
     
-    pub fn attack_strength(&self) -> u8 {
-        if let Some(b) = self.get_current_block() {
-           b.attack_strength() 
-        } else {
-            1
+    /// The tool in the active slot of the hotbar, if it holds one
+    pub fn current_tool(&self) -> Option<Tool> {
+        self.bar_items[self.current_item].and_then(|stack| stack.item.tool())
+    }
+
+    /// The food in the active slot of the hotbar, if it holds some
+    pub fn current_food(&self) -> Option<Food> {
+        self.bar_items[self.current_item].and_then(|stack| stack.item.food())
+    }
+
+    /// Number of uses left to the tool in this slot of the hotbar
+    pub fn durability(&self, index: usize) -> Option<u16> {
+        self.bar_items[index]?.durability()
+    }
+
+    /// Wears the tool of the active slot by one use, it breaks and leaves its slot once worn out.
+    /// Returns true if the tool broke.
+    pub fn use_current_tool(&mut self) -> bool {
+        let slot = &mut self.bar_items[self.current_item];
+        let Some(stack) = slot.as_mut().filter(|stack| stack.item.tool().is_some()) else {
+            return false;
+        };
+        stack.wear += 1;
+        if stack.durability() != Some(0) {
+            return false;
         }
+        *slot = None;
+        true
+    }
+
+    pub fn attack_strength(&self) -> u8 {
+        self.bar_items[self.current_item].map_or(1, |stack| stack.item.attack_strength())
+    }
+
+    /// Adds a new item, in the hotbar if there is room for it, or else in the storage
+    pub fn collect(&mut self, item: impl Into<Item>) {
+        self.collect_stack(ItemStack::new(item, 1));
     }
 
-    pub fn collect(&mut self, block: Block) {
+    /// Adds the items of the stack one by one, see `collect`. The tools keep their wear.
+    /// Returns the items left once the inventory is full, if any.
+    pub fn collect_stack(&mut self, stack: ItemStack) -> Option<ItemStack> {
 
-        fn place_in_collection(list: &mut [Option<ItemStack>], block: Block) -> bool {
+        fn place_in_collection(list: &mut [Option<ItemStack>], item: ItemStack) -> bool {
             // First check if the item already exists in the list
             // If so, simply increase the counter
-            for i in 0..list.len() {
-                if let Some((b, count)) = list[i] {
-                    if b == block && count < MAX_ITEMS_IN_SLOT {
-                        list[i] = Some((b, count + 1));
-                        return true;
-                    }
+            for slot in list.iter_mut() {
+                if Inventory::put_item_in_stack(slot, item) {
+                    return true;
                 }
             }
 
             // Second, try to place the item in the first remaining slot
-            for i in 0..list.len() {
-                if list[i].is_none() {
-                    list[i] = Some((block, 1));
+            for slot in list.iter_mut() {
+                if slot.is_none() {
+                    *slot = Some(item);
                     return true;
                 }
             }
@@ -108,57 +177,116 @@ impl PlayerItems {
             false
         }
 
-        if !place_in_collection(&mut self.bar_items, block) {
-            place_in_collection(&mut self.inventory_items, block);
+        let item = ItemStack { count: 1, ..stack };
+        for placed in 0..stack.count {
+            if !place_in_collection(&mut self.bar_items, item) && !place_in_collection(&mut self.inventory_items, item) {
+                return Some(ItemStack { count: stack.count - placed, ..stack });
+            }
         }
+        None
+    }
 
+    /// Removes one of these items from the hotbar, from the active slot if it holds them
+    pub fn consume(&mut self, item: impl Into<Item>) {
+        let item = item.into();
+        let holds = |slot: &Option<ItemStack>| slot.is_some_and(|stack| stack.item == item);
+        if holds(&self.bar_items[self.current_item]) {
+            Self::take_item(&mut self.bar_items[self.current_item]);
+            return;
+        }
+        if let Some(slot) = self.bar_items.iter_mut().find(|slot| holds(slot)) {
+            Self::take_item(slot);
+        }
+    }
+
+    /// Number of items of this kind, in the hotbar and in the storage
+    pub fn count(&self, item: impl Into<Item>) -> usize {
+        let item = item.into();
+        self.bar_items.iter()
+            .chain(self.inventory_items.iter())
+            .flatten()
+            .filter(|stack| stack.item == item)
+            .map(|stack| stack.count)
+            .sum()
     }
 
-    pub fn consume(&mut self, block: Block) {
-        for i in 0..CURRENT_ITEMS_SIZE {
-            if let Some((b,c)) = self.bar_items[i] {
-                if b == block {
-                    if c == 1 {
-                        self.bar_items[i] = None
-                    } else {
-                        self.bar_items[i] = Some((b, c-1))
-                    }
-                    return;
+    /// Removes `count` items of this kind, from the storage first.
+    /// Nothing is removed if there are not enough of them.
+    pub fn remove(&mut self, item: impl Into<Item>, count: usize) -> bool {
+        let item = item.into();
+        if self.count(item) < count {
+            return false;
+        }
+        let mut left = count;
+        for slot in self.inventory_items.iter_mut().chain(self.bar_items.iter_mut()) {
+            if let Some(stack) = *slot {
+                if stack.item == item && left > 0 {
+                    let taken = stack.count.min(left);
+                    left -= taken;
+                    *slot = if stack.count > taken { Some(ItemStack { count: stack.count - taken, ..stack }) } else { None };
                 }
             }
         }
+        true
+    }
+
+    /// Whether the inventory holds the ingredients of the recipe
+    pub fn can_craft(&self, recipe: &Recipe) -> bool {
+        recipe.ingredients().iter().all(|(block, count)| self.count(*block) >= *count)
+    }
+
+    /// Consumes the ingredients of the recipe and collects its output.
+    /// Returns false, without changing anything, if some ingredients are missing.
+    pub fn craft(&mut self, recipe: &Recipe) -> bool {
+        if !self.can_craft(recipe) {
+            return false;
+        }
+        for (block, count) in recipe.ingredients() {
+            self.remove(block, count);
+        }
+        self.collect_stack(recipe.output());
+        true
     }
 
     pub fn has_block(&self, block: Block) -> bool {
         self.bar_items.iter()
-            .any(|item| item.is_some_and(|(b, _)| b == block))
+            .any(|item| item.is_some_and(|stack| stack.item == Item::Block(block)))
     }
 
     pub fn debug(&self) {
         println!("Debugging items...");
-        for i in 0..CURRENT_ITEMS_SIZE {
+        for i in 0..HOTBAR_SIZE {
             println!("{:?}", self.bar_items[i])
         }
     }
 
+    /// Selects a slot of the hotbar, the slots outside of it are ignored
     pub fn set_current_item(&mut self, current_item: usize) {
-        self.current_item = current_item;
+        if current_item < HOTBAR_SIZE {
+            self.current_item = current_item;
+        }
     }
 
     pub fn current_item(&self) -> usize {
         self.current_item
     }
 
-    fn take_item(itemstack: &mut Option<ItemStack>) -> Option<Block> {
-        let mut ret: Option<Block> = None;
+    /// Moves the current item by `steps` slots of the bar, wrapping around its ends
+    pub fn cycle_current_item(&mut self, steps: i32) {
+        self.current_item = (self.current_item as i32 + steps).rem_euclid(HOTBAR_SIZE as i32) as usize;
+    }
+
+    /// Takes one item of the slot, if there is one. A tool keeps its wear.
+    pub fn take_item(itemstack: &mut Option<ItemStack>) -> Option<ItemStack> {
+        let mut ret: Option<ItemStack> = None;
         
-        *itemstack = if let Some((block, count)) = itemstack {
-            if *count > 0 {
-                ret = Some(*block);
+        *itemstack = if let Some(stack) = itemstack {
+            if stack.count > 0 {
+                ret = Some(ItemStack { count: 1, ..*stack });
             }
             
-            if *count > 1 {
-                Some((*block, *count - 1))
+            if stack.count > 1 {
+                Some(ItemStack { count: stack.count - 1, ..*stack })
             } else {
                 None
             }
@@ -169,36 +297,38 @@ impl PlayerItems {
         ret
     }
 
-    fn put_item(itemstack: &mut Option<ItemStack>, block: Block) -> bool {
-        let mut success: bool = false;
-        *itemstack = match itemstack {
-            Some((block2, count)) => {
-                if *block2 == block {
-                    // TODO put stack limit
-                    success = true;
-                    Some((*block2, *count + 1))
-                } else {
-                    Some((*block2, *count))
-                }
-            },
-            None => {
-                success = true;
-                Some((block, 1))
-            }
-        };
+    /// Adds one item (`item` is a stack of one) to the slot if it is empty or holds the same items without being full
+    pub fn put_item(itemstack: &mut Option<ItemStack>, item: ItemStack) -> bool {
+        if itemstack.is_none() {
+            *itemstack = Some(item);
+            return true;
+        }
+        Self::put_item_in_stack(itemstack, item)
+    }
 
-        success
+    /// Adds one item to the stack of the slot if it holds the same items without being full
+    fn put_item_in_stack(itemstack: &mut Option<ItemStack>, item: ItemStack) -> bool {
+        match itemstack {
+            Some(stack) if stack.item == item.item && stack.count < item.item.max_in_slot() => {
+                stack.count += 1;
+                true
+            }
+            _ => false,
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::block_kind::Block::COBBELSTONE;
-    use crate::player_items::PlayerItems;
+    use crate::block_kind::Block::{COBBELSTONE, DIRT, GRASS, OAKLEAVES, OAKLOG, SAND, SLAB, STONE, WATER};
+    use crate::crafting::{CraftingManager, Recipe, LARGE_GRID, SMALL_GRID};
+    use crate::item::Item;
+    use crate::player_items::{Inventory, ItemStack, HOTBAR_SIZE, STORAGE_SIZE};
+    use crate::tool::Tool;
 
     #[test]
     fn test_basic_operations() {
-        let mut items = PlayerItems::empty();
+        let mut items = Inventory::empty();
 
         // At first, there is simply no block to add
         assert_eq!(items.get_current_block(), None);
@@ -220,4 +350,99 @@ mod tests {
         // After consuming 2 stones, we don't have anymore cubes to place
         assert_eq!(items.get_current_block(), None);
     }
+
+    #[test]
+    fn test_hotbar_and_storage() {
+        let mut items = Inventory::empty();
+        assert_eq!(items.get_bar_items().len(), HOTBAR_SIZE);
+
+        // A full stack, then a new one in the next slot
+        for _ in 0..65 {
+            items.collect(DIRT);
+        }
+        assert_eq!(items.get_bar_item(0), Some(ItemStack::new(DIRT, 64)));
+        assert_eq!(items.get_bar_item(1), Some(ItemStack::new(DIRT, 1)));
+        assert!(!items.put_bar_item(0, ItemStack::new(DIRT, 1)));
+
+        // The blocks go to the storage once the hotbar is full
+        for block in [GRASS, COBBELSTONE, OAKLOG, OAKLEAVES, STONE, SAND, WATER] {
+            items.collect(block);
+        }
+        items.collect(Tool::Sword);
+        assert_eq!(items.get_inventory_item(0), Some(ItemStack::new(Tool::Sword, 1)));
+        assert_eq!(items.get_inventory_item(STORAGE_SIZE - 1), None);
+
+        // Placing takes from the active slot, even if another slot has the same block
+        items.set_current_item(1);
+        assert_eq!(items.get_current_block(), Some(DIRT));
+        items.consume(DIRT);
+        assert_eq!(items.get_bar_item(0), Some(ItemStack::new(DIRT, 64)));
+        assert_eq!(items.get_bar_item(1), None);
+        // The empty slot stays selected
+        assert_eq!(items.get_current_block(), None);
+
+        items.set_current_item(HOTBAR_SIZE);
+        assert_eq!(items.current_item(), 1);
+        items.set_current_item(8);
+        assert_eq!(items.get_current_block(), Some(WATER));
+    }
+
+    #[test]
+    fn test_tools_wear_out() {
+        let mut items = Inventory::empty();
+        // The tools do not stack
+        items.collect(Tool::Pickaxe);
+        items.collect(Tool::Pickaxe);
+        assert_eq!(items.get_bar_item(0), Some(ItemStack::new(Tool::Pickaxe, 1)));
+        assert_eq!(items.get_bar_item(1), Some(ItemStack::new(Tool::Pickaxe, 1)));
+        assert_eq!(items.current_tool(), Some(Tool::Pickaxe));
+        assert_eq!(items.get_current_block(), None);
+
+        let durability = Tool::Pickaxe.durability();
+        for _ in 0..durability - 1 {
+            assert!(!items.use_current_tool());
+        }
+        assert_eq!(items.durability(0), Some(1));
+        assert_eq!(items.durability(1), Some(durability));
+
+        // The tool keeps its wear when it is moved to another slot
+        let worn = items.take_bar_item(0).unwrap();
+        assert_eq!(worn.wear, durability - 1);
+        assert!(items.put_inventory_item(3, worn));
+        assert_eq!(items.get_inventory_item(3).unwrap().durability(), Some(1));
+        let worn = items.take_inventory_item(3).unwrap();
+        assert!(items.put_bar_item(0, worn));
+        assert_eq!(items.durability(0), Some(1));
+
+        // The last use breaks the tool
+        assert!(items.use_current_tool());
+        assert_eq!(items.get_bar_item(0), None);
+        assert_eq!(items.current_tool(), None);
+        assert!(!items.use_current_tool());
+        assert_eq!(items.get_bar_item(1).map(|stack| stack.item), Some(Item::Tool(Tool::Pickaxe)));
+    }
+
+    #[test]
+    fn test_craft() {
+        let mut items = Inventory::empty();
+        let manager = CraftingManager::with_default_recipes();
+        let stone = Recipe::shaped(&[&[Some(COBBELSTONE), Some(COBBELSTONE)], &[Some(COBBELSTONE), Some(COBBELSTONE)]], STONE, 4);
+        for _ in 0..5 {
+            items.collect(COBBELSTONE);
+        }
+        items.collect(OAKLOG);
+        let craftable: Vec<Item> = manager.craftable(&items, SMALL_GRID).iter().map(|recipe| recipe.output().item).collect();
+        assert_eq!(craftable, vec![Item::Block(STONE)]);
+        let craftable: Vec<Item> = manager.craftable(&items, LARGE_GRID).iter().map(|recipe| recipe.output().item).collect();
+        assert_eq!(craftable, vec![Item::Tool(Tool::Sword), Item::Block(STONE), Item::Block(SLAB)]);
+
+        assert!(items.craft(&stone));
+        assert_eq!(items.count(COBBELSTONE), 1);
+        assert_eq!(items.count(STONE), 4);
+
+        // Not enough cobblestone left: nothing changes
+        assert!(!items.craft(&stone));
+        assert_eq!(items.count(COBBELSTONE), 1);
+        assert_eq!(items.count(OAKLOG), 1);
+    }
 }
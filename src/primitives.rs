@@ -2,4 +2,7 @@ pub mod position;
 pub mod math;
 pub mod vector;
 mod matrix;
-pub mod face;
\ No newline at end of file
+pub mod face;
+pub mod voxel_traversal;
+pub mod frustum;
+pub mod ray;
\ No newline at end of file
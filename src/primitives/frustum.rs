@@ -0,0 +1,101 @@
+use crate::aabb::AABB;
+use crate::primitives::vector::Vector3;
+
+/// The volume seen by the camera, bounded by 6 planes.
+/// Each plane is `[a, b, c, d]` such that `a x + b y + c z + d >= 0` inside of the frustum.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Frustum {
+    planes: [[f32; 4]; 6],
+}
+
+/// Product of two matrices, stored column by column as given to OpenGL
+fn multiply(a: &[[f32; 4]; 4], b: &[[f32; 4]; 4]) -> [[f32; 4]; 4] {
+    let mut m = [[0.; 4]; 4];
+    for col in 0..4 {
+        for row in 0..4 {
+            m[col][row] = (0..4).map(|k| a[k][row] * b[col][k]).sum();
+        }
+    }
+    m
+}
+
+impl Frustum {
+    /// Builds the frustum from the view and the projection matrices (as given to the shaders).
+    /// The frustum is then expressed in the same space as the view matrix, the world space.
+    pub fn from_matrices(view: &[[f32; 4]; 4], projection: &[[f32; 4]; 4]) -> Self {
+        // A point is visible when -w <= x, y, z <= w in the clip space (Gribb & Hartmann)
+        let m = multiply(projection, view);
+        let row = |i: usize| [m[0][i], m[1][i], m[2][i], m[3][i]];
+        let plane = |i: usize, sign: f32| {
+            let (r, w) = (row(i), row(3));
+            [w[0] + sign * r[0], w[1] + sign * r[1], w[2] + sign * r[2], w[3] + sign * r[3]]
+        };
+        Self {
+            planes: [plane(0, 1.), plane(0, -1.), plane(1, 1.), plane(1, -1.), plane(2, 1.), plane(2, -1.)],
+        }
+    }
+
+    pub fn contains_point(&self, point: &Vector3) -> bool {
+        self.planes
+            .iter()
+            .all(|[a, b, c, d]| a * point.x() + b * point.y() + c * point.z() + d >= 0.)
+    }
+
+    /// Returns false if the box is entirely outside of the frustum.
+    /// It may return true for a box that is just outside, next to a corner of the frustum,
+    /// which is fine to decide what to draw.
+    pub fn intersects_aabb(&self, aabb: &AABB) -> bool {
+        self.planes.iter().all(|[a, b, c, d]| {
+            // The corner of the box the farthest inside of this plane
+            let x = if *a >= 0. { aabb.east() } else { aabb.west() };
+            let y = if *b >= 0. { aabb.top() } else { aabb.bottom() };
+            let z = if *c >= 0. { aabb.north() } else { aabb.south() };
+            a * x + b * y + c * z + d >= 0.
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aabb::AABB;
+    use crate::camera::perspective_matrix;
+    use crate::primitives::frustum::Frustum;
+    use crate::primitives::vector::Vector3;
+
+    const IDENTITY: [[f32; 4]; 4] = [
+        [1., 0., 0., 0.],
+        [0., 1., 0., 0.],
+        [0., 0., 1., 0.],
+        [0., 0., 0., 1.],
+    ];
+
+    #[test]
+    fn test_frustum_of_perspective() {
+        // The camera is at the origin and looks toward +z
+        let frustum = Frustum::from_matrices(&IDENTITY, &perspective_matrix((800, 600), 1.));
+        assert!(frustum.contains_point(&Vector3::new(0., 0., 10.)));
+        assert!(!frustum.contains_point(&Vector3::new(0., 0., -10.)));
+        // Beyond the far plane
+        assert!(!frustum.contains_point(&Vector3::new(0., 0., 2000.)));
+        // Far on the side, outside of the field of view
+        assert!(!frustum.contains_point(&Vector3::new(50., 0., 10.)));
+
+        let in_front = AABB::new(12., 8., 2., -2., 2., -2.).unwrap();
+        let behind = AABB::new(-8., -12., 2., -2., 2., -2.).unwrap();
+        // Its center is outside, but a part of it is seen
+        let across_the_side = AABB::new(12., 8., 2., -2., 40., 0.).unwrap();
+        assert!(frustum.intersects_aabb(&in_front));
+        assert!(!frustum.intersects_aabb(&behind));
+        assert!(frustum.intersects_aabb(&across_the_side));
+    }
+
+    #[test]
+    fn test_frustum_follows_the_view() {
+        // The camera moved by 100 along +x, the view matrix moves the world the other way
+        let mut view = IDENTITY;
+        view[3][0] = -100.;
+        let frustum = Frustum::from_matrices(&view, &perspective_matrix((800, 600), 1.));
+        assert!(frustum.contains_point(&Vector3::new(100., 0., 10.)));
+        assert!(!frustum.contains_point(&Vector3::new(0., 0., 10.)));
+    }
+}
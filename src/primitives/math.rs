@@ -19,9 +19,23 @@ pub fn lcm(a: u32, b: u32) -> u32 {
     return a * (b / gcd(a, b));
 }
 
+/// One step of SplitMix64. Unlike the hashers of the standard library, its output is fixed,
+/// so what is derived from a saved seed stays the same across platforms and Rust releases.
+pub fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9E3779B97F4A7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+}
+
+/// Mixes a seed with a few integers (usually coordinates) into the seed of a random generator
+pub fn mix_seed(seed: u64, values: &[i64]) -> u64 {
+    values.iter().fold(splitmix64(seed), |mixed, &value| splitmix64(mixed ^ value as u64))
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::primitives::math::{gcd, lcm};
+    use crate::primitives::math::{gcd, lcm, mix_seed, splitmix64};
 
  
     #[test]
@@ -44,4 +58,13 @@ mod tests {
         assert_eq!(gcd(10, 15), 5);
     }
 
+    #[test]
+    fn test_seeds_are_stable() {
+        // The first output of the reference SplitMix64 generator
+        assert_eq!(splitmix64(0), 0xE220A8397B1DCDAF);
+        assert_eq!(mix_seed(42, &[1, -2]), mix_seed(42, &[1, -2]));
+        assert_ne!(mix_seed(42, &[1, -2]), mix_seed(42, &[-2, 1]));
+        assert_ne!(mix_seed(42, &[1, -2]), mix_seed(43, &[1, -2]));
+    }
+
 }
\ No newline at end of file
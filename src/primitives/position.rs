@@ -61,15 +61,16 @@ impl Position {
         format!("{},{},{},{},{}", self.pos.x(), self.pos.y(), self.pos.z(), self.yaw, self.pitch).into_bytes()
     }
 
-    pub fn from_bytes(bytes_to_parse: &[u8]) -> Self {
-        let text = from_utf8(bytes_to_parse).unwrap();
+    /// The position written by `to_bytes`, None if the bytes are not one
+    pub fn from_bytes(bytes_to_parse: &[u8]) -> Option<Self> {
+        let text = from_utf8(bytes_to_parse).ok()?;
         let mut iter = text.split(',');
-        let x = iter.next().unwrap().parse::<f32>().unwrap();
-        let y = iter.next().unwrap().parse::<f32>().unwrap();
-        let z = iter.next().unwrap().parse::<f32>().unwrap();
-        let yaw = iter.next().unwrap().parse::<f32>().unwrap();
-        let pitch = iter.next().unwrap().parse::<f32>().unwrap();
-        Self { pos: Vector3::new(x, y, z), yaw, pitch }
+        let x = iter.next()?.parse::<f32>().ok()?;
+        let y = iter.next()?.parse::<f32>().ok()?;
+        let z = iter.next()?.parse::<f32>().ok()?;
+        let yaw = iter.next()?.parse::<f32>().ok()?;
+        let pitch = iter.next()?.parse::<f32>().ok()?;
+        Some(Self { pos: Vector3::new(x, y, z), yaw, pitch })
     }
 
     pub fn yaw(&self) -> f32 {
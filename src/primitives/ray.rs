@@ -0,0 +1,112 @@
+use crate::primitives::vector::Vector3;
+
+/// A half-line starting at `origin`, its direction is always normalized
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ray {
+    origin: Vector3,
+    direction: Vector3,
+}
+
+/// A cell of the unit grid crossed by a ray
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RayStep {
+    /// Corner of the cell, as for the position of a cube
+    pub cell: Vector3,
+    /// Normal of the face through which the ray entered the cell, zero for the cell of the origin
+    pub normal: Vector3,
+    /// Distance along the ray where it entered the cell
+    pub distance: f32,
+}
+
+impl Ray {
+    pub fn new(origin: Vector3, mut direction: Vector3) -> Self {
+        direction.normalize();
+        Self { origin, direction }
+    }
+
+    pub fn origin(&self) -> Vector3 {
+        self.origin
+    }
+
+    pub fn direction(&self) -> Vector3 {
+        self.direction
+    }
+
+    /// Point at this distance along the ray
+    pub fn at(&self, distance: f32) -> Vector3 {
+        self.origin + self.direction * distance
+    }
+
+    /// Returns the cells of the unit grid crossed by the ray in order, up to `max_distance` (DDA traversal).
+    /// The first one contains the origin. Two consecutive cells always share a face.
+    pub fn cells(&self, max_distance: f32) -> Vec<RayStep> {
+        let mut cell = self.origin.to_cube_coordinates();
+
+        // For each axis: the direction of the steps, the distance at which the next border is crossed,
+        // and the distance between two borders
+        let mut step = [0.; 3];
+        let mut t_max = [f32::MAX; 3];
+        let mut t_delta = [f32::MAX; 3];
+        for i in 0..3 {
+            let d = self.direction[i];
+            if d > 0. {
+                step[i] = 1.;
+                t_max[i] = (cell[i] + 1. - self.origin[i]) / d;
+                t_delta[i] = 1. / d;
+            } else if d < 0. {
+                step[i] = -1.;
+                t_max[i] = (cell[i] - self.origin[i]) / d;
+                t_delta[i] = -1. / d;
+            }
+        }
+
+        let mut steps = vec![RayStep { cell, normal: Vector3::empty(), distance: 0. }];
+        loop {
+            let axis = (0..3)
+                .min_by(|&i, &j| t_max[i].total_cmp(&t_max[j]))
+                .unwrap();
+            let distance = t_max[axis];
+            if distance > max_distance {
+                return steps;
+            }
+            cell[axis] += step[axis];
+            t_max[axis] += t_delta[axis];
+            let mut normal = Vector3::empty();
+            normal[axis] = -step[axis];
+            steps.push(RayStep { cell, normal, distance });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::primitives::ray::Ray;
+    use crate::primitives::vector::Vector3;
+
+    #[test]
+    fn test_ray_cells() {
+        // Along +x, the cells are entered through their -x face
+        let ray = Ray::new(Vector3::new(0.5, 0.5, 0.5), Vector3::new(2., 0., 0.));
+        assert_eq!(ray.direction(), Vector3::unit_x());
+        let steps = ray.cells(3.);
+        assert_eq!(steps.len(), 4);
+        assert_eq!(steps[0].normal, Vector3::empty());
+        assert!(steps[3].cell.equals(&Vector3::new(3., 0., 0.)));
+        assert_eq!(steps[3].normal, Vector3::new(-1., 0., 0.));
+        assert_eq!(steps[3].distance, 2.5);
+
+        // Looking down
+        let steps = Ray::new(Vector3::new(0.5, 3.5, 0.5), Vector3::new(0., -1., 0.)).cells(1.);
+        assert_eq!(steps.len(), 2);
+        assert!(steps[1].cell.equals(&Vector3::new(0., 2., 0.)));
+        assert_eq!(steps[1].normal, Vector3::unit_y());
+
+        // In diagonal, every cell touches the previous one by a face, and the distances grow
+        let steps = Ray::new(Vector3::new(0.2, 0.7, 0.1), Vector3::new(1., -0.8, 1.4)).cells(10.);
+        for pair in steps.windows(2) {
+            assert_eq!(pair[0].cell.distance_to(&pair[1].cell), 1.);
+            assert!(pair[0].distance <= pair[1].distance);
+            assert!(pair[1].distance <= 10.);
+        }
+    }
+}
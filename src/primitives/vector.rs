@@ -91,20 +91,31 @@ impl Vector3 {
     }
     
     pub fn to_cube_coordinates(&self) -> Vector3 {
-        Vector3::new(self.x.floor(), self.y.floor(), self.z.floor())
+        self.to_scaled_cube_coordinates(1.)
+    }
+
+    /// Same as `to_cube_coordinates`, for a grid where a cube has a side of `scale`
+    pub fn to_scaled_cube_coordinates(&self, scale: f32) -> Vector3 {
+        Vector3::new((self.x / scale).floor(), (self.y / scale).floor(), (self.z / scale).floor())
+    }
+
+    /// Integer coordinates of the cube holding this position
+    pub fn to_cell(&self) -> [i32; 3] {
+        [self.x.floor() as i32, self.y.floor() as i32, self.z.floor() as i32]
     }
 
     pub fn to_bytes(&self) -> Vec<u8> {
         format!("{},{},{}", self.x, self.y, self.z).into_bytes()
     }
 
-    pub fn from_bytes(bytes_to_parse: &[u8]) -> Self {
-        let text = from_utf8(bytes_to_parse).unwrap();
-        let mut pos = Vector3::empty();
-        for (i, part) in text.split(',').enumerate() {
-            pos[i] = part.parse::<f32>().unwrap();
-        }
-        pos
+    /// The vector written by `to_bytes`, None if the bytes are not one
+    pub fn from_bytes(bytes_to_parse: &[u8]) -> Option<Self> {
+        let text = from_utf8(bytes_to_parse).ok()?;
+        let mut iter = text.split(',');
+        let x = iter.next()?.parse::<f32>().ok()?;
+        let y = iter.next()?.parse::<f32>().ok()?;
+        let z = iter.next()?.parse::<f32>().ok()?;
+        Some(Vector3::new(x, y, z))
     }
 }
 
@@ -0,0 +1,69 @@
+use crate::primitives::vector::Vector3;
+
+/// Returns the cells of the unit grid crossed by the segment going from `from` to `to`, in order.
+/// The first cell contains `from` and the last one contains `to`.
+/// Two consecutive cells always share a face (Amanatides & Woo traversal).
+pub fn cells_on_segment(from: &Vector3, to: &Vector3) -> Vec<Vector3> {
+    let mut cell = from.to_cube_coordinates();
+    let last = to.to_cube_coordinates();
+    let delta = from.line_to(to);
+
+    // For each axis: the direction of the steps, the fraction of the segment
+    // at which the next border is crossed, and the fraction between two borders
+    let mut step = [0.; 3];
+    let mut t_max = [f32::MAX; 3];
+    let mut t_delta = [f32::MAX; 3];
+    for i in 0..3 {
+        if delta[i] > 0. {
+            step[i] = 1.;
+            t_max[i] = (cell[i] + 1. - from[i]) / delta[i];
+            t_delta[i] = 1. / delta[i];
+        } else if delta[i] < 0. {
+            step[i] = -1.;
+            t_max[i] = (cell[i] - from[i]) / delta[i];
+            t_delta[i] = -1. / delta[i];
+        }
+    }
+
+    // Each step crosses exactly one border, bounding the loop protects against rounding errors
+    let n_steps = (0..3).map(|i| (last[i] - cell[i]).abs() as usize).sum::<usize>();
+    let mut cells = vec![cell];
+    for _ in 0..n_steps {
+        let axis = (0..3)
+            .min_by(|&i, &j| t_max[i].total_cmp(&t_max[j]))
+            .unwrap();
+        cell[axis] += step[axis];
+        t_max[axis] += t_delta[axis];
+        cells.push(cell);
+    }
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::primitives::vector::Vector3;
+    use crate::primitives::voxel_traversal::cells_on_segment;
+
+    #[test]
+    fn test_cells_on_segment() {
+        // Along one axis
+        let cells = cells_on_segment(&Vector3::new(0.5, 0.5, 0.5), &Vector3::new(3.5, 0.5, 0.5));
+        assert_eq!(cells.len(), 4);
+        assert!(cells[3].equals(&Vector3::new(3., 0., 0.)));
+
+        // Backward, across the origin
+        let cells = cells_on_segment(&Vector3::new(0.5, 0.5, 0.5), &Vector3::new(-1.5, 0.5, 0.5));
+        assert_eq!(cells.len(), 3);
+        assert!(cells[2].equals(&Vector3::new(-2., 0., 0.)));
+
+        // In diagonal, every cell touches the previous one by a face
+        let cells = cells_on_segment(&Vector3::new(0.2, 0.7, 0.1), &Vector3::new(3.9, -2.4, 5.3));
+        assert!(cells.last().unwrap().equals(&Vector3::new(3., -3., 5.)));
+        for pair in cells.windows(2) {
+            assert_eq!(pair[0].distance_to(&pair[1]), 1.);
+        }
+
+        // Inside a single cell
+        assert_eq!(cells_on_segment(&Vector3::new(0.2, 0.2, 0.2), &Vector3::new(0.8, 0.9, 0.1)).len(), 1);
+    }
+}
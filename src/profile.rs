@@ -0,0 +1,133 @@
+use std::time::{Duration, Instant};
+use strum::{EnumCount, EnumIter, IntoEnumIterator};
+
+/// The phases of a frame that are timed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, EnumCount)]
+pub enum Phase {
+    /// Building the instances of the cubes of the new or changed chunks
+    ChunkMeshing,
+    /// Choosing what is drawn: level of detail of the chunks, fade in
+    Culling,
+    /// Sending the instances of the cubes to the GPU
+    InstanceUpload,
+    /// Controls, cooldowns and selection of the cube of the player
+    CameraStep,
+    /// Gravity and collisions of the player
+    Gravity,
+}
+
+/// Time spent in each phase of the current frame.
+/// When disabled, nothing is measured: `start` does not even read the clock.
+pub struct FrameProfile {
+    enabled: bool,
+    durations: [Duration; Phase::COUNT],
+}
+
+impl FrameProfile {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            durations: [Duration::ZERO; Phase::COUNT],
+        }
+    }
+
+    pub fn disabled() -> Self {
+        Self::new(false)
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    /// Starts timing a phase, to be given back to `stop`
+    pub fn start(&self) -> Option<Instant> {
+        if self.enabled {
+            Some(Instant::now())
+        } else {
+            None
+        }
+    }
+
+    /// Adds the time since `start` to the phase
+    pub fn stop(&mut self, phase: Phase, start: Option<Instant>) {
+        if let Some(start) = start {
+            self.record(phase, start.elapsed());
+        }
+    }
+
+    /// Times the closure as part of the phase
+    pub fn measure<T>(&mut self, phase: Phase, f: impl FnOnce() -> T) -> T {
+        let start = self.start();
+        let result = f();
+        self.stop(phase, start);
+        result
+    }
+
+    /// Adds a duration to the phase
+    pub fn record(&mut self, phase: Phase, duration: Duration) {
+        if self.enabled {
+            self.durations[phase as usize] += duration;
+        }
+    }
+
+    pub fn get(&self, phase: Phase) -> Duration {
+        self.durations[phase as usize]
+    }
+
+    /// Time spent in all the phases
+    pub fn total(&self) -> Duration {
+        self.durations.iter().sum()
+    }
+
+    /// Starts a new frame
+    pub fn reset(&mut self) {
+        self.durations = [Duration::ZERO; Phase::COUNT];
+    }
+
+    pub fn print_statistics(&self) {
+        for phase in Phase::iter() {
+            println!("{:?} = {:?}", phase, self.get(phase));
+        }
+        println!("total = {:?}", self.total());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use strum::IntoEnumIterator;
+    use crate::profile::{FrameProfile, Phase};
+
+    #[test]
+    fn test_profile_sums_phases() {
+        let mut profile = FrameProfile::new(true);
+        let sleep = Duration::from_millis(5);
+        profile.measure(Phase::CameraStep, || std::thread::sleep(sleep));
+        profile.measure(Phase::CameraStep, || std::thread::sleep(sleep));
+        profile.measure(Phase::Gravity, || std::thread::sleep(sleep));
+        profile.record(Phase::InstanceUpload, Duration::from_millis(3));
+
+        assert!(profile.get(Phase::CameraStep) >= 2 * sleep);
+        assert!(profile.get(Phase::Gravity) >= sleep);
+        assert!(profile.get(Phase::Gravity) < profile.get(Phase::CameraStep));
+        assert_eq!(profile.get(Phase::InstanceUpload), Duration::from_millis(3));
+        assert_eq!(profile.get(Phase::ChunkMeshing), Duration::ZERO);
+        assert_eq!(profile.total(), Phase::iter().map(|phase| profile.get(phase)).sum());
+
+        profile.reset();
+        assert_eq!(profile.total(), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_disabled_profile_records_nothing() {
+        let mut profile = FrameProfile::disabled();
+        assert!(profile.start().is_none());
+        profile.measure(Phase::Culling, || std::thread::sleep(Duration::from_millis(2)));
+        profile.record(Phase::Gravity, Duration::from_millis(3));
+        assert_eq!(profile.total(), Duration::ZERO);
+    }
+}
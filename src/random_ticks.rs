@@ -0,0 +1,71 @@
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+
+use crate::chunk::{ChunkCoord, CHUNK_HEIGHT, CHUNK_SIZE};
+use crate::primitives::vector::Vector3;
+
+/// Number of cells of each chunk picked at each tick of the world
+pub const DEFAULT_RANDOM_TICKS: usize = 3;
+
+/// Picks random cells in the chunks, whose block may then change slowly (the grass spreading, see `World::random_tick`).
+/// Only a few cells of each chunk are looked at per tick, instead of the whole world.
+pub struct RandomTicks {
+    rng: SmallRng,
+    per_chunk: usize,
+}
+
+impl RandomTicks {
+    /// The same seed always picks the same cells
+    pub fn new(seed: u64) -> Self {
+        Self { rng: SmallRng::seed_from_u64(seed), per_chunk: DEFAULT_RANDOM_TICKS }
+    }
+
+    pub fn per_chunk(&self) -> usize {
+        self.per_chunk
+    }
+
+    pub fn set_per_chunk(&mut self, per_chunk: usize) {
+        self.per_chunk = per_chunk;
+    }
+
+    /// `per_chunk` random cells of the chunk at this position, on the grid. A cell can be picked twice.
+    pub fn pick(&mut self, coord: ChunkCoord) -> Vec<Vector3> {
+        (0..self.per_chunk)
+            .map(|_| {
+                let x = coord.0 + self.rng.gen_range(0..CHUNK_SIZE) as i32;
+                let y = self.rng.gen_range(0..CHUNK_HEIGHT) as i32;
+                let z = coord.1 + self.rng.gen_range(0..CHUNK_SIZE) as i32;
+                Vector3::newi(x, y, z)
+            })
+            .collect()
+    }
+
+    /// The generator of the ticks, for the blocks choosing how they change
+    pub fn rng(&mut self) -> &mut SmallRng {
+        &mut self.rng
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::chunk::{CHUNK_HEIGHT, CHUNK_SIZE};
+    use crate::random_ticks::{RandomTicks, DEFAULT_RANDOM_TICKS};
+
+    #[test]
+    fn test_picks_in_the_chunk() {
+        let s = CHUNK_SIZE as f32;
+        let mut ticks = RandomTicks::new(3);
+        let cells = ticks.pick((-8, 16));
+        assert_eq!(cells.len(), DEFAULT_RANDOM_TICKS);
+
+        ticks.set_per_chunk(500);
+        for cell in ticks.pick((-8, 16)) {
+            assert!(cell.x() >= -8. && cell.x() < -8. + s);
+            assert!(cell.z() >= 16. && cell.z() < 16. + s);
+            assert!(cell.y() >= 0. && cell.y() < CHUNK_HEIGHT as f32);
+        }
+
+        // The same seed picks the same cells
+        assert_eq!(RandomTicks::new(3).pick((-8, 16)), cells);
+    }
+}
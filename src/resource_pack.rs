@@ -0,0 +1,66 @@
+use std::path::{Path, PathBuf};
+
+/// Folder of the assets shipped with the game
+pub const BUILT_IN_ASSETS: &str = "./resources/";
+/// Folder of the resource pack used when the settings do not name another one
+pub const DEFAULT_RESOURCE_PACK: &str = "./resourcepack/";
+
+/// Assets replacing the built-in ones, to reskin the game without building it again.
+/// The folder of the pack is laid out as `BUILT_IN_ASSETS` (`block/grass_top.png`, `fonts.png`, `entity/...`,
+/// and `sounds/...` once there are sounds), and each file missing from it is taken from the built-in assets.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResourcePack {
+    root: PathBuf,
+}
+
+impl ResourcePack {
+    pub fn new(root: impl AsRef<Path>) -> Self {
+        let root = root.as_ref().to_path_buf();
+        if root.is_dir() {
+            println!("Using the resource pack in {}", root.display());
+        }
+        Self { root }
+    }
+
+    /// Path of the asset, given relatively to the folder of the assets: in the pack if it is there,
+    /// in the built-in assets otherwise
+    pub fn path(&self, asset: &str) -> PathBuf {
+        let path = self.root.join(asset);
+        if path.is_file() {
+            path
+        } else {
+            Path::new(BUILT_IN_ASSETS).join(asset)
+        }
+    }
+
+    /// Reads the asset, see `path`
+    pub fn read(&self, asset: &str) -> std::io::Result<Vec<u8>> {
+        std::fs::read(self.path(asset))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::resource_pack::{ResourcePack, BUILT_IN_ASSETS};
+    use std::path::Path;
+
+    #[test]
+    fn test_pack_overrides_built_in_assets() {
+        let root = std::env::temp_dir().join("crafty_test_resource_pack");
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("block")).unwrap();
+        std::fs::write(root.join("block/grass_top.png"), b"reskinned").unwrap();
+
+        let pack = ResourcePack::new(&root);
+        assert_eq!(pack.path("block/grass_top.png"), root.join("block/grass_top.png"));
+        assert_eq!(pack.read("block/grass_top.png").unwrap(), b"reskinned");
+
+        // The other assets come from the game
+        assert_eq!(pack.path("fonts.png"), Path::new(BUILT_IN_ASSETS).join("fonts.png"));
+        assert!(pack.read("fonts.png").is_ok());
+
+        // Without the folder, everything is built-in
+        let missing = ResourcePack::new(root.join("nowhere"));
+        assert_eq!(missing.path("block/grass_top.png"), Path::new(BUILT_IN_ASSETS).join("block/grass_top.png"));
+    }
+}
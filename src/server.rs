@@ -1,4 +1,5 @@
 pub mod game_server;
 pub mod server_state;
 pub mod world_dispatcher;
-pub mod monster_manager;
\ No newline at end of file
+pub mod monster_manager;
+pub mod autosave;
\ No newline at end of file
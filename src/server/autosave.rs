@@ -0,0 +1,84 @@
+use std::time::{Duration, Instant};
+use crate::persistence::SaveDir;
+
+/// Returns true if the world must be saved again, `interval` after the last save.
+/// There is no autosave without an interval.
+pub fn should_save(last_save: Instant, now: Instant, interval: Option<Duration>) -> bool {
+    match interval {
+        Some(interval) => now.saturating_duration_since(last_save) >= interval,
+        None => false,
+    }
+}
+
+/// Decides when the server saves the world, so that a crash only loses the last minutes of the game.
+/// The world is saved by the server, see `GameServer::save_to`.
+pub struct Autosave {
+    /// Directory of the saves where the world is saved
+    saves: SaveDir,
+    /// Time between two saves, no autosave if None
+    interval: Option<Duration>,
+    last_save: Instant,
+    /// Whether the world is also saved when the server stops
+    on_exit: bool,
+}
+
+impl Autosave {
+    pub fn new(saves: SaveDir) -> Self {
+        Self {
+            saves,
+            interval: None,
+            last_save: Instant::now(),
+            on_exit: false,
+        }
+    }
+
+    pub fn set_interval(&mut self, interval: Option<Duration>) {
+        self.interval = interval;
+    }
+
+    pub fn set_saves(&mut self, saves: SaveDir) {
+        self.saves = saves;
+    }
+
+    pub fn saves(&self) -> &SaveDir {
+        &self.saves
+    }
+
+    pub fn set_on_exit(&mut self, on_exit: bool) {
+        self.on_exit = on_exit;
+    }
+
+    /// Returns true if the world must be saved now, the interval is then counted again from now
+    pub fn step(&mut self, now: Instant) -> bool {
+        if !should_save(self.last_save, now, self.interval) {
+            return false;
+        }
+        self.last_save = now;
+        true
+    }
+
+    /// Whether the world is saved one last time when the server stops
+    pub fn on_exit(&self) -> bool {
+        self.on_exit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::server::autosave::should_save;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_should_save() {
+        let last_save = Instant::now();
+        let interval = Some(Duration::from_secs(60));
+
+        assert!(!should_save(last_save, last_save, interval));
+        assert!(!should_save(last_save, last_save + Duration::from_secs(59), interval));
+        assert!(should_save(last_save, last_save + Duration::from_secs(60), interval));
+        assert!(should_save(last_save, last_save + Duration::from_secs(600), interval));
+
+        // Disabled autosave
+        assert!(!should_save(last_save, last_save + Duration::from_secs(600), None));
+    }
+}
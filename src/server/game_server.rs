@@ -1,20 +1,69 @@
 use crate::actions::Action;
+use crate::block_kind::Block;
+use crate::bow::MAX_ARROW_SPEED;
+use crate::chunk::{Chunk, ChunkCoord};
+use crate::chunk_workers::{ChunkJob, ChunkResult, ChunkWorkers};
+use crate::entity::arrow::arrow_damage;
 use crate::entity::entity::EntityKind;
+use crate::entity::pig::push_pigs;
+use crate::entity::zombie;
+use crate::entity::store::{EntityId, Hit};
+use crate::entity::humanoid::scaled_humanoid_aabb;
 use crate::network::server_update::ServerUpdate;
-use crate::network::server_update::ServerUpdate::{Attack, LoggedIn, RegisterEntity, SendAction, UpdatePosition, RemoveEntity};
+use crate::player::GRAVITY;
+use crate::persistence::region::RegionStore;
+use crate::persistence::{SaveDir, SavedPlayer};
+use crate::network::server_update::ServerUpdate::{Attack, Chat, DropLoot, LoggedIn, PositionAck, RegisterPlayer, RemoveWorldEntity, Respawn, SendAction, UpdatePosition, RemoveEntity, UnloadChunk, WorldEntity};
 use crate::primitives::position::Position;
+use crate::primitives::vector::Vector3;
+use crate::server::autosave::Autosave;
 use crate::server::monster_manager::MonsterManager;
 use crate::server::server_state::ServerState;
 use crate::server::world_dispatcher::WorldDispatcher;
+use crate::sky::Sky;
+use crate::tool::Tool;
 use crate::world::World;
-use std::collections::HashMap;
+use crate::aabb::AABB;
+use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, Mutex};
 use crate::attack::EntityAttack;
 use std::time::{Duration, Instant};
+use strum::IntoEnumIterator;
+
+/// Name given to the messages written in the console of the server
+pub const SERVER_NAME: &str = "Server";
+/// Longest line [char] relayed in the chat, the rest is cut
+pub const MAX_CHAT_LENGTH: usize = 100;
+
+/// Moves of a player longer than this [cube] are rejected, the player stays where it was.
+/// Only the server moves the players that far, see `on_respawn`.
+const MAX_MOVE: f32 = 8.;
+
+/// Distance [cube] allowed beyond the reach of the game mode between the eye of a player and the center of a cube
+/// it edits, the reach being measured up to the face of the cube
+const REACH_MARGIN: f32 = 1.;
+
+/// Farthest [cube] a player hits an entity from: twice the range of an arrow shot at full speed on flat ground,
+/// for the arrows shot from above
+const MAX_HIT_DISTANCE: f32 = 2. * MAX_ARROW_SPEED * MAX_ARROW_SPEED / GRAVITY;
+
+/// Most health a player takes from an entity in one hit, with the strongest tool or with an arrow shot at full speed
+fn max_hit_damage() -> u8 {
+    Tool::iter().map(|tool| tool.damage()).chain([arrow_damage(MAX_ARROW_SPEED)]).max().unwrap_or(1)
+}
+
+/// Time between two snapshots of the positions of the players sent to the other players (20 Hz),
+/// the clients interpolate between them
+pub const SNAPSHOT_PERIOD: Duration = Duration::from_millis(50);
+
+/// Time between two ticks of the block updates (flowing water)
+const BLOCK_UPDATE_PERIOD: Duration = Duration::from_millis(250);
 
 /// Main function of the thread in charge of entities
 pub fn handle_entity_thread(server: Arc<Mutex<GameServer>>) {
     let sleep_time = Duration::from_millis(15);
+    let mut last_block_update = Instant::now();
+    let mut last_snapshot = Instant::now();
 
     let mut t = Instant::now();
     let mut dt;
@@ -23,9 +72,25 @@ pub fn handle_entity_thread(server: Arc<Mutex<GameServer>>) {
         t = Instant::now();
 
         if let Ok(mut server) = server.lock() {
-            let player_list = server.state.connected_players().cloned().collect();
-            server.monster_manager.step(dt, &player_list);
-            server.add_monster_updates();
+            server.collect_generated_chunks();
+            server.autosave(Instant::now());
+            if last_snapshot.elapsed() >= SNAPSHOT_PERIOD {
+                last_snapshot = Instant::now();
+                server.broadcast_positions();
+            }
+            if !server.paused {
+                let player_list = server.state.connected_players().cloned().collect();
+                server.monster_manager.step(dt, &player_list);
+                server.add_monster_updates();
+                server.sky.step(dt);
+                server.step_zombies(dt);
+                if last_block_update.elapsed() >= BLOCK_UPDATE_PERIOD {
+                    last_block_update = Instant::now();
+                    server.tick_world();
+                }
+                server.step_entities(dt);
+            }
+            server.broadcast_entities();
         }
 
         std::thread::sleep(sleep_time);
@@ -48,20 +113,142 @@ pub struct GameServer {
 
     /// Internal state of the server (expect the entities)
     state: ServerState,
+
+    /// In charge of saving the world regularly
+    autosave: Autosave,
+
+    /// The players who moved since the last snapshot, see `broadcast_positions`
+    moved_players: HashSet<usize>,
+
+    /// The last position sent to the players of each entity of the world they draw, see `broadcast_entities`
+    sent_entities: HashMap<EntityId, Position>,
+
+    /// Generate the chunks that were never visited, without blocking the server
+    chunk_workers: ChunkWorkers,
+    /// The chunks being generated, with the players waiting for them
+    pending_chunks: HashMap<ChunkCoord, HashSet<usize>>,
+
+    /// The saved chunks, loaded when a player comes near them instead of being generated
+    regions: Option<RegionStore>,
+
+    /// The monsters, the flowing water and the falling blocks are frozen
+    paused: bool,
+
+    /// The time of the world, the zombies come out at night
+    sky: Sky,
+}
+
+/// Reads a saved chunk, a chunk that cannot be read is generated again
+fn load_saved_chunk(regions: &RegionStore, coord: ChunkCoord) -> Option<Chunk> {
+    match regions.load_chunk(coord) {
+        Ok(chunk) => chunk,
+        Err(err) => {
+            println!("[SERVER] Could not load the chunk {coord:?}: {err}");
+            None
+        }
+    }
+}
+
+/// The actions putting the cell at `at` as it is in the world: its cube, and the state of the cube if it has one
+fn cell_actions(world: &World, at: Vector3) -> Vec<Action> {
+    let Some(cube) = world.cube_at(at) else {
+        return vec![Action::Destroy { at }];
+    };
+    let mut actions = vec![Action::Add { at, block: *cube.block(), metadata: cube.metadata() }];
+    if let Some(entity) = world.block_entity(&at) {
+        actions.push(Action::SetBlockEntity { at, entity: entity.clone() });
+    }
+    actions
 }
 
 impl GameServer {
     pub fn new(world: World) -> Self {
+        let mut world_dispatcher = WorldDispatcher::new();
+        world_dispatcher.set_render_distance(world.meta().render_distance);
+        let sky = Sky::new(world.meta().day_length);
         let ref_to_world = Arc::new(Mutex::new(world));
         Self {
             world: Arc::clone(&ref_to_world),
-            world_dispatcher: WorldDispatcher::new(),
+            world_dispatcher,
             server_updates_buffer: HashMap::new(),
             monster_manager: MonsterManager::new(ref_to_world),
             state: ServerState::new(),
+            autosave: Autosave::new(SaveDir::default()),
+            moved_players: HashSet::new(),
+            sent_entities: HashMap::new(),
+            chunk_workers: ChunkWorkers::with_available_threads(),
+            pending_chunks: HashMap::new(),
+            regions: None,
+            paused: false,
+            sky,
+        }
+    }
+
+    /// Sets where the chunks of the world were saved, they are loaded from there when the players need them
+    pub fn set_regions(&mut self, regions: RegionStore) {
+        self.regions = Some(regions);
+    }
+
+    /// Sets the time between two saves of the world, or disables the autosave with None
+    pub fn set_autosave_interval(&mut self, interval: Option<Duration>) {
+        self.autosave.set_interval(interval);
+    }
+
+    /// Sets the directory of the saves where the world is autosaved
+    pub fn set_autosave_dir(&mut self, saves: SaveDir) {
+        self.autosave.set_saves(saves);
+    }
+
+    /// Sets the number of chunks sent in each direction around the players
+    pub fn set_render_distance(&mut self, render_distance: i32) {
+        self.world_dispatcher.set_render_distance(render_distance);
+    }
+
+    /// Whether the world is also saved when the server stops
+    pub fn set_save_on_exit(&mut self, on_exit: bool) {
+        self.autosave.set_on_exit(on_exit);
+    }
+
+    /// Freezes or resumes the world, the chunks are still sent to the players
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Saves the world if the autosave interval is elapsed
+    pub fn autosave(&mut self, now: Instant) {
+        if self.autosave.step(now) {
+            self.save_or_report();
         }
     }
 
+    /// To be called when the server stops cleanly
+    pub fn on_exit(&mut self) {
+        if self.autosave.on_exit() {
+            self.save_or_report();
+        }
+    }
+
+    /// Saves the world in the directory of the autosave, a failure is only reported
+    fn save_or_report(&self) {
+        if let Err(err) = self.save_to(self.autosave.saves()) {
+            println!("[SERVER] Autosave failed: {err}");
+        }
+    }
+
+    /// Saves the world and the position of the players in their directory of the saves
+    pub fn save_to(&self, saves: &SaveDir) -> std::io::Result<()> {
+        saves.save(&self.world.lock().unwrap(), &self.state.saved_players())
+    }
+
+    /// Puts the players of a save back where they were, for when they log in
+    pub fn restore_players(&mut self, players: Vec<SavedPlayer>) {
+        self.state.restore_players(players);
+    }
+
     /// Logins a new player into the server
     /// Returns the ID of the registered player
     pub fn login(&mut self, name: String) -> usize {
@@ -74,15 +261,23 @@ impl GameServer {
         let mut initial_updates = vec![LoggedIn(player.id as u8, player.pos.clone())];
 
         // Initialize it directly with a LoggedIn message and the position of the other players
-        for (i, connected) in self.state.connected_players().enumerate() {
+        for connected in self.state.connected_players() {
             if connected.id != player.id {
-                initial_updates.push(RegisterEntity(i as u8, EntityKind::Player, player.pos.clone()))
+                let other_name = self.state.name_of(connected.id).unwrap_or_default().to_string();
+                initial_updates.push(RegisterPlayer(connected.id as u8, other_name, connected.pos.clone()))
             }
         }
 
         let monster_entry = self.monster_manager.get_monsters();
         initial_updates.append(&mut monster_entry.clone());
 
+        // The entities of the world, as the other players last received them
+        for (id, kind, _, _) in self.world.lock().unwrap().entities().replicas() {
+            if let Some(position) = self.sent_entities.get(&id) {
+                initial_updates.push(WorldEntity(id, kind, position.clone()));
+            }
+        }
+
         self.server_updates_buffer.insert(player.id, initial_updates);
 
         // Register the player in the dispatcher
@@ -92,7 +287,7 @@ impl GameServer {
         for other_player in self.state.connected_players() {
             if player.id != other_player.id {
                 self.server_updates_buffer.get_mut(&other_player.id).unwrap()
-                    .push(RegisterEntity(player.id as u8, EntityKind::Player, player.pos.clone()));
+                    .push(RegisterPlayer(player.id as u8, name.clone(), player.pos.clone()));
                 
             }
         }
@@ -116,34 +311,139 @@ impl GameServer {
 
     // Implementation of the 'callbacks': entry points of the server
 
-    /// Called when receiving the position of a new player
-    pub fn on_new_position_update(&mut self, player_id: usize, position: Position) {
+    /// The position kept for the player moving to `position`: its move from the last position known is stopped by
+    /// the blocks of the world of the server, which the client may not have received yet.
+    /// A move longer than `MAX_MOVE` is rejected, the player only turns.
+    fn resolve_move(&self, player_id: usize, position: Position) -> Position {
+        let Some(previous) = self.state.player_pos(player_id) else {
+            return position;
+        };
+        let world = self.world.lock().unwrap();
+        let scale = world.scale();
+        let delta = position.pos() - previous.pos();
+        if delta.norm() > MAX_MOVE * scale {
+            return Position::new(previous.pos(), position.yaw(), position.pitch());
+        }
+        let allowed = world.resolve_aabb_movement(&scaled_humanoid_aabb(previous, scale), &delta);
+        Position::new(previous.pos() + allowed, position.yaw(), position.pitch())
+    }
+
+    /// Called when receiving the position of a new player, predicted by its client.
+    /// The position kept is sent back with the sequence number of the move, for the client to correct itself.
+    pub fn on_new_position_update(&mut self, player_id: usize, sequence: u32, position: Position) {
+        let position = self.resolve_move(player_id, position);
+        if let Some(updates) = self.server_updates_buffer.get_mut(&player_id) {
+            updates.push(PositionAck(sequence, position.clone()));
+        }
+        self.move_player(player_id, position);
+    }
+
+    /// Called when a player died: it is put back at the spawn point of the world, and told where it is
+    pub fn on_respawn(&mut self, player_id: usize) {
+        let spawn = self.world.lock().unwrap().find_safe_spawn().unwrap_or_else(Position::spawn_position);
+        println!("[SERVER] Player {player_id} respawns at {:?}", spawn.pos());
+        let Some(updates) = self.server_updates_buffer.get_mut(&player_id) else {
+            return;
+        };
+        updates.push(Respawn(spawn.clone()));
+        self.move_player(player_id, spawn);
+    }
+
+    /// Puts the player at this position, sends it the chunks around it and tells the other players at the next snapshot
+    fn move_player(&mut self, player_id: usize, position: Position) {
         // Update the world dispatcher. to compute if the player needs to be sent new chunks
-        if let Some((chunks_to_send, _chunks_to_delete)) = self.world_dispatcher.update_position(player_id, (position.x(), position.z())) {
+        if let Some((chunks_to_send, chunks_to_delete)) = self.world_dispatcher.update_position(player_id, (position.x(), position.z())) {
+            let mut world = self.world.lock().unwrap();
+            let meta = world.meta().clone();
+            let updates = self.server_updates_buffer.get_mut(&player_id).unwrap();
+            self.chunk_workers.set_focus(position.x(), position.z());
             for corner in chunks_to_send {
-                // Find the correct chunk
-                if let Some(to_send) = self.world.lock().unwrap().get_chunk(corner) {
-                    self.server_updates_buffer.get_mut(&player_id).unwrap().push(ServerUpdate::LoadChunk(to_send))
+                if world.get_chunk(corner).is_none() {
+                    if let Some(saved) = self.regions.as_ref().and_then(|regions| load_saved_chunk(regions, corner)) {
+                        world.add_chunk(saved);
+                    }
+                }
+                if let Some(to_send) = world.get_chunk(corner) {
+                    updates.push(ServerUpdate::LoadChunk(to_send))
                 } else {
-                    // TODO generate chunk !!!
+                    // The chunks that were never visited are generated in the background, see `collect_generated_chunks`
+                    let workers = &self.chunk_workers;
+                    self.pending_chunks
+                        .entry(corner)
+                        .or_insert_with(|| {
+                            workers.submit(ChunkJob::Generate { meta: meta.clone(), coord: corner });
+                            HashSet::new()
+                        })
+                        .insert(player_id);
                 }
             }
-        }
-
-        // Update other players
-        for player in self.state.connected_players() {
-            if player.id != player_id {
-                self.server_updates_buffer.get_mut(&player.id).unwrap().push(UpdatePosition(player_id as u8, position.clone()))
+            for corner in chunks_to_delete {
+                if let Some(waiting) = self.pending_chunks.get_mut(&corner) {
+                    waiting.remove(&player_id);
+                }
+                updates.push(UnloadChunk(corner));
             }
         }
 
+        // The other players are updated at the next snapshot
+        self.moved_players.insert(player_id);
+
         // Update internal state
         self.state.set_player_pos(player_id, position.clone());
     }
 
+    /// Sends the position of the players who moved since the last call to the other players.
+    /// Called every `SNAPSHOT_PERIOD`, whatever the rate at which the clients send their position.
+    pub fn broadcast_positions(&mut self) {
+        for moved in self.moved_players.drain() {
+            let Some(position) = self.state.player_pos(moved).cloned() else {
+                continue;
+            };
+            for player in self.state.connected_players() {
+                if player.id != moved {
+                    self.server_updates_buffer.get_mut(&player.id).unwrap().push(UpdatePosition(moved as u8, position.clone()))
+                }
+            }
+        }
+    }
+
+    /// Adds the chunks generated in the background to the world, and sends them to the players waiting for them
+    pub fn collect_generated_chunks(&mut self) {
+        let results = self.chunk_workers.try_recv();
+        if results.is_empty() {
+            return;
+        }
+        let mut world = self.world.lock().unwrap();
+        for result in results {
+            let ChunkResult::Generated(chunk) = result else {
+                continue;
+            };
+            let coord = chunk.coord();
+            if world.get_chunk(coord).is_none() {
+                world.add_chunk(chunk);
+            }
+            for player_id in self.pending_chunks.remove(&coord).unwrap_or_default() {
+                if let (Some(updates), Some(chunk)) = (self.server_updates_buffer.get_mut(&player_id), world.get_chunk(coord)) {
+                    updates.push(ServerUpdate::LoadChunk(chunk));
+                }
+            }
+        }
+    }
+
+    /// Called when a player edits the world. The action is checked against the world of the server, like the
+    /// actions of the player on its side (see `check_action`): the rejected ones are neither applied nor forwarded,
+    /// the player gets the cell back as the server has it.
     pub fn on_new_action(&mut self, player_id: usize, action: Action) {
         // Edit the world of the server
-        self.world.lock().unwrap().apply_action(&action);
+        if !self.check_action(player_id, &action) {
+            println!("[SERVER] Rejected the action of the player {player_id}: {action:?}");
+            let (Action::Destroy { at } | Action::Add { at, .. } | Action::SetBlockEntity { at, .. }) = action;
+            let restored = cell_actions(&self.world.lock().unwrap(), at);
+            if let Some(updates) = self.server_updates_buffer.get_mut(&player_id) {
+                updates.extend(restored.into_iter().map(SendAction));
+            }
+            return;
+        }
 
         // Forward the action to all the other connected players
         for player in self.state.connected_players() {
@@ -153,6 +453,186 @@ impl GameServer {
         }
     }
 
+    /// Applies the action of a player if it could have done it in the world of the server, returns false otherwise.
+    /// * The cell must be within the reach of the player, for the game mode of the world
+    /// * A block is broken like by the player (see `World::break_block`): the bedrock stays, and the items left are
+    ///   only collected by the player on its side
+    /// * A block is placed like by the player (see `World::check_placement`): in a free cell, below the build limit,
+    ///   outside of the player. The farmland only replaces the dirt or the grass, see `World::till_at`
+    /// * The state of a cube (the items of a chest) is only replaced if the cube has one
+    fn check_action(&mut self, player_id: usize, action: &Action) -> bool {
+        let Some(eye) = self.state.player_pos(player_id).cloned() else {
+            return false;
+        };
+        let mut world = self.world.lock().unwrap();
+        let scale = world.scale();
+        let game_mode = world.meta().game_mode;
+        let (Action::Destroy { at } | Action::Add { at, .. } | Action::SetBlockEntity { at, .. }) = action;
+        let center = *at + Vector3::new(0.5, 0.5, 0.5);
+        if (center - eye.pos() / scale).norm() > game_mode.reach() + REACH_MARGIN {
+            return false;
+        }
+        match action {
+            Action::Destroy { at } => {
+                let broken = world.break_block(*at, game_mode, None).is_some();
+                world.take_events();
+                broken
+            }
+            Action::Add { at, block: Block::FARMLAND, .. } if world.till_at(*at).is_some() => {
+                world.apply_action(action);
+                true
+            }
+            Action::Add { at, block, metadata } => {
+                let placed = block.can_be_placed()
+                    && world.check_placement(*at, *block, *metadata, &scaled_humanoid_aabb(&eye, scale)).is_ok();
+                if placed {
+                    world.apply_action(action);
+                }
+                placed
+            }
+            Action::SetBlockEntity { at, .. } => {
+                let exists = world.block_entity(at).is_some();
+                if exists {
+                    world.apply_action(action);
+                }
+                exists
+            }
+        }
+    }
+
+    /// Processes the block updates (flowing water, falling sand) and the random ticks (spreading grass) of the world, and forwards the changes to all the players.
+    /// The animals spawn there too, see `World::spawn_animals`.
+    pub fn tick_world(&mut self) {
+        let mut world = self.world.lock().unwrap();
+        let mut actions = world.tick();
+        actions.extend(world.random_tick());
+        world.spawn_animals(BLOCK_UPDATE_PERIOD.as_secs_f32());
+        drop(world);
+        self.broadcast_actions(actions);
+    }
+
+    /// Moves the entities of the world, the pigs being pushed by the players, and forwards the blocks that landed to all the players
+    pub fn step_entities(&mut self, dt: f32) {
+        let mut world = self.world.lock().unwrap();
+        let scale = world.scale();
+        for player in self.state.connected_players() {
+            push_pigs(world.entities_mut(), &scaled_humanoid_aabb(&player.pos, scale).scaled(1. / scale));
+        }
+        let actions = world.step_entities(dt);
+        drop(world);
+        self.broadcast_actions(actions);
+    }
+
+    /// Spawns the zombies around the players at night (see `World::spawn_monsters`), sends them after the closest player,
+    /// and lets the ones touching a player attack it
+    pub fn step_zombies(&mut self, dt: f32) {
+        let mut world = self.world.lock().unwrap();
+        let scale = world.scale();
+        let players: Vec<(usize, AABB)> = self.state.connected_players()
+            .map(|player| (player.id, scaled_humanoid_aabb(&player.pos, scale).scaled(1. / scale)))
+            .collect();
+        let feet: Vec<Vector3> = players.iter()
+            .map(|(_, aabb)| Vector3::new((aabb.west() + aabb.east()) / 2., aabb.bottom(), (aabb.south() + aabb.north()) / 2.))
+            .collect();
+        for player in &feet {
+            world.spawn_monsters(dt, player, self.sky.daylight());
+        }
+        zombie::chase(world.entities_mut(), &feet);
+
+        let mut attacks = Vec::new();
+        for (id, aabb) in &players {
+            let damage = zombie::attack(world.entities_mut(), aabb);
+            if damage > 0 {
+                let mut attack = EntityAttack::new(*id as u8);
+                attack.set_strength(damage);
+                attacks.push(attack);
+            }
+        }
+        drop(world);
+        for attack in attacks {
+            self.on_new_attack(attack);
+        }
+    }
+
+    /// Called when a player hits an entity of the world (a pig...), see `EntityStore::hurt`.
+    /// The loot of the entity killed is sent to this player, the other players only see it disappear.
+    /// The entities out of the range of the player are not hit, and the damage is capped to what a player can deal.
+    pub fn on_hit_entity(&mut self, player_id: usize, id: EntityId, damage: u8, direction: Vector3) {
+        let Some(eye) = self.state.player_pos(player_id).cloned() else {
+            return;
+        };
+        let mut world = self.world.lock().unwrap();
+        let scale = world.scale();
+        let Some(center) = world.entities().body(id).map(|body| body.center()) else {
+            return;
+        };
+        if (center - eye.pos() / scale).norm() > MAX_HIT_DISTANCE {
+            return;
+        }
+        let hit = world.entities_mut().hurt(id, damage.min(max_hit_damage()), &direction);
+        drop(world);
+        if let (Hit::Killed(at, loot), Some(updates)) = (hit, self.server_updates_buffer.get_mut(&player_id)) {
+            updates.push(DropLoot(at, loot));
+        }
+    }
+
+    /// Sends the entities of the world that moved since the last call to all the players, and the ones that
+    /// are gone, so that the players draw them where they are (the falling blocks...)
+    pub fn broadcast_entities(&mut self) {
+        let mut updates = Vec::new();
+        let mut present = HashSet::new();
+        for (id, kind, bottom, yaw) in self.world.lock().unwrap().entities().replicas() {
+            present.insert(id);
+            let position = Position::new(bottom, yaw, 0.);
+            if self.sent_entities.get(&id) != Some(&position) {
+                self.sent_entities.insert(id, position.clone());
+                updates.push(WorldEntity(id, kind, position));
+            }
+        }
+        self.sent_entities.retain(|id, _| {
+            let kept = present.contains(id);
+            if !kept {
+                updates.push(RemoveWorldEntity(*id));
+            }
+            kept
+        });
+        for player in self.state.connected_players() {
+            self.server_updates_buffer.get_mut(&player.id).unwrap().extend(updates.iter().cloned());
+        }
+    }
+
+    fn broadcast_actions(&mut self, actions: Vec<Action>) {
+        for action in actions {
+            for player in self.state.connected_players() {
+                self.server_updates_buffer.get_mut(&player.id).unwrap().push(SendAction(action.clone()))
+            }
+        }
+    }
+
+    /// Called when a player writes in the chat: the line is sent to all the players, the sender included
+    pub fn on_chat(&mut self, player_id: usize, text: String) {
+        let Some(name) = self.state.name_of(player_id).map(String::from) else {
+            return;
+        };
+        self.broadcast_chat(name, text);
+    }
+
+    /// Sends a line to all the players in the name of the server
+    pub fn announce(&mut self, text: String) {
+        self.broadcast_chat(String::from(SERVER_NAME), text);
+    }
+
+    fn broadcast_chat(&mut self, sender: String, text: String) {
+        let text: String = text.trim().chars().take(MAX_CHAT_LENGTH).collect();
+        if text.is_empty() {
+            return;
+        }
+        println!("[CHAT] <{sender}> {text}");
+        for player in self.state.connected_players() {
+            self.server_updates_buffer.get_mut(&player.id).unwrap().push(Chat(sender.clone(), text.clone()))
+        }
+    }
+
     pub fn on_new_attack(&mut self, attack: EntityAttack) {
         println!("Attacked received: {attack:?}");
         let victim = attack.victim_id() as usize;
@@ -204,10 +684,24 @@ impl GameServer {
 
 #[cfg(test)]
 mod tests {
+    use crate::actions::Action;
     use crate::attack::EntityAttack;
+    use crate::block_kind::Block;
+    use crate::chunk::{Chunk, CHUNK_SIZE};
+    use crate::entity::replica::ReplicaKind;
+    use crate::entity::store::HIT_COOLDOWN;
+    use crate::entity::zombie::Zombie;
+    use crate::food::Food;
+    use crate::item::Item;
+    use crate::player::{DIAMETER, FOREHEAD, PLAYER_HEIGHT};
     use crate::network::server_update::ServerUpdate;
-    use crate::server::game_server::GameServer;
+    use crate::persistence::SaveDir;
+    use crate::primitives::position::Position;
+    use crate::primitives::vector::Vector3;
+    use crate::server::game_server::{max_hit_damage, GameServer, MAX_CHAT_LENGTH, SERVER_NAME};
+    use std::time::{Duration, Instant};
     use crate::world::World;
+    use crate::world_meta::WorldMeta;
 
     #[test]
     fn test_two_clients_connecting() {
@@ -231,13 +725,64 @@ mod tests {
         // We expect 1 new update for the first player: the register message
         let updates = server.consume_updates(id1);
         assert_eq!(1, updates.len());
-        assert!(matches!(updates[0], ServerUpdate::RegisterEntity(_, _, _)));
+        assert!(matches!(&updates[0], ServerUpdate::RegisterPlayer(id, name, _) if *id as usize == id2 && name == "johan"));
 
         // The second player must have 2 messages: LoggedIn and Register
         let updates = server.consume_updates(id2);
         assert_eq!(2, updates.len());
         assert!(matches!(updates[0], ServerUpdate::LoggedIn(_, _)));
-        assert!(matches!(updates[1], ServerUpdate::RegisterEntity(_, _, _)));
+        assert!(matches!(&updates[1], ServerUpdate::RegisterPlayer(id, name, _) if *id as usize == id1 && name == "arthur"));
+    }
+
+    #[test]
+    fn test_autosave_goes_where_the_server_loads_from() {
+        let dir = std::env::temp_dir().join("crafty_test_autosave");
+        let _ = std::fs::remove_dir_all(&dir);
+        let mut chunk = Chunk::new([0., 0.]);
+        chunk.fill_layer(0, Block::STONE);
+        let mut server = GameServer::new(World::new(vec![chunk]));
+        server.set_autosave_dir(SaveDir::new(&dir));
+        server.set_autosave_interval(Some(Duration::from_secs(60)));
+        server.login("arthur".to_string());
+
+        server.autosave(Instant::now());
+        assert!(SaveDir::new(&dir).list().is_empty());
+        server.autosave(Instant::now() + Duration::from_secs(61));
+
+        // The save is found by a server started with `--init disk`
+        let saves = SaveDir::new(&dir);
+        let name = WorldMeta::default().name;
+        let (_, players) = saves.load(&name).unwrap();
+        assert_eq!(players[0].0, "arthur");
+        let chunk = saves.regions(&name).load_chunk((0, 0)).unwrap().unwrap();
+        assert_eq!(World::new(vec![chunk]).block_at(&Vector3::new(1., 0., 1.)), Some(Block::STONE));
+    }
+
+    #[test]
+    fn test_positions_sent_at_each_snapshot() {
+        let mut server = GameServer::new(World::empty());
+        let id1 = server.login("arthur".to_string());
+        let id2 = server.login("johan".to_string());
+        server.consume_updates(id1);
+        server.consume_updates(id2);
+
+        // Several moves between two snapshots, only the last one is sent
+        server.state.set_player_pos(id1, Position::from_pos(Vector3::new(0., 100., 0.)));
+        for x in 0..5 {
+            server.on_new_position_update(id1, x, Position::from_pos(Vector3::new(x as f32, 100., 0.)));
+        }
+        let is_update = |update: &ServerUpdate| matches!(update, ServerUpdate::UpdatePosition(_, _));
+        assert!(!server.consume_updates(id2).iter().any(is_update));
+        server.broadcast_positions();
+        let updates: Vec<ServerUpdate> = server.consume_updates(id2).into_iter().filter(is_update).collect();
+        assert_eq!(updates.len(), 1);
+        assert!(matches!(&updates[0], ServerUpdate::UpdatePosition(id, position) if *id as usize == id1 && position.x() == 4.));
+        // The player does not receive its own position
+        assert!(!server.consume_updates(id1).iter().any(is_update));
+
+        // Nobody moved
+        server.broadcast_positions();
+        assert!(!server.consume_updates(id2).iter().any(is_update));
     }
 
     #[test]
@@ -263,4 +808,292 @@ mod tests {
         assert_eq!(0, server.consume_updates(id2).len());
         assert_eq!(1, server.consume_updates(id3).len());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_chat_relayed_to_every_player() {
+        let mut server = GameServer::new(World::empty());
+        let id1 = server.login("arthur".to_string());
+        let id2 = server.login("johan".to_string());
+        server.consume_updates(id1);
+        server.consume_updates(id2);
+
+        server.on_chat(id1, "  hello johan ".to_string());
+        server.on_chat(id2, "   ".to_string());
+        server.announce("x".repeat(2 * MAX_CHAT_LENGTH));
+        for id in [id1, id2] {
+            let updates = server.consume_updates(id);
+            assert_eq!(2, updates.len());
+            assert!(matches!(&updates[0], ServerUpdate::Chat(name, text) if name == "arthur" && text == "hello johan"));
+            assert!(matches!(&updates[1], ServerUpdate::Chat(name, text) if name == SERVER_NAME && text.len() == MAX_CHAT_LENGTH));
+        }
+    }
+
+    #[test]
+    fn test_moves_stopped_by_the_blocks_of_the_server() {
+        let mut chunk = Chunk::new([0., 0.]);
+        chunk.fill_layer(0, Block::STONE);
+        for z in 0..CHUNK_SIZE {
+            for y in 1..4 {
+                chunk.add_cube(Vector3::newi(6, y, z as i32), Block::STONE, 0);
+            }
+        }
+        let mut server = GameServer::new(World::new(vec![chunk]));
+        let id = server.login("arthur".to_string());
+        let eye = |x: f32| Position::new(Vector3::new(x, 1. + PLAYER_HEIGHT - FOREHEAD, 4.), 0.3, 0.);
+
+        // Put there, then walking to the wall
+        server.state.set_player_pos(id, eye(2.));
+        server.on_new_position_update(id, 0, eye(2.));
+        server.on_new_position_update(id, 1, eye(4.));
+        // Through the wall the client did not know about
+        server.on_new_position_update(id, 2, eye(7.));
+        let acks: Vec<(u32, Position)> = server.consume_updates(id).into_iter().filter_map(|update| match update {
+            ServerUpdate::PositionAck(sequence, position) => Some((sequence, position)),
+            _ => None,
+        }).collect();
+        assert_eq!(acks.len(), 3);
+        assert_eq!(acks[1], (1, eye(4.)));
+        let (sequence, stopped) = &acks[2];
+        assert_eq!(*sequence, 2);
+        assert!((stopped.x() - (6. - DIAMETER / 2.)).abs() < 1e-3, "{stopped:?}");
+        assert_eq!(stopped.yaw(), 0.3);
+    }
+
+    #[test]
+    fn test_long_moves_rejected_until_respawn() {
+        let mut chunk = Chunk::new([0., 0.]);
+        chunk.fill_layer(0, Block::STONE);
+        let mut server = GameServer::new(World::new(vec![chunk]));
+        let id = server.login("arthur".to_string());
+        let start = Position::new(Vector3::new(4., 30., 4.), 0., 0.);
+        server.state.set_player_pos(id, start.clone());
+        server.consume_updates(id);
+
+        // Teleported by the client: the player only turns
+        server.on_new_position_update(id, 0, Position::new(Vector3::new(4., 30., 100.), 0.5, 0.));
+        let acks: Vec<Position> = server.consume_updates(id).into_iter().filter_map(|update| match update {
+            ServerUpdate::PositionAck(_, position) => Some(position),
+            _ => None,
+        }).collect();
+        assert_eq!(acks, vec![Position::new(start.pos(), 0.5, 0.)]);
+
+        // Put back at the spawn point by the server, on top of the ground
+        server.on_respawn(id);
+        let spawns: Vec<Position> = server.consume_updates(id).into_iter().filter_map(|update| match update {
+            ServerUpdate::Respawn(position) => Some(position),
+            _ => None,
+        }).collect();
+        assert_eq!(spawns.len(), 1);
+        assert_eq!(server.state.player_pos(id), Some(&spawns[0]));
+        assert!(spawns[0].y() > 1. && spawns[0].y() < 10., "{spawns:?}");
+    }
+
+    #[test]
+    fn test_chunks_streamed_around_the_player() {
+        let mut world = World::empty();
+        world.meta_mut().render_distance = 1;
+        let mut server = GameServer::new(world);
+        let id = server.login("arthur".to_string());
+        server.consume_updates(id);
+
+        // The chunks around the player are generated in the background, then sent
+        server.move_player(id, Position::from_pos(Vector3::new(1., 20., 1.)));
+        let updates = wait_for_chunks(&mut server, id, 4);
+        assert_eq!(count_loaded(&updates), 4);
+
+        // Far away, the previous chunks are unloaded
+        let far = 10. * CHUNK_SIZE as f32;
+        server.move_player(id, Position::from_pos(Vector3::new(far, 20., 1.)));
+        let updates = wait_for_chunks(&mut server, id, 4);
+        assert_eq!(count_loaded(&updates), 4);
+        assert_eq!(updates.iter().filter(|u| matches!(u, ServerUpdate::UnloadChunk(_))).count(), 4);
+
+        // Coming back, the chunks already generated are sent at once
+        server.move_player(id, Position::from_pos(Vector3::new(1., 20., 1.)));
+        assert_eq!(count_loaded(&server.consume_updates(id)), 4);
+    }
+
+    #[test]
+    fn test_falling_blocks_sent_to_the_players() {
+        let mut chunk = Chunk::new([0., 0.]);
+        chunk.fill_layer(0, Block::STONE);
+        let mut server = GameServer::new(World::new(vec![chunk]));
+        let id1 = server.login("arthur".to_string());
+        server.consume_updates(id1);
+        server.state.set_player_pos(id1, Position::from_pos(Vector3::new(3.5, 1. + PLAYER_HEIGHT - FOREHEAD, 0.5)));
+
+        // Placed over the air, the sand falls and the players see it falling
+        let at = Vector3::new(3., 6., 3.);
+        server.on_new_action(id1, Action::Add { at, block: Block::SAND, metadata: 0 });
+        server.tick_world();
+        server.broadcast_entities();
+        let falling: Vec<u32> = server.consume_updates(id1).into_iter().filter_map(|update| match update {
+            ServerUpdate::WorldEntity(id, ReplicaKind::FallingBlock(Block::SAND), _) => Some(id),
+            _ => None,
+        }).collect();
+        assert_eq!(falling.len(), 1);
+
+        // A player logging in meanwhile sees it too
+        server.step_entities(0.05);
+        server.broadcast_entities();
+        let id2 = server.login("johan".to_string());
+        assert!(server.consume_updates(id2).iter().any(|update| matches!(update, ServerUpdate::WorldEntity(id, _, _) if *id == falling[0])));
+
+        // Until it lands
+        for _ in 0..100 {
+            server.step_entities(0.02);
+            server.broadcast_entities();
+        }
+        let updates = server.consume_updates(id1);
+        assert!(updates.iter().any(|update| matches!(update, ServerUpdate::RemoveWorldEntity(id) if *id == falling[0])));
+        let landed = Action::Add { at: Vector3::new(3., 1., 3.), block: Block::SAND, metadata: 0 };
+        assert!(updates.iter().any(|update| matches!(update, ServerUpdate::SendAction(action) if *action == landed)));
+    }
+
+    #[test]
+    fn test_actions_checked_by_the_server() {
+        let mut chunk = Chunk::new([0., 0.]);
+        chunk.fill_layer(0, Block::STONE);
+        let mut server = GameServer::new(World::new(vec![chunk]));
+        let id1 = server.login("arthur".to_string());
+        let id2 = server.login("johan".to_string());
+        server.state.set_player_pos(id1, Position::from_pos(Vector3::new(2.5, 1. + PLAYER_HEIGHT - FOREHEAD, 2.5)));
+        server.consume_updates(id1);
+        server.consume_updates(id2);
+        let sent = |updates: Vec<ServerUpdate>| -> Vec<Action> {
+            updates.into_iter().filter_map(|update| match update {
+                ServerUpdate::SendAction(action) => Some(action),
+                _ => None,
+            }).collect()
+        };
+
+        // The bedrock, a cell out of reach, a block inside the player, a block never placed: the player gets the cells back
+        let bedrock = Vector3::new(4., 0., 4.);
+        let far = Vector3::new(20., 1., 2.);
+        let feet = Vector3::new(2., 1., 2.);
+        server.on_new_action(id1, Action::Destroy { at: bedrock });
+        server.on_new_action(id1, Action::Add { at: far, block: Block::STONE, metadata: 0 });
+        server.on_new_action(id1, Action::Add { at: feet, block: Block::STONE, metadata: 0 });
+        server.on_new_action(id1, Action::Add { at: Vector3::new(4., 1., 4.), block: Block::WHEAT, metadata: 0 });
+        assert_eq!(sent(server.consume_updates(id1)), vec![
+            Action::Add { at: bedrock, block: Block::STONE, metadata: 0 },
+            Action::Destroy { at: far },
+            Action::Destroy { at: feet },
+            Action::Destroy { at: Vector3::new(4., 1., 4.) },
+        ]);
+        assert!(sent(server.consume_updates(id2)).is_empty());
+        let world = server.world.lock().unwrap();
+        assert_eq!(world.block_at(&bedrock), Some(Block::STONE));
+        assert_eq!(world.block_at(&far), None);
+        assert_eq!(world.block_at(&feet), None);
+        drop(world);
+
+        // Next to the player, the block is placed then broken, and the other players see it
+        let placed = Action::Add { at: Vector3::new(4., 1., 4.), block: Block::STONE, metadata: 0 };
+        let broken = Action::Destroy { at: Vector3::new(4., 1., 4.) };
+        server.on_new_action(id1, placed.clone());
+        assert_eq!(server.world.lock().unwrap().block_at(&Vector3::new(4., 1., 4.)), Some(Block::STONE));
+        server.on_new_action(id1, broken.clone());
+        assert_eq!(sent(server.consume_updates(id2)), vec![placed, broken]);
+        assert!(sent(server.consume_updates(id1)).is_empty());
+        // The items are collected by the player on its side
+        assert!(server.world.lock().unwrap().take_events().is_empty());
+    }
+
+    #[test]
+    fn test_pigs_live_on_the_server() {
+        let mut chunk = Chunk::new([0., 0.]);
+        chunk.fill_layer(0, Block::GRASS);
+        let mut server = GameServer::new(World::new(vec![chunk]));
+        let id1 = server.login("arthur".to_string());
+        let id2 = server.login("johan".to_string());
+
+        // The pigs spawn with the ticks of the world, and the players see them
+        let mut pigs = Vec::new();
+        for _ in 0..1000 {
+            server.tick_world();
+            server.broadcast_entities();
+            pigs = server.consume_updates(id2).into_iter().filter_map(|update| match update {
+                ServerUpdate::WorldEntity(id, ReplicaKind::Pig, _) => Some(id),
+                _ => None,
+            }).collect();
+            if !pigs.is_empty() {
+                break;
+            }
+        }
+        assert_eq!(pigs.len(), 1);
+        server.consume_updates(id1);
+
+        // The damage is capped to what a player deals, killed by the first player at the second hit, who gets the porkchops
+        let pig = pigs[0];
+        let health = server.world.lock().unwrap().entities().health(pig).unwrap();
+        // Without knockback, so that the pig does not fall off the chunk
+        server.on_hit_entity(id1, pig, u8::MAX, Vector3::empty());
+        assert_eq!(server.world.lock().unwrap().entities().health(pig), Some(health - max_hit_damage()));
+        for _ in 0..20 {
+            server.step_entities(HIT_COOLDOWN / 10.);
+        }
+        server.on_hit_entity(id1, pig, u8::MAX, Vector3::unit_x());
+        server.broadcast_entities();
+        let updates = server.consume_updates(id1);
+        assert!(updates.iter().any(|update| matches!(update, ServerUpdate::DropLoot(_, loot) if loot.iter().all(|item| *item == Item::Food(Food::Porkchop)))));
+        assert!(updates.iter().any(|update| matches!(update, ServerUpdate::RemoveWorldEntity(id) if *id == pig)));
+        let updates = server.consume_updates(id2);
+        assert!(!updates.iter().any(|update| matches!(update, ServerUpdate::DropLoot(_, _))));
+        assert!(updates.iter().any(|update| matches!(update, ServerUpdate::RemoveWorldEntity(id) if *id == pig)));
+    }
+
+    #[test]
+    fn test_zombies_come_out_at_night_on_the_server() {
+        let mut chunks = Vec::new();
+        for x in -1..=1 {
+            for z in -1..=1 {
+                let mut chunk = Chunk::new([(x * CHUNK_SIZE as i32) as f32, (z * CHUNK_SIZE as i32) as f32]);
+                chunk.fill_layer(0, Block::GRASS);
+                chunks.push(chunk);
+            }
+        }
+        let mut server = GameServer::new(World::new(chunks));
+        let id = server.login("arthur".to_string());
+        server.move_player(id, Position::new(Vector3::new(0.5, 1. + PLAYER_HEIGHT - FOREHEAD, 0.5), 0., 0.));
+        server.consume_updates(id);
+        let is_zombie = |update: &ServerUpdate| matches!(update, ServerUpdate::WorldEntity(_, ReplicaKind::Zombie, _));
+
+        // Never during the day
+        for _ in 0..100 {
+            server.step_zombies(1.);
+        }
+        server.broadcast_entities();
+        assert!(!server.consume_updates(id).iter().any(is_zombie));
+
+        // At midnight, the player sees them coming
+        server.sky.set_time_of_day(0.75);
+        for _ in 0..100 {
+            server.step_zombies(1.);
+        }
+        server.broadcast_entities();
+        assert!(server.consume_updates(id).iter().any(is_zombie));
+
+        // The one touching the player attacks it
+        Zombie::spawn(server.world.lock().unwrap().entities_mut(), Vector3::new(0.5, 1., 0.5));
+        server.step_zombies(0.01);
+        let updates = server.consume_updates(id);
+        assert!(updates.iter().any(|update| matches!(update, ServerUpdate::Attack(attack) if attack.victim_id() as usize == id)));
+    }
+
+    fn count_loaded(updates: &[ServerUpdate]) -> usize {
+        updates.iter().filter(|u| matches!(u, ServerUpdate::LoadChunk(_))).count()
+    }
+
+    /// Collects the generated chunks until `n_chunks` of them were sent to the player
+    fn wait_for_chunks(server: &mut GameServer, id: usize, n_chunks: usize) -> Vec<ServerUpdate> {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let mut updates = Vec::new();
+        while count_loaded(&updates) < n_chunks && Instant::now() < deadline {
+            server.collect_generated_chunks();
+            updates.extend(server.consume_updates(id));
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        updates
+    }
+}
@@ -1,4 +1,5 @@
 use std::collections::{HashMap, HashSet};
+use crate::persistence::SavedPlayer;
 use crate::primitives::position::Position;
 
 #[derive(Clone)]
@@ -41,6 +42,16 @@ impl ServerState {
             .map(|(k, v)| v)
     }
 
+    /// The last position known of the player with this id
+    pub fn player_pos(&self, id: usize) -> Option<&Position> {
+        self.players.values().find(|v| v.id == id).map(|v| &v.pos)
+    }
+
+    /// The name under which the player with this id logged in
+    pub fn name_of(&self, id: usize) -> Option<&str> {
+        self.players.iter().find(|(_, v)| v.id == id).map(|(k, _)| k.as_str())
+    }
+
     pub fn n_players_connected(&self) -> usize {
         self.connected.len()
     }
@@ -52,10 +63,28 @@ impl ServerState {
             player_state.pos = pos;
         }
     }
+
+    /// The name and the position of every player who ever logged in, to be saved with the world
+    pub fn saved_players(&self) -> Vec<SavedPlayer> {
+        self.players.iter().map(|(name, player)| (name.clone(), player.pos.clone())).collect()
+    }
+
+    /// Gives back their saved position to the players, when they log in again.
+    /// The players already known keep their current position.
+    pub fn restore_players(&mut self, players: Vec<SavedPlayer>) {
+        for (name, pos) in players {
+            if !self.players.contains_key(&name) {
+                let id = self.players.len();
+                self.players.insert(name, PlayerState { id, pos });
+            }
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::primitives::position::Position;
+    use crate::primitives::vector::Vector3;
     use crate::server::server_state::{PlayerState, ServerState};
 
     #[test]
@@ -74,6 +103,24 @@ mod tests {
         assert_eq!(1, state.n_players_connected());
         let connected: Vec<&PlayerState> = state.connected_players().collect();
         assert_eq!(1, connected.len());
-        assert_eq!(1, connected[0].id)
+        assert_eq!(1, connected[0].id);
+        assert_eq!(Some("johan"), state.name_of(1));
+        assert_eq!(None, state.name_of(2));
+    }
+
+    #[test]
+    fn test_restore_saved_players() {
+        let mut state = ServerState::new();
+        let pos = Position::new(Vector3::new(4., 12., -8.), 1., 0.);
+        state.restore_players(vec![(String::from("arthur"), pos.clone())]);
+        assert_eq!(0, state.n_players_connected());
+        assert_eq!(state.saved_players(), vec![(String::from("arthur"), pos.clone())]);
+
+        // Logging in again puts the player back where it was, just above
+        let player = state.login("arthur".to_string());
+        assert_eq!(0, player.id);
+        assert_eq!(player.pos.x(), pos.x());
+        assert!(player.pos.y() > pos.y());
+        assert_eq!(1, state.login("johan".to_string()).id);
     }
 }
\ No newline at end of file
@@ -1,7 +1,13 @@
 use crate::chunk::CHUNK_SIZE;
+use crate::primitives::vector::Vector3;
+use crate::world::World;
 use std::collections::HashSet;
 
-const VISIBLE_CHUNKS: i32 = 4;
+/// Default number of chunks loaded in each direction around a player
+pub const VISIBLE_CHUNKS: i32 = 4;
+
+/// The chunk of a player whose chunks are not known yet, it is never the corner of a chunk
+const UNKNOWN_CHUNK: (i32, i32) = (-10, -10);
 
 /// A struct in charge of keeping track of the chunks loaded by each players.
 pub struct WorldDispatcher {
@@ -16,7 +22,10 @@ pub struct WorldDispatcher {
 
     /// The current position of this player
     /// We only care about the position on the 'xy' plane.
-    player_current_chunk: Vec<(i32, i32)>
+    player_current_chunk: Vec<(i32, i32)>,
+
+    /// Number of chunks loaded in each direction around the players
+    render_distance: i32,
 }
 
 impl WorldDispatcher {
@@ -24,29 +33,36 @@ impl WorldDispatcher {
     pub fn new() -> Self {
         Self {
             player_chunks: Vec::new(),
-            player_current_chunk: vec![]
+            player_current_chunk: vec![],
+            render_distance: VISIBLE_CHUNKS,
         }
     }
 
+    /// Sets the number of chunks loaded in each direction, it applies from the next position of each player
+    pub fn set_render_distance(&mut self, render_distance: i32) {
+        self.render_distance = render_distance.max(1);
+        // The chunks of the players are computed again, as if they all entered a new chunk
+        self.player_current_chunk.fill(UNKNOWN_CHUNK);
+    }
+
     pub fn register_player(&mut self, _id: usize) {
         self.player_chunks.push(HashSet::new());
-        self.player_current_chunk.push((-10, -10))
+        self.player_current_chunk.push(UNKNOWN_CHUNK)
     }
 
     pub fn logout(&mut self, id: usize) {
         self.player_chunks[id] = HashSet::new();
     }
 
-    /// Updates the position and returns a list of new chunks to be loaded
+    /// Updates the position and returns the chunks to be loaded and the ones to be unloaded
     pub fn update_position(&mut self, id: usize, pos: (f32, f32)) -> Option<(HashSet<(i32, i32)>, HashSet<(i32, i32)>)> {
         // Compute the current chunk
-        let s = CHUNK_SIZE as f32;
-        let current_chunk = ((pos.0 - pos.0 % s) as i32, (pos.1 - pos.1 % s) as i32);
+        let current_chunk = World::chunk_of(&Vector3::new(pos.0, 0., pos.1));
 
         // If the player has changed chunk, then we send the new chunks that are further away
         if current_chunk != self.player_current_chunk[id] {
             // Compute the necessary chunks
-            let chunks_to_see = Self::get_visible_chunk(current_chunk);
+            let chunks_to_see = self.get_visible_chunk(current_chunk);
             
             // Compute the diff
             let chunks_to_send = &chunks_to_see - &self.player_chunks[id];
@@ -54,9 +70,7 @@ impl WorldDispatcher {
             
             // Update the information about this player...
             self.player_current_chunk[id] = current_chunk;
-            chunks_to_send.iter().for_each(|chunk| {
-                self.player_chunks[id].insert(chunk.clone());
-            });
+            self.player_chunks[id] = chunks_to_see;
             
             return Some((chunks_to_send, chunks_to_delete))
         }
@@ -64,10 +78,10 @@ impl WorldDispatcher {
     }
 
     // TODO output of this function can be memoized
-    fn get_visible_chunk(from: (i32, i32)) -> HashSet<(i32, i32)> {
+    fn get_visible_chunk(&self, from: (i32, i32)) -> HashSet<(i32, i32)> {
         let mut chunks = HashSet::new();
-        for i in -VISIBLE_CHUNKS..VISIBLE_CHUNKS {
-            for j in -VISIBLE_CHUNKS..VISIBLE_CHUNKS {
+        for i in -self.render_distance..self.render_distance {
+            for j in -self.render_distance..self.render_distance {
                 chunks.insert((from.0 + i * CHUNK_SIZE as i32,from.1 + j * CHUNK_SIZE as i32));
             }
         }
@@ -99,4 +113,40 @@ mod tests {
         assert_eq!(to_delete.len(), 2 * VISIBLE_CHUNKS as usize);
         
     }
+
+    #[test]
+    fn test_chunks_left_behind_are_unloaded() {
+        let mut dispatcher = WorldDispatcher::new();
+        dispatcher.set_render_distance(2);
+        dispatcher.register_player(0);
+        let s = CHUNK_SIZE as f32;
+
+        let (to_send, _) = dispatcher.update_position(0, (0., 0.)).unwrap();
+        assert_eq!(to_send.len(), 16);
+
+        // Far away, everything is loaded again and all the previous chunks are unloaded
+        let (to_send, to_delete) = dispatcher.update_position(0, (10. * s, 0.)).unwrap();
+        assert_eq!(to_send.len(), 16);
+        assert_eq!(to_delete.len(), 16);
+
+        // Coming back loads the chunks that were unloaded
+        let (to_send, to_delete) = dispatcher.update_position(0, (0., 0.)).unwrap();
+        assert_eq!(to_send.len(), 16);
+        assert_eq!(to_delete.len(), 16);
+    }
+
+    #[test]
+    fn test_render_distance_applies_while_playing() {
+        let mut dispatcher = WorldDispatcher::new();
+        dispatcher.set_render_distance(2);
+        dispatcher.register_player(0);
+        dispatcher.update_position(0, (1., 1.)).unwrap();
+        assert!(dispatcher.update_position(0, (2., 2.)).is_none());
+
+        // The chunks are updated without leaving the current chunk
+        dispatcher.set_render_distance(3);
+        let (to_send, to_delete) = dispatcher.update_position(0, (2., 2.)).unwrap();
+        assert_eq!(to_send.len(), 36 - 16);
+        assert!(to_delete.is_empty());
+    }
 }
@@ -0,0 +1,116 @@
+use crate::camera::FOV;
+use crate::input_map::InputMap;
+use crate::resource_pack::DEFAULT_RESOURCE_PACK;
+use crate::server::world_dispatcher::VISIBLE_CHUNKS;
+use serde::{Deserialize, Serialize};
+
+/// File of the settings of the player, next to the saved worlds
+pub const SETTINGS_FILE: &str = "settings.json";
+
+/// Rotation [rad] of the view for each pixel of mouse motion
+pub const DEFAULT_MOUSE_SENSITIVITY: f32 = 0.005;
+
+/// Bounds of the vertical field of view [rad]
+pub const MIN_FOV: f32 = 0.5;
+pub const MAX_FOV: f32 = 2.;
+
+/// The preferences of the player, the same for every world.
+/// The values missing from the file keep their default, so that an old file still loads.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Number of chunks loaded in each direction, given to the server when it runs for this player only
+    pub render_distance: i32,
+    /// Vertical field of view [rad], before the effects of the sprint and the zoom
+    pub fov: f32,
+    /// See `DEFAULT_MOUSE_SENSITIVITY`
+    pub mouse_sensitivity: f32,
+    /// Paces the frames on the refresh rate of the monitor, instead of drawing them as fast as possible
+    pub vsync: bool,
+    pub fullscreen: bool,
+    /// Which action each key and mouse button triggers
+    pub controls: InputMap,
+    /// Volume of the game, from 0 (muted) to 1. There are no sounds yet, it is kept for when there are.
+    pub volume: f32,
+    /// Folder whose assets replace the built-in ones, see `ResourcePack`. It is loaded when the game starts.
+    pub resource_pack: String,
+}
+
+impl Settings {
+    /// Loads the settings from a file, the default ones are used if the file is missing or not valid
+    pub fn load(name: &str) -> Self {
+        match std::fs::read_to_string(name) {
+            Ok(data) => match serde_json::from_str::<Settings>(&data) {
+                Ok(settings) => settings.sanitized(),
+                Err(error) => {
+                    println!("Invalid settings in {name}, using the default ones: {error}");
+                    Self::default()
+                }
+            },
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn save(&self, name: &str) {
+        if let Err(error) = std::fs::write(name, serde_json::to_string_pretty(self).unwrap()) {
+            println!("Could not save the settings to {name}: {error}");
+        }
+    }
+
+    /// The same settings, with each value brought back into its valid range
+    pub fn sanitized(mut self) -> Self {
+        self.render_distance = self.render_distance.max(1);
+        self.fov = self.fov.clamp(MIN_FOV, MAX_FOV);
+        self.mouse_sensitivity = self.mouse_sensitivity.max(0.);
+        self.volume = self.volume.clamp(0., 1.);
+        self
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            render_distance: VISIBLE_CHUNKS,
+            fov: FOV,
+            mouse_sensitivity: DEFAULT_MOUSE_SENSITIVITY,
+            vsync: true,
+            fullscreen: false,
+            controls: InputMap::default(),
+            volume: 1.,
+            resource_pack: DEFAULT_RESOURCE_PACK.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::input_map::{Binding, InputAction};
+    use crate::settings::{Settings, MAX_FOV};
+    use winit::keyboard::KeyCode;
+
+    #[test]
+    fn test_settings_round_trip() {
+        let mut settings = Settings::default();
+        settings.fov = 1.2;
+        settings.vsync = false;
+        settings.controls.bind(InputAction::Jump, Binding::Key(KeyCode::KeyJ));
+
+        let data = serde_json::to_string(&settings).unwrap();
+        assert_eq!(serde_json::from_str::<Settings>(&data).unwrap(), settings);
+    }
+
+    #[test]
+    fn test_missing_and_invalid_values() {
+        // Only some of the values, the others keep their default
+        let settings: Settings = serde_json::from_str(r#"{"fov": 10, "volume": -1, "controls": {"jump": "KeyJ"}}"#).unwrap();
+        let settings = settings.sanitized();
+        assert_eq!(settings.fov, MAX_FOV);
+        assert_eq!(settings.volume, 0.);
+        assert_eq!(settings.controls.action_for_key(KeyCode::KeyJ), Some(InputAction::Jump));
+        assert_eq!(settings.controls.action_for_key(KeyCode::KeyW), Some(InputAction::MoveForward));
+        assert_eq!(settings.render_distance, Settings::default().render_distance);
+
+        // A file that cannot be read gives the default settings
+        assert_eq!(Settings::load("no_such_settings.json"), Settings::default());
+    }
+}
@@ -0,0 +1,153 @@
+use std::f32::consts::PI;
+
+use crate::graphics::color::Color;
+use crate::primitives::vector::Vector3;
+
+/// Duration of a full day [s], as in minecraft
+pub const DEFAULT_DAY_LENGTH: f32 = 1200.;
+
+/// The day starts in the morning, when the sun already lights the ground
+const START_TIME: f32 = 0.05;
+
+const DAY_COLOR: [f32; 3] = [146. / 255., 210. / 255., 249. / 255.];
+const DUSK_COLOR: [f32; 3] = [0.95, 0.55, 0.35];
+const NIGHT_COLOR: [f32; 3] = [0.02, 0.03, 0.08];
+
+/// Light of the faces in the dark, and under the full sun
+const NIGHT_AMBIENT: f32 = 0.2;
+const DAY_AMBIENT: f32 = 0.55;
+/// Light added to the faces facing the sun at noon
+const SUN_INTENSITY: f32 = 0.45;
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0., 1.);
+    t * t * (3. - 2. * t)
+}
+
+fn mix(a: [f32; 3], b: [f32; 3], t: f32) -> [f32; 3] {
+    [a[0] + (b[0] - a[0]) * t, a[1] + (b[1] - a[1]) * t, a[2] + (b[2] - a[2]) * t]
+}
+
+/// The time of the world and the sun moving with it.
+/// The time of day goes from 0 to 1: the sun rises in the east (+x) at 0, is the highest at 0.25,
+/// sets in the west at 0.5 and is the lowest at midnight (0.75).
+#[derive(Clone, Debug)]
+pub struct Sky {
+    /// Duration of a full day [s]
+    day_length: f32,
+    time_of_day: f32,
+}
+
+impl Sky {
+    pub fn new(day_length: f32) -> Self {
+        Self { day_length, time_of_day: START_TIME }
+    }
+
+    pub fn day_length(&self) -> f32 {
+        self.day_length
+    }
+
+    pub fn set_day_length(&mut self, day_length: f32) {
+        self.day_length = day_length;
+    }
+
+    pub fn time_of_day(&self) -> f32 {
+        self.time_of_day
+    }
+
+    pub fn set_time_of_day(&mut self, time_of_day: f32) {
+        self.time_of_day = time_of_day.rem_euclid(1.);
+    }
+
+    /// Moves the time forward by `dt` seconds
+    pub fn step(&mut self, dt: f32) {
+        if self.day_length > 0. {
+            self.set_time_of_day(self.time_of_day + dt / self.day_length);
+        }
+    }
+
+    /// Direction from the ground toward the sun, normalized.
+    /// The path of the sun is slightly tilted toward +z, so that it is never exactly vertical.
+    pub fn sun_direction(&self) -> Vector3 {
+        let angle = 2. * PI * self.time_of_day;
+        let mut direction = Vector3::new(angle.cos(), angle.sin(), 0.3);
+        direction.normalize();
+        direction
+    }
+
+    /// Direction in which the light of the sun travels, as given to the shaders
+    pub fn light_direction(&self) -> [f32; 3] {
+        self.sun_direction().opposite().as_array()
+    }
+
+    /// 1 during the day, 0 during the night, fading around the sunrise and the sunset
+    pub fn daylight(&self) -> f32 {
+        smoothstep(-0.15, 0.25, self.sun_direction().y())
+    }
+
+    /// Light received by every face, whatever its orientation
+    pub fn ambient(&self) -> f32 {
+        NIGHT_AMBIENT + (DAY_AMBIENT - NIGHT_AMBIENT) * self.daylight()
+    }
+
+    /// Light added to the faces turned toward the sun, none at night
+    pub fn sun_intensity(&self) -> f32 {
+        SUN_INTENSITY * self.daylight()
+    }
+
+    /// Color of the sky: blue during the day, orange around the horizon at dusk and dawn, dark at night
+    pub fn clear_color(&self) -> Color {
+        let height = self.sun_direction().y();
+        let dusk = 1. - smoothstep(0., 0.3, height.abs());
+        let [r, g, b] = mix(mix(NIGHT_COLOR, DAY_COLOR, self.daylight()), DUSK_COLOR, 0.6 * dusk);
+        Color::Custom([r, g, b, 1.])
+    }
+}
+
+impl Default for Sky {
+    fn default() -> Self {
+        Self::new(DEFAULT_DAY_LENGTH)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sky::Sky;
+
+    #[test]
+    fn test_sun_follows_the_time() {
+        let mut sky = Sky::new(100.);
+        sky.set_time_of_day(0.);
+        assert!(sky.sun_direction().x() > 0.9);
+        assert!(sky.sun_direction().y().abs() < 1e-3);
+
+        // Noon, a quarter of a day later
+        sky.step(25.);
+        assert!((sky.time_of_day() - 0.25).abs() < 1e-4);
+        assert!(sky.sun_direction().y() > 0.9);
+        // The light comes from above
+        assert!(sky.light_direction()[1] < -0.9);
+
+        // Wraps around to the next day
+        sky.step(80.);
+        assert!((sky.time_of_day() - 0.05).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_light_fades_at_night() {
+        let mut sky = Sky::default();
+        sky.set_time_of_day(0.25);
+        let noon = (sky.ambient(), sky.sun_intensity(), sky.clear_color().rgba());
+        sky.set_time_of_day(0.75);
+        let midnight = (sky.ambient(), sky.sun_intensity(), sky.clear_color().rgba());
+        sky.set_time_of_day(0.5);
+        let dusk = sky.clear_color().rgba();
+
+        assert!(noon.0 > midnight.0);
+        assert_eq!(midnight.1, 0.);
+        assert!(noon.2[2] > midnight.2[2]);
+        // The sky turns orange at sunset
+        assert!(dusk[0] > dusk[2]);
+        assert!(dusk[0] > noon.2[0]);
+    }
+}
@@ -0,0 +1,200 @@
+use serde::{Deserialize, Serialize};
+
+use crate::block_kind::Block;
+use crate::block_kind::Block::{COBBELSTONE, OAKLEAVES, OAKLOG};
+use crate::chunk::{Chunk, CHUNK_HEIGHT};
+use crate::primitives::vector::Vector3;
+
+/// Rotation of a structure around the vertical axis, as seen from above
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StructureRotation {
+    #[default]
+    Deg0,
+    /// The +x axis of the structure goes along +z
+    Deg90,
+    Deg180,
+    Deg270,
+}
+
+impl StructureRotation {
+    /// Rotates an offset relative to the anchor of the structure
+    pub fn rotate(&self, offset: [i32; 3]) -> [i32; 3] {
+        let [x, y, z] = offset;
+        match self {
+            StructureRotation::Deg0 => [x, y, z],
+            StructureRotation::Deg90 => [-z, y, x],
+            StructureRotation::Deg180 => [-x, y, -z],
+            StructureRotation::Deg270 => [z, y, -x],
+        }
+    }
+}
+
+/// A template of blocks placed together, as a hut or a tree.
+/// The blocks are given relative to the anchor of the structure.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Structure {
+    blocks: Vec<([i32; 3], Block)>,
+}
+
+impl Structure {
+    pub fn new() -> Self {
+        Self { blocks: Vec::new() }
+    }
+
+    /// Adds a block, replacing the one already at this offset
+    pub fn add_block(&mut self, offset: [i32; 3], block: Block) {
+        self.blocks.retain(|(o, _)| *o != offset);
+        self.blocks.push((offset, block));
+    }
+
+    pub fn blocks(&self) -> &[([i32; 3], Block)] {
+        &self.blocks
+    }
+
+    /// The blocks with their offsets rotated
+    pub fn rotated_blocks(&self, rotation: StructureRotation) -> Vec<([i32; 3], Block)> {
+        self.blocks.iter().map(|(offset, block)| (rotation.rotate(*offset), *block)).collect()
+    }
+
+    /// Largest horizontal distance [cube] between the anchor and a block, whatever the rotation
+    pub fn reach(&self) -> i32 {
+        self.blocks.iter().map(|([x, _, z], _)| x.abs().max(z.abs())).max().unwrap_or(0)
+    }
+
+    /// Stamps the part of the structure that falls in the chunk, on its empty cells only.
+    /// The generation calls it on each chunk the structure overlaps, so that it can cross the chunk borders
+    /// even though the chunks are generated one by one (see `WorldGenerator::plant_structures`).
+    /// Returns the number of blocks placed.
+    pub fn place_in_chunk(&self, chunk: &mut Chunk, anchor: [i32; 3], rotation: StructureRotation) -> usize {
+        let mut placed = 0;
+        for ([x, y, z], block) in self.rotated_blocks(rotation) {
+            let at = Vector3::newi(anchor[0] + x, anchor[1] + y, anchor[2] + z);
+            if !chunk.is_in(&at) || at.y() < 0. || at.y() >= CHUNK_HEIGHT as f32 || chunk.cube_at(&at).is_some() {
+                continue;
+            }
+            chunk.add_cube(at, block, 0);
+            placed += 1;
+        }
+        placed
+    }
+
+    /// A hut of 3x3 cobblestone walls, with a door on the +x side and a flat roof
+    pub fn hut() -> Self {
+        let mut hut = Self::new();
+        for x in -1..=1 {
+            for z in -1..=1 {
+                for y in 0..3 {
+                    let is_wall = x != 0 || z != 0;
+                    let is_door = x == 1 && z == 0 && y < 2;
+                    if (is_wall && !is_door) || y == 2 {
+                        hut.add_block([x, y, z], COBBELSTONE);
+                    }
+                }
+            }
+        }
+        hut
+    }
+
+    /// A thin tree: a trunk of `height` logs, topped by leaves
+    pub fn tree(height: i32) -> Self {
+        let mut tree = Self::new();
+        for y in 0..height {
+            tree.add_block([0, y, 0], OAKLOG);
+        }
+        for x in -1..=1 {
+            for z in -1..=1 {
+                if x != 0 || z != 0 {
+                    tree.add_block([x, height - 1, z], OAKLEAVES);
+                }
+            }
+        }
+        tree.add_block([0, height, 0], OAKLEAVES);
+        tree
+    }
+
+    /// An oak: a trunk of `height` logs in a canopy of leaves, two wide layers around the top of the trunk
+    /// and two narrow ones above them. The corners of the layers above the lowest one are left out to round the canopy.
+    pub fn oak(height: i32) -> Self {
+        let mut oak = Self::new();
+        for (y, radius) in [(height - 2, 2i32), (height - 1, 2), (height, 1), (height + 1, 1)] {
+            for x in -radius..=radius {
+                for z in -radius..=radius {
+                    let is_corner = x.abs() == radius && z.abs() == radius;
+                    if !is_corner || (radius == 2 && y == height - 2) {
+                        oak.add_block([x, y, z], OAKLEAVES);
+                    }
+                }
+            }
+        }
+        // The trunk goes through the canopy
+        for y in 0..height {
+            oak.add_block([0, y, 0], OAKLOG);
+        }
+        oak
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::block_kind::Block::{DIRT, OAKLEAVES, OAKLOG};
+    use crate::chunk::{Chunk, CHUNK_SIZE};
+    use crate::primitives::vector::Vector3;
+    use crate::structure::{Structure, StructureRotation};
+
+    #[test]
+    fn test_rotations() {
+        let offset = [2, 1, 1];
+        assert_eq!(StructureRotation::Deg0.rotate(offset), [2, 1, 1]);
+        assert_eq!(StructureRotation::Deg90.rotate(offset), [-1, 1, 2]);
+        assert_eq!(StructureRotation::Deg180.rotate(offset), [-2, 1, -1]);
+        assert_eq!(StructureRotation::Deg270.rotate(offset), [1, 1, -2]);
+
+        // Four quarters make a full turn
+        let mut turned = offset;
+        for _ in 0..4 {
+            turned = StructureRotation::Deg90.rotate(turned);
+        }
+        assert_eq!(turned, offset);
+    }
+
+    #[test]
+    fn test_add_block_replaces() {
+        let mut structure = Structure::new();
+        structure.add_block([0, 0, 0], DIRT);
+        structure.add_block([0, 0, 0], OAKLOG);
+        assert_eq!(structure.blocks(), &[([0, 0, 0], OAKLOG)]);
+    }
+
+    #[test]
+    fn test_oak_canopy() {
+        let oak = Structure::oak(5);
+        assert_eq!(oak.reach(), 2);
+        let block_at = |offset: [i32; 3]| oak.blocks().iter().find(|(o, _)| *o == offset).map(|(_, b)| *b);
+        assert_eq!(block_at([0, 0, 0]), Some(OAKLOG));
+        assert_eq!(block_at([0, 4, 0]), Some(OAKLOG));
+        assert_eq!(block_at([2, 4, 1]), Some(OAKLEAVES));
+        assert_eq!(block_at([0, 6, 0]), Some(OAKLEAVES));
+        // The top corners are rounded
+        assert_eq!(block_at([2, 4, 2]), None);
+        assert_eq!(block_at([1, 6, 1]), None);
+        assert!(oak.reach() < CHUNK_SIZE as i32);
+    }
+
+    #[test]
+    fn test_place_across_chunks() {
+        let s = CHUNK_SIZE as i32;
+        let oak = Structure::oak(4);
+        let mut left = Chunk::new([0., 0.]);
+        let mut right = Chunk::new([s as f32, 0.]);
+        left.add_cube(Vector3::newi(s - 2, 4, 4), DIRT, 0);
+
+        // The oak on the border is split between the two chunks, and never replaces a block
+        let anchor = [s - 1, 1, 4];
+        let in_left = oak.place_in_chunk(&mut left, anchor, StructureRotation::Deg0);
+        let in_right = oak.place_in_chunk(&mut right, anchor, StructureRotation::Deg0);
+        assert_eq!(in_left + in_right + 1, oak.blocks().len());
+        assert_eq!(left.cube_at(&Vector3::newi(s - 1, 1, 4)).map(|c| *c.block()), Some(OAKLOG));
+        assert_eq!(left.cube_at(&Vector3::newi(s - 2, 4, 4)).map(|c| *c.block()), Some(DIRT));
+        assert_eq!(right.cube_at(&Vector3::newi(s + 1, 3, 4)).map(|c| *c.block()), Some(OAKLEAVES));
+    }
+}
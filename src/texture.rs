@@ -3,30 +3,11 @@ use glium::texture::Texture2dArray;
 use glium::Display;
 use image::{GenericImageView, ImageBuffer, Rgba};
 
-use crate::block_kind::Block;
 use crate::primitives::math;
+use crate::resource_pack::ResourcePack;
 
 pub type ImageCut = [f32; 4];
 
-/// Builds the array of 2D textures using all the blocks
-/// Each block is associated with 3 textures: side, top and bottom
-/// All these textures are loaded into one single texture array, that is fed to OpenGL.
-/// The fragment shader responsible for the cubes is then in charge of selecting the correct element of this array.
-pub fn build_textures_array(display: &Display<WindowSurface>) -> Texture2dArray {
-    // Get the path of the block textures
-    let root = "./resources/block/";
-    let extension = ".png";
-    let all_textures = Block::get_texture_files();
-    let source = all_textures.iter().map(|name| {
-        println!(" Adding texture {name} into texture array");
-        let data = std::fs::read(root.to_string() + name + extension).unwrap();
-        let image = image::load(std::io::Cursor::new(data), image::ImageFormat::Png).unwrap().to_rgba8();
-        let image_dimensions = image.dimensions();
-        glium::texture::RawImage2d::from_raw_rgba_reversed(&image.into_raw(), image_dimensions)
-    }).collect();
-    Texture2dArray::new(display, source).unwrap()
-}
-
 /// Loads a texture and returns it
 pub fn load_texture(bytes: &[u8], display: &Display<WindowSurface>) -> Texture2d {
     let image = image::load(std::io::Cursor::new(bytes),
@@ -36,19 +17,19 @@ pub fn load_texture(bytes: &[u8], display: &Display<WindowSurface>) -> Texture2d
     Texture2d::new(display, image).unwrap()
 }
 
-/// Loads an image from a path,
+/// Loads images from the resource pack, in its `root` folder,
 /// uses a list of ImageCut to divide the image into sub images,
 /// rescales the sub image to a common size and
 /// returns a Texture2dArray with these images.
 /// ImageCut is in the format \[x, y, height, width\] (u,v) coord,
 /// (0, 0) is top left and x, y, height and width are in fraction of the image.
-pub fn load_texture_cut(root : &str, all_textures_name: Vec<&str>, display: &Display<WindowSurface>, cut: &[ImageCut]) -> Texture2dArray {
+pub fn load_texture_cut(pack: &ResourcePack, root : &str, all_textures_name: Vec<&str>, display: &Display<WindowSurface>, cut: &[ImageCut]) -> Texture2dArray {
     let mut lcm_x: u32 = 1;
     let mut lcm_y: u32 = 1;
     let mut source: Vec<ImageBuffer<Rgba<u8>,Vec<u8>>> = all_textures_name.iter()
         .map(|name| {
             println!(" Adding texture {} into texture array",root.to_string() + name);
-            let data = std::fs::read(root.to_string() + name).unwrap();
+            let data = pack.read(&(root.to_string() + name)).unwrap();
             let image = image::load(std::io::Cursor::new(data),                  
                                image::ImageFormat::Png).unwrap().to_rgba8();
             // Set a scaling factor which is a common multiplier for every texture
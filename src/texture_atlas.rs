@@ -0,0 +1,221 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use glium::glutin::surface::WindowSurface;
+use glium::texture::{MipmapsOption, RawImage2d, Texture2dArray};
+use glium::Display;
+use image::imageops::FilterType;
+use image::RgbaImage;
+use strum::{EnumIter, IntoEnumIterator};
+
+use crate::block_kind::Block;
+use crate::item::Item;
+use crate::resource_pack::ResourcePack;
+
+/// Folder and extension of the textures of the blocks, in the assets
+const TEXTURES_FOLDER: &str = "block/";
+const TEXTURES_EXTENSION: &str = ".png";
+
+/// The faces of a cube that can have their own texture
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, EnumIter)]
+pub enum BlockFace {
+    Side,
+    Top,
+    Bottom,
+}
+
+impl BlockFace {
+    /// The face looking toward the `axis` (0 for x, 1 for y, 2 for z), on its positive (`side` > 0) or negative side
+    pub fn from_axis(axis: usize, side: i32) -> Self {
+        match (axis, side > 0) {
+            (1, true) => BlockFace::Top,
+            (1, false) => BlockFace::Bottom,
+            _ => BlockFace::Side,
+        }
+    }
+
+    /// End of the name of the texture file of the face
+    pub fn suffix(&self) -> &'static str {
+        match self {
+            BlockFace::Side => "_side",
+            BlockFace::Top => "_top",
+            BlockFace::Bottom => "_bottom",
+        }
+    }
+}
+
+/// A texture file of the atlas, and the layers of its images
+struct AtlasFile {
+    name: String,
+    first_layer: u32,
+    /// Number of images of the animation, stacked vertically in the file
+    frames: u8,
+}
+
+/// Where the texture of each face of each item is in the texture array of the blocks.
+/// A file used by several faces (or several blocks) is only loaded once.
+/// Each image of an animated texture has its own layer, right after the one of the previous image:
+/// the shaders add the current image to the layer of the face.
+pub struct TextureAtlas {
+    files: Vec<AtlasFile>,
+    /// Layer of the first image of each face
+    layers: HashMap<(Item, BlockFace), u32>,
+}
+
+impl TextureAtlas {
+    /// The layers of the textures of all the items, in the order of the items and then of the faces
+    pub fn new() -> Self {
+        let mut atlas = Self { files: Vec::new(), layers: HashMap::new() };
+        for item in Item::iter() {
+            for face in BlockFace::iter() {
+                let frames = item.block().map_or(1, |block| block.animation_frames());
+                atlas.add(item, face, item.texture_file(face), frames);
+            }
+        }
+        atlas
+    }
+
+    /// The atlas of the blocks, shared by the renderer and the workers building the meshes
+    pub fn get() -> &'static TextureAtlas {
+        static ATLAS: OnceLock<TextureAtlas> = OnceLock::new();
+        ATLAS.get_or_init(TextureAtlas::new)
+    }
+
+    /// Gives the file to the face, in new layers (one per image) unless the file already has some
+    fn add(&mut self, item: Item, face: BlockFace, file: String, frames: u8) {
+        let layer = match self.files.iter().find(|f| f.name == file) {
+            Some(f) => f.first_layer,
+            None => {
+                let first_layer = self.n_layers() as u32;
+                self.files.push(AtlasFile { name: file, first_layer, frames: frames.max(1) });
+                first_layer
+            }
+        };
+        self.layers.insert((item, face), layer);
+    }
+
+    /// Index in the texture array of the texture of the face, of its first image if it is animated
+    pub fn layer(&self, block: Block, face: BlockFace) -> f32 {
+        self.item_layer(Item::Block(block), face)
+    }
+
+    /// Index in the texture array of the texture of the face of any item, see `layer`
+    pub fn item_layer(&self, item: Item, face: BlockFace) -> f32 {
+        self.layers[&(item, face)] as f32
+    }
+
+    /// Layers of the side, top and bottom textures of the block
+    pub fn layers(&self, block: Block) -> [f32; 3] {
+        self.item_layers(Item::Block(block))
+    }
+
+    /// Layers of the side, top and bottom textures of any item
+    pub fn item_layers(&self, item: Item) -> [f32; 3] {
+        [BlockFace::Side, BlockFace::Top, BlockFace::Bottom].map(|face| self.item_layer(item, face))
+    }
+
+    /// Number of images of the texture starting at this layer, 1 if it is not animated
+    pub fn frames(&self, layer: f32) -> u8 {
+        let next = self.files.partition_point(|file| file.first_layer as f32 <= layer);
+        next.checked_sub(1).map_or(1, |i| self.files[i].frames)
+    }
+
+    /// Number of layers of the texture array
+    pub fn n_layers(&self) -> usize {
+        self.files.iter().map(|f| f.frames as usize).sum()
+    }
+
+    /// Loads the textures of the resource pack into a texture array, with their mipmaps.
+    /// The textures smaller than the largest one are scaled up without smoothing, so that they keep their pixels.
+    /// The mipmaps must be sampled with a nearest filter as well (`MinifySamplerFilter::NearestMipmapNearest`).
+    pub fn build_texture(&self, display: &Display<WindowSurface>, pack: &ResourcePack) -> Texture2dArray {
+        let images: Vec<RgbaImage> = self.files.iter().flat_map(|file| {
+            println!(" Adding texture {} into texture array", file.name);
+            let data = pack.read(&(TEXTURES_FOLDER.to_string() + &file.name + TEXTURES_EXTENSION)).unwrap();
+            let image = image::load(std::io::Cursor::new(data), image::ImageFormat::Png).unwrap().to_rgba8();
+            split_frames(&image, file.frames)
+        }).collect();
+        let width = images.iter().map(|image| image.width()).max().unwrap_or(1);
+        let height = images.iter().map(|image| image.height()).max().unwrap_or(1);
+        let source = images.into_iter().map(|image| {
+            let image = if image.dimensions() == (width, height) {
+                image
+            } else {
+                image::imageops::resize(&image, width, height, FilterType::Nearest)
+            };
+            RawImage2d::from_raw_rgba_reversed(&image.into_raw(), (width, height))
+        }).collect();
+        Texture2dArray::with_mipmaps(display, source, MipmapsOption::AutoGeneratedMipmaps).unwrap()
+    }
+}
+
+/// The images of an animated texture, stacked vertically from the top one
+fn split_frames(image: &RgbaImage, frames: u8) -> Vec<RgbaImage> {
+    let height = image.height() / frames as u32;
+    (0..frames as u32)
+        .map(|i| image::imageops::crop_imm(image, 0, i * height, image.width(), height).to_image())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::block_kind::Block;
+    use crate::item::Item;
+    use crate::texture_atlas::{split_frames, BlockFace, TextureAtlas};
+    use crate::tool::Tool;
+    use image::{Rgba, RgbaImage};
+    use strum::IntoEnumIterator;
+
+    #[test]
+    fn test_layers_of_the_faces() {
+        let atlas = TextureAtlas::get();
+        // Every face of every block has a texture, and the grass has 3 different ones
+        for block in Block::iter() {
+            for layer in atlas.layers(block) {
+                assert!((layer as usize) < atlas.n_layers());
+            }
+        }
+        let [side, top, bottom] = atlas.layers(Block::GRASS);
+        assert!(side != top && top != bottom && side != bottom);
+        let file = atlas.files.iter().find(|f| f.first_layer == top as u32).unwrap();
+        assert_eq!(file.name, "grass_top");
+
+        // The tools have their own textures
+        let [side, ..] = atlas.item_layers(Item::Tool(Tool::Pickaxe));
+        let file = atlas.files.iter().find(|f| f.first_layer == side as u32).unwrap();
+        assert_eq!(file.name, "stone_pickaxe_side");
+
+        assert_eq!(BlockFace::from_axis(1, 1), BlockFace::Top);
+        assert_eq!(BlockFace::from_axis(1, -1), BlockFace::Bottom);
+        assert_eq!(BlockFace::from_axis(0, -1), BlockFace::Side);
+    }
+
+    #[test]
+    fn test_shared_files_are_loaded_once() {
+        let mut atlas = TextureAtlas { files: Vec::new(), layers: Default::default() };
+        for face in BlockFace::iter() {
+            atlas.add(Block::STONE.into(), face, String::from("stone_side"), 1);
+        }
+        atlas.add(Block::GRASS.into(), BlockFace::Top, String::from("grass_top"), 1);
+        assert_eq!(atlas.n_layers(), 2);
+        assert_eq!(atlas.layers(Block::STONE), [0., 0., 0.]);
+        assert_eq!(atlas.layer(Block::GRASS, BlockFace::Top), 1.);
+    }
+
+    #[test]
+    fn test_animated_textures_take_one_layer_per_image() {
+        let mut atlas = TextureAtlas { files: Vec::new(), layers: Default::default() };
+        atlas.add(Block::WATER.into(), BlockFace::Top, String::from("water_top"), 4);
+        atlas.add(Block::GRASS.into(), BlockFace::Top, String::from("grass_top"), 1);
+        assert_eq!(atlas.n_layers(), 5);
+        assert_eq!(atlas.layer(Block::GRASS, BlockFace::Top), 4.);
+        assert_eq!(atlas.frames(0.), 4);
+        assert_eq!(atlas.frames(4.), 1);
+
+        let strip = RgbaImage::from_fn(2, 8, |_, y| Rgba([y as u8, 0, 0, 255]));
+        let frames = split_frames(&strip, 4);
+        assert_eq!(frames.len(), 4);
+        assert_eq!(frames[1].dimensions(), (2, 2));
+        assert_eq!(frames[1].get_pixel(0, 0)[0], 2);
+    }
+}
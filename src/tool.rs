@@ -0,0 +1,113 @@
+use serde::{Deserialize, Serialize};
+use strum::EnumIter;
+
+use crate::block_kind::Block;
+use crate::texture_atlas::BlockFace;
+
+/// Factor of the breaking speed of the blocks of the material a tool is made for
+const TOOL_SPEED: f32 = 4.;
+/// Factor of the breaking speed of the plants with a sword
+const SWORD_SPEED: f32 = 1.5;
+
+/// What a block is made of, see `Block::material`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Material {
+    /// The stone and the ores, only harvested with a pickaxe
+    Rock,
+    /// The dirt, the sand and the gravel
+    Earth,
+    Wood,
+    /// The leaves and the crops
+    Plant,
+    Other,
+}
+
+/// The items used as tools (see `Item`). They cannot be placed like the blocks,
+/// but they speed up the breaking of the blocks of their material and wear out as they are used.
+/// The tools were blocks before: they keep the id (see `Item::id`) and accept the name they had then in the saves.
+#[derive(Clone, Copy, EnumIter, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Tool {
+    #[serde(alias = "PICKAXE")]
+    Pickaxe = 27,
+    #[serde(alias = "SHOVEL")]
+    Shovel = 28,
+    #[serde(alias = "AXE")]
+    Axe = 29,
+    #[serde(alias = "SWORD")]
+    Sword = 8,
+    #[serde(alias = "HOE")]
+    Hoe = 17,
+    /// Shoots the arrows of the inventory, see `BowCharge`
+    #[serde(alias = "BOW")]
+    Bow = 30,
+}
+
+impl Tool {
+    /// The id of the tool in the saves, never given to a block (see `Block::id`)
+    pub fn id(&self) -> u8 {
+        *self as u8
+    }
+
+    fn file_name(&self) -> &'static str {
+        match self {
+            Tool::Pickaxe => "stone_pickaxe",
+            Tool::Shovel => "stone_shovel",
+            Tool::Axe => "stone_axe",
+            Tool::Sword => "stone_sword",
+            Tool::Hoe => "stone_hoe",
+            Tool::Bow => "bow",
+        }
+    }
+
+    /// Name of the texture file of the face of the tool drawn as a cube, without its extension
+    pub fn texture_file(&self, face: BlockFace) -> String {
+        self.file_name().to_string() + face.suffix()
+    }
+
+    /// Number of uses (blocks broken, hits) before the tool breaks
+    pub fn durability(&self) -> u16 {
+        match self {
+            Tool::Hoe => 64,
+            Tool::Bow => 384,
+            _ => 131,
+        }
+    }
+
+    /// Damage dealt by a hit with this tool in hand, the bare hand deals 1
+    pub fn damage(&self) -> u8 {
+        match self {
+            Tool::Sword => 4,
+            Tool::Axe => 3,
+            Tool::Pickaxe | Tool::Shovel => 2,
+            Tool::Hoe | Tool::Bow => 1,
+        }
+    }
+
+    /// Factor of the breaking speed of the block with this tool in hand, 1 for the bare hand
+    pub fn speed(&self, block: &Block) -> f32 {
+        match (self, block.material()) {
+            (Tool::Pickaxe, Material::Rock) | (Tool::Shovel, Material::Earth) | (Tool::Axe, Material::Wood) => TOOL_SPEED,
+            (Tool::Sword, Material::Plant) => SWORD_SPEED,
+            _ => 1.,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::block_kind::Block;
+    use crate::tool::Tool;
+
+    #[test]
+    fn test_tools_and_their_materials() {
+        // Each tool is faster on its own material only
+        assert!(Tool::Pickaxe.speed(&Block::STONE) > 1.);
+        assert!(Tool::Shovel.speed(&Block::DIRT) > 1.);
+        assert!(Tool::Axe.speed(&Block::OAKLOG) > 1.);
+        assert_eq!(Tool::Pickaxe.speed(&Block::DIRT), 1.);
+        assert_eq!(Tool::Shovel.speed(&Block::STONE), 1.);
+
+        // The sword hits the hardest
+        assert!(Tool::Sword.damage() > Tool::Axe.damage());
+    }
+}
@@ -1,84 +1,480 @@
 use std::env;
+use std::fmt::{Display as FmtDisplay, Error, Formatter};
 use crate::aabb::AABB;
 use crate::actions::Action;
-use crate::block_kind::Block;
-use crate::chunk::{Chunk, CHUNK_SIZE};
+use crate::block_entity::BlockEntity;
+use crate::block_kind::{Block, MAX_CROP_STAGE};
+use crate::block_shape::placed_block;
+use crate::block_updates::BlockUpdates;
+use crate::chunk::{fade_alpha, Chunk, ChunkCoord, CHUNK_HEIGHT, CHUNK_SIZE, LOD_CUBE_SIZE};
+use crate::chunk_workers::{ChunkJob, ChunkResult, ChunkWorkers};
 use crate::collidable::{Collidable, CollisionData};
 use crate::cube::Cube;
 use crate::cubes_to_draw::CubesToDraw;
-use crate::graphics::cube::CubeInstance;
+use crate::entity::arrow::strike;
+use crate::entity::pig::{Pig, MAX_PIGS, PIG_SPAWN_RATE};
+use crate::entity::zombie::{Zombie, MAX_SPAWN_DISTANCE, MAX_SPAWN_LIGHT, MAX_ZOMBIES, MIN_SPAWN_DISTANCE, NIGHT_DAYLIGHT, ZOMBIE_SPAWN_RATE};
+use crate::entity::store::{EntityId, EntityStore};
+use crate::falling_block::FallingBlock;
+use crate::game_mode::GameMode;
+use crate::graphics::chunk_mesh::{greedy_mesh, ChunkMesh};
+use crate::graphics::cube::{CubeInstance, NEIGHBOR_FACES};
+use crate::item_drop::merge_drops;
+use crate::lighting::{ChunkLight, Lighting};
+use crate::player_items::ItemStack;
+use crate::primitives::frustum::Frustum;
 use crate::primitives::position::Position;
+use crate::primitives::ray::Ray;
 use crate::primitives::vector::Vector3;
-use crate::world_serializer::{get_serialize_container, serialize_one_chunk, SerializedWorld};
+use crate::primitives::voxel_traversal::cells_on_segment;
+use crate::random_ticks::RandomTicks;
+use crate::structure::{Structure, StructureRotation};
+use crate::tool::Tool;
+use crate::world_generation::biome::{BiomeConfig, BiomeGenerator};
+use crate::world_generation::biomes_def::BIOMES;
+use crate::world_generation::world_preview::{columns_at_distance, spawn_position, WorldPreview};
+use crate::world_meta::WorldMeta;
+use crate::world_serializer::{get_serialize_container, serialize_one_chunk, unpack_cube_state, SerializedWorld};
 use glium::glutin::surface::WindowSurface;
 use glium::{Display, VertexBuffer};
+use rand::rngs::SmallRng;
+use rand::Rng;
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::hash::{DefaultHasher, Hash, Hasher};
 use std::ops::Index;
+use std::time::{Duration, Instant};
 use strum::IntoEnumIterator;
 
+/// Lowest light level (of the sky or of the blocks) at which the crops grow
+pub const MIN_CROP_LIGHT: u8 = 9;
+
+/// Reasons why a block cannot be placed
+#[derive(Debug, PartialEq)]
+pub enum PlacementError {
+    /// The ray does not cross any face of the touched cube
+    NoFaceHit,
+    /// There is already a block that cannot be replaced
+    Occupied,
+    /// The block would be placed inside the player
+    OverlapsPlayer,
+    /// The block would be placed above the build limit of the world
+    AboveBuildLimit,
+    /// A block would be outside of the loaded chunks
+    OutOfBounds,
+    /// The block cannot stand against this face, like a torch on a wall
+    WrongFace,
+    /// The crops are only planted on farmland
+    NoFarmland,
+}
+
+impl FmtDisplay for PlacementError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(f, "{}", match self {
+            PlacementError::NoFaceHit => "Aim at a face of the block to place against it",
+            PlacementError::Occupied => "There is already a block here",
+            PlacementError::OverlapsPlayer => "The block would be placed inside a player",
+            PlacementError::AboveBuildLimit => "Blocks cannot be placed above the build limit",
+            PlacementError::OutOfBounds => "This part of the world is not loaded",
+            PlacementError::WrongFace => "This block cannot stand against this face",
+            PlacementError::NoFarmland => "Crops can only be planted on farmland",
+        })
+    }
+}
+
+/// Something that happened in the world, for the game to react to
+#[derive(Debug, Clone, PartialEq)]
+pub enum WorldEvent {
+    /// A broken block left items at this position
+    ItemDropped { pos: Vector3, stack: ItemStack },
+}
+
+/// The first cube hit by a ray, see `World::raycast`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RayHit {
+    pub cube: Cube,
+    /// Normal of the face of the cube that was hit, zero if the ray starts inside of the cube
+    pub normal: Vector3,
+    /// Distance from the origin of the ray to the face [world units]
+    pub distance: f32,
+    /// Where the ray enters the cube, on the grid
+    pub point: Vector3,
+    /// Unit direction of the ray
+    pub direction: Vector3,
+}
+
+/// The cubes of the game.
+/// The rendering data is only built by `set_cubes_to_draw`, so a world can be used without
+/// any OpenGL context (on the server or in tests).
 pub struct World {
     /// The list of the chunks currently being displayed
-    chunks: Vec<Chunk>,
+    chunks: HashMap<ChunkCoord, Chunk>,
     cubes_to_draw: Option<CubesToDraw>,
+    /// Side of one cube, in world units.
+    /// The chunks and cubes always live on a grid of unit cubes, this is only applied when going
+    /// from the world space (player, rendering) to the grid and back.
+    scale: f32,
+    /// Settings of this world, saved with it
+    meta: WorldMeta,
+    /// Unit vector of the direction the entities fall to
+    gravity_direction: Vector3,
+    /// Chunks drawn with super-cubes, because they are far from the camera
+    coarse_chunks: HashSet<ChunkCoord>,
+    /// Cells to update at the next tick, for the water to flow
+    block_updates: BlockUpdates,
+    /// Events that happened since the last call to `take_events`
+    events: Vec<WorldEvent>,
+    /// Chunks whose mesh must be built again, see `take_dirty_meshes`
+    dirty_meshes: HashSet<ChunkCoord>,
+    /// Sky and block light of every cell, baked into the chunk meshes
+    lighting: Lighting,
+    /// The entities moving in the world: the sand and gravel falling until they land, the items dropped...
+    entities: EntityStore,
+    /// Picks the cells whose block changes slowly, see `random_tick`
+    random_ticks: RandomTicks,
 }
 
 impl World {
     pub fn empty() -> Self {
-        let chunks = Vec::new();
         Self {
-            chunks,
+            chunks: HashMap::new(),
             cubes_to_draw: None,
+            scale: 1.,
+            meta: WorldMeta::default(),
+            gravity_direction: Vector3::new(0., -1., 0.),
+            coarse_chunks: HashSet::new(),
+            block_updates: BlockUpdates::new(),
+            events: Vec::new(),
+            dirty_meshes: HashSet::new(),
+            lighting: Lighting::new(),
+            entities: EntityStore::new(),
+            random_ticks: RandomTicks::new(rand::random()),
         }
     }
 
     pub fn new(chunks: Vec<Chunk>) -> Self {
         let mut w = Self {
-            chunks,
+            chunks: chunks.into_iter().map(|chunk| (chunk.coord(), chunk)).collect(),
             cubes_to_draw: None,
+            scale: 1.,
+            meta: WorldMeta::default(),
+            gravity_direction: Vector3::new(0., -1., 0.),
+            coarse_chunks: HashSet::new(),
+            block_updates: BlockUpdates::new(),
+            events: Vec::new(),
+            dirty_meshes: HashSet::new(),
+            lighting: Lighting::new(),
+            entities: EntityStore::new(),
+            random_ticks: RandomTicks::new(rand::random()),
         };
 
         w.compute_visible_cubes();
+        let coords: Vec<ChunkCoord> = w.chunks.keys().copied().collect();
+        w.relight_chunks(&coords);
         w
     }
 
     pub fn fill_for_demo(&mut self) {
         let s = CHUNK_SIZE as f32;
-        self.chunks.push(Chunk::new_for_demo([0., 0.], 0));
-        self.chunks.push(Chunk::new_for_demo([s, 0.], 2));
-        self.chunks.push(Chunk::new_for_demo([0., -s], 2));
-        self.chunks.push(Chunk::new_for_demo([0., s], 2));
-        self.chunks.push(Chunk::new_for_demo([-s, 0.], 0));
-        self.chunks.push(Chunk::new_for_demo([-2. * s, 0.], 0));
+        for (corner, kind) in [([0., 0.], 0), ([s, 0.], 2), ([0., -s], 2), ([0., s], 2), ([-s, 0.], 0), ([-2. * s, 0.], 0)] {
+            let chunk = Chunk::new_for_demo(corner, kind);
+            self.chunks.insert(chunk.coord(), chunk);
+        }
     }
 
+    /// The chunks currently loaded, in no particular order
+    pub fn chunks(&self) -> impl Iterator<Item = &Chunk> {
+        self.chunks.values()
+    }
+
+    pub fn meta(&self) -> &WorldMeta {
+        &self.meta
+    }
+
+    pub fn meta_mut(&mut self) -> &mut WorldMeta {
+        &mut self.meta
+    }
+
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// Sets the side of one cube in world units
+    pub fn set_scale(&mut self, scale: f32) {
+        self.scale = scale;
+    }
+
+    /// Direction the entities fall to, straight down by default
+    pub fn gravity_direction(&self) -> Vector3 {
+        self.gravity_direction
+    }
+
+    /// Changes the direction of the gravity, for instance to walk on the ceiling
+    pub fn set_gravity_direction(&mut self, direction: Vector3) {
+        let mut direction = direction;
+        direction.normalize();
+        self.gravity_direction = direction;
+    }
+
+    /// Converts a position in the world space into the grid of the cubes
+    pub fn to_grid(&self, pos: &Vector3) -> Vector3 {
+        *pos / self.scale
+    }
+
+    /// Returns the position of the cube containing this point of the world space
+    pub fn to_cube_coordinates(&self, pos: &Vector3) -> Vector3 {
+        pos.to_scaled_cube_coordinates(self.scale)
+    }
+
+    /// Returns the space occupied by the cube at `cube_pos` (on the grid), in the world space
+    pub fn cube_aabb(&self, cube_pos: Vector3) -> AABB {
+        Cube::cube_aabb(cube_pos).scaled(self.scale)
+    }
+
+    /// `pos` is expressed on the grid
     pub fn cubes_near_player(&self, pos: Vector3) -> impl Iterator<Item = &Option<Cube>> {
         self.chunks
-            .iter()
+            .values()
             .filter(move |chunk| chunk.is_near_player(&pos))
             .flat_map(|chunk| chunk.cubes_iter())
     }
 
-    pub fn add_chunk(&mut self, chunk: Chunk) {
+    /// Adds a chunk loaded while the game is running, it will fade in from now
+    pub fn add_chunk(&mut self, mut chunk: Chunk) {
+        chunk.set_load_time(Some(Instant::now()));
+        chunk.compute_visible_cubes();
+        let transparent: Vec<Vector3> = chunk
+            .cubes_iter()
+            .flatten()
+            .filter(|c| c.is_transparent())
+            .map(|c| *c.position())
+            .collect();
+        let bottom: Vec<Vector3> = chunk
+            .cubes_iter()
+            .flatten()
+            .filter(|c| c.position().y() == 0.)
+            .map(|c| *c.position())
+            .collect();
+        let coord = chunk.coord();
+        self.chunks.insert(coord, chunk);
+        // The bottom layer is left out by `Chunk::compute_visible_cubes`
+        for pos in bottom {
+            let count = self.count_neighbors(&pos);
+            self.cube_at_mut(pos).unwrap().set_n_neighbors(count);
+        }
+
+        // Only the visible cubes are drawn, with the faces against the others hidden
         if self.cubes_to_draw.is_some() {
-            self.cubes_to_draw.as_mut().unwrap().add_chunk(&chunk);
+            let chunk = &self.chunks[&coord];
+            let visible: Vec<(Cube, u8)> = chunk
+                .cubes_iter()
+                .flatten()
+                .filter(|c| c.is_visible())
+                .map(|c| (*c, self.hidden_faces(c)))
+                .collect();
+            let cubes_to_draw = self.cubes_to_draw.as_mut().unwrap();
+            for (cube, hidden_faces) in visible {
+                cubes_to_draw.add_cube_with_hidden_faces(&cube, hidden_faces);
+            }
+        }
+        self.update_shared_borders(coord);
+        self.mark_meshes_dirty_around(coord);
+        let lit = self.lighting.add_chunk(coord, &self.chunks);
+        self.dirty_meshes.extend(lit);
+        for position in transparent {
+            self.update_hidden_faces_around(position);
+        }
+    }
+
+    /// Unloads the chunk at this position, its cubes are not drawn anymore.
+    /// The cubes of the chunks next to it that were covered by its border are revealed.
+    pub fn remove_chunk(&mut self, coord: ChunkCoord) -> Option<Chunk> {
+        let chunk = self.chunks.remove(&coord)?;
+        self.coarse_chunks.remove(&coord);
+        self.lighting.remove_chunk(coord);
+        if let Some(cubes_to_draw) = self.cubes_to_draw.as_mut() {
+            cubes_to_draw.remove_chunk(coord);
+        }
+        self.update_shared_borders(coord);
+        self.mark_meshes_dirty_around(coord);
+        Some(chunk)
+    }
+
+    /// Number of opaque cubes around this position, the bottommost layer is never seen from below
+    fn count_neighbors(&self, pos: &Vector3) -> u8 {
+        let count = Cube::neighbors_positions(*pos)
+            .iter()
+            .filter(|neighbor| !self.is_position_free_or_transparent(neighbor))
+            .count() as u8;
+        if pos.y() == 0. {
+            count + 1
+        } else {
+            count
+        }
+    }
+
+    /// Recomputes the visibility of the cubes on the faces shared by this chunk and the loaded chunks next to it.
+    /// Only these cubes are added to or removed from the cubes to draw, the rest of the chunks is left as it is.
+    /// The hidden faces of the ones that stay visible are updated at once.
+    fn update_shared_borders(&mut self, coord: ChunkCoord) {
+        let s = CHUNK_SIZE as i32;
+        let (x0, z0) = coord;
+        let mut positions = Vec::new();
+        for (dx, dz) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+            let neighbor = (x0 + dx * s, z0 + dz * s);
+            if !self.chunks.contains_key(&neighbor) {
+                continue;
+            }
+            // The cells of both chunks along their shared face
+            let side = |d: i32, origin: i32, t: i32| match d {
+                1 => origin + s - 1,
+                -1 => origin,
+                _ => origin + t,
+            };
+            for t in 0..s {
+                for y in 0..CHUNK_HEIGHT as i32 {
+                    let inside = Vector3::newi(side(dx, x0, t), y, side(dz, z0, t));
+                    positions.push(inside);
+                    positions.push(inside + Vector3::newi(dx, 0, dz));
+                }
+            }
+        }
+
+        let mut hidden_faces: HashMap<[i32; 3], u8> = HashMap::new();
+        for pos in positions {
+            let Some(cube) = self.cube_at(pos) else {
+                continue;
+            };
+            let was_visible = cube.is_visible();
+            let count = self.count_neighbors(&pos);
+            let cube = self.cube_at_mut(pos).unwrap();
+            cube.set_n_neighbors(count);
+            let cube = *cube;
+
+            if self.cubes_to_draw.is_none() {
+                continue;
+            }
+            let faces = self.hidden_faces(&cube);
+            let cubes_to_draw = self.cubes_to_draw.as_mut().unwrap();
+            if was_visible && !cube.is_visible() {
+                cubes_to_draw.remove_cube(&pos);
+            } else if !was_visible && cube.is_visible() {
+                cubes_to_draw.add_cube_with_hidden_faces(&cube, faces);
+            } else if cube.is_visible() {
+                hidden_faces.insert(pos.as_array().map(|x| x as i32), faces);
+            }
+        }
+        if let Some(cubes_to_draw) = self.cubes_to_draw.as_mut() {
+            cubes_to_draw.set_hidden_faces_of(&hidden_faces);
         }
-        self.chunks.push(chunk);
     }
 
     pub fn get_chunk(&self, corner: (i32, i32)) -> Option<Chunk> {
-        for chunk in &self.chunks {
-            let tmp = chunk.corner();
-            if tmp[0] == corner.0 as f32 && tmp[1] == corner.1 as f32 {
-                return Some(chunk.clone());
+        self.chunks.get(&corner).cloned()
+    }
+
+    /// The coordinates of the chunk whose column contains the position, whatever its height.
+    /// Negative positions belong to the chunk below them: x = -1 is in the chunk of corner -CHUNK_SIZE.
+    pub fn chunk_of(pos: &Vector3) -> ChunkCoord {
+        let s = CHUNK_SIZE as i32;
+        let x = pos.x().floor() as i32;
+        let z = pos.z().floor() as i32;
+        (x.div_euclid(s) * s, z.div_euclid(s) * s)
+    }
+
+    /// Finds in which chunk a global position falls, and where inside this chunk.
+    /// Returns the coordinates of the chunk (see `chunk_of`), and the local `[lx, ly, lz]` position
+    /// (see `Chunk::local_to_global`). The positions below or above the chunks are in none of them.
+    pub fn global_to_chunk(pos: &Vector3) -> Option<(ChunkCoord, [usize; 3])> {
+        let y = pos.y().floor();
+        if y < 0. || y >= CHUNK_HEIGHT as f32 {
+            return None;
+        }
+        let s = CHUNK_SIZE as i32;
+        let local = [(pos.x().floor() as i32).rem_euclid(s) as usize, y as usize, (pos.z().floor() as i32).rem_euclid(s) as usize];
+        Some((Self::chunk_of(pos), local))
+    }
+
+    /// Summarizes the world that would be generated from this seed, without generating it
+    pub fn preview(seed: u64, sample_size: i32) -> WorldPreview {
+        WorldPreview::new(seed, sample_size)
+    }
+
+    /// Finds where a player can spawn: on top of the solid column closest to the origin.
+    /// Returns None if no loaded column has a solid cube with two free cells above it.
+    pub fn find_safe_spawn(&self) -> Option<Position> {
+        for distance in 0.. {
+            let columns = columns_at_distance(distance);
+            let in_world: Vec<&[i32; 2]> = columns.iter()
+                .filter(|[x, z]| self.chunk_containing(&Vector3::new(*x as f32, 0., *z as f32)).is_some())
+                .collect();
+            if in_world.is_empty() {
+                return None;
+            }
+
+            for [x, z] in in_world {
+                let top = (0..CHUNK_HEIGHT as i32).rev().find(|&y| {
+                    self.block_at(&Vector3::new(*x as f32, y as f32, *z as f32)).is_some()
+                });
+                if let Some(top) = top {
+                    let block_at = |y: i32| self.block_at(&Vector3::new(*x as f32, y as f32, *z as f32));
+                    let solid = block_at(top).is_some_and(|block| !block.is_transparent());
+                    if solid && block_at(top + 1).is_none() && block_at(top + 2).is_none() {
+                        return Some(spawn_position(*x, top, *z));
+                    }
+                }
             }
         }
         None
     }
 
+    /// The biome of the column of this position, from the seed of the world.
+    /// It gives the colors of the grass and of the leaves there.
+    pub fn biome_at(&self, pos: &Vector3) -> &'static BiomeConfig {
+        let [x, _, z] = pos.to_cell();
+        &BIOMES[BiomeGenerator::biome_at(self.meta.seed, x, z) as usize]
+    }
+
+    /// Generates the chunk at this position from the seed and the generator of the world, if it is not loaded yet
+    pub fn generate_chunk(&mut self, coord: ChunkCoord) {
+        if self.get_chunk(coord).is_none() {
+            self.add_chunk(self.meta.generate_chunk(coord));
+        }
+    }
+
+    /// Generates the chunk at this position like `generate_chunk`, with the decorator applied on its terrain
+    pub fn generate_chunk_decorated(&mut self, coord: ChunkCoord, decorator: &impl Fn(&mut Chunk, ChunkCoord, &mut SmallRng)) {
+        if self.get_chunk(coord).is_none() {
+            self.add_chunk(self.meta.generate_decorated_chunk(coord, decorator));
+        }
+    }
+
     /// Loads a world from a file.
     pub fn from_file(name: &str) -> Option<Self> {
+        Self::load(name).map(|(world, _)| world)
+    }
+
+    /// Loads a world and the settings saved with it.
+    /// The world also keeps its settings, they are returned for convenience.
+    pub fn load(name: &str) -> Option<(Self, WorldMeta)> {
+        Self::load_with(name, Self::from_json)
+    }
+
+    /// Loads a world and its settings like `load`, but the chunks are filled on `threads` threads.
+    /// The result does not depend on the number of threads.
+    pub fn load_parallel(name: &str, threads: usize) -> Option<(Self, WorldMeta)> {
+        Self::load_with(name, |data| Self::from_json_parallel(data, threads))
+    }
+
+    fn load_with(name: &str, parse: impl FnOnce(String) -> Self) -> Option<(Self, WorldMeta)> {
         match std::fs::read_to_string(name) {
-            Ok(data) => Some(Self::from_json(data)),
+            Ok(data) => {
+                let mut world = parse(data);
+                let coords: Vec<ChunkCoord> = world.chunks.keys().copied().collect();
+                world.relight_chunks(&coords);
+                let meta = world.meta.clone();
+                Some((world, meta))
+            }
             Err(err) => {
                 println!("Could not read: {name} with error: {err}");
                 None
@@ -88,10 +484,7 @@ impl World {
 
     /// Saves the current map to the given file
     pub fn save_to_file(&self, name: &str) {
-        // Note: so far I am using `serde_json` but we will be able to change this in the future.
-        //       There seems to be many options suited for us: https://serde.rs/#data-formats
-        let serialized = self.to_json();
-        match std::fs::write(name, serialized) {
+        match self.save(name) {
             Ok(_) => println!("Map was saved at {name}"),
             Err(err) => {
                 println!("Error while saving {name}: {err}")
@@ -99,22 +492,55 @@ impl World {
         }
     }
 
+    /// Saves the current map to the given file.
+    /// The map is first written next to it and then renamed, so that a crash while saving
+    /// leaves the previous save untouched.
+    pub fn save(&self, name: &str) -> std::io::Result<()> {
+        // Note: so far I am using `serde_json` but we will be able to change this in the future.
+        //       There seems to be many options suited for us: https://serde.rs/#data-formats
+        let serialized = self.to_json();
+        let tmp = format!("{name}.tmp");
+        std::fs::write(&tmp, serialized)?;
+        std::fs::rename(&tmp, name)
+    }
+
     /// Returns a list of cube attributes to be drawn on the screen.
     /// Each item on this list will result in a cube drawn in the screen.
-    ///
-    /// 'selected_cube': the currently selected cube, that will be rendered differently.
     pub fn set_cubes_to_draw(&mut self) {
         // I know that this function looks bad, but... Trust the optimizer
         // I have tried to optimize this shit using a custom class that does not re-allocate everything
         // but it does not improve anything ... So let's keep the simple solution of always calling `push`
         let mut positions: Vec<CubeInstance> = Vec::new();
-        for chunk in &self.chunks {
+        let mut transparent_positions: Vec<CubeInstance> = Vec::new();
+        for chunk in self.chunks.values() {
+            if self.coarse_chunks.contains(&chunk.coord()) {
+                for (corner, block) in chunk.coarse_cubes() {
+                    let instance = CubeInstance::new_coarse(&corner, LOD_CUBE_SIZE as f32, block);
+                    if block.is_transparent() {
+                        transparent_positions.push(instance);
+                    } else {
+                        positions.push(instance);
+                    }
+                }
+                continue;
+            }
             for layer in chunk.cubes() {
                 for row in layer {
                     for cube in row {
                         if let Some(c) = cube {
                             if c.is_visible() {
-                                positions.push(CubeInstance::new(c));
+                                let hidden_faces = self.hidden_faces(c);
+                                for mut instance in CubeInstance::instances(c) {
+                                    instance.set_hidden_faces(hidden_faces);
+                                    if c.block().takes_foliage_tint() {
+                                        instance.set_tint(self.biome_at(c.position()).foliage_tint);
+                                    }
+                                    if c.is_transparent() {
+                                        transparent_positions.push(instance);
+                                    } else {
+                                        positions.push(instance);
+                                    }
+                                }
                             }
                         }
                     }
@@ -122,63 +548,428 @@ impl World {
             }
         }
         if self.cubes_to_draw.is_none() {
-            self.cubes_to_draw = Some(CubesToDraw::new());
+            self.cubes_to_draw = Some(CubesToDraw::with_seed(self.meta.seed));
         }
         self.cubes_to_draw
             .as_mut()
             .unwrap()
-            .set_cube_to_draw(positions);
+            .set_cube_to_draw(positions, transparent_positions);
+    }
+
+    /// Draws the chunks farther than `distance` [world units] from the camera with super-cubes,
+    /// or all of them with every cube when there is no distance.
+    /// The cubes to draw are only rebuilt when a chunk changes of level of detail, in which case true is returned.
+    pub fn update_lod(&mut self, camera: &Vector3, distance: Option<f32>) -> bool {
+        let camera = *camera / self.scale;
+        let coarse_chunks: HashSet<ChunkCoord> = match distance {
+            Some(distance) => self
+                .chunks
+                .values()
+                .filter(|chunk| chunk.horizontal_distance_to(&camera) > distance / self.scale)
+                .map(|chunk| chunk.coord())
+                .collect(),
+            None => HashSet::new(),
+        };
+        if coarse_chunks == self.coarse_chunks {
+            return false;
+        }
+        self.coarse_chunks = coarse_chunks;
+        if self.cubes_to_draw.is_some() {
+            self.set_cubes_to_draw();
+        }
+        true
     }
 
     pub fn cube_to_draw(&self) -> &[CubeInstance] {
         self.cubes_to_draw.as_ref().unwrap().cubes_to_draw()
     }
 
-    /// Returns the OpenGL buffer with cubes to be drawn
-    /// If you want to have one cube drawn as 'selected', pass it in the argument `selected`
-    pub fn get_cubes_buffer(
+    pub fn transparent_cube_to_draw(&self) -> &[CubeInstance] {
+        self.cubes_to_draw.as_ref().unwrap().transparent_cubes_to_draw()
+    }
+
+    /// Returns the OpenGL buffer with the transparent cubes, sorted back-to-front from the camera
+    pub fn get_transparent_cubes_buffer(
         &mut self,
         display: &Display<WindowSurface>,
-        selected: Option<Cube>,
+        camera: &Vector3,
     ) -> VertexBuffer<CubeInstance> {
+        let camera = self.to_grid(camera);
         self.cubes_to_draw
             .as_mut()
             .unwrap()
-            .get_cubes_buffer(display, selected)
+            .get_transparent_cubes_buffer(display, &camera)
+    }
+
+    /// Updates the opacity of the chunks that are still fading in.
+    /// Once a chunk is fully opaque, it does not fade anymore.
+    pub fn update_fade_in(&mut self, now: Instant, duration: Duration) {
+        for chunk in self.chunks.values_mut() {
+            if let Some(load_time) = chunk.load_time() {
+                let alpha = fade_alpha(now, load_time, duration);
+                if let Some(cubes_to_draw) = self.cubes_to_draw.as_mut() {
+                    cubes_to_draw.set_chunk_alpha(chunk.coord(), alpha);
+                }
+                if alpha >= 1. {
+                    chunk.set_load_time(None);
+                }
+            }
+        }
+    }
+
+    /// Advances the animated textures to this time [s] of the animation clock
+    pub fn set_animation_time(&mut self, time: f32, fps: f32) {
+        if let Some(cubes_to_draw) = self.cubes_to_draw.as_mut() {
+            cubes_to_draw.set_animation_time(time, fps);
+        }
+    }
+
+    /// Faces of a cube that cannot be seen, as a bitmask.
+    /// An opaque cube hides the faces against the other opaque cubes, and against the bottom of the world.
+    /// A transparent cube hides the faces that touch a transparent cube of the same kind when its kind merges with itself:
+    /// drawing them would only show the inside of a body of water (or of a glass wall), see `Block::culls_same_neighbor`.
+    /// All the faces of the blocks that are not full cubes are drawn, most of them are inside of the cell.
+    fn hidden_faces(&self, cube: &Cube) -> u8 {
+        if !cube.shape().is_full() {
+            return 0;
+        }
+        let mut hidden_faces = 0;
+        for (pos, face) in Cube::neighbors_positions(*cube.position()).iter().zip(NEIGHBOR_FACES) {
+            let is_hidden = if cube.is_transparent() {
+                cube.block().culls_same_neighbor() && self.block_at(pos) == Some(*cube.block())
+            } else {
+                pos.y() < 0. || !self.is_position_free_or_transparent(pos)
+            };
+            if is_hidden {
+                hidden_faces |= 1 << face;
+            }
+        }
+        hidden_faces
+    }
+
+    /// Recomputes the hidden faces of the cubes at and around this position
+    fn update_hidden_faces_around(&mut self, at: Vector3) {
+        if self.cubes_to_draw.is_none() {
+            return;
+        }
+        let mut positions = Cube::neighbors_positions(at).to_vec();
+        positions.push(at);
+        for pos in positions {
+            if let Some(cube) = self.cube_at(pos).cloned() {
+                let hidden_faces = self.hidden_faces(&cube);
+                self.cubes_to_draw.as_mut().unwrap().set_hidden_faces(&pos, hidden_faces);
+            }
+        }
+    }
+
+    /// Returns the OpenGL buffer with cubes to be drawn.
+    /// The cube targeted by the player is drawn on its own, see `BlockHighlight`.
+    /// The opaque cubes of the `skipped` chunks are left out: they are drawn with their mesh, or not seen.
+    pub fn get_cubes_buffer(&mut self, display: &Display<WindowSurface>, skipped: &HashSet<ChunkCoord>) -> VertexBuffer<CubeInstance> {
+        self.cubes_to_draw.as_mut().unwrap().get_cubes_buffer(display, skipped)
     }
 
     pub fn number_cubes_rendered(&self) -> usize {
         self.cubes_to_draw.as_ref().unwrap().number_cubes_rendered()
     }
 
+    pub fn number_loaded_chunks(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// Memory taken by the cubes of the loaded chunks [byte]
+    pub fn chunks_memory(&self) -> usize {
+        self.chunks.len() * std::mem::size_of::<Chunk>()
+    }
+
     /// Returns the block at the given position
     pub fn block_at(&self, pos: &Vector3) -> Option<Block> {
-        for chunk in &self.chunks {
-            if chunk.is_in(pos) {
-                if let Some(cube) = chunk.cube_at(pos) {
-                    return Some(cube.block().clone());
-                }
-            }
-        }
-
-        None
+        self.chunk_containing(pos)?.cube_at(pos).map(|cube| cube.block().clone())
     }
     
+    /// Whether this position, in the world space, is inside water (used to tint the view of a submerged camera)
+    pub fn is_in_water(&self, pos: &Vector3) -> bool {
+        self.block_at(&self.to_grid(pos)) == Some(Block::WATER)
+    }
+
     /// Returns true if there is a cube at this position
     pub fn is_position_free_or_transparent(&self, pos: &Vector3) -> bool {
-        for chunk in &self.chunks {
-            if chunk.is_in(pos) {
-                if !chunk.is_position_free_or_transparent(pos) {
-                    return false;
+        self.chunk_containing(pos)
+            .map_or(true, |chunk| chunk.is_position_free_or_transparent(pos))
+    }
+
+    /// Returns true if no opaque cube lies strictly between the cubes of `a` and `b` (in the world space).
+    /// The cubes containing the two endpoints are not considered as obstacles.
+    pub fn has_line_of_sight(&self, a: &Vector3, b: &Vector3) -> bool {
+        let cells = cells_on_segment(&self.to_grid(a), &self.to_grid(b));
+        if cells.len() <= 2 {
+            return true;
+        }
+        cells[1..cells.len() - 1]
+            .iter()
+            .all(|cell| !self.block_at(cell).is_some_and(|block| !block.is_transparent()))
+    }
+
+    /// Walks the grid along the ray (in the world space) and returns the first cube it hits, up to `max_distance`.
+    /// With `through_transparent`, the transparent cubes (water, leaves) are crossed as if they were air.
+    pub fn raycast(&self, ray: &Ray, max_distance: f32, through_transparent: bool) -> Option<RayHit> {
+        let on_grid = Ray::new(self.to_grid(&ray.origin()), ray.direction());
+        on_grid
+            .cells(max_distance / self.scale)
+            .into_iter()
+            .find_map(|step| {
+                let cube = self.cube_at(step.cell)?;
+                if through_transparent && cube.is_transparent() {
+                    return None;
+                }
+                Some(RayHit {
+                    cube: *cube,
+                    normal: step.normal,
+                    distance: step.distance * self.scale,
+                    point: on_grid.origin() + on_grid.direction() * step.distance,
+                    direction: on_grid.direction(),
+                })
+            })
+    }
+
+    /// Returns the part of the movement `delta` that the box `aabb` can do before hitting a cube,
+    /// both in the world space. The axes are resolved one after the other (y first, then x and z),
+    /// so that a box blocked on one axis still slides along the others. A blocked box stops flush
+    /// against the cube.
+    pub fn resolve_aabb_movement(&self, aabb: &AABB, delta: &Vector3) -> Vector3 {
+        self.resolve_on_grid(&aabb.scaled(1. / self.scale), &(*delta / self.scale)) * self.scale
+    }
+
+    /// Same as `resolve_aabb_movement`, with the box and the movement given on the grid
+    pub fn resolve_on_grid(&self, aabb: &AABB, delta: &Vector3) -> Vector3 {
+        let mut aabb = aabb.clone();
+        let mut allowed = Vector3::empty();
+        for axis in [1, 0, 2] {
+            allowed[axis] = self.allowed_movement_along(&aabb, axis, delta[axis]);
+            let mut step = Vector3::empty();
+            step[axis] = allowed[axis];
+            aabb = aabb.translated(&step);
+        }
+        allowed
+    }
+
+    /// Height [world units] by which the box has to be raised to get over the blocks stopping its horizontal
+    /// movement `delta`, with the bottom of the box then flush with the top of the obstacle.
+    /// None if nothing stops the box, or if the obstacle is more than `max_height` cubes high
+    /// or there is no room above it (or above the box) to climb it.
+    pub fn obstruction_height(&self, aabb: &AABB, delta: &Vector3, max_height: u32) -> Option<f32> {
+        let aabb = aabb.scaled(1. / self.scale);
+        let delta = Vector3::new(delta.x(), 0., delta.z()) / self.scale;
+        let reach = |aabb: &AABB| {
+            let allowed = self.resolve_on_grid(aabb, &delta);
+            allowed.x().abs() + allowed.z().abs()
+        };
+        let blocked = reach(&aabb);
+        if blocked >= delta.x().abs() + delta.z().abs() {
+            return None;
+        }
+
+        // The obstacle is climbed half a cell at a time (the height of the slabs and of the steps of the stairs),
+        // from the ground the box stands on
+        let bottom = aabb.bottom();
+        let ground = ((bottom + 1e-3) * 2.).floor() / 2.;
+        (1..=2 * max_height).find_map(|halves| {
+            let lift = ground + halves as f32 / 2. - bottom;
+            let room = self.allowed_movement_along(&aabb, 1, lift);
+            if room < lift {
+                // Hitting the ceiling, higher cells cannot be reached either
+                return Some(None);
+            }
+            let lifted = aabb.translated(&Vector3::new(0., lift, 0.));
+            (reach(&lifted) > blocked + 1e-4).then_some(Some(lift * self.scale))
+        })
+        .flatten()
+    }
+
+    /// Movement of the box (on the grid) along one axis until the first block in its way.
+    /// The blocks stop the box with the boxes of their shape, see `BlockShape`.
+    fn allowed_movement_along(&self, aabb: &AABB, axis: usize, distance: f32) -> f32 {
+        // Tolerance for the faces that touch, a box lying on the floor does not collide with it
+        const EPSILON: f32 = 1e-4;
+        if distance == 0. {
+            return 0.;
+        }
+
+        // The cells covered by the box across the axis
+        let (u, v) = match axis {
+            0 => (1, 2),
+            1 => (0, 2),
+            _ => (0, 1),
+        };
+        let cells_across = |i: usize| {
+            let (min, max) = aabb.range(i);
+            (min + EPSILON).floor() as i32..=(max - EPSILON).ceil() as i32 - 1
+        };
+        let overlaps_across = |other: &AABB| {
+            [u, v].iter().all(|&i| {
+                let ((min, max), (other_min, other_max)) = (aabb.range(i), other.range(i));
+                other_min < max - EPSILON && other_max > min + EPSILON
+            })
+        };
+
+        // The layers of cells swept by the box, including the ones it is in since a shape can fill them partly
+        let (min, max) = aabb.range(axis);
+        let layers = if distance > 0. {
+            (max - EPSILON).floor() as i32..=(max + distance).floor() as i32
+        } else {
+            (min + distance).floor() as i32..=(min + EPSILON).floor() as i32
+        };
+        let mut allowed = distance;
+        for layer in layers {
+            for a in cells_across(u) {
+                for b in cells_across(v) {
+                    let mut cell = Vector3::empty();
+                    cell[axis] = layer as f32;
+                    cell[u] = a as f32;
+                    cell[v] = b as f32;
+                    let Some(cube) = self.cube_at(cell + Vector3::new(0.5, 0.5, 0.5)) else {
+                        continue;
+                    };
+                    if cell.y() < 0. || !cube.block().is_solid() {
+                        continue;
+                    }
+                    for shape_aabb in cube.shape().aabbs(&cell).iter().filter(|other| overlaps_across(other)) {
+                        let (other_min, other_max) = shape_aabb.range(axis);
+                        if distance > 0. && other_min >= max - EPSILON {
+                            allowed = allowed.min(other_min - max).max(0.);
+                        } else if distance < 0. && other_max <= min + EPSILON {
+                            allowed = allowed.max(other_max - min).min(0.);
+                        }
+                    }
                 }
             }
         }
-        true
+        allowed
+    }
+
+    /// Returns the action placing `block` when the player clicks on the cube `hit`.
+    /// * If `hit` is replaceable (water), the new block takes its cell
+    /// * Otherwise the block goes into the cell next to the face of `hit` crossed by the ray
+    ///
+    /// The block is turned after the face and the direction of the player, see `placed_block`.
+    /// The placement is rejected if the target cell holds a block that is not replaceable,
+    /// if it is above the build limit of the world, if the block cannot stand against the face,
+    /// or if the new block would overlap the bounding box of the player.
+    /// `eye` and `player_aabb` are given in the world space.
+    pub fn place_block(
+        &self,
+        hit: &Cube,
+        block: Block,
+        eye: Vector3,
+        direction: Vector3,
+        player_aabb: &AABB,
+    ) -> Result<Action, PlacementError> {
+        let eye = self.to_grid(&eye);
+        let at = if hit.block().is_replaceable() {
+            *hit.position()
+        } else {
+            hit.position_to_add_new_cube(eye, direction)
+                .map_err(|_| PlacementError::NoFaceHit)?
+        };
+        let point = eye + direction * hit.intersection_with(eye, direction).unwrap_or(0.);
+        let (block, metadata) = placed_block(block, &(at - *hit.position()), point.y(), &direction).ok_or(PlacementError::WrongFace)?;
+        self.check_placement(at, block, metadata, player_aabb)
+    }
+
+    /// Same as `place_block`, the block goes against the face hit by the ray (see `raycast`)
+    pub fn place_block_on(&self, hit: &RayHit, block: Block, player_aabb: &AABB) -> Result<Action, PlacementError> {
+        let at = if hit.cube.block().is_replaceable() {
+            *hit.cube.position()
+        } else if hit.normal.equals(&Vector3::empty()) {
+            // The ray starts inside of the cube, no face was crossed
+            return Err(PlacementError::NoFaceHit);
+        } else {
+            *hit.cube.position() + hit.normal
+        };
+        let (block, metadata) = placed_block(block, &hit.normal, hit.point.y(), &hit.direction).ok_or(PlacementError::WrongFace)?;
+        self.check_placement(at, block, metadata, player_aabb)
+    }
+
+    /// Checks that `block`, turned by `metadata`, can be added at `at`, see `place_block`.
+    /// The server checks the blocks placed by the players with it.
+    pub fn check_placement(&self, at: Vector3, block: Block, metadata: u8, player_aabb: &AABB) -> Result<Action, PlacementError> {
+        if at.y() > self.meta.build_limit as f32 {
+            return Err(PlacementError::AboveBuildLimit);
+        }
+
+        if let Some(existing) = self.block_at(&at) {
+            if !existing.is_replaceable() {
+                return Err(PlacementError::Occupied);
+            }
+        }
+
+        if block == Block::WHEATCROP && self.block_at(&(at - Vector3::unit_y())) != Some(Block::FARMLAND) {
+            return Err(PlacementError::NoFarmland);
+        }
+
+        let overlaps = |aabb: &AABB| aabb.scaled(self.scale).collides(player_aabb);
+        if block.is_solid() && block.shape(metadata).aabbs(&at).iter().any(overlaps) {
+            return Err(PlacementError::OverlapsPlayer);
+        }
+
+        Ok(Action::Add { at, block, metadata })
+    }
+
+    /// Turns the dirt or the grass hit into farmland, as done with a hoe. Nothing is tilled under another block.
+    pub fn till(&self, hit: &RayHit) -> Option<Action> {
+        self.till_at(*hit.cube.position())
+    }
+
+    /// Same as `till`, for the cube at this position
+    pub fn till_at(&self, at: Vector3) -> Option<Action> {
+        let tillable = matches!(self.block_at(&at), Some(Block::DIRT | Block::GRASS));
+        (tillable && self.block_at(&(at + Vector3::unit_y())).is_none())
+            .then_some(Action::Add { at, block: Block::FARMLAND, metadata: 0 })
+    }
+
+    /// Returns false for the cubes of the bedrock layers, at the bottom of the world
+    pub fn is_breakable(&self, at: &Vector3) -> bool {
+        at.y() > self.meta.bedrock_limit as f32
+    }
+
+    /// Destroys the cube at this position and returns its block.
+    /// Returns None, and leaves the world unchanged, if there is no cube or if it is bedrock.
+    /// If the game mode of the player breaking it drops items (survival), the items left by the block
+    /// (see `Block::harvest`) are announced with a `WorldEvent::ItemDropped`, as well as each stack of a chest.
+    /// The block only leaves its own items when it is broken with the right `tool` (see `Block::is_harvested_by`).
+    pub fn break_block(&mut self, at: Vector3, game_mode: GameMode, tool: Option<Tool>) -> Option<Block> {
+        if !self.is_breakable(&at) {
+            return None;
+        }
+        let block = self.block_at(&at)?;
+        let harvest = if block.is_harvested_by(tool) { block.harvest(self.cube_at(at)?.metadata()) } else { Vec::new() };
+        let content: Vec<ItemStack> = self.block_entity(&at)
+            .and_then(|entity| entity.container())
+            .map(|container| container.stacks().copied().collect())
+            .unwrap_or_default();
+        self.apply_action(&Action::Destroy { at });
+        if game_mode.drops_items() {
+            for stack in harvest.into_iter().map(|item| ItemStack::new(item, 1)).chain(content) {
+                self.events.push(WorldEvent::ItemDropped { pos: at, stack });
+            }
+        }
+        Some(block)
+    }
+
+    /// State of the cube at this position that does not fit in its cell (the items of a chest)
+    pub fn block_entity(&self, at: &Vector3) -> Option<&BlockEntity> {
+        self.chunk_containing(at)?.block_entity(at)
+    }
+
+    /// Returns the events that happened since the last call
+    pub fn take_events(&mut self) -> Vec<WorldEvent> {
+        std::mem::take(&mut self.events)
     }
 
     pub fn apply_action(&mut self, action: &Action) {
         match action {
             Action::Destroy { at } => {
+                self.block_updates.remove_level(at);
                 let revealed_cubes = self.destroy_cube(at.clone());
                 if self.cubes_to_draw.is_some() {
                     // Remove the cube from the rendered cube
@@ -187,10 +978,15 @@ impl World {
                     for cube in revealed_cubes {
                         self.cubes_to_draw.as_mut().unwrap().add_cube(&cube)
                     }
+                    self.update_hidden_faces_around(*at);
                 }
             }
-            Action::Add { at, block } => {
-                let (cubes_to_destroy, cube) = self.add_cube(at.clone(), block.clone());
+            Action::Add { at, block, metadata } => {
+                // A replaced block (water) is removed first, so that the neighbors and the rendering stay consistent
+                if self.cube_at(*at).is_some() {
+                    self.apply_action(&Action::Destroy { at: *at });
+                }
+                let (cubes_to_destroy, cube) = self.add_cube(at.clone(), block.clone(), *metadata);
                 if self.cubes_to_draw.is_some() {
                     // Add the cube from the rendered cube
                     self.cubes_to_draw.as_mut().unwrap().add_cube(&cube);
@@ -198,37 +994,496 @@ impl World {
                     for position in cubes_to_destroy {
                         self.cubes_to_draw.as_mut().unwrap().remove_cube(&position)
                     }
+                    self.update_hidden_faces_around(*at);
+                }
+            }
+            Action::SetBlockEntity { at, entity } => {
+                if let Some(chunk) = self.chunk_containing_mut(at) {
+                    chunk.set_block_entity(at, entity.clone());
                 }
             }
         }
+        match action {
+            // The state of a cube changes neither its neighbors nor the light
+            Action::SetBlockEntity { .. } => {}
+            Action::Destroy { at } | Action::Add { at, .. } => {
+                self.block_updates.schedule_around(at);
+                // The faces of the cubes next to the cell may be in the next chunk
+                for neighbor in Cube::neighbors_positions(*at).iter().chain([at]) {
+                    self.dirty_meshes.insert(Self::chunk_of(neighbor));
+                }
+                // The light may change farther than the next chunks
+                let lit = self.lighting.update_cell(at.to_cell(), &self.chunks);
+                self.dirty_meshes.extend(lit);
+            }
+        }
     }
 
-    fn cube_at_mut(&mut self, pos: Vector3) -> Option<&mut Cube> {
-        for chunk in &mut self.chunks {
-            if chunk.is_in(&pos) {
-                return chunk.cube_at_mut(&pos);
+    /// Sets how many cells the water flows away from its source
+    pub fn set_flow_distance(&mut self, flow_distance: u8) {
+        self.block_updates.set_flow_distance(flow_distance);
+    }
+
+    /// Level of the water at this position: the flow distance for a source, and one less at each cell
+    /// it flows to. Returns None if there is no water.
+    pub fn water_level(&self, at: &Vector3) -> Option<u8> {
+        if self.block_at(at) == Some(Block::WATER) {
+            Some(self.block_updates.level(at))
+        } else {
+            None
+        }
+    }
+
+    /// Processes the cells scheduled since the last tick: the water falls into the air below it,
+    /// or spreads on the cells next to it with a lower level.
+    /// The flowing water that is not fed anymore (its source was removed) dries up, one cell at each tick.
+    /// The water flows by one cell at each tick: the cells filled during a tick only flow at the next one.
+    /// The sand and gravel without support are removed and start falling, see `step_entities`.
+    /// The block entities are ticked too, the ones that changed are sent with a `Action::SetBlockEntity`.
+    /// Returns the actions applied on the world, to be sent to the players.
+    pub fn tick(&mut self) -> Vec<Action> {
+        let mut actions = Vec::new();
+        let mut filled = HashSet::new();
+        for at in self.block_updates.take_pending() {
+            if let Some(block) = self.block_at(&at).filter(|block| block.falls()) {
+                if self.can_fall_into(&(at - Vector3::unit_y())) {
+                    let action = Action::Destroy { at };
+                    self.apply_action(&action);
+                    actions.push(action);
+                    FallingBlock::spawn(&mut self.entities, block, at);
+                }
+                continue;
+            }
+            if self.block_at(&at) != Some(Block::WATER) || filled.contains(&at.to_cell()) {
+                continue;
+            }
+            let level = self.block_updates.level(&at);
+            if !self.block_updates.is_source(&at) && !self.is_water_fed(&at, level) {
+                let action = Action::Destroy { at };
+                self.apply_action(&action);
+                actions.push(action);
+                continue;
+            }
+            let below = at - Vector3::unit_y();
+            let targets = if self.is_air(&below) {
+                vec![(below, level)]
+            } else if level > 0 && self.block_at(&below) != Some(Block::WATER) {
+                [Vector3::unit_x(), Vector3::unit_z(), Vector3::unit_x().opposite(), Vector3::unit_z().opposite()]
+                    .iter()
+                    .map(|side| (at + *side, level - 1))
+                    .collect()
+            } else {
+                Vec::new()
+            };
+
+            for (target, level) in targets {
+                if self.is_air(&target) {
+                    let action = Action::Add { at: target, block: Block::WATER, metadata: 0 };
+                    self.apply_action(&action);
+                    self.block_updates.set_level(&target, level);
+                    filled.insert(target.to_cell());
+                    actions.push(action);
+                }
             }
         }
-        None
+        for chunk in self.chunks.values_mut() {
+            for (at, entity) in chunk.tick_block_entities() {
+                actions.push(Action::SetBlockEntity { at, entity });
+            }
+        }
+        actions
     }
 
-    pub fn cube_at(&self, pos: Vector3) -> Option<&Cube> {
-        for chunk in &self.chunks {
-            if chunk.is_in(&pos) {
-                return chunk.cube_at(&pos);
+    /// Picks a few random cells in each loaded chunk and lets their block change, see `random_tick_at`.
+    /// Returns the actions applied on the world, to be sent to the players.
+    pub fn random_tick(&mut self) -> Vec<Action> {
+        let mut coords: Vec<ChunkCoord> = self.chunks.keys().copied().collect();
+        coords.sort();
+        let mut actions = Vec::new();
+        for coord in coords {
+            for at in self.random_ticks.pick(coord) {
+                actions.extend(self.random_tick_at(at));
             }
         }
-        None
+        actions
+    }
+
+    /// The slow change of the block of this cell, applied on the world, if any:
+    /// * The grass covered by an opaque cube turns into dirt
+    /// * Otherwise it spreads to a random dirt next to it (one cell up or down), if nothing opaque covers the dirt
+    /// * The crops on farmland grow by one stage, if their cell is lit enough (see `MIN_CROP_LIGHT`)
+    pub fn random_tick_at(&mut self, at: Vector3) -> Option<Action> {
+        let block = self.block_at(&at).filter(|block| block.receives_random_ticks())?;
+        let covered = |world: &Self, at: &Vector3| {
+            world.block_at(&(*at + Vector3::unit_y())).is_some_and(|block| block.is_opaque_cube())
+        };
+        let action = match block {
+            Block::GRASS if covered(self, &at) => Action::Add { at, block: Block::DIRT, metadata: 0 },
+            Block::GRASS => {
+                let rng = self.random_ticks.rng();
+                let target = at + Vector3::newi(rng.gen_range(-1..=1), rng.gen_range(-1..=1), rng.gen_range(-1..=1));
+                if self.block_at(&target) != Some(Block::DIRT) || covered(self, &target) {
+                    return None;
+                }
+                Action::Add { at: target, block: Block::GRASS, metadata: 0 }
+            }
+            Block::WHEATCROP => {
+                let stage = self.cube_at(at)?.metadata();
+                let (sky, emitted) = self.light_at(&at);
+                let on_farmland = self.block_at(&(at - Vector3::unit_y())) == Some(Block::FARMLAND);
+                if stage >= MAX_CROP_STAGE || sky.max(emitted) < MIN_CROP_LIGHT || !on_farmland {
+                    return None;
+                }
+                Action::Add { at, block: Block::WHEATCROP, metadata: stage + 1 }
+            }
+            _ => return None,
+        };
+        self.apply_action(&action);
+        Some(action)
+    }
+
+    /// Whether a block can fall into this cell: it is in a loaded chunk and not solid
+    fn can_fall_into(&self, at: &Vector3) -> bool {
+        at.y() >= 0.
+            && self.chunk_containing(at).is_some()
+            && !self.block_at(at).is_some_and(|block| block.is_solid())
+    }
+
+    /// Moves the entities during `dt` seconds (see `EntityStore::step`), merges the item drops lying
+    /// next to each other and lets the arrows hit the entities on their way. The falling blocks that reach a solid block land on it and become cubes again.
+    /// Returns the actions applied on the world, to be sent to the players.
+    pub fn step_entities(&mut self, dt: f32) -> Vec<Action> {
+        let mut entities = std::mem::take(&mut self.entities);
+        let actions = entities.step(dt, self);
+        merge_drops(&mut entities);
+        strike(&mut entities);
+        self.entities = entities;
+        for action in &actions {
+            self.apply_action(action);
+        }
+        actions
+    }
+
+    pub fn entities(&self) -> &EntityStore {
+        &self.entities
+    }
+
+    pub fn entities_mut(&mut self) -> &mut EntityStore {
+        &mut self.entities
+    }
+
+    /// Now and then (`PIG_SPAWN_RATE` times per second), tries to spawn a pig on a random column of a loaded chunk,
+    /// as long as there are fewer than `MAX_PIGS` of them. The column has to be topped with grass, with room for
+    /// the pig above it. Returns the id of the pig, if one was spawned.
+    pub fn spawn_animals(&mut self, dt: f32) -> Option<EntityId> {
+        if self.chunks.is_empty() || self.entities.of_type::<Pig>().count() >= MAX_PIGS {
+            return None;
+        }
+        let rng = self.random_ticks.rng();
+        if rng.gen::<f32>() >= dt * PIG_SPAWN_RATE {
+            return None;
+        }
+        let index = rng.gen_range(0..self.chunks.len());
+        let (x, z) = (rng.gen_range(0..CHUNK_SIZE) as i32, rng.gen_range(0..CHUNK_SIZE) as i32);
+        let seed = rng.gen();
+
+        let coord = *self.chunks.keys().nth(index)?;
+        let top = self.surface(coord.0 + x, coord.1 + z)?;
+        if self.block_at(&top) != Some(Block::GRASS) {
+            return None;
+        }
+        Some(Pig::spawn(&mut self.entities, top + Vector3::unit_y(), seed))
+    }
+
+    /// At night (`NIGHT_DAYLIGHT`), now and then (`ZOMBIE_SPAWN_RATE` times per second) tries to spawn a zombie
+    /// on a random column around the player, whose bottom is at `player` (in cube coordinates), as long as there
+    /// are fewer than `MAX_ZOMBIES` of them. The cell above the column has to be dark, see `MAX_SPAWN_LIGHT`.
+    /// The zombies are all removed at dawn.
+    /// Returns the id of the zombie, if one was spawned.
+    pub fn spawn_monsters(&mut self, dt: f32, player: &Vector3, daylight: f32) -> Option<EntityId> {
+        if daylight > NIGHT_DAYLIGHT {
+            self.entities.retain_of_type::<Zombie>(|_, _| false);
+            return None;
+        }
+        if self.entities.of_type::<Zombie>().count() >= MAX_ZOMBIES {
+            return None;
+        }
+        let rng = self.random_ticks.rng();
+        if rng.gen::<f32>() >= dt * ZOMBIE_SPAWN_RATE {
+            return None;
+        }
+        let dx = rng.gen_range(-MAX_SPAWN_DISTANCE..=MAX_SPAWN_DISTANCE);
+        let dz = rng.gen_range(-MAX_SPAWN_DISTANCE..=MAX_SPAWN_DISTANCE);
+        if dx.abs().max(dz.abs()) < MIN_SPAWN_DISTANCE {
+            return None;
+        }
+
+        let at = self.surface(player.x().floor() as i32 + dx, player.z().floor() as i32 + dz)? + Vector3::unit_y();
+        let (sky, block) = self.light_at(&at);
+        let light = block.max((sky as f32 * daylight) as u8);
+        if light > MAX_SPAWN_LIGHT {
+            return None;
+        }
+        Some(Zombie::spawn(&mut self.entities, at))
+    }
+
+    /// The highest solid cube of the column, if there are two free cells above it for an entity to stand
+    fn surface(&self, x: i32, z: i32) -> Option<Vector3> {
+        let is_solid = |y: i32| self.block_at(&Vector3::newi(x, y, z)).is_some_and(|block| block.is_solid());
+        let top = (0..CHUNK_HEIGHT as i32).rev().find(|&y| is_solid(y))?;
+        (!is_solid(top + 1) && !is_solid(top + 2)).then_some(Vector3::newi(x, top, z))
+    }
+
+    /// Whether the flowing water of this cell still comes from somewhere: the water falling from above,
+    /// or the water with a higher level next to it, as long as it spreads (it is not falling itself)
+    fn is_water_fed(&self, at: &Vector3, level: u8) -> bool {
+        if self.block_at(&(*at + Vector3::unit_y())) == Some(Block::WATER) {
+            return true;
+        }
+        [Vector3::unit_x(), Vector3::unit_z(), Vector3::unit_x().opposite(), Vector3::unit_z().opposite()]
+            .iter()
+            .map(|side| *at + *side)
+            .any(|next| {
+                let below = next - Vector3::unit_y();
+                self.block_at(&next) == Some(Block::WATER)
+                    && self.block_updates.level(&next) > level
+                    && !self.is_air(&below)
+                    && self.block_at(&below) != Some(Block::WATER)
+            })
+    }
+
+    /// Returns true if this cell is empty and inside a loaded chunk
+    fn is_air(&self, at: &Vector3) -> bool {
+        at.y() >= 0.
+            && at.y() < CHUNK_HEIGHT as f32
+            && self.chunk_containing(at).is_some()
+            && self.block_at(at).is_none()
+    }
+
+    /// Sets all the cells of the box between `min` and `max` (included, in cube coordinates) to `block`.
+    /// With `only_replace`, only the cells currently holding this block are changed, so that
+    /// the structures made of other blocks are left untouched.
+    /// Cells outside of the loaded chunks are ignored.
+    ///
+    /// Unlike `apply_action`, the cells are all changed first, and the visibility and the cubes to draw
+    /// are only recomputed once at the end. Returns the chunks that were modified.
+    pub fn fill_region(&mut self, min: Vector3, max: Vector3, block: Block, only_replace: Option<Block>) -> Vec<ChunkCoord> {
+        let min = min.to_cube_coordinates();
+        let max = max.to_cube_coordinates();
+        let mut dirty_chunks: Vec<ChunkCoord> = Vec::new();
+
+        for x in min.x() as i32..=max.x() as i32 {
+            for y in min.y().max(0.) as i32..=max.y() as i32 {
+                for z in min.z() as i32..=max.z() as i32 {
+                    let at = Vector3::new(x as f32, y as f32, z as f32);
+                    let coord = Self::chunk_of(&at);
+                    let Some(chunk) = self.chunks.get_mut(&coord) else {
+                        continue;
+                    };
+
+                    let current = chunk.cube_at(&at).map(|cube| *cube.block());
+                    if current == Some(block) {
+                        continue;
+                    }
+                    if only_replace.is_some() && current != only_replace {
+                        continue;
+                    }
+
+                    // The neighbors are counted again once the whole region is filled
+                    if chunk.add_cube(at, block, 0).is_some() && !dirty_chunks.contains(&coord) {
+                        dirty_chunks.push(coord);
+                    }
+                }
+            }
+        }
+
+        if !dirty_chunks.is_empty() {
+            self.compute_visible_cubes_in(&dirty_chunks);
+            if self.cubes_to_draw.is_some() {
+                self.set_cubes_to_draw();
+            }
+            for coord in &dirty_chunks {
+                self.mark_meshes_dirty_around(*coord);
+            }
+            self.relight_chunks(&dirty_chunks);
+        }
+        dirty_chunks
+    }
+
+    /// Stamps the structure with its anchor at `anchor` (in cube coordinates), rotated around the vertical axis.
+    /// The existing blocks are replaced only with `overwrite`, otherwise these cells are skipped.
+    /// If any block falls outside of the loaded chunks, nothing is placed.
+    ///
+    /// As in `fill_region`, the visibility and the cubes to draw are recomputed once at the end.
+    /// Returns the chunks that were modified.
+    pub fn place_structure(
+        &mut self,
+        structure: &Structure,
+        anchor: Vector3,
+        rotation: StructureRotation,
+        overwrite: bool,
+    ) -> Result<Vec<ChunkCoord>, PlacementError> {
+        let anchor = anchor.to_cube_coordinates();
+        let cells: Vec<(Vector3, Block)> = structure
+            .rotated_blocks(rotation)
+            .into_iter()
+            .map(|([x, y, z], block)| (anchor + Vector3::newi(x, y, z), block))
+            .collect();
+
+        // Check everything first, so that a structure is never partially placed
+        let in_bounds = |at: &Vector3| {
+            at.y() >= 0.
+                && at.y() < CHUNK_HEIGHT as f32
+                && self.chunk_containing(at).is_some()
+        };
+        if !cells.iter().all(|(at, _)| in_bounds(at)) {
+            return Err(PlacementError::OutOfBounds);
+        }
+
+        let mut dirty_chunks: Vec<ChunkCoord> = Vec::new();
+        for (at, block) in cells {
+            let coord = Self::chunk_of(&at);
+            let chunk = self.chunks.get_mut(&coord).unwrap();
+            if !overwrite && chunk.cube_at(&at).is_some() {
+                continue;
+            }
+            // The neighbors are counted again once the whole structure is placed
+            if chunk.add_cube(at, block, 0).is_some() && !dirty_chunks.contains(&coord) {
+                dirty_chunks.push(coord);
+            }
+        }
+
+        if !dirty_chunks.is_empty() {
+            self.compute_visible_cubes_in(&dirty_chunks);
+            if self.cubes_to_draw.is_some() {
+                self.set_cubes_to_draw();
+            }
+            for coord in &dirty_chunks {
+                self.mark_meshes_dirty_around(*coord);
+            }
+            self.relight_chunks(&dirty_chunks);
+        }
+        Ok(dirty_chunks)
+    }
+
+    /// Marks the mesh of this chunk, and of the ones next to it, to be built again
+    fn mark_meshes_dirty_around(&mut self, coord: ChunkCoord) {
+        let s = CHUNK_SIZE as i32;
+        for (dx, dz) in [(0, 0), (1, 0), (-1, 0), (0, 1), (0, -1)] {
+            self.dirty_meshes.insert((coord.0 + dx * s, coord.1 + dz * s));
+        }
+    }
+
+    /// Lights these chunks again from scratch, and marks the meshes whose light changed.
+    /// Used when many cells changed at once, instead of updating the light cell by cell.
+    fn relight_chunks(&mut self, coords: &[ChunkCoord]) {
+        for coord in coords {
+            self.lighting.remove_chunk(*coord);
+        }
+        for coord in coords {
+            let lit = self.lighting.add_chunk(*coord, &self.chunks);
+            self.dirty_meshes.extend(lit);
+        }
+    }
+
+    /// Sky and block light of the cell at this position (in cube coordinates), from 0 to `lighting::MAX_LIGHT`
+    pub fn light_at(&self, pos: &Vector3) -> (u8, u8) {
+        self.lighting.light_at(pos.to_cell())
+    }
+
+    /// Copies of the light of this chunk and of the chunks next to it, to build its mesh away from the world
+    pub fn light_around(&self, coord: ChunkCoord) -> Vec<ChunkLight> {
+        let s = CHUNK_SIZE as i32;
+        [(0, 0), (1, 0), (-1, 0), (0, 1), (0, -1)]
+            .iter()
+            .filter_map(|(dx, dz)| self.lighting.chunk((coord.0 + dx * s, coord.1 + dz * s)).cloned())
+            .collect()
+    }
+
+    /// Marks the mesh of every chunk to be built again
+    pub fn mark_all_meshes_dirty(&mut self) {
+        self.dirty_meshes.extend(self.chunks.keys().copied());
+    }
+
+    /// Returns the chunks whose mesh changed since the last call.
+    /// Some of them may not be loaded, their mesh is to be dropped.
+    pub fn take_dirty_meshes(&mut self) -> Vec<ChunkCoord> {
+        self.dirty_meshes.drain().collect()
+    }
+
+    /// Copies of this chunk and of the loaded chunks next to it, all what is needed to build its mesh
+    /// away from the world (see `greedy_mesh_in`). None if it is not loaded.
+    pub fn chunk_with_neighbors(&self, coord: ChunkCoord) -> Option<(Chunk, Vec<Chunk>)> {
+        let chunk = self.chunks.get(&coord)?.clone();
+        let s = CHUNK_SIZE as i32;
+        let neighbors = [(1, 0), (-1, 0), (0, 1), (0, -1)]
+            .iter()
+            .filter_map(|(dx, dz)| self.chunks.get(&(coord.0 + dx * s, coord.1 + dz * s)).cloned())
+            .collect();
+        Some((chunk, neighbors))
+    }
+
+    /// Builds the mesh of the opaque cubes of this chunk, None if it is not loaded.
+    /// The faces against the cubes of the next chunks are left out.
+    pub fn mesh_chunk(&self, coord: ChunkCoord) -> Option<ChunkMesh> {
+        let chunk = self.chunks.get(&coord)?;
+        // The bottommost layer is never seen from below
+        let is_exposed = |pos: &Vector3| pos.y() >= 0. && self.is_position_free_or_transparent(pos);
+        Some(greedy_mesh(chunk, is_exposed, |pos| self.light_at(pos), |pos| self.biome_at(pos).grass_tint))
+    }
+
+    /// Opacity of this chunk while it fades in, see `update_fade_in`
+    pub fn chunk_alpha(&self, coord: ChunkCoord, now: Instant, duration: Duration) -> f32 {
+        self.chunks
+            .get(&coord)
+            .and_then(|chunk| chunk.load_time())
+            .map_or(1., |load_time| fade_alpha(now, load_time, duration))
+    }
+
+    /// Chunks entirely outside of the frustum (given in the world space), they do not need to be drawn
+    pub fn chunks_outside(&self, frustum: &Frustum) -> HashSet<ChunkCoord> {
+        self.chunks
+            .values()
+            .filter(|chunk| !frustum.intersects_aabb(&chunk.aabb().scaled(self.scale)))
+            .map(|chunk| chunk.coord())
+            .collect()
+    }
+
+    /// Returns true if this chunk is drawn with super-cubes
+    pub fn is_coarse(&self, coord: &ChunkCoord) -> bool {
+        self.coarse_chunks.contains(coord)
+    }
+
+    fn cube_at_mut(&mut self, pos: Vector3) -> Option<&mut Cube> {
+        self.chunk_containing_mut(&pos)?.cube_at_mut(&pos)
+    }
+
+    pub fn cube_at(&self, pos: Vector3) -> Option<&Cube> {
+        self.chunk_containing(&pos)?.cube_at(&pos)
+    }
+
+    /// Whether the chunk in which this position (on the grid) falls is loaded
+    pub fn is_loaded(&self, pos: &Vector3) -> bool {
+        self.chunk_containing(pos).is_some()
+    }
+
+    /// The loaded chunk in which this position (on the grid) falls
+    fn chunk_containing(&self, pos: &Vector3) -> Option<&Chunk> {
+        self.chunks.get(&Self::chunk_of(pos)).filter(|chunk| chunk.is_in(pos))
+    }
+
+    fn chunk_containing_mut(&mut self, pos: &Vector3) -> Option<&mut Chunk> {
+        self.chunks.get_mut(&Self::chunk_of(pos)).filter(|chunk| chunk.is_in(pos))
     }
 
     /// Adds a cube and then recomputes the visibility of the affected cubes (neighbors)
     /// Return the cube that need to be rendered
     /// and the position of the rendered cube that need to be destroyed
-    fn add_cube(&mut self, at: Vector3, block: Block) -> (Vec<Vector3>, Cube) {
+    fn add_cube(&mut self, at: Vector3, block: Block, metadata: u8) -> (Vec<Vector3>, Cube) {
         // For all the neighbors positions, increase their internal counter
         let mut count = 0;
         let mut to_hide = Vec::new();
-        if !block.is_transparent() {
+        if block.is_opaque_cube() {
             for pos in Cube::neighbors_positions(at) {
                 // Toggle this position
                 if let Some(cube_to_toggle) = self.cube_at_mut(pos) {
@@ -242,33 +1497,29 @@ impl World {
                 }
             }
         }
-        self.add_cube_unsafe(at, block, count);
+        self.add_cube_unsafe(at, block, count, metadata);
         (to_hide, self.cube_at_mut(at).unwrap().clone())
     }
 
     /// Adds a cube without recomputing the visibility
-    fn add_cube_unsafe(&mut self, at: Vector3, block: Block, neighbors: u8) {
-        for chunk in &mut self.chunks {
-            if chunk.is_in(&at) {
-                chunk.add_cube(at, block, neighbors);
-            }
+    fn add_cube_unsafe(&mut self, at: Vector3, block: Block, neighbors: u8, metadata: u8) {
+        if let Some(chunk) = self.chunk_containing_mut(&at) {
+            chunk.add_cube(at, block, neighbors);
+            chunk.set_metadata(&at, metadata);
         }
     }
 
     /// Destroy a cube and return the neighboring cubes that need to be rendered
     fn destroy_cube(&mut self, at: Vector3) -> Vec<Cube> {
         // Find the chunk where the cube is located
-        let mut chunk_index = 0;
-        for i in 0..self.chunks.len() {
-            if self.chunks[i].is_in(&at) {
-                chunk_index = i;
-                break;
-            }
-        }
+        let Some(chunk) = self.chunk_containing(&at) else {
+            return Vec::new();
+        };
+        let coord = chunk.coord();
 
         let mut cubes_to_reveal = Vec::new();
-        // Mark all the neighbors cube as visible
-        if let Some(_cube) = self.chunks[chunk_index].cube_at(&at) {
+        // Mark all the neighbors cube as visible, the cubes which do not hide their neighbors were not counted by them
+        if chunk.cube_at(&at).is_some_and(|cube| cube.block().is_opaque_cube()) {
             for pos in Cube::neighbors_positions(at) {
                 if let Some(cube_to_toggle) = self.cube_at_mut(pos) {
                     // If the cube was not visible before, add it
@@ -281,31 +1532,54 @@ impl World {
         }
 
         // Shouldn't this be inside the if let ?
-        self.chunks[chunk_index].destroy_cube(at);
+        self.chunks.get_mut(&coord).unwrap().destroy_cube(at);
         cubes_to_reveal
     }
 
     #[cfg(test)]
     fn visible_cubes_count(&self) -> usize {
         self.chunks
-            .iter()
+            .values()
             .map(|chunk| chunk.visible_cube_count())
             .sum()
     }
 
     /// Goes through all the cubes in the world, and sets whether the cube is touching air.
     fn compute_visible_cubes(&mut self) {
+        let coords: Vec<ChunkCoord> = self.chunks.keys().copied().collect();
+        self.compute_visible_cubes_in(&coords);
+    }
+
+    /// Same as `compute_visible_cubes`, restricted to the given chunks.
+    /// The borders of the chunks next to them are recomputed as well, since they touch the changed cubes.
+    fn compute_visible_cubes_in(&mut self, coords: &[ChunkCoord]) {
+        let s = CHUNK_SIZE as i32;
+        let is_changed = |coord: ChunkCoord| coords.contains(&coord);
+        let is_next_to_changed = |coord: ChunkCoord| {
+            is_changed(coord)
+                || is_changed((coord.0 + s, coord.1))
+                || is_changed((coord.0 - s, coord.1))
+                || is_changed((coord.0, coord.1 + s))
+                || is_changed((coord.0, coord.1 - s))
+        };
+
         // 1. First pass inside each chunk
-        for chunk in &mut self.chunks {
-            chunk.compute_visible_cubes();
+        for chunk in self.chunks.values_mut() {
+            if is_changed(chunk.coord()) {
+                chunk.compute_visible_cubes();
+            }
         }
 
         // 2. Handle the borders of each chunk
-        for i in 0..self.chunks.len() {
-            let border = self.chunks[i].border();
+        let coords: Vec<ChunkCoord> = self.chunks.keys().copied().collect();
+        for coord in coords {
+            if !is_next_to_changed(coord) {
+                continue;
+            }
+            let border = self.chunks[&coord].border();
             for index in border {
                 // Count the number of neighbors of this cube
-                let mut count = if let Some(cube_at_border) = self.chunks[i].cube_at_index(index) {
+                let mut count = if let Some(cube_at_border) = self.chunks[&coord].cube_at_index(index) {
                     let neighbors = Cube::neighbors_positions(cube_at_border.position().clone());
                     let count = neighbors
                         .iter()
@@ -323,7 +1597,7 @@ impl World {
 
                 // Set it
                 // You need to do this separatly than the previous block.
-                if let Some(cube_at_border) = self.chunks[i].cube_at_index_mut(index) {
+                if let Some(cube_at_border) = self.chunks.get_mut(&coord).unwrap().cube_at_index_mut(index) {
                     cube_at_border.set_n_neighbors(count);
                 }
             }
@@ -334,17 +1608,23 @@ impl World {
 impl World {
     fn to_json(&self) -> String {
         // Provide all the chunks corner
-        let chunk_corners: Vec<[f32; 2]> = self.chunks.iter().map(|chunk| chunk.corner()).collect();
+        let chunk_corners: Vec<[f32; 2]> = self.chunks.values().map(|chunk| chunk.corner()).collect();
 
         // Provide all the cubes, sorted by kind
         let mut all_cubes = get_serialize_container();
-        for chunk in &self.chunks {
+        for chunk in self.chunks.values() {
             serialize_one_chunk(&mut all_cubes, chunk);
         }
 
+        let block_entities = self.chunks.values()
+            .flat_map(|chunk| chunk.block_entities().map(|(at, entity)| (at, entity.clone())))
+            .collect();
+
         let world = SerializedWorld {
+            meta: Some(self.meta.clone()),
             chunk_corners,
             cubes_by_kind: all_cubes,
+            block_entities,
         };
 
         serde_json::to_string(&world).unwrap()
@@ -354,38 +1634,126 @@ impl World {
         // If we end up with stack-overflows, we could not read the entire file but instead provide the reader.
         let serialized_world: SerializedWorld = serde_json::from_str(data.as_str()).unwrap();
 
-        // First, build all the chunks
-        let mut chunks = Vec::new();
-        for corner in serialized_world.chunk_corners {
-            chunks.push(Chunk::new(corner));
+        // First, build all the chunks
+        let mut chunks = HashMap::new();
+        for corner in serialized_world.chunk_corners {
+            let chunk = Chunk::new(corner);
+            chunks.insert(chunk.coord(), chunk);
+        }
+
+        // Build the world
+        let mut world = Self {
+            chunks,
+            cubes_to_draw: None,
+            scale: 1.,
+            meta: serialized_world.meta.unwrap_or_default(),
+            gravity_direction: Vector3::new(0., -1., 0.),
+            coarse_chunks: HashSet::new(),
+            block_updates: BlockUpdates::new(),
+            events: Vec::new(),
+            dirty_meshes: HashSet::new(),
+            lighting: Lighting::new(),
+            entities: EntityStore::new(),
+            random_ticks: RandomTicks::new(rand::random()),
+        };
+
+        // Fill all the chunks by building all the cubes
+        // The worlds saved before a block existed have no cubes for it
+        for block_kind in Block::iter() {
+            let cubes = serialized_world.cubes_by_kind.get(&block_kind).into_iter().flatten();
+            for cube_data in cubes {
+                let x = cube_data[0] as f32;
+                let y = cube_data[1] as f32;
+                let z = cube_data[2] as f32;
+                let (neighbors, metadata) = unpack_cube_state(cube_data[3]);
+                world.add_cube_unsafe(Vector3::new(x, y, z), block_kind, neighbors, metadata);
+            }
+        }
+        world.load_block_entities(serialized_world.block_entities);
+
+        world
+    }
+
+    /// Gives back their saved state to the cubes, once they are all loaded
+    fn load_block_entities(&mut self, block_entities: Vec<([i32; 3], BlockEntity)>) {
+        for (at, entity) in block_entities {
+            let at = Vector3::new(at[0] as f32, at[1] as f32, at[2] as f32);
+            if let Some(chunk) = self.chunk_containing_mut(&at) {
+                chunk.set_block_entity(&at, entity);
+            }
+        }
+    }
+
+    fn from_json_parallel(data: String, threads: usize) -> Self {
+        let serialized_world: SerializedWorld = serde_json::from_str(data.as_str()).unwrap();
+
+        let corners = &serialized_world.chunk_corners;
+        let index_of: HashMap<ChunkCoord, usize> = corners.iter()
+            .enumerate()
+            .map(|(i, corner)| ((corner[0] as i32, corner[1] as i32), i))
+            .collect();
+
+        // Sort the cubes by chunk, so that every chunk can be filled independently
+        let mut cubes_by_chunk: Vec<Vec<(Block, [i32; 4])>> = vec![Vec::new(); corners.len()];
+        for block_kind in Block::iter() {
+            for cube_data in serialized_world.cubes_by_kind.get(&block_kind).into_iter().flatten() {
+                let at = Vector3::new(cube_data[0] as f32, cube_data[1] as f32, cube_data[2] as f32);
+                if let Some(&i) = index_of.get(&Self::chunk_of(&at)) {
+                    cubes_by_chunk[i].push((block_kind, *cube_data));
+                }
+            }
+        }
+
+        let workers = ChunkWorkers::new(threads);
+        for (corner, cubes) in corners.iter().zip(cubes_by_chunk) {
+            workers.submit(ChunkJob::Load { corner: *corner, cubes });
         }
+        let chunks: HashMap<ChunkCoord, Chunk> = (0..corners.len())
+            .filter_map(|_| match workers.recv()? {
+                ChunkResult::Loaded(chunk) => Some((chunk.coord(), chunk)),
+                _ => None,
+            })
+            .collect();
 
-        // Build the world
         let mut world = Self {
             chunks,
             cubes_to_draw: None,
+            scale: 1.,
+            meta: serialized_world.meta.unwrap_or_default(),
+            gravity_direction: Vector3::new(0., -1., 0.),
+            coarse_chunks: HashSet::new(),
+            block_updates: BlockUpdates::new(),
+            events: Vec::new(),
+            dirty_meshes: HashSet::new(),
+            lighting: Lighting::new(),
+            entities: EntityStore::new(),
+            random_ticks: RandomTicks::new(rand::random()),
         };
+        world.load_block_entities(serialized_world.block_entities);
+        world
+    }
 
-        // Fill all the chunks by building all the cubes
-        for block_kind in Block::iter() {
-            let cubes = serialized_world.cubes_by_kind.get(&block_kind).unwrap();
-            for cube_data in cubes {
-                let x = cube_data[0] as f32;
-                let y = cube_data[1] as f32;
-                let z = cube_data[2] as f32;
-                let neighbors = cube_data[3] as u8;
-                world.add_cube_unsafe(Vector3::new(x, y, z), block_kind, neighbors);
+    /// Hash of the cubes of all the chunks, regardless of the order in which they were loaded
+    pub fn content_hash(&self) -> u64 {
+        let mut chunks: Vec<&Chunk> = self.chunks.values().collect();
+        chunks.sort_by_key(|chunk| chunk.coord());
+        let mut hasher = DefaultHasher::new();
+        for chunk in chunks {
+            chunk.coord().hash(&mut hasher);
+            for cube in chunk.cubes_iter() {
+                cube.map(|c| (c.block_id(), c.n_neighbors())).hash(&mut hasher);
             }
         }
-
-        world
+        hasher.finish()
     }
 }
 
+// The arguments are in the world space, the chunks collide on the grid
 impl Collidable for World {
     fn collides(&self, aabb: &AABB) -> bool {
-        for chunk in &self.chunks {
-            if chunk.collides(aabb) {
+        let aabb = aabb.scaled(1. / self.scale);
+        for chunk in self.chunks.values() {
+            if chunk.collides(&aabb) {
                 return true;
             }
         }
@@ -401,12 +1769,18 @@ impl Collidable for World {
         target: &AABB,
         velocity: &Vector3,
     ) -> Option<CollisionData> {
+        // Scaling everything by the same factor does not change the time of collision
+        let position = &Position::new(self.to_grid(&position.pos()), position.yaw(), position.pitch());
+        let aabb = &aabb.scaled(1. / self.scale);
+        let target = &target.scaled(1. / self.scale);
+        let velocity = &(*velocity / self.scale);
+
         // find with which chunks it is colliding
         let mut acc_time = f32::MAX;
         let mut acc_normal = Vector3::empty();
 
         // TODO be smarter
-        for chunk in &self.chunks {
+        for chunk in self.chunks.values() {
             if chunk.is_near_player(&position.pos()) {
                 if let Some(CollisionData { time, normal }) =
                     chunk.collision_time(position, aabb, target, velocity)
@@ -432,15 +1806,30 @@ impl Collidable for World {
 
 #[cfg(test)]
 mod tests {
+    use crate::aabb::AABB;
     use crate::actions::Action;
-    use crate::block_kind::Block;
+    use crate::block_entity::{BlockEntity, Container};
+    use crate::block_kind::{Block, MAX_CROP_STAGE};
     use crate::block_kind::Block::GRASS;
-    use crate::chunk::{Chunk, CHUNK_FLOOR, CHUNK_SIZE};
+    use crate::block_shape::{BlockShape, Facing};
+    use crate::chunk::{Chunk, CHUNK_FLOOR, CHUNK_HEIGHT, CHUNK_SIZE, LOD_CUBE_SIZE};
     use crate::collidable::Collidable;
     use crate::entity::humanoid::humanoid_aabb;
+    use crate::entity::pig::{Pig, MAX_PIGS, PIG_SPAWN_RATE};
+    use crate::entity::zombie::{Zombie, MAX_ZOMBIES, MIN_SPAWN_DISTANCE, ZOMBIE_SPAWN_RATE};
+    use crate::falling_block::FallingBlock;
+    use crate::game_mode::GameMode;
+    use crate::player::{FOREHEAD, PLAYER_HEIGHT};
+    use crate::player_items::ItemStack;
+    use crate::tool::Tool;
+    use crate::camera::perspective_matrix;
+    use crate::primitives::frustum::Frustum;
     use crate::primitives::position::Position;
+    use crate::primitives::ray::Ray;
     use crate::primitives::vector::Vector3;
-    use crate::world::World;
+    use crate::random_ticks::RandomTicks;
+    use crate::structure::{Structure, StructureRotation};
+    use crate::world::{PlacementError, RayHit, World, WorldEvent};
     use crate::world_generation::world_generator::WorldGenerator;
     use std::time::Instant;
 
@@ -449,8 +1838,8 @@ mod tests {
         let mut world = World::empty();
         // Adding one chunk
         let s = CHUNK_SIZE as f32;
-        world.chunks.push(Chunk::new_for_demo([-s, 0.], 0));
-        world.chunks[0].print_all_cubes();
+        world.chunks.insert((-(CHUNK_SIZE as i32), 0), Chunk::new_for_demo([-s, 0.], 0));
+        world.chunks[&(-(CHUNK_SIZE as i32), 0)].print_all_cubes();
 
         // Assert some positions
         assert!(!world.is_position_free_or_transparent(&Vector3::new(-4.0, CHUNK_FLOOR as f32 - 1.5, 4.0)));
@@ -464,7 +1853,7 @@ mod tests {
     fn test_chunk_collision_2() {
         let mut world = World::empty();
         // Adding one chunk
-        world.chunks.push(Chunk::new_for_demo([0., 0.], 0));
+        world.chunks.insert((0, 0), Chunk::new_for_demo([0., 0.], 0));
         assert!(world.is_position_free_or_transparent(&Vector3::new(4.0, 10.2, 3.0)));
     }
 
@@ -481,8 +1870,8 @@ mod tests {
         chunk2.fill_layer(1, GRASS);
         chunk2.fill_layer(2, GRASS);
 
-        world.chunks.push(chunk1);
-        world.chunks.push(chunk2);
+        world.chunks.insert(chunk1.coord(), chunk1);
+        world.chunks.insert(chunk2.coord(), chunk2);
 
         let count1 = world.visible_cubes_count();
         world.compute_visible_cubes();
@@ -505,8 +1894,8 @@ mod tests {
         chunk2.fill_layer(1, GRASS);
         chunk2.fill_layer(2, GRASS);
 
-        world.chunks.push(chunk1);
-        world.chunks.push(chunk2);
+        world.chunks.insert(chunk1.coord(), chunk1);
+        world.chunks.insert(chunk2.coord(), chunk2);
 
         let count1 = world.visible_cubes_count();
         world.compute_visible_cubes();
@@ -523,7 +1912,7 @@ mod tests {
         chunk.fill_layer(0, GRASS);
         chunk.fill_layer(1, GRASS);
         chunk.fill_layer(2, GRASS);
-        world.chunks.push(chunk);
+        world.chunks.insert(chunk.coord(), chunk);
         world.compute_visible_cubes();
 
         let top = Vector3::new(4., 2., 4.);
@@ -532,13 +1921,13 @@ mod tests {
 
         // Initially, the cube in the middle is not supposed to be visible
         // Note that the bottommost layer is not showed
-        assert_eq!(world.chunks[0].cube_at(&top).unwrap().is_visible(), true);
+        assert_eq!(world.chunks[&(0, 0)].cube_at(&top).unwrap().is_visible(), true);
         assert_eq!(
-            world.chunks[0].cube_at(&middle).unwrap().is_visible(),
+            world.chunks[&(0, 0)].cube_at(&middle).unwrap().is_visible(),
             false
         );
         assert_eq!(
-            world.chunks[0].cube_at(&bottom).unwrap().is_visible(),
+            world.chunks[&(0, 0)].cube_at(&bottom).unwrap().is_visible(),
             false
         );
 
@@ -546,28 +1935,28 @@ mod tests {
         world.apply_action(&Action::Destroy { at: top });
 
         // Assert the cube in the middle is now visible
-        assert_eq!(world.chunks[0].cube_at(&middle).unwrap().is_visible(), true);
+        assert_eq!(world.chunks[&(0, 0)].cube_at(&middle).unwrap().is_visible(), true);
 
         // But so far, the sides of `bottom` should not be visible yet
         let one_side = middle + Vector3::unit_x();
         let another_side = middle + Vector3::unit_z();
         assert_eq!(
-            world.chunks[0].cube_at(&one_side).unwrap().is_visible(),
+            world.chunks[&(0, 0)].cube_at(&one_side).unwrap().is_visible(),
             false
         );
         assert_eq!(
-            world.chunks[0].cube_at(&another_side).unwrap().is_visible(),
+            world.chunks[&(0, 0)].cube_at(&another_side).unwrap().is_visible(),
             false
         );
 
         // But we if delete the middle block, the sides get in contact with air, so they are supposed to be visible.
         world.apply_action(&Action::Destroy { at: middle });
         assert_eq!(
-            world.chunks[0].cube_at(&one_side).unwrap().is_visible(),
+            world.chunks[&(0, 0)].cube_at(&one_side).unwrap().is_visible(),
             true
         );
         assert_eq!(
-            world.chunks[0].cube_at(&another_side).unwrap().is_visible(),
+            world.chunks[&(0, 0)].cube_at(&another_side).unwrap().is_visible(),
             true
         );
     }
@@ -579,7 +1968,7 @@ mod tests {
         chunk.fill_layer(0, GRASS);
         chunk.fill_layer(1, GRASS);
         chunk.fill_layer(2, GRASS);
-        world.chunks.push(chunk);
+        world.chunks.insert(chunk.coord(), chunk);
         world.compute_visible_cubes();
 
         let above = Vector3::new(4., 3., 4.);
@@ -589,11 +1978,12 @@ mod tests {
         world.apply_action(&Action::Add {
             at: above,
             block: Block::COBBELSTONE,
+            metadata: 0,
         });
 
         // Assert the visibility: the block 'top' should not be rendered anymore
-        assert_eq!(world.chunks[0].cube_at(&above).unwrap().is_visible(), true);
-        assert_eq!(world.chunks[0].cube_at(&top).unwrap().is_visible(), false);
+        assert_eq!(world.chunks[&(0, 0)].cube_at(&above).unwrap().is_visible(), true);
+        assert_eq!(world.chunks[&(0, 0)].cube_at(&top).unwrap().is_visible(), false);
     }
 
     #[test]
@@ -603,15 +1993,943 @@ mod tests {
         chunk.fill_layer(0, GRASS);
         chunk.fill_layer(1, GRASS);
         chunk.fill_layer(2, GRASS);
-        world.chunks.push(chunk);
+        world.chunks.insert(chunk.coord(), chunk);
         world.compute_visible_cubes();
         let bottom = Vector3::new(4., 0., 4.);
         assert_eq!(
-            world.chunks[0].cube_at(&bottom).unwrap().is_visible(),
+            world.chunks[&(0, 0)].cube_at(&bottom).unwrap().is_visible(),
             false
         );
     }
 
+    #[test]
+    fn test_adjacent_water_cubes_hide_shared_face() {
+        let mut world = World::empty();
+        let mut chunk = Chunk::new([0., 0.]);
+        chunk.fill_layer(0, GRASS);
+        chunk.add_cube(Vector3::new(4., 1., 4.), Block::WATER, 0);
+        chunk.add_cube(Vector3::new(5., 1., 4.), Block::WATER, 0);
+        world.chunks.insert(chunk.coord(), chunk);
+        world.compute_visible_cubes();
+        world.set_cubes_to_draw();
+
+        let transparent = world.transparent_cube_to_draw();
+        assert_eq!(transparent.len(), 2);
+        let left = transparent.iter().find(|c| c.position() == [4., 1., 4.]).unwrap();
+        let right = transparent.iter().find(|c| c.position() == [5., 1., 4.]).unwrap();
+        // Face 1 is +x and face 3 is -x
+        assert!(left.is_face_hidden(1));
+        assert!(right.is_face_hidden(3));
+        for face in [0, 2, 3, 4, 5] {
+            assert!(!left.is_face_hidden(face));
+        }
+        // The water stays visible from below, since the grass is not water
+        assert!(!right.is_face_hidden(5));
+
+        // A third water cube added through an action also updates its neighbor
+        world.apply_action(&Action::Add { at: Vector3::new(4., 1., 5.), block: Block::WATER, metadata: 0 });
+        let left = world.transparent_cube_to_draw().iter().find(|c| c.position() == [4., 1., 4.]).unwrap();
+        assert!(left.is_face_hidden(2));
+
+        // And removing it shows the face again
+        world.apply_action(&Action::Destroy { at: Vector3::new(4., 1., 5.) });
+        let left = world.transparent_cube_to_draw().iter().find(|c| c.position() == [4., 1., 4.]).unwrap();
+        assert!(!left.is_face_hidden(2));
+        assert_eq!(world.transparent_cube_to_draw().len(), 2);
+    }
+
+    #[test]
+    fn test_glass_merges_but_leaves_do_not() {
+        let mut world = World::empty();
+        let mut chunk = Chunk::new([0., 0.]);
+        chunk.fill_layer(0, GRASS);
+        chunk.add_cube(Vector3::new(4., 1., 4.), Block::GLASS, 0);
+        chunk.add_cube(Vector3::new(5., 1., 4.), Block::GLASS, 0);
+        chunk.add_cube(Vector3::new(4., 1., 6.), Block::OAKLEAVES, 0);
+        chunk.add_cube(Vector3::new(5., 1., 6.), Block::OAKLEAVES, 0);
+        world.chunks.insert(chunk.coord(), chunk);
+        world.compute_visible_cubes();
+        world.set_cubes_to_draw();
+
+        let transparent = world.transparent_cube_to_draw();
+        let at = |position: [f32; 3]| transparent.iter().find(|c| c.position() == position).unwrap();
+        // Face 1 is +x
+        assert!(at([4., 1., 4.]).is_face_hidden(1));
+        assert!(!at([4., 1., 6.]).is_face_hidden(1));
+    }
+
+    #[test]
+    fn test_scaled_world() {
+        let mut world = World::empty();
+        let mut chunk = Chunk::new([0., 0.]);
+        chunk.fill_layer(0, GRASS);
+        world.chunks.insert(chunk.coord(), chunk);
+        world.compute_visible_cubes();
+        world.set_scale(2.);
+
+        // The cube (4, 0, 4) of the grid spans from (8, 0, 8) to (10, 2, 10)
+        let aabb = world.cube_aabb(Vector3::new(4., 0., 4.));
+        assert_eq!((aabb.west(), aabb.east()), (8., 10.));
+        assert_eq!((aabb.bottom(), aabb.top()), (0., 2.));
+        assert_eq!((aabb.south(), aabb.north()), (8., 10.));
+        assert!(Vector3::new(4., 0., 4.).equals(&world.to_cube_coordinates(&Vector3::new(9.9, 1.9, 8.1))));
+        assert!(Vector3::new(-1., 0., 5.).equals(&world.to_cube_coordinates(&Vector3::new(-0.1, 0.1, 10.))));
+
+        // The ground is now 2 units high
+        let eye_above = Position::new(Vector3::new(9., 2.1 + PLAYER_HEIGHT - FOREHEAD, 9.), 0., 0.);
+        let eye_inside = Position::new(Vector3::new(9., 1.9 + PLAYER_HEIGHT - FOREHEAD, 9.), 0., 0.);
+        assert!(!world.collides(&humanoid_aabb(&eye_above)));
+        assert!(world.collides(&humanoid_aabb(&eye_inside)));
+
+        // Falling from above, the player is stopped on top of the scaled cube
+        let velocity = Vector3::new(0., -1., 0.);
+        let target = humanoid_aabb(&(&eye_above + velocity));
+        let collision = world.collision_time(&eye_above, &humanoid_aabb(&eye_above), &target, &velocity).unwrap();
+        assert!((collision.time - 0.1).abs() < 1e-4);
+        assert!(collision.normal.equals(&Vector3::unit_y()));
+    }
+
+    #[test]
+    fn test_global_to_chunk() {
+        let s = CHUNK_SIZE as i32;
+        assert_eq!(World::global_to_chunk(&Vector3::new(0., 3., 0.)), Some(((0, 0), [0, 3, 0])));
+        assert_eq!(World::global_to_chunk(&Vector3::new(s as f32 - 0.5, 3., 1.5)), Some(((0, 0), [CHUNK_SIZE - 1, 3, 1])));
+        assert_eq!(World::global_to_chunk(&Vector3::new(s as f32, 3., 0.)), Some(((s, 0), [0, 3, 0])));
+
+        // Negative coordinates: x = -1 is the last cube of the chunk at -CHUNK_SIZE, not of the chunk 0
+        assert_eq!(World::global_to_chunk(&Vector3::new(-1., 0., 0.)), Some(((-s, 0), [CHUNK_SIZE - 1, 0, 0])));
+        assert_eq!(World::global_to_chunk(&Vector3::new(-0.5, 0., -0.5)), Some(((-s, -s), [CHUNK_SIZE - 1, 0, CHUNK_SIZE - 1])));
+        assert_eq!(World::global_to_chunk(&Vector3::new(-s as f32, 0., -s as f32 - 1.)), Some(((-s, -2 * s), [0, 0, CHUNK_SIZE - 1])));
+
+        // Below and above the world, the position is in no layer of the chunk of its column
+        assert_eq!(World::global_to_chunk(&Vector3::new(1., -0.5, 1.)), None);
+        assert_eq!(World::global_to_chunk(&Vector3::new(1., CHUNK_HEIGHT as f32, 1.)), None);
+        assert_eq!(World::chunk_of(&Vector3::new(1., -0.5, -1.)), (0, -s));
+    }
+
+    #[test]
+    fn test_chunk_coordinates_round_trip() {
+        for corner in [[0., 0.], [-(CHUNK_SIZE as f32), 0.], [16., -24.], [-40., -8.]] {
+            let chunk = Chunk::new(corner);
+            for (lx, ly, lz) in [(0, 0, 0), (CHUNK_SIZE - 1, 5, 0), (3, 12, CHUNK_SIZE - 1)] {
+                let [x, y, z] = chunk.local_to_global(lx, ly, lz);
+                let global = Vector3::new(x as f32, y as f32, z as f32);
+                assert!(chunk.is_in(&global));
+                assert_eq!(World::global_to_chunk(&global), Some((chunk.coord(), [lx, ly, lz])));
+                // Anywhere inside the cube gives the same answer
+                let inside = global + Vector3::new(0.9, 0.9, 0.9);
+                assert_eq!(World::global_to_chunk(&inside), Some((chunk.coord(), [lx, ly, lz])));
+            }
+        }
+    }
+
+    /// A world with a floor of grass on the layer 0, water at (4, 1, 4) and a cobblestone at (6, 1, 6)
+    fn world_for_placement() -> World {
+        let mut chunk = Chunk::new([0., 0.]);
+        chunk.fill_layer(0, GRASS);
+        chunk.add_cube(Vector3::new(4., 1., 4.), Block::WATER, 0);
+        chunk.add_cube(Vector3::new(6., 1., 6.), Block::COBBELSTONE, 0);
+        let mut world = World::new(vec![chunk]);
+        world.set_cubes_to_draw();
+        world
+    }
+
+    fn looking_down() -> Vector3 {
+        Vector3::new(0., -1., 0.)
+    }
+
+    #[test]
+    fn test_place_block_against_face() {
+        let world = world_for_placement();
+        let far_away = humanoid_aabb(&Position::new(Vector3::new(100., 100., 100.), 0., 0.));
+        let hit = *world.cube_at(Vector3::new(2., 0., 3.)).unwrap();
+
+        let action = world.place_block(&hit, Block::DIRT, Vector3::new(2.5, 5., 3.5), looking_down(), &far_away);
+        assert_eq!(action.unwrap(), Action::Add { at: Vector3::new(2., 1., 3.), block: Block::DIRT, metadata: 0 });
+
+        // Clicking on the side of a cube
+        let hit = *world.cube_at(Vector3::new(6., 1., 6.)).unwrap();
+        let action = world.place_block(&hit, Block::DIRT, Vector3::new(3.5, 1.5, 6.5), Vector3::unit_x(), &far_away);
+        assert_eq!(action.unwrap(), Action::Add { at: Vector3::new(5., 1., 6.), block: Block::DIRT, metadata: 0 });
+    }
+
+    #[test]
+    fn test_place_block_in_player_is_rejected() {
+        let world = world_for_placement();
+        // The player stands on the cube (2, 0, 3) and looks at its feet
+        let eye = Vector3::new(2.5, 1. + PLAYER_HEIGHT - FOREHEAD + 0.01, 3.5);
+        let player = humanoid_aabb(&Position::new(eye, 0., 0.));
+        let hit = *world.cube_at(Vector3::new(2., 0., 3.)).unwrap();
+
+        let action = world.place_block(&hit, Block::DIRT, eye, looking_down(), &player);
+        assert_eq!(action, Err(PlacementError::OverlapsPlayer));
+    }
+
+    #[test]
+    fn test_place_block_on_raycast_hit() {
+        let world = world_for_placement();
+        let far_away = humanoid_aabb(&Position::new(Vector3::new(100., 100., 100.), 0., 0.));
+
+        // Against the side of the cobblestone
+        let ray = Ray::new(Vector3::new(3.5, 1.5, 6.5), Vector3::unit_x());
+        let hit = world.raycast(&ray, 10., false).unwrap();
+        let action = world.place_block_on(&hit, Block::DIRT, &far_away);
+        assert_eq!(action.unwrap(), Action::Add { at: Vector3::new(5., 1., 6.), block: Block::DIRT, metadata: 0 });
+
+        // The water is replaced
+        let ray = Ray::new(Vector3::new(4.5, 4.5, 4.5), looking_down());
+        let hit = world.raycast(&ray, 10., false).unwrap();
+        let action = world.place_block_on(&hit, Block::DIRT, &far_away);
+        assert_eq!(action.unwrap(), Action::Add { at: Vector3::new(4., 1., 4.), block: Block::DIRT, metadata: 0 });
+
+        // Not in the player looking at its feet
+        let eye = Vector3::new(2.5, 1. + PLAYER_HEIGHT - FOREHEAD + 0.01, 3.5);
+        let player = humanoid_aabb(&Position::new(eye, 0., 0.));
+        let hit = world.raycast(&Ray::new(eye, looking_down()), 10., false).unwrap();
+        assert_eq!(world.place_block_on(&hit, Block::DIRT, &player), Err(PlacementError::OverlapsPlayer));
+    }
+
+    #[test]
+    fn test_placed_shape_follows_the_hit() {
+        let mut world = world_for_placement();
+        let far_away = humanoid_aabb(&Position::new(Vector3::new(100., 100., 100.), 0., 0.));
+
+        // Against the upper half of the side of the cobblestone, the slab goes on the top of the cell
+        let hit = world.raycast(&Ray::new(Vector3::new(3.5, 1.8, 6.5), Vector3::unit_x()), 10., false).unwrap();
+        let action = world.place_block_on(&hit, Block::SLAB, &far_away);
+        assert_eq!(action.unwrap(), Action::Add { at: Vector3::new(5., 1., 6.), block: Block::SLABTOP, metadata: 0 });
+        // The torches do not hold on the sides
+        assert_eq!(world.place_block_on(&hit, Block::TORCH, &far_away), Err(PlacementError::WrongFace));
+
+        // The stairs climb away from the player, their facing is kept in the metadata of the cell
+        let action = world.place_block_on(&hit, Block::STAIRS, &far_away).unwrap();
+        assert_eq!(action, Action::Add { at: Vector3::new(5., 1., 6.), block: Block::STAIRS, metadata: Facing::East.metadata() });
+        world.apply_action(&action);
+        let stairs = world.cube_at(Vector3::new(5., 1., 6.)).unwrap();
+        assert_eq!(stairs.shape(), BlockShape::Stairs(Facing::East));
+    }
+
+    #[test]
+    fn test_walking_on_a_slab() {
+        let mut chunk = Chunk::new([0., 0.]);
+        chunk.fill_layer(0, GRASS);
+        chunk.add_cube(Vector3::new(3., 1., 3.), Block::SLAB, 0);
+        let world = World::new(vec![chunk]);
+
+        // A box falling on the slab stops on its top, half a cube above the floor
+        let small = AABB::new(3.8, 3.2, 4., 3., 3.8, 3.2).unwrap();
+        let allowed = world.resolve_aabb_movement(&small, &Vector3::new(0., -5., 0.));
+        assert!((allowed.y() + 1.5).abs() < 1e-5, "{allowed:?}");
+
+        // Walking into it from the floor only needs half a step
+        let walking = AABB::new(3.8, 3.2, 2., 1., 2.9, 2.3).unwrap();
+        let height = world.obstruction_height(&walking, &Vector3::new(0.5, 0., 0.), 1).unwrap();
+        assert!((height - 0.5).abs() < 1e-5, "{height}");
+    }
+
+    #[test]
+    fn test_place_block_into_water_replaces_it() {
+        let mut world = world_for_placement();
+        let far_away = humanoid_aabb(&Position::new(Vector3::new(100., 100., 100.), 0., 0.));
+        let water = Vector3::new(4., 1., 4.);
+        let hit = *world.cube_at(water).unwrap();
+        assert_eq!(world.transparent_cube_to_draw().len(), 1);
+
+        let action = world.place_block(&hit, Block::COBBELSTONE, Vector3::new(4.5, 5., 4.5), looking_down(), &far_away).unwrap();
+        assert_eq!(action, Action::Add { at: water, block: Block::COBBELSTONE, metadata: 0 });
+        world.apply_action(&action);
+
+        assert_eq!(world.block_at(&water), Some(Block::COBBELSTONE));
+        assert_eq!(world.transparent_cube_to_draw().len(), 0);
+        assert!(world.cube_to_draw().iter().any(|c| c.position() == water.as_array()));
+        // The grass below is now hidden
+        assert!(!world.cube_at(Vector3::new(4., 0., 4.)).unwrap().is_visible());
+    }
+
+    #[test]
+    fn test_place_block_on_occupied_cell_is_rejected() {
+        let world = world_for_placement();
+        let far_away = humanoid_aabb(&Position::new(Vector3::new(100., 100., 100.), 0., 0.));
+        // Looking from inside the cube (4, 0, 4) towards +x targets the grass cube next to it
+        let hit = *world.cube_at(Vector3::new(4., 0., 4.)).unwrap();
+        let action = world.place_block(&hit, Block::DIRT, Vector3::new(4.5, 0.5, 4.5), Vector3::unit_x(), &far_away);
+        assert_eq!(action, Err(PlacementError::Occupied));
+    }
+
+    #[test]
+    fn test_line_of_sight() {
+        let mut world = world_for_placement();
+        let a = Vector3::new(1.5, 1.5, 6.5);
+        let b = Vector3::new(7.5, 2.5, 6.5);
+
+        // Only air in between
+        assert!(world.has_line_of_sight(&a, &b));
+        assert!(world.has_line_of_sight(&b, &a));
+
+        // Looking through the water
+        assert!(world.has_line_of_sight(&Vector3::new(4.5, 1.5, 1.5), &Vector3::new(4.5, 1.5, 7.5)));
+
+        // A wall in between
+        world.apply_action(&Action::Add { at: Vector3::new(4., 1., 6.), block: Block::COBBELSTONE, metadata: 0 });
+        world.apply_action(&Action::Add { at: Vector3::new(4., 2., 6.), block: Block::COBBELSTONE, metadata: 0 });
+        assert!(!world.has_line_of_sight(&a, &b));
+        assert!(!world.has_line_of_sight(&b, &a));
+
+        // Both endpoints in the same cell, even inside a cube
+        let inside = Vector3::new(6.2, 1.2, 6.2);
+        assert!(world.has_line_of_sight(&inside, &Vector3::new(6.8, 1.9, 6.5)));
+        // The cubes of the endpoints do not block the view
+        assert!(world.has_line_of_sight(&inside, &Vector3::new(6.5, 4.5, 6.5)));
+    }
+
+    #[test]
+    fn test_raycast() {
+        let mut world = world_for_placement();
+
+        // Looking down on the floor, the top face is hit
+        let down = Ray::new(Vector3::new(2.5, 4.5, 3.5), Vector3::new(0., -1., 0.));
+        let hit = world.raycast(&down, 10., false).unwrap();
+        assert!(hit.cube.position().equals(&Vector3::new(2., 0., 3.)));
+        assert_eq!(hit.normal, Vector3::unit_y());
+        assert_eq!(hit.distance, 3.5);
+        // Too far away
+        assert!(world.raycast(&down, 3., false).is_none());
+
+        // The water is hit, unless the ray goes through it
+        let toward_water = Ray::new(Vector3::new(0.5, 1.5, 4.5), Vector3::new(1., 0., 0.));
+        assert_eq!(*world.raycast(&toward_water, 10., false).unwrap().cube.block(), Block::WATER);
+        assert!(world.raycast(&toward_water, 10., true).is_none());
+        let toward_cobble = Ray::new(Vector3::new(6.5, 1.5, 0.5), Vector3::new(0., 0., 1.));
+        let hit = world.raycast(&toward_cobble, 10., true).unwrap();
+        assert_eq!(hit.normal, Vector3::new(0., 0., -1.));
+        assert_eq!(hit.distance, 5.5);
+
+        // The distances are in the world space
+        world.set_scale(0.5);
+        let hit = world.raycast(&Ray::new(Vector3::new(1.25, 2.25, 1.75), Vector3::new(0., -1., 0.)), 10., false).unwrap();
+        assert_eq!(hit.distance, 1.75);
+    }
+
+    #[test]
+    fn test_resolve_aabb_movement() {
+        // A floor on the layer 0, and a wall at x = 6
+        let mut chunk = Chunk::new([0., 0.]);
+        chunk.fill_layer(0, GRASS);
+        for y in 1..4 {
+            for z in 0..CHUNK_SIZE {
+                chunk.add_cube(Vector3::new(6., y as f32, z as f32), Block::STONE, 0);
+            }
+        }
+        let world = World::new(vec![chunk]);
+        let close = |a: f32, b: f32| (a - b).abs() < 1e-5;
+
+        // A small box falling on the floor stops on top of it
+        let small = AABB::new(2.6, 2.4, 3., 2.8, 2.6, 2.4).unwrap();
+        let allowed = world.resolve_aabb_movement(&small, &Vector3::new(0., -5., 0.));
+        assert!(close(allowed.y(), -1.8), "{allowed:?}");
+        assert!(close(allowed.x(), 0.) && close(allowed.z(), 0.));
+
+        // A wide box going to the wall stops against it, while keeping its motion along the wall
+        let wide = AABB::new(4.5, 2.5, 3.5, 1.5, 4.2, 2.2).unwrap();
+        let allowed = world.resolve_aabb_movement(&wide, &Vector3::new(3., 0., 1.));
+        assert!(close(allowed.x(), 1.8), "{allowed:?}");
+        assert!(close(allowed.z(), 1.));
+        assert!(close(allowed.y(), 0.));
+
+        // Both at the same time: each axis stops independently
+        let allowed = world.resolve_aabb_movement(&small, &Vector3::new(5., -5., 0.5));
+        assert!(close(allowed.y(), -1.8) && close(allowed.x(), 3.4) && close(allowed.z(), 0.5), "{allowed:?}");
+
+        // A box lying on the floor slides freely, and is not pushed through the floor
+        let lying = AABB::new(1.5, 0.5, 2.8, 1., 1.5, 0.5).unwrap();
+        let allowed = world.resolve_aabb_movement(&lying, &Vector3::new(2., -1., 0.));
+        assert!(close(allowed.x(), 2.) && close(allowed.y(), 0.), "{allowed:?}");
+
+        // Away from the obstacles, the movement is not changed
+        let allowed = world.resolve_aabb_movement(&small, &Vector3::new(-1., 2., 1.));
+        assert!(allowed.equals(&Vector3::new(-1., 2., 1.)));
+    }
+
+    #[test]
+    fn test_pigs_spawn_on_grass() {
+        let mut grass = Chunk::new([0., 0.]);
+        grass.fill_layer(0, GRASS);
+        let mut stone = Chunk::new([CHUNK_SIZE as f32, 0.]);
+        stone.fill_layer(0, Block::STONE);
+        let mut world = World::new(vec![grass, stone]);
+
+        for _ in 0..200 {
+            world.spawn_animals(1. / PIG_SPAWN_RATE);
+        }
+        let pigs: Vec<Vector3> = world.entities().of_type::<Pig>().map(|(_, body, _)| body.position()).collect();
+        assert_eq!(pigs.len(), MAX_PIGS);
+        for pig in pigs {
+            assert_eq!(pig.y(), 1.);
+            assert!(pig.x() < CHUNK_SIZE as f32);
+        }
+    }
+
+    #[test]
+    fn test_zombies_spawn_at_night() {
+        let mut chunks = Vec::new();
+        for (x, z) in [(0, 0), (-1, 0), (0, -1), (-1, -1), (1, 0), (0, 1), (1, 1), (-1, 1), (1, -1)] {
+            let mut chunk = Chunk::new([(x * CHUNK_SIZE as i32) as f32, (z * CHUNK_SIZE as i32) as f32]);
+            chunk.fill_layer(0, GRASS);
+            chunks.push(chunk);
+        }
+        let mut world = World::new(chunks);
+        let player = Vector3::new(0.5, 1., 0.5);
+
+        // Never during the day
+        for _ in 0..100 {
+            assert!(world.spawn_monsters(1. / ZOMBIE_SPAWN_RATE, &player, 1.).is_none());
+        }
+        for _ in 0..500 {
+            world.spawn_monsters(1. / ZOMBIE_SPAWN_RATE, &player, 0.);
+        }
+        let zombies: Vec<Vector3> = world.entities().of_type::<Zombie>().map(|(_, body, _)| body.position()).collect();
+        assert_eq!(zombies.len(), MAX_ZOMBIES);
+        for zombie in zombies {
+            assert_eq!(zombie.y(), 1.);
+            assert!((zombie.x() - player.x()).abs().max((zombie.z() - player.z()).abs()) >= MIN_SPAWN_DISTANCE as f32 - 1.);
+        }
+
+        // They are gone at dawn
+        world.spawn_monsters(0.1, &player, 1.);
+        assert_eq!(world.entities().of_type::<Zombie>().count(), 0);
+    }
+
+    #[test]
+    fn test_sand_falls_and_lands() {
+        let mut chunk = Chunk::new([0., 0.]);
+        chunk.fill_layer(0, GRASS);
+        chunk.add_cube(Vector3::new(2., 1., 2.), Block::DIRT, 0);
+        chunk.add_cube(Vector3::new(2., 2., 2.), Block::SAND, 0);
+        chunk.add_cube(Vector3::new(2., 3., 2.), Block::GRAVEL, 0);
+        let mut world = World::new(vec![chunk]);
+
+        // Placed over the air, the sand falls to the floor
+        world.apply_action(&Action::Add { at: Vector3::new(5., 6., 5.), block: Block::SAND, metadata: 0 });
+        assert_eq!(world.tick(), vec![Action::Destroy { at: Vector3::new(5., 6., 5.) }]);
+        assert_eq!(world.entities().of_type::<FallingBlock>().count(), 1);
+        let mut landed = Vec::new();
+        for _ in 0..100 {
+            landed.extend(world.step_entities(0.02));
+        }
+        assert_eq!(landed, vec![Action::Add { at: Vector3::new(5., 1., 5.), block: Block::SAND, metadata: 0 }]);
+        assert!(world.entities().is_empty());
+
+        // Without the dirt below them, the whole column falls
+        world.apply_action(&Action::Destroy { at: Vector3::new(2., 1., 2.) });
+        for _ in 0..20 {
+            world.tick();
+            world.step_entities(0.05);
+        }
+        assert_eq!(world.block_at(&Vector3::new(2., 1., 2.)), Some(Block::SAND));
+        assert_eq!(world.block_at(&Vector3::new(2., 2., 2.)), Some(Block::GRAVEL));
+        assert_eq!(world.block_at(&Vector3::new(2., 3., 2.)), None);
+    }
+
+    #[test]
+    fn test_water_is_passable() {
+        let mut chunk = Chunk::new([0., 0.]);
+        chunk.fill_layer(0, GRASS);
+        chunk.fill_layer(1, Block::WATER);
+        chunk.fill_layer(2, Block::WATER);
+        let world = World::new(vec![chunk]);
+
+        // A box sinks through the water down to the floor
+        let small = AABB::new(2.6, 2.4, 3., 2.8, 2.6, 2.4).unwrap();
+        let allowed = world.resolve_aabb_movement(&small, &Vector3::new(0., -5., 0.));
+        assert!((allowed.y() + 1.8).abs() < 1e-5, "{allowed:?}");
+        assert!(!world.collides(&AABB::new(2.6, 2.4, 1.6, 1.4, 2.6, 2.4).unwrap()));
+
+        assert!(world.is_in_water(&Vector3::new(2.5, 2.5, 2.5)));
+        assert!(!world.is_in_water(&Vector3::new(2.5, 3.5, 2.5)));
+    }
+
+    #[test]
+    fn test_water_flow() {
+        // The source is at the corner of 4 chunks
+        let s = CHUNK_SIZE as f32;
+        let chunks = [[0., 0.], [s, 0.], [0., s], [s, s]].map(|corner| {
+            let mut chunk = Chunk::new(corner);
+            chunk.fill_layer(0, GRASS);
+            chunk
+        });
+        let mut world = World::new(chunks.to_vec());
+        world.set_flow_distance(3);
+
+        // A source above the floor
+        let source = Vector3::new(8., 2., 8.);
+        world.apply_action(&Action::Add { at: source, block: Block::WATER, metadata: 0 });
+        let water_at_floor = |world: &World| {
+            let mut cells = Vec::new();
+            for x in 0..2 * CHUNK_SIZE as i32 {
+                for z in 0..2 * CHUNK_SIZE as i32 {
+                    if let Some(level) = world.water_level(&Vector3::newi(x, 1, z)) {
+                        cells.push(([x, z], level));
+                    }
+                }
+            }
+            cells
+        };
+
+        // First it falls, with the level of the source
+        assert_eq!(world.tick().len(), 1);
+        assert_eq!(water_at_floor(&world), vec![([8, 8], 3)]);
+        assert_eq!(world.water_level(&source), Some(3));
+
+        // Then it spreads by one cell at each tick
+        assert_eq!(world.tick().len(), 4);
+        assert_eq!(water_at_floor(&world).len(), 5);
+        assert_eq!(world.water_level(&Vector3::new(9., 1., 8.)), Some(2));
+
+        // Until it reaches the flow distance
+        for _ in 0..10 {
+            world.tick();
+        }
+        let cells = water_at_floor(&world);
+        assert_eq!(cells.len(), 1 + 4 + 8 + 12);
+        for ([x, z], level) in cells {
+            let distance = (x - 8).abs() + (z - 8).abs();
+            assert_eq!(level as i32, 3 - distance);
+        }
+        assert_eq!(world.water_level(&Vector3::new(12., 1., 8.)), None);
+        assert_eq!(world.water_level(&Vector3::new(9., 2., 8.)), None);
+        assert!(world.tick().is_empty());
+
+        // Without its source, the water dries up from where it fell
+        world.apply_action(&Action::Destroy { at: source });
+        let drained = world.tick();
+        assert_eq!(drained, vec![Action::Destroy { at: Vector3::new(8., 1., 8.) }]);
+        for _ in 0..10 {
+            world.tick();
+        }
+        assert!(water_at_floor(&world).is_empty());
+        assert!(world.tick().is_empty());
+    }
+
+    #[test]
+    fn test_loading_neighbor_chunk_hides_shared_border() {
+        let s = CHUNK_SIZE as f32;
+        let solid_chunk = |corner: [f32; 2]| {
+            let mut chunk = Chunk::new(corner);
+            for h in 0..3 {
+                chunk.fill_layer(h, GRASS);
+            }
+            chunk.compute_visible_cubes();
+            chunk
+        };
+        let is_drawn = |world: &World, pos: [f32; 3]| world.cube_to_draw().iter().any(|c| c.position() == pos);
+
+        let mut world = World::empty();
+        world.set_cubes_to_draw();
+        world.add_chunk(solid_chunk([0., 0.]));
+
+        // Alone, the border of the chunk is exposed
+        let border = [s - 1., 1., 4.];
+        assert!(world.cube_at(Vector3::newf(border)).unwrap().is_visible());
+        assert!(is_drawn(&world, border));
+
+        // Once the next chunk is loaded, the shared border is hidden on both sides
+        world.add_chunk(solid_chunk([s, 0.]));
+        assert!(!world.cube_at(Vector3::newf(border)).unwrap().is_visible());
+        assert!(!is_drawn(&world, border));
+        assert!(!is_drawn(&world, [s, 1., 4.]));
+
+        // But not the top of the chunks, nor the other borders
+        assert!(is_drawn(&world, [s - 1., 2., 4.]));
+        assert!(is_drawn(&world, [0., 1., 4.]));
+        assert!(is_drawn(&world, [2. * s - 1., 1., 4.]));
+    }
+
+    #[test]
+    fn test_unloading_chunk_reveals_shared_border() {
+        let s = CHUNK_SIZE as f32;
+        let mut world = World::empty();
+        world.set_cubes_to_draw();
+        for corner in [[0., 0.], [s, 0.]] {
+            let mut chunk = Chunk::new(corner);
+            for h in 0..3 {
+                chunk.fill_layer(h, GRASS);
+            }
+            world.add_chunk(chunk);
+        }
+        let border = [s - 1., 1., 4.];
+        let is_drawn = |world: &World, pos: [f32; 3]| world.cube_to_draw().iter().any(|c| c.position() == pos);
+        assert!(!is_drawn(&world, border));
+
+        let removed = world.remove_chunk((CHUNK_SIZE as i32, 0)).unwrap();
+        assert_eq!(removed.coord(), (CHUNK_SIZE as i32, 0));
+        assert!(world.get_chunk((CHUNK_SIZE as i32, 0)).is_none());
+        assert!(world.remove_chunk((CHUNK_SIZE as i32, 0)).is_none());
+
+        // None of its cubes are drawn anymore, and the border of the other chunk is exposed again
+        assert!(world.cube_to_draw().iter().all(|c| c.position()[0] < s));
+        assert!(world.cube_at(Vector3::newf(border)).unwrap().is_visible());
+        assert!(is_drawn(&world, border));
+        assert!(world.take_dirty_meshes().contains(&(CHUNK_SIZE as i32, 0)));
+    }
+
+    #[test]
+    fn test_torch_lights_the_next_chunk() {
+        let s = CHUNK_SIZE as f32;
+        let mut world = World::empty();
+        for corner in [[0., 0.], [s, 0.]] {
+            let mut chunk = Chunk::new(corner);
+            chunk.fill_layer(0, GRASS);
+            chunk.fill_layer(4, GRASS);
+            world.add_chunk(chunk);
+        }
+        // Under the roof, only the sides let some sky light in
+        let under = Vector3::new(s, 1., 4.);
+        assert!(world.light_at(&under).0 < 15);
+        assert_eq!(world.light_at(&under).1, 0);
+        world.take_dirty_meshes();
+
+        world.apply_action(&Action::Add { at: Vector3::new(s - 1., 1., 4.), block: Block::TORCH, metadata: 0 });
+        assert_eq!(world.light_at(&under).1, 13);
+        assert!(world.take_dirty_meshes().contains(&(CHUNK_SIZE as i32, 0)));
+
+        world.apply_action(&Action::Destroy { at: Vector3::new(s - 1., 1., 4.) });
+        assert_eq!(world.light_at(&under).1, 0);
+    }
+
+    #[test]
+    fn test_mesh_chunk_across_borders() {
+        let s = CHUNK_SIZE as f32;
+        let mut world = World::empty();
+        for corner in [[0., 0.], [s, 0.]] {
+            let mut chunk = Chunk::new(corner);
+            chunk.fill_layer(0, GRASS);
+            world.add_chunk(chunk);
+        }
+        let dirty = world.take_dirty_meshes();
+        assert!(dirty.contains(&(0, 0)) && dirty.contains(&(CHUNK_SIZE as i32, 0)));
+        assert!(world.take_dirty_meshes().is_empty());
+
+        // Top, and the 3 sides not against the other chunk. The bottom is never seen.
+        let mesh = world.mesh_chunk((0, 0)).unwrap();
+        assert_eq!(mesh.n_quads(), 4);
+        assert!(world.mesh_chunk((-(CHUNK_SIZE as i32), 0)).is_none());
+
+        // Changing a cube at the border marks both chunks
+        world.apply_action(&Action::Destroy { at: Vector3::new(s - 1., 0., 3.) });
+        let dirty = world.take_dirty_meshes();
+        assert!(dirty.contains(&(0, 0)) && dirty.contains(&(CHUNK_SIZE as i32, 0)));
+    }
+
+    #[test]
+    fn test_only_exposed_faces_are_drawn() {
+        let mut world = World::empty();
+        world.set_cubes_to_draw();
+        // The visibility of the chunk is not computed yet
+        let mut chunk = Chunk::new([0., 0.]);
+        for h in 0..3 {
+            chunk.fill_layer(h, GRASS);
+        }
+        world.add_chunk(chunk);
+
+        // The cubes surrounded by others, or by the bottom of the world, are not drawn.
+        // The missing chunks around do not hide the border: only the inside of the two lower layers is hidden.
+        let s = CHUNK_SIZE;
+        let inside = (s - 2) * (s - 2);
+        assert_eq!(world.cube_to_draw().len(), 3 * s * s - 2 * inside);
+        assert!(world.cube_to_draw().iter().any(|c| c.position() == [0., 0., 4.]));
+        assert!(world.cube_to_draw().iter().all(|c| c.position() != [4., 1., 4.]));
+        assert!(world.cube_to_draw().iter().all(|c| c.position() != [4., 0., 4.]));
+
+        // Only the top face of a cube on top is drawn
+        let top = world.cube_to_draw().iter().find(|c| c.position() == [4., 2., 4.]).unwrap();
+        assert!(!top.is_face_hidden(4));
+        assert!([0, 1, 2, 3, 5].iter().all(|face| top.is_face_hidden(*face)));
+
+        // Digging a hole reveals the faces around it
+        world.apply_action(&Action::Destroy { at: Vector3::new(4., 2., 4.) });
+        let below = world.cube_to_draw().iter().find(|c| c.position() == [4., 1., 4.]).unwrap();
+        assert!(!below.is_face_hidden(4));
+        let side = world.cube_to_draw().iter().find(|c| c.position() == [5., 2., 4.]).unwrap();
+        assert_eq!((0..6).filter(|face| !side.is_face_hidden(*face)).count(), 2);
+    }
+
+    #[test]
+    fn test_biome_gives_the_surface() {
+        let seed = 11;
+        let mut world = World::empty();
+        world.meta_mut().seed = seed;
+        world.generate_chunk((0, 0));
+
+        for (x, z) in [(0, 0), (5, 6), (7, 3)] {
+            let top = (0..CHUNK_HEIGHT as i32).rev().find_map(|y| world.block_at(&Vector3::newi(x, y, z))).unwrap();
+            // The trees grow over the surface
+            if top != Block::OAKLOG && top != Block::OAKLEAVES {
+                assert_eq!(Some(top), world.biome_at(&Vector3::newi(x, 50, z)).get_block_at(0));
+            }
+        }
+        // Nothing but the seed decides the biome
+        let mut other = World::empty();
+        other.meta_mut().seed = seed;
+        assert_eq!(other.biome_at(&Vector3::new(3.5, 0., 7.2)).name, world.biome_at(&Vector3::newi(3, 40, 7)).name);
+    }
+
+    #[test]
+    fn test_place_structure() {
+        let mut world = World::empty();
+        world.add_chunk(Chunk::new([0., 0.]));
+        let mut structure = Structure::new();
+        structure.add_block([0, 0, 0], Block::OAKLOG);
+        structure.add_block([2, 0, 0], Block::STONE);
+        structure.add_block([0, 1, 1], Block::SAND);
+
+        // A quarter turn sends +x along +z, and +z along -x
+        let anchor = Vector3::new(4., 1., 4.);
+        let dirty = world.place_structure(&structure, anchor, StructureRotation::Deg90, false).unwrap();
+        assert_eq!(dirty, vec![(0, 0)]);
+        assert_eq!(world.block_at(&Vector3::new(4., 1., 4.)), Some(Block::OAKLOG));
+        assert_eq!(world.block_at(&Vector3::new(4., 1., 6.)), Some(Block::STONE));
+        assert_eq!(world.block_at(&Vector3::new(3., 2., 4.)), Some(Block::SAND));
+        assert_eq!(world.block_at(&Vector3::new(6., 1., 4.)), None);
+
+        // Without overwriting, the existing blocks are kept
+        let mut other = Structure::new();
+        other.add_block([0, 0, 0], Block::DIRT);
+        other.add_block([1, 0, 0], Block::DIRT);
+        world.place_structure(&other, anchor, StructureRotation::Deg0, false).unwrap();
+        assert_eq!(world.block_at(&Vector3::new(4., 1., 4.)), Some(Block::OAKLOG));
+        assert_eq!(world.block_at(&Vector3::new(5., 1., 4.)), Some(Block::DIRT));
+        world.place_structure(&other, anchor, StructureRotation::Deg0, true).unwrap();
+        assert_eq!(world.block_at(&Vector3::new(4., 1., 4.)), Some(Block::DIRT));
+    }
+
+    #[test]
+    fn test_place_structure_out_of_bounds() {
+        let mut world = World::empty();
+        world.add_chunk(Chunk::new([0., 0.]));
+        let mut structure = Structure::new();
+        structure.add_block([0, 0, 0], Block::STONE);
+        structure.add_block([3, 0, 0], Block::STONE);
+
+        // The second block would be in a chunk that is not loaded
+        let anchor = Vector3::new(CHUNK_SIZE as f32 - 2., 1., 0.);
+        let result = world.place_structure(&structure, anchor, StructureRotation::Deg0, true);
+        assert_eq!(result, Err(PlacementError::OutOfBounds));
+        assert_eq!(world.block_at(&anchor), None);
+
+        // Turned the other way, it fits
+        assert!(world.place_structure(&structure, anchor, StructureRotation::Deg180, true).is_ok());
+        assert_eq!(world.block_at(&anchor), Some(Block::STONE));
+        assert_eq!(world.block_at(&Vector3::new(CHUNK_SIZE as f32 - 5., 1., 0.)), Some(Block::STONE));
+    }
+
+    #[test]
+    fn test_fill_region() {
+        let mut world = World::empty();
+        world.add_chunk(Chunk::new([0., 0.]));
+        world.add_chunk(Chunk::new([CHUNK_SIZE as f32, 0.]));
+        world.add_chunk(Chunk::new([0., CHUNK_SIZE as f32]));
+
+        // The region is across the border between the first two chunks
+        let min = Vector3::new(6., 1., 2.);
+        let max = Vector3::new(9., 4., 5.);
+        let dirty = world.fill_region(min, max, Block::STONE, None);
+
+        let mut count = 0;
+        for x in 6..=9 {
+            for y in 1..=4 {
+                for z in 2..=5 {
+                    assert_eq!(world.block_at(&Vector3::new(x as f32, y as f32, z as f32)), Some(Block::STONE));
+                    count += 1;
+                }
+            }
+        }
+        assert_eq!(count, 64);
+        assert_eq!(dirty.len(), 2);
+        assert!(dirty.contains(&(0, 0)));
+        assert!(dirty.contains(&(CHUNK_SIZE as i32, 0)));
+
+        // Only the outside of the box is visible, even across the border of the chunks
+        assert!(world.cube_at(Vector3::new(6., 1., 2.)).unwrap().is_visible());
+        assert!(world.cube_at(Vector3::new(9., 4., 5.)).unwrap().is_visible());
+        for x in 7..=8 {
+            for y in 2..=3 {
+                for z in 3..=4 {
+                    assert!(!world.cube_at(Vector3::new(x as f32, y as f32, z as f32)).unwrap().is_visible());
+                }
+            }
+        }
+
+        // Filling again with the same block changes nothing
+        assert!(world.fill_region(min, max, Block::STONE, None).is_empty());
+    }
+
+    #[test]
+    fn test_fill_region_only_replace() {
+        let mut world = World::empty();
+        let mut chunk = Chunk::new([0., 0.]);
+        chunk.fill_layer(1, Block::WATER);
+        chunk.add_cube(Vector3::new(2., 1., 2.), Block::OAKLOG, 0);
+        chunk.add_cube(Vector3::new(2., 2., 2.), Block::OAKLOG, 0);
+        world.add_chunk(chunk);
+
+        let dirty = world.fill_region(Vector3::new(0., 0., 0.), Vector3::new(3., 2., 3.), Block::SAND, Some(Block::WATER));
+        assert_eq!(dirty, vec![(0, 0)]);
+
+        // The water was replaced, but not the log, nor the empty cells
+        assert_eq!(world.block_at(&Vector3::new(0., 1., 0.)), Some(Block::SAND));
+        assert_eq!(world.block_at(&Vector3::new(3., 1., 3.)), Some(Block::SAND));
+        assert_eq!(world.block_at(&Vector3::new(2., 1., 2.)), Some(Block::OAKLOG));
+        assert_eq!(world.block_at(&Vector3::new(2., 2., 2.)), Some(Block::OAKLOG));
+        assert_eq!(world.block_at(&Vector3::new(0., 2., 0.)), None);
+        assert_eq!(world.block_at(&Vector3::new(0., 0., 0.)), None);
+        // Outside of the region, the water is still there
+        assert_eq!(world.block_at(&Vector3::new(4., 1., 4.)), Some(Block::WATER));
+    }
+
+    #[test]
+    fn test_build_limit() {
+        let mut world = world_for_placement();
+        world.meta_mut().build_limit = 2;
+        let far_away = humanoid_aabb(&Position::new(Vector3::new(100., 100., 100.), 0., 0.));
+
+        // On top of the cobblestone, at the ceiling
+        let hit = *world.cube_at(Vector3::new(6., 1., 6.)).unwrap();
+        let action = world.place_block(&hit, Block::DIRT, Vector3::new(6.5, 5., 6.5), looking_down(), &far_away);
+        assert_eq!(action, Ok(Action::Add { at: Vector3::new(6., 2., 6.), block: Block::DIRT, metadata: 0 }));
+        world.apply_action(&action.unwrap());
+
+        // One above the ceiling
+        let hit = *world.cube_at(Vector3::new(6., 2., 6.)).unwrap();
+        let action = world.place_block(&hit, Block::DIRT, Vector3::new(6.5, 5., 6.5), looking_down(), &far_away);
+        assert_eq!(action, Err(PlacementError::AboveBuildLimit));
+    }
+
+    #[test]
+    fn test_bedrock_cannot_be_broken() {
+        let mut world = world_for_placement();
+        let bedrock = Vector3::new(2., 0., 3.);
+
+        assert!(!world.is_breakable(&bedrock));
+        assert_eq!(world.break_block(bedrock, GameMode::Survival, None), None);
+        assert_eq!(world.block_at(&bedrock), Some(GRASS));
+
+        // Anything above can be broken
+        assert_eq!(world.break_block(Vector3::new(6., 1., 6.), GameMode::Survival, None), Some(Block::COBBELSTONE));
+        assert_eq!(world.block_at(&Vector3::new(6., 1., 6.)), None);
+        // And nothing is returned where there is nothing to break
+        assert_eq!(world.break_block(Vector3::new(6., 1., 6.), GameMode::Survival, None), None);
+    }
+
+    #[test]
+    fn test_break_block_drops() {
+        let mut world = world_for_placement();
+        world.apply_action(&Action::Add { at: Vector3::new(2., 1., 2.), block: Block::STONE, metadata: 0 });
+
+        // Survival: the stone leaves a cobblestone, only to a pickaxe
+        assert_eq!(world.break_block(Vector3::new(2., 1., 2.), GameMode::Survival, Some(Tool::Pickaxe)), Some(Block::STONE));
+        assert_eq!(world.take_events(), vec![WorldEvent::ItemDropped { pos: Vector3::new(2., 1., 2.), stack: ItemStack::new(Block::COBBELSTONE, 1) }]);
+        assert!(world.take_events().is_empty());
+
+        // The water, the air and the bedrock leave nothing
+        world.break_block(Vector3::new(4., 1., 4.), GameMode::Survival, None);
+        world.break_block(Vector3::new(4., 1., 4.), GameMode::Survival, None);
+        world.break_block(Vector3::new(2., 0., 3.), GameMode::Survival, None);
+        assert!(world.take_events().is_empty());
+
+        world.apply_action(&Action::Add { at: Vector3::new(2., 1., 2.), block: Block::STONE, metadata: 0 });
+        assert_eq!(world.break_block(Vector3::new(2., 1., 2.), GameMode::Survival, None), Some(Block::STONE));
+        assert!(world.take_events().is_empty());
+
+        // Creative: the block is broken without any drop
+        assert_eq!(world.break_block(Vector3::new(6., 1., 6.), GameMode::Creative, Some(Tool::Pickaxe)), Some(Block::COBBELSTONE));
+        assert!(world.take_events().is_empty());
+    }
+
+    #[test]
+    fn test_grass_spreads_with_random_ticks() {
+        let mut world = World::empty();
+        world.add_chunk(Chunk::new([0., 0.]));
+        world.random_ticks = RandomTicks::new(7);
+        let grass = Vector3::new(3., 5., 3.);
+        let dirt = Vector3::new(4., 4., 3.);
+        world.apply_action(&Action::Add { at: grass, block: GRASS, metadata: 0 });
+        world.apply_action(&Action::Add { at: dirt, block: Block::DIRT, metadata: 0 });
+
+        // The grass ends up on the dirt next to it, one cell lower
+        let spread = (0..500).find_map(|_| world.random_tick_at(grass)).unwrap();
+        assert_eq!(spread, Action::Add { at: dirt, block: GRASS, metadata: 0 });
+        assert_eq!(world.block_at(&dirt), Some(GRASS));
+        // Only the grass changes
+        assert_eq!(world.random_tick_at(Vector3::new(3., 6., 3.)), None);
+
+        // Covered by a cube, the grass turns into dirt
+        world.apply_action(&Action::Add { at: dirt + Vector3::unit_y(), block: Block::STONE, metadata: 0 });
+        assert_eq!(world.random_tick_at(dirt), Some(Action::Add { at: dirt, block: Block::DIRT, metadata: 0 }));
+    }
+
+    #[test]
+    fn test_farming() {
+        let mut world = world_for_placement();
+        world.random_ticks = RandomTicks::new(5);
+        let far_away = humanoid_aabb(&Position::new(Vector3::new(100., 100., 100.), 0., 0.));
+        let soil = Vector3::new(2., 0., 3.);
+        let crop = soil + Vector3::unit_y();
+        let hit = |world: &World, at: Vector3| RayHit {
+            cube: *world.cube_at(at).unwrap(),
+            normal: Vector3::unit_y(),
+            distance: 1.,
+            point: at + Vector3::new(0.5, 1., 0.5),
+            direction: Vector3::unit_y().opposite(),
+        };
+
+        // The seeds are only planted on farmland, tilled with the hoe
+        let planting = world.place_block_on(&hit(&world, soil), Block::SEEDS, &far_away);
+        assert_eq!(planting, Err(PlacementError::NoFarmland));
+        let tilling = world.till(&hit(&world, soil)).unwrap();
+        assert_eq!(tilling, Action::Add { at: soil, block: Block::FARMLAND, metadata: 0 });
+        world.apply_action(&tilling);
+        let planting = world.place_block_on(&hit(&world, soil), Block::SEEDS, &far_away).unwrap();
+        assert_eq!(planting, Action::Add { at: crop, block: Block::WHEATCROP, metadata: 0 });
+        world.apply_action(&planting);
+        assert_eq!(world.till(&hit(&world, soil)), None);
+
+        // The crop grows at each random tick until its last stage, then it gives wheat
+        for stage in 1..=MAX_CROP_STAGE {
+            assert_eq!(world.random_tick_at(crop), Some(Action::Add { at: crop, block: Block::WHEATCROP, metadata: stage }));
+        }
+        assert_eq!(world.random_tick_at(crop), None);
+        world.break_block(crop, GameMode::Survival, None);
+        let harvest: Vec<ItemStack> = world.take_events().into_iter().map(|WorldEvent::ItemDropped { stack, .. }| stack).collect();
+        assert_eq!(harvest, [Block::WHEAT, Block::SEEDS, Block::SEEDS].map(|item| ItemStack::new(item, 1)));
+    }
+
+    #[test]
+    fn test_broken_chest_drops_its_items() {
+        let mut world = world_for_placement();
+        let at = Vector3::new(2., 1., 2.);
+        world.apply_action(&Action::Add { at, block: Block::CHEST, metadata: 0 });
+        let mut container = Container::new();
+        container.put(0, ItemStack::new(Block::SAND, 1));
+        container.put(0, ItemStack::new(Block::SAND, 1));
+        container.put(1, ItemStack { wear: 5, ..ItemStack::new(Tool::Axe, 1) });
+        world.apply_action(&Action::SetBlockEntity { at, entity: BlockEntity::Chest(container) });
+        assert_eq!(world.block_entity(&at).and_then(|entity| entity.container()).unwrap().get(0), Some(ItemStack::new(Block::SAND, 2)));
+
+        // Saved with the world
+        let reconstructed = World::from_json(world.to_json());
+        assert_eq!(reconstructed.block_entity(&at), world.block_entity(&at));
+
+        assert_eq!(world.break_block(at, GameMode::Survival, None), Some(Block::CHEST));
+        let drops: Vec<ItemStack> = world.take_events().into_iter().map(|WorldEvent::ItemDropped { stack, .. }| stack).collect();
+        // The stacks of the chest are dropped whole, and its tools keep their wear
+        assert_eq!(drops, vec![
+            ItemStack::new(Block::CHEST, 1),
+            ItemStack::new(Block::SAND, 2),
+            ItemStack { wear: 5, ..ItemStack::new(Tool::Axe, 1) },
+        ]);
+        assert!(world.block_entity(&at).is_none());
+    }
+
     #[test]
     fn test_world_persistence() {
         let world = WorldGenerator::create_new_random_world(2);
@@ -620,12 +2938,151 @@ mod tests {
         assert_eq!(world.chunks, reconstructed.chunks);
     }
 
+    #[test]
+    fn test_chunks_outside_of_the_frustum() {
+        let s = CHUNK_SIZE as f32;
+        let mut world = World::empty();
+        for corner in [[0., 0.], [0., -2. * s], [10. * s, 0.]] {
+            world.add_chunk(Chunk::new(corner));
+        }
+        // The camera is at the origin and looks toward +z
+        let view = [[1., 0., 0., 0.], [0., 1., 0., 0.], [0., 0., 1., 0.], [0., 0., 0., 1.]];
+        let frustum = Frustum::from_matrices(&view, &perspective_matrix((800, 600), 1.));
+
+        let outside = world.chunks_outside(&frustum);
+        assert_eq!(outside.len(), 2);
+        assert!(outside.contains(&(0, -2 * CHUNK_SIZE as i32)));
+        assert!(outside.contains(&(10 * CHUNK_SIZE as i32, 0)));
+    }
+
+    #[test]
+    fn test_far_chunks_level_of_detail() {
+        let s = CHUNK_SIZE as f32;
+        let mut chunks = Vec::new();
+        for corner in [[0., 0.], [10. * s, 0.]] {
+            let mut chunk = Chunk::new(corner);
+            for h in 0..4 {
+                chunk.fill_layer(h, Block::DIRT);
+            }
+            chunks.push(chunk);
+        }
+        let mut world = World::new(chunks);
+        world.set_cubes_to_draw();
+        let full_chunk = world.number_cubes_rendered() / 2;
+        let far_chunk = &world.chunks[&(10 * CHUNK_SIZE as i32, 0)];
+        let coarse_chunk = far_chunk.coarse_cubes().len();
+        // Each super-cube stands for a group of cubes with a visible one, the hidden groups are skipped
+        let n = LOD_CUBE_SIZE;
+        let groups = (0..CHUNK_HEIGHT).step_by(n).flat_map(|y| {
+            (0..CHUNK_SIZE).step_by(n).flat_map(move |x| (0..CHUNK_SIZE).step_by(n).map(move |z| (x, y, z)))
+        });
+        let visible_groups = groups
+            .filter(|&(x, y, z)| {
+                (0..n * n * n).any(|i| {
+                    let [gx, gy, gz] = far_chunk.local_to_global(x + (i / n) % n, y + i / (n * n), z + i % n);
+                    far_chunk.cube_at(&Vector3::newi(gx, gy, gz)).is_some_and(|cube| cube.is_visible())
+                })
+            })
+            .count();
+        assert_eq!(coarse_chunk, visible_groups);
+        assert!(far_chunk.coarse_cubes().iter().all(|(_, block)| *block == Block::DIRT));
+        assert!(coarse_chunk < full_chunk);
+
+        // Only the far chunk becomes coarse
+        let near_first = Vector3::new(4., 5., 4.);
+        assert!(world.update_lod(&near_first, Some(3. * s)));
+        assert_eq!(world.number_cubes_rendered(), full_chunk + coarse_chunk);
+        assert!(!world.update_lod(&near_first, Some(3. * s)));
+
+        // Walking to the other chunk swaps them
+        assert!(world.update_lod(&Vector3::new(10. * s + 4., 5., 4.), Some(3. * s)));
+        assert_eq!(world.number_cubes_rendered(), full_chunk + coarse_chunk);
+        assert!(world.cube_to_draw().iter().any(|c| c.position() == [0., 2., 0.]));
+        assert!(!world.cube_to_draw().iter().any(|c| c.position() == [0., 3., 0.]));
+
+        assert!(world.update_lod(&near_first, None));
+        assert_eq!(world.number_cubes_rendered(), 2 * full_chunk);
+    }
+
+    #[test]
+    fn test_parallel_load() {
+        let s = CHUNK_SIZE as i32;
+        let mut world = World::empty();
+        for i in 0..4 {
+            for j in 0..4 {
+                world.generate_chunk((i * s, j * s));
+            }
+        }
+
+        let file = std::env::temp_dir().join("crafty_test_parallel_load.json");
+        let file = file.to_str().unwrap();
+        world.save_to_file(file);
+        let (parallel, _) = World::load_parallel(file, 3).unwrap();
+        let (sequential, _) = World::load(file).unwrap();
+        std::fs::remove_file(file).unwrap();
+
+        assert_eq!(parallel.content_hash(), world.content_hash());
+        assert_eq!(parallel.content_hash(), sequential.content_hash());
+        assert_eq!(parallel.chunks, sequential.chunks);
+    }
+
+    #[test]
+    fn test_save_replaces_the_file() {
+        let file = std::env::temp_dir().join("crafty_test_atomic_save.json");
+        let file = file.to_str().unwrap();
+        // A previous save is already there
+        std::fs::write(file, "not a world").unwrap();
+
+        let world = WorldGenerator::create_new_random_world(1);
+        world.save(file).unwrap();
+        let loaded = World::from_file(file).unwrap();
+        std::fs::remove_file(file).unwrap();
+
+        assert_eq!(loaded.content_hash(), world.content_hash());
+        // The temporary file was renamed, not left behind
+        assert!(!std::path::Path::new(&format!("{file}.tmp")).exists());
+    }
+
+    #[test]
+    fn test_world_meta_persistence() {
+        let seed = 7;
+        let mut world = WorldGenerator::create_new_random_world_with_seed(1, seed);
+        world.meta_mut().game_mode = GameMode::Creative;
+        world.meta_mut().name = String::from("test world");
+
+        let file = std::env::temp_dir().join("crafty_test_world_meta.json");
+        let file = file.to_str().unwrap();
+        world.save_to_file(file);
+        let (mut loaded, meta) = World::load(file).unwrap();
+        std::fs::remove_file(file).unwrap();
+
+        assert_eq!(meta.seed, seed);
+        assert_eq!(meta.game_mode, GameMode::Creative);
+        assert_eq!(meta.name, "test world");
+        assert_eq!(loaded.meta(), &meta);
+
+        // A chunk that was not saved is generated as the original world would have
+        let s = CHUNK_SIZE as i32;
+        let coord = (2 * s, -2 * s);
+        assert!(loaded.get_chunk(coord).is_none());
+        loaded.generate_chunk(coord);
+        let bigger = WorldGenerator::create_new_random_world_with_seed(2, seed);
+        let blocks = |chunk: Chunk| -> Vec<_> {
+            chunk.cubes_iter().map(|c| c.map(|c| (c.position().as_array(), *c.block()))).collect()
+        };
+        assert_eq!(blocks(loaded.get_chunk(coord).unwrap()), blocks(bigger.get_chunk(coord).unwrap()));
+
+        // While another seed gives another world
+        let other = WorldGenerator::create_new_random_world_with_seed(2, seed + 1);
+        assert_ne!(blocks(loaded.get_chunk(coord).unwrap()), blocks(other.get_chunk(coord).unwrap()));
+    }
+
     #[test]
     fn test_cube_iter() {
         let mut world = World::empty();
         let mut chunk = Chunk::new([0., 0.]);
         chunk.fill_layer(0, GRASS);
-        world.chunks.push(chunk);
+        world.chunks.insert(chunk.coord(), chunk);
 
         let count = world.cubes_near_player(Vector3::empty()).filter(|c| c.is_some()).count();
         assert_eq!(count, CHUNK_SIZE * CHUNK_SIZE)
@@ -3,4 +3,10 @@
 pub mod perlin;
 pub mod world_generator;
 pub mod biome;
-pub mod biomes_def;
\ No newline at end of file
+pub mod biomes_def;
+pub mod caves;
+pub mod ores;
+pub mod climate;
+pub mod world_preview;
+pub mod preset;
+pub mod generators;
\ No newline at end of file
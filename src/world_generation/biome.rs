@@ -1,4 +1,4 @@
-use std::hash::{Hash, Hasher};
+use crate::primitives::math::mix_seed;
 use std::vec;
 
 use rand::distributions::Open01;
@@ -8,7 +8,8 @@ use rand::{Rng, RngCore, SeedableRng};
 use crate::block_kind::Block;
 use crate::chunk::CHUNK_SIZE;
 
-use super::biomes_def::NUM_BIOMES;
+use super::biomes_def::{MOUNTAIN, NUM_BIOMES, OCEAN};
+use super::climate::Climate;
 use super::perlin::{PerlinNoiseConfig, MAX_LEVEL_NOISE};
 
 const PROBABILITY_BIOME_CENTER_IN_CHUNK: f32 = 0.05;
@@ -35,11 +36,16 @@ pub struct BiomeConfig {
     pub noise_config: [PerlinNoiseConfig; MAX_LEVEL_NOISE],
     pub layers: [Option<BiomeLayer>; MAX_NUMBER_LAYER],
     pub num_layer: usize,
+    /// Probability that a tree grows on a column of grass
+    pub tree_density: f32,
+    /// Colors multiplying the textures of the grass and of the leaves (RGB, from 0 to 1)
+    pub grass_tint: [f32; 3],
+    pub foliage_tint: [f32; 3],
 }
 
 impl BiomeConfig {
     // The constructor enforces that layers respect the convention, allowing for efficient binary search
-    pub fn new(name: &'static str, terrain_offset: f32, terrain_scale: f32, noise_config: [PerlinNoiseConfig; MAX_LEVEL_NOISE], layers: [Option<BiomeLayer>; MAX_NUMBER_LAYER], num_layer: usize) -> Self {
+    pub fn new(name: &'static str, terrain_offset: f32, terrain_scale: f32, noise_config: [PerlinNoiseConfig; MAX_LEVEL_NOISE], layers: [Option<BiomeLayer>; MAX_NUMBER_LAYER], num_layer: usize, tree_density: f32) -> Self {
         if num_layer == 0 || (num_layer == 1 && layers[0].is_none()) {
             panic!("Should have at least one layer")
         }
@@ -64,10 +70,20 @@ impl BiomeConfig {
             terrain_scale,
             noise_config,
             layers,
-            num_layer
+            num_layer,
+            tree_density,
+            grass_tint: [1.; 3],
+            foliage_tint: [1.; 3],
         }
     }
 
+    /// Same biome, with the grass and the leaves drawn in these colors
+    pub fn with_tints(mut self, grass_tint: [f32; 3], foliage_tint: [f32; 3]) -> Self {
+        self.grass_tint = grass_tint;
+        self.foliage_tint = foliage_tint;
+        self
+    }
+
     pub fn get_block_at(&self, y: i32) -> Option<Block> {
 
         if self.layers.is_empty() || self.layers[0].is_none() || self.layers[0].as_ref().unwrap().start_y_from_top > y {
@@ -116,14 +132,8 @@ impl BiomeGenerator {
 
     /// For a given chunk, computes if it has a biome center and if it does of what kind and where
     fn get_chunk_biome_center(seed: u64, chunk_coord: [i64; 2]) -> (Option<[i32; 2]>, u64) {
-        // Generate a seed for deterministic PRNG
-        let mut hasher = std::hash::DefaultHasher::new();
-        // Hash the seed
-        seed.hash(&mut hasher);
-        // Hash the chunk coordinates
-        chunk_coord.hash(&mut hasher);
-        // Combine the hashes into a final value
-        let specific_seed_hash = hasher.finish();  
+        // Generate a seed for deterministic PRNG, mixing the seed with the chunk coordinates
+        let specific_seed_hash = mix_seed(seed, &chunk_coord);
 
         let mut rng: SmallRng = SmallRng::seed_from_u64(specific_seed_hash);
 
@@ -208,6 +218,15 @@ impl BiomeGenerator {
         }
     }
 
+    /// The biome of a column. The mountains and the oceans follow the closest biome center,
+    /// elsewhere the climate chooses between the plains, the forests, the deserts and the snowy lands.
+    pub fn biome_at(seed: u64, x: i32, z: i32) -> u64 {
+        match Self::find_closest_biome(seed, x, z) {
+            biome @ (MOUNTAIN | OCEAN) => biome,
+            _ => Climate::at(seed, x, z).biome(),
+        }
+    }
+
 }
 
 
@@ -220,13 +239,7 @@ use crate::world_generation::biome::*;
         let seed: u64 = 42;
         let chunk_coord: [i64; 2] = [0, 1];
 
-        let mut hasher = std::hash::DefaultHasher::new();
-        // Hash the seed
-        seed.hash(&mut hasher);
-        // Hash the chunk coordinates
-        chunk_coord.hash(&mut hasher);
-        // Combine the hashes into a final value
-        let specific_seed_hash = hasher.finish();  
+        let specific_seed_hash = mix_seed(seed, &chunk_coord);
 
         let mut rng: SmallRng = SmallRng::seed_from_u64(specific_seed_hash);
 
@@ -294,13 +307,7 @@ use crate::world_generation::biome::*;
                 let seed: u64 = 42;
                 let chunk_coord: [i64; 2] = [i, j];
                 // Generate a seed for deterministic PRNG
-                let mut hasher = std::hash::DefaultHasher::new();
-                // Hash the seed
-                seed.hash(&mut hasher);
-                // Hash the chunk coordinates
-                chunk_coord.hash(&mut hasher);
-                // Combine the hashes into a final value
-                let specific_seed_hash = hasher.finish();  
+                let specific_seed_hash = mix_seed(seed, &chunk_coord);
 
                 let mut rng: SmallRng = SmallRng::seed_from_u64(specific_seed_hash);
 
@@ -329,7 +336,7 @@ use crate::world_generation::biome::*;
             PerlinNoiseConfig{scale:0.0, amplitude: 0.0},
             PerlinNoiseConfig{scale:0.0, amplitude: 0.0},
             PerlinNoiseConfig{scale:0.0, amplitude: 0.0}, ];
-        let config = BiomeConfig::new("Test", 0.0, 0.0, noise_config, layers, num_layer);
+        let config = BiomeConfig::new("Test", 0.0, 0.0, noise_config, layers, num_layer, 0.0);
 
         let cube_height = 20;
         for i in 0..cube_height {
@@ -356,7 +363,7 @@ use crate::world_generation::biome::*;
             PerlinNoiseConfig{scale:0.0, amplitude: 0.0},
             PerlinNoiseConfig{scale:0.0, amplitude: 0.0},
             PerlinNoiseConfig{scale:0.0, amplitude: 0.0}, ];
-        let config = BiomeConfig::new("Test", 0.0, 0.0, noise_config, search_in, num_layer);
+        let config = BiomeConfig::new("Test", 0.0, 0.0, noise_config, search_in, num_layer, 0.0);
 
         let y = 9;
 
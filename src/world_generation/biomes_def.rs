@@ -3,10 +3,18 @@ use crate::block_kind::Block;
 use super::{biome::{BiomeConfig, BiomeLayer}, perlin::{PerlinNoiseConfig, MAX_LEVEL_NOISE}};
 
 /// Number of biomes
-pub const NUM_BIOMES: u64 = 4;
+pub const NUM_BIOMES: u64 = 6;
+
+/// Index of each biome in `BIOMES`
+pub const PLAIN: u64 = 0;
+pub const FOREST: u64 = 1;
+pub const MOUNTAIN: u64 = 2;
+pub const OCEAN: u64 = 3;
+pub const DESERT: u64 = 4;
+pub const SNOWY: u64 = 5;
 
 /// This file contains all the actual biomes data
-pub const BIOMES: [BiomeConfig; NUM_BIOMES as usize] = [
+pub static BIOMES: [BiomeConfig; NUM_BIOMES as usize] = [
     // Plain biome
     BiomeConfig {
         name: "Plain",
@@ -39,10 +47,13 @@ pub const BIOMES: [BiomeConfig; NUM_BIOMES as usize] = [
             Some(BiomeLayer {start_y_from_top: 1, block: Block::DIRT}),
             None, None, None, None, None, None
         ],
-        num_layer: 2
+        num_layer: 2,
+        tree_density: 0.004,
+        grass_tint: [1., 1., 1.],
+        foliage_tint: [1., 1., 1.],
     },
 
-    // Plain biome
+    // Forest biome
     BiomeConfig {
         name: "Forest",
         terrain_offset: 35.,
@@ -74,10 +85,13 @@ pub const BIOMES: [BiomeConfig; NUM_BIOMES as usize] = [
             Some(BiomeLayer {start_y_from_top: 1, block: Block::DIRT}),
             None, None, None, None, None, None
         ],
-        num_layer: 2
+        num_layer: 2,
+        tree_density: 0.04,
+        grass_tint: [0.8, 0.95, 0.75],
+        foliage_tint: [0.75, 0.9, 0.7],
     },
 
-    // Plain biome
+    // Mountain biome
     BiomeConfig {
         name: "Mountain",
         terrain_offset: 35.,
@@ -110,10 +124,13 @@ pub const BIOMES: [BiomeConfig; NUM_BIOMES as usize] = [
             Some(BiomeLayer {start_y_from_top:  2, block: Block::STONE}),
             None, None, None, None, None, 
         ],
-        num_layer: 3
+        num_layer: 3,
+        tree_density: 0.002,
+        grass_tint: [0.85, 0.95, 0.9],
+        foliage_tint: [0.85, 0.95, 0.9],
     },
 
-    // Plain biome
+    // Ocean biome
     BiomeConfig {
         name: "Ocean",
         terrain_offset: 30.,
@@ -144,7 +161,86 @@ pub const BIOMES: [BiomeConfig; NUM_BIOMES as usize] = [
             Some(BiomeLayer {start_y_from_top:  0, block: Block::SAND}),
             None, None, None, None, None, None, None
         ],
-        num_layer: 1
+        num_layer: 1,
+        tree_density: 0.,
+        grass_tint: [1., 1., 1.],
+        foliage_tint: [1., 1., 1.],
+    },
+
+    // Desert biome
+    BiomeConfig {
+        name: "Desert",
+        terrain_offset: 35.,
+        terrain_scale: 6.,
+        noise_config: [
+            PerlinNoiseConfig {
+                scale: 50.,
+                amplitude: 1.0,
+            },
+            PerlinNoiseConfig {
+                scale: 25.,
+                amplitude: 0.5,
+            },
+            PerlinNoiseConfig {
+                scale: 12.5,
+                amplitude: 0.25,
+            },
+            PerlinNoiseConfig {
+                scale: 6.25,
+                amplitude: 0.125,
+            },
+            PerlinNoiseConfig {
+                scale: 3.125,
+                amplitude: 0.0625,
+            },
+        ],
+        layers: [
+            Some(BiomeLayer {start_y_from_top:  0, block: Block::SAND}),
+            Some(BiomeLayer {start_y_from_top:  4, block: Block::STONE}),
+            None, None, None, None, None, None
+        ],
+        num_layer: 2,
+        tree_density: 0.,
+        grass_tint: [1., 0.85, 0.55],
+        foliage_tint: [0.95, 0.85, 0.55],
+    },
+
+    // Snowy biome
+    BiomeConfig {
+        name: "Snowy",
+        terrain_offset: 35.,
+        terrain_scale: 8.,
+        noise_config: [
+            PerlinNoiseConfig {
+                scale: 50.,
+                amplitude: 1.0,
+            },
+            PerlinNoiseConfig {
+                scale: 25.,
+                amplitude: 0.5,
+            },
+            PerlinNoiseConfig {
+                scale: 12.5,
+                amplitude: 0.25,
+            },
+            PerlinNoiseConfig {
+                scale: 6.25,
+                amplitude: 0.125,
+            },
+            PerlinNoiseConfig {
+                scale: 3.125,
+                amplitude: 0.0625,
+            },
+        ],
+        layers: [
+            Some(BiomeLayer {start_y_from_top:  0, block: Block::SNOWYGRASS}),
+            Some(BiomeLayer {start_y_from_top:  1, block: Block::DIRT}),
+            None, None, None, None, None, None
+        ],
+        num_layer: 2,
+        tree_density: 0.01,
+        grass_tint: [0.8, 0.9, 1.],
+        foliage_tint: [0.7, 0.85, 0.9],
     },
 ];
 
@@ -0,0 +1,242 @@
+use std::f32::consts::PI;
+use crate::primitives::math::mix_seed;
+
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+
+use crate::chunk::{Chunk, ChunkCoord, CHUNK_HEIGHT, CHUNK_SIZE};
+use crate::primitives::vector::Vector3;
+
+/// Mixed into the seed of the random generator of the caves, so that it differs from the other ones of the chunk
+const CAVES_SALT: u64 = 0x6361_7665;
+
+/// A tunnel of a cave: the centers and the radii [cube] of the spheres carved along it
+pub type Worm = Vec<([f32; 3], f32)>;
+
+/// How the caves are carved under the terrain, see `CaveCarver`
+#[derive(Clone, Debug, PartialEq)]
+pub struct CaveConfig {
+    /// The flat and demo worlds have no cave
+    pub enabled: bool,
+    /// Probability that a cave system starts in a chunk
+    pub frequency: f32,
+    /// Number of steps [cube] of the main tunnel of a cave, the branch is half as long
+    pub min_length: usize,
+    pub max_length: usize,
+    /// Radius [cube] of the tunnels, the widest in their middle
+    pub min_radius: f32,
+    pub max_radius: f32,
+    /// The caves start between the floor and this layer
+    pub max_start_height: f32,
+    /// The layers up to this one are never carved
+    pub floor: i32,
+    /// Number of cubes left under the surface, so that the caves do not open the ground
+    pub crust: i32,
+}
+
+impl Default for CaveConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            frequency: 0.25,
+            min_length: 24,
+            max_length: 48,
+            min_radius: 1.2,
+            max_radius: 2.8,
+            max_start_height: 28.,
+            floor: 1,
+            crust: 4,
+        }
+    }
+}
+
+impl CaveConfig {
+    pub fn disabled() -> Self {
+        Self { enabled: false, ..Self::default() }
+    }
+}
+
+/// Carves the caves of a world into its chunks, as tunnels wandering from one chunk to the next ("worms").
+/// The caves starting in a chunk only depend on the seed and the chunk, so each chunk carves its part
+/// of the caves of its neighbours as well: a cave system goes on whichever chunk is generated first.
+/// A cave branches in its middle, and the caves of different chunks cross each other, which connects them.
+pub struct CaveCarver<'a> {
+    config: &'a CaveConfig,
+}
+
+impl<'a> CaveCarver<'a> {
+    pub fn new(config: &'a CaveConfig) -> Self {
+        Self { config }
+    }
+
+    /// Number of chunks around a chunk that its caves can reach
+    fn reach(&self) -> i32 {
+        ((self.config.max_length as f32 + self.config.max_radius) / CHUNK_SIZE as f32).ceil() as i32
+    }
+
+    /// The tunnels of the cave system starting in the chunk at this position, if there is one
+    pub fn worms_of_chunk(&self, seed: u64, coord: ChunkCoord) -> Vec<Worm> {
+        let config = self.config;
+        if !config.enabled {
+            return Vec::new();
+        }
+        let mut rng = SmallRng::seed_from_u64(mix_seed(seed ^ CAVES_SALT, &[coord.0 as i64, coord.1 as i64]));
+        if rng.gen::<f32>() >= config.frequency {
+            return Vec::new();
+        }
+
+        let start = [
+            coord.0 as f32 + rng.gen_range(0. ..CHUNK_SIZE as f32),
+            rng.gen_range(config.floor as f32 + config.max_radius..config.max_start_height.max(config.floor as f32 + config.max_radius + 1.)),
+            coord.1 as f32 + rng.gen_range(0. ..CHUNK_SIZE as f32),
+        ];
+        let yaw = rng.gen_range(0. ..2. * PI);
+        let length = rng.gen_range(config.min_length..=config.max_length.max(config.min_length));
+        let main = self.walk(&mut rng, start, yaw, length);
+
+        // The branch leaves the middle of the main tunnel sideways
+        let (middle, _) = main[length / 2];
+        let side = if rng.gen::<bool>() { PI / 2. } else { -PI / 2. };
+        let branch = self.walk(&mut rng, middle, yaw + side, length / 2);
+        vec![main, branch]
+    }
+
+    /// A tunnel of `length` steps of one cube from `start`, turning slowly
+    fn walk(&self, rng: &mut SmallRng, start: [f32; 3], mut yaw: f32, length: usize) -> Worm {
+        let config = self.config;
+        let mut position = start;
+        let mut pitch: f32 = 0.;
+        let mut worm = Vec::with_capacity(length);
+        for step in 0..length {
+            let progress = step as f32 / length as f32;
+            let radius = config.min_radius + (config.max_radius - config.min_radius) * (PI * progress).sin();
+            worm.push((position, radius));
+            position[0] += yaw.cos() * pitch.cos();
+            position[1] += pitch.sin();
+            position[2] += yaw.sin() * pitch.cos();
+            yaw += rng.gen_range(-0.3..0.3);
+            pitch = (pitch + rng.gen_range(-0.2..0.2)).clamp(-0.6, 0.6);
+        }
+        worm
+    }
+
+    /// Removes the cubes of the chunk inside the caves of the world of this seed, but the floor and the crust
+    /// under the surface of each column. Returns the number of cubes removed.
+    pub fn carve(&self, chunk: &mut Chunk, seed: u64) -> usize {
+        if !self.config.enabled {
+            return 0;
+        }
+        let (x0, z0) = chunk.coord();
+        let s = CHUNK_SIZE as i32;
+        // Highest cube of each column, before anything is carved
+        let mut tops = [[-1; CHUNK_SIZE]; CHUNK_SIZE];
+        for (lx, row) in tops.iter_mut().enumerate() {
+            for (lz, top) in row.iter_mut().enumerate() {
+                let [x, _, z] = chunk.local_to_global(lx, 0, lz);
+                *top = (0..CHUNK_HEIGHT as i32).rev().find(|&y| chunk.cube_at(&Vector3::newi(x, y, z)).is_some()).unwrap_or(-1);
+            }
+        }
+
+        let mut carved = 0;
+        let reach = self.reach();
+        for dx in -reach..=reach {
+            for dz in -reach..=reach {
+                for worm in self.worms_of_chunk(seed, (x0 + dx * s, z0 + dz * s)) {
+                    for (center, radius) in worm {
+                        carved += self.carve_sphere(chunk, &tops, center, radius);
+                    }
+                }
+            }
+        }
+        carved
+    }
+
+    fn carve_sphere(&self, chunk: &mut Chunk, tops: &[[i32; CHUNK_SIZE]; CHUNK_SIZE], center: [f32; 3], radius: f32) -> usize {
+        let (x0, z0) = chunk.coord();
+        let s = CHUNK_SIZE as i32;
+        let low = |axis: usize| (center[axis] - radius).floor() as i32;
+        let high = |axis: usize| (center[axis] + radius).floor() as i32;
+        let mut carved = 0;
+        for x in low(0).max(x0)..=high(0).min(x0 + s - 1) {
+            for z in low(2).max(z0)..=high(2).min(z0 + s - 1) {
+                let top = tops[(x - x0) as usize][(z - z0) as usize];
+                for y in low(1).max(self.config.floor + 1)..=high(1).min(top - self.config.crust) {
+                    let offset = [x as f32 + 0.5 - center[0], y as f32 + 0.5 - center[1], z as f32 + 0.5 - center[2]];
+                    let inside = offset.iter().map(|d| d * d).sum::<f32>() <= radius * radius;
+                    let at = Vector3::newi(x, y, z);
+                    if inside && chunk.cube_at(&at).is_some() {
+                        chunk.destroy_cube(at);
+                        carved += 1;
+                    }
+                }
+            }
+        }
+        carved
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::block_kind::Block::STONE;
+    use crate::chunk::{Chunk, CHUNK_SIZE};
+    use crate::primitives::vector::Vector3;
+    use crate::world_generation::caves::{CaveCarver, CaveConfig};
+
+    /// A chunk of stone up to the layer 40
+    fn stone_chunk(coord: (i32, i32)) -> Chunk {
+        let mut chunk = Chunk::new([coord.0 as f32, coord.1 as f32]);
+        for y in 0..=40 {
+            chunk.fill_layer(y, STONE);
+        }
+        chunk
+    }
+
+    #[test]
+    fn test_caves_cross_the_chunks() {
+        let s = CHUNK_SIZE as i32;
+        let config = CaveConfig::default();
+        let carver = CaveCarver::new(&config);
+        let seed = 5;
+
+        // The first chunk around the origin with a cave
+        let coord = (0..20).map(|i| (i * s, 0)).find(|coord| !carver.worms_of_chunk(seed, *coord).is_empty()).unwrap();
+        let worms = carver.worms_of_chunk(seed, coord);
+        assert_eq!(worms, carver.worms_of_chunk(seed, coord));
+        assert_eq!(worms.len(), 2);
+
+        // Every chunk the cave goes through has its part of the tunnels, and the floor and the crust are kept
+        let mut chunks = std::collections::HashMap::new();
+        for (center, _) in worms.iter().flatten() {
+            let at = Vector3::new(center[0], center[1], center[2]).to_cube_coordinates();
+            if at.y() <= config.floor as f32 || at.y() > (40 - config.crust) as f32 {
+                continue;
+            }
+            let chunk_coord = ((at.x() as i32).div_euclid(s) * s, (at.z() as i32).div_euclid(s) * s);
+            let chunk = chunks.entry(chunk_coord).or_insert_with(|| {
+                let mut chunk = stone_chunk(chunk_coord);
+                assert!(carver.carve(&mut chunk, seed) > 0);
+                chunk
+            });
+            assert!(chunk.cube_at(&at).is_none());
+        }
+        for chunk in chunks.values() {
+            let [x, _, z] = chunk.local_to_global(0, 0, 0);
+            for y in [0, 1, 40, 40 - config.crust + 1] {
+                for (dx, dz) in [(0, 0), (7, 7), (5, 3)] {
+                    assert!(chunk.cube_at(&Vector3::newi(x + dx, y, z + dz)).is_some());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_disabled_caves() {
+        let config = CaveConfig::disabled();
+        let carver = CaveCarver::new(&config);
+        let mut chunk = stone_chunk((0, 0));
+        let before = chunk.clone();
+        assert_eq!(carver.carve(&mut chunk, 5), 0);
+        assert_eq!(chunk, before);
+        assert!((0..10).all(|i| carver.worms_of_chunk(5, (i * CHUNK_SIZE as i32, 0)).is_empty()));
+    }
+}
@@ -0,0 +1,69 @@
+use super::biomes_def::{DESERT, FOREST, PLAIN, SNOWY};
+use super::perlin::{PerlinNoise, PerlinNoiseConfig};
+
+/// Size [cube] of the hot and cold, wet and dry areas
+const CLIMATE_SCALE: f32 = 250.;
+
+/// Offsets of the seeds of the climate noises from the seed of the world, so that they differ from the terrain
+const TEMPERATURE_SEED: u64 = 1 << 32;
+const HUMIDITY_SEED: u64 = 2 << 32;
+
+/// Below this temperature the land is snowy
+const COLD: f32 = -0.25;
+/// Above this temperature, and below `DRY`, the land is a desert
+const HOT: f32 = 0.25;
+const DRY: f32 = 0.;
+/// Above this humidity, trees grow into a forest
+const WET: f32 = 0.15;
+
+/// Temperature and humidity of a column, both noises going from about -1 to 1
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Climate {
+    pub temperature: f32,
+    pub humidity: f32,
+}
+
+impl Climate {
+    /// The climate of the column of the world of this seed
+    pub fn at(seed: u64, x: i32, z: i32) -> Self {
+        let config = PerlinNoiseConfig { scale: CLIMATE_SCALE, amplitude: 1. };
+        let coord = [x as f32, z as f32];
+        Self {
+            temperature: PerlinNoise::new(seed.wrapping_add(TEMPERATURE_SEED), config.clone()).at(coord),
+            humidity: PerlinNoise::new(seed.wrapping_add(HUMIDITY_SEED), config).at(coord),
+        }
+    }
+
+    /// The biome growing in this climate, see `BiomeGenerator::biome_at`
+    pub fn biome(&self) -> u64 {
+        if self.temperature < COLD {
+            SNOWY
+        } else if self.temperature > HOT && self.humidity < DRY {
+            DESERT
+        } else if self.humidity > WET {
+            FOREST
+        } else {
+            PLAIN
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::world_generation::biomes_def::{DESERT, FOREST, PLAIN, SNOWY};
+    use crate::world_generation::climate::Climate;
+
+    #[test]
+    fn test_biome_of_the_climate() {
+        let climate = |temperature, humidity| Climate { temperature, humidity }.biome();
+        assert_eq!(climate(-0.5, 0.5), SNOWY);
+        assert_eq!(climate(0.5, -0.5), DESERT);
+        assert_eq!(climate(0.5, 0.5), FOREST);
+        assert_eq!(climate(0., 0.), PLAIN);
+
+        // The climate only depends on the seed and the column, and changes slowly
+        assert_eq!(Climate::at(3, 10, -20), Climate::at(3, 10, -20));
+        let (a, b) = (Climate::at(3, 10, -20), Climate::at(3, 11, -20));
+        assert!((a.temperature - b.temperature).abs() < 0.05);
+    }
+}
@@ -0,0 +1,177 @@
+use crate::block_kind::Block;
+use crate::block_kind::Block::{DIRT, GRASS, GRAVEL, SAND, SNOWYGRASS, STONE};
+use crate::chunk::{Chunk, ChunkCoord, CHUNK_FLOOR, CHUNK_HEIGHT, CHUNK_SIZE};
+use crate::world::World;
+use crate::world_generation::caves::CaveConfig;
+use crate::world_generation::ores::OreConfig;
+use crate::world_generation::perlin::OctavesConfig;
+use serde::{Deserialize, Serialize};
+use crate::world_generation::world_generator::{TerrainConfig, WorldGenerator};
+
+/// How the chunks of a world are generated, see `GeneratorPreset` for the ones offered at the creation of a world.
+/// A chunk must only depend on the generator and its position, so that the chunks generated later fit the first ones.
+pub trait ChunkGenerator {
+    fn generate_chunk(&self, coord: ChunkCoord) -> Chunk;
+
+    /// Creates a world of `n_chunks` in each direction, as in `WorldGenerator::create_new_flat_world`
+    fn create_world(&self, n_chunks: i32) -> World {
+        let s = CHUNK_SIZE as i32;
+        let mut chunks = vec![];
+        for i in -n_chunks..n_chunks + 1 {
+            for j in -n_chunks..n_chunks + 1 {
+                chunks.push(self.generate_chunk((i * s, j * s)));
+            }
+        }
+        World::new(chunks)
+    }
+}
+
+/// The hills of the biomes, with trees, ores and caves, see `WorldGenerator::generate_chunk`
+pub struct NoiseGenerator {
+    pub seed: u64,
+    pub ores: OreConfig,
+}
+
+impl NoiseGenerator {
+    /// The hills of this seed, with the default ores
+    pub fn new(seed: u64) -> Self {
+        Self { seed, ores: OreConfig::default() }
+    }
+}
+
+impl ChunkGenerator for NoiseGenerator {
+    fn generate_chunk(&self, coord: ChunkCoord) -> Chunk {
+        WorldGenerator::generate_chunk_with_ores(self.seed, coord, &self.ores)
+    }
+
+    /// The noise is shared by all the chunks, which is faster
+    fn create_world(&self, n_chunks: i32) -> World {
+        WorldGenerator::create_new_random_world_with_caves(n_chunks, self.seed, &CaveConfig::default(), &self.ores)
+    }
+}
+
+impl ChunkGenerator for TerrainConfig {
+    fn generate_chunk(&self, coord: ChunkCoord) -> Chunk {
+        WorldGenerator::terrain_chunk(self, coord)
+    }
+}
+
+impl TerrainConfig {
+    /// Hills and valleys 2.5 times as high as the default ones, the peaks are cut at the top of the chunks
+    pub fn amplified(seed: u64) -> Self {
+        let default = Self::default();
+        Self {
+            seed,
+            base_height: 2.5 * CHUNK_FLOOR as f32,
+            noise: OctavesConfig { amplitude: 2.5 * default.noise.amplitude, ..default.noise },
+            ..default
+        }
+    }
+}
+
+/// The same layers of blocks everywhere, which makes deterministic worlds for the tests and the demos
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SuperflatGenerator {
+    /// The blocks and the number of layers [cube] of each of them, from the bottom
+    layers: Vec<(Block, usize)>,
+}
+
+impl Default for SuperflatGenerator {
+    /// Dirt with a layer of grass on top, at the floor of the chunks
+    fn default() -> Self {
+        Self::new(vec![(DIRT, CHUNK_FLOOR), (GRASS, 1)])
+    }
+}
+
+impl SuperflatGenerator {
+    pub fn new(layers: Vec<(Block, usize)>) -> Self {
+        Self { layers }
+    }
+
+    /// The layers offered at the creation of a flat world, with their name, the default ones first
+    pub fn presets() -> Vec<(&'static str, Self)> {
+        vec![
+            ("grassland", Self::default()),
+            ("desert", Self::new(vec![(STONE, CHUNK_FLOOR - 3), (SAND, 4)])),
+            ("quarry", Self::new(vec![(STONE, CHUNK_FLOOR), (GRAVEL, 1)])),
+            ("snowfield", Self::new(vec![(DIRT, CHUNK_FLOOR), (SNOWYGRASS, 1)])),
+        ]
+    }
+
+    /// Number of layers [cube] of the ground, it never goes above the chunks
+    pub fn height(&self) -> usize {
+        self.layers.iter().map(|(_, n)| n).sum::<usize>().min(CHUNK_HEIGHT)
+    }
+}
+
+impl ChunkGenerator for SuperflatGenerator {
+    fn generate_chunk(&self, coord: ChunkCoord) -> Chunk {
+        let mut chunk = Chunk::new([coord.0 as f32, coord.1 as f32]);
+        let blocks = self.layers.iter().flat_map(|(block, n)| std::iter::repeat(*block).take(*n));
+        for (y, block) in blocks.take(CHUNK_HEIGHT).enumerate() {
+            chunk.fill_layer(y, block);
+        }
+        chunk
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::block_kind::Block::{COALORE, DIAMONDORE, DIRT, GOLDORE, GRASS, IRONORE, SAND, STONE};
+    use crate::chunk::{Chunk, CHUNK_FLOOR, CHUNK_HEIGHT, CHUNK_SIZE};
+    use crate::primitives::vector::Vector3;
+    use crate::world_generation::generators::{ChunkGenerator, NoiseGenerator, SuperflatGenerator};
+    use crate::world_generation::ores::OreConfig;
+    use crate::world_generation::world_generator::{TerrainConfig, WorldGenerator};
+
+    #[test]
+    fn test_superflat_layers() {
+        let generator = SuperflatGenerator::new(vec![(STONE, 2), (SAND, 3)]);
+        assert_eq!(generator.height(), 5);
+        let world = generator.create_world(1);
+        let s = CHUNK_SIZE as i32;
+        for (x, z) in [(-s, -s), (0, 0), (2 * s - 1, 5)] {
+            let column: Vec<_> = (0..7).map(|y| world.block_at(&Vector3::newi(x, y, z))).collect();
+            assert_eq!(column, vec![Some(STONE), Some(STONE), Some(SAND), Some(SAND), Some(SAND), None, None]);
+        }
+
+        // The default layers make the flat world, and the ground never goes above the chunks
+        let default = SuperflatGenerator::default();
+        assert_eq!(default.height(), CHUNK_FLOOR + 1);
+        // The neighbors are only counted once the chunk is in a world, so only the blocks are compared
+        let blocks = |chunk: &Chunk| chunk.cubes_iter().map(|c| c.map(|c| *c.block())).collect::<Vec<_>>();
+        assert_eq!(blocks(&default.generate_chunk((0, 0))), blocks(&WorldGenerator::create_new_flat_world(0).get_chunk((0, 0)).unwrap()));
+        let world = SuperflatGenerator::new(vec![(DIRT, 2 * CHUNK_HEIGHT), (GRASS, 1)]).create_world(0);
+        assert_eq!(world.block_at(&Vector3::newi(0, CHUNK_HEIGHT as i32 - 1, 0)), Some(DIRT));
+    }
+
+    #[test]
+    fn test_noise_generator_uses_its_ores() {
+        let ores = |chunk: &Chunk| {
+            chunk.cubes_iter().flatten().filter(|c| [COALORE, IRONORE, GOLDORE, DIAMONDORE].contains(c.block())).count()
+        };
+        let s = CHUNK_SIZE as i32;
+        let coords: Vec<_> = (0..16).map(|i| (i * s, 0)).collect();
+        let with_ores = NoiseGenerator::new(3);
+        let without = NoiseGenerator { ores: OreConfig::none(), ..NoiseGenerator::new(3) };
+        assert!(coords.iter().map(|coord| ores(&with_ores.generate_chunk(*coord))).sum::<usize>() > 0);
+        assert!(coords.iter().all(|coord| ores(&without.generate_chunk(*coord)) == 0));
+        assert_eq!(ores(&without.create_world(0).get_chunk((0, 0)).unwrap()), 0);
+    }
+
+    #[test]
+    fn test_amplified_terrain_is_higher() {
+        let mean_height = |config: &TerrainConfig| -> f32 {
+            let columns: Vec<_> = (0..16).flat_map(|i| (0..16).map(move |j| (i * 4 * CHUNK_SIZE as i32, j * 4 * CHUNK_SIZE as i32))).collect();
+            columns.iter().map(|coord| WorldGenerator::heightmap(config, *coord)[0][0] as f32).sum::<f32>() / columns.len() as f32
+        };
+        let spread = |config: &TerrainConfig| -> i32 {
+            let heights: Vec<i32> = (0..64).map(|i| WorldGenerator::heightmap(config, (i * CHUNK_SIZE as i32, 0))[0][0]).collect();
+            heights.iter().max().unwrap() - heights.iter().min().unwrap()
+        };
+        let default = TerrainConfig { seed: 9, ..Default::default() };
+        let amplified = TerrainConfig::amplified(9);
+        assert!(mean_height(&amplified) > mean_height(&default));
+        assert!(spread(&amplified) > spread(&default));
+    }
+}
@@ -0,0 +1,134 @@
+use crate::primitives::math::mix_seed;
+
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+
+use crate::block_kind::Block;
+use crate::chunk::{Chunk, CHUNK_HEIGHT, CHUNK_SIZE};
+use crate::primitives::vector::Vector3;
+
+/// Mixed into the seed of the random generator of the ores, so that it differs from the other ones of the chunk
+const ORES_SALT: u64 = 0x6f72_6573;
+
+/// The six steps a vein can take from one cube to the next
+const STEPS: [[i32; 3]; 6] = [[1, 0, 0], [-1, 0, 0], [0, 1, 0], [0, -1, 0], [0, 0, 1], [0, 0, -1]];
+
+/// Clusters of one ore, scattered in a range of layers
+#[derive(Clone, Debug, PartialEq)]
+pub struct OreVein {
+    pub block: Block,
+    /// Layers [cube] the veins start in, the lowest included and the highest excluded
+    pub min_height: i32,
+    pub max_height: i32,
+    /// Average number of veins per chunk, the rarer ores have less than one
+    pub veins_per_chunk: f32,
+    /// Number of cubes of a vein
+    pub size: usize,
+}
+
+/// The ores of the ground, see `OreConfig::scatter`
+#[derive(Clone, Debug, PartialEq)]
+pub struct OreConfig {
+    pub veins: Vec<OreVein>,
+}
+
+impl Default for OreConfig {
+    /// The deeper, the more precious: the coal is everywhere under the surface, the diamonds only near the bottom
+    fn default() -> Self {
+        Self {
+            veins: vec![
+                OreVein { block: Block::COALORE, min_height: 2, max_height: 40, veins_per_chunk: 6., size: 10 },
+                OreVein { block: Block::IRONORE, min_height: 2, max_height: 28, veins_per_chunk: 4., size: 6 },
+                OreVein { block: Block::GOLDORE, min_height: 2, max_height: 16, veins_per_chunk: 1., size: 5 },
+                OreVein { block: Block::DIAMONDORE, min_height: 2, max_height: 10, veins_per_chunk: 0.4, size: 4 },
+            ],
+        }
+    }
+}
+
+impl OreConfig {
+    /// Without any ore
+    pub fn none() -> Self {
+        Self { veins: Vec::new() }
+    }
+
+    /// Whether a vein replaces this block: the stone, and the dirt in the biomes without stone
+    fn is_ground(block: Block) -> bool {
+        matches!(block, Block::STONE | Block::DIRT)
+    }
+
+    /// Replaces the ground of the chunk by veins of ore, each one wandering from a random cube of its range.
+    /// The veins only depend on the seed and the chunk, and stay inside of it. Returns the number of cubes replaced.
+    pub fn scatter(&self, chunk: &mut Chunk, seed: u64) -> usize {
+        let coord = chunk.coord();
+        let mut rng = SmallRng::seed_from_u64(mix_seed(seed ^ ORES_SALT, &[coord.0 as i64, coord.1 as i64]));
+
+        let mut replaced = 0;
+        for vein in &self.veins {
+            let height = vein.min_height.max(0)..vein.max_height.min(CHUNK_HEIGHT as i32);
+            // The fraction of a vein is the probability of one more
+            let n_veins = vein.veins_per_chunk.floor() as usize + (rng.gen::<f32>() < vein.veins_per_chunk.fract()) as usize;
+            for _ in 0..n_veins {
+                if height.is_empty() {
+                    break;
+                }
+                let [x, _, z] = chunk.local_to_global(rng.gen_range(0..CHUNK_SIZE), 0, rng.gen_range(0..CHUNK_SIZE));
+                let mut cell = [x, rng.gen_range(height.clone()), z];
+                for _ in 0..vein.size {
+                    let at = Vector3::newi(cell[0], cell[1], cell[2]);
+                    let is_ground = chunk.is_in(&at) && chunk.cube_at(&at).is_some_and(|cube| Self::is_ground(*cube.block()));
+                    if is_ground {
+                        chunk.add_cube(at, vein.block, 0);
+                        replaced += 1;
+                    }
+                    let step = STEPS[rng.gen_range(0..STEPS.len())];
+                    cell = [0, 1, 2].map(|axis| cell[axis] + step[axis]);
+                }
+            }
+        }
+        replaced
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::block_kind::Block;
+    use crate::chunk::{Chunk, CHUNK_HEIGHT};
+    use crate::world_generation::ores::{OreConfig, OreVein};
+
+    fn ores_per_height(chunk: &Chunk, block: Block) -> Vec<i32> {
+        chunk.cubes_iter().flatten().filter(|cube| *cube.block() == block).map(|cube| cube.position()[1] as i32).collect()
+    }
+
+    #[test]
+    fn test_veins_in_their_layers() {
+        let mut chunk = Chunk::new([16., -32.]);
+        for y in 0..40 {
+            chunk.fill_layer(y, Block::STONE);
+        }
+        let config = OreConfig {
+            veins: vec![OreVein { block: Block::DIAMONDORE, min_height: 5, max_height: 8, veins_per_chunk: 3., size: 4 }],
+        };
+        let mut again = chunk.clone();
+        let replaced = config.scatter(&mut chunk, 9);
+        assert!(replaced > 0);
+        // Same seed, same veins
+        assert_eq!(config.scatter(&mut again, 9), replaced);
+        assert_eq!(chunk, again);
+
+        // A vein only wanders a few cubes away from its layers
+        let heights = ores_per_height(&chunk, Block::DIAMONDORE);
+        assert_eq!(heights.len(), replaced);
+        assert!(heights.iter().all(|y| (5 - 4..8 + 4).contains(y)));
+    }
+
+    #[test]
+    fn test_ores_only_replace_the_ground() {
+        let mut chunk = Chunk::new([0., 0.]);
+        for y in 0..CHUNK_HEIGHT {
+            chunk.fill_layer(y, Block::SAND);
+        }
+        assert_eq!(OreConfig::default().scatter(&mut chunk, 3), 0);
+        assert_eq!(OreConfig::none().scatter(&mut Chunk::new([0., 0.]), 3), 0);
+    }
+}
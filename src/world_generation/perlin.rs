@@ -11,7 +11,7 @@ use rand::distributions::Open01;
 use rand::rngs::SmallRng;
 use rand::{Rng, SeedableRng};
 use std::collections::HashMap;
-use std::hash::{Hash, Hasher};
+use crate::primitives::math::mix_seed;
 use std::iter::zip;
 
 pub const MAX_LEVEL_NOISE: usize = 5;
@@ -23,10 +23,48 @@ pub struct PerlinNoiseConfig {
     pub amplitude: f32,
 }
 
+/// Layered noise described by its octaves: starting from `frequency` [1/cube] and `amplitude`,
+/// each octave has `lacunarity` times the frequency and `persistence` times the amplitude of the previous one.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OctavesConfig {
+    pub octaves: usize,
+    pub frequency: f32,
+    pub amplitude: f32,
+    pub persistence: f32,
+    pub lacunarity: f32,
+}
+
+impl Default for OctavesConfig {
+    fn default() -> Self {
+        Self {
+            octaves: 4,
+            frequency: 1. / 64.,
+            amplitude: 12.,
+            persistence: 0.5,
+            lacunarity: 2.,
+        }
+    }
+}
+
+impl OctavesConfig {
+    /// The configuration of the Perlin noise of each octave
+    pub fn layers(&self) -> Vec<PerlinNoiseConfig> {
+        let mut frequency = self.frequency;
+        let mut amplitude = self.amplitude;
+        let mut layers = Vec::with_capacity(self.octaves);
+        for _ in 0..self.octaves {
+            layers.push(PerlinNoiseConfig { scale: 1. / frequency, amplitude });
+            frequency *= self.lacunarity;
+            amplitude *= self.persistence;
+        }
+        layers
+    }
+}
+
 /// Class containing the different scales of Perlin noise,
 /// combines them to return a single value for each querried coord.
 pub struct MultiscalePerlinNoise {
-    perlin_noises: [PerlinNoise; MAX_LEVEL_NOISE],
+    perlin_noises: Vec<PerlinNoise>,
 }
 
 impl MultiscalePerlinNoise {
@@ -34,17 +72,25 @@ impl MultiscalePerlinNoise {
     /// These values will change the world aspect.
     pub fn new(seed: u64, perlin_conf: [PerlinNoiseConfig; MAX_LEVEL_NOISE]) -> Self {
         
+        Self::from_layers(seed, perlin_conf.to_vec())
+    }
+
+    /// Same as `new`, with any number of scales
+    pub fn from_layers(seed: u64, perlin_conf: Vec<PerlinNoiseConfig>) -> Self {
         Self {
             perlin_noises: perlin_conf
                 .into_iter()
                 .enumerate()
                 .map(|(i, conf)| PerlinNoise::new(seed + (i as u64), conf))
-                .collect::<Vec<PerlinNoise>>()
-                .try_into()
-                .unwrap(),
+                .collect(),
         }
     }
 
+    /// Creates the noise with one scale per octave
+    pub fn from_octaves(seed: u64, config: &OctavesConfig) -> Self {
+        Self::from_layers(seed, config.layers())
+    }
+
     /// Returns the noise value at a given coordinate of the world
     pub fn at(&mut self, coord: [f32; 2]) -> f32 {
         let mut value: f32 = 0.0;
@@ -57,8 +103,8 @@ impl MultiscalePerlinNoise {
     }
 
     pub fn change_config(&mut self, new_conf: [PerlinNoiseConfig; MAX_LEVEL_NOISE]) {
-        for i in 0..MAX_LEVEL_NOISE {
-            self.perlin_noises[i].update_config(new_conf[i].clone());
+        for (noise, conf) in self.perlin_noises.iter_mut().zip(new_conf) {
+            noise.update_config(conf);
         }
     }
 }
@@ -129,14 +175,8 @@ impl PerlinNoise {
 
 /// Returns a deterministic random gradient for a given coord and seed
 fn random_gradient(coord: &[i64; 2], seed: u64) -> [f32; 2] {
-    // Generate a seed for deterministic PRNG
-    let mut hasher = std::hash::DefaultHasher::new();
-    // Hash the seed
-    seed.hash(&mut hasher);
-    // Hash the chunk coordinates
-    coord.hash(&mut hasher);
-    // Combine the hashes into a final value
-    let specific_seed_hash = hasher.finish();  
+    // Generate a seed for deterministic PRNG, mixing the seed with the coordinates
+    let specific_seed_hash = mix_seed(seed, coord);
 
     let mut rng: SmallRng = SmallRng::seed_from_u64(specific_seed_hash);
 
@@ -271,6 +311,27 @@ mod tests {
         assert_eq!(noise_a, noise_b);
     }
 
+    #[test]
+    fn test_octaves_layers() {
+        let config = OctavesConfig {
+            octaves: 3,
+            frequency: 1. / 32.,
+            amplitude: 8.,
+            persistence: 0.5,
+            lacunarity: 2.,
+        };
+        let layers = config.layers();
+        assert_eq!(layers.len(), 3);
+        assert_eq!(layers.iter().map(|l| l.scale).collect::<Vec<f32>>(), vec![32., 16., 8.]);
+        assert_eq!(layers.iter().map(|l| l.amplitude).collect::<Vec<f32>>(), vec![8., 4., 2.]);
+
+        // The noise never goes beyond the sum of the amplitudes
+        let mut noise = MultiscalePerlinNoise::from_octaves(42, &config);
+        for x in 0..100 {
+            assert!(noise.at([x as f32 * 1.7, x as f32 * 0.3]).abs() <= 14.);
+        }
+    }
+
     #[test]
     fn show_various_gradients() {
         let seed: u64 = 42;
@@ -0,0 +1,135 @@
+use serde::{Deserialize, Serialize};
+use strum::EnumIter;
+use crate::world::World;
+use crate::world_generation::generators::{ChunkGenerator, NoiseGenerator, SuperflatGenerator};
+use crate::world_generation::world_generator::TerrainConfig;
+use crate::world_meta::WorldMeta;
+
+/// Number of chunks generated in each direction when a world is created, the others come as the players move
+pub const INITIAL_CHUNKS: i32 = 5;
+
+/// How the first chunks of a new world are generated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, EnumIter)]
+#[serde(rename_all = "snake_case")]
+pub enum GeneratorPreset {
+    /// Hills, see `WorldGenerator::create_new_random_world`
+    #[default]
+    Random,
+    /// Hills and valleys, see `WorldGenerator::create_new_terrain_world`
+    Terrain,
+    /// The same layers everywhere, grassland by default, see `WorldMeta::flat_layers`
+    Flat,
+    /// Much higher hills and deeper valleys, see `TerrainConfig::amplified`
+    Amplified,
+}
+
+impl GeneratorPreset {
+    pub fn name(&self) -> &'static str {
+        match self {
+            GeneratorPreset::Random => "random",
+            GeneratorPreset::Terrain => "terrain",
+            GeneratorPreset::Flat => "flat",
+            GeneratorPreset::Amplified => "amplified",
+        }
+    }
+
+    /// The next preset, back to the first one after the last
+    pub fn next(&self) -> Self {
+        match self {
+            GeneratorPreset::Random => GeneratorPreset::Terrain,
+            GeneratorPreset::Terrain => GeneratorPreset::Flat,
+            GeneratorPreset::Flat => GeneratorPreset::Amplified,
+            GeneratorPreset::Amplified => GeneratorPreset::Random,
+        }
+    }
+
+    /// Generates the first chunks of a world, the seed and the preset are kept in its settings
+    pub fn generate(&self, seed: u64) -> World {
+        self.generate_around(seed, INITIAL_CHUNKS)
+    }
+
+    /// Generates the first chunks of a world, `n_chunks` in each direction as in `WorldGenerator::create_new_flat_world`.
+    /// A flat world gets the default layers.
+    pub fn generate_around(&self, seed: u64, n_chunks: i32) -> World {
+        WorldMeta { seed, generator: *self, ..Default::default() }.generate_world(n_chunks)
+    }
+
+    /// The generator of the chunks of the world of this seed, the flat worlds are made of these layers
+    pub fn generator(&self, seed: u64, flat_layers: &SuperflatGenerator) -> Box<dyn ChunkGenerator> {
+        match self {
+            GeneratorPreset::Random => Box::new(NoiseGenerator::new(seed)),
+            GeneratorPreset::Terrain => Box::new(TerrainConfig { seed, ..Default::default() }),
+            GeneratorPreset::Flat => Box::new(flat_layers.clone()),
+            GeneratorPreset::Amplified => Box::new(TerrainConfig::amplified(seed)),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use strum::IntoEnumIterator;
+    use rand::rngs::SmallRng;
+    use crate::block_kind::Block;
+    use crate::block_kind::Block::{GLASS, GRASS};
+    use crate::chunk::{Chunk, ChunkCoord, CHUNK_SIZE};
+    use crate::primitives::vector::Vector3;
+    use crate::world_generation::generators::{ChunkGenerator, SuperflatGenerator};
+    use crate::world_generation::preset::GeneratorPreset;
+    use crate::world_meta::WorldMeta;
+
+    fn blocks(chunk: &Chunk) -> Vec<Option<([f32; 3], Block)>> {
+        chunk.cubes_iter().map(|c| c.map(|c| (c.position().as_array(), *c.block()))).collect()
+    }
+
+    #[test]
+    fn test_same_seed_same_world() {
+        let far = (7 * CHUNK_SIZE as i32, -4 * CHUNK_SIZE as i32);
+        for preset in GeneratorPreset::iter() {
+            let world = preset.generate_around(1234, 1);
+            assert_eq!(world.meta().seed, 1234);
+            assert_eq!(world.meta().generator, preset);
+
+            // The chunks generated later continue the first ones
+            let first = world.get_chunk((0, 0)).unwrap();
+            assert_eq!(blocks(&world.meta().generate_chunk((0, 0))), blocks(&first));
+            assert_eq!(blocks(&world.meta().generate_chunk(far)), blocks(&world.meta().generate_chunk(far)));
+        }
+        // Another seed gives another world
+        let [a, b] = [1234, 1235].map(|seed| blocks(&WorldMeta::new(String::new(), seed).generate_chunk(far)));
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn test_flat_worlds_keep_their_layers() {
+        let (name, layers) = SuperflatGenerator::presets().swap_remove(1);
+        assert_ne!(layers, SuperflatGenerator::default(), "{name}");
+        let meta = WorldMeta { generator: GeneratorPreset::Flat, flat_layers: layers.clone(), ..Default::default() };
+        let world = meta.generate_world(0);
+        assert_eq!(world.meta().flat_layers, layers);
+
+        // The chunks generated later, after a save, have the same layers
+        let saved: WorldMeta = serde_json::from_str(&serde_json::to_string(world.meta()).unwrap()).unwrap();
+        let far = (5 * CHUNK_SIZE as i32, 0);
+        assert_eq!(blocks(&saved.generate_chunk(far)), blocks(&layers.generate_chunk(far)));
+        assert_eq!(blocks(&saved.generate_chunk((0, 0))), blocks(&world.get_chunk((0, 0)).unwrap()));
+    }
+
+    #[test]
+    fn test_decorated_chunks_follow_the_preset() {
+        let marker = |chunk: &mut Chunk, _: ChunkCoord, _: &mut SmallRng| {
+            let [x, y, z] = chunk.local_to_global(1, 40, 1);
+            chunk.add_cube(Vector3::newi(x, y, z), GLASS, 0);
+        };
+        let mut world = GeneratorPreset::Flat.generate_around(5, 0);
+        let coord = (3 * CHUNK_SIZE as i32, 0);
+        world.generate_chunk_decorated(coord, &marker);
+
+        // The flat grassland, with the marker on it
+        let top = SuperflatGenerator::default().height() as f32;
+        let x0 = coord.0 as f32;
+        assert_eq!(world.cube_at(Vector3::new(x0, top - 1., 0.)).map(|c| *c.block()), Some(GRASS));
+        assert!(world.cube_at(Vector3::new(x0, top, 0.)).is_none());
+        assert_eq!(world.cube_at(Vector3::new(x0 + 1., 40., 1.)).map(|c| *c.block()), Some(GLASS));
+    }
+}
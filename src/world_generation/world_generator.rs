@@ -1,6 +1,10 @@
 use super::biome::BiomeGenerator;
 use super::biomes_def::{BIOMES, SINGLE_NOISE_CONFIG, BASE_BIOME_CONFIG};
+use super::caves::{CaveCarver, CaveConfig};
+use super::generators::{ChunkGenerator, SuperflatGenerator};
+use super::ores::OreConfig;
 use super::perlin::MultiscalePerlinNoise;
+use super::perlin::OctavesConfig;
 use super::perlin::PerlinNoiseConfig;
 use crate::block_kind::Block;
 use crate::block_kind::Block::DIRT;
@@ -8,11 +12,58 @@ use crate::block_kind::Block::GRASS;
 use crate::block_kind::Block::COBBELSTONE;
 use crate::block_kind::Block::OAKLOG;
 use crate::block_kind::Block::OAKLEAVES;
+use crate::block_kind::Block::SNOWYGRASS;
+use crate::block_kind::Block::STONE;
 use crate::chunk::Chunk;
+use crate::chunk::ChunkCoord;
 use crate::chunk::CHUNK_FLOOR;
+use crate::chunk::CHUNK_HEIGHT;
 use crate::chunk::CHUNK_SIZE;
 use crate::primitives::vector::Vector3;
+use crate::structure::{Structure, StructureRotation};
 use crate::world::World;
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+use crate::primitives::math::mix_seed;
+
+/// Seed of the worlds when none is given
+pub const DEFAULT_SEED: u64 = 42;
+
+/// Number of dirt cubes below the grass of the terrain, the rest is stone
+const DIRT_DEPTH: i32 = 3;
+
+/// Heights [cube] of the trunks of the trees
+const TREE_HEIGHTS: std::ops::RangeInclusive<i32> = 4..=6;
+
+/// Mixed into the seed of the random generator of the structures, so that it differs from the one of the decorators
+const STRUCTURES_SALT: u64 = 0x7265_6573;
+
+/// Shape of the terrain made of hills and valleys, see `WorldGenerator::heightmap`
+#[derive(Clone, Debug, PartialEq)]
+pub struct TerrainConfig {
+    pub seed: u64,
+    /// Height [cube] of the terrain where the noise is 0
+    pub base_height: f32,
+    pub noise: OctavesConfig,
+    /// Caves under the terrain, see `CaveConfig::disabled` for none
+    pub caves: CaveConfig,
+    pub ores: OreConfig,
+}
+
+impl Default for TerrainConfig {
+    fn default() -> Self {
+        Self {
+            seed: DEFAULT_SEED,
+            base_height: 2. * CHUNK_FLOOR as f32,
+            noise: OctavesConfig::default(),
+            caves: CaveConfig::default(),
+            ores: OreConfig::default(),
+        }
+    }
+}
+
+/// Height of the terrain of each column of a chunk, indexed as `[lx][lz]`
+pub type Heightmap = [[i32; CHUNK_SIZE]; CHUNK_SIZE];
 
 /// Class which manages the generation of a new world
 pub struct WorldGenerator {}
@@ -20,62 +71,248 @@ pub struct WorldGenerator {}
 impl WorldGenerator {
     /// Creates a simple world with hills
     pub fn create_new_random_world(n_chunks: i32) -> World {
-        //let mut noise = PerlinNoise::new(121, 32.);
-        let seed: u64 = 42;
-        let mut noise = MultiscalePerlinNoise::new(seed, BIOMES[0].noise_config.clone());
+        Self::create_new_random_world_with_seed(n_chunks, DEFAULT_SEED)
+    }
+
+    /// Creates a simple world with hills, from the given seed
+    pub fn create_new_random_world_with_seed(n_chunks: i32, seed: u64) -> World {
+        Self::create_new_random_world_with_caves(n_chunks, seed, &CaveConfig::default(), &OreConfig::default())
+    }
+
+    /// Creates a simple world with hills from the given seed, with these caves and these ores under them
+    pub fn create_new_random_world_with_caves(n_chunks: i32, seed: u64, caves: &CaveConfig, ores: &OreConfig) -> World {
+        let mut noise = Self::noise(seed);
 
-        let s = CHUNK_SIZE as f32;
+        let s = CHUNK_SIZE as i32;
         let mut chunks = vec![];
 
         // Yes this is slow, but it will be fine for now
         for i in -n_chunks..n_chunks + 1 {
             for j in -n_chunks..n_chunks + 1 {
-                let x0 = i as f32 * s;
-                let z0 = j as f32 * s;
-                let mut chunk = Chunk::new([x0, z0]);
-
-                // get the height from the perlin noise for each block
-                for x in 0..8 {
-                    for z in 0..8 {
-                        let biome_t: u64 = BiomeGenerator::find_closest_biome(seed, x + x0 as i32, z + z0 as i32);
-                        
-                        let biome_config = &BIOMES[biome_t as usize];
-
-                        if SINGLE_NOISE_CONFIG {
-                            noise.change_config(BASE_BIOME_CONFIG);
-                        } else {
-                            noise.change_config(biome_config.noise_config.clone());
-                        }
-
-                        let height = biome_config.terrain_offset
-                            + biome_config.terrain_scale
-                                * noise.at([i as f32 * s + x as f32, j as f32 * s + z as f32]);
-
-                        let cube_height = height.floor() as i32;
-
-                        for y in 0..cube_height {
-                            let block_at_height = biome_config.get_block_at(cube_height - y - 1);
-
-                            if let Some(block) = block_at_height {
-                                chunk.add_cube(
-                                    Vector3::new(
-                                        i as f32 * s + x as f32,
-                                        y as f32,
-                                        j as f32 * s + z as f32,
-                                    ),
-                                    block,
-                                    0,
-                                );
-                            }
-                        }
+                chunks.push(Self::random_chunk(&mut noise, seed, (i * s, j * s), caves, ores));
+            }
+        }
+
+        let mut world = World::new(chunks);
+        world.meta_mut().seed = seed;
+        world
+    }
+
+    /// Generates a single chunk of a random world.
+    /// A chunk only depends on the seed and its position, so it can be generated at any time.
+    pub fn generate_chunk(seed: u64, coord: ChunkCoord) -> Chunk {
+        Self::generate_decorated_chunk(seed, coord, &Self::no_decoration)
+    }
+
+    /// Same as `generate_chunk`, with these ores instead of the default ones
+    pub fn generate_chunk_with_ores(seed: u64, coord: ChunkCoord, ores: &OreConfig) -> Chunk {
+        Self::random_chunk(&mut Self::noise(seed), seed, coord, &CaveConfig::default(), ores)
+    }
+
+    /// Generates a single chunk of a random world, then lets the decorator add its own cubes on the terrain.
+    /// The decorator gets a random generator seeded from the seed and the chunk, so the result stays deterministic.
+    pub fn generate_decorated_chunk(
+        seed: u64,
+        coord: ChunkCoord,
+        decorator: &impl Fn(&mut Chunk, ChunkCoord, &mut SmallRng),
+    ) -> Chunk {
+        let mut chunk = Self::random_chunk(&mut Self::noise(seed), seed, coord, &CaveConfig::default(), &OreConfig::default());
+        Self::decorate(&mut chunk, seed, decorator);
+        chunk
+    }
+
+    /// Applies the decorator on a chunk, with the random generator of the seed and the chunk
+    pub fn decorate(chunk: &mut Chunk, seed: u64, decorator: &impl Fn(&mut Chunk, ChunkCoord, &mut SmallRng)) {
+        let coord = chunk.coord();
+        decorator(chunk, coord, &mut Self::chunk_rng(seed, coord));
+    }
+
+    /// The decoration scattering the veins of ores in the stone
+    pub fn ores_decoration(seed: u64, ores: &OreConfig) -> impl Fn(&mut Chunk, ChunkCoord, &mut SmallRng) + '_ {
+        move |chunk: &mut Chunk, _: ChunkCoord, _: &mut SmallRng| {
+            ores.scatter(chunk, seed);
+        }
+    }
+
+    /// The decoration carving the caves under the terrain
+    pub fn caves_decoration(seed: u64, caves: &CaveConfig) -> impl Fn(&mut Chunk, ChunkCoord, &mut SmallRng) + '_ {
+        move |chunk: &mut Chunk, _: ChunkCoord, _: &mut SmallRng| {
+            CaveCarver::new(caves).carve(chunk, seed);
+        }
+    }
+
+    /// The decoration planting the trees, see `structures_of_chunk`
+    pub fn trees_decoration(seed: u64) -> impl Fn(&mut Chunk, ChunkCoord, &mut SmallRng) {
+        move |chunk: &mut Chunk, _: ChunkCoord, _: &mut SmallRng| {
+            Self::plant_structures(chunk, &mut Self::noise(seed), seed)
+        }
+    }
+
+    /// The decoration of the built-in generation: the terrain is left as it is
+    pub fn no_decoration(_chunk: &mut Chunk, _coord: ChunkCoord, _rng: &mut SmallRng) {}
+
+    fn chunk_rng(seed: u64, coord: ChunkCoord) -> SmallRng {
+        SmallRng::seed_from_u64(mix_seed(seed, &[coord.0 as i64, coord.1 as i64]))
+    }
+
+    /// The noise of the terrain of the seed, to give to `structures_of_chunk`
+    pub fn noise(seed: u64) -> MultiscalePerlinNoise {
+        MultiscalePerlinNoise::new(seed, BIOMES[0].noise_config.clone())
+    }
+
+    /// Returns the biome and the height of the terrain of one column of a random world
+    pub fn column(seed: u64, x: i32, z: i32) -> (u64, i32) {
+        Self::column_with_noise(&mut Self::noise(seed), seed, x, z)
+    }
+
+    fn column_with_noise(noise: &mut MultiscalePerlinNoise, seed: u64, x: i32, z: i32) -> (u64, i32) {
+        let biome_t: u64 = BiomeGenerator::biome_at(seed, x, z);
+
+        let biome_config = &BIOMES[biome_t as usize];
+
+        if SINGLE_NOISE_CONFIG {
+            noise.change_config(BASE_BIOME_CONFIG);
+        } else {
+            noise.change_config(biome_config.noise_config.clone());
+        }
+
+        let height = biome_config.terrain_offset
+            + biome_config.terrain_scale
+                * noise.at([x as f32, z as f32]);
+
+        (biome_t, height.floor() as i32)
+    }
+
+    fn random_chunk(noise: &mut MultiscalePerlinNoise, seed: u64, coord: ChunkCoord, caves: &CaveConfig, ores: &OreConfig) -> Chunk {
+        let (x0, z0) = coord;
+        let mut chunk = Chunk::new([x0 as f32, z0 as f32]);
+
+        // get the height from the perlin noise for each block
+        for x in 0..CHUNK_SIZE as i32 {
+            for z in 0..CHUNK_SIZE as i32 {
+                let (biome_t, cube_height) = Self::column_with_noise(noise, seed, x + x0, z + z0);
+                let biome_config = &BIOMES[biome_t as usize];
+
+                for y in 0..cube_height {
+                    let block_at_height = biome_config.get_block_at(cube_height - y - 1);
+
+                    if let Some(block) = block_at_height {
+                        chunk.add_cube(
+                            Vector3::new((x0 + x) as f32, y as f32, (z0 + z) as f32),
+                            block,
+                            0,
+                        );
                     }
                 }
+            }
+        }
+
+        // The caves open the veins they go through
+        Self::decorate(&mut chunk, seed, &Self::ores_decoration(seed, ores));
+        Self::decorate(&mut chunk, seed, &Self::caves_decoration(seed, caves));
+        Self::decorate(&mut chunk, seed, &Self::trees_decoration(seed));
+        chunk
+    }
+
+    /// The structures anchored in the chunk at this position, with the offset of their anchor and their rotation:
+    /// the trees growing on its grass, more or less of them depending on the biome.
+    /// They only depend on the seed and the chunk, so the neighbours of the chunk find the same ones.
+    pub fn structures_of_chunk(
+        noise: &mut MultiscalePerlinNoise,
+        seed: u64,
+        coord: ChunkCoord,
+    ) -> Vec<(Structure, [i32; 3], StructureRotation)> {
+        let mut rng = Self::chunk_rng(seed ^ STRUCTURES_SALT, coord);
+        let max_density = BIOMES.iter().map(|biome| biome.tree_density).fold(0., f32::max);
+        let rotations = [StructureRotation::Deg0, StructureRotation::Deg90, StructureRotation::Deg180, StructureRotation::Deg270];
+
+        let mut structures = vec![];
+        for x in coord.0..coord.0 + CHUNK_SIZE as i32 {
+            for z in coord.1..coord.1 + CHUNK_SIZE as i32 {
+                // Always drawn, so that each column gets the same numbers whatever happens on the others
+                let roll: f32 = rng.gen();
+                let height = rng.gen_range(TREE_HEIGHTS);
+                let rotation = rotations[rng.gen_range(0..rotations.len())];
+                // The noise of the terrain is only computed for the columns that may get a tree
+                if roll >= max_density {
+                    continue;
+                }
+                let (biome_t, top) = Self::column_with_noise(noise, seed, x, z);
+                let biome = &BIOMES[biome_t as usize];
+                let on_grass = matches!(biome.get_block_at(0), Some(GRASS | SNOWYGRASS));
+                if roll < biome.tree_density && on_grass && top > 0 && top < CHUNK_HEIGHT as i32 {
+                    structures.push((Structure::oak(height), [x, top, z], rotation));
+                }
+            }
+        }
+        structures
+    }
+
+    /// Plants the structures of the chunk and of its neighbours, each one where it overlaps the chunk.
+    /// A structure crossing a border is then whole once both chunks are generated, whichever comes first.
+    fn plant_structures(chunk: &mut Chunk, noise: &mut MultiscalePerlinNoise, seed: u64) {
+        let (x0, z0) = chunk.coord();
+        let s = CHUNK_SIZE as i32;
+        for dx in -1..=1 {
+            for dz in -1..=1 {
+                for (structure, anchor, rotation) in Self::structures_of_chunk(noise, seed, (x0 + dx * s, z0 + dz * s)) {
+                    structure.place_in_chunk(chunk, anchor, rotation);
+                }
+            }
+        }
+    }
 
-                chunks.push(chunk);
+    /// Heights of the terrain of the chunk, from the layered noise of the configuration.
+    /// There is always at least one cube, and the terrain never goes above the chunks.
+    pub fn heightmap(config: &TerrainConfig, coord: ChunkCoord) -> Heightmap {
+        let mut noise = MultiscalePerlinNoise::from_octaves(config.seed, &config.noise);
+        let (x0, z0) = coord;
+        let mut heights = [[0; CHUNK_SIZE]; CHUNK_SIZE];
+        for (x, row) in heights.iter_mut().enumerate() {
+            for (z, height) in row.iter_mut().enumerate() {
+                let value = config.base_height + noise.at([(x0 + x as i32) as f32, (z0 + z as i32) as f32]);
+                *height = (value.floor() as i32).clamp(1, CHUNK_HEIGHT as i32 - 1);
             }
         }
+        heights
+    }
+
+    /// Generates a chunk of the terrain: grass on top, a few cubes of dirt, then stone, with ores and caves below
+    pub fn terrain_chunk(config: &TerrainConfig, coord: ChunkCoord) -> Chunk {
+        let (x0, z0) = coord;
+        let mut chunk = Chunk::new([x0 as f32, z0 as f32]);
+        let heights = Self::heightmap(config, coord);
+        for (x, row) in heights.iter().enumerate() {
+            for (z, height) in row.iter().enumerate() {
+                for y in 0..*height {
+                    let depth = height - y - 1;
+                    let block = match depth {
+                        0 => GRASS,
+                        d if d <= DIRT_DEPTH => DIRT,
+                        _ => STONE,
+                    };
+                    let at = Vector3::new((x0 + x as i32) as f32, y as f32, (z0 + z as i32) as f32);
+                    chunk.add_cube(at, block, 0);
+                }
+            }
+        }
+        Self::decorate(&mut chunk, config.seed, &Self::ores_decoration(config.seed, &config.ores));
+        Self::decorate(&mut chunk, config.seed, &Self::caves_decoration(config.seed, &config.caves));
+        chunk
+    }
 
-        World::new(chunks)
+    /// Creates a world of hills and valleys, `n_chunks` in each direction as in `create_new_flat_world`
+    pub fn create_new_terrain_world(n_chunks: i32, config: &TerrainConfig) -> World {
+        let s = CHUNK_SIZE as i32;
+        let mut chunks = vec![];
+        for i in -n_chunks..n_chunks + 1 {
+            for j in -n_chunks..n_chunks + 1 {
+                chunks.push(Self::terrain_chunk(config, (i * s, j * s)));
+            }
+        }
+        let mut world = World::new(chunks);
+        world.meta_mut().seed = config.seed;
+        world
     }
 
     /// Creates a basic, flat world. For now this is a simple, flat
@@ -85,21 +322,144 @@ impl WorldGenerator {
     /// direction; i.e., (2nchunks + 1) x (2nchunks + 1) chunks will
     /// be created
     pub fn create_new_flat_world(n_chunks: i32) -> World {
-        let s = CHUNK_SIZE as f32;
-        let mut chunks = vec![];
+        SuperflatGenerator::default().create_world(n_chunks)
+    }
+}
 
-        // Yes this is slow, but it will be fine for now
-        for i in -n_chunks..n_chunks + 1 {
-            for j in -n_chunks..n_chunks + 1 {
-                let mut chunk = Chunk::new([i as f32 * s, j as f32 * s]);
-                for k in 0..CHUNK_FLOOR {
-                    chunk.fill_layer(k, DIRT);
-                }
-                chunk.fill_layer(CHUNK_FLOOR, GRASS);
-                chunks.push(chunk);
+#[cfg(test)]
+mod tests {
+    use crate::block_kind::Block::{GLASS, GRASS, OAKLOG};
+    use crate::chunk::{Chunk, ChunkCoord, CHUNK_HEIGHT, CHUNK_SIZE};
+    use crate::primitives::vector::Vector3;
+    use crate::world::World;
+    use crate::world_generation::biomes_def::BIOMES;
+    use crate::world_generation::caves::CaveConfig;
+    use crate::world_generation::ores::OreConfig;
+    use crate::world_generation::perlin::{MultiscalePerlinNoise, OctavesConfig};
+    use crate::world_generation::world_generator::{TerrainConfig, WorldGenerator, DEFAULT_SEED};
+    use rand::rngs::SmallRng;
+    use rand::Rng;
+
+    fn place_markers(chunk: &mut Chunk, _coord: ChunkCoord, rng: &mut SmallRng) {
+        let [x, y, z] = chunk.local_to_global(2, 40, 5);
+        chunk.add_cube(Vector3::new(x as f32, y as f32, z as f32), GLASS, 0);
+        let [x, y, z] = chunk.local_to_global(rng.gen_range(0..CHUNK_SIZE), 50, 0);
+        chunk.add_cube(Vector3::new(x as f32, y as f32, z as f32), GLASS, 0);
+    }
+
+    #[test]
+    fn test_decorator_places_marker() {
+        let s = CHUNK_SIZE as i32;
+        let seed = 3;
+        let mut world = World::empty();
+        world.meta_mut().seed = seed;
+        for coord in [(0, 0), (-s, 2 * s), (5 * s, -s)] {
+            world.generate_chunk_decorated(coord, &place_markers);
+            let (x0, z0) = (coord.0 as f32, coord.1 as f32);
+            let marker = world.cube_at(Vector3::new(x0 + 2., 40., z0 + 5.));
+            assert_eq!(marker.map(|c| *c.block()), Some(GLASS));
+
+            // Generating the chunk again gives the same blocks (the world recounts the neighbors of the cubes)
+            let again = WorldGenerator::generate_decorated_chunk(seed, coord, &place_markers);
+            let blocks = |chunk: &Chunk| chunk.cubes_iter().map(|c| c.map(|c| *c.block())).collect::<Vec<_>>();
+            assert_eq!(blocks(&world.get_chunk(coord).unwrap()), blocks(&again));
+        }
+    }
+
+    #[test]
+    fn test_trees_cross_the_chunk_borders() {
+        let s = CHUNK_SIZE as i32;
+        let seed = 7;
+        let mut noise = MultiscalePerlinNoise::new(seed, BIOMES[0].noise_config.clone());
+
+        // A tree close enough to the +x border of its chunk to reach into the next one
+        let (coord, (tree, anchor, rotation)) = (-5..5)
+            .flat_map(|i| (-5..5).map(move |j| (i * s, j * s)))
+            .find_map(|coord| {
+                WorldGenerator::structures_of_chunk(&mut noise, seed, coord)
+                    .into_iter()
+                    .find(|(_, anchor, _)| anchor[0] >= coord.0 + s - 2)
+                    .map(|tree| (coord, tree))
+            })
+            .unwrap();
+        assert!(tree.blocks().iter().any(|(_, block)| *block == OAKLOG));
+
+        // Each chunk has its part of the tree, whichever is generated
+        let chunks = [coord, (coord.0 + s, coord.1)].map(|c| WorldGenerator::generate_chunk(seed, c));
+        let mut in_next_chunk = 0;
+        for ([x, y, z], _) in tree.rotated_blocks(rotation) {
+            let at = Vector3::newi(anchor[0] + x, anchor[1] + y, anchor[2] + z);
+            if at.y() >= CHUNK_HEIGHT as f32 {
+                continue;
+            }
+            if let Some(chunk) = chunks.iter().find(|chunk| chunk.is_in(&at)) {
+                assert!(chunk.cube_at(&at).is_some());
+                in_next_chunk += chunks[1].is_in(&at) as usize;
             }
         }
+        assert!(in_next_chunk > 0);
+    }
+
+    #[test]
+    fn test_caves_can_be_disabled() {
+        let seed = 4;
+        let with_caves = WorldGenerator::create_new_random_world(1);
+        let without = WorldGenerator::create_new_random_world_with_caves(1, DEFAULT_SEED, &CaveConfig::disabled(), &OreConfig::default());
+        let count = |world: &World| -> usize {
+            let s = CHUNK_SIZE as i32;
+            (-1..=1)
+                .flat_map(|i| (-1..=1).map(move |j| (i * s, j * s)))
+                .map(|coord| world.get_chunk(coord).unwrap().cubes_iter().flatten().count())
+                .sum()
+        };
+        // The caves only remove cubes, under a surface that is left as it was
+        assert!(count(&with_caves) < count(&without));
+        assert_eq!(with_caves.find_safe_spawn(), without.find_safe_spawn());
+
+        // The terrain has its caves too, unless they are disabled
+        let config = TerrainConfig { seed, ..TerrainConfig::default() };
+        let flat = TerrainConfig { caves: CaveConfig::disabled(), ..config.clone() };
+        let carved = (0..4).any(|i| {
+            let coord = (i * CHUNK_SIZE as i32, 0);
+            WorldGenerator::terrain_chunk(&config, coord) != WorldGenerator::terrain_chunk(&flat, coord)
+        });
+        assert!(carved);
+    }
+
+    #[test]
+    fn test_terrain_heightmap() {
+        let config = TerrainConfig::default();
+        let heights = WorldGenerator::heightmap(&config, (0, 0));
 
-        World::new(chunks)
+        // The same seed always gives the same terrain
+        assert_eq!(heights, WorldGenerator::heightmap(&config, (0, 0)));
+
+        // It is not a flat slab
+        let all: Vec<i32> = heights.iter().flatten().copied().collect();
+        let (min, max) = (*all.iter().min().unwrap(), *all.iter().max().unwrap());
+        assert!(max > min);
+
+        // Without any octave, it is flat at the base height
+        let flat = TerrainConfig { noise: OctavesConfig { octaves: 0, ..OctavesConfig::default() }, ..config.clone() };
+        let flat_heights = WorldGenerator::heightmap(&flat, (0, 0));
+        assert!(flat_heights.iter().flatten().all(|h| *h == config.base_height as i32));
+
+        // Another seed gives another terrain
+        let other = TerrainConfig { seed: config.seed + 1, ..config.clone() };
+        assert_ne!(heights, WorldGenerator::heightmap(&other, (0, 0)));
+    }
+
+    #[test]
+    fn test_terrain_chunk_follows_heightmap() {
+        let config = TerrainConfig::default();
+        let coord = (CHUNK_SIZE as i32, -(CHUNK_SIZE as i32));
+        let heights = WorldGenerator::heightmap(&config, coord);
+        let chunk = WorldGenerator::terrain_chunk(&config, coord);
+        for (lx, lz) in [(0, 0), (3, 7), (CHUNK_SIZE - 1, CHUNK_SIZE - 1)] {
+            let [x, _, z] = chunk.local_to_global(lx, 0, lz);
+            let top = heights[lx][lz] as f32 - 1.;
+            assert_eq!(chunk.cube_at(&Vector3::new(x as f32, top, z as f32)).map(|c| *c.block()), Some(GRASS));
+            assert!(chunk.cube_at(&Vector3::new(x as f32, top + 1., z as f32)).is_none());
+        }
     }
 }
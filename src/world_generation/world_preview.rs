@@ -0,0 +1,157 @@
+use std::collections::{BTreeSet, HashMap};
+
+use super::biomes_def::{BIOMES, NUM_BIOMES};
+use super::world_generator::WorldGenerator;
+use crate::block_kind::Block;
+use crate::chunk::{CHUNK_HEIGHT, CHUNK_SIZE};
+use crate::player::{FOREHEAD, PLAYER_HEIGHT};
+use crate::primitives::position::Position;
+use crate::primitives::vector::Vector3;
+
+/// A summary of the terrain of a seed, computed from a few columns only
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorldPreview {
+    pub seed: u64,
+    /// Name of the biome covering most of the sampled columns
+    pub dominant_biome: &'static str,
+    /// Average height [cube] of the terrain over the sampled columns
+    pub average_height: f32,
+    /// Same position as `World::find_safe_spawn` on the generated world, if one was found in the sample
+    pub spawn: Option<Position>,
+}
+
+impl WorldPreview {
+    /// Samples the `sample_size` x `sample_size` columns around the origin.
+    /// Only the heights of the columns are computed, no chunk is created.
+    pub fn new(seed: u64, sample_size: i32) -> Self {
+        let half = sample_size / 2;
+        let mut biome_count = [0; NUM_BIOMES as usize];
+        let mut height_sum = 0.;
+        let mut spawn = None;
+        let structure_tops = Self::structure_tops(seed, half);
+
+        // The columns are visited in the same order as when looking for the spawn
+        for distance in 0..=half {
+            for [x, z] in columns_at_distance(distance) {
+                let (biome, height) = WorldGenerator::column(seed, x, z);
+                biome_count[biome as usize] += 1;
+                height_sum += height as f32;
+
+                if spawn.is_none() {
+                    let top = match structure_tops.get(&[x, z]) {
+                        Some((top, block)) => Some(*top).filter(|_| !block.is_transparent()),
+                        None => Self::surface(biome, height),
+                    };
+                    spawn = top.map(|top| spawn_position(x, top, z));
+                }
+            }
+        }
+
+        let n_columns = (2 * half + 1) * (2 * half + 1);
+        let dominant = (0..biome_count.len()).max_by_key(|&i| (biome_count[i], usize::MAX - i)).unwrap();
+
+        Self {
+            seed,
+            dominant_biome: BIOMES[dominant].name,
+            average_height: height_sum / n_columns as f32,
+            spawn,
+        }
+    }
+
+    /// The topmost block of the structures planted over each column of the sample, when it is above the terrain.
+    /// The structures are looked at in the order they are planted, so that the first one gets a shared cell.
+    fn structure_tops(seed: u64, half: i32) -> HashMap<[i32; 2], (i32, Block)> {
+        let s = CHUNK_SIZE as i32;
+        // The chunks of the sample and their neighbours, ordered as in `WorldGenerator::plant_structures`
+        let chunks: BTreeSet<(i32, i32)> = (-half / s - 2..=half / s + 1)
+            .flat_map(|i| (-half / s - 2..=half / s + 1).map(move |j| (i * s, j * s)))
+            .collect();
+
+        let mut noise = WorldGenerator::noise(seed);
+        let mut tops: HashMap<[i32; 2], (i32, Block)> = HashMap::new();
+        for coord in chunks {
+            for (structure, anchor, rotation) in WorldGenerator::structures_of_chunk(&mut noise, seed, coord) {
+                for ([x, y, z], block) in structure.rotated_blocks(rotation) {
+                    let (x, y, z) = (anchor[0] + x, anchor[1] + y, anchor[2] + z);
+                    if x.abs().max(z.abs()) > half || y >= CHUNK_HEIGHT as i32 {
+                        continue;
+                    }
+                    let (_, height) = WorldGenerator::column(seed, x, z);
+                    let covered = tops.get(&[x, z]).is_some_and(|(top, _)| *top >= y);
+                    if y >= height && !covered {
+                        tops.insert([x, z], (y, block));
+                    }
+                }
+            }
+        }
+        tops
+    }
+
+    /// Height of the topmost cube of a column if it is solid, with the same rules as the generation of the chunks
+    fn surface(biome: u64, height: i32) -> Option<i32> {
+        let config = &BIOMES[biome as usize];
+        let top = (0..height.min(CHUNK_HEIGHT as i32))
+            .rev()
+            .find(|&y| config.get_block_at(height - y - 1).is_some())?;
+        // Nobody spawns on water
+        if config.get_block_at(height - top - 1).is_some_and(|block| block.is_transparent()) {
+            return None;
+        }
+        Some(top)
+    }
+}
+
+/// The columns at a given (chessboard) distance from the origin, in a fixed order
+pub fn columns_at_distance(distance: i32) -> Vec<[i32; 2]> {
+    let mut columns = Vec::new();
+    for x in -distance..=distance {
+        for z in -distance..=distance {
+            if x.abs().max(z.abs()) == distance {
+                columns.push([x, z]);
+            }
+        }
+    }
+    columns
+}
+
+/// Position of the eyes of a player standing on top of the cube at (x, top, z)
+pub fn spawn_position(x: i32, top: i32, z: i32) -> Position {
+    let eyes = top as f32 + 1. + PLAYER_HEIGHT - FOREHEAD;
+    Position::new(Vector3::new(x as f32 + 0.5, eyes, z as f32 + 0.5), 0., 0.)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::world::World;
+    use crate::world_generation::world_generator::WorldGenerator;
+    use crate::world_generation::world_preview::columns_at_distance;
+
+    #[test]
+    fn test_columns_at_distance() {
+        assert_eq!(columns_at_distance(0), vec![[0, 0]]);
+        assert_eq!(columns_at_distance(1).len(), 8);
+        assert_eq!(columns_at_distance(3).len(), 24);
+    }
+
+    #[test]
+    fn test_preview_matches_generated_world() {
+        for seed in [1, 42, 1234] {
+            let preview = World::preview(seed, 16);
+            let world = WorldGenerator::create_new_random_world_with_seed(1, seed);
+
+            assert!(preview.spawn.is_some());
+            assert_eq!(preview.spawn, world.find_safe_spawn());
+        }
+    }
+
+    #[test]
+    fn test_preview_is_deterministic() {
+        let preview = World::preview(7, 16);
+        assert_eq!(preview, World::preview(7, 16));
+        assert!(preview.average_height > 0.);
+
+        // The preview does not depend on what was generated before
+        World::preview(8, 16);
+        assert_eq!(preview, World::preview(7, 16));
+    }
+}
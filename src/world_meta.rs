@@ -0,0 +1,94 @@
+use serde::{Deserialize, Serialize};
+use crate::chunk::CHUNK_HEIGHT;
+use crate::game_mode::GameMode;
+use crate::server::world_dispatcher::VISIBLE_CHUNKS;
+use crate::sky::DEFAULT_DAY_LENGTH;
+use crate::chunk::{Chunk, ChunkCoord};
+use crate::world::World;
+use crate::world_generation::generators::{ChunkGenerator, SuperflatGenerator};
+use crate::world_generation::preset::GeneratorPreset;
+use crate::world_generation::world_generator::{WorldGenerator, DEFAULT_SEED};
+use rand::rngs::SmallRng;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Current time [s since 1970], as kept in `WorldMeta::last_played`
+pub fn unix_time() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs())
+}
+
+/// The settings of a world, saved along with its chunks
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WorldMeta {
+    pub name: String,
+    /// Seed used to generate the chunks that are not loaded yet
+    pub seed: u64,
+    /// Number of chunks visible in each direction
+    pub render_distance: i32,
+    pub game_mode: GameMode,
+    /// Highest layer where a block can be placed
+    pub build_limit: i32,
+    /// The layers up to this one are bedrock: their cubes cannot be broken
+    pub bedrock_limit: i32,
+    /// Duration of a full day and night [s]
+    pub day_length: f32,
+    /// How the world was created
+    pub generator: GeneratorPreset,
+    /// Layers of the ground of a flat world, from the bottom
+    pub flat_layers: SuperflatGenerator,
+    /// Time of the last save [s since 1970], 0 if never saved
+    pub last_played: u64,
+}
+
+impl WorldMeta {
+    pub fn new(name: String, seed: u64) -> Self {
+        Self {
+            name,
+            seed,
+            ..Default::default()
+        }
+    }
+
+    /// The generator of the chunks of this world, from its preset and its seed
+    pub fn chunk_generator(&self) -> Box<dyn ChunkGenerator> {
+        self.generator.generator(self.seed, &self.flat_layers)
+    }
+
+    /// Generates the first chunks of a world with these settings, `n_chunks` in each direction
+    pub fn generate_world(&self, n_chunks: i32) -> World {
+        let mut world = self.chunk_generator().create_world(n_chunks);
+        *world.meta_mut() = self.clone();
+        world
+    }
+
+    /// Generates the chunk at this position as the first chunks of the world were.
+    /// The chunk only depends on the settings of the world and its position.
+    pub fn generate_chunk(&self, coord: ChunkCoord) -> Chunk {
+        self.chunk_generator().generate_chunk(coord)
+    }
+
+    /// Generates the chunk at this position like `generate_chunk`, then lets the decorator add its own cubes on it,
+    /// see `WorldGenerator::generate_decorated_chunk`
+    pub fn generate_decorated_chunk(&self, coord: ChunkCoord, decorator: &impl Fn(&mut Chunk, ChunkCoord, &mut SmallRng)) -> Chunk {
+        let mut chunk = self.generate_chunk(coord);
+        WorldGenerator::decorate(&mut chunk, self.seed, decorator);
+        chunk
+    }
+}
+
+impl Default for WorldMeta {
+    fn default() -> Self {
+        Self {
+            name: String::from("world"),
+            seed: DEFAULT_SEED,
+            render_distance: VISIBLE_CHUNKS,
+            game_mode: GameMode::default(),
+            build_limit: CHUNK_HEIGHT as i32 - 1,
+            bedrock_limit: 0,
+            day_length: DEFAULT_DAY_LENGTH,
+            generator: GeneratorPreset::default(),
+            flat_layers: SuperflatGenerator::default(),
+            last_played: 0,
+        }
+    }
+}
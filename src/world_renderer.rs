@@ -2,53 +2,108 @@ extern crate glium;
 extern crate winit;
 
 // use std::ops::ControlFlow;
+use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
 use crate::actions::Action;
-use crate::actions::Action::{Add, Destroy};
-use crate::block_kind::Block::{COBBELSTONE, DIRT, GRASS, OAKLEAVES, OAKLOG, SWORD};
+use crate::actions::Action::{Add, Destroy, SetBlockEntity};
+use crate::block_entity::BlockEntity;
+use crate::block_kind::Block::{CHEST, COBBELSTONE, DIRT, GRASS, OAKLEAVES, OAKLOG, SEEDS, TORCH};
+use crate::bow::BowCharge;
+use crate::chat::Chat;
+use crate::chunk::ChunkCoord;
+use crate::chunk_workers::{ChunkJob, ChunkResult, ChunkWorkers};
+use crate::entity::arrow::Arrow;
 use crate::entity::entity_manager::EntityManager;
+use crate::entity::store::EntityId;
 use crate::entity::humanoid;
+use crate::entity::entity::EntityKind;
 use crate::fps::FpsManager;
-use crate::graphics::cube::{CUBE_FRAGMENT_SHADER, CUBE_VERTEX_SHADER, VERTICES};
+use crate::input::MotionState;
+use crate::graphics::chunk_mesh::{ChunkVertex, CHUNK_FRAGMENT_SHADER, CHUNK_VERTEX_SHADER};
+use crate::graphics::cube::{SelectionMode, CUBE_FRAGMENT_SHADER, CUBE_VERTEX_SHADER, VERTICES};
+use crate::graphics::block_outline::{BlockHighlight, CRACK_STAGES, OUTLINE_FRAGMENT_SHADER, OUTLINE_VERTEX_SHADER};
 
-use crate::camera::perspective_matrix;
-use crate::graphics::color::Color;
+use crate::camera::{project_on_screen, CameraMode, Projection, ProjectionMode};
 use crate::graphics::entity::{ENTITY_FRAGMENT_SHADER, ENTITY_VERTEX_SHADER};
 use crate::graphics::font::GLChar;
 use crate::graphics::hud_renderer::HUDRenderer;
+use crate::graphics::crosshair::CrosshairConfig;
 use crate::graphics::inventory_event::InventoryEvent;
 use crate::graphics::menu_debug::DebugData;
+use crate::graphics::menu_screen::MenuScreen;
+use crate::game_state::{next_fov, next_render_distance, GameState, MenuEntry};
+use crate::persistence::SaveDir;
+use crate::world_meta::{unix_time, WorldMeta};
+use crate::world_selection::{create_world_menu, world_list_menu, NewWorld, WorldChoice, WorldLauncher};
 use crate::graphics::rectangle::{RECT_FRAGMENT_SHADER, RECT_VERTEX_SHADER, RECT_VERTICES};
-use crate::input::MotionState;
+use crate::input_map::InputAction;
+use crate::settings::{Settings, SETTINGS_FILE};
+use crate::gamepad::{Gamepad, GamepadConfig};
+use crate::network::prediction::{MovePrediction, RECONCILE_TOLERANCE};
 use crate::network::proxy::Proxy;
 use crate::network::server_update::ServerUpdate;
-use crate::player::{Player, CLICK_TIME_TO_BREAK};
-use crate::player_items::PlayerItems;
+use crate::player::Player;
+use crate::item::Item;
+use crate::item_drop::{pick_up, ItemDrop};
+use crate::player_items::{Inventory, ItemStack};
+use crate::sky::Sky;
 use crate::primitives::position::Position;
+use crate::primitives::vector::Vector3;
+use crate::profile::{FrameProfile, Phase};
 use crate::texture;
-use crate::world::World;
-use crate::health::Health;
+use crate::texture_atlas::TextureAtlas;
+use crate::tool::Tool;
+use crate::resource_pack::ResourcePack;
+use crate::world::{World, WorldEvent};
+use crate::health::{fall_damage, Health, MAX_HEALTH};
+use crate::hunger::{Hunger, HungerEffect, JUMP_EXHAUSTION, SPRINT_EXHAUSTION, STARVATION_DAMAGE};
 use glium::uniforms::{MagnifySamplerFilter, MinifySamplerFilter};
-use glium::{uniform, Surface};
+use glium::glutin::surface::WindowSurface;
+use glium::{uniform, Display, IndexBuffer, Surface, VertexBuffer};
 use winit::event::ElementState::Pressed;
-use winit::event::{AxisId, ElementState, KeyEvent, MouseButton};
+use winit::event::{AxisId, ElementState, KeyEvent, MouseButton, MouseScrollDelta};
 use winit::keyboard::{KeyCode, PhysicalKey};
 use winit::window::{CursorGrabMode, Fullscreen, Window};
 
 
-/// 16ms => 60 FPS roughly
+/// 16ms => 60 FPS roughly, with the vsync when the refresh rate of the monitor is not known
 const TARGET_FRAME_DURATION: Duration = Duration::from_millis(16);
 /// If the frame is `MIN_SLEEP_TIME` shorter than the target duration or less,
 /// does not sleep, because of granularity of time in `std::thread::sleep`
 const MIN_SLEEP_TIME: Duration = Duration::from_millis(2);
 
+/// Duration of a frame with the vsync: one refresh of the monitor the window is on, which may change at any frame
+fn frame_duration(window: &Window) -> Duration {
+    window
+        .current_monitor()
+        .and_then(|monitor| monitor.refresh_rate_millihertz())
+        .filter(|millihertz| *millihertz > 0)
+        .map_or(TARGET_FRAME_DURATION, |millihertz| Duration::from_secs_f64(1000. / millihertz as f64))
+}
+
+/// Speed of the animated textures [image/s]
+const ANIMATION_FPS: f32 = 8.;
+
+/// Time taken by a newly loaded chunk to become fully opaque
+const CHUNK_FADE_DURATION: Duration = Duration::from_millis(500);
+
+/// Distance [world units] beyond which the chunks are drawn with super-cubes
+const LOD_DISTANCE: f32 = 64.;
+
+/// Distance [cube] beyond which the names of the other players are not shown
+const NAME_TAG_RANGE: f32 = 32.;
+
 /// The struct in charge of drawing the world
 pub struct WorldRenderer {
     /// Link with the server
-    /// The proxy needs to be already logged-in
-    proxy: Arc<Mutex<dyn Proxy>>,
+    /// The proxy needs to be already logged-in, there is none until a world is chosen
+    proxy: Option<Arc<Mutex<dyn Proxy>>>,
+    /// Starts the server of the world chosen in the menus, none when connected to a remote server
+    launcher: Option<WorldLauncher>,
+    /// The world played right away when the game starts, instead of showing the main menu
+    initial_world: Option<WorldChoice>,
 
     /// Currently displayed world
     world: World,
@@ -57,40 +112,263 @@ pub struct WorldRenderer {
     player: Player,
 
     /// Items of the player
-    items: PlayerItems,
+    items: Inventory,
+
+    /// Time of the day, and the light of the sun
+    sky: Sky,
 
     /// Health of the player
     health: Health,
+    /// Food of the player, which heals it when full
+    hunger: Hunger,
+
+    /// Drawn while the button placing the blocks is held with a bow in hand
+    bow: BowCharge,
+
+    /// The lines exchanged with the other players
+    chat: Chat,
+
+    /// The moves of the player sent to the server, until it acknowledges them
+    prediction: MovePrediction,
 
     /// In charge of rendering of the 2D menus on the screen
     hud_renderer: HUDRenderer,
+    crosshair_config: CrosshairConfig,
+    chunk_fade_duration: Duration,
+    lod_distance: Option<f32>,
+    projection: Projection,
+    highlight_enabled: bool,
+    selection_mode: SelectionMode,
+
+    /// Draw the opaque cubes with one mesh per chunk, instead of one instance per cube
+    greedy_meshing: bool,
+    /// Buffers of the meshes of the chunks, built again when a chunk changes
+    chunk_meshes: HashMap<ChunkCoord, (VertexBuffer<ChunkVertex>, IndexBuffer<u32>)>,
+    /// Build the meshes away from the render loop
+    chunk_workers: ChunkWorkers,
+    /// Ticket of the last mesh asked for each chunk, the results of the older ones are dropped
+    mesh_tickets: HashMap<ChunkCoord, u64>,
+    next_mesh_ticket: u64,
 
     /// In charge of renderin all the other entites
     entity_manager: EntityManager,
+    /// The id in the world of the client of each entity of the world of the server, see `ServerUpdate::WorldEntity`
+    remote_entities: HashMap<u32, EntityId>,
 
     /// Computes the current FPS
     fps_manager: FpsManager,
 
+    /// Time spent in the phases of the last frame, only measured when enabled
+    profile: FrameProfile,
+
     /// Is the window displayed in fullscreen ?
     fullscreen: bool,
+
+    /// Playing, or in one of the menus
+    state: GameState,
+    /// Last position of the cursor on the screen, from -1 to 1 on both axes
+    cursor: (f32, f32),
+    /// The worlds listed in the menu, read when it opens
+    saved_worlds: Vec<WorldMeta>,
+    /// The world being created in the menu
+    new_world: NewWorld,
+    /// Why the last choice in the menu failed, shown as its title
+    menu_error: Option<&'static str>,
+
+    /// The preferences of the player, including the bindings of the keys and the mouse buttons
+    settings: Settings,
+    /// The controller, driving the player next to the keyboard and the mouse
+    gamepad: Gamepad,
 }
 
 impl WorldRenderer {
     pub fn new(proxy: Arc<Mutex<dyn Proxy>>, world: World, player: Player) -> Self {
+        Self::with_proxy_and_launcher(Some(proxy), None, world, player)
+    }
+
+    /// A renderer without any server, until the player chooses a world in the menus.
+    /// The launcher then starts the server of this world.
+    pub fn with_world_launcher(launcher: WorldLauncher) -> Self {
+        Self::with_proxy_and_launcher(None, Some(launcher), World::empty(), Player::new())
+    }
+
+    fn with_proxy_and_launcher(proxy: Option<Arc<Mutex<dyn Proxy>>>, launcher: Option<WorldLauncher>, world: World, player: Player) -> Self {
+        let sky = Sky::new(world.meta().day_length);
+        let settings = Settings::load(SETTINGS_FILE);
         Self {
             proxy,
+            launcher,
+            initial_world: None,
             world,
             player,
-            health: Health::new(10),
+            health: Health::new(MAX_HEALTH),
+            hunger: Hunger::new(),
+            bow: BowCharge::new(),
+            chat: Chat::new(),
+            prediction: MovePrediction::new(),
             hud_renderer: HUDRenderer::new(),
+            crosshair_config: CrosshairConfig::new(),
+            chunk_fade_duration: CHUNK_FADE_DURATION,
+            lod_distance: Some(LOD_DISTANCE),
+            projection: Projection::new((800, 600)).with_fov(settings.fov),
+            highlight_enabled: true,
+            selection_mode: SelectionMode::default(),
+            greedy_meshing: true,
+            chunk_meshes: HashMap::new(),
+            chunk_workers: ChunkWorkers::with_available_threads(),
+            mesh_tickets: HashMap::new(),
+            next_mesh_ticket: 0,
             fps_manager: FpsManager::new(),
-            items: PlayerItems::empty(),
+            profile: FrameProfile::disabled(),
+            items: Inventory::empty(),
+            sky,
 
             fullscreen: false,
+            state: GameState::default(),
+            cursor: (0., 0.),
+            saved_worlds: Vec::new(),
+            new_world: NewWorld::default(),
+            menu_error: None,
             entity_manager: EntityManager::new(),
+            remote_entities: HashMap::new(),
+            settings,
+            gamepad: Gamepad::new(GamepadConfig::default()),
         }
     }
 
+    /// Plays this world as soon as the game starts, without going through the menus
+    pub fn set_initial_world(&mut self, choice: WorldChoice) {
+        self.initial_world = Some(choice);
+    }
+
+    /// Sets the dead zone, the look speed and the buttons of the controller
+    pub fn set_gamepad_config(&mut self, config: GamepadConfig) {
+        self.gamepad.set_config(config);
+    }
+
+    /// Changes the look of the crosshair and of the outline of the selected cube
+    pub fn set_crosshair_config(&mut self, config: CrosshairConfig) {
+        self.crosshair_config = config;
+        self.hud_renderer.set_crosshair_config(config);
+    }
+
+    /// Time taken by the newly loaded chunks to fade in, zero to make them appear at once
+    pub fn set_chunk_fade_duration(&mut self, duration: Duration) {
+        self.chunk_fade_duration = duration;
+    }
+
+    /// Distance beyond which the chunks are drawn with less detail, none to always draw every cube
+    pub fn set_lod_distance(&mut self, distance: Option<f32>) {
+        self.lod_distance = distance;
+    }
+
+    /// Switches between the perspective and the orthographic projection
+    pub fn set_projection_mode(&mut self, mode: ProjectionMode) {
+        self.projection.set_mode(mode);
+    }
+
+    /// Sets the vertical field of view [rad], before the effects of the sprint and the zoom
+    pub fn set_fov(&mut self, fov: f32) {
+        let mut settings = self.settings.clone();
+        settings.fov = fov;
+        self.set_settings(settings);
+    }
+
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    /// Applies the settings right away, so that the menus can change them while playing.
+    /// The fullscreen mode is applied at the next frame, with the window.
+    pub fn set_settings(&mut self, settings: Settings) {
+        let settings = settings.sanitized();
+        if settings.render_distance != self.settings.render_distance {
+            self.with_proxy(|proxy| proxy.set_render_distance(settings.render_distance));
+        }
+        self.settings = settings;
+        self.projection.set_fov(self.settings.fov);
+    }
+
+    /// Writes the current settings to `SETTINGS_FILE`, where they are loaded from at the next start
+    pub fn save_settings(&self) {
+        self.settings.save(SETTINGS_FILE);
+    }
+
+    /// Shows or hides the cube targeted by the player
+    pub fn set_highlight_enabled(&mut self, enabled: bool) {
+        self.highlight_enabled = enabled;
+    }
+
+    /// How the cube targeted by the player is shown
+    pub fn set_selection_mode(&mut self, mode: SelectionMode) {
+        self.selection_mode = mode;
+    }
+
+    /// Draws the opaque cubes with the meshes of the chunks, or with one instance per cube
+    pub fn set_greedy_meshing(&mut self, enabled: bool) {
+        self.greedy_meshing = enabled;
+        if enabled {
+            self.world.mark_all_meshes_dirty();
+        }
+    }
+
+    /// Asks the workers to build again the meshes of the chunks that changed, the closest to the player first,
+    /// and uploads the meshes they finished. The meshes of the unloaded chunks are dropped.
+    fn update_chunk_meshes(&mut self, display: &Display<WindowSurface>) {
+        let dirty = self.world.take_dirty_meshes();
+        if !self.greedy_meshing {
+            self.chunk_meshes.clear();
+            self.mesh_tickets.clear();
+            return;
+        }
+        let player = self.player.position().pos() / self.world.scale();
+        self.chunk_workers.set_focus(player.x(), player.z());
+        for coord in dirty {
+            // Until its new mesh is ready, the chunk is drawn with one instance per cube
+            self.chunk_meshes.remove(&coord);
+            match self.world.chunk_with_neighbors(coord) {
+                Some((chunk, neighbors)) => {
+                    self.next_mesh_ticket += 1;
+                    self.mesh_tickets.insert(coord, self.next_mesh_ticket);
+                    let lights = self.world.light_around(coord);
+                    let seed = self.world.meta().seed;
+                    self.chunk_workers.submit(ChunkJob::Mesh { chunk, neighbors, lights, seed, ticket: self.next_mesh_ticket });
+                }
+                None => {
+                    self.mesh_tickets.remove(&coord);
+                }
+            }
+        }
+
+        for result in self.chunk_workers.try_recv() {
+            let ChunkResult::Meshed(coord, mesh, ticket) = result else {
+                continue;
+            };
+            // The chunk changed again, or was unloaded, since this mesh was asked
+            if self.mesh_tickets.get(&coord) != Some(&ticket) {
+                continue;
+            }
+            self.mesh_tickets.remove(&coord);
+            let vertices = VertexBuffer::immutable(display, mesh.vertices()).unwrap();
+            let indices = IndexBuffer::immutable(
+                display,
+                glium::index::PrimitiveType::TrianglesList,
+                mesh.indices(),
+            ).unwrap();
+            self.chunk_meshes.insert(coord, (vertices, indices));
+        }
+    }
+
+    /// Measures the time spent in the phases of each frame
+    pub fn set_profiling(&mut self, enabled: bool) {
+        self.profile.set_enabled(enabled);
+    }
+
+    /// Time spent in the phases of the last frame
+    pub fn profile(&self) -> &FrameProfile {
+        &self.profile
+    }
+
     pub fn run(&mut self) {
         // We start by creating the EventLoop, this can only be done once per process.
         // This also needs to happen on the main thread to make the program portable.
@@ -102,20 +380,25 @@ impl WorldRenderer {
             .with_title("Crafty")
             .build(&event_loop);
 
-        window.set_cursor_visible(false);
+        self.projection.set_dimensions(display.get_framebuffer_dimensions());
 
         // Add a few items
-        self.items.collect(SWORD);
+        self.items.collect(Tool::Sword);
+        self.items.collect(Tool::Pickaxe);
         for _ in 0..16 { self.items.collect(COBBELSTONE); }
         for _ in 0..8 { self.items.collect(OAKLOG); }
-
-        // Try to lock the mouse to the window, this doen't work for all OS
-        let lock_mouse = window
-            .set_cursor_grab(CursorGrabMode::Confined)
-            .or_else(|_e| window.set_cursor_grab(CursorGrabMode::Locked));
-        if lock_mouse.is_err() {
-            println!("Can't lock")
+        for _ in 0..8 { self.items.collect(TORCH); }
+        self.items.collect(Tool::Hoe);
+        for _ in 0..8 { self.items.collect(SEEDS); }
+
+        // The game starts in the main menu, with the mouse released, unless a world is given to play right away
+        let mut initial_state = GameState::MainMenu;
+        if let Some(choice) = self.initial_world.take() {
+            if self.launch_world(choice) {
+                initial_state = GameState::Playing;
+            }
         }
+        self.set_state(initial_state, &window);
 
         // Construct the buffer of vertices (for single objects, we use OpenGL's instancing to multiply them)
         let cube_vertex_buffer = glium::VertexBuffer::new(&display, &VERTICES).unwrap();
@@ -123,27 +406,35 @@ impl WorldRenderer {
         let indices = glium::index::NoIndices(glium::index::PrimitiveType::TrianglesList);
 
         // Build the texture library, and change the sampler to use the proper filters
-        let textures = texture::build_textures_array(&display);
+        // The assets of the resource pack replace the built-in ones
+        let pack = ResourcePack::new(&self.settings.resource_pack);
+        let textures = TextureAtlas::get().build_texture(&display, &pack);
         let cubes_texture_sampler = textures
             .sampled()
             .magnify_filter(MagnifySamplerFilter::Nearest)
-            .minify_filter(MinifySamplerFilter::Nearest);
+            .minify_filter(MinifySamplerFilter::NearestMipmapNearest);
 
         // Load other textures that are used
         let selected_texture = texture::load_texture(
-            std::fs::read("./resources/selected.png")
+            pack.read("selected.png")
+                .unwrap()
+                .as_slice(),
+            &display,
+        );
+        let crack_texture = texture::load_texture(
+            pack.read("destroy_stages.png")
                 .unwrap()
                 .as_slice(),
             &display,
         );
         let font_atlas = texture::load_texture(
-            std::fs::read("./resources/fonts.png").unwrap().as_slice(),
+            pack.read("fonts.png").unwrap().as_slice(),
             &display,
         );
 
         // Textures for entities
         let humanoid_texture = humanoid::load_humanoid_textures(
-            "./resources/entity/", &display);
+            &pack, "entity/", &display);
         let humanoid_texture_sample = humanoid_texture
             .sampled()
             .magnify_filter(MagnifySamplerFilter::Nearest)
@@ -154,9 +445,15 @@ impl WorldRenderer {
         let cube_program =
             glium::Program::from_source(&display, CUBE_VERTEX_SHADER, CUBE_FRAGMENT_SHADER, None)
                 .unwrap();
+        let chunk_program =
+            glium::Program::from_source(&display, CHUNK_VERTEX_SHADER, CHUNK_FRAGMENT_SHADER, None)
+                .unwrap();
         let rect_program =
             glium::Program::from_source(&display, RECT_VERTEX_SHADER, RECT_FRAGMENT_SHADER, None)
                 .unwrap();
+        let outline_program =
+            glium::Program::from_source(&display, OUTLINE_VERTEX_SHADER, OUTLINE_FRAGMENT_SHADER, None)
+                .unwrap();
         let entity_program = glium::Program::from_source(
             &display,
             ENTITY_VERTEX_SHADER,
@@ -173,14 +470,7 @@ impl WorldRenderer {
         self.update_items_bar();
 
         // Initially, ask for server updates
-        self.proxy
-            .lock()
-            .unwrap()
-            .send_position_update(self.player.position().clone());
-        self.handle_server_updates();
-
-        // Initialize cube_to_draw, this SHOULD NOT go into handle_server_update as it is call at every loop !
-        self.world.set_cubes_to_draw();
+        self.start_world();
 
         // Uniform for rect computed before the loop
         let rect_uniforms = uniform! {
@@ -192,8 +482,10 @@ impl WorldRenderer {
         // Event loop
         let mut t = Instant::now();
         let initial_waiting_delay =
-            Duration::from_millis(self.proxy.lock().unwrap().loading_delay());
+            Duration::from_millis(self.with_proxy(|proxy| proxy.loading_delay()).unwrap_or(0));
         let mut is_initializing = true;
+        // Time of the animated textures
+        let animation_clock = Instant::now();
         event_loop.run(move |event, window_target| {
 
             // This block is here to leave some initial time for the world to load updates.
@@ -210,33 +502,97 @@ impl WorldRenderer {
                     winit::event::WindowEvent::CloseRequested => window_target.exit(),
                     winit::event::WindowEvent::Resized(_) => {
                         self.hud_renderer.set_dimension(display.get_framebuffer_dimensions());
+                        self.projection.set_dimensions(display.get_framebuffer_dimensions());
                     }
                     winit::event::WindowEvent::RedrawRequested => {
                         // Step the camera with the elapsed time
                         let dt = t.elapsed();
                         t = Instant::now();
 
+                        // The settings may have been changed since the last frame
+                        if self.settings.fullscreen != self.fullscreen {
+                            self.toggle_fullscreen(&window);
+                        }
+
+                        // In the menus, the world is frozen
+                        let playing = self.state.is_playing();
+
                         let mut target = display.draw();
-                        target.clear_color_and_depth(Color::Sky1.to_tuple(), 1.0);
+                        if playing {
+                            self.sky.step(dt.as_secs_f32());
+                        }
+                        target.clear_color_and_depth(self.sky.clear_color().to_tuple(), 1.0);
 
                         // Step the camera with the elapsed time
-                        // Try to break the selected cube
-                        if self.player.is_time_to_break_over(dt.as_secs_f32()) {
+                        // Try to break the selected cube, with the tool in hand
+                        self.player.set_tool(self.items.current_tool());
+                        if playing && self.player.is_time_to_break_over(dt.as_secs_f32()) {
                             self.apply_action(Destroy { at: self.player.selected_cube().unwrap().to_cube_coordinates() });
                         }
+                        // Try to place a block, at a steady rate while the button is held
+                        if playing && self.player.is_time_to_place() {
+                            self.place_selected_block(&window);
+                        }
+                        // The bow is drawn while the button is held, and shoots when it is released
+                        let drawing = playing && self.items.current_tool() == Some(Tool::Bow) && self.player.right_click();
+                        if let Some(speed) = self.bow.step(drawing, dt.as_secs_f32()) {
+                            self.shoot_arrow(speed);
+                        }
+
+                        // The controller acts like the keyboard and the mouse, except in the inventory
+                        let gamepad_actions = self.gamepad.poll();
+                        if playing && !self.hud_renderer.is_inventory_open() {
+                            for (action, pressed) in gamepad_actions {
+                                self.handle_input_action(action, pressed, &window);
+                            }
+                            let [right, forward] = self.gamepad.movement();
+                            self.player.set_analog_movement(right, forward);
+                            let [yaw, pitch] = self.gamepad.look(dt.as_secs_f32());
+                            self.player.mousemove(yaw, pitch, 1.);
+                        }
 
                         // Step
+                        self.profile.reset();
                         self.fps_manager.step(dt);
-                        self.player.step(dt, &self.world);
+                        if playing {
+                            let before = self.player.position().pos();
+                            self.player.step_with_profile(dt, &self.world, &mut self.profile);
+                            if self.player.game_mode().takes_fall_damage() {
+                                self.hurt(fall_damage(self.player.landing_speed()));
+                            }
+                            if self.player.game_mode().gets_hungry() {
+                                self.step_hunger(dt.as_secs_f32(), &before);
+                            }
+                            self.world.step_entities(dt.as_secs_f32());
+                            let player = self.player.position().pos() / self.world.scale();
+                            if pick_up(self.world.entities_mut(), &player, &mut self.items) {
+                                self.update_items_bar();
+                            }
+                        }
+
+                        if self.chat.step(dt.as_secs_f32()) {
+                            self.hud_renderer.set_chat(&self.chat);
+                        }
+
+                        self.entity_manager.step(dt.as_secs_f32());
 
                         // Server updates
-                        self.proxy.lock().unwrap().send_position_update(self.player.position().clone());
+                        self.send_position();
+                        self.send_remote_hits();
+                        let start = self.profile.start();
                         self.handle_server_updates();
+                        self.update_chunk_meshes(&display);
+                        self.profile.stop(Phase::ChunkMeshing, start);
 
                         // HUD updates
+                        self.hud_renderer.set_underwater(self.world.is_in_water(&self.player.camera_position()));
+                        self.update_name_tags();
                         if self.hud_renderer.show_debug() {
-                            self.hud_renderer
-                                .set_debug(DebugData::new(self.fps_manager.fps(), self.player.position().clone(), self.world.number_cubes_rendered()));
+                            let chunk = World::chunk_of(&(self.player.position().pos() / self.world.scale()));
+                            let debug = DebugData::new(self.fps_manager.fps(), self.player.position().clone(), self.world.number_cubes_rendered())
+                                .with_frame_time(self.fps_manager.frame_time())
+                                .with_chunks(chunk, self.world.number_loaded_chunks(), self.world.chunks_memory());
+                            self.hud_renderer.set_debug(debug);
                         }
 
                         // I) Draw the cubes
@@ -253,18 +609,46 @@ impl WorldRenderer {
                             ..Default::default()
                         };
 
+                        // The cube targeted by the player, with the cracks of its breaking
+                        let highlight = BlockHighlight::new(self.player.selected_cube(), self.highlight_enabled, self.selection_mode)
+                            .with_break_progress(if self.player.left_click() { self.player.break_progress() } else { 0. });
+
                         // Define our uniforms (same uniforms for all cubes)...
                         let uniforms = uniform! {
                             view: self.player.view_matrix(),
-                            perspective: perspective_matrix(target.get_dimensions()),
+                            perspective: self.projection.matrix(self.world.scale(), self.player.fov_factor()),
+                            scale: self.world.scale(),
                             textures: cubes_texture_sampler,
                             selected_texture: &selected_texture,
-                            selected_intensity: if self.player.left_click() {self.player.left_click_time() / CLICK_TIME_TO_BREAK} else {0.2},
+                            selected_intensity: highlight.intensity,
+                            crack_texture: crack_texture.sampled().magnify_filter(MagnifySamplerFilter::Nearest),
+                            crack_stage: highlight.crack.map_or(-1, |stage| stage as i32),
+                            crack_stages: CRACK_STAGES as i32,
+                            light_direction: self.sky.light_direction(),
+                            ambient: self.sky.ambient(),
+                            sun_intensity: self.sky.sun_intensity(),
                         };
 
                         // We use OpenGL's instancing feature which allows us to render huge amounts ot cubes at once.
                         // OpenGL instancing = instead of setting 1000 times different uniforms, you give once 1000 attributes
-                        let position_buffer = self.world.get_cubes_buffer(&display, self.player.selected_cube());
+                        let start = self.profile.start();
+                        self.world.update_lod(&self.player.position().pos(), self.lod_distance);
+                        self.world.update_fade_in(Instant::now(), self.chunk_fade_duration);
+                        self.world.set_animation_time(animation_clock.elapsed().as_secs_f32(), ANIMATION_FPS);
+                        self.profile.stop(Phase::Culling, start);
+                        let start = self.profile.start();
+                        // The chunks out of the view are not drawn at all
+                        let projection = self.projection.matrix(self.world.scale(), self.player.fov_factor());
+                        let outside = self.world.chunks_outside(&self.player.frustum(&projection));
+                        // The coarse chunks are still drawn with their super-cubes
+                        let meshed: HashSet<ChunkCoord> = self.chunk_meshes
+                            .keys()
+                            .filter(|coord| !self.world.is_coarse(coord) && !outside.contains(coord))
+                            .copied()
+                            .collect();
+                        let skipped: HashSet<ChunkCoord> = meshed.union(&outside).copied().collect();
+                        let position_buffer = self.world.get_cubes_buffer(&display, &skipped);
+                        self.profile.stop(Phase::InstanceUpload, start);
                         target.draw(
                             (&cube_vertex_buffer, position_buffer.per_instance().unwrap()),
                             &indices,
@@ -272,18 +656,90 @@ impl WorldRenderer {
                             &uniforms,
                             &params).unwrap();
 
+                        // The meshes are drawn one chunk at a time, with the opacity of the chunk
+                        let now = Instant::now();
+                        for coord in &meshed {
+                            let (vertices, mesh_indices) = &self.chunk_meshes[coord];
+                            let chunk_uniforms = uniform! {
+                                view: self.player.view_matrix(),
+                                perspective: self.projection.matrix(self.world.scale(), self.player.fov_factor()),
+                                scale: self.world.scale(),
+                                textures: cubes_texture_sampler,
+                                alpha: self.world.chunk_alpha(*coord, now, self.chunk_fade_duration),
+                                light_direction: self.sky.light_direction(),
+                                ambient: self.sky.ambient(),
+                                sun_intensity: self.sky.sun_intensity(),
+                                animation_time: animation_clock.elapsed().as_secs_f32(),
+                                animation_fps: ANIMATION_FPS,
+                            };
+                            target.draw(
+                                vertices,
+                                mesh_indices,
+                                &chunk_program,
+                                &chunk_uniforms,
+                                &params).unwrap();
+                        }
+
+                        // The entities, drawn apart from the cubes of the chunks
+                        if !self.world.entities().is_empty() {
+                            let entities_buffer = VertexBuffer::immutable(&display, &self.world.entities().instances()).unwrap();
+                            target.draw(
+                                (&cube_vertex_buffer, entities_buffer.per_instance().unwrap()),
+                                &indices,
+                                &cube_program,
+                                &uniforms,
+                                &params).unwrap();
+                        }
+
+                        // The cube targeted by the player, over the cubes and the meshes
+                        if let Some(faces) = highlight.faces {
+                            let faces_buffer = VertexBuffer::immutable(&display, &[faces]).unwrap();
+                            target.draw(
+                                (&cube_vertex_buffer, faces_buffer.per_instance().unwrap()),
+                                &indices,
+                                &cube_program,
+                                &uniforms,
+                                &params).unwrap();
+                        }
+                        let outline = &self.crosshair_config;
+                        if let Some(edges) = highlight.edges.filter(|_| outline.outline_thickness > 0.) {
+                            let edges_buffer = VertexBuffer::immutable(&display, &edges).unwrap();
+                            let outline_uniforms = uniform! {
+                                view: self.player.view_matrix(),
+                                perspective: self.projection.matrix(self.world.scale(), self.player.fov_factor()),
+                                scale: self.world.scale(),
+                                outline_color: outline.outline_color.rgba(),
+                            };
+                            let outline_params = glium::DrawParameters {
+                                line_width: Some(outline.outline_thickness),
+                                ..params.clone()
+                            };
+                            target.draw(
+                                &edges_buffer,
+                                glium::index::NoIndices(glium::index::PrimitiveType::LinesList),
+                                &outline_program,
+                                &outline_uniforms,
+                                &outline_params).unwrap();
+                        }
+
                         // II) Draw the entity
 
                         // Define our uniforms (same uniforms for all cubes)...
                         let entity_uniforms = uniform! {
                             view: self.player.view_matrix(),
-                            perspective: perspective_matrix(target.get_dimensions()),
+                            perspective: self.projection.matrix(self.world.scale(), self.player.fov_factor()),
                             entity_textures: humanoid_texture_sample,
                         };
 
                         // Prepare the entity buffer to send to the gpu
                         // TODO why is this dynamic and not immutable ?
-                        let entity_buffer = glium::VertexBuffer::dynamic(&display, &mut self.entity_manager.get_opengl_entities()).unwrap();
+                        let mut entities = self.entity_manager.get_opengl_entities();
+                        entities.extend(self.world.entities().parts(self.world.scale()));
+                        // The player only sees itself from behind
+                        if self.player.camera_mode() == CameraMode::ThirdPerson {
+                            entities.extend(humanoid::get_opengl_entities(self.player.position().clone(), EntityKind::Player.to_u8()));
+                        }
+                        let entity_buffer = glium::VertexBuffer::dynamic(&display, &mut entities).unwrap();
                         target.draw(
                             (&cube_vertex_buffer, entity_buffer.per_instance().unwrap()),
                             &indices,
@@ -291,7 +747,29 @@ impl WorldRenderer {
                             &entity_uniforms,
                             &params).unwrap();
 
-                        // III) Drawn the tiles
+                        // III) Draw the transparent cubes
+                        // They come last and back-to-front so that they can blend with everything behind them.
+                        // They still test against the depth buffer but don't write in it, otherwise
+                        // the faces behind the closest water would be discarded.
+                        let transparent_params = glium::DrawParameters {
+                            depth: glium::Depth {
+                                test: glium::draw_parameters::DepthTest::IfLessOrEqual,
+                                write: false,
+                                ..Default::default()
+                            },
+                            ..params.clone()
+                        };
+                        let start = self.profile.start();
+                        let transparent_buffer = self.world.get_transparent_cubes_buffer(&display, &self.player.position().pos());
+                        self.profile.stop(Phase::InstanceUpload, start);
+                        target.draw(
+                            (&cube_vertex_buffer, transparent_buffer.per_instance().unwrap()),
+                            &indices,
+                            &cube_program,
+                            &uniforms,
+                            &transparent_params).unwrap();
+
+                        // IV) Drawn the tiles
 
                         // We change the draw parameters here to allow transparency.
                         let draw_parameters = glium::draw_parameters::DrawParameters {
@@ -309,8 +787,16 @@ impl WorldRenderer {
                         target.finish().unwrap();
                     }
                     winit::event::WindowEvent::MouseInput { device_id: _, state, button } => {
+                        if !self.state.is_playing() {
+                            // Clicking a button of the menu chooses it
+                            let (x, y) = self.cursor;
+                            if button == MouseButton::Left && state == Pressed && self.hud_renderer.hover_menu(x, y) && self.choose_menu_entry(&window) {
+                                window_target.exit();
+                            }
+                            return;
+                        }
                         if !self.hud_renderer.is_inventory_open() {
-                            self.handle_button_event(button, state)
+                            self.handle_button_event(button, state, &window)
                         }
 
                         // left click
@@ -318,20 +804,41 @@ impl WorldRenderer {
                             self.hud_renderer.maybe_forward_inventory_event(InventoryEvent::Button(state))
                         }
                     }
-                    winit::event::WindowEvent::KeyboardInput { device_id: _, event, is_synthetic: _ } => self.handle_key_event(event, &window),
+                    winit::event::WindowEvent::MouseWheel { delta, .. } => {
+                        if self.state.is_playing() && !self.hud_renderer.is_inventory_open() {
+                            self.handle_wheel_event(delta)
+                        }
+                    }
+                    winit::event::WindowEvent::KeyboardInput { device_id: _, event, is_synthetic: _ } => {
+                        if self.handle_key_event(event, &window) {
+                            window_target.exit();
+                        }
+                    }
+                    // The game pauses when the player switches to another window
+                    winit::event::WindowEvent::Focused(false) => {
+                        if self.state.is_playing() {
+                            self.set_state(GameState::Paused, &window);
+                        }
+                    }
                     // Inventory requires us to deal with cursor events, not mouse events
                     // TODO capture nicely the events for the inventory
                     winit::event::WindowEvent::CursorMoved { position, .. } => {
                         let x: f32 = -1. + 2. * position.x as f32 / window.inner_size().width as f32;
                         let y: f32 = 1. - 2. * position.y as f32 / window.inner_size().height as f32;
-                        self.hud_renderer.maybe_forward_inventory_event(InventoryEvent::CursorMoved(x, y));
+                        self.cursor = (x, y);
+                        if self.state.is_playing() {
+                            self.hud_renderer.maybe_forward_inventory_event(InventoryEvent::CursorMoved(x, y));
+                        } else {
+                            self.hud_renderer.hover_menu(x, y);
+                        }
                     }
                     _ => (),
                 },
                 winit::event::Event::AboutToWait => {
-                    let opt_time_to_sleep = (t + TARGET_FRAME_DURATION - MIN_SLEEP_TIME).checked_duration_since(Instant::now());
+                    let opt_time_to_sleep = (t + frame_duration(&window) - MIN_SLEEP_TIME).checked_duration_since(Instant::now());
 
-                    if let Some(time_to_sleep) = opt_time_to_sleep {
+                    // Without the vsync, the next frame is drawn right away
+                    if let (Some(time_to_sleep), true) = (opt_time_to_sleep, self.settings.vsync) {
                         std::thread::sleep(time_to_sleep + MIN_SLEEP_TIME);
                     }
                     window.request_redraw()
@@ -345,13 +852,21 @@ impl WorldRenderer {
         }).unwrap();
     }
 
-    fn handle_key_event(&mut self, event: KeyEvent, window: &Window) {
+    /// Returns true when the player asked to quit the game
+    fn handle_key_event(&mut self, event: KeyEvent, window: &Window) -> bool {
         self.handle_general_key_event(&event, window);
-        
-        if self.hud_renderer.is_inventory_open() {
-            self.handle_inventory_key_event(event, window)
+
+        if !self.state.is_playing() {
+            self.handle_menu_key_event(event, window)
+        } else if self.chat.is_open() {
+            self.handle_chat_key_event(event);
+            false
+        } else if self.hud_renderer.is_inventory_open() {
+            self.handle_inventory_key_event(event, window);
+            false
         } else {
-            self.handle_game_key_event(event, window)
+            self.handle_game_key_event(event, window);
+            false
         }
     }
 
@@ -363,7 +878,13 @@ impl WorldRenderer {
                 PhysicalKey::Code(key) => {
                     match key {
                         KeyCode::F11 => self.toggle_fullscreen(&window),
-                        KeyCode::Escape => std::process::exit(1),
+                        // Escape closes the chat or the inventory, or else pauses and resumes the game
+                        KeyCode::Escape if self.chat.is_open() => {
+                            self.chat.close();
+                            self.hud_renderer.set_chat(&self.chat);
+                        }
+                        KeyCode::Escape if self.hud_renderer.is_inventory_open() => self.close_inventory(window),
+                        KeyCode::Escape => self.set_state(self.state.escape(), window),
                         _ => {}
                     }
                 }
@@ -376,14 +897,8 @@ impl WorldRenderer {
         if event.state == Pressed {
             match event.physical_key {
                 PhysicalKey::Code(key) => {
-                    match key {
-                        KeyCode::KeyE => {
-                            if let Some(items) = self.hud_renderer.close_inventory() {
-                                self.items = items;
-                                window.set_cursor_visible(false);
-                                self.update_items_bar();
-                            }
-                        }
+                    match self.settings.controls.action_for_key(key) {
+                        Some(InputAction::OpenInventory) => self.close_inventory(window),
                         _ => {}
                     }
                 }
@@ -392,113 +907,448 @@ impl WorldRenderer {
         }
     }
 
-    fn handle_game_key_event(&mut self, event: KeyEvent, window: &Window) {
-        // Handle keys related to motion (toggle is important here)
-        if event.repeat {
+    /// Writes the characters typed in the chat, and sends the line with enter
+    fn handle_chat_key_event(&mut self, event: KeyEvent) {
+        if event.state != Pressed {
             return;
         }
-        let pressed = event.state.is_pressed();
         match event.physical_key {
-            PhysicalKey::Code(key) => {
-                match key {
-                    KeyCode::KeyW => self.player.toggle_state(MotionState::Up, pressed),
-                    KeyCode::KeyS => self.player.toggle_state(MotionState::Down, pressed),
-                    KeyCode::KeyD => self.player.toggle_state(MotionState::Right, pressed),
-                    KeyCode::KeyA => self.player.toggle_state(MotionState::Left, pressed),
-                    KeyCode::KeyK => self.player.up(),
-                    KeyCode::KeyJ => self.player.down(),
-                    KeyCode::Space => self.player.toggle_state(MotionState::Jump, pressed),
-                    _ => {}
+            PhysicalKey::Code(KeyCode::Enter) => {
+                if let Some(text) = self.chat.send() {
+                    self.with_proxy(|proxy| proxy.send_chat(text));
+                }
+            }
+            PhysicalKey::Code(KeyCode::Backspace) => self.chat.erase(),
+            _ => {
+                if let Some(text) = &event.text {
+                    text.chars().for_each(|c| self.chat.type_char(c));
+                }
+            }
+        }
+        self.hud_renderer.set_chat(&self.chat);
+    }
+
+    /// Moves in the menu with the arrows, and chooses an entry with enter.
+    /// The characters typed go to the selected text field, if any.
+    /// Returns true when the player asked to quit the game.
+    fn handle_menu_key_event(&mut self, event: KeyEvent, window: &Window) -> bool {
+        if event.state != Pressed {
+            return false;
+        }
+        let selected = self.hud_renderer.selected_menu_entry();
+        if let Some(field) = selected.filter(|entry| entry.is_text_field()) {
+            if event.physical_key == PhysicalKey::Code(KeyCode::Backspace) {
+                self.new_world.erase(field);
+            } else if let Some(text) = &event.text {
+                text.chars().for_each(|c| self.new_world.type_char(field, c));
+            }
+            self.menu_error = None;
+            self.refresh_menu(selected);
+        }
+        match event.physical_key {
+            PhysicalKey::Code(KeyCode::ArrowUp) => self.hud_renderer.move_menu_selection(-1),
+            PhysicalKey::Code(KeyCode::ArrowDown) => self.hud_renderer.move_menu_selection(1),
+            PhysicalKey::Code(KeyCode::Enter) => return self.choose_menu_entry(window),
+            _ => {}
+        }
+        false
+    }
+
+    /// Does what the selected entry of the menu asks for. Returns true when the game must stop.
+    fn choose_menu_entry(&mut self, window: &Window) -> bool {
+        let Some(entry) = self.hud_renderer.selected_menu_entry() else {
+            return false;
+        };
+        match entry {
+            // Applied at the next frame, with the window
+            MenuEntry::Fullscreen => self.settings.fullscreen = !self.settings.fullscreen,
+            MenuEntry::Vsync => self.settings.vsync = !self.settings.vsync,
+            MenuEntry::FieldOfView => self.set_fov(next_fov(self.settings.fov)),
+            MenuEntry::RenderDistance => {
+                let mut settings = self.settings.clone();
+                settings.render_distance = next_render_distance(settings.render_distance);
+                self.set_settings(settings);
+            }
+            MenuEntry::SaveAndQuit => {
+                self.with_proxy(|proxy| proxy.save_world());
+            }
+            MenuEntry::Generator => self.new_world.generator = self.new_world.generator.next(),
+            MenuEntry::FlatLayers => self.new_world.next_flat_layers(),
+            MenuEntry::OpenWorld(index) => {
+                let Some(name) = self.saved_worlds.get(index).map(|meta| meta.name.clone()) else {
+                    return false;
+                };
+                if !self.launch_world(WorldChoice::Load(name)) {
+                    return false;
+                }
+            }
+            MenuEntry::Create => {
+                if let Err(reason) = self.new_world.check(&SaveDir::default()) {
+                    self.menu_error = Some(reason);
+                    self.refresh_menu(Some(entry));
+                    return false;
+                }
+                if !self.launch_world(WorldChoice::Create(self.new_world.clone())) {
+                    return false;
                 }
             }
             _ => {}
         }
+        match self.state.choose(entry) {
+            Some(state) => {
+                self.set_state(state, window);
+                false
+            }
+            None => {
+                self.save_settings();
+                true
+            }
+        }
+    }
+
+    /// Changes the state of the game: the menu shown, the grab of the mouse, and whether the world runs
+    fn set_state(&mut self, state: GameState, window: &Window) {
+        // Connected to a remote server, there is no world to choose
+        let state = if state == GameState::WorldList && self.launcher.is_none() { GameState::Playing } else { state };
+        let in_settings = |state: GameState| matches!(state, GameState::Settings { .. });
+        if in_settings(self.state) && !in_settings(state) {
+            self.save_settings();
+        }
+        if state == GameState::WorldList {
+            self.saved_worlds = SaveDir::default().summaries();
+        }
+        // The same menu keeps its selection, for the entries changing a setting
+        let selected = if state == self.state { self.hud_renderer.selected_menu_entry() } else { None };
+        if state != self.state {
+            self.menu_error = None;
+        }
+        self.state = state;
+
+        self.with_proxy(|proxy| proxy.set_paused(!state.is_playing()));
+        self.player.release_inputs();
+        self.grab_cursor(state.is_playing(), window);
+        self.refresh_menu(selected);
+    }
+
+    /// Builds again the menu of the current state, with the entry selected if it is still there
+    fn refresh_menu(&mut self, selected: Option<MenuEntry>) {
+        let menu = match self.state {
+            GameState::WorldList => Some(world_list_menu(&self.saved_worlds, unix_time())),
+            GameState::CreateWorld => Some(create_world_menu(&self.new_world, self.menu_error)),
+            state => state.menu().map(|(title, entries)| {
+                let labels = entries.iter().map(|entry| (*entry, entry.label(&self.settings))).collect();
+                MenuScreen::new(title, labels)
+            }),
+        };
+        let menu = menu.map(|mut menu| {
+            if let Some(entry) = selected {
+                menu.select(entry);
+            }
+            menu
+        });
+        self.hud_renderer.set_menu(menu);
+    }
+
+    /// Runs `f` with the proxy, when connected to a server
+    fn with_proxy<T>(&self, f: impl FnOnce(&mut dyn Proxy) -> T) -> Option<T> {
+        self.proxy.as_ref().map(|proxy| f(&mut *proxy.lock().unwrap()))
+    }
+
+    /// Starts the chosen world with the launcher, and connects to its server.
+    /// Returns false if it could not be started.
+    fn launch_world(&mut self, choice: WorldChoice) -> bool {
+        let Some(launcher) = self.launcher.as_mut() else {
+            return false;
+        };
+        match launcher(choice) {
+            Ok(proxy) => {
+                proxy.lock().unwrap().set_render_distance(self.settings.render_distance);
+                self.proxy = Some(proxy);
+                // All the chunks and the entities come from the new server
+                self.world = World::empty();
+                self.chunk_meshes.clear();
+                self.mesh_tickets.clear();
+                self.entity_manager = EntityManager::new();
+                self.remote_entities.clear();
+                self.prediction = MovePrediction::new();
+                self.start_world();
+                true
+            }
+            Err(err) => {
+                println!("Could not open the world: {err}");
+                false
+            }
+        }
+    }
 
-        // Second match is for other stuff that only needs to be detected when pressed
+    /// Projects the names of the other players close enough on the screen, above their head
+    fn update_name_tags(&mut self) {
+        let scale = self.world.scale();
+        let view = self.player.view_matrix();
+        let projection = self.projection.matrix(scale, self.player.fov_factor());
+        let tags: Vec<(String, [f32; 2])> = self.entity_manager
+            .name_tags(self.player.camera_position(), NAME_TAG_RANGE * scale, scale)
+            .into_iter()
+            .filter_map(|(name, at)| Some((name, project_on_screen(&view, &projection, at)?)))
+            .collect();
+        self.hud_renderer.set_name_tags(&tags);
+    }
+
+    /// Sends the hits on the entities of the world of the server (the pigs...) to it, see `EntityStore::take_remote_hits`
+    fn send_remote_hits(&mut self) {
+        for (local, damage, direction) in self.world.entities_mut().take_remote_hits() {
+            if let Some(id) = self.remote_entities.iter().find_map(|(id, other)| (*other == local).then_some(*id)) {
+                self.with_proxy(|proxy| proxy.hit_entity(id, damage, direction));
+            }
+        }
+    }
+
+    /// Sends the position of the player, moved by the client without waiting for the server.
+    /// It is tagged to be matched with the position kept by the server, see `ServerUpdate::PositionAck`.
+    fn send_position(&mut self) {
+        let Some(proxy) = self.proxy.as_ref() else {
+            return;
+        };
+        let position = self.player.position().clone();
+        let sequence = self.prediction.predict(position.pos());
+        proxy.lock().unwrap().send_position_update(sequence, position);
+    }
+
+    /// Asks the server for the first chunks around the player
+    fn start_world(&mut self) {
+        self.send_position();
+        self.handle_server_updates();
+
+        // Initialize cube_to_draw, this SHOULD NOT go into handle_server_update as it is call at every loop !
+        self.world.set_cubes_to_draw();
+        self.world.mark_all_meshes_dirty();
+    }
+
+    /// Locks the mouse to the window and hides it while playing, releases it in the menus
+    fn grab_cursor(&self, grab: bool, window: &Window) {
+        if grab {
+            // Try to lock the mouse to the window, this doen't work for all OS
+            let lock_mouse = window
+                .set_cursor_grab(CursorGrabMode::Confined)
+                .or_else(|_e| window.set_cursor_grab(CursorGrabMode::Locked));
+            if lock_mouse.is_err() {
+                println!("Can't lock")
+            }
+        } else if let Err(err) = window.set_cursor_grab(CursorGrabMode::None) {
+            println!("Can't release the mouse: {err}");
+        }
+        window.set_cursor_visible(!grab);
+    }
+
+    /// Gives the items back to the player, unless some are left in the crafting grid.
+    /// The items left in an opened chest are stored in it, in the world and on the server.
+    fn close_inventory(&mut self, window: &Window) {
+        if let Some((items, container)) = self.hud_renderer.close_inventory() {
+            self.items = items;
+            window.set_cursor_visible(false);
+            if let Some((at, container)) = container {
+                self.apply_action(SetBlockEntity { at, entity: BlockEntity::Chest(container) });
+            }
+            self.update_items_bar();
+        }
+    }
+
+    fn handle_game_key_event(&mut self, event: KeyEvent, window: &Window) {
+        // Handle keys related to motion (toggle is important here)
+        if event.repeat {
+            return;
+        }
+        let PhysicalKey::Code(key) = event.physical_key else {
+            return;
+        };
+        if let Some(action) = self.settings.controls.action_for_key(key) {
+            self.handle_input_action(action, event.state.is_pressed(), window);
+        }
+
+        // The item bar shortcuts are not bound in the input map
         if event.state == Pressed {
-            match event.physical_key {
-                PhysicalKey::Code(key) => {
-                    match key {
-                        // Inventory
-                        KeyCode::KeyE => {
-                            self.hud_renderer.open_inventory(self.items.clone());
-                            window.set_cursor_visible(true);
-                        }
+            let slot = match key {
+                KeyCode::Digit1 => Some(0),
+                KeyCode::Digit2 => Some(1),
+                KeyCode::Digit3 => Some(2),
+                KeyCode::Digit4 => Some(3),
+                KeyCode::Digit5 => Some(4),
+                KeyCode::Digit6 => Some(5),
+                KeyCode::Digit7 => Some(6),
+                KeyCode::Digit8 => Some(7),
+                KeyCode::Digit9 => Some(8),
+                _ => None,
+            };
+            if let Some(slot) = slot {
+                self.items.set_current_item(slot);
+                self.update_items_bar();
+            }
+        }
+    }
 
-                        // Item bar shortcuts
-                        KeyCode::Digit1 => {
-                            self.items.set_current_item(0);
-                            self.update_items_bar();
-                        }
-                        KeyCode::Digit2 => {
-                            self.items.set_current_item(1);
-                            self.update_items_bar();
-                        }
-                        KeyCode::Digit3 => {
-                            self.items.set_current_item(2);
-                            self.update_items_bar();
-                        }
-                        KeyCode::Digit4 => {
-                            self.items.set_current_item(3);
-                            self.update_items_bar();
-                        }
-                        KeyCode::Digit5 => {
-                            self.items.set_current_item(4);
-                            self.update_items_bar();
-                        }
-                        KeyCode::Digit6 => {
-                            self.items.set_current_item(5);
-                            self.update_items_bar();
-                        }
-                        KeyCode::Digit7 => {
-                            self.items.set_current_item(6);
-                            self.update_items_bar();
-                        }
-                        KeyCode::Digit8 => {
-                            self.items.set_current_item(7);
-                            self.update_items_bar();
-                        }
-                        KeyCode::Digit9 => {
-                            self.items.set_current_item(8);
-                            self.update_items_bar();
-                        }
-                        KeyCode::KeyP => {
-                            println!("=================");
-                            println!("Debug Information");
-                            println!("=================");
-                            self.player.debug();
-                        }
-                        KeyCode::KeyX => {
-                            println!("Ask to spawn a monster");
-                            let mut monster_pos = Position::new(self.player.position().pos().clone(), 0., 0.);
-                            monster_pos.small_raise();
-                            self.proxy.lock().unwrap().request_to_spawn(monster_pos);
-                            
-                        }
-                        KeyCode::F10 => self.world.save_to_file("map.json"),
-                        KeyCode::F3 => self.hud_renderer.toggle_debug_menu(),
-                        KeyCode::F12 => self.hud_renderer.toggle_help_menu(),
-                        _ => {}
+    /// Does what the key or the button bound to the action asks for.
+    /// The motions follow the input while it is held, the other actions happen once when it is pressed.
+    fn handle_input_action(&mut self, action: InputAction, pressed: bool, window: &Window) {
+        if action == InputAction::BreakBlock && pressed && self.attack_entity() {
+            return;
+        }
+        if action == InputAction::PlaceBlock && pressed && self.eat_current_item() {
+            return;
+        }
+        if action == InputAction::BreakBlock && !self.player.is_selecting_cube() {
+            // Without any cube to break, the click attacks the entity in front of the player
+            if pressed {
+                if let Some(mut attack) = self.entity_manager.attack(self.player.position().pos(), self.player.direction()) {
+                    // Forward the attack to the server
+                    attack.set_strength(self.items.attack_strength());
+                    self.with_proxy(|proxy| proxy.on_new_attack(attack));
+                    if self.player.game_mode().wears_tools() {
+                        self.items.use_current_tool();
+                        self.update_items_bar();
                     }
                 }
-                PhysicalKey::Unidentified(_) => {}
             }
+            return;
+        }
+        // The blocks are placed and broken at each frame while the input is held (see `place_selected_block`)
+        if let Some(state) = action.motion_state() {
+            self.player.toggle_state(state, pressed);
+            return;
+        }
+        if !pressed {
+            return;
+        }
+        match action {
+            InputAction::OpenInventory => {
+                self.hud_renderer.open_inventory(self.items.clone());
+                window.set_cursor_visible(true);
+            }
+            InputAction::DebugInfo => {
+                println!("=================");
+                println!("Debug Information");
+                println!("=================");
+                self.player.debug();
+            }
+            InputAction::SpawnMonster => {
+                println!("Ask to spawn a monster");
+                let mut monster_pos = Position::new(self.player.position().pos().clone(), 0., 0.);
+                monster_pos.small_raise();
+                self.with_proxy(|proxy| proxy.request_to_spawn(monster_pos));
+            }
+            InputAction::SaveMap => self.world.save_to_file("map.json"),
+            InputAction::Chat => {
+                // The keys are typed in the chat, the player stops moving
+                self.player.release_inputs();
+                self.chat.open();
+                self.hud_renderer.set_chat(&self.chat);
+            }
+            InputAction::DebugMenu => self.hud_renderer.toggle_debug_menu(),
+            InputAction::HelpMenu => self.hud_renderer.toggle_help_menu(),
+            InputAction::ThirdPerson => self.player.set_camera_mode(self.player.camera_mode().toggle()),
+            InputAction::ToggleGameMode => {
+                let game_mode = self.player.game_mode().toggle();
+                println!("Game mode: {game_mode:?}");
+                self.player.set_game_mode(game_mode);
+            }
+            _ => {}
+        }
+    }
+
+    /// Hits the entity of the world under the crosshair (closer than the selected cube) with the item in hand.
+    /// Returns false if there is none.
+    fn attack_entity(&mut self) -> bool {
+        let scale = self.world.scale();
+        let reach = self.player.selected_hit().map_or(self.player.game_mode().reach(), |hit| hit.distance / scale);
+        let eye = self.player.eye_position() / scale;
+        let direction = self.player.direction();
+        let Some((id, _)) = self.world.entities().raycast(&eye, &direction, reach) else {
+            return false;
+        };
+        if self.world.entities_mut().hit(id, self.items.attack_strength(), &direction) && self.player.game_mode().wears_tools() {
+            self.items.use_current_tool();
+            self.update_items_bar();
+        }
+        true
+    }
+
+    /// Eats the food in hand when the player is hungry. Returns false if there is nothing to eat.
+    fn eat_current_item(&mut self) -> bool {
+        let Some(food) = self.items.current_food() else {
+            return false;
+        };
+        if self.hunger.is_full() {
+            return false;
+        }
+        self.items.consume(food);
+        self.hunger.eat(food.points());
+        self.update_items_bar();
+        self.hud_renderer.set_food(&self.hunger);
+        true
+    }
+
+    /// Makes the player hungry with its sprint since `before` (its position before the step) and its jumps,
+    /// then heals or hurts it depending on its food
+    fn step_hunger(&mut self, dt: f32, before: &Vector3) {
+        let food = self.hunger.food();
+        let mut moved = self.player.position().pos() - *before;
+        moved[1] = 0.;
+        if self.player.is_sprinting() {
+            self.hunger.exhaust(moved.norm() / self.world.scale() * SPRINT_EXHAUSTION);
+        }
+        if self.player.jumped() {
+            self.hunger.exhaust(JUMP_EXHAUSTION);
+        }
+        match self.hunger.step(dt, self.health.health()) {
+            HungerEffect::Regenerate => {
+                self.health.heal(1);
+                self.hud_renderer.set_health(&self.health);
+            }
+            HungerEffect::Starve => self.hurt(STARVATION_DAMAGE),
+            HungerEffect::None => {}
+        }
+        if self.hunger.food() != food {
+            self.hud_renderer.set_food(&self.hunger);
         }
     }
 
+    /// Shoots an arrow of the inventory from the eyes of the player, along its look, at this speed [cube/s]
+    fn shoot_arrow(&mut self, speed: f32) {
+        if !self.items.remove(Item::Arrow, 1) {
+            return;
+        }
+        if self.player.game_mode().wears_tools() {
+            self.items.use_current_tool();
+        }
+        self.update_items_bar();
+        let direction = self.player.direction();
+        let eye = self.player.eye_position() / self.world.scale();
+        Arrow::spawn(self.world.entities_mut(), eye + direction * 0.5, direction * speed);
+    }
+
     fn apply_action(&mut self, action: Action) {
         // Handle items
         match action {
             Destroy { at } => {
-                if let Some(block) = self.world.block_at(&at) {
-                    self.items.collect(block.block_dropped());
+                // The world removes the cube and tells what it leaves behind, with the content of a chest
+                let tool = self.items.current_tool();
+                if self.world.break_block(at, self.player.game_mode(), tool).is_none() {
+                    return;
                 }
-            }
-            Add { at, block } => {
-                if self.player.is_in(at) {
-                    return; // cannot place a block on oneself
+                if self.player.game_mode().wears_tools() {
+                    self.items.use_current_tool();
                 }
-
+                for event in self.world.take_events() {
+                    match event {
+                        WorldEvent::ItemDropped { pos, stack } => {
+                            ItemDrop::spawn(self.world.entities_mut(), stack, pos);
+                        }
+                    }
+                }
+                self.update_items_bar();
+                self.with_proxy(|proxy| proxy.on_new_action(action));
+                return;
+            }
+            Add { block, .. } => {
                 // cannot place some blocks (i.e. swords)
                 if let Some(block) = self.items.get_current_block() {
                     if !block.can_be_placed() {
@@ -506,18 +1356,24 @@ impl WorldRenderer {
                     }
                 }
 
-                self.items.consume(block);
+                self.items.consume(block.item());
             }
+            SetBlockEntity { .. } => {}
         }
 
         // Currently, all actions end up editing the items.
         self.update_items_bar();
 
+        self.apply_world_action(action);
+    }
+
+    /// Applies the action on the cubes and forwards it to the server, without touching the items
+    fn apply_world_action(&mut self, action: Action) {
         // Handle cubes
         self.world.apply_action(&action);
 
         // Forward to server
-        self.proxy.lock().unwrap().on_new_action(action);
+        self.with_proxy(|proxy| proxy.on_new_action(action));
     }
 
     fn update_items_bar(&mut self) {
@@ -525,45 +1381,62 @@ impl WorldRenderer {
             .set_player_items(self.items.get_bar_items(), self.items.current_item());
     }
 
-    fn handle_button_event(&mut self, button: MouseButton, state: ElementState) {
-        match button {
-            MouseButton::Left => {
-                if self.player.is_selecting_cube() {
-                    self.player.toggle_state(MotionState::LeftClick, state.is_pressed());
-                } else if state.is_pressed() {
-                    if let Some(mut attack) = self.entity_manager.attack(self.player.position().pos(), self.player.direction()) {
-                        // Forward the attack to the server
-                        attack.set_strength(self.items.attack_strength());
-                        self.proxy.lock().unwrap().on_new_attack(attack);
-                    }
+    fn handle_button_event(&mut self, button: MouseButton, state: ElementState, window: &Window) {
+        if let Some(action) = self.settings.controls.action_for_button(button) {
+            self.handle_input_action(action, state.is_pressed(), window);
+        }
+    }
+
+    fn handle_wheel_event(&mut self, delta: MouseScrollDelta) {
+        let lines = match delta {
+            MouseScrollDelta::LineDelta(_, y) => y,
+            MouseScrollDelta::PixelDelta(position) => position.y as f32,
+        };
+        self.player.scroll(lines, &mut self.items);
+        self.update_items_bar();
+    }
+
+    /// Places the current block against the selected cube, or opens the selected chest
+    fn place_selected_block(&mut self, window: &Window) {
+        // We know where is the player and we know
+        if let Some(hit) = self.player.selected_hit() {
+            if *hit.cube.block() == CHEST {
+                let at = *hit.cube.position();
+                if let Some(container) = self.world.block_entity(&at).and_then(|entity| entity.container()) {
+                    // Opened once per click, the button is released while the chest is opened
+                    self.player.toggle_state(MotionState::RightClick, false);
+                    self.hud_renderer.open_container(self.items.clone(), at, container.clone());
+                    window.set_cursor_visible(true);
                 }
+                return;
             }
-            MouseButton::Right => {
-                if state == Pressed {
-                    // Right click = add a new cube
-                    // We know where is the player and we know
-                    if let Some(touched_cube) = self.player.selected_cube() {
-                        if let Some(block) = self.items.get_current_block() {
-                            // TODO Sometimes, I can't understand why, we end up not finding a place to add the new cube.
-                            //      I know this is not easy to debug...
-                            if let Ok(at) = touched_cube.position_to_add_new_cube(self.player.position().pos(), self.player.direction()) {
-                                self.apply_action(Action::Add { at, block, })
-                            }
-                        }
+            if self.items.current_tool() == Some(Tool::Hoe) {
+                if let Some(action) = self.world.till(&hit) {
+                    self.apply_world_action(action);
+                    if self.player.game_mode().wears_tools() {
+                        self.items.use_current_tool();
+                        self.update_items_bar();
                     }
                 }
+                return;
+            }
+            if let Some(block) = self.items.get_current_block() {
+                // The world checks that the cell is free and that we are not placing the block on ourself
+                let placement = self.world.place_block_on(&hit, block, &self.player.aabb());
+                if let Ok(action) = placement {
+                    self.apply_action(action)
+                }
             }
-            _ => ()
         }
     }
 
     fn handle_motion_event(&mut self, axis: AxisId, value: f64) {
         // TODO make something cleaner
-        if !self.hud_renderer.is_inventory_open() {
+        if self.state.is_playing() && !self.hud_renderer.is_inventory_open() {
             if axis == 0 {
-                self.player.mousemove(value as f32, 0.0, 0.005);
+                self.player.mousemove(value as f32, 0.0, self.settings.mouse_sensitivity);
             } else {
-                self.player.mousemove(0.0, -value as f32, 0.005);
+                self.player.mousemove(0.0, -value as f32, self.settings.mouse_sensitivity);
             }
         }
     }
@@ -577,27 +1450,86 @@ impl WorldRenderer {
             window.set_fullscreen(Some(Fullscreen::Borderless(Some(monitor_handle))));
             self.fullscreen = true;
         }
+        self.settings.fullscreen = self.fullscreen;
+    }
+
+    /// Takes the damage and, when the player dies, asks the server to respawn it at the spawn point of the world
+    /// (see `ServerUpdate::Respawn`) with full health and food
+    fn hurt(&mut self, damage: u8) {
+        if damage == 0 {
+            return;
+        }
+        self.health.damage(damage);
+        if !self.health.alive() {
+            if self.with_proxy(|proxy| proxy.request_respawn()).is_none() {
+                let spawn = self.world.find_safe_spawn().unwrap_or_else(Position::spawn_position);
+                self.player.respawn(spawn);
+            }
+            self.health.reset();
+            self.hunger.reset();
+            self.hud_renderer.set_food(&self.hunger);
+        }
+        self.hud_renderer.set_health(&self.health);
     }
 
     fn handle_server_updates(&mut self) {
-        let updates = self.proxy.lock().unwrap().consume_server_updates();
+        let updates = self.with_proxy(|proxy| proxy.consume_server_updates()).unwrap_or_default();
         for update in updates {
             match update {
                 ServerUpdate::LoadChunk(chunk) => self.world.add_chunk(chunk),
                 ServerUpdate::LoggedIn(client_id, position) => {
                     println!("Client registered ID: {client_id} with position: {position:?}");
-                    self.player.set_position(position)
+                    self.player.set_position(position);
+                    // The moves sent before started elsewhere, the server rejects them
+                    self.prediction.clear();
                 }
                 ServerUpdate::SendAction(action) => self.world.apply_action(&action),
                 ServerUpdate::RegisterEntity(id, entity_kind, pos) => {
                     self.entity_manager.register_new_entity(id, entity_kind, pos)
                 }
+                ServerUpdate::RegisterPlayer(id, name, pos) => self.entity_manager.register_player(id, name, pos),
                 ServerUpdate::UpdatePosition(id, pos) => self.entity_manager.set_position(id, pos),
                 ServerUpdate::Attack(attack) => {
-                    self.health.damage(attack.strength());
-                    self.hud_renderer.set_health(&self.health);
+                    if self.player.game_mode().is_attacked() {
+                        self.hurt(attack.strength());
+                    }
+                }
+                ServerUpdate::RemoveEntity(id) => self.entity_manager.remove_entity(id as u8),
+                ServerUpdate::UnloadChunk(coord) => {
+                    self.world.remove_chunk(coord);
+                }
+                ServerUpdate::PositionAck(sequence, position) => {
+                    // The server stopped the player where the client let it go
+                    let tolerance = RECONCILE_TOLERANCE * self.world.scale();
+                    if let Some(correction) = self.prediction.reconcile(sequence, position.pos(), tolerance) {
+                        let mut corrected = self.player.position().clone();
+                        corrected += correction;
+                        self.player.set_position(corrected);
+                    }
+                }
+                ServerUpdate::Chat(sender, text) => {
+                    self.chat.receive(sender, text);
+                    self.hud_renderer.set_chat(&self.chat);
+                }
+                ServerUpdate::WorldEntity(id, kind, position) => {
+                    let entities = self.world.entities_mut();
+                    let local = *self.remote_entities.entry(id).or_insert_with(|| kind.spawn_remote(entities, position.pos()));
+                    entities.place(local, position.pos(), position.yaw());
+                }
+                ServerUpdate::RemoveWorldEntity(id) => {
+                    if let Some(local) = self.remote_entities.remove(&id) {
+                        self.world.entities_mut().remove(local);
+                    }
+                }
+                ServerUpdate::Respawn(spawn) => {
+                    self.player.respawn(spawn);
+                    self.prediction.clear();
+                }
+                ServerUpdate::DropLoot(at, loot) => {
+                    for item in loot {
+                        ItemDrop::spawn(self.world.entities_mut(), ItemStack::new(item, 1), at);
+                    }
                 }
-                ServerUpdate::RemoveEntity(id) => self.entity_manager.remove_entity(id as u8)
             }
         }
     }
@@ -0,0 +1,283 @@
+use crate::args::WorldInitializer;
+use crate::game_state::MenuEntry;
+use crate::graphics::menu_screen::MenuScreen;
+use crate::network::proxy::Proxy;
+use crate::persistence::region::RegionStore;
+use crate::persistence::{SaveDir, SavedPlayer};
+use crate::world::World;
+use crate::world_generation::generators::SuperflatGenerator;
+use crate::world_generation::preset::{GeneratorPreset, INITIAL_CHUNKS};
+use crate::world_meta::WorldMeta;
+use std::io::{Error, ErrorKind};
+use std::sync::{Arc, Mutex};
+
+/// Number of saved worlds listed, the most recently played ones
+pub const MAX_LISTED_WORLDS: usize = 5;
+/// Longest name of a world
+const MAX_NAME_LENGTH: usize = 16;
+/// Longest seed that can be typed, in digits, so that it always fits in a u64
+const MAX_SEED_DIGITS: usize = 19;
+
+/// The world the player wants to play in
+#[derive(Debug, Clone, PartialEq)]
+pub enum WorldChoice {
+    /// A saved world, by name
+    Load(String),
+    Create(NewWorld),
+}
+
+impl WorldChoice {
+    /// The world asked for on the command line: the default saved world, or a new world with the first free name
+    /// after its generator
+    pub fn from_initializer(init: &WorldInitializer, saves: &SaveDir) -> Self {
+        let generator = match init {
+            WorldInitializer::DISK => return WorldChoice::Load(WorldMeta::default().name),
+            WorldInitializer::RANDOM => GeneratorPreset::Random,
+            WorldInitializer::FLAT => GeneratorPreset::Flat,
+            WorldInitializer::TERRAIN => GeneratorPreset::Terrain,
+            WorldInitializer::AMPLIFIED => GeneratorPreset::Amplified,
+        };
+        let new_world = (1..)
+            .map(|i| NewWorld { name: format!("{}-{i}", generator.name()), generator, ..Default::default() })
+            .find(|new_world| new_world.check(saves).is_ok())
+            .unwrap();
+        WorldChoice::Create(new_world)
+    }
+}
+
+/// Starts the server of the chosen world and logs the player in, see `WorldRenderer::with_world_launcher`
+pub type WorldLauncher = Box<dyn FnMut(WorldChoice) -> std::io::Result<Arc<Mutex<dyn Proxy>>>>;
+
+/// A world to create, as typed by the player
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct NewWorld {
+    pub name: String,
+    /// The digits of the seed, a random seed is chosen if there are none
+    pub seed: String,
+    pub generator: GeneratorPreset,
+    /// The layers of a flat world, index in `SuperflatGenerator::presets`
+    pub flat_layers: usize,
+}
+
+impl NewWorld {
+    /// Adds a character typed in the field of the name or of the seed.
+    /// The names only keep letters, digits and dashes, as they name the directory of the save, and the seeds only digits.
+    pub fn type_char(&mut self, field: MenuEntry, c: char) {
+        match field {
+            MenuEntry::WorldName if self.name.len() < MAX_NAME_LENGTH => {
+                let c = c.to_ascii_lowercase();
+                if c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' {
+                    self.name.push(c);
+                }
+            }
+            MenuEntry::Seed if self.seed.len() < MAX_SEED_DIGITS && c.is_ascii_digit() => self.seed.push(c),
+            _ => {}
+        }
+    }
+
+    /// Removes the last character of the field
+    pub fn erase(&mut self, field: MenuEntry) {
+        match field {
+            MenuEntry::WorldName => self.name.pop(),
+            MenuEntry::Seed => self.seed.pop(),
+            _ => None,
+        };
+    }
+
+    /// Name of the layers of a flat world
+    pub fn flat_layers_name(&self) -> &'static str {
+        SuperflatGenerator::presets()[self.flat_layers].0
+    }
+
+    /// Chooses the next layers of a flat world, back to the first ones after the last
+    pub fn next_flat_layers(&mut self) {
+        self.flat_layers = (self.flat_layers + 1) % SuperflatGenerator::presets().len();
+    }
+
+    /// The settings of the world to create, with the seed typed or a random one. Only call it once per world.
+    pub fn meta(&self) -> WorldMeta {
+        WorldMeta {
+            name: self.name.clone(),
+            seed: self.seed(),
+            generator: self.generator,
+            flat_layers: SuperflatGenerator::presets().swap_remove(self.flat_layers).1,
+            ..Default::default()
+        }
+    }
+
+    /// The seed typed, or a random one. Only call it once per world.
+    pub fn seed(&self) -> u64 {
+        self.seed.parse().unwrap_or_else(|_| rand::random::<u32>() as u64)
+    }
+
+    /// Why the world cannot be created in these saves, if it cannot.
+    /// The reasons are short enough to be the title of the menu.
+    pub fn check(&self, saves: &SaveDir) -> Result<(), &'static str> {
+        if self.name.is_empty() {
+            Err("name needed")
+        } else if saves.world_dir(&self.name).exists() {
+            Err("name taken")
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// The chosen world, with its saved players and the regions of its chunks.
+/// A new world is saved right away, so that it is listed even if the game stops before it is saved again.
+pub fn open_world(choice: &WorldChoice, saves: &SaveDir) -> std::io::Result<(World, Vec<SavedPlayer>, RegionStore)> {
+    match choice {
+        WorldChoice::Load(name) => {
+            let (world, players) = saves.load(name)?;
+            Ok((world, players, saves.regions(name)))
+        }
+        WorldChoice::Create(new_world) => {
+            new_world.check(saves).map_err(|reason| Error::new(ErrorKind::InvalidInput, reason))?;
+            let world = new_world.meta().generate_world(INITIAL_CHUNKS);
+            saves.save(&world, &[])?;
+            Ok((world, Vec::new(), saves.regions(&new_world.name)))
+        }
+    }
+}
+
+/// How long ago the world was last played, from the times [s since 1970]
+pub fn played_ago(last_played: u64, now: u64) -> String {
+    if last_played == 0 {
+        return String::from("never played");
+    }
+    let elapsed = now.saturating_sub(last_played);
+    let (amount, unit) = match elapsed {
+        0..=59 => return String::from("played just now"),
+        60..=3599 => (elapsed / 60, "minute"),
+        3600..=86399 => (elapsed / 3600, "hour"),
+        _ => (elapsed / 86400, "day"),
+    };
+    let plural = if amount > 1 { "s" } else { "" };
+    format!("played {amount} {unit}{plural} ago")
+}
+
+/// The saved worlds with their seed and when they were played, then the entries to create a new one or to go back
+pub fn world_list_menu(worlds: &[WorldMeta], now: u64) -> MenuScreen {
+    let mut entries: Vec<(MenuEntry, String)> = worlds
+        .iter()
+        .take(MAX_LISTED_WORLDS)
+        .enumerate()
+        .map(|(i, meta)| {
+            let details = format!("seed {}, {}", meta.seed, played_ago(meta.last_played, now));
+            (MenuEntry::OpenWorld(i), format!("{}\n{}", meta.name, details))
+        })
+        .collect();
+    entries.push((MenuEntry::NewWorld, MenuEntry::NewWorld.name().to_string()));
+    entries.push((MenuEntry::Back, MenuEntry::Back.name().to_string()));
+    MenuScreen::new("worlds", entries)
+}
+
+/// The fields of the new world, the title tells why it cannot be created when it failed
+pub fn create_world_menu(new_world: &NewWorld, error: Option<&str>) -> MenuScreen {
+    let seed = if new_world.seed.is_empty() { "random" } else { &new_world.seed };
+    let field = |entry: MenuEntry, value: &str| (entry, format!("{}: {}", entry.name(), value));
+    let mut entries = vec![
+        field(MenuEntry::WorldName, &new_world.name),
+        field(MenuEntry::Seed, seed),
+        field(MenuEntry::Generator, new_world.generator.name()),
+    ];
+    // The layers only matter to the flat worlds
+    if new_world.generator == GeneratorPreset::Flat {
+        entries.push(field(MenuEntry::FlatLayers, new_world.flat_layers_name()));
+    }
+    entries.push((MenuEntry::Create, MenuEntry::Create.name().to_string()));
+    entries.push((MenuEntry::Back, MenuEntry::Back.name().to_string()));
+    MenuScreen::new(error.unwrap_or("new world"), entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::args::WorldInitializer;
+    use crate::game_state::MenuEntry;
+    use crate::persistence::SaveDir;
+    use crate::world_generation::generators::SuperflatGenerator;
+    use crate::world_generation::preset::GeneratorPreset;
+    use crate::world_selection::{open_world, played_ago, NewWorld, WorldChoice};
+
+    #[test]
+    fn test_typing_a_new_world() {
+        let mut new_world = NewWorld::default();
+        for c in "My World!".chars() {
+            new_world.type_char(MenuEntry::WorldName, c);
+        }
+        assert_eq!(new_world.name, "myworld");
+        for c in "12a3".chars() {
+            new_world.type_char(MenuEntry::Seed, c);
+        }
+        new_world.erase(MenuEntry::Seed);
+        assert_eq!(new_world.seed, "12");
+        assert_eq!(new_world.seed(), 12);
+
+        // Without digits, the seed is random
+        new_world.seed.clear();
+        let _ = new_world.seed();
+
+        // The layers are cycled, back to the first ones
+        assert_eq!(new_world.flat_layers_name(), "grassland");
+        new_world.next_flat_layers();
+        assert_eq!(new_world.flat_layers_name(), "desert");
+        (1..SuperflatGenerator::presets().len()).for_each(|_| new_world.next_flat_layers());
+        assert_eq!(new_world.flat_layers, 0);
+    }
+
+    #[test]
+    fn test_played_ago() {
+        assert_eq!(played_ago(0, 1000), "never played");
+        assert_eq!(played_ago(1000, 1030), "played just now");
+        assert_eq!(played_ago(1000, 1000 + 60), "played 1 minute ago");
+        assert_eq!(played_ago(1000, 1000 + 3 * 3600 + 5), "played 3 hours ago");
+        assert_eq!(played_ago(1000, 1000 + 2 * 86400), "played 2 days ago");
+    }
+
+    #[test]
+    fn test_create_then_load() {
+        let root = std::env::temp_dir().join("crafty_test_world_selection");
+        let _ = std::fs::remove_dir_all(&root);
+        let saves = SaveDir::new(&root);
+
+        let new_world = NewWorld { name: String::from("flatland"), seed: String::from("7"), generator: GeneratorPreset::Flat, flat_layers: 1 };
+        let (world, players, _) = open_world(&WorldChoice::Create(new_world.clone()), &saves).unwrap();
+        assert_eq!(world.meta().seed, 7);
+        assert!(players.is_empty());
+
+        // Listed with its settings, and cannot be created twice
+        let summaries = saves.summaries();
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(summaries[0].name, "flatland");
+        assert_eq!(summaries[0].generator, GeneratorPreset::Flat);
+        assert_eq!(new_world.check(&saves), Err("name taken"));
+        assert_eq!(NewWorld::default().check(&saves), Err("name needed"));
+
+        let (world, _, _) = open_world(&WorldChoice::Load(String::from("flatland")), &saves).unwrap();
+        assert_eq!(world.meta().generator, GeneratorPreset::Flat);
+        assert_eq!(world.meta().flat_layers, SuperflatGenerator::presets()[1].1);
+        assert!(open_world(&WorldChoice::Load(String::from("nowhere")), &saves).is_err());
+    }
+
+    #[test]
+    fn test_world_from_the_command_line() {
+        let root = std::env::temp_dir().join("crafty_test_world_from_the_command_line");
+        let _ = std::fs::remove_dir_all(&root);
+        let saves = SaveDir::new(&root);
+
+        let choice = WorldChoice::from_initializer(&WorldInitializer::FLAT, &saves);
+        let WorldChoice::Create(new_world) = &choice else {
+            panic!("a new world is created");
+        };
+        assert_eq!((new_world.name.as_str(), new_world.generator), ("flat-1", GeneratorPreset::Flat));
+
+        // The next one does not overwrite it
+        open_world(&choice, &saves).unwrap();
+        let WorldChoice::Create(new_world) = WorldChoice::from_initializer(&WorldInitializer::FLAT, &saves) else {
+            panic!("a new world is created");
+        };
+        assert_eq!(new_world.name, "flat-2");
+
+        assert!(matches!(WorldChoice::from_initializer(&WorldInitializer::DISK, &saves), WorldChoice::Load(_)));
+    }
+}
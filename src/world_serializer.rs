@@ -1,13 +1,37 @@
 use std::collections::HashMap;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use strum::IntoEnumIterator;
+use crate::block_entity::BlockEntity;
 use crate::block_kind::Block;
 use crate::chunk::Chunk;
+use crate::cube::Cube;
+use crate::item::LegacyItem;
+use crate::world_meta::WorldMeta;
 
 #[derive(Serialize, Deserialize)]
 pub struct SerializedWorld {
+    /// Header of the file, older saves and the chunks sent over the network do not have one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<WorldMeta>,
     pub chunk_corners: Vec<[f32;2]>,
-    pub cubes_by_kind: HashMap<Block, Vec<[i32;4]>>
+    #[serde(deserialize_with = "deserialize_cubes_by_kind")]
+    pub cubes_by_kind: HashMap<Block, Vec<[i32;4]>>,
+    /// The states of the cubes (see `BlockEntity`) by their position, older saves do not have any
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub block_entities: Vec<([i32; 3], BlockEntity)>,
+}
+
+/// Reads the cubes of each kind. The saves written when all the items were blocks have an empty list for each
+/// tool, food and arrow, they could not be placed.
+fn deserialize_cubes_by_kind<'de, D: Deserializer<'de>>(deserializer: D) -> Result<HashMap<Block, Vec<[i32; 4]>>, D::Error> {
+    let saved: HashMap<LegacyItem, Vec<[i32; 4]>> = HashMap::deserialize(deserializer)?;
+    Ok(saved
+        .into_iter()
+        .filter_map(|(kind, cubes)| match kind {
+            LegacyItem::Block(block) => Some((block, cubes)),
+            _ => None,
+        })
+        .collect())
 }
 
 /// Returns a container to be used to serialize chunks or world.
@@ -28,8 +52,19 @@ pub fn serialize_one_chunk(all_cubes: &mut HashMap<Block, Vec<[i32; 4]>>, chunk:
                 cube.position().x() as i32,
                 cube.position().y() as i32,
                 cube.position().z() as i32,
-                cube.n_neighbors() as i32
+                pack_cube_state(cube)
             ]);
         }
     }
 }
+
+/// The last value of a serialized cube: its number of neighbors, with the metadata of its cell above them.
+/// The cubes saved before the metadata only have their neighbors, they get the metadata 0.
+pub fn pack_cube_state(cube: &Cube) -> i32 {
+    cube.n_neighbors() as i32 | (cube.metadata() as i32) << 4
+}
+
+/// The number of neighbors and the metadata of a cube, see `pack_cube_state`
+pub fn unpack_cube_state(state: i32) -> (u8, u8) {
+    ((state & 0x0F) as u8, (state >> 4 & 0x0F) as u8)
+}
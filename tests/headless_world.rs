@@ -0,0 +1,49 @@
+//! The simulation (world, player, collisions) must run without any window or OpenGL context.
+//! Nothing here creates a `Display`, only the in-memory simulation is used.
+
+use std::time::Duration;
+
+use crafty::chunk::CHUNK_FLOOR;
+use crafty::collidable::Collidable;
+use crafty::entity::humanoid::humanoid_aabb;
+use crafty::player::{Player, FOREHEAD, PLAYER_HEIGHT};
+use crafty::primitives::position::Position;
+use crafty::primitives::vector::Vector3;
+use crafty::world_generation::world_generator::WorldGenerator;
+
+const FRAME: Duration = Duration::from_millis(16);
+
+#[test]
+fn test_player_lands_on_flat_world() {
+    let world = WorldGenerator::create_new_flat_world(1);
+    let mut player = Player::new();
+    player.set_position(Position::spawn_position());
+
+    for _ in 0..60 {
+        player.step(FRAME, &world);
+    }
+
+    // The grass layer is the last one, so the feet of the player rest on top of it
+    let expected_eyes = CHUNK_FLOOR as f32 + 1. + PLAYER_HEIGHT - FOREHEAD;
+    let position = player.position().pos();
+    assert!((position.y() - expected_eyes).abs() < 1e-3, "player at {:?}", position);
+    assert_eq!(position.x(), 0.);
+    assert_eq!(position.z(), 0.);
+}
+
+#[test]
+fn test_player_falls_in_random_world_without_going_through_it() {
+    let world = WorldGenerator::create_new_random_world(1);
+    let start = Vector3::new(0.5, 60., 0.5);
+    let mut player = Player::new();
+    player.set_position(Position::new(start, 0., 0.));
+
+    for _ in 0..60 {
+        player.step(FRAME, &world);
+    }
+
+    let position = player.position().pos();
+    assert!(position.y() < start.y());
+    assert!(position.y() > 0.);
+    assert!(!world.collides(&humanoid_aabb(player.position())));
+}